@@ -0,0 +1,651 @@
+use crate::configopt_type::{
+    generate,
+    parse::{self, CasingStyle, StructOptTy},
+};
+use proc_macro2::{Span, TokenStream};
+use proc_macro_error::abort;
+use proc_macro_roids::DeriveInputExt;
+use quote::{quote, quote_spanned};
+use syn::{
+    parse_quote, punctuated::Punctuated, spanned::Spanned, Attribute, Data, DeriveInput, Field,
+    Fields, Ident, Lit, Meta, Token, Type,
+};
+
+/// How a field of a section is converted into its full version
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    /// A nested section
+    Section,
+    /// An `Option` which is `None` if unset
+    Optional,
+    /// A `bool` or `Vec` which falls back to its default if unset
+    Defaulted,
+    /// A value which must be set
+    Required,
+}
+
+/// A field of a config only section
+///
+/// Sections have no CLI surface so none of the `structopt` analysis of `ParsedField` applies. A
+/// field is either a leaf value which is wrapped in an `Option` or a nested section.
+struct SectionField {
+    ident: Ident,
+    span: Span,
+    kind: Kind,
+    // Is the field deserialized as a whole without requiring `Serialize`
+    leaf: bool,
+    // Is the field serialized with `configopt::os_str`
+    os_str: bool,
+    // The type of the field on the full type or the `configopt` type of a section
+    ty: Type,
+    serde_name: String,
+    comment: String,
+}
+
+impl SectionField {
+    fn new(field: &mut Field, rename_all: CasingStyle) -> Self {
+        let span = field.span();
+        let ident = field
+            .ident
+            .clone()
+            .unwrap_or_else(|| abort!(span, "`ConfigOptSection` only supports named fields"));
+        let attrs = field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("configopt"))
+            .map(|attr| match attr.parse_args::<Ident>() {
+                Ok(ident) if ident == "section" || ident == "leaf" => ident,
+                _ => abort!(
+                    attr,
+                    "`ConfigOptSection` fields only support `configopt(section)` and `configopt(leaf)`"
+                ),
+            })
+            .collect::<Vec<_>>();
+        let section = attrs.iter().any(|a| a == "section");
+        let leaf = attrs.iter().any(|a| a == "leaf");
+        if section && leaf {
+            abort!(
+                span,
+                "`configopt(section)` cannot be combined with `configopt(leaf)`"
+            );
+        }
+        let structopt_ty = StructOptTy::from_syn_ty(&field.ty);
+        let kind = match structopt_ty {
+            _ if section => Kind::Section,
+            StructOptTy::Option | StructOptTy::OptionOption | StructOptTy::OptionVec => {
+                Kind::Optional
+            }
+            StructOptTy::Bool | StructOptTy::Vec => Kind::Defaulted,
+            StructOptTy::Other => Kind::Required,
+        };
+        if section && structopt_ty != StructOptTy::Other {
+            abort!(
+                span,
+                "`configopt(section)` expects the type of a struct deriving `ConfigOptSection`"
+            );
+        }
+        let comment = doc_comment(&field.attrs);
+        // The key is what `serde` deserializes the field from
+        let serde_name = parse::serde_rename(&field.attrs)
+            .unwrap_or_else(|| rename_all.rename(&ident.to_string()));
+        let os_str = !section && !leaf && parse::is_os_str_field(field);
+
+        // The below logic converts the field into a partial field
+        retain_attrs(&mut field.attrs);
+        if section {
+            let inner_ty = parse::inner_ty(&mut field.ty);
+            *inner_ty = parse::configopt_ident(inner_ty);
+            // A missing table is an empty section
+            field.attrs.push(parse_quote! {#[serde(default)]});
+        }
+        if os_str {
+            field
+                .attrs
+                .push(parse_quote! {#[serde(default, with = "::configopt::os_str")]});
+        }
+        let ty = field.ty.clone();
+        if kind != Kind::Section && kind != Kind::Optional {
+            field.ty = parse_quote!(Option<#ty>);
+        }
+
+        Self {
+            serde_name,
+            ident,
+            span,
+            kind,
+            leaf,
+            os_str,
+            ty,
+            comment,
+        }
+    }
+
+    fn ident(&self) -> &Ident {
+        &self.ident
+    }
+
+    fn is_section(&self) -> bool {
+        self.kind == Kind::Section
+    }
+}
+
+impl Spanned for SectionField {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+fn doc_comment(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(meta)) => match meta.lit {
+                Lit::Str(lit) => Some(format!("### {}\n", lit.value().trim())),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+// Sections are only read from config files so their `serde` attributes are kept
+fn retain_attrs(attrs: &mut Vec<Attribute>) {
+    attrs.retain(|a| a.path.is_ident("serde") || a.path.is_ident("doc"));
+    for attr in attrs {
+        parse::trim_serde_attr(attr);
+    }
+}
+
+pub fn derive(original_type: DeriveInput) -> TokenStream {
+    let ident = original_type.ident.clone();
+    let configopt_ident = parse::configopt_ident(&ident);
+    let mut configopt_type = original_type;
+    configopt_type.ident = configopt_ident.clone();
+
+    let mut derives = configopt_type
+        .tag_parameters(&parse_quote!(configopt), &parse_quote!(derive))
+        .into_iter()
+        .collect::<Punctuated<_, Token![,]>>();
+    // The keys of a section are checked by the `#[configopt(strict)]` type it is loaded with
+    retain_attrs(&mut configopt_type.attrs);
+    let rename_all =
+        parse::serde_rename_all(&configopt_type.attrs).unwrap_or(CasingStyle::Verbatim);
+
+    let fields = match &mut configopt_type.data {
+        Data::Struct(data) => match &mut data.fields {
+            Fields::Named(fields) => fields
+                .named
+                .iter_mut()
+                .map(|field| SectionField::new(field, rename_all))
+                .collect::<Vec<_>>(),
+            fields => abort!(fields, "`ConfigOptSection` only supports named fields"),
+        },
+        _ => abort!(ident, "`ConfigOptSection` can only be derived for structs"),
+    };
+
+    derives.push(parse_quote! {Default});
+    // The section is deserialized from the table of its key in config files
+    derives.push(parse_quote! {serde::Deserialize});
+    configopt_type.append_derives(derives);
+
+    let lints = generate::lints();
+    let partial = generate::partial::generate(&ident, &configopt_ident);
+    let take = merge(&fields, |field, self_field, other_field| {
+        if field.is_section() {
+            quote! {#self_field.take(&mut #other_field);}
+        } else {
+            quote! {
+                if #other_field.is_some() {
+                    #self_field = #other_field.take();
+                }
+            }
+        }
+    });
+    let patch = merge(&fields, |field, self_field, other_field| {
+        if field.is_section() {
+            quote! {#self_field.patch(&mut #other_field);}
+        } else {
+            quote! {
+                if #self_field.is_none() {
+                    #self_field = #other_field.take();
+                }
+            }
+        }
+    });
+    let take_for = merge(&fields, |field, self_field, other_field| {
+        if field.is_section() {
+            quote! {#self_field.take_for(&mut #other_field);}
+        } else if field.kind == Kind::Optional {
+            quote! {
+                if #self_field.is_some() {
+                    #other_field = #self_field.take();
+                }
+            }
+        } else {
+            quote! {
+                if let Some(value) = #self_field.take() {
+                    #other_field = value;
+                }
+            }
+        }
+    });
+    let patch_for = merge(&fields, |field, self_field, other_field| {
+        if field.is_section() {
+            quote! {#self_field.patch_for(&mut #other_field);}
+        } else if field.kind == Kind::Optional {
+            quote! {
+                if #other_field.is_none() {
+                    #other_field = #self_field.take();
+                }
+            }
+        } else {
+            quote! {}
+        }
+    });
+    let is_empty = all(&fields, |field, self_field| {
+        if field.is_section() {
+            quote! {#self_field.is_empty()}
+        } else {
+            quote! {#self_field.is_none()}
+        }
+    });
+    let is_complete = all(&fields, |field, self_field| {
+        if field.is_section() {
+            quote! {#self_field.is_complete()}
+        } else {
+            quote! {#self_field.is_some()}
+        }
+    });
+    let is_convertible = all(&fields, |field, self_field| {
+        if field.is_section() {
+            quote! {#self_field.is_convertible()}
+        } else if field.kind == Kind::Required {
+            quote! {#self_field.is_some()}
+        } else {
+            quote! {true}
+        }
+    });
+    let missing_fields = each(&fields, |field, self_field, serde_name| {
+        if field.is_section() {
+            quote! {
+                missing.extend(
+                    #self_field
+                        .missing_fields()
+                        .into_iter()
+                        .map(|f| format!("{}.{}", #serde_name, f)),
+                );
+            }
+        } else if field.kind == Kind::Required {
+            quote! {
+                if #self_field.is_none() {
+                    missing.push(String::from(#serde_name));
+                }
+            }
+        } else {
+            quote! {}
+        }
+    });
+    let set_fields = each(&fields, |field, self_field, serde_name| {
+        if field.is_section() {
+            quote! {
+                set.extend(
+                    #self_field
+                        .set_fields()
+                        .into_iter()
+                        .map(|f| format!("{}.{}", #serde_name, f)),
+                );
+            }
+        } else {
+            quote! {
+                if #self_field.is_some() {
+                    set.push(String::from(#serde_name));
+                }
+            }
+        }
+    });
+    let toml_config = each(&fields, |field, self_field, serde_name| {
+        if field.is_section() {
+            return quote! {
+                let mut prefix = serde_prefix.to_vec();
+                prefix.push(String::from(#serde_name));
+                result = format!("{}{}", result, #self_field.toml_config_with_prefix(&prefix));
+            };
+        }
+        let comment = &field.comment;
+        let key = quote! {
+            let key = if serde_prefix.is_empty() {
+                String::from(#serde_name)
+            } else {
+                format!("{}.{}", serde_prefix.join("."), #serde_name)
+            };
+        };
+        if field.leaf {
+            // Leaves are not required to implement `Serialize` so only their key is shown
+            return quote! {
+                #key
+                result = format!("{}{}# {} =\n\n", result, #comment, key);
+            };
+        }
+        let self_field = if field.os_str {
+            quote! {::configopt::os_str::Serialized(&#self_field)}
+        } else {
+            self_field
+        };
+        quote! {
+            #key
+            // An empty list is a set value (`key = []`)
+            match toml::Value::try_from(&#self_field) {
+                Ok(val) => {
+                    result = format!("{}{}{} = {}\n\n", result, #comment, key, ::configopt::inline_toml(&val));
+                }
+                Err(toml::ser::Error::UnsupportedNone) => {
+                    result = format!("{}{}# {} =\n\n", result, #comment, key);
+                }
+                // Keep values TOML cannot represent (eg integers above `i64::MAX`) visible
+                Err(e) => {
+                    result = format!("{}{}### [error: {}]\n# {} =\n\n", result, #comment, e, key);
+                }
+            }
+        }
+    });
+    let toml_values = each(&fields, |field, self_field, serde_name| {
+        if field.is_section() {
+            return quote! {
+                let mut prefix = serde_prefix.to_vec();
+                prefix.push(String::from(#serde_name));
+                let values = #self_field.toml_values_with_prefix(&prefix, secrets)?;
+                if !values.is_empty() {
+                    table.insert(String::from(#serde_name), toml::Value::Table(values));
+                }
+            };
+        }
+        let self_field = if field.os_str {
+            quote! {::configopt::os_str::Serialized(&#self_field)}
+        } else {
+            self_field
+        };
+        generate::toml_values::insert_value(field.span(), serde_name, self_field, false, false)
+    });
+    let schema_fields = fields
+        .iter()
+        .map(|field| {
+            let span = field.span();
+            let serde_name = &field.serde_name;
+            let ty = &field.ty;
+            if field.is_section() {
+                quote_spanned! {span=>
+                    fields.extend(<#ty>::schema_fields(&format!("{}{}.", prefix, #serde_name)));
+                }
+            } else {
+                let ty = quote! {#ty}.to_string().replace(' ', "");
+                let required = field.kind == Kind::Required;
+                let values = parse::possible_values(&field.ty);
+                quote_spanned! {span=>
+                    fields.push(::configopt::SchemaField {
+                        path: format!("{}{}", prefix, #serde_name),
+                        ty: String::from(#ty),
+                        required: #required,
+                        aliases: Vec::new(),
+                        format: None,
+                        env: None,
+                        decode_env: false,
+                        arg: None,
+                        values: #values,
+                        constraints: ::std::default::Default::default(),
+                    });
+                }
+            }
+        })
+        .collect::<TokenStream>();
+    let interpolate = fields
+        .iter()
+        .map(|field| {
+            let field_ident = field.ident();
+            let serde_name = &field.serde_name;
+            if field.is_section() {
+                quote! {
+                    self.#field_ident.interpolate_with(lookup)?;
+                }
+            } else {
+                quote! {
+                    (&mut self.#field_ident)
+                        .interpolate(lookup)
+                        .map_err(|e| format!("`{}` {}", #serde_name, e))?;
+                }
+            }
+        })
+        .collect::<TokenStream>();
+    let resolve_references = fields
+        .iter()
+        .map(|field| {
+            let field_ident = field.ident();
+            let serde_name = &field.serde_name;
+            if field.is_section() {
+                quote! {
+                    self.#field_ident.resolve_references_with(resolve)?;
+                }
+            } else {
+                quote! {
+                    (&mut self.#field_ident)
+                        .resolve_reference(resolve)
+                        .map_err(|e| format!("`{}` {}", #serde_name, e))?;
+                }
+            }
+        })
+        .collect::<TokenStream>();
+    let from = fields
+        .iter()
+        .map(|field| {
+            let field_ident = field.ident();
+            match field.kind {
+                Kind::Section => quote! {#field_ident: other.#field_ident.into(),},
+                Kind::Optional => quote! {#field_ident: other.#field_ident,},
+                Kind::Defaulted | Kind::Required => {
+                    quote! {#field_ident: Some(other.#field_ident),}
+                }
+            }
+        })
+        .collect::<TokenStream>();
+    let try_from = fields
+        .iter()
+        .map(|field| {
+            let field_ident = field.ident();
+            let serde_name = &field.serde_name;
+            match field.kind {
+                Kind::Section => quote! {#field_ident: configopt.#field_ident.try_into()?,},
+                Kind::Optional => quote! {#field_ident: configopt.#field_ident,},
+                Kind::Defaulted => {
+                    quote! {#field_ident: configopt.#field_ident.unwrap_or_default(),}
+                }
+                Kind::Required => quote! {
+                    #field_ident: configopt.#field_ident.ok_or_else(|| {
+                        ::configopt::Error::MissingFields(vec![String::from(#serde_name)])
+                    })?,
+                },
+            }
+        })
+        .collect::<TokenStream>();
+
+    quote! {
+        #lints
+        #configopt_type
+
+        #lints
+        impl #configopt_ident {
+            /// Take each field from `other` and set it in `self`
+            pub fn take(&mut self, other: &mut #configopt_ident) {
+                #take
+            }
+
+            /// For each field in `self` if it is `None`, take the value from `other` and set it in `self`
+            pub fn patch(&mut self, other: &mut #configopt_ident) {
+                #patch
+            }
+
+            /// Take each field from `self` and set it in `other`
+            pub fn take_for(&mut self, other: &mut #ident) {
+                #take_for
+            }
+
+            /// For each field in `other` if it is `None`, take the value from `self` and set it in `other`
+            pub fn patch_for(&mut self, other: &mut #ident) {
+                #patch_for
+            }
+
+            /// Check if all fields of `self` are `None`
+            #[allow(clippy::eq_op)]
+            pub fn is_empty(&self) -> bool {
+                #is_empty
+            }
+
+            /// Check if all fields of `self` are `Some` applied recursively
+            #[allow(clippy::eq_op)]
+            pub fn is_complete(&self) -> bool {
+                #is_complete
+            }
+
+            /// Check if `self` can be converted into a full version
+            #[allow(clippy::eq_op)]
+            pub fn is_convertible(&self) -> bool {
+                #is_convertible
+            }
+
+            /// The dotted paths of the required fields which prevent `self` from being
+            /// converted into a full version
+            pub fn missing_fields(&self) -> Vec<String> {
+                let mut missing = Vec::new();
+                #missing_fields
+                missing
+            }
+
+            /// The dotted paths of the fields which are set
+            pub fn set_fields(&self) -> Vec<String> {
+                let mut set = Vec::new();
+                #set_fields
+                set
+            }
+
+            /// Generate the TOML of the section with its keys under `serde_prefix`
+            pub fn toml_config_with_prefix(&self, serde_prefix: &[String]) -> String {
+                let mut result = String::new();
+                #toml_config
+                result
+            }
+
+            /// The values of the section which are set with its keys under `serde_prefix`
+            pub fn toml_values_with_prefix(
+                &self,
+                serde_prefix: &[String],
+                secrets: bool,
+            ) -> ::std::result::Result<toml::value::Table, ::configopt::TomlValueError> {
+                #[allow(unused_imports)]
+                use ::configopt::{TomlValueOther as _, TomlValueSerialize as _};
+                let mut table = toml::value::Table::new();
+                #toml_values
+                Ok(table)
+            }
+
+            /// Replace each `${name}` in string values using `lookup`
+            pub fn interpolate_with(
+                &mut self,
+                lookup: &dyn Fn(&str) -> Option<String>,
+            ) -> ::std::result::Result<(), String> {
+                #[allow(unused_imports)]
+                use ::configopt::{Interpolate as _, InterpolateOther as _};
+                #interpolate
+                Ok(())
+            }
+
+            /// Replace each reference to a value stored elsewhere using `resolve`
+            pub fn resolve_references_with(
+                &mut self,
+                resolve: &dyn Fn(&str) -> Option<::std::result::Result<String, String>>,
+            ) -> ::std::result::Result<(), String> {
+                #[allow(unused_imports)]
+                use ::configopt::{ResolveReference as _, ResolveReferenceOther as _};
+                #resolve_references
+                Ok(())
+            }
+
+            #[doc(hidden)]
+            pub fn schema_fields(prefix: &str) -> Vec<::configopt::SchemaField> {
+                let mut fields = Vec::new();
+                #schema_fields
+                fields
+            }
+        }
+
+        #partial
+
+        #lints
+        impl ::std::convert::From<#ident> for #configopt_ident {
+            fn from(other: #ident) -> Self {
+                Self {
+                    #from
+                }
+            }
+        }
+
+        #lints
+        impl ::std::convert::TryFrom<#configopt_ident> for #ident {
+            type Error = ::configopt::Error;
+            fn try_from(configopt: #configopt_ident) -> ::std::result::Result<Self, Self::Error> {
+                use ::std::convert::TryInto;
+
+                if !configopt.is_convertible() {
+                    return Err(::configopt::Error::MissingFields(configopt.missing_fields()));
+                }
+                Ok(Self {
+                    #try_from
+                })
+            }
+        }
+    }
+}
+
+fn merge(
+    fields: &[SectionField],
+    f: impl Fn(&SectionField, TokenStream, TokenStream) -> TokenStream,
+) -> TokenStream {
+    fields
+        .iter()
+        .map(|field| {
+            let field_ident = field.ident();
+            f(
+                field,
+                quote! {self.#field_ident},
+                quote! {other.#field_ident},
+            )
+        })
+        .collect()
+}
+
+fn all(
+    fields: &[SectionField],
+    f: impl Fn(&SectionField, TokenStream) -> TokenStream,
+) -> TokenStream {
+    if fields.is_empty() {
+        return quote! {true};
+    }
+    let fields = fields.iter().map(|field| {
+        let field_ident = field.ident();
+        f(field, quote! {self.#field_ident})
+    });
+    quote! {
+        #(#fields)&&*
+    }
+}
+
+fn each(
+    fields: &[SectionField],
+    f: impl Fn(&SectionField, TokenStream, &str) -> TokenStream,
+) -> TokenStream {
+    fields
+        .iter()
+        .map(|field| {
+            let field_ident = field.ident();
+            f(field, quote! {self.#field_ident}, &field.serde_name)
+        })
+        .collect()
+}
@@ -2,15 +2,94 @@ pub mod generate;
 pub mod parse;
 
 use generate::default_config_files::Attribute as DefaultConfigFilesAttribute;
+use generate::interpolate::Pass;
 use parse::{CasingStyle, ParsedField, ParsedVariant};
-use proc_macro2::TokenStream;
+use proc_macro2::{Span, TokenStream};
+use proc_macro_error::abort;
 use proc_macro_roids::DeriveInputExt;
 use quote::quote;
-use syn::{parse_quote, punctuated::Punctuated, Data, DeriveInput, Fields, Ident, Token};
+use syn::{
+    parse_quote, punctuated::Punctuated, Data, DeriveInput, Fields, Ident, Lit, LitStr, Meta,
+    NestedMeta, Token,
+};
 
 pub enum ConfigOptConstruct {
-    Struct(Ident, Option<DefaultConfigFilesAttribute>, Vec<ParsedField>),
-    Enum(Ident, Vec<ParsedVariant>),
+    Struct {
+        ident: Ident,
+        default_config_file: Option<DefaultConfigFilesAttribute>,
+        parsed_fields: Vec<ParsedField>,
+        // Should a `Debug` impl which redacts secrets be generated
+        redact_debug: bool,
+        // Does the configopt type derive `PartialEq` so it can be diffed
+        partial_eq: bool,
+        // The environment variable containing a JSON document to patch the config with
+        env_json: Option<String>,
+        // The prefix of the environment variables which set nested keys and the separator between
+        // the keys of a variable name
+        env_prefix: Option<String>,
+        env_separator: String,
+        // The config document embedded in the binary as the lowest priority layer
+        default_config: Option<LitStr>,
+        // The dotenv file loaded into the environment before parsing
+        dotenv: Option<LitStr>,
+        // The sections which come first in the generated config in this order
+        section_order: Vec<LitStr>,
+        // The table of a config file shared with other binaries holding the keys of this binary
+        binary: Option<LitStr>,
+        // Should a `config` subcommand editing the default config file be generated
+        config_command: bool,
+        // Are unknown keys anywhere in a config file rejected
+        strict: bool,
+        // Are the `${name}` templates of string values interpolated while loading
+        interpolate: bool,
+        // Is the loaded config stored in a static with `init_global` and `global` accessors
+        global: bool,
+        // The function returning the presets defined in code
+        presets: Option<syn::Path>,
+        // The function checking the rules between the fields of the loaded config
+        validate: Option<syn::Path>,
+        // The key holding the schema version of config files
+        version_field: Option<LitStr>,
+        // The function returning the migrations between schema versions
+        migrations: Option<syn::Path>,
+        skip: Skip,
+    },
+    Enum(Ident, Vec<ParsedVariant>, bool, Skip),
+}
+
+/// The generated surface a type opted out of with `#[configopt(skip(..))]`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Skip {
+    // The builder methods of a struct
+    builder: bool,
+    // `diff` and `diff_with_prefix`
+    diff: bool,
+    // The `Merge` and `Partial` impls
+    partial: bool,
+    // The public `schema`, `lint_file`, and `reference_doc`
+    schema: bool,
+}
+
+impl Skip {
+    fn parse(metas: Vec<NestedMeta>) -> Self {
+        let mut skip = Self::default();
+        for meta in metas {
+            let name = proc_macro_roids::nested_meta_to_path(&meta)
+                .and_then(|path| path.get_ident().cloned())
+                .unwrap_or_else(|| abort!(meta, "`configopt(skip(..))` expected an ident"));
+            match name.to_string().as_str() {
+                "builder" => skip.builder = true,
+                "diff" => skip.diff = true,
+                "partial" => skip.partial = true,
+                "schema" => skip.schema = true,
+                _ => abort!(
+                    name,
+                    "`configopt(skip(..))` expected one of `builder`, `diff`, `partial`, or `schema`"
+                ),
+            }
+        }
+        skip
+    }
 }
 
 impl ConfigOptConstruct {
@@ -26,16 +105,140 @@ impl ConfigOptConstruct {
             .tag_parameter(&parse_quote!(configopt), &parse_quote!(default_config_file))
             .map(|a| a.into());
 
+        // Check if we have an environment variable with a JSON document
+        let env_json = configopt_type
+            .tag_parameter(&parse_quote!(configopt), &parse_quote!(env_json))
+            .map(|meta| match meta {
+                NestedMeta::Lit(Lit::Str(lit)) => lit.value(),
+                meta => abort!(meta, "`configopt(env_json)` expected a string literal"),
+            });
+
+        // Check if we read nested keys from prefixed environment variables
+        let env_prefix = configopt_type
+            .tag_parameter(&parse_quote!(configopt), &parse_quote!(env_prefix))
+            .map(|meta| match meta {
+                NestedMeta::Lit(Lit::Str(lit)) => lit.value(),
+                meta => abort!(meta, "`configopt(env_prefix)` expected a string literal"),
+            });
+        let env_separator = configopt_type
+            .tag_parameter(&parse_quote!(configopt), &parse_quote!(env_separator))
+            .map(|meta| match meta {
+                NestedMeta::Lit(Lit::Str(lit)) if !lit.value().is_empty() => lit.value(),
+                meta => abort!(
+                    meta,
+                    "`configopt(env_separator)` expected a non-empty string literal"
+                ),
+            })
+            .unwrap_or_else(|| String::from("__"));
+
+        // Check if we have a config document to embed in the binary
+        let default_config = configopt_type
+            .tag_parameter(&parse_quote!(configopt), &parse_quote!(default_config))
+            .map(|meta| match meta {
+                NestedMeta::Lit(Lit::Str(lit)) => lit,
+                meta => abort!(
+                    meta,
+                    "`configopt(default_config)` expected a string literal"
+                ),
+            });
+
+        // Check if we load a dotenv file before parsing
+        let dotenv = configopt_type
+            .tag_parameter(&parse_quote!(configopt), &parse_quote!(dotenv))
+            .map(|meta| match meta {
+                NestedMeta::Lit(Lit::Str(lit)) => lit,
+                meta => abort!(meta, "`configopt(dotenv)` expected a string literal"),
+            });
+
+        // Check if the sections of the generated config are ordered
+        let section_order = configopt_type
+            .tag_parameters(&parse_quote!(configopt), &parse_quote!(section_order))
+            .into_iter()
+            .map(|meta| match meta {
+                NestedMeta::Lit(Lit::Str(lit)) => lit,
+                meta => abort!(
+                    meta,
+                    "`configopt(section_order(..))` expected string literals"
+                ),
+            })
+            .collect::<Vec<_>>();
+
+        // Check if the config file is shared with other binaries
+        let binary = configopt_type
+            .tag_parameter(&parse_quote!(configopt), &parse_quote!(binary))
+            .map(|meta| match meta {
+                NestedMeta::Lit(Lit::Str(lit)) => lit,
+                meta => abort!(meta, "`configopt(binary)` expected a string literal"),
+            });
+
+        // Check if we have presets defined in code
+        let presets = configopt_type
+            .tag_parameter(&parse_quote!(configopt), &parse_quote!(presets))
+            .map(|meta| match meta {
+                NestedMeta::Meta(Meta::Path(path)) => path,
+                meta => abort!(meta, "`configopt(presets)` expected a path"),
+            });
+
+        // Check if the loaded config is validated as a whole
+        let validate = configopt_type
+            .tag_parameter(&parse_quote!(configopt), &parse_quote!(validate))
+            .map(|meta| match meta {
+                NestedMeta::Meta(Meta::Path(path)) => path,
+                meta => abort!(meta, "`configopt(validate)` expected a path"),
+            });
+
+        // Check if config files are versioned and migrated between versions
+        let version_field = configopt_type
+            .tag_parameter(&parse_quote!(configopt), &parse_quote!(version_field))
+            .map(|meta| match meta {
+                NestedMeta::Lit(Lit::Str(lit)) => lit,
+                meta => abort!(meta, "`configopt(version_field)` expected a string literal"),
+            });
+        let migrations = configopt_type
+            .tag_parameter(&parse_quote!(configopt), &parse_quote!(migrations))
+            .map(|meta| match meta {
+                NestedMeta::Meta(Meta::Path(path)) => path,
+                meta => abort!(meta, "`configopt(migrations)` expected a path"),
+            });
+        if let (None, Some(path)) = (&version_field, &migrations) {
+            abort!(
+                path,
+                "`configopt(migrations)` requires `configopt(version_field)`"
+            );
+        }
+
+        // Check if the names of a layer use a different casing style than `structopt`
+        let rename_all_file = casing_style(&configopt_type, "rename_all_file");
+        let rename_all_env = casing_style(&configopt_type, "rename_all_env");
+        if let (Some((lit, _)), Data::Enum(_)) = (&rename_all_file, &configopt_type.data) {
+            abort!(
+                lit,
+                "`configopt(rename_all_file)` is only supported on structs"
+            );
+        }
+        let rename_all_file = rename_all_file.map(|(_, style)| style);
+        let rename_all_env = rename_all_env.map(|(_, style)| style);
+
+        // Check which generated surface the type opted out of
+        let skip = Skip::parse(
+            configopt_type.tag_parameters(&parse_quote!(configopt), &parse_quote!(skip)),
+        );
+
         // Get a list of attributes to retain on the configopt type
         let mut retained_attrs = configopt_type
             .tag_parameters(&parse_quote!(configopt), &parse_quote!(attrs))
             .into_iter()
             .map(|meta| {
                 proc_macro_roids::nested_meta_to_path(&meta)
-                    .expect("#[configopt(attrs(..))] expected a path not a Rust literal")
+                    .unwrap_or_else(|| {
+                        abort!(
+                            meta,
+                            "#[configopt(attrs(..))] expected a path not a Rust literal"
+                        )
+                    })
                     .get_ident()
                     .cloned()
-                    .expect("#[configopt(attrs(..))] expected an ident")
+                    .unwrap_or_else(|| abort!(meta, "#[configopt(attrs(..))] expected an ident"))
             })
             .collect::<Vec<_>>();
 
@@ -48,14 +251,55 @@ impl ConfigOptConstruct {
             .into_iter()
             .collect::<Punctuated<_, Token![,]>>();
 
+        // Check if unknown keys in config files should be rejected
+        let strict = configopt_type.contains_tag(&parse_quote!(configopt), &parse_quote!(strict));
+
+        // Check if string values should be interpolated while loading
+        let interpolate =
+            configopt_type.contains_tag(&parse_quote!(configopt), &parse_quote!(interpolate));
+
+        // Check if `init_global` and `global` accessors should be generated
+        let global = configopt_type.contains_tag(&parse_quote!(configopt), &parse_quote!(global));
+        if global && !configopt_type.generics.params.is_empty() {
+            abort!(
+                configopt_type.generics,
+                "`configopt(global)` cannot be used on a generic type, the global config is a \
+                 `static` which cannot depend on the type parameters"
+            );
+        }
+
+        // Check if a `config` subcommand should be generated
+        let config_command =
+            configopt_type.contains_tag(&parse_quote!(configopt), &parse_quote!(config_command));
+
         parse::retain_attrs(&mut configopt_type.attrs, &retained_attrs);
 
+        // The configopt type reads the environment variables named by `configopt(rename_all_env)`
+        // and the config keys named by `configopt(rename_all_file)`
+        if let Some(style) = rename_all_env {
+            for attr in &mut configopt_type.attrs {
+                parse::remove_structopt_items(attr, &["rename_all_env"]);
+            }
+            let style = style.structopt_name();
+            configopt_type
+                .attrs
+                .push(parse_quote! {#[structopt(rename_all_env = #style)]});
+        }
+        if let Some(style) = rename_all_file.and_then(CasingStyle::serde_name) {
+            configopt_type
+                .attrs
+                .push(parse_quote! {#[serde(rename_all = #style)]});
+        }
+
         // Determine the global rename casing style for structopt and serde
         let structopt_rename = parse::structopt_rename_all(&configopt_type.attrs)
             // Structopt defaults to kebab case if no `rename_all` attribute is specified
             .unwrap_or(CasingStyle::Kebab);
-        // TODO: Actually lookup the serde name
-        let serde_rename = CasingStyle::Verbatim;
+        // Structopt defaults to screaming snake case for environment variables
+        let structopt_rename_env = parse::structopt_rename_all_env(&configopt_type.attrs)
+            .unwrap_or(CasingStyle::ScreamingSnake);
+        // TODO: Actually lookup the serde name, only `configopt(rename_all_file)` is known
+        let serde_rename = rename_all_file.unwrap_or(CasingStyle::Verbatim);
 
         // Make all fields configopt
         let configopt_construct = match &mut configopt_type.data {
@@ -72,17 +316,52 @@ impl ConfigOptConstruct {
                                 ParsedField::new(
                                     field,
                                     structopt_rename,
+                                    structopt_rename_env,
                                     serde_rename,
                                     &retained_attrs,
                                 )
                             })
                             .collect::<Vec<_>>();
-                        ConfigOptConstruct::Struct(ident, default_config_file, parsed_fields)
+                        // Secrets must not show up in the `Debug` output so replace the derive
+                        // with our own impl and add a field to read each secret from a file
+                        let partial_eq = derives.iter().any(is_partial_eq);
+                        let mut redact_debug = false;
+                        if generate::secret::has_secrets(&parsed_fields) {
+                            let len = derives.len();
+                            derives = derives.into_iter().filter(|d| !is_debug(d)).collect();
+                            redact_debug = derives.len() != len;
+                            for field in generate::secret::file_fields(&parsed_fields).named {
+                                fields.named.push(field);
+                            }
+                        }
+                        ConfigOptConstruct::Struct {
+                            ident,
+                            default_config_file,
+                            parsed_fields,
+                            redact_debug,
+                            partial_eq,
+                            env_json,
+                            env_prefix,
+                            env_separator,
+                            default_config,
+                            dotenv,
+                            section_order,
+                            binary,
+                            config_command,
+                            strict,
+                            interpolate,
+                            global,
+                            presets,
+                            validate,
+                            version_field,
+                            migrations,
+                            skip,
+                        }
                     }
-                    Fields::Unnamed(_) => {
-                        panic!("`ConfigOpt` cannot be derived for unnamed struct")
+                    Fields::Unnamed(fields) => {
+                        abort!(fields, "`ConfigOpt` cannot be derived for unnamed struct")
                     }
-                    Fields::Unit => panic!("`ConfigOpt` cannot be derived for unit structs"),
+                    Fields::Unit => abort!(ident, "`ConfigOpt` cannot be derived for unit structs"),
                 }
             }
             Data::Enum(data) => {
@@ -93,13 +372,16 @@ impl ConfigOptConstruct {
                         &ident,
                         variant,
                         structopt_rename,
+                        structopt_rename_env,
                         serde_rename,
                         &retained_attrs,
                     ));
                 }
-                ConfigOptConstruct::Enum(ident, parsed_variants)
+                ConfigOptConstruct::Enum(ident, parsed_variants, global, skip)
+            }
+            Data::Union(data) => {
+                abort!(data.union_token, "`ConfigOpt` cannot be derived for unions")
             }
-            Data::Union(_) => panic!("`ConfigOpt` cannot be derived for unions"),
         };
 
         // Add the derives
@@ -116,8 +398,50 @@ impl ConfigOptConstruct {
         let ident = self.ident();
         let other = parse_quote! {other};
         let configopt_ident = parse::configopt_ident(ident);
+        let partial = if self.skip().partial {
+            quote! {}
+        } else {
+            generate::partial::generate(ident, &configopt_ident)
+        };
+        let global = if self.global() {
+            generate::global::generate(ident)
+        } else {
+            quote! {}
+        };
+        let schema_skipped = self.skip().schema;
+        // The runtime schema is still generated so loading and checking values keep working
+        let public_schema = if schema_skipped {
+            quote! {
+                fn schema() -> ::configopt::Schema {
+                    ::configopt::Schema::new(Vec::new())
+                }
+            }
+        } else {
+            quote! {}
+        };
         match self {
-            Self::Struct(_, default_config_file, parsed_fields) => {
+            Self::Struct {
+                default_config_file,
+                parsed_fields,
+                redact_debug,
+                partial_eq,
+                env_json,
+                env_prefix,
+                env_separator,
+                default_config,
+                dotenv,
+                section_order,
+                binary,
+                config_command,
+                strict,
+                interpolate,
+                presets,
+                validate,
+                version_field,
+                migrations,
+                skip,
+                ..
+            } => {
                 use generate::core::struct_type;
 
                 let configopt_patch = struct_type::patch(&parsed_fields);
@@ -127,20 +451,308 @@ impl ConfigOptConstruct {
                 let configopt_is_empty = struct_type::is_empty(&parsed_fields);
                 let configopt_is_complete = struct_type::is_complete(&parsed_fields);
                 let configopt_is_convertible = struct_type::is_convertible(&parsed_fields);
+                let configopt_missing_fields = struct_type::missing_fields(&parsed_fields);
+                let configopt_set_fields = struct_type::set_fields(&parsed_fields);
+                let builder = if skip.builder {
+                    quote! {}
+                } else {
+                    generate::builder::for_struct(&parsed_fields)
+                };
                 let configopt_from = struct_type::from(&parsed_fields, &other);
                 let configopt_try_from = struct_type::try_from(&parsed_fields);
                 let default_config_files =
                     generate::default_config_files::generate(default_config_file.as_ref());
+                let config_command = generate::config_command::generate(ident, *config_command);
+                let env_json = generate::env_json::generate(env_json.as_deref());
+                let env_prefix =
+                    generate::env_json::generate_prefix(env_prefix.as_deref(), env_separator);
                 let handle_config_files_generate =
                     generate::handle_config_files::generate_for_struct(parsed_fields.as_slice());
+                let handle_config_files_explain =
+                    generate::handle_config_files::explain_for_struct(parsed_fields.as_slice());
                 let handle_config_files_patch = generate::handle_config_files::patch_for_struct(
                     parsed_fields.as_slice(),
                     &configopt_ident,
                 );
                 let toml_config_generator_with_prefix =
                     generate::toml_config::for_struct(&parsed_fields);
+                let toml_values = generate::toml_values::for_struct(&parsed_fields);
                 let configopt_defaults_field_match =
                     generate::configopt_defaults::for_struct(&parsed_fields);
+                let resolve_secret_files =
+                    generate::secret::resolve_files_for_struct(&parsed_fields);
+                let decrypt_fields = generate::secret::decrypt_for_struct(&parsed_fields);
+                let arg_is_secret = generate::secret::arg_is_secret_for_struct(&parsed_fields);
+                let deprecated_aliases = generate::deprecated::for_struct(&parsed_fields);
+                let negatable_args = generate::negation::args_for_struct(&parsed_fields);
+                let negate_arg = generate::negation::negate_for_struct(&parsed_fields);
+                let arg_prefixes = generate::prefix::for_struct(&parsed_fields);
+                let decoded_env_args = generate::decode_env::for_struct(&parsed_fields);
+                let interpolate_with =
+                    generate::interpolate::for_struct(&parsed_fields, Pass::Interpolate);
+                let resolve_references_with =
+                    generate::interpolate::for_struct(&parsed_fields, Pass::Resolve);
+                let schema_fields = generate::schema::for_struct(&parsed_fields);
+                let load_dir = generate::strict::load(
+                    *strict,
+                    quote! {unknown_keys},
+                    |unknown_keys| quote! {::configopt::from_toml_table(table, path, #unknown_keys)?},
+                );
+                let dir_and_field_paths = quote! {
+                    /// Read each file in the directory `path` as a key like a mounted Kubernetes
+                    /// ConfigMap or Secret
+                    ///
+                    /// See [`dir_table`](../configopt/fn.dir_table.html) for the layout.
+                    pub fn from_dir(path: impl ::std::convert::AsRef<::std::path::Path>) -> ::configopt::Result<Self> {
+                        Self::from_dir_collecting_unknown(path.as_ref(), &mut Vec::new())
+                    }
+
+                    /// Read a directory like `from_dir` collecting the files which are not
+                    /// recognized instead of ignoring them
+                    pub fn from_dir_collecting_unknown(
+                        path: &::std::path::Path,
+                        unknown_keys: &mut Vec<::configopt::UnknownKey>,
+                    ) -> ::configopt::Result<Self> {
+                        let started = ::std::time::Instant::now();
+                        let table = ::configopt::dir_table(path, &<Self as ::configopt::ConfigOptType>::runtime_schema())?;
+                        let configopt: Self = #load_dir;
+                        configopt.finish_load(
+                            ::configopt::TraceSource::Dir { path: path.to_string_lossy().into_owned() },
+                            Some(path),
+                            started,
+                        )
+                    }
+
+                    /// The value of the field at the dotted `path` or `None` if it is not set
+                    ///
+                    /// Secrets are never returned.
+                    pub fn get(&self, path: &::configopt::FieldPath) -> Option<::configopt::Value> {
+                        ::configopt::get_field(self, path)
+                    }
+
+                    /// Set the field at the dotted `path` parsing a string `value` as the kind
+                    /// of value the field holds
+                    pub fn set(
+                        &mut self,
+                        path: &::configopt::FieldPath,
+                        value: impl ::std::convert::Into<::configopt::Value>,
+                    ) -> ::std::result::Result<(), String> {
+                        ::configopt::set_field(self, path, value.into())
+                    }
+                };
+                // `lint_file` and `reference_doc` only describe the public schema
+                let schema_outputs = if schema_skipped {
+                    quote! {}
+                } else {
+                    quote! {
+                        /// Lint a config file reporting unknown keys, type mismatches, deprecated
+                        /// keys, and missing required values without constructing the full type
+                        pub fn lint_file(path: impl ::std::convert::AsRef<::std::path::Path>) -> Vec<::configopt::Lint> {
+                            ::configopt::lint_toml_file(
+                                path,
+                                &<Self as ::configopt::ConfigOptType>::schema(),
+                                &<Self as ::configopt::ConfigOptType>::deprecated_aliases(),
+                            )
+                        }
+
+                        /// Document every option with its CLI flag, environment variable, config
+                        /// key, type, default, and description
+                        pub fn reference_doc(format: ::configopt::DocFormat) -> String {
+                            ::configopt::reference_doc(
+                                &<#ident as ::configopt::ConfigOpt>::prefixed_clap(),
+                                &<Self as ::configopt::ConfigOptType>::schema(),
+                                format,
+                            )
+                        }
+                    }
+                };
+                let versioned = version_field.is_some();
+                let async_source =
+                    generate::async_source::for_struct(&parsed_fields, versioned, *strict);
+                let load_file = generate::subcommand::load_for_struct(
+                    &parsed_fields,
+                    versioned,
+                    *strict,
+                    quote! {path},
+                    quote! {unknown_keys},
+                    |unknown_keys| match &binary {
+                        Some(binary) => {
+                            quote! {::configopt::from_shared_config_file(path, #binary, <Self as ::configopt::ConfigOptType>::shared_sections(), <Self as ::configopt::ConfigOptType>::runtime_schema, &presets, &aliases, #unknown_keys)}
+                        }
+                        None => {
+                            quote! {::configopt::from_config_file(path, <Self as ::configopt::ConfigOptType>::runtime_schema, &presets, &aliases, #unknown_keys)}
+                        }
+                    },
+                );
+                let load_file_with_profile = generate::subcommand::load_for_struct(
+                    &parsed_fields,
+                    versioned,
+                    *strict,
+                    quote! {path},
+                    quote! {&mut Vec::new()},
+                    |unknown_keys| quote! {::configopt::from_toml_file_with_profile_and_presets(path, profile, &presets, &aliases, #unknown_keys)},
+                );
+                let load_str = generate::subcommand::load_for_struct(
+                    &parsed_fields,
+                    versioned,
+                    *strict,
+                    quote! {path},
+                    quote! {&mut Vec::new()},
+                    |unknown_keys| match &binary {
+                        Some(binary) => {
+                            quote! {::configopt::from_shared_config_str(contents, format, path, #binary, <Self as ::configopt::ConfigOptType>::shared_sections(), <Self as ::configopt::ConfigOptType>::runtime_schema, &presets, &aliases, #unknown_keys)}
+                        }
+                        None => {
+                            quote! {::configopt::from_config_str_with_schema(contents, format, path, <Self as ::configopt::ConfigOptType>::runtime_schema, &presets, &aliases, #unknown_keys)}
+                        }
+                    },
+                );
+                // `include_str!` resolves a path relative to the file invoking it so anchor the path
+                // at the crate root instead
+                let from_default_config = match default_config {
+                    Some(lit) => {
+                        let path = lit.value();
+                        let format = if path.ends_with(".json") {
+                            quote! {::configopt::ConfigFormat::Json}
+                        } else if path.ends_with(".yaml") || path.ends_with(".yml") {
+                            quote! {::configopt::ConfigFormat::Yaml}
+                        } else if path.ends_with(".ini") {
+                            quote! {::configopt::ConfigFormat::Ini}
+                        } else if path.ends_with(".properties") {
+                            quote! {::configopt::ConfigFormat::Properties}
+                        } else if path.ends_with(".conf") || path.ends_with(".hocon") {
+                            quote! {::configopt::ConfigFormat::Hocon}
+                        } else {
+                            quote! {::configopt::ConfigFormat::Toml}
+                        };
+                        quote! {
+                            let started = ::std::time::Instant::now();
+                            let contents = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/", #lit));
+                            let format = #format;
+                            let path = ::std::path::Path::new(#lit);
+                            let aliases = <Self as ::configopt::ConfigOptType>::deprecated_aliases();
+                            let presets = <Self as ::configopt::ConfigOptType>::presets();
+                            let configopt: Self = #load_str;
+                            configopt.finish_load(
+                                ::configopt::TraceSource::DefaultConfig { path: String::from(#lit) },
+                                None,
+                                started,
+                            )
+                        }
+                    }
+                    None => quote! {
+                        Ok(Self::default())
+                    },
+                };
+                let subcommand_path = generate::subcommand::path_for_struct(&parsed_fields);
+                let presets = presets
+                    .as_ref()
+                    .map(|path| {
+                        quote! {
+                            fn presets() -> Vec<::configopt::Preset> {
+                                #path()
+                            }
+                        }
+                    })
+                    .unwrap_or_default();
+                let (versions, migrate_file) = match version_field {
+                    Some(version_field) => {
+                        let migrations = migrations
+                            .as_ref()
+                            .map(|path| {
+                                quote! {
+                                    fn migrations() -> Vec<::configopt::Migration> {
+                                        #path()
+                                    }
+                                }
+                            })
+                            .unwrap_or_default();
+                        let versions = quote! {
+                            fn version_field() -> Option<&'static str> {
+                                Some(#version_field)
+                            }
+
+                            #migrations
+                        };
+                        let migrate_file = quote! {
+                            /// Upgrade the config file at `path` to the current schema version
+                            /// rewriting it in place, returning if it was rewritten
+                            pub fn migrate_file(path: impl ::std::convert::AsRef<::std::path::Path>) -> ::configopt::Result<bool> {
+                                ::configopt::migrate_file(
+                                    path,
+                                    #version_field,
+                                    &<Self as ::configopt::ConfigOptType>::migrations(),
+                                )
+                            }
+                        };
+                        (versions, migrate_file)
+                    }
+                    None => (quote! {}, quote! {}),
+                };
+                let dotenv = dotenv
+                    .as_ref()
+                    .map(|lit| {
+                        quote! {
+                            fn dotenv_file() -> Option<&'static str> {
+                                Some(#lit)
+                            }
+                        }
+                    })
+                    .unwrap_or_default();
+                let section_order = if section_order.is_empty() {
+                    quote! {}
+                } else {
+                    quote! {
+                        fn section_order() -> &'static [&'static str] {
+                            &[#(#section_order),*]
+                        }
+                    }
+                };
+                let binary = binary
+                    .as_ref()
+                    .map(|binary| {
+                        let sections = parsed_fields
+                            .iter()
+                            .filter(|f| f.is_section())
+                            .map(|f| f.serde_name());
+                        quote! {
+                            fn binary() -> Option<&'static str> {
+                                Some(#binary)
+                            }
+
+                            fn shared_sections() -> &'static [&'static str] {
+                                &[#(#sections),*]
+                            }
+                        }
+                    })
+                    .unwrap_or_default();
+                let validate = validate
+                    .as_ref()
+                    .map(|path| {
+                        quote! {
+                            fn validate(&self, provenance: &::configopt::Provenance) -> ::configopt::Result<()> {
+                                #path(self, provenance)
+                            }
+                        }
+                    })
+                    .unwrap_or_default();
+                let identity =
+                    generate::identity::for_struct(ident, &configopt_ident, &parsed_fields);
+                let diff = if *partial_eq && !skip.diff {
+                    generate::diff::for_struct(&configopt_ident, &parsed_fields)
+                } else {
+                    quote! {}
+                };
+                let debug = if *redact_debug {
+                    let debug =
+                        generate::secret::debug_for_struct(&configopt_ident, &parsed_fields);
+                    quote! {
+                        #lints
+                        #debug
+                    }
+                } else {
+                    quote! {}
+                };
                 quote! {
                     #lints
                     impl #configopt_ident {
@@ -182,9 +794,161 @@ impl ConfigOptConstruct {
                             #configopt_is_convertible
                         }
 
+                        /// The dotted paths of the required fields which prevent `self` from being
+                        /// converted into a full version
+                        pub fn missing_fields(&self) -> Vec<String> {
+                            let mut missing = Vec::new();
+                            #configopt_missing_fields
+                            missing
+                        }
+
+                        /// The dotted paths of the fields which are set
+                        pub fn set_fields(&self) -> Vec<String> {
+                            let mut set = Vec::new();
+                            #configopt_set_fields
+                            set
+                        }
+
+                        /// Parse a config file collecting the keys which are not recognized instead of
+                        /// ignoring them
+                        pub fn try_from_path_collecting_unknown(
+                            path: &::std::path::Path,
+                            unknown_keys: &mut Vec<::configopt::UnknownKey>,
+                        ) -> ::configopt::Result<Self> {
+                            let started = ::std::time::Instant::now();
+                            let aliases = <Self as ::configopt::ConfigOptType>::deprecated_aliases();
+                            let presets = <Self as ::configopt::ConfigOptType>::presets();
+                            let configopt: Self = #load_file;
+                            configopt.finish_load(
+                                ::configopt::TraceSource::ConfigFile { path: path.to_string_lossy().into_owned() },
+                                path.parent(),
+                                started,
+                            )
+                        }
+
+                        /// Parse a config file merging the `[profiles.<profile>]` table over the base values
+                        pub fn from_file_with_profile(
+                            path: impl ::std::convert::AsRef<::std::path::Path>,
+                            profile: &str,
+                        ) -> ::configopt::Result<Self> {
+                            let started = ::std::time::Instant::now();
+                            let path = path.as_ref();
+                            let aliases = <Self as ::configopt::ConfigOptType>::deprecated_aliases();
+                            let presets = <Self as ::configopt::ConfigOptType>::presets();
+                            let configopt: Self = #load_file_with_profile;
+                            configopt.finish_load(
+                                ::configopt::TraceSource::ConfigFile { path: path.to_string_lossy().into_owned() },
+                                path.parent(),
+                                started,
+                            )
+                        }
+
+                        /// Parse a config document which is already in memory
+                        ///
+                        /// Errors refer to the document as `<memory>`.
+                        pub fn try_from_config_str(
+                            contents: &str,
+                            format: ::configopt::ConfigFormat,
+                        ) -> ::configopt::Result<Self> {
+                            let started = ::std::time::Instant::now();
+                            let path = ::std::path::Path::new("<memory>");
+                            let aliases = <Self as ::configopt::ConfigOptType>::deprecated_aliases();
+                            let presets = <Self as ::configopt::ConfigOptType>::presets();
+                            let configopt: Self = #load_str;
+                            configopt.finish_load(
+                                ::configopt::TraceSource::ConfigFile { path: path.to_string_lossy().into_owned() },
+                                None,
+                                started,
+                            )
+                        }
+
+                        /// Parse the config embedded in the binary with `#[configopt(default_config)]`
+                        ///
+                        /// This is the lowest priority layer. It is empty if no config is embedded.
+                        pub fn from_default_config() -> ::configopt::Result<Self> {
+                            #from_default_config
+                        }
+
+                        #migrate_file
+
+                        #dir_and_field_paths
+
+                        #schema_outputs
+
+                        #async_source
+
+                        /// Parse and merge config files collecting the keys which are not recognized
+                        /// instead of ignoring them
+                        pub fn try_from_paths_collecting_unknown<T: ::std::convert::AsRef<::std::path::Path>>(
+                            paths: &[T],
+                            unknown_keys: &mut Vec<::configopt::UnknownKey>,
+                        ) -> ::configopt::Result<Self> {
+                            let mut result = #configopt_ident::default();
+                            for path in paths {
+                                match #configopt_ident::try_from_path_collecting_unknown(path.as_ref(), unknown_keys) {
+                                    Ok(mut from_default_config_file) => {
+                                        result.take(&mut from_default_config_file);
+                                    },
+                                    Err(e) if e.config_file_not_found() => {
+                                        // If we could not find the config file do nothing.
+                                    },
+                                    Err(e) => return Err(e),
+                                }
+                            }
+                            Ok(result)
+                        }
+
+                        /// Read each unset secret field from its `<field>_file` path
+                        pub fn resolve_secret_files(&mut self) -> ::configopt::Result<()> {
+                            self.resolve_secret_files_in(None)
+                        }
+
+                        /// Read each unset secret field from its `<field>_file` path where relative
+                        /// paths are relative to `dir`, the directory of the config file they were
+                        /// read from
+                        pub fn resolve_secret_files_in(&mut self, dir: Option<&::std::path::Path>) -> ::configopt::Result<()> {
+                            #resolve_secret_files
+                            Ok(())
+                        }
+
+                        /// Decrypt each `#[configopt(decrypt)]` field which is set
+                        pub fn decrypt_fields(&mut self) -> ::configopt::Result<()> {
+                            #decrypt_fields
+                            Ok(())
+                        }
+
+                        /// Finish loading a layer read from `source` by reading the secret files
+                        /// relative to `dir` and decrypting fields then record it in the load trace
+                        #[doc(hidden)]
+                        pub fn finish_load(
+                            mut self,
+                            source: ::configopt::TraceSource,
+                            dir: Option<&::std::path::Path>,
+                            started: ::std::time::Instant,
+                        ) -> ::configopt::Result<Self> {
+                            self.resolve_secret_files_in(dir)?;
+                            self.decrypt_fields()?;
+                            ::configopt::LoadTrace::record(source, || self.set_fields(), started);
+                            Ok(self)
+                        }
+
                         #default_config_files
+
+                        #config_command
+
+                        #env_json
+
+                        #env_prefix
+
+                        #builder
                     }
 
+                    #debug
+
+                    #identity
+
+                    #diff
+
                     #lints
                     impl ::std::convert::From<#ident> for #configopt_ident {
                         fn from(other: #ident) -> Self {
@@ -194,12 +958,12 @@ impl ConfigOptConstruct {
 
                     #lints
                     impl ::std::convert::TryFrom<#configopt_ident> for #ident {
-                        type Error = #configopt_ident;
+                        type Error = ::configopt::Error;
                         fn try_from(configopt: #configopt_ident) -> ::std::result::Result<Self, Self::Error> {
                             use ::std::convert::TryInto;
 
                             if !configopt.is_convertible() {
-                                return Err(configopt);
+                                return Err(::configopt::Error::MissingFields(configopt.missing_fields()));
                             }
                             #configopt_try_from
                         }
@@ -210,7 +974,7 @@ impl ConfigOptConstruct {
                         type Error = ::configopt::Error;
 
                         fn try_from(path: &::std::path::Path) -> ::std::result::Result<Self, Self::Error> {
-                            ::configopt::from_toml_file(path)
+                            Self::try_from_path_collecting_unknown(path, &mut Vec::new())
                         }
                     }
 
@@ -219,19 +983,7 @@ impl ConfigOptConstruct {
                         type Error = ::configopt::Error;
 
                         fn try_from(paths: &[T]) -> ::std::result::Result<Self, Self::Error> {
-                            let mut result = #configopt_ident::default();
-                            for path in paths {
-                                match #configopt_ident::try_from(path.as_ref()) {
-                                    Ok(mut from_default_config_file) => {
-                                        result.take(&mut from_default_config_file);
-                                    },
-                                    Err(e) if e.config_file_not_found() => {
-                                        // If we could not find the config file do nothing.
-                                    },
-                                    Err(e) => return Err(e),
-                                }
-                            }
-                            Ok(result)
+                            Self::try_from_paths_collecting_unknown(paths, &mut Vec::new())
                         }
                     }
 
@@ -245,10 +997,27 @@ impl ConfigOptConstruct {
                                 None
                             }
                         }
+
+                        fn arg_is_secret(&self, arg_path: &[String]) -> bool {
+                            let full_arg_path = arg_path;
+                            if let Some((arg_name, arg_path)) = full_arg_path.split_first() {
+                                #arg_is_secret
+                            } else {
+                                false
+                            }
+                        }
                     }
 
+                    #partial
+
+                    #global
+
                     #lints
-                    impl ::configopt::IgnoreHelp for #configopt_ident {}
+                    impl ::configopt::IgnoreHelp for #configopt_ident {
+                        #arg_prefixes
+
+                        #decoded_env_args
+                    }
 
                     #lints
                     impl ::configopt::ConfigOptType for #configopt_ident {
@@ -257,14 +1026,90 @@ impl ConfigOptConstruct {
                             None
                         }
 
+                        fn maybe_explain_config(&self) -> bool {
+                            #handle_config_files_explain
+                        }
+
                         fn patch_with_config_files(&mut self) -> ::configopt::Result<&mut #configopt_ident> {
                             #handle_config_files_patch
                         }
 
+                        fn patch_with_config_str(
+                            &mut self,
+                            contents: &str,
+                            format: ::configopt::ConfigFormat,
+                        ) -> ::configopt::Result<&mut #configopt_ident> {
+                            let mut from_config_str = #configopt_ident::try_from_config_str(contents, format)?;
+                            self.patch(&mut from_config_str);
+                            Ok(self)
+                        }
+
                         fn toml_config_with_prefix(&self, serde_prefix: &[String]) -> String {
                             let app = #ident::clap();
                             #toml_config_generator_with_prefix
                         }
+
+                        fn toml_values_with_prefix(
+                            &self,
+                            serde_prefix: &[String],
+                            secrets: bool,
+                        ) -> ::std::result::Result<toml::value::Table, ::configopt::TomlValueError> {
+                            #toml_values
+                        }
+
+                        fn deprecated_aliases() -> Vec<::configopt::Alias> {
+                            #deprecated_aliases
+                        }
+
+                        #presets
+
+                        #versions
+
+                        #dotenv
+
+                        #section_order
+
+                        #binary
+
+                        fn negatable_args() -> Vec<&'static str> {
+                            #negatable_args
+                        }
+
+                        fn negate_arg(&mut self, arg: &str) {
+                            #negate_arg
+                        }
+
+                        fn set_fields(&self) -> Vec<String> {
+                            #configopt_ident::set_fields(self)
+                        }
+
+                        fn interpolates() -> bool {
+                            #interpolate
+                        }
+
+                        fn interpolate_with(
+                            &mut self,
+                            lookup: &dyn Fn(&str) -> Option<String>,
+                        ) -> ::std::result::Result<(), String> {
+                            #interpolate_with
+                        }
+
+                        fn resolve_references_with(
+                            &mut self,
+                            resolve: &dyn Fn(&str) -> Option<::std::result::Result<String, String>>,
+                        ) -> ::std::result::Result<(), String> {
+                            #resolve_references_with
+                        }
+
+                        fn schema_fields(prefix: &str) -> Vec<::configopt::SchemaField> {
+                            #schema_fields
+                        }
+
+                        #public_schema
+
+                        fn subcommand_path(&self) -> Vec<&'static str> {
+                            #subcommand_path
+                        }
                     }
 
                     #lints
@@ -278,27 +1123,49 @@ impl ConfigOptConstruct {
                         fn take(&mut self, other: &mut Self::ConfigOptType) {
                             other.take_for(self);
                         }
+
+                        #validate
                     }
                 }
             }
-            Self::Enum(_, parsed_variants) => {
+            Self::Enum(_, parsed_variants, _, _) => {
                 use generate::core::enum_type;
 
                 let configopt_patch = enum_type::patch(&parsed_variants);
+                let decoded_env_args = generate::decode_env::for_enum(&parsed_variants);
                 let configopt_take = enum_type::take(&parsed_variants);
                 let configopt_patch_for = enum_type::patch_for(&parsed_variants);
                 let configopt_take_for = enum_type::take_for(&parsed_variants);
                 let configopt_is_empty = enum_type::is_empty(&parsed_variants);
                 let configopt_is_complete = enum_type::is_complete(&parsed_variants);
                 let configopt_is_convertible = enum_type::is_convertible(&parsed_variants);
+                let configopt_missing_fields = enum_type::missing_fields(&parsed_variants);
+                let configopt_set_fields = enum_type::set_fields(&parsed_variants);
                 let configopt_from = enum_type::from(&parsed_variants);
                 let configopt_try_from = enum_type::try_from(&parsed_variants);
                 let handle_config_files_generate =
                     generate::handle_config_files::generate_for_enum(parsed_variants);
+                let handle_config_files_explain =
+                    generate::handle_config_files::explain_for_enum(parsed_variants);
                 let handle_config_files_patch =
                     generate::handle_config_files::patch_for_enum(parsed_variants);
+                let handle_config_str_patch =
+                    generate::handle_config_files::patch_str_for_enum(parsed_variants);
                 let configopt_defaults_variant =
                     generate::configopt_defaults::for_enum(&parsed_variants);
+                let arg_is_secret_variant =
+                    generate::secret::arg_is_secret_for_enum(&parsed_variants);
+                let deprecated_aliases = generate::deprecated::for_enum(&parsed_variants);
+                let negatable_args = generate::negation::args_for_enum(&parsed_variants);
+                let negate_arg = generate::negation::negate_for_enum(&parsed_variants);
+                let interpolate_with =
+                    generate::interpolate::for_enum(&parsed_variants, Pass::Interpolate);
+                let resolve_references_with =
+                    generate::interpolate::for_enum(&parsed_variants, Pass::Resolve);
+                let schema_fields = generate::schema::for_enum(&parsed_variants);
+                let toml_values = generate::toml_values::for_enum(&parsed_variants);
+                let subcommand_variants = generate::subcommand::variants_for_enum(&parsed_variants);
+                let subcommand_path = generate::subcommand::path_for_enum(&parsed_variants);
 
                 quote! {
 
@@ -359,6 +1226,25 @@ impl ConfigOptConstruct {
                                 #configopt_is_convertible
                             }
                         }
+
+                        /// The dotted paths of the required fields which prevent `self` from being
+                        /// converted into a full version
+                        pub fn missing_fields(&self) -> Vec<String> {
+                            let mut missing = Vec::new();
+                            match self {
+                                #configopt_missing_fields
+                            }
+                            missing
+                        }
+
+                        /// The dotted paths of the fields which are set
+                        pub fn set_fields(&self) -> Vec<String> {
+                            let mut set = Vec::new();
+                            match self {
+                                #configopt_set_fields
+                            }
+                            set
+                        }
                     }
 
                     #lints
@@ -372,12 +1258,12 @@ impl ConfigOptConstruct {
 
                     #lints
                     impl ::std::convert::TryFrom<#configopt_ident> for #ident {
-                        type Error = #configopt_ident;
+                        type Error = ::configopt::Error;
                         fn try_from(configopt: #configopt_ident) -> ::std::result::Result<Self, Self::Error> {
                             use ::std::convert::TryInto;
 
                             if !configopt.is_convertible() {
-                                return Err(configopt);
+                                return Err(::configopt::Error::MissingFields(configopt.missing_fields()));
                             }
                             match configopt {
                                 #configopt_try_from
@@ -393,10 +1279,26 @@ impl ConfigOptConstruct {
                                 _ => None,
                             }
                         }
+
+                        fn arg_is_secret(&self, arg_path: &[String]) -> bool {
+                            if arg_path.is_empty() {
+                                return false;
+                            }
+                            match self {
+                                #arg_is_secret_variant
+                                _ => false,
+                            }
+                        }
                     }
 
+                    #partial
+
+                    #global
+
                     #lints
-                    impl ::configopt::IgnoreHelp for #configopt_ident {}
+                    impl ::configopt::IgnoreHelp for #configopt_ident {
+                        #decoded_env_args
+                    }
 
                     #lints
                     impl ::configopt::ConfigOptType for #configopt_ident {
@@ -408,6 +1310,13 @@ impl ConfigOptConstruct {
                             None
                         }
 
+                        fn maybe_explain_config(&self) -> bool {
+                            match self {
+                                #handle_config_files_explain
+                                _ => false,
+                            }
+                        }
+
 
                         fn patch_with_config_files(&mut self) -> ::configopt::Result<&mut #configopt_ident> {
                             match self {
@@ -417,9 +1326,75 @@ impl ConfigOptConstruct {
                             Ok(self)
                         }
 
+                        fn patch_with_config_str(
+                            &mut self,
+                            contents: &str,
+                            format: ::configopt::ConfigFormat,
+                        ) -> ::configopt::Result<&mut #configopt_ident> {
+                            match self {
+                                #handle_config_str_patch
+                                _ => {}
+                            }
+                            Ok(self)
+                        }
+
                         fn toml_config_with_prefix(&self, serde_prefix: &[String]) -> String {
                             todo!()
                         }
+
+                        fn toml_values_with_prefix(
+                            &self,
+                            serde_prefix: &[String],
+                            secrets: bool,
+                        ) -> ::std::result::Result<toml::value::Table, ::configopt::TomlValueError> {
+                            #toml_values
+                        }
+
+                        fn deprecated_aliases() -> Vec<::configopt::Alias> {
+                            #deprecated_aliases
+                        }
+
+                        fn negatable_args() -> Vec<&'static str> {
+                            #negatable_args
+                        }
+
+                        fn negate_arg(&mut self, arg: &str) {
+                            #negate_arg
+                        }
+
+                        fn set_fields(&self) -> Vec<String> {
+                            #configopt_ident::set_fields(self)
+                        }
+
+                        fn interpolate_with(
+                            &mut self,
+                            lookup: &dyn Fn(&str) -> Option<String>,
+                        ) -> ::std::result::Result<(), String> {
+                            #interpolate_with
+                        }
+
+                        fn resolve_references_with(
+                            &mut self,
+                            resolve: &dyn Fn(&str) -> Option<::std::result::Result<String, String>>,
+                        ) -> ::std::result::Result<(), String> {
+                            #resolve_references_with
+                        }
+
+                        fn schema_fields(prefix: &str) -> Vec<::configopt::SchemaField> {
+                            #schema_fields
+                        }
+
+                        #public_schema
+
+                        fn subcommand_variants() -> Vec<::configopt::SubcommandVariant> {
+                            #subcommand_variants
+                        }
+
+                        fn subcommand_path(&self) -> Vec<&'static str> {
+                            match self {
+                                #subcommand_path
+                            }
+                        }
                     }
 
                     #lints
@@ -441,8 +1416,53 @@ impl ConfigOptConstruct {
 
     fn ident(&self) -> &Ident {
         match self {
-            Self::Struct(ident, _, _) => ident,
-            Self::Enum(ident, _) => ident,
+            Self::Struct { ident, .. } => ident,
+            Self::Enum(ident, _, _, _) => ident,
         }
     }
+
+    fn skip(&self) -> Skip {
+        match self {
+            Self::Struct { skip, .. } => *skip,
+            Self::Enum(_, _, _, skip) => *skip,
+        }
+    }
+
+    fn global(&self) -> bool {
+        match self {
+            Self::Struct { global, .. } => *global,
+            Self::Enum(_, _, global, _) => *global,
+        }
+    }
+}
+
+fn is_debug(derive: &NestedMeta) -> bool {
+    match derive {
+        NestedMeta::Meta(Meta::Path(path)) => path.is_ident("Debug"),
+        _ => false,
+    }
+}
+
+fn is_partial_eq(derive: &NestedMeta) -> bool {
+    match derive {
+        NestedMeta::Meta(Meta::Path(path)) => path.is_ident("PartialEq"),
+        _ => false,
+    }
+}
+
+// The casing style of a `configopt(rename_all_*("style"))` container attribute
+fn casing_style(configopt_type: &DeriveInput, name: &str) -> Option<(LitStr, CasingStyle)> {
+    let path: syn::Path = Ident::new(name, Span::call_site()).into();
+    configopt_type
+        .tag_parameter(&parse_quote!(configopt), &path)
+        .map(|meta| match meta {
+            NestedMeta::Lit(Lit::Str(lit)) => {
+                let style = lit
+                    .value()
+                    .parse()
+                    .unwrap_or_else(|e: String| abort!(lit, e));
+                (lit, style)
+            }
+            meta => abort!(meta, "`configopt({})` expected a string literal", name),
+        })
 }
@@ -1,11 +1,26 @@
+pub mod async_source;
+pub mod builder;
+pub mod config_command;
 pub mod configopt_defaults;
 pub mod core;
+pub mod decode_env;
 pub mod default_config_files;
+pub mod deprecated;
+pub mod diff;
+pub mod env_json;
+pub mod global;
 pub mod handle_config_files;
+pub mod identity;
+pub mod interpolate;
+pub mod negation;
+pub mod partial;
+pub mod prefix;
+pub mod schema;
+pub mod secret;
+pub mod strict;
+pub mod subcommand;
 pub mod toml_config;
-mod try_from_error;
-
-pub use try_from_error::generate as try_from_error;
+pub mod toml_values;
 
 use proc_macro2::TokenStream;
 use quote::quote;
@@ -25,6 +40,6 @@ pub fn lints() -> TokenStream {
             clippy::cargo
         )]
         #[deny(clippy::correctness)]
-        #[allow(dead_code, unreachable_code)]
+        #[allow(dead_code, unreachable_code, unused_mut)]
     }
 }
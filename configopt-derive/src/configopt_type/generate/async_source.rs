@@ -0,0 +1,63 @@
+use super::subcommand;
+use crate::configopt_type::parse::ParsedField;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+pub fn for_struct(fields: &[ParsedField], versioned: bool, strict: bool) -> TokenStream {
+    if !cfg!(feature = "async") {
+        return quote! {};
+    }
+    let url = if cfg!(feature = "http") {
+        quote! {
+            /// Fetch a config file with an HTTP `GET` request and use it to patch any fields which
+            /// are not set
+            pub async fn patch_from_url_async(&mut self, url: &str) -> ::configopt::Result<&mut Self> {
+                self.patch_from_source_async(&::configopt::UrlSource::new(url)).await
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let load = subcommand::load_for_struct(
+        fields,
+        versioned,
+        strict,
+        quote! {::std::path::Path::new(&source.name())},
+        quote! {&mut Vec::new()},
+        |unknown_keys| quote! {::configopt::from_source_async(source, &aliases, #unknown_keys).await},
+    );
+    quote! {
+        /// Parse a config file without blocking the async runtime
+        pub async fn from_file_async(
+            path: impl ::std::convert::AsRef<::std::path::Path>,
+        ) -> ::configopt::Result<Self> {
+            Self::from_source_async(&::configopt::FileSource::new(path.as_ref())).await
+        }
+
+        /// Read and parse a config file from `source`
+        pub async fn from_source_async(
+            source: &dyn ::configopt::AsyncSource,
+        ) -> ::configopt::Result<Self> {
+            let started = ::std::time::Instant::now();
+            let aliases = <Self as ::configopt::ConfigOptType>::deprecated_aliases();
+            let configopt: Self = #load;
+            configopt.finish_load(
+                ::configopt::TraceSource::ConfigFile { path: source.name() },
+                None,
+                started,
+            )
+        }
+
+        /// Read a config file from `source` and use it to patch any fields which are not set
+        pub async fn patch_from_source_async(
+            &mut self,
+            source: &dyn ::configopt::AsyncSource,
+        ) -> ::configopt::Result<&mut Self> {
+            let mut other = Self::from_source_async(source).await?;
+            self.patch(&mut other);
+            Ok(self)
+        }
+
+        #url
+    }
+}
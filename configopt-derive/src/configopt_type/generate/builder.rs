@@ -0,0 +1,90 @@
+use crate::configopt_type::parse::{ParsedField, StructOptTy};
+use proc_macro2::TokenStream;
+use proc_macro_error::abort;
+use proc_macro_roids::IdentExt;
+use quote::quote_spanned;
+use syn::{spanned::Spanned, GenericArgument, PathArguments, Type};
+
+/// The `T` in `Option<T>`
+fn option_inner_ty(ty: &Type) -> &Type {
+    if let Type::Path(type_path) = ty {
+        if let Some(segment) = type_path.path.segments.last() {
+            if let PathArguments::AngleBracketed(args) = &segment.arguments {
+                if let Some(GenericArgument::Type(ty)) = args.args.first() {
+                    return ty;
+                }
+            }
+        }
+    }
+    abort!(ty, "`ConfigOpt` expected an `Option` type");
+}
+
+/// Is this a type which is commonly constructed from a reference (eg a `String` from a `&str`)
+fn takes_into(ty: &Type) -> bool {
+    if let Type::Path(type_path) = ty {
+        if let Some(segment) = type_path.path.segments.last() {
+            return segment.ident == "String"
+                || segment.ident == "PathBuf"
+                || segment.ident == "OsString"
+                || segment.ident == "Cow"
+                || segment.ident == "SmolStr"
+                || segment.ident == "CompactString"
+                || segment.ident == "Interned";
+        }
+    }
+    false
+}
+
+pub fn for_struct(fields: &[ParsedField]) -> TokenStream {
+    fields
+        .iter()
+        .map(|field| {
+            let field_ident = field.ident();
+            let span = field.span();
+            let setter = field_ident.prepend("with_");
+            let doc = format!("Set `{}` returning `self`", field_ident);
+            let configopt_ty = field.configopt_ty();
+            // The type of the value and how to store it in the field
+            let (value_ty, set) = if field.structopt_ty().is_bool() {
+                (
+                    quote_spanned! {span=> bool},
+                    quote_spanned! {span=> ::configopt::ConfigOptBool::from(value)},
+                )
+            } else if field.is_structopt_flatten()
+                || field.is_section()
+                || field.is_positional_vec()
+                || field.is_map()
+            {
+                (
+                    quote_spanned! {span=> #configopt_ty},
+                    quote_spanned! {span=> value},
+                )
+            } else {
+                let inner_ty = match field.structopt_ty() {
+                    StructOptTy::Option | StructOptTy::OptionOption | StructOptTy::OptionVec => {
+                        option_inner_ty(configopt_ty)
+                    }
+                    _ => configopt_ty,
+                };
+                if takes_into(inner_ty) {
+                    (
+                        quote_spanned! {span=> impl ::std::convert::Into<#inner_ty>},
+                        quote_spanned! {span=> Some(value.into())},
+                    )
+                } else {
+                    (
+                        quote_spanned! {span=> #inner_ty},
+                        quote_spanned! {span=> Some(value)},
+                    )
+                }
+            };
+            quote_spanned! {span=>
+                #[doc = #doc]
+                pub fn #setter(mut self, value: #value_ty) -> Self {
+                    self.#field_ident = #set;
+                    self
+                }
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,47 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::abort;
+use quote::quote;
+use syn::Ident;
+
+pub fn generate(ident: &Ident, config_command: bool) -> TokenStream {
+    if !config_command {
+        return quote! {};
+    }
+    if !cfg!(feature = "edit") {
+        abort!(
+            ident,
+            "`configopt(config_command)` requires the `edit` feature of `configopt`"
+        );
+    }
+    quote! {
+        /// Run the `config` subcommand if it is the first argument of `iter` returning if it ran
+        ///
+        /// The subcommand reads and changes the first default config file unless it is given
+        /// `--file`. Call this before parsing the CLI so `config` is not parsed as an argument of
+        /// the app.
+        pub fn try_run_config_command<I>(iter: I) -> ::configopt::Result<bool>
+        where
+            I: ::std::iter::IntoIterator,
+            I::Item: ::std::convert::Into<::std::ffi::OsString> + ::std::clone::Clone,
+        {
+            use ::structopt::StructOpt;
+            let args = iter.into_iter().map(::std::convert::Into::into).collect::<Vec<::std::ffi::OsString>>();
+            if args.get(1).map_or(true, |arg| arg != "config") {
+                return Ok(false);
+            }
+            let command = ::configopt::ConfigCommand::from_iter_safe(&args[1..])?;
+            let path = command.path(&Self::default_config_files()).ok_or_else(|| {
+                ::configopt::Error::ConfigFile(
+                    ::std::path::PathBuf::new(),
+                    ::std::io::Error::new(
+                        ::std::io::ErrorKind::NotFound,
+                        "there is no default config file so `--file` must be given",
+                    ),
+                )
+            })?;
+            let stdout = ::std::io::stdout();
+            command.run::<Self>(&path, &mut stdout.lock())?;
+            Ok(true)
+        }
+    }
+}
@@ -1,15 +1,22 @@
 use crate::configopt_type::parse::{FieldType, ParsedField, ParsedVariant, StructOptTy};
 use proc_macro2::TokenStream;
+use proc_macro_error::abort;
 use quote::{quote, quote_spanned};
 use syn::spanned::Spanned;
 
 fn to_os_string(field: &ParsedField) -> TokenStream {
     if field.is_structopt_flatten() {
-        panic!("`to_os_string` does not make sense for a flattened field");
+        abort!(
+            field.span(),
+            "`to_os_string` does not make sense for a flattened field"
+        );
     }
 
     if field.is_subcommand() {
-        panic!("`to_os_string` does not make sense for a subcommand field");
+        abort!(
+            field.span(),
+            "`to_os_string` does not make sense for a subcommand field"
+        );
     }
 
     let field_ident = field.ident();
@@ -98,7 +105,9 @@ fn to_os_string(field: &ParsedField) -> TokenStream {
 pub fn for_struct(fields: &[ParsedField]) -> TokenStream {
     let normal_fields = fields
         .iter()
-        .filter(|f| !f.is_structopt_flatten() && !f.is_subcommand());
+        .filter(|f| !f.is_structopt_flatten() && !f.is_subcommand() && !f.is_map())
+        // Sections and leaves have no CLI arguments
+        .filter(|f| !f.is_section() && !f.is_leaf());
     let normal_fields = normal_fields
         .map(|field| {
             let arg_name = field.structopt_name();
@@ -170,7 +170,7 @@ pub(crate) fn take_for(variants: &[ParsedVariant]) -> TokenStream {
                     let other_fields_match =
                         comma_separated_fields("other_", fields, true);
                     let inner =
-                        struct_type::patch_for_with_prefix("self_", "other_", true, fields);
+                        struct_type::take_for_with_prefix("self_", "other_", true, fields);
                     quote! {
                         (#full_configopt_ident{#self_fields_match}, #full_ident{#other_fields_match}) => {
                             #inner
@@ -304,6 +304,82 @@ pub(crate) fn is_convertible(variants: &[ParsedVariant]) -> TokenStream {
         .collect()
 }
 
+pub(crate) fn missing_fields(variants: &[ParsedVariant]) -> TokenStream {
+    if variants.is_empty() {
+        return quote! {
+            _ => {}
+        };
+    }
+    variants
+        .iter()
+        .map(|variant| {
+            let full_configopt_ident = variant.full_configopt_ident();
+            match variant.field_type() {
+                FieldType::Unnamed => {
+                    quote! {
+                        #full_configopt_ident(inner) => {
+                            missing.extend(inner.missing_fields());
+                        }
+                    }
+                }
+                FieldType::Unit => {
+                    quote! {
+                        #full_configopt_ident => {}
+                    }
+                }
+                FieldType::Named(fields) => {
+                    let fields = fields.as_slice();
+                    let fields_match = comma_separated_fields("", fields, false);
+                    let inner = struct_type::missing_fields_with_prefix("", fields);
+                    quote! {
+                        #full_configopt_ident {#fields_match} => {
+                            #inner
+                        }
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn set_fields(variants: &[ParsedVariant]) -> TokenStream {
+    if variants.is_empty() {
+        return quote! {
+            _ => {}
+        };
+    }
+    variants
+        .iter()
+        .map(|variant| {
+            let full_configopt_ident = variant.full_configopt_ident();
+            match variant.field_type() {
+                FieldType::Unnamed => {
+                    quote! {
+                        #full_configopt_ident(inner) => {
+                            set.extend(inner.set_fields());
+                        }
+                    }
+                }
+                FieldType::Unit => {
+                    quote! {
+                        #full_configopt_ident => {}
+                    }
+                }
+                FieldType::Named(fields) => {
+                    let fields = fields.as_slice();
+                    let fields_match = comma_separated_fields("", fields, false);
+                    let inner = struct_type::set_fields_with_prefix("", fields);
+                    quote! {
+                        #full_configopt_ident {#fields_match} => {
+                            #inner
+                        }
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
 pub(crate) fn from(variants: &[ParsedVariant]) -> TokenStream {
     variants
         .iter()
@@ -347,7 +423,7 @@ pub(crate) fn try_from(variants: &[ParsedVariant]) -> TokenStream {
                 FieldType::Unnamed => {
                     quote! {
                         #full_configopt_ident(inner) => {
-                            Ok(#full_ident(inner.try_into().ok().unwrap()))
+                            Ok(#full_ident(inner.try_into()?))
                         }
                     }
                 }
@@ -1,4 +1,4 @@
-use crate::configopt_type::parse::{ParsedField, StructOptTy};
+use crate::configopt_type::parse::{MergePolicy, ParsedField, StructOptTy};
 use proc_macro2::TokenStream;
 use quote::{quote, quote_spanned};
 use syn::{spanned::Spanned, Ident};
@@ -53,18 +53,38 @@ pub(crate) fn patch_with_prefix(
                 deref_other_field: _,
                 deref_self_field,
             } = FieldNames::new(field_ident, self_prefix, other_prefix, references);
-            if field.is_structopt_flatten() {
+            if field.is_structopt_flatten() || field.is_section() {
                 quote_spanned! {span=>
                     #self_field.patch(#other_field);
                 }
+            } else if field.is_map() {
+                quote_spanned! {span=>
+                    for (key, mut other_value) in ::std::mem::take(#other_field) {
+                        if let Some(self_value) = (#self_field).get_mut(&key) {
+                            self_value.patch(&mut other_value);
+                        } else {
+                            (#self_field).insert(key, other_value);
+                        }
+                    }
+                }
+            } else if field.is_subcommand() {
+                let merge = merge_subcommand(&self_field, &other_field, quote! {patch});
+                // `self` has a higher precedence than `other`
+                quote_spanned! {span=>
+                    if !#merge && (#self_field).is_none() {
+                        #deref_self_field = (#other_field).take();
+                    }
+                }
             } else if field.is_positional_vec() {
-                quote_spanned! {span=>}
-            } else {
+                // Positional values are replaced as a whole, they are never merged
                 quote_spanned! {span=>
-                    if (#self_field).is_none() {
-                        #deref_self_field = (#other_field).take().into();
+                    if (#self_field).is_empty() {
+                        ::std::mem::swap(#self_field, #other_field);
                     }
                 }
+            } else {
+                // `self` has a higher precedence than `other`
+                merge(field, &self_field, &deref_self_field, &other_field, false)
             }
         })
         .collect()
@@ -87,25 +107,128 @@ pub(crate) fn take_with_prefix(
                 deref_other_field: _,
                 deref_self_field,
             } = FieldNames::new(field_ident, self_prefix, other_prefix, references);
-            if field.is_structopt_flatten() {
+            if field.is_structopt_flatten() || field.is_section() {
                 quote_spanned! {span=>
                     #self_field.take(#other_field);
                 }
-            } else if field.is_positional_vec() {
+            } else if field.is_map() {
                 quote_spanned! {span=>
-                    ::std::mem::swap(#self_field, #other_field);
+                    for (key, mut other_value) in ::std::mem::take(#other_field) {
+                        if let Some(self_value) = (#self_field).get_mut(&key) {
+                            self_value.take(&mut other_value);
+                        } else {
+                            (#self_field).insert(key, other_value);
+                        }
+                    }
                 }
-            } else {
+            } else if field.is_subcommand() {
+                let merge = merge_subcommand(&self_field, &other_field, quote! {take});
+                // `other` has a higher precedence than `self`
                 quote_spanned! {span=>
-                    if (#other_field).is_some() {
-                        #deref_self_field = (#other_field).take().into();
+                    if !#merge && (#other_field).is_some() {
+                        #deref_self_field = (#other_field).take();
                     }
                 }
+            } else if field.is_positional_vec() {
+                // An empty list of positional values is unset
+                quote_spanned! {span=>
+                    if !(#other_field).is_empty() {
+                        ::std::mem::swap(#self_field, #other_field);
+                    }
+                }
+            } else {
+                // `other` has a higher precedence than `self`
+                merge(field, &self_field, &deref_self_field, &other_field, true)
             }
         })
         .collect()
 }
 
+/// Merge the fields of the subcommand in `other` into `self` if both layers set the same
+/// subcommand evaluating to `true` if they were merged
+///
+/// Different subcommands cannot be merged so the layer with the higher precedence replaces the
+/// subcommand as a whole.
+fn merge_subcommand(
+    self_field: &TokenStream,
+    other_field: &TokenStream,
+    method: TokenStream,
+) -> TokenStream {
+    quote! {
+        match ((#self_field).as_mut(), (#other_field).as_mut()) {
+            (Some(self_value), Some(other_value))
+                if ::std::mem::discriminant(self_value) == ::std::mem::discriminant(other_value) =>
+            {
+                self_value.#method(other_value);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Merge a leaf field from `other` into `self` according to its merge policy
+fn merge(
+    field: &ParsedField,
+    self_field: &TokenStream,
+    deref_self_field: &TokenStream,
+    other_field: &TokenStream,
+    other_has_precedence: bool,
+) -> TokenStream {
+    let span = field.span();
+    let use_other_when_self_is_none = quote_spanned! {span=>
+        if (#self_field).is_none() {
+            #deref_self_field = (#other_field).take().into();
+        }
+    };
+    let use_other_when_other_is_some = quote_spanned! {span=>
+        if (#other_field).is_some() {
+            #deref_self_field = (#other_field).take().into();
+        }
+    };
+    match (field.merge(), other_has_precedence) {
+        (MergePolicy::Overwrite, false) | (MergePolicy::Keep, true) => use_other_when_self_is_none,
+        (MergePolicy::Overwrite, true) | (MergePolicy::Keep, false) => use_other_when_other_is_some,
+        (MergePolicy::Append, other_has_precedence) => {
+            let (low, high) = if other_has_precedence {
+                (quote! {self_value}, quote! {other_value})
+            } else {
+                (quote! {other_value}, quote! {self_value})
+            };
+            quote_spanned! {span=>
+                if (#self_field).is_some() || (#other_field).is_some() {
+                    let self_value = (#self_field).take().unwrap_or_default();
+                    let other_value = (#other_field).take().unwrap_or_default();
+                    let mut values = #low;
+                    values.extend(#high);
+                    #deref_self_field = Some(values);
+                }
+            }
+        }
+        (MergePolicy::Add, _) => quote_spanned! {span=>
+            #deref_self_field = match ((#self_field).take(), (#other_field).take()) {
+                (Some(self_value), Some(other_value)) => Some(self_value + other_value),
+                (self_value, other_value) => self_value.or(other_value),
+            };
+        },
+        (policy, _) => {
+            let keep_self = if policy == MergePolicy::Min {
+                quote! {self_value <= other_value}
+            } else {
+                quote! {self_value >= other_value}
+            };
+            quote_spanned! {span=>
+                #deref_self_field = match ((#self_field).take(), (#other_field).take()) {
+                    (Some(self_value), Some(other_value)) => {
+                        Some(if #keep_self { self_value } else { other_value })
+                    }
+                    (self_value, other_value) => self_value.or(other_value),
+                };
+            }
+        }
+    }
+}
+
 pub(crate) fn patch_for_with_prefix(
     self_prefix: &str,
     other_prefix: &str,
@@ -123,7 +246,7 @@ pub(crate) fn patch_for_with_prefix(
                 deref_self_field: _,
                 deref_other_field,
             } = FieldNames::new(field_ident, self_prefix, other_prefix, references);
-            if field.is_structopt_flatten() {
+            if field.is_structopt_flatten() || field.is_section() {
                 if field.no_wrap() {
                     quote_spanned! {span=>
                         #other_field.patch(#self_field);
@@ -133,9 +256,36 @@ pub(crate) fn patch_for_with_prefix(
                         #self_field.patch_for(#other_field);
                     }
                 }
+            } else if field.is_map() {
+                // Instances which do not exist yet are only added if they are convertible. The
+                // others stay in `self` with a warning.
+                let serde_name = field.serde_name();
+                quote_spanned! {span=>
+                    for (key, mut value) in ::std::mem::take(#self_field) {
+                        if let Some(other_value) = (#other_field).get_mut(&key) {
+                            value.patch_for(other_value);
+                        } else if !value.is_convertible() {
+                            let path = format!("{}.{}", #serde_name, key);
+                            let error = ::configopt::Error::MissingFields(value.missing_fields());
+                            ::configopt::warn_skipped_instance(&path, &error);
+                            (#self_field).insert(key, value);
+                        } else {
+                            let path = format!("{}.{}", #serde_name, key);
+                            match ::std::convert::TryInto::try_into(value) {
+                                Ok(other_value) => {
+                                    (#other_field).insert(key, other_value);
+                                }
+                                Err(error) => ::configopt::warn_skipped_instance(&path, &error),
+                            }
+                        }
+                    }
+                }
             } else if field.is_subcommand() {
+                // Only the subcommand which was parsed is updated
                 quote_spanned! {span=>
-                    // TODO: handle subcommands
+                    if let Some(self_value) = (#self_field).as_mut() {
+                        self_value.patch_for(#other_field);
+                    }
                 }
             } else {
                 match field.structopt_ty() {
@@ -172,7 +322,7 @@ pub(crate) fn take_for_with_prefix(
                 deref_other_field,
                 deref_self_field: _,
             } = FieldNames::new(field_ident, self_prefix, other_prefix, references);
-            if field.is_structopt_flatten() {
+            if field.is_structopt_flatten() || field.is_section() {
                 if field.no_wrap() {
                     quote_spanned! {span=>
                         #other_field.take(#self_field);
@@ -182,9 +332,36 @@ pub(crate) fn take_for_with_prefix(
                         #self_field.take_for(#other_field);
                     }
                 }
+            } else if field.is_map() {
+                // Instances which do not exist yet are only added if they are convertible. The
+                // others stay in `self` with a warning.
+                let serde_name = field.serde_name();
+                quote_spanned! {span=>
+                    for (key, mut value) in ::std::mem::take(#self_field) {
+                        if let Some(other_value) = (#other_field).get_mut(&key) {
+                            value.take_for(other_value);
+                        } else if !value.is_convertible() {
+                            let path = format!("{}.{}", #serde_name, key);
+                            let error = ::configopt::Error::MissingFields(value.missing_fields());
+                            ::configopt::warn_skipped_instance(&path, &error);
+                            (#self_field).insert(key, value);
+                        } else {
+                            let path = format!("{}.{}", #serde_name, key);
+                            match ::std::convert::TryInto::try_into(value) {
+                                Ok(other_value) => {
+                                    (#other_field).insert(key, other_value);
+                                }
+                                Err(error) => ::configopt::warn_skipped_instance(&path, &error),
+                            }
+                        }
+                    }
+                }
             } else if field.is_subcommand() {
+                // Only the subcommand which was parsed is updated
                 quote_spanned! {span=>
-                    // TODO: handle subcommands
+                    if let Some(self_value) = (#self_field).as_mut() {
+                        self_value.take_for(#other_field);
+                    }
                 }
             } else {
                 match field.structopt_ty() {
@@ -197,7 +374,9 @@ pub(crate) fn take_for_with_prefix(
                     }
                     StructOptTy::Vec if field.is_positional_vec() => {
                         quote_spanned! {span=>
-                            ::std::mem::swap(#self_field, #other_field);
+                            if !(#self_field).is_empty() {
+                                ::std::mem::swap(#self_field, #other_field);
+                            }
                         }
                     }
                     StructOptTy::Bool | StructOptTy::Other | StructOptTy::Vec => {
@@ -220,17 +399,21 @@ pub(crate) fn is_empty_with_prefix(prefix: &str, fields: &[ParsedField]) -> Toke
         let self_field = format!("{}{}", prefix, field_ident)
             .parse::<TokenStream>()
             .unwrap();
-        if field.is_structopt_flatten() {
+        if field.is_structopt_flatten() || field.is_section() {
             quote_spanned! {span=>
                 #self_field.is_empty()
             }
+        } else if field.is_map() {
+            quote_spanned! {span=>
+                #self_field.values().all(|value| value.is_empty())
+            }
         } else if field.is_subcommand() {
             quote_spanned! {span=>
                 #self_field.is_none()
             }
         } else if field.is_positional_vec() {
             quote_spanned! {span=>
-                true
+                #self_field.is_empty()
             }
         } else {
             quote_spanned! {span=>
@@ -250,10 +433,14 @@ pub(crate) fn is_complete_with_prefix(prefix: &str, fields: &[ParsedField]) -> T
         let self_field = format!("{}{}", prefix, field_ident)
             .parse::<TokenStream>()
             .unwrap();
-        if field.is_structopt_flatten() {
+        if field.is_structopt_flatten() || field.is_section() {
             quote_spanned! {span=>
                 #self_field.is_complete()
             }
+        } else if field.is_map() {
+            quote_spanned! {span=>
+                #self_field.values().all(|value| value.is_complete())
+            }
         } else if field.is_subcommand() {
             quote_spanned! {span=>
                 #self_field.as_ref().map_or(false, |val| val.is_complete())
@@ -280,15 +467,19 @@ pub(crate) fn is_convertible_with_prefix(prefix: &str, fields: &[ParsedField]) -
         let self_field = format!("{}{}", prefix, field_ident)
             .parse::<TokenStream>()
             .unwrap();
-        if field.is_structopt_flatten() {
+        if field.is_structopt_flatten() || field.is_section() {
             quote_spanned! {span=>
                 #self_field.is_convertible()
             }
+        } else if field.is_map() {
+            quote_spanned! {span=>
+                #self_field.values().all(|value| value.is_convertible())
+            }
         } else if field.is_subcommand() {
             quote_spanned! {span=>
                 #self_field.as_ref().map_or(false, |val| val.is_convertible())
             }
-        } else if field.is_positional_vec() {
+        } else if field.is_positional_vec() || field.is_file_only() {
             quote_spanned! {span=>
                 true
             }
@@ -313,15 +504,147 @@ pub(crate) fn is_convertible_with_prefix(prefix: &str, fields: &[ParsedField]) -
     }
 }
 
+pub(crate) fn missing_fields_with_prefix(prefix: &str, fields: &[ParsedField]) -> TokenStream {
+    fields
+        .iter()
+        .map(|field| {
+            let field_ident = field.ident();
+            let span = field.span();
+            let serde_name = field.serde_name();
+            let self_field = format!("{}{}", prefix, field_ident)
+                .parse::<TokenStream>()
+                .unwrap();
+            if field.is_structopt_flatten() {
+                quote_spanned! {span=>
+                    missing.extend(#self_field.missing_fields());
+                }
+            } else if field.is_section() {
+                quote_spanned! {span=>
+                    missing.extend(
+                        #self_field
+                            .missing_fields()
+                            .into_iter()
+                            .map(|f| format!("{}.{}", #serde_name, f)),
+                    );
+                }
+            } else if field.is_map() {
+                quote_spanned! {span=>
+                    let mut instances = #self_field.iter().collect::<Vec<_>>();
+                    instances.sort_by(|a, b| a.0.cmp(b.0));
+                    for (key, value) in instances {
+                        missing.extend(
+                            value
+                                .missing_fields()
+                                .into_iter()
+                                .map(|f| format!("{}.{}.{}", #serde_name, key, f)),
+                        );
+                    }
+                }
+            } else if field.is_subcommand() {
+                quote_spanned! {span=>
+                    match &#self_field {
+                        Some(value) => missing.extend(value.missing_fields()),
+                        None => missing.push(String::from(#serde_name)),
+                    }
+                }
+            } else if field.is_positional_vec() || field.is_file_only() {
+                quote_spanned! {span=>}
+            } else {
+                match field.structopt_ty() {
+                    // Only fields which make a type not convertible are missing
+                    StructOptTy::Other => quote_spanned! {span=>
+                        if #self_field.is_none() {
+                            missing.push(String::from(#serde_name));
+                        }
+                    },
+                    _ => quote_spanned! {span=>},
+                }
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn set_fields_with_prefix(prefix: &str, fields: &[ParsedField]) -> TokenStream {
+    fields
+        .iter()
+        .map(|field| {
+            let field_ident = field.ident();
+            let span = field.span();
+            let serde_name = field.serde_name();
+            let self_field = format!("{}{}", prefix, field_ident)
+                .parse::<TokenStream>()
+                .unwrap();
+            if field.is_structopt_flatten() {
+                quote_spanned! {span=>
+                    set.extend(#self_field.set_fields());
+                }
+            } else if field.is_section() {
+                quote_spanned! {span=>
+                    set.extend(
+                        #self_field
+                            .set_fields()
+                            .into_iter()
+                            .map(|f| format!("{}.{}", #serde_name, f)),
+                    );
+                }
+            } else if field.is_map() {
+                quote_spanned! {span=>
+                    let mut instances = #self_field.iter().collect::<Vec<_>>();
+                    instances.sort_by(|a, b| a.0.cmp(b.0));
+                    for (key, value) in instances {
+                        set.extend(
+                            value
+                                .set_fields()
+                                .into_iter()
+                                .map(|f| format!("{}.{}.{}", #serde_name, key, f)),
+                        );
+                    }
+                }
+            } else if field.is_subcommand() {
+                quote_spanned! {span=>
+                    if let Some(value) = &#self_field {
+                        set.extend(value.set_fields());
+                    }
+                }
+            } else if field.is_positional_vec() {
+                quote_spanned! {span=>
+                    if !#self_field.is_empty() {
+                        set.push(String::from(#serde_name));
+                    }
+                }
+            } else {
+                quote_spanned! {span=>
+                    if #self_field.is_some() {
+                        set.push(String::from(#serde_name));
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
 pub(crate) fn from(fields: &[ParsedField], other: &Ident) -> TokenStream {
     let field_tokens = fields.iter().map(|field| {
         let field_ident = field.ident();
         let span = field.span();
         let other_field = quote! {#other.#field_ident};
-        if field.is_structopt_flatten() {
+        if field.is_secret() {
+            let file_ident = field.secret_file_ident();
+            quote_spanned! {span=>
+                #field_ident: Some(#other_field),
+                #file_ident: None,
+            }
+        } else if field.is_structopt_flatten() || field.is_section() {
             quote_spanned! {span=>
                 #field_ident: #other_field.into(),
             }
+        } else if field.is_map() {
+            quote_spanned! {span=>
+                #field_ident: #other_field
+                    .into_iter()
+                    .map(|(key, value)| (key, value.into()))
+                    .collect(),
+            }
         } else if field.is_subcommand() {
             quote_spanned! {span=>
                 #field_ident: Some(#other_field.into()),
@@ -334,6 +657,12 @@ pub(crate) fn from(fields: &[ParsedField], other: &Ident) -> TokenStream {
                 StructOptTy::Bool => quote_spanned! {span=>
                     #field_ident: #other_field.into(),
                 },
+                StructOptTy::Vec if field.empty_is_unset() => quote_spanned! {span=>
+                    #field_ident: Some(#other_field).filter(|values| !values.is_empty()),
+                },
+                StructOptTy::OptionVec if field.empty_is_unset() => quote_spanned! {span=>
+                    #field_ident: #other_field.filter(|values| !values.is_empty()),
+                },
                 StructOptTy::Vec | StructOptTy::Other => quote_spanned! {span=>
                     #field_ident: Some(#other_field),
                 },
@@ -356,15 +685,43 @@ pub(crate) fn try_from(fields: &[ParsedField]) -> TokenStream {
     let field_tokens = fields.iter().map(|field| {
         let field_ident = field.ident();
         let span = field.span();
+        let serde_name = field.serde_name();
         let self_field = quote! {configopt.#field_ident};
-        // We check upfront if the type `is_convertible` so all these `unwrap`'s are ok
-        if field.is_structopt_flatten() {
+        // We check upfront if the type `is_convertible` so these errors are only reached if a
+        // field is missing from `missing_fields`
+        let missing = quote_spanned! {span=>
+            ::configopt::Error::MissingFields(vec![String::from(#serde_name)])
+        };
+        if field.is_structopt_flatten() || field.is_section() {
+            quote_spanned! {span=>
+                #field_ident: #self_field.try_into()?,
+            }
+        } else if field.is_map() {
             quote_spanned! {span=>
-                #field_ident: #self_field.try_into().ok().unwrap(),
+                #field_ident: #self_field
+                    .into_iter()
+                    .map(|(key, value)| value.try_into().map(|value| (key, value)))
+                    .collect::<::configopt::Result<_>>()?,
             }
         } else if field.is_subcommand() {
             quote_spanned! {span=>
-                #field_ident: #self_field.unwrap().try_into().ok().unwrap(),
+                #field_ident: #self_field.ok_or_else(|| #missing)?.try_into()?,
+            }
+        } else if field.is_file_only() {
+            // Fall back to the same default `structopt` uses for skipped fields
+            let default = match field.file_only_default() {
+                Some(default) => quote! {::std::convert::Into::into(#default)},
+                None => quote! {::std::default::Default::default()},
+            };
+            match field.structopt_ty() {
+                StructOptTy::Option | StructOptTy::OptionOption | StructOptTy::OptionVec => {
+                    quote_spanned! {span=>
+                        #field_ident: #self_field.or_else(|| #default),
+                    }
+                }
+                _ => quote_spanned! {span=>
+                    #field_ident: #self_field.unwrap_or_else(|| #default),
+                },
             }
         } else {
             match field.structopt_ty() {
@@ -375,7 +732,7 @@ pub(crate) fn try_from(fields: &[ParsedField]) -> TokenStream {
                     #field_ident: #self_field.unwrap_or_default(),
                 },
                 StructOptTy::Other => quote_spanned! {span=>
-                    #field_ident: #self_field.unwrap(),
+                    #field_ident: #self_field.ok_or_else(|| #missing)?,
                 },
                 _ => {
                     quote_spanned! {span=>
@@ -419,3 +776,11 @@ pub(crate) fn is_complete(fields: &[ParsedField]) -> TokenStream {
 pub(crate) fn is_convertible(fields: &[ParsedField]) -> TokenStream {
     is_convertible_with_prefix("self.", fields)
 }
+
+pub(crate) fn missing_fields(fields: &[ParsedField]) -> TokenStream {
+    missing_fields_with_prefix("self.", fields)
+}
+
+pub(crate) fn set_fields(fields: &[ParsedField]) -> TokenStream {
+    set_fields_with_prefix("self.", fields)
+}
@@ -0,0 +1,66 @@
+use crate::configopt_type::parse::{FieldType, ParsedField, ParsedVariant};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+fn push_args(fields: &[ParsedField]) -> Vec<TokenStream> {
+    fields
+        .iter()
+        .filter_map(|field| {
+            if field.is_structopt_flatten() || field.is_subcommand() {
+                let configopt_ty = field.configopt_inner_ty();
+                Some(quote! {
+                    args.extend(<#configopt_ty as ::configopt::IgnoreHelp>::decoded_env_args());
+                })
+            } else if field.decode_env() {
+                let structopt_name = field.structopt_name();
+                Some(quote! {
+                    args.push(#structopt_name);
+                })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn decoded_env_args(args: Vec<TokenStream>) -> TokenStream {
+    // Without any the default implementation is used
+    if args.is_empty() {
+        return quote! {};
+    }
+    quote! {
+        fn decoded_env_args() -> Vec<&'static str> {
+            let mut args = Vec::new();
+            #(#args)*
+            args
+        }
+    }
+}
+
+/// The names of the `#[configopt(decode_env)]` arguments including the arguments of flattened
+/// types and subcommands
+pub fn for_struct(fields: &[ParsedField]) -> TokenStream {
+    decoded_env_args(push_args(fields))
+}
+
+pub fn for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    let args = variants
+        .iter()
+        .flat_map(|variant| match variant.field_type() {
+            FieldType::Named(fields) => push_args(fields),
+            FieldType::Unnamed => variant
+                .unnamed_configopt_ty()
+                .map(|configopt_ty| {
+                    quote! {
+                        args.extend(
+                            <#configopt_ty as ::configopt::IgnoreHelp>::decoded_env_args()
+                        );
+                    }
+                })
+                .into_iter()
+                .collect(),
+            FieldType::Unit => Vec::new(),
+        })
+        .collect();
+    decoded_env_args(args)
+}
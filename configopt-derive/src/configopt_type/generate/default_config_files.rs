@@ -1,4 +1,5 @@
 use proc_macro2::TokenStream;
+use proc_macro_error::abort;
 use quote::quote;
 use syn::{NestedMeta, Path};
 
@@ -12,7 +13,10 @@ impl From<NestedMeta> for Attribute {
         match m {
             syn::NestedMeta::Lit(syn::Lit::Str(m)) => Self::Lit(m.value()),
             syn::NestedMeta::Meta(syn::Meta::Path(path)) => Self::Path(path),
-            _ => panic!("`configopt(default_config_file)` expected string literal or path"),
+            m => abort!(
+                m,
+                "`configopt(default_config_file)` expected string literal or path"
+            ),
         }
     }
 }
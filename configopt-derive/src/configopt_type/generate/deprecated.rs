@@ -0,0 +1,107 @@
+use crate::configopt_type::parse::{map_value_ty, FieldType, ParsedField, ParsedVariant};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Add the aliases of a nested configopt type whose keys are in the table at `table`
+fn nested_aliases(configopt_ty: TokenStream, table: &[&str]) -> TokenStream {
+    quote! {
+        aliases.extend(
+            <#configopt_ty as ::configopt::ConfigOptType>::deprecated_aliases()
+                .into_iter()
+                .map(|alias| alias.nested(&[#(#table),*])),
+        );
+    }
+}
+
+pub fn aliases_for_fields(fields: &[ParsedField]) -> TokenStream {
+    fields
+        .iter()
+        .map(|field| {
+            let configopt_ty = field.configopt_ty();
+            let serde_name = field.serde_name();
+            // A subcommand's table is at the top level of the file so its variants add the table
+            if field.is_serde_flatten() || field.is_subcommand() {
+                return nested_aliases(quote! {#configopt_ty}, &[]);
+            }
+            if field.is_structopt_flatten() {
+                return nested_aliases(quote! {#configopt_ty}, &[serde_name]);
+            }
+            let nested = if field.is_map() {
+                let mut configopt_ty = configopt_ty.clone();
+                let value_ty = map_value_ty(&mut configopt_ty);
+                nested_aliases(quote! {#value_ty}, &[serde_name, "*"])
+            } else {
+                quote! {}
+            };
+            // A deprecated field without an alias is an alias of itself
+            let (alias, deprecated) = match (field.alias(), field.deprecated()) {
+                (Some(alias), deprecated) => (alias, deprecated),
+                (None, Some(deprecated)) => (field.serde_name(), Some(deprecated)),
+                (None, None) => return nested,
+            };
+            let arg = field.structopt_name();
+            let alias_arg = field.structopt_rename().rename(alias);
+            let note = if let Some(deprecated) = deprecated {
+                quote! {Some(#deprecated)}
+            } else {
+                quote! {None}
+            };
+            quote! {
+                #nested
+                aliases.push(::configopt::Alias {
+                    table: Vec::new(),
+                    key: #serde_name,
+                    alias_key: #alias,
+                    arg: #arg,
+                    alias_arg: #alias_arg,
+                    deprecated: #note,
+                });
+            }
+        })
+        .collect()
+}
+
+pub fn for_struct(fields: &[ParsedField]) -> TokenStream {
+    let aliases = aliases_for_fields(fields);
+    quote! {
+        let mut aliases = Vec::new();
+        #aliases
+        aliases
+    }
+}
+
+pub fn for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    // The arguments of a subcommand are in a config file table named after the subcommand
+    let aliases = variants
+        .iter()
+        .map(|variant| {
+            let name = variant.structopt_name();
+            match variant.field_type() {
+                FieldType::Unnamed => {
+                    let configopt_ty = variant
+                        .unnamed_configopt_ty()
+                        .expect("unnamed variant to have a type");
+                    nested_aliases(quote! {#configopt_ty}, &[name])
+                }
+                FieldType::Named(fields) => {
+                    let aliases = aliases_for_fields(fields);
+                    quote! {
+                        aliases.extend({
+                            let mut aliases = Vec::new();
+                            #aliases
+                            aliases
+                                .into_iter()
+                                .map(|alias: ::configopt::Alias| alias.nested(&[#name]))
+                        });
+                    }
+                }
+                FieldType::Unit => quote! {},
+            }
+        })
+        .collect::<TokenStream>();
+    quote! {
+        let mut aliases = Vec::new();
+        #aliases
+        aliases
+    }
+}
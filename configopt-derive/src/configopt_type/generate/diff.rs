@@ -0,0 +1,121 @@
+use crate::configopt_type::parse::ParsedField;
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{spanned::Spanned, Ident};
+
+fn diff_fields(fields: &[ParsedField]) -> TokenStream {
+    fields
+        .iter()
+        // The `configopt_fields` only control loading so they are not part of the config
+        .filter(|f| {
+            !["generate_config", "explain_config", "config_files"]
+                .iter()
+                .any(|name| f.ident() == name)
+        })
+        .map(|field| {
+            let field_ident = field.ident();
+            let span = field.span();
+            let serde_name = field.serde_name();
+            let diff = if field.is_structopt_flatten() && field.is_serde_flatten() {
+                quote_spanned! {span=>
+                    self.#field_ident.diff_with_prefix(&other.#field_ident, prefix, changed, restart);
+                }
+            } else if field.is_structopt_flatten() {
+                quote_spanned! {span=>
+                    self.#field_ident.diff_with_prefix(
+                        &other.#field_ident,
+                        &format!("{}{}.", prefix, #serde_name),
+                        changed,
+                        restart,
+                    );
+                }
+            } else if field.is_map() {
+                quote_spanned! {span=>
+                    let mut keys = self
+                        .#field_ident
+                        .keys()
+                        .chain(other.#field_ident.keys())
+                        .collect::<Vec<_>>();
+                    keys.sort();
+                    keys.dedup();
+                    for key in keys {
+                        match (self.#field_ident.get(key), other.#field_ident.get(key)) {
+                            (Some(self_value), Some(other_value)) => {
+                                self_value.diff_with_prefix(
+                                    other_value,
+                                    &format!("{}{}.{}.", prefix, #serde_name, key),
+                                    changed,
+                                    restart,
+                                );
+                            }
+                            _ => changed.push(format!("{}{}.{}", prefix, #serde_name, key)),
+                        }
+                    }
+                }
+            } else if field.is_feature_flags() {
+                quote_spanned! {span=>
+                    let empty = ::configopt::flags::FeatureFlags::default();
+                    let self_flags = self.#field_ident.as_ref().unwrap_or(&empty);
+                    let other_flags = other.#field_ident.as_ref().unwrap_or(&empty);
+                    for name in self_flags.changed(other_flags) {
+                        changed.push(format!("{}{}.{}", prefix, #serde_name, name));
+                    }
+                }
+            } else {
+                // Subcommands are compared as a whole
+                quote_spanned! {span=>
+                    if self.#field_ident != other.#field_ident {
+                        changed.push(format!("{}{}", prefix, #serde_name));
+                    }
+                }
+            };
+            if field.requires_restart() {
+                // Every change under the field needs a restart
+                quote_spanned! {span=>
+                    {
+                        let before = changed.len();
+                        #diff
+                        for path in &changed[before..] {
+                            if !restart.contains(path) {
+                                restart.push(path.clone());
+                            }
+                        }
+                    }
+                }
+            } else {
+                diff
+            }
+        })
+        .collect()
+}
+
+pub fn for_struct(configopt_ident: &Ident, fields: &[ParsedField]) -> TokenStream {
+    let diff_fields = diff_fields(fields);
+    let lints = super::lints();
+    quote! {
+        #lints
+        impl #configopt_ident {
+            /// Get the fields which differ between `self` and `other` including nested fields
+            ///
+            /// The changed fields marked `#[configopt(reload = "restart")]` are also in
+            /// `restart_required`.
+            pub fn diff(&self, other: &#configopt_ident) -> ::configopt::ConfigDiff {
+                let mut changed = Vec::new();
+                let mut restart = Vec::new();
+                self.diff_with_prefix(other, "", &mut changed, &mut restart);
+                ::configopt::ConfigDiff::new(changed).with_restart_required(restart)
+            }
+
+            #[doc(hidden)]
+            pub fn diff_with_prefix(
+                &self,
+                other: &#configopt_ident,
+                prefix: &str,
+                changed: &mut Vec<String>,
+                restart: &mut Vec<String>,
+            ) {
+                #diff_fields
+            }
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+pub fn generate(env_json: Option<&str>) -> TokenStream {
+    let from_env_json = if let Some(name) = env_json {
+        quote! {
+            let started = ::std::time::Instant::now();
+            let configopt: Option<Self> = ::configopt::from_json_env(#name)?;
+            configopt
+                .map(|configopt| {
+                    configopt.finish_load(
+                        ::configopt::TraceSource::EnvJson { name: String::from(#name) },
+                        None,
+                        started,
+                    )
+                })
+                .transpose()
+        }
+    } else {
+        quote! {
+            Ok(None)
+        }
+    };
+    quote! {
+        /// Parse the JSON document in the `configopt(env_json)` environment variable if it is set
+        pub fn from_env_json() -> ::configopt::Result<Option<Self>> {
+            #from_env_json
+        }
+    }
+}
+
+pub fn generate_prefix(env_prefix: Option<&str>, env_separator: &str) -> TokenStream {
+    let from_env_prefix = if let Some(prefix) = env_prefix {
+        quote! {
+            let started = ::std::time::Instant::now();
+            let table = ::configopt::prefixed_env_table(
+                #prefix,
+                #env_separator,
+                &<Self as ::configopt::ConfigOptType>::runtime_schema(),
+            )?;
+            if table.is_empty() {
+                return Ok(None);
+            }
+            let configopt: Self =
+                ::configopt::from_toml_table(table, ::std::path::Path::new(#prefix), &mut Vec::new())?;
+            configopt
+                .finish_load(
+                    ::configopt::TraceSource::EnvPrefix { prefix: String::from(#prefix) },
+                    None,
+                    started,
+                )
+                .map(Some)
+        }
+    } else {
+        quote! {
+            Ok(None)
+        }
+    };
+    quote! {
+        /// Parse the environment variables starting with the `configopt(env_prefix)` if any is set
+        pub fn from_env_prefix() -> ::configopt::Result<Option<Self>> {
+            #from_env_prefix
+        }
+    }
+}
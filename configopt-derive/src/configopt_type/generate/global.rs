@@ -0,0 +1,69 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Ident;
+
+pub fn generate(ident: &Ident) -> TokenStream {
+    let lints = super::lints();
+    quote! {
+        #lints
+        impl #ident {
+            fn configopt_global() -> &'static ::configopt::Global<#ident> {
+                static GLOBAL: ::configopt::Global<#ident> = ::configopt::Global::new();
+                &GLOBAL
+            }
+
+            /// Initialize the global config with `loader` if it is not already initialized
+            ///
+            /// Holding on to the returned reference proves the global config was initialized.
+            pub fn init_global<F>(loader: F) -> ::configopt::Result<&'static #ident>
+            where
+                F: FnOnce() -> ::configopt::Result<#ident>,
+            {
+                Self::configopt_global().get_or_try_init(loader)
+            }
+
+            /// Get the global config if it was initialized
+            pub fn try_global() -> Option<&'static #ident> {
+                Self::configopt_global().get()
+            }
+
+            /// Get the global config as seen through any `configopt::with_override` on the current
+            /// thread if it was initialized or overridden
+            pub fn try_current() -> Option<::std::sync::Arc<#ident>> {
+                Self::configopt_global().current()
+            }
+
+            /// Get the global config as seen through any `configopt::with_override` on the current
+            /// thread
+            ///
+            /// # Panics
+            ///
+            /// Panics if `init_global` was not called first and there is no override. Use
+            /// `try_current` to handle that case.
+            pub fn current() -> ::std::sync::Arc<#ident> {
+                Self::try_current().expect(concat!(
+                    "`",
+                    stringify!(#ident),
+                    "::init_global` must be called before `",
+                    stringify!(#ident),
+                    "::current`"
+                ))
+            }
+
+            /// Get the global config
+            ///
+            /// # Panics
+            ///
+            /// Panics if `init_global` was not called first. Use `try_global` to handle that case.
+            pub fn global() -> &'static #ident {
+                Self::try_global().expect(concat!(
+                    "`",
+                    stringify!(#ident),
+                    "::init_global` must be called before `",
+                    stringify!(#ident),
+                    "::global`"
+                ))
+            }
+        }
+    }
+}
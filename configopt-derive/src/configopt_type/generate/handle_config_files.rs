@@ -19,6 +19,18 @@ pub fn generate_for_struct(parsed: &[ParsedField]) -> TokenStream {
     }
 }
 
+pub fn explain_for_struct(parsed: &[ParsedField]) -> TokenStream {
+    if parse::has_configopt_fields(parsed) {
+        quote! {
+            self.explain_config.unwrap_or_default()
+        }
+    } else {
+        quote! {
+            false
+        }
+    }
+}
+
 pub fn patch_for_struct(parsed: &[ParsedField], configopt_ident: &Ident) -> TokenStream {
     let has_config_fields = parse::has_configopt_fields(parsed);
     let patch_subcommands = parsed
@@ -45,12 +57,31 @@ pub fn patch_for_struct(parsed: &[ParsedField], configopt_ident: &Ident) -> Toke
             } else {
                 from_default_config_files
             };
+            // The environment takes precedence over config files
+            if let Some(mut from_env_json) = #configopt_ident::from_env_json()? {
+                from_env_json.patch(&mut from_config_files);
+                from_config_files = from_env_json;
+            }
+            // Prefixed variables are more specific than the JSON document
+            if let Some(mut from_env_prefix) = #configopt_ident::from_env_prefix()? {
+                from_env_prefix.patch(&mut from_config_files);
+                from_config_files = from_env_prefix;
+            }
             self.patch(&mut from_config_files);
+            // The config embedded in the binary has the lowest priority
+            self.patch(&mut #configopt_ident::from_default_config()?);
             #patch_subcommands
             Ok(self)
         }
     } else {
         quote! {
+            if let Some(mut from_env_prefix) = #configopt_ident::from_env_prefix()? {
+                self.patch(&mut from_env_prefix);
+            }
+            if let Some(mut from_env_json) = #configopt_ident::from_env_json()? {
+                self.patch(&mut from_env_json);
+            }
+            self.patch(&mut #configopt_ident::from_default_config()?);
             #patch_subcommands
             Ok(self)
         }
@@ -78,6 +109,23 @@ pub fn generate_for_enum(variants: &[ParsedVariant]) -> TokenStream {
         .collect()
 }
 
+pub fn explain_for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    variants
+        .iter()
+        .map(|variant| match variant.field_type() {
+            FieldType::Unnamed => {
+                let full_configopt_ident = variant.full_configopt_ident();
+                quote! {
+                    #full_configopt_ident(variant) => variant.maybe_explain_config(),
+                }
+            }
+            FieldType::Named(_) | FieldType::Unit => {
+                quote! {}
+            }
+        })
+        .collect()
+}
+
 pub fn patch_for_enum(variants: &[ParsedVariant]) -> TokenStream {
     variants
         .iter()
@@ -97,3 +145,20 @@ pub fn patch_for_enum(variants: &[ParsedVariant]) -> TokenStream {
         })
         .collect()
 }
+
+pub fn patch_str_for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    variants
+        .iter()
+        .map(|variant| match variant.field_type() {
+            FieldType::Unnamed => {
+                let full_configopt_ident = variant.full_configopt_ident();
+                quote! {
+                    #full_configopt_ident(variant) => {
+                        variant.patch_with_config_str(contents, format)?;
+                    }
+                }
+            }
+            FieldType::Named(_) | FieldType::Unit => quote! {},
+        })
+        .collect()
+}
@@ -0,0 +1,46 @@
+use crate::configopt_type::parse::{ParsedField, StructOptTy};
+use proc_macro2::TokenStream;
+use proc_macro_error::abort;
+use quote::quote;
+use syn::{spanned::Spanned, Ident};
+
+/// Implement `configopt::Identity` for the full and `configopt` type using the `#[configopt(id)]`
+/// field
+pub fn for_struct(ident: &Ident, configopt_ident: &Ident, fields: &[ParsedField]) -> TokenStream {
+    let mut id_fields = fields.iter().filter(|f| f.is_id());
+    let field = match id_fields.next() {
+        Some(field) => field,
+        None => return quote! {},
+    };
+    if let Some(duplicate) = id_fields.next() {
+        abort!(
+            duplicate.span(),
+            "`configopt(id)` can only be set on one field"
+        );
+    }
+    let field_ident = field.ident();
+    let full_id = match field.structopt_ty() {
+        StructOptTy::Option => quote! {
+            self.#field_ident.as_ref().map(|id| ::std::convert::AsRef::<str>::as_ref(id))
+        },
+        _ => quote! {
+            Some(::std::convert::AsRef::<str>::as_ref(&self.#field_ident))
+        },
+    };
+    let lints = super::lints();
+    quote! {
+        #lints
+        impl ::configopt::Identity for #ident {
+            fn id(&self) -> Option<&str> {
+                #full_id
+            }
+        }
+
+        #lints
+        impl ::configopt::Identity for #configopt_ident {
+            fn id(&self) -> Option<&str> {
+                self.#field_ident.as_ref().map(|id| ::std::convert::AsRef::<str>::as_ref(id))
+            }
+        }
+    }
+}
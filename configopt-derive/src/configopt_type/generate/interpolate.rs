@@ -0,0 +1,164 @@
+use crate::configopt_type::parse::{FieldType, ParsedField, ParsedVariant};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// A pass over the string values of a config
+#[derive(Clone, Copy)]
+pub enum Pass {
+    /// Replace each `${name}` using `lookup`
+    Interpolate,
+    /// Replace each reference to a value stored elsewhere using `resolve`
+    Resolve,
+}
+
+impl Pass {
+    fn method(self) -> TokenStream {
+        match self {
+            Self::Interpolate => quote! {interpolate_with},
+            Self::Resolve => quote! {resolve_references_with},
+        }
+    }
+
+    fn value_method(self) -> TokenStream {
+        match self {
+            Self::Interpolate => quote! {interpolate},
+            Self::Resolve => quote! {resolve_reference},
+        }
+    }
+
+    fn arg(self) -> TokenStream {
+        match self {
+            Self::Interpolate => quote! {lookup},
+            Self::Resolve => quote! {resolve},
+        }
+    }
+
+    fn imports(self) -> TokenStream {
+        match self {
+            Self::Interpolate => quote! {
+                #[allow(unused_imports)]
+                use ::configopt::{Interpolate as _, InterpolateOther as _};
+            },
+            Self::Resolve => quote! {
+                #[allow(unused_imports)]
+                use ::configopt::{ResolveReference as _, ResolveReferenceOther as _};
+            },
+        }
+    }
+
+    /// Is `field` left as it is
+    ///
+    /// Secrets are never interpolated so a template cannot leak them but they are the values most
+    /// likely to be references.
+    fn skips(self, field: &ParsedField) -> bool {
+        match self {
+            Self::Interpolate => field.no_interpolate() || field.is_secret(),
+            Self::Resolve => false,
+        }
+    }
+}
+
+fn interpolate_fields(prefix: &str, fields: &[ParsedField], pass: Pass) -> TokenStream {
+    let method = pass.method();
+    let value_method = pass.value_method();
+    let lookup = pass.arg();
+    fields
+        .iter()
+        .map(|field| {
+            let self_field = format!("{}{}", prefix, field.ident())
+                .parse::<TokenStream>()
+                .unwrap();
+            if field.is_structopt_flatten() {
+                quote! {
+                    ::configopt::ConfigOptType::#method(&mut #self_field, #lookup)?;
+                }
+            } else if field.is_section() {
+                quote! {
+                    #self_field.#method(#lookup)?;
+                }
+            } else if field.is_subcommand() {
+                quote! {
+                    if let Some(subcommand) = #self_field.as_mut() {
+                        ::configopt::ConfigOptType::#method(subcommand, #lookup)?;
+                    }
+                }
+            } else if field.is_map() {
+                quote! {
+                    for value in #self_field.values_mut() {
+                        ::configopt::ConfigOptType::#method(value, #lookup)?;
+                    }
+                }
+            } else if pass.skips(field) {
+                quote! {}
+            } else {
+                let name = field.serde_name();
+                // Enum variant fields are bound by reference
+                let field_ref = if prefix.is_empty() {
+                    quote! {&mut *#self_field}
+                } else {
+                    quote! {&mut #self_field}
+                };
+                quote! {
+                    (#field_ref)
+                        .#value_method(#lookup)
+                        .map_err(|e| format!("`{}` {}", #name, e))?;
+                }
+            }
+        })
+        .collect()
+}
+
+pub fn for_struct(fields: &[ParsedField], pass: Pass) -> TokenStream {
+    let imports = pass.imports();
+    let interpolate = interpolate_fields("self.", fields, pass);
+    quote! {
+        #imports
+        #interpolate
+        Ok(())
+    }
+}
+
+pub fn for_enum(variants: &[ParsedVariant], pass: Pass) -> TokenStream {
+    let method = pass.method();
+    let lookup = pass.arg();
+    let arms = variants
+        .iter()
+        .map(|variant| {
+            let full_configopt_ident = variant.full_configopt_ident();
+            match variant.field_type() {
+                FieldType::Unnamed => quote! {
+                    #full_configopt_ident(variant) => {
+                        ::configopt::ConfigOptType::#method(variant, #lookup)?;
+                    }
+                },
+                FieldType::Named(fields) => {
+                    let idents = fields.iter().map(ParsedField::ident);
+                    let interpolate = interpolate_fields("", fields, pass);
+                    quote! {
+                        #full_configopt_ident { #(#idents,)* } => {
+                            #interpolate
+                        }
+                    }
+                }
+                FieldType::Unit => quote! {
+                    #full_configopt_ident => {}
+                },
+            }
+        })
+        .collect::<TokenStream>();
+    // Handle the case of an empty enum
+    let empty = if variants.is_empty() {
+        quote! {_ => {}}
+    } else {
+        quote! {}
+    };
+    let imports = pass.imports();
+    quote! {
+        #imports
+        match self {
+            #arms
+            #empty
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use crate::configopt_type::parse::{FieldType, ParsedField, ParsedVariant};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+fn args_for_fields(fields: &[ParsedField]) -> TokenStream {
+    fields
+        .iter()
+        .map(|field| {
+            if field.is_structopt_flatten() || field.is_subcommand() {
+                let configopt_ty = field.configopt_inner_ty();
+                quote! {
+                    args.extend(<#configopt_ty as ::configopt::ConfigOptType>::negatable_args());
+                }
+            } else if field.is_negatable() {
+                let arg = field.structopt_name();
+                quote! {
+                    args.push(#arg);
+                }
+            } else {
+                quote! {}
+            }
+        })
+        .collect()
+}
+
+fn negate_for_fields(prefix: &str, fields: &[ParsedField]) -> TokenStream {
+    fields
+        .iter()
+        .map(|field| {
+            let self_field = format!("{}{}", prefix, field.ident())
+                .parse::<TokenStream>()
+                .unwrap();
+            if field.is_structopt_flatten() {
+                quote! {
+                    #self_field.negate_arg(arg);
+                }
+            } else if field.is_subcommand() {
+                quote! {
+                    if let Some(subcommand) = #self_field.as_mut() {
+                        subcommand.negate_arg(arg);
+                    }
+                }
+            } else if field.is_negatable() {
+                let name = field.structopt_name();
+                // Enum variant fields are bound by reference
+                let deref_self_field = if prefix.is_empty() {
+                    quote! {*#self_field}
+                } else {
+                    self_field
+                };
+                quote! {
+                    if arg == #name {
+                        #deref_self_field = false.into();
+                    }
+                }
+            } else {
+                quote! {}
+            }
+        })
+        .collect()
+}
+
+pub fn args_for_struct(fields: &[ParsedField]) -> TokenStream {
+    let args = args_for_fields(fields);
+    quote! {
+        let mut args = Vec::new();
+        #args
+        args
+    }
+}
+
+pub fn negate_for_struct(fields: &[ParsedField]) -> TokenStream {
+    let negate = negate_for_fields("self.", fields);
+    quote! {
+        #negate
+    }
+}
+
+pub fn args_for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    let args = variants
+        .iter()
+        .map(|variant| match variant.field_type() {
+            FieldType::Unnamed => {
+                let configopt_ty = variant
+                    .unnamed_configopt_ty()
+                    .expect("unnamed variant to have a type");
+                quote! {
+                    args.extend(<#configopt_ty as ::configopt::ConfigOptType>::negatable_args());
+                }
+            }
+            FieldType::Named(fields) => args_for_fields(fields),
+            FieldType::Unit => quote! {},
+        })
+        .collect::<TokenStream>();
+    quote! {
+        let mut args = Vec::new();
+        #args
+        args
+    }
+}
+
+pub fn negate_for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    let arms = variants
+        .iter()
+        .map(|variant| {
+            let full_configopt_ident = variant.full_configopt_ident();
+            match variant.field_type() {
+                FieldType::Unnamed => quote! {
+                    #full_configopt_ident(variant) => variant.negate_arg(arg),
+                },
+                FieldType::Named(fields) => {
+                    let idents = fields.iter().map(ParsedField::ident);
+                    let negate = negate_for_fields("", fields);
+                    quote! {
+                        #full_configopt_ident { #(#idents,)* } => {
+                            #negate
+                        }
+                    }
+                }
+                FieldType::Unit => quote! {
+                    #full_configopt_ident => {}
+                },
+            }
+        })
+        .collect::<TokenStream>();
+    // Handle the case of an empty enum
+    let empty = if variants.is_empty() {
+        quote! {_ => {}}
+    } else {
+        quote! {}
+    };
+    quote! {
+        match self {
+            #arms
+            #empty
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Ident;
+
+/// Implement `configopt::Merge` and `configopt::Partial` by delegating to the inherent methods of
+/// the `configopt` type
+pub fn generate(ident: &Ident, configopt_ident: &Ident) -> TokenStream {
+    let lints = super::lints();
+    quote! {
+        #lints
+        impl ::configopt::Merge for #configopt_ident {
+            fn take(&mut self, other: &mut Self) {
+                #configopt_ident::take(self, other)
+            }
+
+            fn patch(&mut self, other: &mut Self) {
+                #configopt_ident::patch(self, other)
+            }
+        }
+
+        #lints
+        impl ::configopt::Partial for #configopt_ident {
+            type Full = #ident;
+
+            fn take_for(&mut self, full: &mut #ident) {
+                #configopt_ident::take_for(self, full)
+            }
+
+            fn patch_for(&mut self, full: &mut #ident) {
+                #configopt_ident::patch_for(self, full)
+            }
+
+            fn is_empty(&self) -> bool {
+                #configopt_ident::is_empty(self)
+            }
+
+            fn is_complete(&self) -> bool {
+                #configopt_ident::is_complete(self)
+            }
+
+            fn is_convertible(&self) -> bool {
+                #configopt_ident::is_convertible(self)
+            }
+
+            fn missing_fields(&self) -> Vec<String> {
+                #configopt_ident::missing_fields(self)
+            }
+
+            fn set_fields(&self) -> Vec<String> {
+                #configopt_ident::set_fields(self)
+            }
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use crate::configopt_type::parse::ParsedField;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+pub fn for_struct(fields: &[ParsedField]) -> TokenStream {
+    // A flattened type may have prefixed fields of its own so the prefixes of every flattened
+    // type are collected, without one the default implementation is used
+    if !fields.iter().any(ParsedField::is_structopt_flatten) {
+        return quote! {};
+    }
+    let prefix_args = fields
+        .iter()
+        .filter(|field| field.is_structopt_flatten())
+        .map(|field| {
+            let configopt_ty = field.configopt_inner_ty();
+            let prefix = match field.prefix() {
+                Some(prefix) => quote! {Some(#prefix)},
+                None => quote! {None},
+            };
+            quote! {
+                ::configopt::prefix_args(
+                    &mut prefixes,
+                    &<#configopt_ty as ::structopt::StructOpt>::clap(),
+                    <#configopt_ty as ::configopt::IgnoreHelp>::arg_prefixes(),
+                    #prefix,
+                );
+            }
+        });
+    quote! {
+        fn arg_prefixes() -> Vec<(String, String)> {
+            let mut prefixes = Vec::new();
+            #(#prefix_args)*
+            prefixes
+        }
+    }
+}
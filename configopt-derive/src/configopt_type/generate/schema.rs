@@ -0,0 +1,172 @@
+use crate::configopt_type::parse::{
+    map_value_ty, possible_values, Constraints, FieldType, ParsedField, ParsedVariant, StructOptTy,
+};
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+
+pub fn for_struct(fields: &[ParsedField]) -> TokenStream {
+    let fields = fields
+        .iter()
+        .map(|field| {
+            let span = field.span();
+            let serde_name = field.serde_name();
+            let configopt_ty = field.configopt_ty();
+            if field.is_cli_only() {
+                quote! {}
+            } else if field.is_subcommand() {
+                quote_spanned! {span=>
+                    fields.extend(<#configopt_ty as ::configopt::ConfigOptType>::schema_fields(
+                        &format!("{}{}.", prefix, #serde_name),
+                    ));
+                }
+            } else if field.is_serde_flatten() {
+                quote_spanned! {span=>
+                    fields.extend(<#configopt_ty as ::configopt::ConfigOptType>::schema_fields(prefix));
+                }
+            } else if field.is_structopt_flatten() {
+                quote_spanned! {span=>
+                    fields.extend(<#configopt_ty as ::configopt::ConfigOptType>::schema_fields(
+                        &format!("{}{}.", prefix, #serde_name),
+                    ));
+                }
+            } else if field.is_section() {
+                quote_spanned! {span=>
+                    fields.extend(<#configopt_ty>::schema_fields(
+                        &format!("{}{}.", prefix, #serde_name),
+                    ));
+                }
+            } else if field.is_map() {
+                let mut configopt_ty = configopt_ty.clone();
+                let value_ty = map_value_ty(&mut configopt_ty);
+                quote_spanned! {span=>
+                    fields.extend(<#value_ty as ::configopt::ConfigOptType>::schema_fields(
+                        &format!("{}{}.*.", prefix, #serde_name),
+                    ));
+                }
+            } else {
+                let ty = quote! {#configopt_ty}.to_string().replace(' ', "");
+                // Only a field which would be missing without a config file is required
+                let required = *field.structopt_ty() == StructOptTy::Other
+                    && !field.has_default_value()
+                    && !field.is_file_only();
+                let aliases = field.alias().into_iter();
+                let format = match field.human_parser() {
+                    Some(parser) => quote! {Some(String::from(#parser))},
+                    None => quote! {None},
+                };
+                let env = match field.env() {
+                    Some(env) => quote! {Some(String::from(#env))},
+                    None => quote! {None},
+                };
+                let arg = if field.is_file_only() {
+                    quote! {None}
+                } else {
+                    let structopt_name = field.structopt_name();
+                    quote! {Some(String::from(#structopt_name))}
+                };
+                let values = possible_values(configopt_ty);
+                let decode_env = field.decode_env();
+                let constraints = constraints(field.constraints());
+                // Each flag is a key so a single flag can be set by its path
+                let flags = if field.is_feature_flags() {
+                    quote_spanned! {span=>
+                        fields.push(::configopt::SchemaField {
+                            path: format!("{}{}.*", prefix, #serde_name),
+                            ty: String::from("Flag"),
+                            required: false,
+                            aliases: Vec::new(),
+                            format: None,
+                            env: None,
+                            decode_env: false,
+                            arg: None,
+                            values: Vec::new(),
+                            constraints: ::configopt::Constraints::default(),
+                        });
+                    }
+                } else {
+                    quote! {}
+                };
+                quote_spanned! {span=>
+                    fields.push(::configopt::SchemaField {
+                        path: format!("{}{}", prefix, #serde_name),
+                        ty: String::from(#ty),
+                        required: #required,
+                        aliases: vec![#(format!("{}{}", prefix, #aliases)),*],
+                        format: #format,
+                        env: #env,
+                        decode_env: #decode_env,
+                        arg: #arg,
+                        values: #values,
+                        constraints: #constraints,
+                    });
+                    #flags
+                }
+            }
+        })
+        .collect::<TokenStream>();
+    quote! {
+        let mut fields = Vec::new();
+        #fields
+        fields
+    }
+}
+
+fn constraints(constraints: &Constraints) -> TokenStream {
+    let number = |number: &Option<String>| match number {
+        Some(number) => {
+            let number = format!("{}f64", number).parse::<TokenStream>().unwrap();
+            quote! {Some(#number)}
+        }
+        None => quote! {None},
+    };
+    let string = |string: &Option<String>| match string {
+        Some(string) => quote! {Some(String::from(#string))},
+        None => quote! {None},
+    };
+    let min = number(&constraints.min);
+    let max = number(&constraints.max);
+    let pattern = string(&constraints.pattern);
+    let required_if = string(&constraints.required_if);
+    quote! {
+        ::configopt::Constraints {
+            min: #min,
+            max: #max,
+            pattern: #pattern,
+            required_if: #required_if,
+        }
+    }
+}
+
+pub fn for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    // The keys of a variant are under its name which is the form a config file selects it with
+    // (eg `[command.Serve]`)
+    let variants = variants
+        .iter()
+        .map(|variant| {
+            let variant_name = variant.variant_name();
+            match (variant.field_type(), variant.unnamed_configopt_ty()) {
+                (FieldType::Unnamed, Some(configopt_ty)) => quote! {
+                    fields.extend(<#configopt_ty as ::configopt::ConfigOptType>::schema_fields(
+                        &format!("{}{}.", prefix, #variant_name),
+                    ));
+                },
+                (FieldType::Named(named), _) => {
+                    let named = for_struct(named);
+                    quote! {
+                        fields.extend({
+                            let prefix = format!("{}{}.", prefix, #variant_name);
+                            let prefix = prefix.as_str();
+                            #named
+                        });
+                    }
+                }
+                _ => quote! {},
+            }
+        })
+        .collect::<TokenStream>();
+    quote! {
+        let mut fields = Vec::new();
+        #variants
+        fields
+    }
+}
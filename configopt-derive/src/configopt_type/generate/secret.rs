@@ -0,0 +1,175 @@
+use crate::configopt_type::parse::{FieldType, ParsedField, ParsedVariant};
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{spanned::Spanned, FieldsNamed, Ident};
+
+pub fn has_secrets(fields: &[ParsedField]) -> bool {
+    fields.iter().any(|f| f.is_secret())
+}
+
+/// The additional `<field>_file` fields added to the `configopt` type for each secret field
+pub fn file_fields(fields: &[ParsedField]) -> FieldsNamed {
+    let file_fields = fields.iter().filter(|f| f.is_secret()).map(|field| {
+        let file_ident = field.secret_file_ident();
+        quote! {
+            #[structopt(skip)]
+            #file_ident: Option<::std::path::PathBuf>
+        }
+    });
+    syn::parse_quote!({
+        #(#file_fields),*
+    })
+}
+
+pub fn debug_for_struct(configopt_ident: &Ident, fields: &[ParsedField]) -> TokenStream {
+    let configopt_ident_str = configopt_ident.to_string();
+    let field_tokens = fields.iter().map(|field| {
+        let field_ident = field.ident();
+        let field_ident_str = field_ident.to_string();
+        let span = field.span();
+        if field.is_secret() {
+            let file_ident = field.secret_file_ident();
+            let file_ident_str = file_ident.to_string();
+            quote_spanned! {span=>
+                .field(#field_ident_str, &::configopt::Redacted::new(self.#field_ident.is_some()))
+                .field(#file_ident_str, &self.#file_ident)
+            }
+        } else {
+            quote_spanned! {span=>
+                .field(#field_ident_str, &self.#field_ident)
+            }
+        }
+    });
+    quote! {
+        impl ::std::fmt::Debug for #configopt_ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.debug_struct(#configopt_ident_str)
+                    #(#field_tokens)*
+                    .finish()
+            }
+        }
+    }
+}
+
+pub fn resolve_files_for_struct(fields: &[ParsedField]) -> TokenStream {
+    fields
+        .iter()
+        .map(|field| {
+            let field_ident = field.ident();
+            let self_field = quote! {self.#field_ident};
+            let span = field.span();
+            if field.is_structopt_flatten() {
+                quote_spanned! {span=>
+                    #self_field.resolve_secret_files_in(dir)?;
+                }
+            } else if field.is_secret() {
+                let file_ident = field.secret_file_ident();
+                quote_spanned! {span=>
+                    if let Some(path) = self.#file_ident.take() {
+                        if #self_field.is_none() {
+                            let path = match dir {
+                                Some(dir) => dir.join(path),
+                                None => path,
+                            };
+                            #self_field = Some(::configopt::read_secret_file(&path)?);
+                        }
+                    }
+                }
+            } else {
+                quote! {}
+            }
+        })
+        .collect()
+}
+
+pub fn decrypt_for_struct(fields: &[ParsedField]) -> TokenStream {
+    fields
+        .iter()
+        .map(|field| {
+            let field_ident = field.ident();
+            let self_field = quote! {self.#field_ident};
+            let span = field.span();
+            if field.is_structopt_flatten() {
+                quote_spanned! {span=>
+                    #self_field.decrypt_fields()?;
+                }
+            } else if let Some(with) = field.decrypt() {
+                let serde_name = field.serde_name();
+                let with = match with {
+                    Some(path) => quote! {Some(#path)},
+                    None => quote! {None},
+                };
+                quote_spanned! {span=>
+                    if let Some(value) = #self_field.take() {
+                        #self_field = Some(::configopt::decrypt_value(#serde_name, &value, #with)?);
+                    }
+                }
+            } else {
+                quote! {}
+            }
+        })
+        .collect()
+}
+
+pub fn arg_is_secret_for_struct(fields: &[ParsedField]) -> TokenStream {
+    let secret_fields = fields
+        .iter()
+        .filter(|f| f.is_secret())
+        .map(|field| {
+            let arg_name = field.structopt_name();
+            quote! {
+                if arg_name == #arg_name {
+                    return true;
+                }
+            }
+        })
+        .collect::<TokenStream>();
+    let nested_fields = fields
+        .iter()
+        .filter(|f| f.is_structopt_flatten() || f.is_subcommand())
+        .map(|field| {
+            let field_ident = field.ident();
+            let self_field = quote! {self.#field_ident};
+            if field.is_structopt_flatten() {
+                quote! {
+                    if #self_field.arg_is_secret(full_arg_path) {
+                        return true;
+                    }
+                }
+            } else {
+                quote! {
+                    if let Some(value) = #self_field.as_ref() {
+                        if value.arg_is_secret(full_arg_path) {
+                            return true;
+                        }
+                    }
+                }
+            }
+        })
+        .collect::<TokenStream>();
+    quote! {
+        #secret_fields
+        #nested_fields
+        false
+    }
+}
+
+pub fn arg_is_secret_for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    variants
+        .iter()
+        .map(|variant| match variant.field_type() {
+            FieldType::Unnamed => {
+                let full_configopt_ident = variant.full_configopt_ident();
+                let structopt_name = variant.structopt_name();
+                quote! {
+                    #full_configopt_ident(value) if #structopt_name == arg_path[0] => {
+                        value.arg_is_secret(&arg_path[1..])
+                    }
+                }
+            }
+            FieldType::Named(_) | FieldType::Unit => {
+                quote! {}
+            }
+        })
+        .collect()
+}
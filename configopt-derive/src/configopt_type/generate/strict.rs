@@ -0,0 +1,28 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Deserialize with `load` rejecting unknown keys if the type is `#[configopt(strict)]`
+///
+/// `load` is given the tokens of the unknown keys to collect into. A strict type collects them
+/// itself so the keys of every nested table are checked, not only the keys of its own fields.
+pub fn load(
+    strict: bool,
+    unknown_keys: TokenStream,
+    load: impl Fn(TokenStream) -> TokenStream,
+) -> TokenStream {
+    if !strict {
+        return load(unknown_keys);
+    }
+    let load = load(quote! {&mut strict_unknown_keys});
+    quote! {
+        {
+            let mut strict_unknown_keys = Vec::new();
+            let configopt = #load;
+            ::configopt::reject_unknown_keys(
+                &strict_unknown_keys,
+                &<Self as ::configopt::ConfigOptType>::runtime_schema(),
+            )?;
+            configopt
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use super::strict;
+use crate::configopt_type::parse::{FieldType, ParsedField, ParsedVariant};
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+
+/// Deserialize a config file with `load` selecting the subcommands named by the file
+///
+/// `load` is given the tokens of the unknown keys to collect into. If there are subcommand fields
+/// the file is first loaded as a table so `command = "<name>"` and its `[<name>]` table can be
+/// rewritten into the subcommand variant. The table is also loaded first when the type is
+/// versioned with `#[configopt(version_field)]` so older files are migrated before deserializing.
+/// Unknown keys are rejected if the type is `strict`.
+pub fn load_for_struct(
+    fields: &[ParsedField],
+    versioned: bool,
+    strict: bool,
+    path: TokenStream,
+    unknown_keys: TokenStream,
+    load: impl Fn(TokenStream) -> TokenStream,
+) -> TokenStream {
+    let selects = fields
+        .iter()
+        .filter(|f| f.is_subcommand())
+        .map(|field| {
+            let span = field.span();
+            let serde_name = field.serde_name();
+            let configopt_ty = field.configopt_ty();
+            quote_spanned! {span=>
+                ::configopt::select_subcommand(
+                    &mut table,
+                    #serde_name,
+                    &<#configopt_ty as ::configopt::ConfigOptType>::subcommand_variants(),
+                );
+            }
+        })
+        .collect::<Vec<_>>();
+    if selects.is_empty() && !versioned {
+        return strict::load(strict, unknown_keys, |unknown_keys| {
+            let load = load(unknown_keys);
+            quote! {#load?}
+        });
+    }
+    let migrate = if versioned {
+        quote! {
+            ::configopt::migrate_table(
+                &mut table,
+                <Self as ::configopt::ConfigOptType>::version_field(),
+                &<Self as ::configopt::ConfigOptType>::migrations(),
+                #path,
+            )?;
+        }
+    } else {
+        quote! {}
+    };
+    let load = load(quote! {&mut Vec::new()});
+    let deserialize = strict::load(strict, unknown_keys, |unknown_keys| {
+        quote! {::configopt::from_toml_table(table, #path, #unknown_keys)?}
+    });
+    quote! {
+        {
+            let mut table = #load?;
+            #migrate
+            #(#selects)*
+            #deserialize
+        }
+    }
+}
+
+pub fn path_for_struct(fields: &[ParsedField]) -> TokenStream {
+    let fields = fields
+        .iter()
+        .filter(|f| f.is_subcommand())
+        .map(|field| {
+            let field_ident = field.ident();
+            quote! {
+                if let Some(value) = &self.#field_ident {
+                    return ::configopt::ConfigOptType::subcommand_path(value);
+                }
+            }
+        })
+        .collect::<TokenStream>();
+    quote! {
+        #fields
+        Vec::new()
+    }
+}
+
+pub fn variants_for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    let variants = variants.iter().map(|variant| {
+        let name = variant.structopt_name();
+        let variant_name = variant.variant_name();
+        let has_table = match variant.field_type() {
+            FieldType::Unit => false,
+            FieldType::Named(_) | FieldType::Unnamed => true,
+        };
+        quote! {
+            ::configopt::SubcommandVariant {
+                name: #name,
+                variant: #variant_name,
+                has_table: #has_table,
+            }
+        }
+    });
+    quote! {
+        vec![#(#variants),*]
+    }
+}
+
+pub fn path_for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    // Handle the case of an empty enum
+    if variants.is_empty() {
+        return quote! {
+            _ => Vec::new(),
+        };
+    }
+    variants
+        .iter()
+        .map(|variant| {
+            let full_configopt_ident = variant.full_configopt_ident();
+            let name = variant.structopt_name();
+            match variant.field_type() {
+                FieldType::Unnamed => {
+                    quote! {
+                        #full_configopt_ident(inner) => {
+                            let mut path = vec![#name];
+                            path.extend(::configopt::ConfigOptType::subcommand_path(inner));
+                            path
+                        }
+                    }
+                }
+                FieldType::Unit => {
+                    quote! {
+                        #full_configopt_ident => vec![#name],
+                    }
+                }
+                FieldType::Named(_) => {
+                    quote! {
+                        #full_configopt_ident{..} => vec![#name],
+                    }
+                }
+            }
+        })
+        .collect()
+}
@@ -1,4 +1,4 @@
-use crate::configopt_type::parse::ParsedField;
+use crate::configopt_type::parse::{possible_values, ParsedField};
 use proc_macro2::TokenStream;
 use quote::{quote, quote_spanned};
 use syn::spanned::Spanned;
@@ -9,17 +9,122 @@ pub fn for_struct(fields: &[ParsedField]) -> TokenStream {
         let self_field = quote! {self.#field_ident};
         let span = field.span();
         let serde_name = field.serde_name();
-        if field.is_subcommand() {
+        if field.is_subcommand() || field.is_cli_only() {
             quote! {}
         } else if field.is_serde_flatten() {
             quote_spanned! {span=>
                 result = format!("{}{}", result, #self_field.toml_config_with_prefix(&serde_prefix));
             }
+        } else if field.is_section() || field.is_structopt_flatten() {
+            // Nested fields are written one key at a time in declaration order with their help
+            // rather than as an inline table whose keys are sorted
+            quote_spanned! {span=>
+                let mut prefix = serde_prefix.to_vec();
+                prefix.push(String::from(#serde_name));
+                result = format!("{}{}", result, #self_field.toml_config_with_prefix(&prefix));
+            }
+        } else if field.is_map() {
+            // Sort the instances so the generated config is stable
+            quote_spanned! {span=>
+                let mut instances = #self_field.iter().collect::<Vec<_>>();
+                instances.sort_by(|a, b| a.0.cmp(b.0));
+                for (key, value) in instances {
+                    let mut prefix = serde_prefix.to_vec();
+                    prefix.push(String::from(#serde_name));
+                    prefix.push(key.to_string());
+                    result = format!("{}{}", result, value.toml_config_with_prefix(&prefix));
+                }
+            }
         }  else {
             let structopt_name = field.structopt_name();
             let structopt_rename = field.structopt_rename();
             let generate_config_arg_name = structopt_rename.rename("generate-config"); 
             let config_files_arg_name = structopt_rename.rename("config-files"); 
+            let explain_config_arg_name = structopt_rename.rename("explain-config");
+            let value = if field.is_secret() {
+                // Only show if a secret is set never its actual value
+                quote_spanned! {span=>
+                    if #self_field.is_some() {
+                        result = format!("{}{}# {} = {}\n\n", result, comment, key, ::configopt::REDACTED);
+                    } else {
+                        result = format!("{}{}# {} =\n\n", result, comment, key);
+                    }
+                }
+            } else if field.is_leaf() {
+                // Leaves are not required to implement `Serialize` so only their key is shown
+                quote_spanned! {span=>
+                    result = format!("{}{}# {} =\n\n", result, comment, key);
+                }
+            } else {
+                // Write the human friendly format the field is parsed from
+                let self_field = match field.human_parser() {
+                    Some(parser) => {
+                        let format = format!("::configopt::parsers::format_{}", parser)
+                            .parse::<TokenStream>()
+                            .unwrap();
+                        quote_spanned! {span=> #self_field.as_ref().map(#format)}
+                    }
+                    None if field.is_os_str() => {
+                        quote_spanned! {span=> ::configopt::os_str::Serialized(&#self_field)}
+                    }
+                    None => self_field,
+                };
+                // An empty list is a set value (`key = []`) unless it is a positional `Vec` which is
+                // unset when it is empty
+                let unset_when_empty = field.is_positional_vec();
+                // Sort the items of a `HashSet` so the generated config is stable
+                let sort = if field.is_hash_set() {
+                    quote_spanned! {span=>
+                        let val = match val {
+                            Value::Array(mut items) => {
+                                items.sort_by_key(|item| item.to_string());
+                                Value::Array(items)
+                            }
+                            val => val,
+                        };
+                    }
+                } else {
+                    quote! {}
+                };
+                quote_spanned! {span=>
+                    match toml::Value::try_from(&#self_field) {
+                        Ok(val) => {
+                            use toml::value::Value;
+                            #sort
+                            match &val {
+                                Value::Array(a) if a.is_empty() && #unset_when_empty => {
+                                    result = format!("{}{}# {} = {}\n\n", result, comment, key, val);
+                                }
+                                _ => {
+                                    result = format!("{}{}{} = {}\n\n", result, comment, key, ::configopt::inline_toml(&val));
+                                }
+                            }
+                        }
+                        Err(toml::ser::Error::UnsupportedNone) => {
+                            result = format!("{}{}# {} =\n\n", result, comment, key);
+                        }
+                        // Keep values TOML cannot represent (eg integers above `i64::MAX`) visible
+                        Err(e) => {
+                            result = format!("{}{}### [error: {}]\n# {} =\n\n", result, comment, e, key);
+                        }
+                    }
+                }
+            };
+            // Document the exact environment variable `structopt` reads the field from
+            let env_comment = match field.env() {
+                Some(env) => quote_spanned! {span=>
+                    comment = format!("{}### [env: {}]\n", comment, #env);
+                },
+                None => quote! {},
+            };
+            // List the variants of a value enum
+            let values = possible_values(field.configopt_ty());
+            let values_comment = quote_spanned! {span=>
+                let values = #values;
+                if !values.is_empty() {
+                    comment = format!("{}### [possible values: {}]\n", comment, values.join(", "));
+                }
+            };
             quote_spanned! {span=>
                 let key = if serde_prefix.is_empty() {
                     String::from(#serde_name)
@@ -66,27 +171,13 @@ pub fn for_struct(fields: &[ParsedField]) -> TokenStream {
                         }
                     }
                 }
-                if !hidden && !&[#generate_config_arg_name, #config_files_arg_name].contains(&#structopt_name) {
+                if !hidden && !&[#generate_config_arg_name, #explain_config_arg_name, #config_files_arg_name].contains(&#structopt_name) {
                     if !comment.is_empty() {
                         comment = comment.lines().map(|l| format!("### {}\n", l)).collect::<String>();
                     }
-                    match toml::Value::try_from(&#self_field) {
-                        Ok(val) => {
-                            use toml::value::Value;
-                            match &val {
-                                Value::Array(a) if a.is_empty() => {
-                                    result = format!("{}{}# {} = {}\n\n", result, comment, key, val);
-                                }
-                                _ => {
-                                    result = format!("{}{}{} = {}\n\n", result, comment, key, val);
-                                }
-                            }
-                        }
-                        Err(toml::ser::Error::UnsupportedNone) => {
-                            result = format!("{}{}# {} =\n\n", result, comment, key);
-                        }
-                        _ => {}
-                    }
+                    #env_comment
+                    #values_comment
+                    #value
                 }
             }
         }
@@ -0,0 +1,238 @@
+use crate::configopt_type::parse::{FieldType, ParsedField, ParsedVariant};
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+
+/// Convert the value of a field into TOML and insert it into `table` if it is set
+///
+/// `self_field` must be an `Option` or a value converted from one so an unset field is left out.
+pub fn insert_value(
+    span: proc_macro2::Span,
+    serde_name: &str,
+    self_field: TokenStream,
+    sort: bool,
+    unset_when_empty: bool,
+) -> TokenStream {
+    // Sort the items of a `HashSet` so the written config is stable
+    let sort = if sort {
+        quote_spanned! {span=>
+            let val = match val {
+                toml::Value::Array(mut items) => {
+                    items.sort_by_key(|item| item.to_string());
+                    toml::Value::Array(items)
+                }
+                val => val,
+            };
+        }
+    } else {
+        quote! {}
+    };
+    quote_spanned! {span=>
+        match (&::configopt::TomlValueOf(&#self_field)).toml_value() {
+            Ok(Some(val)) => {
+                #sort
+                match &val {
+                    toml::Value::Array(a) if a.is_empty() && #unset_when_empty => {}
+                    _ => {
+                        table.insert(String::from(#serde_name), val);
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                let path = if serde_prefix.is_empty() {
+                    String::from(#serde_name)
+                } else {
+                    format!("{}.{}", serde_prefix.join("."), #serde_name)
+                };
+                return Err(::configopt::TomlValueError {
+                    path,
+                    message: e.to_string(),
+                });
+            }
+        }
+    }
+}
+
+// Insert the values of `fields` into `table` where `self_field` is the expression of a field
+fn insert_fields(
+    fields: &[ParsedField],
+    self_field: impl Fn(&ParsedField) -> TokenStream,
+) -> TokenStream {
+    fields
+        .iter()
+        // The `configopt_fields` only control loading so they are not part of the config
+        .filter(|f| {
+            !["generate_config", "explain_config", "config_files"]
+                .iter()
+                .any(|name| f.ident() == name)
+        })
+        .map(|field| {
+            let self_field = self_field(field);
+            let span = field.span();
+            let serde_name = field.serde_name();
+            if field.is_cli_only() {
+                quote! {}
+            } else if field.is_subcommand() {
+                // The values of the selected variant are nested under its name
+                quote_spanned! {span=>
+                    if let Some(subcommand) = &#self_field {
+                        let mut prefix = serde_prefix.to_vec();
+                        prefix.push(String::from(#serde_name));
+                        let values = ::configopt::ConfigOptType::toml_values_with_prefix(
+                            subcommand,
+                            &prefix,
+                            secrets,
+                        )?;
+                        table.insert(String::from(#serde_name), toml::Value::Table(values));
+                    }
+                }
+            } else if field.is_serde_flatten() {
+                quote_spanned! {span=>
+                    table.extend(#self_field.toml_values_with_prefix(serde_prefix, secrets)?);
+                }
+            } else if field.is_section() || field.is_structopt_flatten() {
+                quote_spanned! {span=>
+                    let mut prefix = serde_prefix.to_vec();
+                    prefix.push(String::from(#serde_name));
+                    let values = #self_field.toml_values_with_prefix(&prefix, secrets)?;
+                    if !values.is_empty() {
+                        table.insert(String::from(#serde_name), toml::Value::Table(values));
+                    }
+                }
+            } else if field.is_map() {
+                quote_spanned! {span=>
+                    let mut instances = toml::value::Table::new();
+                    for (key, value) in &#self_field {
+                        let mut prefix = serde_prefix.to_vec();
+                        prefix.push(String::from(#serde_name));
+                        prefix.push(key.to_string());
+                        let values = value.toml_values_with_prefix(&prefix, secrets)?;
+                        if !values.is_empty() {
+                            instances.insert(key.to_string(), toml::Value::Table(values));
+                        }
+                    }
+                    if !instances.is_empty() {
+                        table.insert(String::from(#serde_name), toml::Value::Table(instances));
+                    }
+                }
+            } else {
+                // Write the human friendly format the field is parsed from
+                let value = match field.human_parser() {
+                    Some(parser) => {
+                        let format = format!("::configopt::parsers::format_{}", parser)
+                            .parse::<TokenStream>()
+                            .unwrap();
+                        quote_spanned! {span=> #self_field.as_ref().map(#format)}
+                    }
+                    None if field.is_os_str() => {
+                        quote_spanned! {span=> ::configopt::os_str::Serialized(&#self_field)}
+                    }
+                    None => self_field,
+                };
+                let insert = insert_value(
+                    span,
+                    &serde_name,
+                    value,
+                    field.is_hash_set(),
+                    field.is_positional_vec(),
+                );
+                if field.is_secret() {
+                    quote_spanned! {span=>
+                        if secrets {
+                            #insert
+                        }
+                    }
+                } else {
+                    insert
+                }
+            }
+        })
+        .collect()
+}
+
+pub fn for_struct(fields: &[ParsedField]) -> TokenStream {
+    let insert_fields = insert_fields(fields, |field| {
+        let field_ident = field.ident();
+        quote! {self.#field_ident}
+    });
+    quote! {
+        #[allow(unused_imports)]
+        use ::configopt::{TomlValueOther as _, TomlValueSerialize as _};
+        let mut table = toml::value::Table::new();
+        #insert_fields
+        Ok(table)
+    }
+}
+
+/// The values of the selected variant in a table with only its serde variant name
+///
+/// This is the form `serde` deserializes the variant from (eg `[command.Serve]`). A unit variant
+/// has an empty table.
+pub fn for_enum(variants: &[ParsedVariant]) -> TokenStream {
+    let arms = variants
+        .iter()
+        .map(|variant| {
+            let full_configopt_ident = variant.full_configopt_ident();
+            let variant_name = variant.variant_name();
+            match variant.field_type() {
+                FieldType::Unnamed => quote! {
+                    #full_configopt_ident(variant) => {
+                        prefix.push(String::from(#variant_name));
+                        let values = ::configopt::ConfigOptType::toml_values_with_prefix(
+                            variant,
+                            &prefix,
+                            secrets,
+                        )?;
+                        table.insert(String::from(#variant_name), toml::Value::Table(values));
+                    }
+                },
+                FieldType::Named(fields) => {
+                    let idents = fields.iter().map(ParsedField::ident);
+                    // Enum variant fields are bound by reference
+                    let insert_fields = insert_fields(fields, |field| {
+                        let field_ident = field.ident();
+                        quote! {(*#field_ident)}
+                    });
+                    quote! {
+                        #full_configopt_ident { #(#idents,)* } => {
+                            prefix.push(String::from(#variant_name));
+                            let values = {
+                                let serde_prefix = prefix.as_slice();
+                                let mut table = toml::value::Table::new();
+                                #insert_fields
+                                table
+                            };
+                            table.insert(String::from(#variant_name), toml::Value::Table(values));
+                        }
+                    }
+                }
+                FieldType::Unit => quote! {
+                    #full_configopt_ident => {
+                        table.insert(
+                            String::from(#variant_name),
+                            toml::Value::Table(toml::value::Table::new()),
+                        );
+                    }
+                },
+            }
+        })
+        .collect::<TokenStream>();
+    // Handle the case of an empty enum
+    let empty = if variants.is_empty() {
+        quote! {_ => {}}
+    } else {
+        quote! {}
+    };
+    quote! {
+        #[allow(unused_imports)]
+        use ::configopt::{TomlValueOther as _, TomlValueSerialize as _};
+        let mut prefix = serde_prefix.to_vec();
+        let mut table = toml::value::Table::new();
+        match self {
+            #arms
+            #empty
+        }
+        Ok(table)
+    }
+}
@@ -8,15 +8,23 @@ mod structopt_parser;
 use configopt_parser::ConfigOptAttr;
 use heck::{CamelCase, KebabCase, MixedCase, ShoutySnakeCase, SnakeCase};
 use proc_macro2::{Span, TokenStream};
+use proc_macro_error::abort;
 use proc_macro_roids::IdentExt;
+use quote::quote;
 use serde_parser::SerdeAttr;
-use std::{convert::Infallible, str::FromStr};
+use std::str::FromStr;
 use structopt_parser::StructOptAttr;
-use syn::{parse_quote, spanned::Spanned, Attribute, Expr, Field, Fields, Ident, Type, Variant};
+use syn::{
+    parse_quote, spanned::Spanned, Attribute, Expr, Field, Fields, GenericArgument, Ident, LitStr,
+    PathArguments, Type, Variant,
+};
 
-pub use serde_parser::trim_attr as trim_serde_attr;
+pub use serde_parser::{
+    rename as serde_rename, rename_all as serde_rename_all, trim_attr as trim_serde_attr,
+};
 pub use structopt_parser::{
-    rename_all as structopt_rename_all, trim_attr as trim_structopt_attr, StructOptTy,
+    remove_items as remove_structopt_items, rename_all as structopt_rename_all,
+    rename_all_env as structopt_rename_all_env, trim_attr as trim_structopt_attr, StructOptTy,
 };
 
 pub fn configopt_ident(ident: &Ident) -> Ident {
@@ -34,17 +42,19 @@ pub enum CasingStyle {
 }
 
 impl FromStr for CasingStyle {
-    type Err = Infallible;
+    type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s {
+        // Accept the same spellings as `structopt` (eg `screaming_snake` or `kebab-case`)
+        let normalized = s.replace(|c| c == '_' || c == '-', "").to_lowercase();
+        Ok(match normalized.as_str() {
             "camel" | "camelcase" => Self::Camel,
             "kebab" | "kebabcase" => Self::Kebab,
             "pascal" | "pascalcase" => Self::Pascal,
             "screamingsnake" | "screamingsnakecase" => Self::ScreamingSnake,
             "snake" | "snakecase" => Self::Snake,
             "verbatim" | "verbatimcase" => Self::Verbatim,
-            _ => panic!("Invalid value for `rename_all` attribute"),
+            _ => return Err(format!("invalid value `{}` for `rename_all`, expected one of `camel`, `kebab`, `pascal`, `screaming_snake`, `snake`, or `verbatim`", s)),
         })
     }
 }
@@ -61,21 +71,207 @@ impl CasingStyle {
             CasingStyle::Verbatim => String::from(s),
         }
     }
+
+    /// The name of the style in a `structopt` `rename_all` or `rename_all_env` attribute
+    pub fn structopt_name(self) -> &'static str {
+        match self {
+            CasingStyle::Camel => "camel",
+            CasingStyle::Kebab => "kebab",
+            CasingStyle::Pascal => "pascal",
+            CasingStyle::ScreamingSnake => "screaming_snake",
+            CasingStyle::Snake => "snake",
+            CasingStyle::Verbatim => "verbatim",
+        }
+    }
+
+    /// The name of the style in a `serde` `rename_all` attribute, `serde` keeps names verbatim
+    /// unless told otherwise so it has no name for it
+    pub fn serde_name(self) -> Option<&'static str> {
+        match self {
+            CasingStyle::Camel => Some("camelCase"),
+            CasingStyle::Kebab => Some("kebab-case"),
+            CasingStyle::Pascal => Some("PascalCase"),
+            CasingStyle::ScreamingSnake => Some("SCREAMING_SNAKE_CASE"),
+            CasingStyle::Snake => Some("snake_case"),
+            CasingStyle::Verbatim => None,
+        }
+    }
+}
+
+/// How a field is merged when patching one layer with another
+#[derive(Clone, Copy, PartialEq)]
+pub enum MergePolicy {
+    /// The value from the layer with the highest precedence is used
+    Overwrite,
+    /// The value from the layer with the lowest precedence is used
+    Keep,
+    /// The values from all layers are concatenated starting with the lowest precedence
+    Append,
+    /// The values from all layers are summed
+    Add,
+    Min,
+    Max,
+}
+
+impl FromStr for MergePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "overwrite" => Self::Overwrite,
+            "keep" => Self::Keep,
+            "append" => Self::Append,
+            "add" => Self::Add,
+            "min" => Self::Min,
+            "max" => Self::Max,
+            _ => return Err(format!("invalid value `{}` for `configopt(merge)`, expected one of `overwrite`, `keep`, `append`, `add`, `min`, or `max`", s)),
+        })
+    }
+}
+
+/// Can a change to a field take effect on reload
+#[derive(Clone, Copy, PartialEq)]
+pub enum ReloadPolicy {
+    Dynamic,
+    /// The value is only read on startup so a change requires a restart
+    Restart,
+}
+
+impl FromStr for ReloadPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "dynamic" => Self::Dynamic,
+            "restart" => Self::Restart,
+            _ => return Err(format!("invalid value `{}` for `configopt(reload)`, expected one of `dynamic` or `restart`", s)),
+        })
+    }
 }
 
 pub fn inner_ty(ty: &mut Type) -> &mut Ident {
+    let span = ty.span();
     match ty {
         Type::Path(type_path) => {
             if let Some(segment) = type_path.path.segments.last_mut() {
                 &mut segment.ident
             } else {
-                panic!(
+                abort!(
+                    span,
                     "`#[configopt]` could not find a last segment in the type path to make partial"
                 );
             }
         }
         _ => {
-            panic!("`#[configopt]` only supports types specified by a path");
+            abort!(
+                span,
+                "`#[configopt]` only supports types specified by a path"
+            );
+        }
+    }
+}
+
+/// Is the field a `PathBuf` or `OsString` which does not already have its own `serde(with)`
+pub fn is_os_str_field(field: &Field) -> bool {
+    is_os_str(&field.ty) && !serde_parser::parse_attrs(&field.attrs).contains(&SerdeAttr::With)
+}
+
+/// Is `ty` a `HashSet` possibly wrapped in `Option`
+fn is_hash_set(ty: &Type) -> bool {
+    if let Type::Path(type_path) = ty {
+        if let Some(segment) = type_path.path.segments.last() {
+            if segment.ident == "Option" {
+                if let PathArguments::AngleBracketed(args) = &segment.arguments {
+                    if let Some(GenericArgument::Type(ty)) = args.args.first() {
+                        return is_hash_set(ty);
+                    }
+                }
+                return false;
+            }
+            return segment.ident == "HashSet";
+        }
+    }
+    false
+}
+
+/// Is `ty` a `configopt::flags::FeatureFlags` possibly wrapped in `Option`
+fn is_feature_flags(ty: &Type) -> bool {
+    if let Type::Path(type_path) = ty {
+        if let Some(segment) = type_path.path.segments.last() {
+            if segment.ident == "Option" {
+                if let PathArguments::AngleBracketed(args) = &segment.arguments {
+                    if let Some(GenericArgument::Type(ty)) = args.args.first() {
+                        return is_feature_flags(ty);
+                    }
+                }
+                return false;
+            }
+            return segment.ident == "FeatureFlags";
+        }
+    }
+    false
+}
+
+/// Is `ty` a `PathBuf` or `OsString` possibly wrapped in `Option` and `Vec`
+fn is_os_str(ty: &Type) -> bool {
+    if let Type::Path(type_path) = ty {
+        if let Some(segment) = type_path.path.segments.last() {
+            if segment.ident == "Option" || segment.ident == "Vec" {
+                if let PathArguments::AngleBracketed(args) = &segment.arguments {
+                    if let Some(GenericArgument::Type(ty)) = args.args.first() {
+                        return is_os_str(ty);
+                    }
+                }
+                return false;
+            }
+            return segment.ident == "PathBuf" || segment.ident == "OsString";
+        }
+    }
+    false
+}
+
+/// The value type of a map type (eg `V` in `HashMap<K, V>`)
+pub fn map_value_ty(ty: &mut Type) -> &mut Type {
+    let span = ty.span();
+    if let Type::Path(type_path) = ty {
+        if let Some(segment) = type_path.path.segments.last_mut() {
+            if let PathArguments::AngleBracketed(args) = &mut segment.arguments {
+                if let Some(GenericArgument::Type(ty)) = args.args.iter_mut().last() {
+                    return ty;
+                }
+            }
+        }
+    }
+    abort!(
+        span,
+        "`configopt(map)` expected a map type with a value type (eg `HashMap<String, T>`)"
+    );
+}
+
+/// The type of a single value of a field without any `Option` or `Vec` wrapping
+pub fn value_ty(ty: &Type) -> &Type {
+    if let Type::Path(type_path) = ty {
+        if let Some(segment) = type_path.path.segments.last() {
+            if segment.ident == "Option" || segment.ident == "Vec" {
+                if let PathArguments::AngleBracketed(args) = &segment.arguments {
+                    if let Some(GenericArgument::Type(ty)) = args.args.first() {
+                        return value_ty(ty);
+                    }
+                }
+            }
+        }
+    }
+    ty
+}
+
+/// The possible values of a field whose type derives `ConfigOptValue` or an empty `Vec`
+pub fn possible_values(ty: &Type) -> TokenStream {
+    let ty = value_ty(ty);
+    quote! {
+        {
+            #[allow(unused_imports)]
+            use ::configopt::{ValuesOfOther as _, ValuesOfValue as _};
+            (&::configopt::ValuesOf::<#ty>::new()).values()
         }
     }
 }
@@ -84,36 +280,157 @@ pub fn has_configopt_fields(parsed: &[ParsedField]) -> bool {
     parsed.iter().any(|f| f.ident() == "generate_config")
 }
 
-#[derive(Clone)]
+/// The rules set with `#[configopt(min, max, matches, required_if)]` which are exported in the
+/// schema
+#[derive(Clone, Default)]
+pub struct Constraints {
+    pub min: Option<String>,
+    pub max: Option<String>,
+    pub pattern: Option<String>,
+    pub required_if: Option<String>,
+}
+
+impl Constraints {
+    fn new(attrs: &[ConfigOptAttr]) -> Self {
+        let mut constraints = Self::default();
+        for attr in attrs {
+            match attr {
+                ConfigOptAttr::Min(min) => constraints.min = Some(min.clone()),
+                ConfigOptAttr::Max(max) => constraints.max = Some(max.clone()),
+                ConfigOptAttr::Pattern(pattern) => constraints.pattern = Some(pattern.clone()),
+                ConfigOptAttr::RequiredIf(other) => constraints.required_if = Some(other.clone()),
+                _ => {}
+            }
+        }
+        constraints
+    }
+
+    fn is_empty(&self) -> bool {
+        self.min.is_none()
+            && self.max.is_none()
+            && self.pattern.is_none()
+            && self.required_if.is_none()
+    }
+}
+
 pub struct ParsedField {
     ident: Ident,
     structopt_ty: StructOptTy,
     configopt_inner_ty: Ident,
+    configopt_ty: Type,
     span: Span,
     structopt_flatten: bool,
     serde_flatten: bool,
     subcommand: bool,
+    section: bool,
+    leaf: bool,
     positional_vec: bool,
     no_wrap: bool,
+    map: bool,
+    merge: MergePolicy,
+    reload: ReloadPolicy,
+    negatable: bool,
+    file_only: Option<Option<Expr>>,
+    cli_only: bool,
+    no_interpolate: bool,
+    // Is the value of the environment variable decoded from a `base64:`, `json:`, `percent:`, or
+    // `raw:` prefix
+    decode_env: bool,
+    empty_is_unset: bool,
+    os_str: bool,
+    human_parser: Option<&'static str>,
+    default_value: bool,
+    id: bool,
+    secret: bool,
+    // `Some(None)` decrypts with the installed decryptor and `Some(Some(f))` with `f`
+    decrypt: Option<Option<syn::Path>>,
+    alias: Option<String>,
+    deprecated: Option<String>,
+    prefix: Option<String>,
     structopt_rename: CasingStyle,
     structopt_name: String,
+    env: Option<String>,
     serde_name: String,
     to_os_string: Option<Expr>,
+    constraints: Constraints,
 }
 
 impl ParsedField {
     pub fn new(
         field: &mut Field,
         structopt_rename: CasingStyle,
+        structopt_rename_env: CasingStyle,
         serde_rename: CasingStyle,
         retained_attrs: &[Ident],
     ) -> Self {
-        let ident = field.ident.clone().expect("field ident to exist");
+        let span = field.span();
+        let ident = field
+            .ident
+            .clone()
+            .unwrap_or_else(|| abort!(span, "`ConfigOpt` only supports named fields"));
 
         let configopt_attrs = configopt_parser::parse_attrs(&field.attrs);
         let no_wrap = configopt_attrs
             .iter()
             .any(|a| matches!(a, ConfigOptAttr::NoWrap));
+        let secret = configopt_attrs
+            .iter()
+            .any(|a| matches!(a, ConfigOptAttr::Secret));
+        let decrypt = configopt_attrs.iter().find_map(|a| match a {
+            ConfigOptAttr::Decrypt(with) => Some(with.clone()),
+            _ => None,
+        });
+        let map = configopt_attrs
+            .iter()
+            .any(|a| matches!(a, ConfigOptAttr::Map));
+        let section = configopt_attrs
+            .iter()
+            .any(|a| matches!(a, ConfigOptAttr::Section));
+        // Fields converted from an intermediate type are deserialized as a whole like leaves
+        let from = configopt_attrs.iter().find_map(|a| match a {
+            ConfigOptAttr::From(ty) => Some(quote! {deserialize_from::<_, #ty, _>}),
+            ConfigOptAttr::TryFrom(ty) => Some(quote! {deserialize_try_from::<_, #ty, _>}),
+            _ => None,
+        });
+        let leaf = from.is_some()
+            || configopt_attrs
+                .iter()
+                .any(|a| matches!(a, ConfigOptAttr::Leaf));
+        let id = configopt_attrs
+            .iter()
+            .any(|a| matches!(a, ConfigOptAttr::Id));
+        let negatable = configopt_attrs
+            .iter()
+            .any(|a| matches!(a, ConfigOptAttr::Negatable));
+        let no_interpolate = configopt_attrs
+            .iter()
+            .any(|a| matches!(a, ConfigOptAttr::NoInterpolate));
+        let decode_env = configopt_attrs
+            .iter()
+            .any(|a| matches!(a, ConfigOptAttr::DecodeEnv));
+        let empty_is_unset = configopt_attrs
+            .iter()
+            .any(|a| matches!(a, ConfigOptAttr::EmptyIsUnset));
+        // Feature flags are merged flag by flag unless the field says otherwise
+        let feature_flags = is_feature_flags(&field.ty);
+        let merge = configopt_attrs
+            .iter()
+            .find_map(|a| match a {
+                ConfigOptAttr::Merge(merge) => Some(*merge),
+                _ => None,
+            })
+            .unwrap_or(if feature_flags {
+                MergePolicy::Append
+            } else {
+                MergePolicy::Overwrite
+            });
+        let reload = configopt_attrs
+            .iter()
+            .find_map(|a| match a {
+                ConfigOptAttr::Reload(reload) => Some(*reload),
+                _ => None,
+            })
+            .unwrap_or(ReloadPolicy::Dynamic);
 
         let structopt_ty = StructOptTy::from_syn_ty(&field.ty);
         let ty = &mut field.ty;
@@ -126,7 +443,14 @@ impl ParsedField {
 
         let structopt_attrs = structopt_parser::parse_attrs(&field.attrs);
         let serde_attrs = serde_parser::parse_attrs(&field.attrs);
-        let serde_name = serde_rename.rename(&ident.to_string());
+        let prefix = configopt_attrs.iter().find_map(|a| match a {
+            ConfigOptAttr::Prefix(prefix) => Some(prefix.clone()),
+            _ => None,
+        });
+        // A prefixed type is read from the table named after its prefix
+        let serde_name = prefix
+            .clone()
+            .unwrap_or_else(|| serde_rename.rename(&ident.to_string()));
         let structopt_name = structopt_attrs
             .iter()
             .find_map(|a| match &a {
@@ -134,6 +458,24 @@ impl ParsedField {
                 _ => None,
             })
             .unwrap_or_else(|| structopt_rename.rename(&ident.to_string()));
+        // Match `structopt` which derives a bare `env` from the name assigned so far, an assigned
+        // name is used verbatim
+        let mut env = None;
+        let mut assigned_name = None;
+        for attr in &structopt_attrs {
+            match attr {
+                StructOptAttr::NameLitStr(name) => assigned_name = Some(name.clone()),
+                StructOptAttr::Env(Some(name)) => env = Some(name.clone()),
+                StructOptAttr::Env(None) => {
+                    env = Some(
+                        assigned_name
+                            .clone()
+                            .unwrap_or_else(|| structopt_rename_env.rename(&ident.to_string())),
+                    )
+                }
+                _ => {}
+            }
+        }
         let structopt_flatten = structopt_attrs.iter().any(|a| match a {
             StructOptAttr::Flatten => true,
             _ => false,
@@ -142,21 +484,269 @@ impl ParsedField {
             StructOptAttr::Subcommand => true,
             _ => false,
         });
-        let positional = structopt_attrs.iter().all(|a| match a {
-            StructOptAttr::Short | StructOptAttr::Long => false,
-            _ => true,
+        let serde_flatten = serde_attrs.iter().any(|a| match a {
+            SerdeAttr::Flatten => true,
+            _ => false,
+        });
+        if prefix.is_some() && (!structopt_flatten || serde_flatten) {
+            abort!(
+                span,
+                "`configopt(prefix)` requires a `structopt(flatten)` field which is not `serde(flatten)`"
+            );
+        }
+        let skip = structopt_attrs.iter().find_map(|a| match a {
+            StructOptAttr::Skip(default) => Some(default.clone()),
+            _ => None,
         });
+        let positional = skip.is_none()
+            && structopt_attrs.iter().all(|a| match a {
+                StructOptAttr::Short | StructOptAttr::Long => false,
+                _ => true,
+            });
         let positional_vec = positional && structopt_ty.is_vec();
+        let occurrences = structopt_attrs
+            .iter()
+            .any(|a| matches!(a, StructOptAttr::FromOccurrences));
+        let human_parser = structopt_attrs.iter().find_map(|a| match a {
+            StructOptAttr::HumanParser(parser) => Some(*parser),
+            _ => None,
+        });
+        let default_value = structopt_attrs
+            .iter()
+            .any(|a| matches!(a, StructOptAttr::DefaultValue));
+        if occurrences && structopt_ty != StructOptTy::Other {
+            abort!(
+                span,
+                "`configopt` only supports `parse(from_occurrences)` on integer fields"
+            );
+        }
+
+        // Secrets are read from a file by parsing the file contents so only single value fields
+        // are supported
+        if secret
+            && (structopt_flatten
+                || subcommand
+                || !matches!(structopt_ty, StructOptTy::Option | StructOptTy::Other))
+        {
+            abort!(
+                span,
+                "`configopt(secret)` only supports single value fields"
+            );
+        }
+        // Only a single string can be decrypted
+        if decrypt.is_some()
+            && (structopt_flatten
+                || subcommand
+                || !matches!(structopt_ty, StructOptTy::Option | StructOptTy::Other))
+        {
+            abort!(
+                span,
+                "`configopt(decrypt)` only supports single value `String` fields"
+            );
+        }
+        if id
+            && (structopt_flatten
+                || subcommand
+                || map
+                || !matches!(structopt_ty, StructOptTy::Option | StructOptTy::Other))
+        {
+            abort!(span, "`configopt(id)` only supports single value fields");
+        }
+        if merge != MergePolicy::Overwrite
+            && (structopt_flatten || subcommand || map || positional_vec)
+        {
+            abort!(span, "`configopt(merge)` is not supported on flattened, subcommand, map, or positional fields");
+        }
+        let cli_only = configopt_attrs
+            .iter()
+            .any(|a| matches!(a, ConfigOptAttr::CliOnly));
+        if cli_only && (structopt_flatten || subcommand || map || skip.is_some()) {
+            abort!(span, "`configopt(cli_only)` is not supported on flattened, subcommand, map, or skipped fields");
+        }
+        let skipped = skip.is_some();
+        // Config only fields keep the `skip` default so they never block conversion
+        let file_only = if configopt_attrs
+            .iter()
+            .any(|a| matches!(a, ConfigOptAttr::FileOnly))
+        {
+            if skip.is_none() || map || secret {
+                abort!(span, "`configopt(file_only)` requires `structopt(skip)` and cannot be combined with `map` or `secret`");
+            }
+            skip
+        } else {
+            None
+        };
+        // Sections have no CLI surface so they are skipped by `structopt`
+        if section && (skip.is_none() || structopt_ty != StructOptTy::Other || map || secret) {
+            abort!(span, "`configopt(section)` requires `structopt(skip)` on a struct deriving `ConfigOptSection`");
+        }
+        // Leaves are deserialized as a whole so they only need to implement `Deserialize`
+        if leaf && (skip.is_none() || section || map || secret || file_only.is_some()) {
+            abort!(span, "`configopt(leaf)`, `configopt(from)`, and `configopt(try_from)` require `structopt(skip)` and cannot be combined with `section`, `map`, `secret`, or `file_only`");
+        }
+        if from.is_some() && !matches!(structopt_ty, StructOptTy::Option | StructOptTy::Other) {
+            abort!(
+                span,
+                "`configopt(from)` and `configopt(try_from)` only support single value fields"
+            );
+        }
+        // Only the value of a single argument is read from an environment variable
+        if decode_env && (structopt_flatten || subcommand || section || map || leaf) {
+            abort!(span, "`configopt(decode_env)` cannot be combined with `flatten`, `subcommand`, `section`, `map`, or `leaf`");
+        }
+        if negatable && (!structopt_ty.is_bool() || positional) {
+            abort!(span, "`configopt(negatable)` only supports `bool` flags");
+        }
+        // Positional `Vec` arguments are always unset when they are empty
+        if empty_is_unset
+            && (!matches!(structopt_ty, StructOptTy::Vec | StructOptTy::OptionVec)
+                || positional_vec)
+        {
+            abort!(
+                span,
+                "`configopt(empty_is_unset)` requires a `Vec` field which is not positional"
+            );
+        }
+        match (merge, &structopt_ty) {
+            (MergePolicy::Append, StructOptTy::Vec)
+            | (MergePolicy::Append, StructOptTy::OptionVec)
+            | (MergePolicy::Min, StructOptTy::Option)
+            | (MergePolicy::Min, StructOptTy::Other)
+            | (MergePolicy::Max, StructOptTy::Option)
+            | (MergePolicy::Max, StructOptTy::Other)
+            | (MergePolicy::Add, StructOptTy::Option)
+            | (MergePolicy::Add, StructOptTy::Other)
+            | (MergePolicy::Overwrite, _)
+            | (MergePolicy::Keep, _) => {}
+            (MergePolicy::Append, _) if feature_flags => {}
+            (MergePolicy::Append, _) => abort!(span, "`configopt(merge = \"append\")` requires a `Vec` field"),
+            _ => abort!(span, "`configopt(merge = \"min\")`, `configopt(merge = \"max\")`, and `configopt(merge = \"add\")` require a single value field"),
+        }
 
         // The below logic converts the field into a `ConfigOpt` field
 
-        // If the field is flattened or a subcommand, modify the type with the configopt type prefix
-        if structopt_flatten || subcommand {
+        // If the field is flattened, a subcommand, or a section, modify the type with the configopt
+        // type prefix
+        if structopt_flatten || subcommand || section {
             *inner_ty = configopt_inner_ty.clone();
         }
+        // If the field is a map of structs, modify the value type with the configopt type prefix.
+        // Each instance in the map is merged individually so an empty map means unset.
+        if map {
+            if structopt_flatten || subcommand || secret {
+                abort!(
+                    span,
+                    "`configopt(map)` cannot be combined with `flatten`, `subcommand`, or `secret`"
+                );
+            }
+            let value_ty = self::inner_ty(map_value_ty(ty));
+            *value_ty = configopt_ident(value_ty);
+        }
+        let configopt_ty = ty.clone();
 
         retain_attrs(&mut field.attrs, &retained_attrs);
 
+        // A `skip` default would take precedence over config files so only the actual type uses it
+        if file_only.is_some() || leaf {
+            for attr in &mut field.attrs {
+                structopt_parser::remove_items(attr, &["skip"]);
+            }
+            field.attrs.push(parse_quote! {#[structopt(skip)]});
+        }
+        // `structopt` does not support `Option` with `from_occurrences` so the `configopt` type uses
+        // a parser which maps zero occurrences to `None`
+        if occurrences {
+            for attr in &mut field.attrs {
+                structopt_parser::remove_items(attr, &["parse"]);
+            }
+            field.attrs.push(
+                parse_quote! {#[structopt(parse(from_occurrences = ::configopt::occurrences))]},
+            );
+        }
+
+        // A missing table is an empty map or section
+        if map || section {
+            field.attrs.push(parse_quote! {#[serde(default)]});
+        }
+        // The intermediate type is converted as soon as a config file is loaded so invalid values
+        // are reported with the file they came from
+        if let Some(from) = &from {
+            let with = LitStr::new(&format!("::configopt::{}", from), field.span());
+            field
+                .attrs
+                .push(parse_quote! {#[serde(default, deserialize_with = #with)]});
+        }
+        // Config files accept the same human friendly format as the CLI
+        if let Some(parser) = human_parser {
+            if let StructOptTy::Option | StructOptTy::Other = structopt_ty {
+                let with = LitStr::new(
+                    &format!("::configopt::parsers::serde_with::{}::option", parser),
+                    field.span(),
+                );
+                field
+                    .attrs
+                    .push(parse_quote! {#[serde(default, with = #with)]});
+            }
+        }
+        // An empty list in a config file is the same as a missing key
+        if empty_is_unset {
+            field.attrs.push(
+                parse_quote! {#[serde(default, deserialize_with = "::configopt::deserialize_empty_as_none")]},
+            );
+        }
+        // OS strings are written as strings, or bytes when they are not UTF-8, instead of `serde`'s
+        // platform specific representation
+        let os_str = is_os_str_field(field)
+            && !(structopt_flatten || subcommand || section || map || leaf || cli_only)
+            && human_parser.is_none()
+            && from.is_none()
+            && !empty_is_unset;
+        if os_str {
+            field
+                .attrs
+                .push(parse_quote! {#[serde(default, with = "::configopt::os_str")]});
+        }
+        // CLI only fields are never read from or written to config files
+        if cli_only && !serde_parser::parse_attrs(&field.attrs).contains(&SerdeAttr::Skip) {
+            field.attrs.push(parse_quote! {#[serde(skip)]});
+        }
+
+        // The keys of a prefixed type are nested under its prefix instead of the field name
+        if let Some(prefix) = &prefix {
+            field.attrs.push(parse_quote! {#[serde(rename = #prefix)]});
+        }
+
+        // Accept the alias from both the CLI and config files
+        let alias = configopt_attrs.iter().find_map(|a| match a {
+            ConfigOptAttr::Alias(alias) => Some(alias.clone()),
+            _ => None,
+        });
+        let deprecated = configopt_attrs.iter().find_map(|a| match a {
+            ConfigOptAttr::Deprecated(note) => Some(note.clone()),
+            _ => None,
+        });
+        if let Some(alias) = &alias {
+            if structopt_flatten || subcommand {
+                abort!(
+                    span,
+                    "`configopt(alias)` is not supported on flattened or subcommand fields"
+                );
+            }
+            let alias_arg = structopt_rename.rename(alias);
+            field.attrs.push(parse_quote! {#[serde(alias = #alias)]});
+            field
+                .attrs
+                .push(parse_quote! {#[structopt(alias = #alias_arg)]});
+        }
+
+        let constraints = Constraints::new(&configopt_attrs);
+        if !constraints.is_empty() && (structopt_flatten || subcommand || section || map) {
+            abort!(
+                span,
+                "`configopt(min, max, matches, required_if)` are only supported on fields with a value"
+            );
+        }
+
         // If the field is not already, wrap its type in an `Option`. This guarantees that the
         // `ConfigOpt` struct can be parsed regardless of complete CLI input.
         if let StructOptTy::Bool | StructOptTy::Vec | StructOptTy::Other = structopt_ty {
@@ -167,14 +757,20 @@ impl ParsedField {
             // followed by a positional value).
             if structopt_ty.is_bool() {
                 field.ty = parse_quote!(::configopt::ConfigOptBool);
-                field
-                    .attrs
-                    .push(parse_quote! {#[structopt(parse(from_flag = ::configopt::ConfigOptBool::from_flag))]});
+                // Skipped fields do not accept a parser
+                if !skipped {
+                    field
+                        .attrs
+                        .push(parse_quote! {#[structopt(parse(from_flag = ::configopt::ConfigOptBool::from_flag))]});
+                }
             }
             // If it was a flattened field all of its fields will be optional so it does not need to
             // be wrapped in an `Option`
             // Positional `Vec` arguments are not allowed to be wrapped in an `Option`
-            else if !structopt_flatten && !positional_vec {
+            // `Occurrences` is an alias of `Option` which `structopt` does not recognize as an `Option`
+            else if occurrences {
+                field.ty = parse_quote!(::configopt::Occurrences<#ty>);
+            } else if !structopt_flatten && !section && !positional_vec && !map {
                 field.ty = parse_quote!(Option<#ty>);
             }
         }
@@ -183,22 +779,50 @@ impl ParsedField {
             ident,
             structopt_ty,
             configopt_inner_ty,
+            configopt_ty,
             span: field.span(),
             structopt_rename,
             structopt_name,
+            env,
             serde_name,
             structopt_flatten,
-            serde_flatten: serde_attrs.iter().any(|a| match a {
-                SerdeAttr::Flatten => true,
-                _ => false,
-            }),
+            serde_flatten,
             subcommand,
+            section,
+            leaf,
             positional_vec,
             no_wrap,
-            to_os_string: configopt_attrs.into_iter().find_map(|a| match a {
-                ConfigOptAttr::ToOsString(expr) => Some(expr),
-                _ => None,
-            }),
+            map,
+            merge,
+            reload,
+            negatable,
+            file_only,
+            cli_only,
+            no_interpolate,
+            decode_env,
+            empty_is_unset,
+            os_str,
+            human_parser,
+            default_value,
+            id,
+            secret,
+            decrypt,
+            alias,
+            deprecated,
+            prefix,
+            constraints,
+            to_os_string: configopt_attrs
+                .into_iter()
+                .find_map(|a| match a {
+                    ConfigOptAttr::ToOsString(expr) => Some(expr),
+                    _ => None,
+                })
+                .or_else(|| {
+                    human_parser.map(|parser| {
+                        let to_os_string = format!("::configopt::parsers::{}_to_os_string", parser);
+                        syn::parse_str(&to_os_string).unwrap()
+                    })
+                }),
         }
     }
 
@@ -210,11 +834,15 @@ impl ParsedField {
         &self.structopt_ty
     }
 
-    #[allow(unused)]
     pub fn configopt_inner_ty(&self) -> &Ident {
         &self.configopt_inner_ty
     }
 
+    /// The type of the field on the `configopt` type without any `Option` wrapping
+    pub fn configopt_ty(&self) -> &Type {
+        &self.configopt_ty
+    }
+
     pub fn is_structopt_flatten(&self) -> bool {
         self.structopt_flatten
     }
@@ -227,6 +855,17 @@ impl ParsedField {
         self.subcommand
     }
 
+    /// Is the field a config only section deriving `ConfigOptSection`
+    pub fn is_section(&self) -> bool {
+        self.section
+    }
+
+    /// Is the field deserialized as a whole without a `configopt` type, either directly or from an
+    /// intermediate type
+    pub fn is_leaf(&self) -> bool {
+        self.leaf
+    }
+
     pub fn is_positional_vec(&self) -> bool {
         self.positional_vec
     }
@@ -235,6 +874,96 @@ impl ParsedField {
         self.no_wrap
     }
 
+    pub fn is_map(&self) -> bool {
+        self.map
+    }
+
+    pub fn merge(&self) -> MergePolicy {
+        self.merge
+    }
+
+    pub fn requires_restart(&self) -> bool {
+        self.reload == ReloadPolicy::Restart
+    }
+
+    pub fn is_negatable(&self) -> bool {
+        self.negatable
+    }
+
+    pub fn is_cli_only(&self) -> bool {
+        self.cli_only
+    }
+
+    pub fn no_interpolate(&self) -> bool {
+        self.no_interpolate
+    }
+
+    pub fn decode_env(&self) -> bool {
+        self.decode_env
+    }
+
+    pub fn empty_is_unset(&self) -> bool {
+        self.empty_is_unset
+    }
+
+    /// Is the field a `PathBuf` or `OsString` (or `Option` or `Vec` of one) serialized with
+    /// `configopt::os_str`
+    pub fn is_os_str(&self) -> bool {
+        self.os_str
+    }
+
+    /// Does the field hold a set whose iteration order is not stable
+    pub fn is_hash_set(&self) -> bool {
+        is_hash_set(&self.configopt_ty)
+    }
+
+    /// Does the field hold feature flags which are merged, diffed, and set flag by flag
+    pub fn is_feature_flags(&self) -> bool {
+        is_feature_flags(&self.configopt_ty)
+    }
+
+    pub fn is_file_only(&self) -> bool {
+        self.file_only.is_some()
+    }
+
+    /// The `structopt(skip = ...)` default of a `file_only` field
+    pub fn file_only_default(&self) -> Option<&Expr> {
+        self.file_only.as_ref().and_then(Option::as_ref)
+    }
+
+    pub fn has_default_value(&self) -> bool {
+        self.default_value
+    }
+
+    pub fn is_id(&self) -> bool {
+        self.id
+    }
+
+    pub fn is_secret(&self) -> bool {
+        self.secret
+    }
+
+    pub fn decrypt(&self) -> Option<Option<&syn::Path>> {
+        self.decrypt.as_ref().map(Option::as_ref)
+    }
+
+    pub fn alias(&self) -> Option<&str> {
+        self.alias.as_deref()
+    }
+
+    pub fn deprecated(&self) -> Option<&str> {
+        self.deprecated.as_deref()
+    }
+
+    pub fn prefix(&self) -> Option<&str> {
+        self.prefix.as_deref()
+    }
+
+    /// The ident of the field a secret can be read from
+    pub fn secret_file_ident(&self) -> Ident {
+        self.ident.append("_file")
+    }
+
     pub fn structopt_rename(&self) -> CasingStyle {
         self.structopt_rename
     }
@@ -243,13 +972,27 @@ impl ParsedField {
         &self.structopt_name
     }
 
+    /// The environment variable `structopt` reads the field from
+    pub fn env(&self) -> Option<&str> {
+        self.env.as_deref()
+    }
+
     pub fn serde_name(&self) -> &str {
         &self.serde_name
     }
 
+    pub fn constraints(&self) -> &Constraints {
+        &self.constraints
+    }
+
     pub fn to_os_string(&self) -> Option<&Expr> {
         self.to_os_string.as_ref()
     }
+
+    /// The `configopt::parsers` parser used on the CLI
+    pub fn human_parser(&self) -> Option<&'static str> {
+        self.human_parser
+    }
 }
 
 impl Spanned for ParsedField {
@@ -269,6 +1012,7 @@ impl FieldType {
     fn new(
         fields: &mut Fields,
         structopt_rename: CasingStyle,
+        structopt_rename_env: CasingStyle,
         serde_rename: CasingStyle,
         retained_attrs: &[Ident],
     ) -> Self {
@@ -277,7 +1021,15 @@ impl FieldType {
                 named_fields
                     .named
                     .iter_mut()
-                    .map(|f| ParsedField::new(f, structopt_rename, serde_rename, retained_attrs))
+                    .map(|f| {
+                        ParsedField::new(
+                            f,
+                            structopt_rename,
+                            structopt_rename_env,
+                            serde_rename,
+                            retained_attrs,
+                        )
+                    })
                     .collect(),
             ),
             Fields::Unnamed(_) => Self::Unnamed,
@@ -289,8 +1041,10 @@ impl FieldType {
 pub struct ParsedVariant {
     full_ident: TokenStream,
     full_configopt_ident: TokenStream,
+    unnamed_configopt_ty: Option<Type>,
     span: Span,
     field_type: FieldType,
+    variant_name: String,
     structopt_name: String,
 }
 
@@ -299,10 +1053,18 @@ impl ParsedVariant {
         type_ident: &Ident,
         variant: &mut Variant,
         structopt_rename: CasingStyle,
+        structopt_rename_env: CasingStyle,
         serde_rename: CasingStyle,
         retained_attrs: &[Ident],
     ) -> Self {
         let variant_ident = &variant.ident;
+        let structopt_name = structopt_parser::parse_attrs(&variant.attrs)
+            .into_iter()
+            .find_map(|a| match a {
+                StructOptAttr::NameLitStr(name) => Some(name),
+                _ => None,
+            })
+            .unwrap_or_else(|| structopt_rename.rename(variant_ident.to_string()));
         let full_ident = parse_quote! {#type_ident::#variant_ident};
         let configopt_type_ident = configopt_ident(&type_ident);
         let full_configopt_ident = parse_quote! {#configopt_type_ident::#variant_ident};
@@ -311,12 +1073,24 @@ impl ParsedVariant {
         let field_type = FieldType::new(
             &mut variant.fields,
             structopt_rename,
+            structopt_rename_env,
             serde_rename,
             retained_attrs,
         );
+        // Only the arguments of the top level app are prefixed
+        if let FieldType::Named(fields) = &field_type {
+            if let Some(field) = fields.iter().find(|f| f.prefix().is_some()) {
+                abort!(
+                    field.ident(),
+                    "`configopt(prefix)` is not supported on the fields of enum variants"
+                );
+            }
+        }
+        let mut unnamed_configopt_ty = None;
         if let Fields::Unnamed(fields) = &mut variant.fields {
             if fields.unnamed.len() > 1 {
-                panic!(
+                abort!(
+                    fields,
                     "`ConfigOpt` cannot be derived on unnamed enums with a length greater than 1"
                 );
             }
@@ -324,15 +1098,17 @@ impl ParsedVariant {
             let field = &mut fields.unnamed[0];
             let ty = inner_ty(&mut field.ty);
             *ty = configopt_ident(ty);
+            unnamed_configopt_ty = Some(field.ty.clone());
         }
 
         Self {
             full_ident,
             full_configopt_ident,
+            unnamed_configopt_ty,
             span: variant.span(),
             field_type,
-            // TODO: Actually lookup the `structopt` name
-            structopt_name: variant_ident.to_string().to_kebab_case(),
+            variant_name: variant_ident.to_string(),
+            structopt_name,
         }
     }
 
@@ -344,10 +1120,20 @@ impl ParsedVariant {
         &self.full_configopt_ident
     }
 
+    /// The `configopt` type of the field of an unnamed variant
+    pub fn unnamed_configopt_ty(&self) -> Option<&Type> {
+        self.unnamed_configopt_ty.as_ref()
+    }
+
     pub fn field_type(&self) -> &FieldType {
         &self.field_type
     }
 
+    /// The name of the variant which `serde` uses
+    pub fn variant_name(&self) -> &str {
+        &self.variant_name
+    }
+
     pub fn structopt_name(&self) -> &str {
         &self.structopt_name
     }
@@ -13,14 +13,13 @@ macro_rules! attribute_trimmer {
                     let lit: LitStr = input.parse()?;
                     quote! {#lit}
                 } else {
-                    match input.parse::<Expr>() {
-                        Ok(expr) => {
-                            quote! {#expr}
-                        }
-                        Err(e) => {
-                            panic!("`configopt` parsing trimmer expected `string literal` or `expression` after `=`, err: {}", e)
-                        }
-                    }
+                    let expr = input.parse::<Expr>().map_err(|e| {
+                        syn::Error::new(
+                            e.span(),
+                            "`configopt` expected `string literal` or `expression` after `=`",
+                        )
+                    })?;
+                    quote! {#expr}
                 };
                 if should_trim {
                     quote! {}
@@ -61,7 +60,7 @@ macro_rules! attribute_trimmer {
             }
             let tokens = attr
                 .parse_args_with(trimmer)
-                .expect("`ConfigOpt` failed to trim attributes");
+                .unwrap_or_else(|e| ::proc_macro_error::abort!(e));
             attr.tokens = quote! {(#tokens)};
         }
     }
@@ -1,4 +1,5 @@
 use proc_macro::TokenStream;
+use proc_macro_error::abort;
 use syn::{
     parse::{Parse, ParseStream, Parser},
     punctuated::Punctuated,
@@ -20,32 +21,41 @@ impl Parse for ConfigOptFieldsAttr {
             // `name = value` attributes.
             input.parse::<Token![=]>()?; // skip '='
 
-            #[allow(clippy::match_wild_err_arm)]
-            match input.parse::<Expr>() {
-                Ok(expr) => {
-                    if name_str == "hidden" {
-                        Ok(ConfigOptFieldsAttr::Hidden(expr))
-                    } else {
-                        panic!("`configopt_fields` unrecognized `{} = ...`", name_str);
-                    }
-                }
-                Err(_) => panic!(
-                    "`configopt_field` expected `expression` after `{} = ...`",
-                    name_str
-                ),
+            let expr = input.parse::<Expr>().map_err(|e| {
+                syn::Error::new(
+                    e.span(),
+                    format!(
+                        "`configopt_fields` expected `expression` after `{} = ...`",
+                        name_str
+                    ),
+                )
+            })?;
+            if name_str == "hidden" {
+                Ok(ConfigOptFieldsAttr::Hidden(expr))
+            } else {
+                Err(syn::Error::new(
+                    name.span(),
+                    format!("`configopt_fields` unrecognized `{} = ...`", name_str),
+                ))
             }
         } else if input.peek(syn::token::Paren) {
             // `name(...)` attributes.
-            panic!(
-                "`configopt_fields` does not have attribute `{}(...)`",
-                name_str
-            )
+            Err(syn::Error::new(
+                name.span(),
+                format!(
+                    "`configopt_fields` does not have attribute `{}(...)`",
+                    name_str
+                ),
+            ))
         } else {
             // Attributes represented with a sole identifier.
-            panic!(
-                "`configopt_fields` unrecognized sole identifier attribute {}",
-                name_str
-            )
+            Err(syn::Error::new(
+                name.span(),
+                format!(
+                    "`configopt_fields` unrecognized sole identifier attribute {}",
+                    name_str
+                ),
+            ))
         }
     }
 }
@@ -55,7 +65,7 @@ pub fn parse(token_stream: TokenStream) -> Vec<ConfigOptFieldsAttr> {
         <Punctuated<ConfigOptFieldsAttr, Token![,]>>::parse_terminated,
         token_stream,
     )
-    .expect("`configopt_fields` failed to parse attrs")
+    .unwrap_or_else(|e| abort!(e))
     .into_iter()
     .collect()
 }
@@ -1,14 +1,41 @@
+use super::{MergePolicy, ReloadPolicy};
+use proc_macro_error::abort;
+use quote::quote;
 use syn::{
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
-    Attribute, Expr, Ident, Token,
+    spanned::Spanned,
+    Attribute, Expr, Ident, LitStr, Token, Type,
 };
 
 #[derive(PartialEq)]
 #[allow(clippy::large_enum_variant)]
 pub enum ConfigOptAttr {
+    Alias(String),
+    CliOnly,
+    DecodeEnv,
+    Decrypt(Option<syn::Path>),
+    Deprecated(String),
+    EmptyIsUnset,
+    FileOnly,
+    From(Type),
+    Id,
+    Leaf,
+    Map,
+    Max(String),
+    Merge(MergePolicy),
+    Min(String),
+    Negatable,
+    NoInterpolate,
     NoWrap,
+    Pattern(String),
+    Prefix(String),
+    Reload(ReloadPolicy),
+    RequiredIf(String),
+    Secret,
+    Section,
     ToOsString(Expr),
+    TryFrom(Type),
 }
 
 impl Parse for ConfigOptAttr {
@@ -20,28 +47,99 @@ impl Parse for ConfigOptAttr {
             // `name = value` attributes.
             input.parse::<Token![=]>()?; // skip '='
 
-            #[allow(clippy::match_wild_err_arm)]
-            match input.parse::<Expr>() {
-                Ok(expr) => {
-                    if name_str == "to_os_string" {
-                        Ok(ConfigOptAttr::ToOsString(expr))
-                    } else {
-                        panic!(
-                            "`configopt` unrecognized `name = value` attribute {}",
-                            name_str
-                        );
+            if input.peek(LitStr) {
+                let lit: LitStr = input.parse()?;
+                let lit_str = lit.value();
+                return Ok(match name_str.as_ref() {
+                    "alias" => ConfigOptAttr::Alias(lit_str),
+                    "decrypt_with" => ConfigOptAttr::Decrypt(Some(lit.parse()?)),
+                    "deprecated" => ConfigOptAttr::Deprecated(lit_str),
+                    "from" => ConfigOptAttr::From(lit.parse()?),
+                    "try_from" => ConfigOptAttr::TryFrom(lit.parse()?),
+                    "matches" | "pattern" => ConfigOptAttr::Pattern(lit_str),
+                    "prefix" => ConfigOptAttr::Prefix(lit_str),
+                    "required_if" => ConfigOptAttr::RequiredIf(lit_str),
+                    "merge" => ConfigOptAttr::Merge(
+                        lit_str
+                            .parse()
+                            .map_err(|e: String| syn::Error::new(lit.span(), e))?,
+                    ),
+                    "reload" => ConfigOptAttr::Reload(
+                        lit_str
+                            .parse()
+                            .map_err(|e: String| syn::Error::new(lit.span(), e))?,
+                    ),
+                    s => {
+                        return Err(syn::Error::new(
+                            name.span(),
+                            format!(
+                                "`configopt` unrecognized `name = \"string literal\"` attribute {}",
+                                s
+                            ),
+                        ))
                     }
+                });
+            }
+
+            let expr = input.parse::<Expr>().map_err(|e| {
+                syn::Error::new(
+                    e.span(),
+                    "`configopt` expected `string literal` or `expression` after `=`",
+                )
+            })?;
+            if name_str == "to_os_string" {
+                Ok(ConfigOptAttr::ToOsString(expr))
+            } else if name_str == "min" || name_str == "max" {
+                // Bounds are exported as JSON numbers so only number literals are accepted
+                let number = quote! {#expr}.to_string().replace(' ', "");
+                if number.parse::<f64>().is_err() {
+                    return Err(syn::Error::new(
+                        expr.span(),
+                        format!("`configopt({})` expected a number literal", name_str),
+                    ));
                 }
-                Err(_) => panic!("`configopt` parsing `structopt` expected `expression` after `=`"),
+                Ok(if name_str == "min" {
+                    ConfigOptAttr::Min(number)
+                } else {
+                    ConfigOptAttr::Max(number)
+                })
+            } else {
+                Err(syn::Error::new(
+                    name.span(),
+                    format!(
+                        "`configopt` unrecognized `name = value` attribute {}",
+                        name_str
+                    ),
+                ))
             }
         } else if input.peek(syn::token::Paren) {
             // `name(...)` attributes.
-            panic!("`configopt` does not have any `name(...)` attributes")
+            Err(syn::Error::new(
+                name.span(),
+                "`configopt` does not have any `name(...)` attributes",
+            ))
         } else {
             // Attributes represented with a sole identifier.
             Ok(match name_str.as_ref() {
+                "cli_only" => ConfigOptAttr::CliOnly,
+                "decode_env" => ConfigOptAttr::DecodeEnv,
+                "decrypt" => ConfigOptAttr::Decrypt(None),
+                "empty_is_unset" => ConfigOptAttr::EmptyIsUnset,
+                "file_only" => ConfigOptAttr::FileOnly,
+                "id" => ConfigOptAttr::Id,
+                "leaf" => ConfigOptAttr::Leaf,
+                "map" => ConfigOptAttr::Map,
+                "negatable" => ConfigOptAttr::Negatable,
+                "no_interpolate" => ConfigOptAttr::NoInterpolate,
                 "nowrap" => ConfigOptAttr::NoWrap,
-                s => panic!("`configopt` unrecognized sole identifier attribute {}", s),
+                "secret" => ConfigOptAttr::Secret,
+                "section" => ConfigOptAttr::Section,
+                s => {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        format!("`configopt` unrecognized sole identifier attribute {}", s),
+                    ))
+                }
             })
         }
     }
@@ -53,7 +151,7 @@ pub fn parse_attrs(attrs: &[Attribute]) -> Vec<ConfigOptAttr> {
         .filter(|attr| attr.path.is_ident("configopt"))
         .flat_map(|attr| {
             attr.parse_args_with(Punctuated::<ConfigOptAttr, Token![,]>::parse_terminated)
-                .expect("`configopt` failed to parse `configopt` attributes")
+                .unwrap_or_else(|e| abort!(e))
         })
         .collect()
 }
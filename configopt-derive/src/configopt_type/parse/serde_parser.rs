@@ -1,4 +1,6 @@
+use super::CasingStyle;
 use proc_macro2::TokenStream;
+use proc_macro_error::abort;
 use quote::quote;
 use syn::{
     parenthesized,
@@ -10,6 +12,11 @@ use syn::{
 #[derive(PartialEq)]
 pub enum SerdeAttr {
     Flatten,
+    Skip,
+    /// `with`, `serialize_with`, or `deserialize_with`
+    With,
+    Rename(String),
+    RenameAll(CasingStyle),
     // We only care about some of the serde attributes
     Unknown,
 }
@@ -24,11 +31,30 @@ impl Parse for SerdeAttr {
             input.parse::<Token![=]>()?; // skip '='
 
             if input.peek(LitStr) {
-                input.parse::<LitStr>()?;
-            } else if let Err(e) = input.parse::<Expr>() {
-                panic!("`configopt` parsing `serde` expected `string literal` or `expression` after `=`, err: {}", e)
+                let lit: LitStr = input.parse()?;
+                match name_str.as_ref() {
+                    "rename" => return Ok(SerdeAttr::Rename(lit.value())),
+                    "rename_all" => {
+                        return Ok(SerdeAttr::RenameAll(
+                            lit.value()
+                                .parse()
+                                .map_err(|e: String| syn::Error::new(lit.span(), e))?,
+                        ))
+                    }
+                    _ => {}
+                }
+            } else {
+                input.parse::<Expr>().map_err(|e| {
+                    syn::Error::new(
+                        e.span(),
+                        "`configopt` parsing `serde` expected `string literal` or `expression` after `=`",
+                    )
+                })?;
             }
-            Ok(SerdeAttr::Unknown)
+            Ok(match name_str.as_ref() {
+                "with" | "serialize_with" | "deserialize_with" => SerdeAttr::With,
+                _ => SerdeAttr::Unknown,
+            })
         } else if input.peek(syn::token::Paren) {
             // `name(...)` attributes.
             let nested;
@@ -40,6 +66,7 @@ impl Parse for SerdeAttr {
             // Attributes represented with a sole identifier.
             Ok(match name_str.as_ref() {
                 "flatten" => SerdeAttr::Flatten,
+                "skip" => SerdeAttr::Skip,
                 _ => SerdeAttr::Unknown,
             })
         }
@@ -52,10 +79,26 @@ pub fn parse_attrs(attrs: &[Attribute]) -> Vec<SerdeAttr> {
         .filter(|attr| attr.path.is_ident("serde"))
         .flat_map(|attr| {
             attr.parse_args_with(Punctuated::<SerdeAttr, Token![,]>::parse_terminated)
-                .expect("`configopt` failed to parse `serde` attributes")
+                .unwrap_or_else(|e| abort!(e))
         })
         .collect()
 }
 
+/// The name given with `serde(rename = "..")`
+pub fn rename(attrs: &[Attribute]) -> Option<String> {
+    parse_attrs(attrs).into_iter().find_map(|a| match a {
+        SerdeAttr::Rename(name) => Some(name),
+        _ => None,
+    })
+}
+
+/// The casing style given with `serde(rename_all = "..")`
+pub fn rename_all(attrs: &[Attribute]) -> Option<CasingStyle> {
+    parse_attrs(attrs).into_iter().find_map(|a| match a {
+        SerdeAttr::RenameAll(style) => Some(style),
+        _ => None,
+    })
+}
+
 const SERDE_FIELDS_TO_TRIM: &[&str] = &["default"];
 attribute_trimmer!("serde", SERDE_FIELDS_TO_TRIM);
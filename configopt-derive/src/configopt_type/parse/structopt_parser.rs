@@ -1,9 +1,11 @@
 use super::CasingStyle;
-use proc_macro2::TokenStream;
+use proc_macro2::{TokenStream, TokenTree};
+use proc_macro_error::abort;
 use quote::quote;
 use syn::{
     parenthesized,
     parse::{Parse, ParseStream},
+    parse_quote,
     punctuated::Punctuated,
     Attribute, Expr, GenericArgument, Ident, LitStr, Path, PathArguments,
     PathArguments::AngleBracketed,
@@ -15,13 +17,24 @@ pub enum StructOptAttr {
     Short,
     Long,
     RenameAll(CasingStyle),
+    RenameAllEnv(CasingStyle),
     NameLitStr(String),
+    // `env` with an explicit name or derived from the field name with `rename_all_env`
+    Env(Option<String>),
     Flatten,
     Subcommand,
+    FromOccurrences,
+    // `parse(try_from_str = configopt::parsers::<parser>)`
+    HumanParser(&'static str),
+    DefaultValue,
+    Skip(Option<Expr>),
     // We only care about some of the structopt attributes
     Unknown,
 }
 
+/// The parsers in `configopt::parsers` which have a matching `serde` module
+pub const HUMAN_PARSERS: &[&str] = &["duration", "byte_size", "socket_addr"];
+
 impl Parse for StructOptAttr {
     fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
         let name: Ident = input.parse()?;
@@ -39,22 +52,43 @@ impl Parse for StructOptAttr {
                     "long" => Ok(StructOptAttr::Long),
                     "short" => Ok(StructOptAttr::Short),
                     "rename_all" => Ok(StructOptAttr::RenameAll(
-                        lit_str.parse().expect("infallible parse"),
+                        lit_str
+                            .parse()
+                            .map_err(|e: String| syn::Error::new(lit.span(), e))?,
+                    )),
+                    "rename_all_env" => Ok(StructOptAttr::RenameAllEnv(
+                        lit_str
+                            .parse()
+                            .map_err(|e: String| syn::Error::new(lit.span(), e))?,
                     )),
                     "name" => Ok(StructOptAttr::NameLitStr(lit_str)),
+                    "env" => Ok(StructOptAttr::Env(Some(lit_str))),
+                    "skip" => Ok(StructOptAttr::Skip(Some(parse_quote!(#lit)))),
+                    "default_value" => Ok(StructOptAttr::DefaultValue),
                     _ => Ok(StructOptAttr::Unknown),
                 }
             } else {
-                match input.parse::<Expr>() {
-                    Ok(_) => {
-                        if name_str == "name" {
-                            // TODO
-                            panic!("`configopt` parsing `structopt` only supports string literal for argument name")
-                        }
-                    }
-                    Err(e) => {
-                        panic!("`configopt` parsing `structopt` expected `string literal` or `expression` after `=`, err: {}", e)
-                    }
+                let expr = input.parse::<Expr>().map_err(|e| {
+                    syn::Error::new(
+                        e.span(),
+                        "`configopt` parsing `structopt` expected `string literal` or `expression` after `=`",
+                    )
+                })?;
+                if name_str == "name" {
+                    // TODO
+                    return Err(syn::Error::new_spanned(
+                        expr,
+                        "`configopt` parsing `structopt` only supports string literal for argument name",
+                    ));
+                }
+                if name_str == "skip" {
+                    return Ok(StructOptAttr::Skip(Some(expr)));
+                }
+                if name_str == "env" {
+                    return Err(syn::Error::new_spanned(
+                        expr,
+                        "`configopt` parsing `structopt` only supports string literal for environment variable name",
+                    ));
                 }
                 Ok(StructOptAttr::Unknown)
             }
@@ -63,7 +97,33 @@ impl Parse for StructOptAttr {
             let nested;
             // Even though we do not do anything here we still need to consume the tokens from the ParseStream
             parenthesized!(nested in input);
-            nested.parse::<TokenStream>()?;
+            let nested = nested.parse::<TokenStream>()?;
+            if name_str == "parse" {
+                let mut tokens = nested.into_iter();
+                if let Some(TokenTree::Ident(kind)) = tokens.next() {
+                    if kind == "from_occurrences" {
+                        if let Some(function) = tokens.next() {
+                            return Err(syn::Error::new(
+                                function.span(),
+                                "`configopt` only supports `parse(from_occurrences)` without a custom function",
+                            ));
+                        }
+                        return Ok(StructOptAttr::FromOccurrences);
+                    }
+                    if kind == "try_from_str" {
+                        let path = tokens
+                            .map(|t| t.to_string())
+                            .collect::<String>()
+                            .replace(' ', "");
+                        let parser = HUMAN_PARSERS
+                            .iter()
+                            .find(|p| path.ends_with(&format!("parsers::{}", p)));
+                        if let Some(parser) = parser {
+                            return Ok(StructOptAttr::HumanParser(parser));
+                        }
+                    }
+                }
+            }
             Ok(StructOptAttr::Unknown)
         } else {
             // Attributes represented with a sole identifier.
@@ -72,6 +132,8 @@ impl Parse for StructOptAttr {
                 "short" => StructOptAttr::Short,
                 "flatten" => StructOptAttr::Flatten,
                 "subcommand" => StructOptAttr::Subcommand,
+                "skip" => StructOptAttr::Skip(None),
+                "env" => StructOptAttr::Env(None),
                 _ => StructOptAttr::Unknown,
             })
         }
@@ -84,7 +146,7 @@ pub fn parse_attrs(attrs: &[Attribute]) -> Vec<StructOptAttr> {
         .filter(|attr| attr.path.is_ident("structopt"))
         .flat_map(|attr| {
             attr.parse_args_with(Punctuated::<StructOptAttr, Token![,]>::parse_terminated)
-                .expect("`configopt` failed to parse `structopt` attributes")
+                .unwrap_or_else(|e| abort!(e))
         })
         .collect()
 }
@@ -109,6 +171,35 @@ const STRUCTOPT_FIELDS_TO_TRIM: &[&str] = &[
 ];
 attribute_trimmer!("structopt", STRUCTOPT_FIELDS_TO_TRIM);
 
+/// Remove the `names` items (eg `parse(...)`) from a `structopt` attribute so the `configopt` type
+/// can replace them with its own
+pub fn remove_items(attr: &mut Attribute, names: &[&str]) {
+    if !attr.path.is_ident("structopt") {
+        return;
+    }
+    let group = match attr.tokens.clone().into_iter().next() {
+        Some(TokenTree::Group(group)) => group,
+        _ => return,
+    };
+    // Split on the top level commas, nested commas are contained in a `Group`
+    let mut items = vec![Vec::new()];
+    for token in group.stream() {
+        match &token {
+            TokenTree::Punct(p) if p.as_char() == ',' => items.push(Vec::new()),
+            _ => items.last_mut().expect("at least one item").push(token),
+        }
+    }
+    let items = items
+        .into_iter()
+        .filter(|item| match item.first() {
+            Some(TokenTree::Ident(name)) => !names.iter().any(|n| name == n),
+            Some(_) => true,
+            None => false,
+        })
+        .map(|item| item.into_iter().collect::<TokenStream>());
+    attr.tokens = quote! {(#(#items),*)};
+}
+
 pub fn rename_all(attrs: &[Attribute]) -> Option<CasingStyle> {
     parse_attrs(attrs).into_iter().find_map(|a| match a {
         StructOptAttr::RenameAll(style) => Some(style),
@@ -116,6 +207,13 @@ pub fn rename_all(attrs: &[Attribute]) -> Option<CasingStyle> {
     })
 }
 
+pub fn rename_all_env(attrs: &[Attribute]) -> Option<CasingStyle> {
+    parse_attrs(attrs).into_iter().find_map(|a| match a {
+        StructOptAttr::RenameAllEnv(style) => Some(style),
+        _ => None,
+    })
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum StructOptTy {
     Bool,
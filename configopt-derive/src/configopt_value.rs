@@ -0,0 +1,211 @@
+use crate::configopt_type::{generate, parse::CasingStyle};
+use proc_macro2::TokenStream;
+use proc_macro_error::abort;
+use proc_macro_roids::DeriveInputExt;
+use quote::quote;
+use syn::{parse_quote, Data, DeriveInput, Fields, Ident, Lit, NestedMeta};
+
+pub fn derive(ast: DeriveInput) -> TokenStream {
+    let ident = &ast.ident;
+    // Match the names `structopt` gives subcommands and arguments by default
+    let rename = ast
+        .tag_parameter(&parse_quote!(configopt), &parse_quote!(rename_all))
+        .map(|meta| match meta {
+            NestedMeta::Lit(Lit::Str(lit)) => lit
+                .value()
+                .parse::<CasingStyle>()
+                .unwrap_or_else(|e| abort!(lit, e)),
+            meta => abort!(meta, "`configopt(rename_all)` expected a string literal"),
+        })
+        .unwrap_or(CasingStyle::Kebab);
+    let tag = ast
+        .tag_parameter(&parse_quote!(configopt), &parse_quote!(tag))
+        .map(|meta| match meta {
+            NestedMeta::Lit(Lit::Str(lit)) => lit.value(),
+            meta => abort!(meta, "`configopt(tag)` expected a string literal"),
+        });
+    let external = ast.contains_tag(&parse_quote!(configopt), &parse_quote!(external));
+    let representation = match (tag, external) {
+        (Some(_), true) => abort!(
+            ident,
+            "`configopt(tag)` and `configopt(external)` cannot be used together"
+        ),
+        (Some(tag), false) => quote! {::configopt::Representation::Internal(#tag)},
+        (None, true) => quote! {::configopt::Representation::External},
+        (None, false) => quote! {::configopt::Representation::Internal("type")},
+    };
+    let (units, variants) = match &ast.data {
+        Data::Enum(data) => {
+            let mut units = Vec::new();
+            let mut variants = Vec::new();
+            for variant in &data.variants {
+                match &variant.fields {
+                    Fields::Unit => units.push(&variant.ident),
+                    Fields::Named(fields) => variants.push((
+                        &variant.ident,
+                        fields
+                            .named
+                            .iter()
+                            .map(|f| f.ident.as_ref().expect("named field"))
+                            .collect::<Vec<_>>(),
+                    )),
+                    Fields::Unnamed(_) => abort!(
+                        variant,
+                        "`ConfigOptValue` can only be derived for enums with unit variants or variants with named fields"
+                    ),
+                }
+            }
+            (units, variants)
+        }
+        _ => abort!(ident, "`ConfigOptValue` can only be derived for enums"),
+    };
+    let unit_names = units
+        .iter()
+        .map(|v| rename.rename(v.to_string()))
+        .collect::<Vec<_>>();
+    let variant_names = variants
+        .iter()
+        .map(|(v, _)| rename.rename(v.to_string()))
+        .collect::<Vec<_>>();
+    let names = unit_names
+        .iter()
+        .chain(variant_names.iter())
+        .collect::<Vec<_>>();
+    let expected = names
+        .iter()
+        .map(|n| format!("`{}`", n))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let lints = generate::lints();
+    let variant_idents = variants.iter().map(|(v, _)| v).collect::<Vec<_>>();
+    let field_idents = variants.iter().map(|(_, f)| f).collect::<Vec<_>>();
+    let field_keys = variants
+        .iter()
+        .map(|(_, fields)| fields.iter().map(|f| field_key(f)).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    // Handle the case of an empty enum
+    let display = if units.is_empty() && variants.is_empty() {
+        quote! {match *self {}}
+    } else {
+        quote! {
+            match self {
+                #(Self::#units => f.write_str(#unit_names),)*
+                #(Self::#variant_idents { .. } => match toml::Value::try_from(self) {
+                    Ok(value) => f.write_str(&::configopt::inline_toml(&value)),
+                    Err(_) => Err(::std::fmt::Error),
+                },)*
+            }
+        }
+    };
+    let serialize = if units.is_empty() && variants.is_empty() {
+        quote! {match *self {}}
+    } else {
+        quote! {
+            match self {
+                #(Self::#units => serializer.serialize_str(#unit_names),)*
+                #(Self::#variant_idents { #(#field_idents),* } => {
+                    let mut fields = toml::value::Table::new();
+                    #(
+                        ::configopt::insert_variant_field(&mut fields, #field_keys, #field_idents)
+                            .map_err(<S::Error as serde::ser::Error>::custom)?;
+                    )*
+                    let value = ::configopt::variant_value(
+                        <Self as ::configopt::ConfigOptValue>::REPRESENTATION,
+                        #variant_names,
+                        fields,
+                    );
+                    serde::Serialize::serialize(&value, serializer)
+                })*
+            }
+        }
+    };
+    // Variants with fields are written on the CLI as inline TOML
+    let from_str_inline = if variants.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            if let Ok(value) = ::configopt::parse_inline_variant(s) {
+                if value.is_table() {
+                    return value.try_into().map_err(|e| e.to_string());
+                }
+            }
+        }
+    };
+    quote! {
+        #lints
+        impl ::configopt::ConfigOptValue for #ident {
+            const VARIANTS: &'static [&'static str] = &[#(#names),*];
+            const REPRESENTATION: ::configopt::Representation = #representation;
+        }
+
+        #lints
+        impl ::std::str::FromStr for #ident {
+            type Err = String;
+
+            fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+                #(
+                    if s.eq_ignore_ascii_case(#unit_names) {
+                        return Ok(Self::#units);
+                    }
+                )*
+                #from_str_inline
+                Err(format!("invalid value `{}`, expected one of {}", s, #expected))
+            }
+        }
+
+        #lints
+        impl ::std::fmt::Display for #ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                #display
+            }
+        }
+
+        #lints
+        impl serde::Serialize for #ident {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+                #serialize
+            }
+        }
+
+        #lints
+        impl<'de> serde::Deserialize<'de> for #ident {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+                let value = <toml::Value as serde::Deserialize>::deserialize(deserializer)?;
+                let (name, mut fields) = ::configopt::split_variant(
+                    <Self as ::configopt::ConfigOptValue>::REPRESENTATION,
+                    value,
+                )
+                .map_err(<D::Error as serde::de::Error>::custom)?;
+                #(
+                    if name.eq_ignore_ascii_case(#unit_names) && fields.is_empty() {
+                        return Ok(Self::#units);
+                    }
+                )*
+                #(
+                    if name.eq_ignore_ascii_case(#variant_names) {
+                        let value = Self::#variant_idents {
+                            #(
+                                #field_idents: ::configopt::take_variant_field(&mut fields, #field_keys)
+                                    .map_err(<D::Error as serde::de::Error>::custom)?,
+                            )*
+                        };
+                        if let Some(key) = fields.keys().next() {
+                            return Err(serde::de::Error::custom(format!("unknown field `{}`", key)));
+                        }
+                        return Ok(value);
+                    }
+                )*
+                Err(serde::de::Error::custom(format!(
+                    "invalid value `{}`, expected one of {}",
+                    name, #expected
+                )))
+            }
+        }
+    }
+}
+
+// The key of a field of a variant is the name of the field like the keys of struct fields
+fn field_key(field: &Ident) -> String {
+    let key = field.to_string();
+    String::from(key.trim_start_matches("r#"))
+}
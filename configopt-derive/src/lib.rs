@@ -1,13 +1,17 @@
 extern crate proc_macro;
 
+mod configopt_section;
 mod configopt_type;
+mod configopt_value;
 
 use configopt_type::generate;
 use configopt_type::ConfigOptConstruct;
+use proc_macro_error::proc_macro_error;
 use proc_macro_roids::FieldsNamedAppend;
 use quote::quote;
 use syn::{parse_macro_input, parse_quote, DeriveInput};
 
+#[proc_macro_error]
 #[proc_macro_derive(ConfigOpt, attributes(configopt))]
 pub fn configopt_derive(ast: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let ast = parse_macro_input!(ast as DeriveInput);
@@ -26,6 +30,32 @@ pub fn configopt_derive(ast: proc_macro::TokenStream) -> proc_macro::TokenStream
     proc_macro::TokenStream::from(expanded)
 }
 
+/// Derive the partial type of a section which is only read from config files
+///
+/// Unlike `ConfigOpt` there is no `structopt` analysis or argument reconstruction. Include the
+/// section in a `ConfigOpt` type with `#[structopt(skip)]` and `#[configopt(section)]`.
+#[proc_macro_error]
+#[proc_macro_derive(ConfigOptSection, attributes(configopt))]
+pub fn configopt_section_derive(ast: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = parse_macro_input!(ast as DeriveInput);
+    proc_macro::TokenStream::from(configopt_section::derive(ast))
+}
+
+/// Implement `FromStr`, `Display`, `Serialize`, and `Deserialize` for an enum used as the value of
+/// a field
+///
+/// Variants are named in kebab case like `structopt` names subcommands unless
+/// `#[configopt(rename_all = "...")]` is set. Variants with named fields are written as
+/// `{ type = "<variant>", .. }` unless `#[configopt(tag = "...")]` selects another key or
+/// `#[configopt(external)]` nests the fields under the name of the variant.
+#[proc_macro_error]
+#[proc_macro_derive(ConfigOptValue, attributes(configopt))]
+pub fn configopt_value_derive(ast: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = parse_macro_input!(ast as DeriveInput);
+    proc_macro::TokenStream::from(configopt_value::derive(ast))
+}
+
+#[proc_macro_error]
 #[proc_macro_attribute]
 pub fn configopt_fields(
     attr: proc_macro::TokenStream,
@@ -48,11 +78,18 @@ pub fn configopt_fields(
         /// Paths to config files to read
         #[structopt(long = "config-files", hidden = #hidden)]
         #[serde(skip)]
+        #[configopt(cli_only)]
         config_files: Vec<::std::path::PathBuf>,
         /// Generate a TOML config
         #[structopt(long = "generate-config", hidden = #hidden)]
         #[serde(skip)]
+        #[configopt(cli_only)]
         generate_config: bool,
+        /// Print the value of every config field and the source which set it
+        #[structopt(long = "explain-config", hidden = #hidden)]
+        #[serde(skip)]
+        #[configopt(cli_only)]
+        explain_config: bool,
     });
     ast.append_named(additional_fields);
 
@@ -0,0 +1,91 @@
+//! Inspect and patch the config of a running app over HTTP
+//!
+//! `cargo run --features examples,admin --example admin`
+//!
+//! The requests are handled in process. Serve `AdminService` with `hyper::Server` to reach it over
+//! the network.
+use configopt::{AdminService, ConfigOpt, LoadTrace, Reloadable};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use std::convert::TryFrom;
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Clone, Debug, PartialEq))]
+struct App {
+    #[structopt(long)]
+    log_level: String,
+    #[structopt(long)]
+    #[configopt(reload = "restart")]
+    port: u16,
+    #[structopt(long)]
+    #[configopt(secret)]
+    token: String,
+}
+
+fn request(method: Method, uri: &str, body: &str) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .body(Body::from(String::from(body)))
+        .unwrap()
+}
+
+async fn body(response: Response<Body>) -> String {
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let args = &[
+        "app",
+        "--log-level",
+        "info",
+        "--port",
+        "80",
+        "--token",
+        "t0k3n",
+    ];
+    let (c, trace) = LoadTrace::capture(|| ConfigOptApp::from_iter_safe(args).unwrap());
+    let live = Reloadable::new(App::try_from(c.clone()).unwrap());
+    let admin = AdminService::builder(Reloadable::new(c), trace)
+        .with_patch(ConfigOptApp::diff, {
+            let live = live.clone();
+            move |c| {
+                live.replace(App::try_from(c.clone()).map_err(|e| e.to_string())?);
+                Ok(())
+            }
+        })
+        .build();
+
+    // Every field is served with its source and secrets are redacted
+    let response = admin.handle(request(Method::GET, "/config", "")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let config = body(response).await;
+    assert!(config.contains("\"log_level\""));
+    assert!(!config.contains("t0k3n"));
+    println!("{}", config);
+
+    // Dynamic fields are patched and handed to the app
+    let patch = request(Method::PATCH, "/config", r#"{"log_level": "debug"}"#);
+    let response = admin.handle(patch).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(live.get().log_level, "debug");
+    let response = admin
+        .handle(request(Method::GET, "/config/log_level", ""))
+        .await;
+    let field = body(response).await;
+    assert!(field.contains("\"debug\""));
+    assert!(field.contains("\"admin\""));
+
+    // Fields which need a restart are rejected
+    let patch = request(Method::PATCH, "/config", r#"{"port": 8080}"#);
+    let response = admin.handle(patch).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(live.get().port, 80);
+}
+
+#[test]
+fn acceptance() {
+    main()
+}
@@ -0,0 +1,88 @@
+//! Reload a config when its file changes and swap it in for readers
+//!
+//! `cargo run --features examples --example hot_reload`
+//!
+//! The files and the clock are in memory so the example runs instantly and deterministically. Use
+//! `FileWatcher::new` to watch real files.
+use configopt::{
+    from_toml_file_in, ConfigOpt, FileSystem, FileWatcher, ManualClock, MemoryFileSystem,
+    Reloadable,
+};
+use std::{
+    convert::TryFrom,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug))]
+struct App {
+    #[structopt(long)]
+    rate_limit: u32,
+    #[structopt(long)]
+    banner: Option<String>,
+}
+
+fn load(fs: &dyn FileSystem, path: &Path) -> Result<App, String> {
+    let c: ConfigOptApp =
+        from_toml_file_in(fs, path, &[], &mut Vec::new()).map_err(|e| e.to_string())?;
+    App::try_from(c).map_err(|e| e.to_string())
+}
+
+fn main() {
+    let clock = Arc::new(ManualClock::default());
+    let fs = Arc::new(MemoryFileSystem::new(clock.clone()));
+    let path = PathBuf::from("/etc/app/config.toml");
+    fs.write(&path, "rate_limit = 10").unwrap();
+
+    let config = Reloadable::new(load(&*fs, &path).unwrap());
+    let reader = config.clone();
+    assert_eq!(reader.get().rate_limit, 10);
+
+    // Each change is parsed and validated before it replaces the current config
+    let mut changes = vec![
+        "rate_limit = 30\nbanner = \"maintenance\"",
+        "rate_limit = \"not a number\"",
+        "rate_limit = 20",
+    ];
+    let mut errors = Vec::new();
+    let mut watcher = FileWatcher::with(&[&path], clock.clone(), fs.clone());
+    clock.advance(Duration::from_secs(1));
+    fs.write(&path, changes.pop().unwrap()).unwrap();
+    watcher.watch(Duration::from_secs(1), |changed| {
+        match load(&*fs, &changed[0]) {
+            Ok(app) => {
+                println!("reloaded {:?}", app);
+                config.replace(app);
+            }
+            // An invalid file keeps the last good config
+            Err(e) => {
+                println!("keeping the current config: {}", e);
+                errors.push(e);
+            }
+        }
+        match changes.pop() {
+            Some(next) => {
+                fs.write(&path, next).unwrap();
+                true
+            }
+            None => false,
+        }
+    });
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        *reader.get(),
+        App {
+            rate_limit: 30,
+            banner: Some(String::from("maintenance")),
+        }
+    );
+}
+
+#[test]
+fn acceptance() {
+    main()
+}
@@ -0,0 +1,91 @@
+//! Config files, environment variables, and the CLI layered on top of each other
+//!
+//! `cargo run --features examples --example precedence`
+//!
+//! The CLI takes precedence over the environment which takes precedence over config files. Later
+//! config files take precedence over earlier ones.
+use configopt::{configopt_fields, ConfigOpt};
+use std::{env, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct App {
+    /// The address to listen on
+    #[structopt(long, default_value = "127.0.0.1")]
+    host: String,
+    /// The port to listen on
+    #[structopt(long, env = "EXAMPLE_PRECEDENCE_PORT")]
+    port: u16,
+    /// The number of worker threads
+    #[structopt(long)]
+    workers: u32,
+}
+
+fn config_file(contents: &str) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", contents).unwrap();
+    file
+}
+
+fn parse(files: &[&NamedTempFile], args: &[&str]) -> configopt::Result<App> {
+    let mut iter = vec![String::from("app")];
+    for file in files {
+        iter.push(String::from("--config-files"));
+        iter.push(file.path().to_string_lossy().into_owned());
+    }
+    iter.extend(args.iter().map(|a| String::from(*a)));
+    App::try_from_iter_with_configopt(iter)
+}
+
+fn main() {
+    let base = config_file("host = \"0.0.0.0\"\nport = 80\nworkers = 4\n");
+    let local = config_file("workers = 8\n");
+    env::remove_var("EXAMPLE_PRECEDENCE_PORT");
+
+    // Config files fill in everything the CLI does not set
+    let app = parse(&[&base], &[]).unwrap();
+    assert_eq!(
+        app,
+        App {
+            host: String::from("0.0.0.0"),
+            port: 80,
+            workers: 4,
+            config_files: app.config_files.clone(),
+            generate_config: false,
+            explain_config: false,
+        }
+    );
+    println!("files: {:?}", app);
+
+    // A later file overrides an earlier file
+    let app = parse(&[&base, &local], &[]).unwrap();
+    assert_eq!(app.workers, 8);
+    assert_eq!(app.host, "0.0.0.0");
+
+    // The environment overrides the files
+    env::set_var("EXAMPLE_PRECEDENCE_PORT", "8080");
+    let app = parse(&[&base, &local], &[]).unwrap();
+    assert_eq!(app.port, 8080);
+    println!("files + env: {:?}", app);
+
+    // The CLI overrides everything
+    let app = parse(&[&base, &local], &["--port", "9090", "--workers", "2"]).unwrap();
+    assert_eq!((app.port, app.workers), (9090, 2));
+    println!("files + env + CLI: {:?}", app);
+    env::remove_var("EXAMPLE_PRECEDENCE_PORT");
+
+    // A default value only applies when no layer sets the field
+    let app = parse(&[&local], &["--port", "1"]).unwrap();
+    assert_eq!(app.host, "127.0.0.1");
+
+    // Required fields must be set by some layer
+    assert!(parse(&[&local], &[]).is_err());
+}
+
+#[test]
+fn acceptance() {
+    main()
+}
@@ -0,0 +1,55 @@
+//! Reload the log level of a running app whenever its config file changes
+//!
+//! `cargo run --features logging --example reload_logging`
+//!
+//! Then edit `level` in the `[logging]` table of `reload_logging.toml`.
+// The partial type of a flattened field must be in scope for the derive
+use configopt::{
+    ConfigOpt, ConfigOptLoggingConfig, FileWatcher, Logging, LoggingConfig, ShutdownToken,
+};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug), default_config_file("reload_logging.toml"))]
+struct App {
+    #[structopt(flatten)]
+    logging: LoggingConfig,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let app = App::from_args_with_configopt();
+    let logging = Logging::init(&app.logging)?;
+
+    let shutdown = ShutdownToken::new();
+    let watcher = FileWatcher::new(&ConfigOptApp::default_config_files()).spawn(
+        Duration::from_secs(1),
+        shutdown.clone(),
+        move |_| {
+            // Reapply the CLI and environment so they still take precedence over the file
+            let reloaded = App::try_from_args_with_configopt()
+                .map_err(|e| e.to_string())
+                .and_then(|app| logging.apply(&app.logging).map_err(|e| e.to_string()));
+            match reloaded {
+                Ok(true) => tracing::info!("reloaded the log level, restart for the rest"),
+                Ok(false) => tracing::info!("reloaded the log level"),
+                Err(e) => tracing::error!("keeping the current log level: {}", e),
+            }
+            true
+        },
+    );
+
+    let started = Instant::now();
+    while started.elapsed() < Duration::from_secs(60) {
+        tracing::debug!("debug is enabled");
+        tracing::info!("info is enabled");
+        thread::sleep(Duration::from_secs(2));
+    }
+    // Stop the watcher before exiting instead of leaking its thread
+    shutdown.cancel();
+    watcher.join().ok();
+    Ok(())
+}
@@ -0,0 +1,67 @@
+//! Hydrate a config from a remote store without blocking the async runtime
+//!
+//! `cargo run --features examples,async --example remote_source`
+//!
+//! The remote store is simulated in memory. Use `UrlSource` with the `http` feature to fetch a
+//! config over HTTP.
+use configopt::{AsyncSource, ConfigOpt, SourceFuture};
+use std::{convert::TryFrom, io};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug))]
+struct App {
+    #[structopt(long)]
+    region: String,
+    #[structopt(long)]
+    replicas: u32,
+}
+
+/// A key value store holding one config document per key
+struct Store {
+    key: &'static str,
+}
+
+impl AsyncSource for Store {
+    fn name(&self) -> String {
+        format!("store://{}", self.key)
+    }
+
+    fn read(&self) -> SourceFuture<'_> {
+        Box::pin(async move {
+            match self.key {
+                "app" => Ok(String::from("region = \"eu-west-1\"\nreplicas = 3\n")),
+                _ => Err(io::Error::new(io::ErrorKind::NotFound, "no such key")),
+            }
+        })
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    // The CLI still takes precedence over the remote config
+    let mut c = ConfigOptApp::from_iter_safe(&["app", "--replicas", "5"]).unwrap();
+    c.patch_from_source_async(&Store { key: "app" })
+        .await
+        .unwrap();
+    let app = App::try_from(c).unwrap();
+    assert_eq!(
+        app,
+        App {
+            region: String::from("eu-west-1"),
+            replicas: 5,
+        }
+    );
+    println!("{:?}", app);
+
+    // Errors name the source they came from
+    let e = ConfigOptApp::from_source_async(&Store { key: "missing" })
+        .await
+        .unwrap_err();
+    assert!(e.to_string().contains("store://missing"));
+}
+
+#[test]
+fn acceptance() {
+    main()
+}
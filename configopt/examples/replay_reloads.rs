@@ -0,0 +1,51 @@
+//! Replay the reloads stored in a reload journal against the config types of this binary
+//!
+//! `cargo run --features dev --example replay_reloads -- <journal dir>`
+use configopt::{configopt_fields, ConfigOpt, ReloadJournal};
+use structopt::StructOpt;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug, PartialEq))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+}
+
+#[derive(StructOpt)]
+struct Replay {
+    /// The directory of the reload journal
+    dir: std::path::PathBuf,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let replay = Replay::from_args();
+    let journal = ReloadJournal::open(replay.dir, usize::MAX)?;
+    let mut current = ConfigOptMyStruct::default();
+    for record in journal.records()? {
+        println!("reload {} at {}", record.sequence, record.timestamp);
+        let mut next = ConfigOptMyStruct::default();
+        match record.replay::<ConfigOptMyStruct>() {
+            Ok(documents) => {
+                for mut document in documents {
+                    next.take(&mut document);
+                }
+            }
+            Err(e) => {
+                println!("  no longer parses: {}", e);
+                continue;
+            }
+        }
+        let replayed = current.diff(&next);
+        if replayed == record.diff() {
+            println!("  changed: {:?}", record.changed);
+        } else {
+            println!("  recorded change: {:?}", record.changed);
+            println!("  replayed change: {:?}", replayed.changed());
+        }
+        current = next;
+    }
+    Ok(())
+}
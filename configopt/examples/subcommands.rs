@@ -0,0 +1,84 @@
+//! Subcommands selected and configured from the CLI or a config file
+//!
+//! `cargo run --features examples --example subcommands`
+use configopt::{ConfigFormat, ConfigOpt};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct App {
+    /// How much to log
+    #[structopt(long)]
+    level: Option<String>,
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+enum Command {
+    /// Serve requests
+    Serve(Serve),
+    /// Check the config and exit
+    Check,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct Serve {
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    tls: bool,
+}
+
+const CONFIG: &str = r#"
+level = "info"
+command = "serve"
+
+[serve]
+port = 8080
+"#;
+
+fn parse(args: &[&str]) -> configopt::Result<App> {
+    let mut iter = vec!["app"];
+    iter.extend_from_slice(args);
+    App::try_from_iter_with_files(iter, &[(ConfigFormat::Toml, CONFIG)])
+}
+
+fn main() {
+    // The config file selects and configures the subcommand
+    let app = parse(&[]).unwrap();
+    assert_eq!(
+        app,
+        App {
+            level: Some(String::from("info")),
+            command: Command::Serve(Serve {
+                port: 8080,
+                tls: false,
+            }),
+        }
+    );
+    println!("from the config file: {:?}", app);
+
+    // Arguments of the subcommand on the CLI are layered over the config file
+    let app = parse(&["serve", "--tls"]).unwrap();
+    assert_eq!(
+        app.command,
+        Command::Serve(Serve {
+            port: 8080,
+            tls: true,
+        })
+    );
+
+    // A different subcommand on the CLI takes precedence
+    let app = parse(&["--level", "debug", "check"]).unwrap();
+    assert_eq!(app.command, Command::Check);
+    assert_eq!(app.level.as_deref(), Some("debug"));
+    println!("from the CLI: {:?}", app);
+}
+
+#[test]
+fn acceptance() {
+    main()
+}
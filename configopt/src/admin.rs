@@ -0,0 +1,322 @@
+//! An HTTP handler exposing the current config of a running app
+//!
+//! [`AdminService`](struct.AdminService.html) is a `hyper` service (a `tower` service) answering:
+//!
+//! * `GET /config` with a JSON array of every set field, its value, and the layer which set it.
+//!   Secrets are redacted.
+//! * `GET /config/<dotted.path>` with a single field or `404 Not Found`.
+//! * `PATCH /config` with a JSON object of dotted paths and the values to set if patching is
+//!   enabled. A patch which changes a field marked `#[configopt(reload = "restart")]` is rejected
+//!   with `409 Conflict` so a patch either fully takes effect or does not happen.
+//!
+//! Serve it on its own listener (eg `127.0.0.1:9090`) which is not reachable by clients since it
+//! can change the config.
+
+use crate::{
+    effective_fields, set_field, ConfigDiff, ConfigOptType, FieldPath, LoadTrace, Merge,
+    Reloadable, TraceSource, Value,
+};
+use hyper::{
+    header::{HeaderValue, ALLOW, CONTENT_TYPE},
+    service::Service,
+    Body, Method, Request, Response, StatusCode,
+};
+use serde::de::DeserializeOwned;
+use std::{
+    convert::Infallible,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+/// The path the config is served at
+pub const CONFIG_PATH: &str = "/config";
+
+type Apply<P> = dyn Fn(&P) -> Result<(), String> + Send + Sync;
+
+struct Patch<P> {
+    diff: fn(&P, &P) -> ConfigDiff,
+    apply: Box<Apply<P>>,
+}
+
+struct Inner<P> {
+    current: Reloadable<P>,
+    trace: Mutex<LoadTrace>,
+    patch: Option<Patch<P>>,
+    // Patches are applied one at a time so two patches cannot both start from the same config
+    patching: Mutex<()>,
+}
+
+/// Serves the current config of an app over HTTP
+///
+/// Create one from the `configopt` type of the config and the trace captured while loading it
+/// then serve it with `hyper`:
+///
+/// ```ignore
+/// let admin = AdminService::builder(current, trace)
+///     .with_patch(ConfigOptApp::diff, |c| {
+///         let app = App::try_from(c.clone()).map_err(|e| e.to_string())?;
+///         live.replace(app);
+///         Ok(())
+///     })
+///     .build();
+/// let make_service = make_service_fn(move |_| {
+///     let admin = admin.clone();
+///     async move { Ok::<_, Infallible>(admin) }
+/// });
+/// Server::bind(&admin_addr).serve(make_service).await?;
+/// ```
+pub struct AdminService<P> {
+    inner: Arc<Inner<P>>,
+}
+
+impl<P> Clone for AdminService<P> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+fn response(status: StatusCode, body: String) -> Response<Body> {
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+fn error(status: StatusCode, message: impl Into<String>) -> Response<Body> {
+    let body = serde_json::json!({ "error": message.into() });
+    response(status, body.to_string())
+}
+
+/// Convert a JSON value of a patch to a `Value`
+fn from_json(value: serde_json::Value) -> Result<Value, String> {
+    Ok(match value {
+        serde_json::Value::Null => return Err(String::from("a value cannot be null")),
+        serde_json::Value::Bool(b) => Value::Bool(b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Float(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Value::String(s),
+        serde_json::Value::Array(items) => {
+            Value::List(items.into_iter().map(from_json).collect::<Result<_, _>>()?)
+        }
+        serde_json::Value::Object(object) => Value::Table(
+            object
+                .into_iter()
+                .map(|(k, v)| Ok((k, from_json(v)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+    })
+}
+
+/// Configures an [`AdminService`](struct.AdminService.html) before it is built
+///
+/// Create it with [`AdminService::builder`](struct.AdminService.html#method.builder).
+pub struct AdminServiceBuilder<P> {
+    inner: Inner<P>,
+}
+
+impl<P> AdminServiceBuilder<P> {
+    /// Accept `PATCH /config` requests
+    ///
+    /// `diff` (eg `ConfigOptApp::diff`) finds the fields a patch changes. `apply` is called with
+    /// the patched config before it replaces the current config so it can validate the config and
+    /// hand it to the app. An error from `apply` rejects the patch.
+    pub fn with_patch(
+        mut self,
+        diff: fn(&P, &P) -> ConfigDiff,
+        apply: impl Fn(&P) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.inner.patch = Some(Patch {
+            diff,
+            apply: Box::new(apply),
+        });
+        self
+    }
+
+    pub fn build(self) -> AdminService<P> {
+        AdminService {
+            inner: Arc::new(self.inner),
+        }
+    }
+}
+
+impl<P> AdminService<P>
+where
+    P: ConfigOptType + Merge + DeserializeOwned + Clone + Send + Sync + 'static,
+{
+    /// Serve `current` reporting the sources `trace` recorded without accepting patches
+    ///
+    /// Capture `trace` with `LoadTrace::capture` around the load which produced the config.
+    pub fn new(current: Reloadable<P>, trace: LoadTrace) -> Self {
+        Self::builder(current, trace).build()
+    }
+
+    /// Like [`new`](#method.new) but configure the service before it is built, for example to
+    /// accept patches
+    pub fn builder(current: Reloadable<P>, trace: LoadTrace) -> AdminServiceBuilder<P> {
+        AdminServiceBuilder {
+            inner: Inner {
+                current,
+                trace: Mutex::new(trace),
+                patch: None,
+                patching: Mutex::new(()),
+            },
+        }
+    }
+
+    /// The config which is currently served
+    pub fn current(&self) -> &Reloadable<P> {
+        &self.inner.current
+    }
+
+    fn get(&self, path: Option<&str>) -> Response<Body> {
+        let config = self.inner.current.get();
+        let trace = self.inner.trace.lock().unwrap_or_else(|e| e.into_inner());
+        let mut fields = effective_fields(&*config, &trace);
+        let body = match path {
+            Some(path) => match fields.iter().position(|f| f.path == path) {
+                Some(i) => serde_json::to_string_pretty(&fields.swap_remove(i)),
+                None => return error(StatusCode::NOT_FOUND, format!("`{}` is not set", path)),
+            },
+            None => serde_json::to_string_pretty(&fields),
+        };
+        response(
+            StatusCode::OK,
+            body.expect("effective fields are always serializable"),
+        )
+    }
+
+    fn patch(&self, body: &[u8]) -> Response<Body> {
+        let patch = match &self.inner.patch {
+            Some(patch) => patch,
+            None => return error(StatusCode::METHOD_NOT_ALLOWED, "patching is not enabled"),
+        };
+        let values =
+            match serde_json::from_slice::<serde_json::Map<String, serde_json::Value>>(body) {
+                Ok(values) => values,
+                Err(e) => {
+                    return error(
+                        StatusCode::BAD_REQUEST,
+                        format!("expected a JSON object, err: {}", e),
+                    )
+                }
+            };
+        let _patching = self
+            .inner
+            .patching
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let current = self.inner.current.get();
+        let mut patched = (*current).clone();
+        for (path, value) in values {
+            let path = match path.parse::<FieldPath>() {
+                Ok(path) => path,
+                Err(e) => return error(StatusCode::BAD_REQUEST, e),
+            };
+            let result = from_json(value)
+                .map_err(|e| format!("`{}` {}", path, e))
+                .and_then(|value| set_field(&mut patched, &path, value));
+            if let Err(e) = result {
+                return error(StatusCode::BAD_REQUEST, e);
+            }
+        }
+        let diff = (patch.diff)(&current, &patched);
+        if let Err(e) = diff.check_reload() {
+            return error(StatusCode::CONFLICT, e.to_string());
+        }
+        if let Err(e) = (patch.apply)(&patched) {
+            return error(StatusCode::UNPROCESSABLE_ENTITY, e);
+        }
+        self.inner.current.replace(patched);
+        self.inner
+            .trace
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push_step(TraceSource::Admin, diff.changed().to_vec());
+        let body = serde_json::json!({ "changed": diff.changed() });
+        response(StatusCode::OK, body.to_string())
+    }
+
+    /// Answer a single request
+    pub async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        let path = request.uri().path().trim_end_matches('/');
+        let field = if path == CONFIG_PATH {
+            None
+        } else if path.starts_with(CONFIG_PATH) && path[CONFIG_PATH.len()..].starts_with('/') {
+            Some(String::from(&path[CONFIG_PATH.len() + 1..]))
+        } else {
+            return error(StatusCode::NOT_FOUND, format!("`{}` is not found", path));
+        };
+        match (request.method(), field) {
+            (&Method::GET, field) => self.get(field.as_deref()),
+            (&Method::PATCH, None) => match hyper::body::to_bytes(request.into_body()).await {
+                Ok(body) => self.patch(&body),
+                Err(e) => error(StatusCode::BAD_REQUEST, e.to_string()),
+            },
+            _ => {
+                let mut response = error(StatusCode::METHOD_NOT_ALLOWED, "method not allowed");
+                let allow = if self.inner.patch.is_some() {
+                    "GET, PATCH"
+                } else {
+                    "GET"
+                };
+                response
+                    .headers_mut()
+                    .insert(ALLOW, HeaderValue::from_static(allow));
+                response
+            }
+        }
+    }
+}
+
+impl<P> Service<Request<Body>> for AdminService<P>
+where
+    P: ConfigOptType + Merge + DeserializeOwned + Clone + Send + Sync + 'static,
+{
+    type Response = Response<Body>;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let service = self.clone();
+        Box::pin(async move { Ok(service.handle(request).await) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_json() {
+        let value = serde_json::json!({ "a": [1, 1.5, "b", true] });
+        assert_eq!(
+            from_json(value).unwrap(),
+            Value::Table(
+                vec![(
+                    String::from("a"),
+                    Value::List(vec![
+                        Value::Integer(1),
+                        Value::Float(1.5),
+                        Value::from("b"),
+                        Value::Bool(true),
+                    ])
+                )]
+                .into_iter()
+                .collect()
+            )
+        );
+        assert!(from_json(serde_json::Value::Null).is_err());
+    }
+}
@@ -0,0 +1,319 @@
+//! AWS SSM Parameter Store and Secrets Manager sources
+//!
+//! [`SsmSource`](struct.SsmSource.html) reads every parameter under a prefix as a config document
+//! and [`AwsClient`](struct.AwsClient.html) resolves the references of a
+//! [`ResolvedSource`](struct.ResolvedSource.html) to a parameter (`ssm:/myapp/db_password`) or a
+//! secret (`secretsmanager:myapp/db` or `secretsmanager:myapp/db#password` to pick a key of a JSON
+//! secret). Credentials and the region
+//! are found the way every AWS SDK finds them (the environment, the profile files, or the instance
+//! metadata).
+
+use crate::{
+    resolve::{other, render, set_value},
+    AsyncSource, ReferenceResolver, Schema, SourceFuture,
+};
+use rusoto_core::Region;
+use rusoto_secretsmanager::{GetSecretValueRequest, SecretsManager, SecretsManagerClient};
+use rusoto_ssm::{GetParameterRequest, GetParametersByPathRequest, Ssm, SsmClient};
+use std::{fmt, io};
+use toml::value::Table;
+
+/// The prefix of a value which refers to an SSM parameter
+pub const SSM_PREFIX: &str = "ssm:";
+/// The prefix of a value which refers to a Secrets Manager secret
+pub const SECRETS_MANAGER_PREFIX: &str = "secretsmanager:";
+
+/// A config value which refers to a value stored in AWS
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AwsReference {
+    /// The name of an SSM parameter
+    Parameter(String),
+    /// The id of a secret and optionally the key of the JSON object it stores
+    Secret { id: String, key: Option<String> },
+}
+
+impl AwsReference {
+    /// Parse a reference returning `None` if `value` is not a reference
+    pub fn parse(value: &str) -> Option<Self> {
+        if value.starts_with(SSM_PREFIX) {
+            let name = &value[SSM_PREFIX.len()..];
+            return if name.is_empty() {
+                None
+            } else {
+                Some(Self::Parameter(String::from(name)))
+            };
+        }
+        if value.starts_with(SECRETS_MANAGER_PREFIX) {
+            let rest = &value[SECRETS_MANAGER_PREFIX.len()..];
+            let (id, key) = match rest.find('#') {
+                Some(i) => (&rest[..i], Some(String::from(&rest[i + 1..]))),
+                None => (rest, None),
+            };
+            return if id.is_empty() {
+                None
+            } else {
+                Some(Self::Secret {
+                    id: String::from(id),
+                    key,
+                })
+            };
+        }
+        None
+    }
+}
+
+impl fmt::Display for AwsReference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Parameter(name) => write!(f, "{}{}", SSM_PREFIX, name),
+            Self::Secret { id, key: None } => write!(f, "{}{}", SECRETS_MANAGER_PREFIX, id),
+            Self::Secret { id, key: Some(key) } => {
+                write!(f, "{}{}#{}", SECRETS_MANAGER_PREFIX, id, key)
+            }
+        }
+    }
+}
+
+/// The SSM and Secrets Manager clients of a region
+#[derive(Clone)]
+pub struct AwsClient {
+    ssm: SsmClient,
+    secrets_manager: SecretsManagerClient,
+}
+
+impl AwsClient {
+    pub fn new(region: Region) -> Self {
+        Self {
+            ssm: SsmClient::new(region.clone()),
+            secrets_manager: SecretsManagerClient::new(region),
+        }
+    }
+
+    /// Fetch the value `reference` refers to decrypting it if it is a `SecureString`
+    pub async fn fetch(&self, reference: &AwsReference) -> io::Result<String> {
+        match reference {
+            AwsReference::Parameter(name) => {
+                let request = GetParameterRequest {
+                    name: name.clone(),
+                    with_decryption: Some(true),
+                    ..Default::default()
+                };
+                self.ssm
+                    .get_parameter(request)
+                    .await
+                    .map_err(other)?
+                    .parameter
+                    .and_then(|p| p.value)
+                    .ok_or_else(|| other(format!("`{}` has no value", reference)))
+            }
+            AwsReference::Secret { id, key } => {
+                let request = GetSecretValueRequest {
+                    secret_id: id.clone(),
+                    ..Default::default()
+                };
+                let secret = self
+                    .secrets_manager
+                    .get_secret_value(request)
+                    .await
+                    .map_err(other)?
+                    .secret_string
+                    .ok_or_else(|| other(format!("`{}` is not a string secret", reference)))?;
+                match key {
+                    Some(key) => secret_key(&secret, key)
+                        .map_err(|e| other(format!("`{}` {}", reference, e))),
+                    None => Ok(secret),
+                }
+            }
+        }
+    }
+
+    /// Fetch the name and value of every parameter under `prefix`
+    pub async fn parameters_by_path(&self, prefix: &str) -> io::Result<Vec<(String, String)>> {
+        let mut parameters = Vec::new();
+        let mut next_token = None;
+        loop {
+            let request = GetParametersByPathRequest {
+                path: String::from(prefix),
+                recursive: Some(true),
+                with_decryption: Some(true),
+                next_token,
+                ..Default::default()
+            };
+            let result = self
+                .ssm
+                .get_parameters_by_path(request)
+                .await
+                .map_err(other)?;
+            for parameter in result.parameters.unwrap_or_default() {
+                if let (Some(name), Some(value)) = (parameter.name, parameter.value) {
+                    parameters.push((name, value));
+                }
+            }
+            next_token = result.next_token;
+            if next_token.is_none() {
+                return Ok(parameters);
+            }
+        }
+    }
+}
+
+impl Default for AwsClient {
+    fn default() -> Self {
+        Self::new(Region::default())
+    }
+}
+
+impl fmt::Debug for AwsClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AwsClient").finish()
+    }
+}
+
+/// The value of `key` of a secret which stores a JSON object
+fn secret_key(secret: &str, key: &str) -> Result<String, String> {
+    let object = serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(secret)
+        .map_err(|_| String::from("is not a JSON object"))?;
+    match object.get(key) {
+        Some(serde_json::Value::String(s)) => Ok(s.clone()),
+        Some(value) => Ok(value.to_string()),
+        None => Err(format!("has no key `{}`", key)),
+    }
+}
+
+/// The dotted key of the parameter `name` under `prefix`
+///
+/// `/myapp/server/port` under the prefix `/myapp` is `server.port`.
+fn parameter_key(prefix: &str, name: &str) -> Option<String> {
+    let rest = name.strip_prefix(prefix)?;
+    if !prefix.ends_with('/') && !rest.starts_with('/') {
+        return None;
+    }
+    let rest = rest.trim_matches('/');
+    if rest.is_empty() {
+        None
+    } else {
+        Some(rest.replace('/', "."))
+    }
+}
+
+/// Every SSM parameter under a prefix read as a config document
+///
+/// The rest of the name of a parameter is split on `/` into the keys of a nested path so with the
+/// prefix `/myapp`, the parameter `/myapp/server/port` sets `port` in the `server` table. Set a
+/// schema with [`with_schema`](#method.with_schema) to parse values which are not strings.
+#[derive(Clone, Debug)]
+pub struct SsmSource {
+    prefix: String,
+    client: AwsClient,
+    schema: Option<Schema>,
+}
+
+impl SsmSource {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self::with_client(prefix, AwsClient::default())
+    }
+
+    pub fn with_client(prefix: impl Into<String>, client: AwsClient) -> Self {
+        Self {
+            prefix: prefix.into(),
+            client,
+            schema: None,
+        }
+    }
+
+    /// Coerce each parameter to the kind of value `schema` expects (eg `T::schema()`)
+    pub fn with_schema(mut self, schema: Schema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+}
+
+impl AsyncSource for SsmSource {
+    fn name(&self) -> String {
+        format!("{}{}", SSM_PREFIX, self.prefix)
+    }
+
+    fn read(&self) -> SourceFuture<'_> {
+        Box::pin(async move {
+            let mut parameters = self.client.parameters_by_path(&self.prefix).await?;
+            // Set the parameters in a stable order so a conflict always fails the same way
+            parameters.sort();
+            let mut table = Table::new();
+            for (name, value) in parameters {
+                if let Some(path) = parameter_key(&self.prefix, &name) {
+                    set_value(&mut table, &path, &value, self.schema.as_ref())?;
+                }
+            }
+            render(table)
+        })
+    }
+}
+
+impl ReferenceResolver for AwsClient {
+    fn schemes(&self) -> &[&'static str] {
+        &[SSM_PREFIX, SECRETS_MANAGER_PREFIX]
+    }
+
+    fn resolve<'a>(&'a self, reference: &'a str) -> SourceFuture<'a> {
+        Box::pin(async move {
+            match AwsReference::parse(reference) {
+                Some(reference) => self.fetch(&reference).await,
+                None => Err(other(format!("`{}` is not a valid reference", reference))),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reference() {
+        assert_eq!(
+            AwsReference::parse("ssm:/myapp/db_password"),
+            Some(AwsReference::Parameter(String::from("/myapp/db_password")))
+        );
+        assert_eq!(
+            AwsReference::parse("secretsmanager:myapp/db#password"),
+            Some(AwsReference::Secret {
+                id: String::from("myapp/db"),
+                key: Some(String::from("password")),
+            })
+        );
+        assert_eq!(AwsReference::parse("ssm:"), None);
+        assert_eq!(AwsReference::parse("hunter2"), None);
+        let reference = "secretsmanager:myapp/db#password";
+        assert_eq!(
+            AwsReference::parse(reference).unwrap().to_string(),
+            reference
+        );
+    }
+
+    #[test]
+    fn test_parameter_key() {
+        assert_eq!(
+            parameter_key("/myapp", "/myapp/server/port").as_deref(),
+            Some("server.port")
+        );
+        assert_eq!(
+            parameter_key("/myapp/", "/myapp/host").as_deref(),
+            Some("host")
+        );
+        assert_eq!(parameter_key("/myapp", "/other/host"), None);
+        assert_eq!(parameter_key("/myapp", "/myapp2/host"), None);
+    }
+
+    #[test]
+    fn test_secret_key() {
+        let secret = r#"{"password": "hunter2", "port": 5432}"#;
+        assert_eq!(secret_key(secret, "password").unwrap(), "hunter2");
+        assert_eq!(secret_key(secret, "port").unwrap(), "5432");
+        assert!(secret_key(secret, "user").is_err());
+        assert!(secret_key("hunter2", "password").is_err());
+    }
+}
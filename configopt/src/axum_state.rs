@@ -0,0 +1,51 @@
+//! Extract the current config in axum handlers
+//!
+//! A [`Reloadable`](../struct.Reloadable.html) can be part of the router state (with `FromRef`)
+//! or be added as an `Extension` layer. Handlers then take a `CurrentConfig<T>` which is a
+//! snapshot of the config for the duration of the request.
+
+use crate::Reloadable;
+use axum::{
+    async_trait,
+    extract::{Extension, FromRef, FromRequestParts},
+    http::request::Parts,
+};
+use std::{convert::Infallible, ops::Deref, sync::Arc};
+
+/// A snapshot of the current value of a `Reloadable` config in the state of an axum router
+///
+/// The state must implement `FromRef` for `Reloadable<T>` which a `Reloadable<T>` state does
+/// itself.
+#[derive(Debug)]
+pub struct CurrentConfig<T>(pub Arc<T>);
+
+impl<T> Deref for CurrentConfig<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+#[async_trait]
+impl<S, T> FromRequestParts<S> for CurrentConfig<T>
+where
+    Reloadable<T>: FromRef<S>,
+    S: Send + Sync,
+    T: Send + Sync + 'static,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(_parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self(Reloadable::<T>::from_ref(state).get()))
+    }
+}
+
+impl<T: Send + Sync + 'static> Reloadable<T> {
+    /// An axum `Extension` layer sharing this handle
+    ///
+    /// Handlers take `Extension<Reloadable<T>>` and call `get` for a snapshot.
+    pub fn extension(&self) -> Extension<Self> {
+        Extension(self.clone())
+    }
+}
@@ -0,0 +1,282 @@
+//! Coerce strings into typed config values
+//!
+//! Strings from environment variables, `key=value` overrides, or a custom source carry no type so
+//! they are coerced into the kind of value the [schema](../struct.Schema.html) expects for their
+//! key. Use the same [`Policy`](struct.Policy.html) for every layer so a value means the same
+//! thing wherever it is set.
+
+use crate::{Schema, SchemaField, ValueKind};
+use toml::value::{Table, Value};
+
+/// The words accepted for a boolean
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoolWords {
+    /// Only `true` and `false`
+    Strict,
+    /// `true`, `yes`, `on`, and `1` or `false`, `no`, `off`, and `0` ignoring case
+    Lenient,
+}
+
+/// How strings are coerced
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Policy {
+    pub bools: BoolWords,
+    /// The separator of the items of a list which is not written as a TOML array (eg `a,b`). If it
+    /// is `None` the whole string is a single item.
+    pub list_separator: Option<char>,
+    /// Remove the whitespace around each item of a list
+    pub trim_list_items: bool,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            bools: BoolWords::Strict,
+            list_separator: Some(','),
+            trim_list_items: true,
+        }
+    }
+}
+
+impl Policy {
+    pub fn with_bools(mut self, bools: BoolWords) -> Self {
+        self.bools = bools;
+        self
+    }
+
+    pub fn with_list_separator(mut self, list_separator: Option<char>) -> Self {
+        self.list_separator = list_separator;
+        self
+    }
+
+    pub fn with_trim_list_items(mut self, trim_list_items: bool) -> Self {
+        self.trim_list_items = trim_list_items;
+        self
+    }
+}
+
+pub fn to_bool(value: &str, policy: &Policy) -> Result<bool, String> {
+    match (policy.bools, value) {
+        (_, "true") => return Ok(true),
+        (_, "false") => return Ok(false),
+        (BoolWords::Strict, _) => {}
+        (BoolWords::Lenient, _) => {
+            let value = value.trim().to_lowercase();
+            match value.as_str() {
+                "true" | "yes" | "on" | "1" => return Ok(true),
+                "false" | "no" | "off" | "0" => return Ok(false),
+                _ => {}
+            }
+        }
+    }
+    Err(format!("`{}` is not a boolean", value))
+}
+
+/// Integers may use `_` to separate digits as in TOML
+pub fn to_integer(value: &str) -> Result<i64, String> {
+    value
+        .trim()
+        .replace('_', "")
+        .parse()
+        .map_err(|_| format!("`{}` is not an integer", value))
+}
+
+pub fn to_float(value: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .replace('_', "")
+        .parse()
+        .map_err(|_| format!("`{}` is not a float", value))
+}
+
+/// Split a list into its items
+pub fn split_list<'a>(value: &'a str, policy: &Policy) -> Vec<&'a str> {
+    if value.is_empty() {
+        return Vec::new();
+    }
+    let items = match policy.list_separator {
+        Some(separator) => value.split(separator).collect(),
+        None => vec![value],
+    };
+    if policy.trim_list_items {
+        items.into_iter().map(str::trim).collect()
+    } else {
+        items
+    }
+}
+
+/// Parse `value` as an inline TOML value (eg `[1, 2]` or `{ a = 1 }`)
+fn parse_inline(value: &str) -> Result<Value, String> {
+    let mut table = toml::from_str::<Table>(&format!("value = {}", value))
+        .map_err(|e| format!("`{}` is not a TOML value: {}", value, e))?;
+    Ok(table.remove("value").expect("the key to be set"))
+}
+
+/// Coerce `value` into a value of `kind`
+///
+/// Strings and values of any kind are used verbatim. A list may be written as a TOML array or as
+/// items split with `policy.list_separator` and a table must be written as an inline TOML table.
+pub fn coerce(value: &str, kind: &ValueKind, policy: &Policy) -> Result<Value, String> {
+    Ok(match kind {
+        ValueKind::String | ValueKind::Any => Value::String(String::from(value)),
+        ValueKind::Integer => Value::Integer(to_integer(value)?),
+        ValueKind::Float => Value::Float(to_float(value)?),
+        ValueKind::Boolean => Value::Boolean(to_bool(value, policy)?),
+        ValueKind::Array(item_kind) => {
+            if value.trim_start().starts_with('[') {
+                let array = parse_inline(value)?;
+                if !kind.matches(&array) {
+                    return Err(format!("`{}` is not an {}", value, kind));
+                }
+                array
+            } else {
+                let items = split_list(value, policy)
+                    .into_iter()
+                    .map(|item| coerce(item, item_kind, policy))
+                    .collect::<Result<_, _>>()?;
+                Value::Array(items)
+            }
+        }
+        ValueKind::Table => match parse_inline(value)? {
+            table @ Value::Table(_) => table,
+            _ => return Err(format!("`{}` is not a table", value)),
+        },
+    })
+}
+
+/// Coerce `value` into the kind of value the schema expects for the dotted `path`
+pub fn coerce_path(
+    schema: &Schema,
+    path: &str,
+    value: &str,
+    policy: &Policy,
+) -> Result<Value, String> {
+    let field = find_field(schema, path).ok_or_else(|| format!("`{}` is not a known key", path))?;
+    coerce(value, &field.kind(), policy).map_err(|e| format!("`{}` {}", path, e))
+}
+
+/// The schema field of the dotted `path` matching the instances of maps
+pub(crate) fn find_field<'a>(schema: &'a Schema, path: &str) -> Option<&'a SchemaField> {
+    schema
+        .fields
+        .iter()
+        .find(|f| matches_path(&f.path, path))
+        .or_else(|| schema.field(path))
+}
+
+/// Does the dotted `path` match the schema `pattern` where `*` matches any key
+fn matches_path(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.split('.').collect::<Vec<_>>();
+    let path = path.split('.').collect::<Vec<_>>();
+    pattern.len() == path.len() && pattern.iter().zip(path).all(|(p, k)| *p == "*" || *p == k)
+}
+
+/// Set `value` at the dotted `path` of `table` creating any missing tables
+pub fn set_path(table: &mut Table, path: &str, value: Value) -> Result<(), String> {
+    let mut keys = path.split('.').collect::<Vec<_>>();
+    let last = keys.pop().unwrap_or_default();
+    let mut current = table;
+    for key in keys {
+        let next = current
+            .entry(String::from(key))
+            .or_insert_with(|| Value::Table(Table::new()));
+        current = match next {
+            Value::Table(table) => table,
+            _ => return Err(format!("`{}` of `{}` is not a table", key, path)),
+        };
+    }
+    current.insert(String::from(last), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SchemaField;
+
+    fn field(path: &str, ty: &str) -> SchemaField {
+        SchemaField {
+            path: String::from(path),
+            ty: String::from(ty),
+            required: false,
+            aliases: Vec::new(),
+            format: None,
+            env: None,
+            decode_env: false,
+            arg: None,
+            values: Vec::new(),
+            constraints: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_coerce() {
+        let policy = Policy::default();
+        let coerce = |value, kind| coerce(value, &kind, &policy);
+        assert_eq!(
+            coerce("1_000", ValueKind::Integer),
+            Ok(Value::Integer(1000))
+        );
+        assert_eq!(coerce("0.5", ValueKind::Float), Ok(Value::Float(0.5)));
+        assert_eq!(coerce("true", ValueKind::Boolean), Ok(Value::Boolean(true)));
+        assert!(coerce("yes", ValueKind::Boolean).is_err());
+        assert!(coerce("a", ValueKind::Integer).is_err());
+        assert_eq!(
+            coerce("007", ValueKind::String),
+            Ok(Value::String(String::from("007")))
+        );
+        let ints = ValueKind::Array(Box::new(ValueKind::Integer));
+        let expected = Value::Array(vec![Value::Integer(1), Value::Integer(2)]);
+        assert_eq!(coerce("1, 2", ints.clone()), Ok(expected.clone()));
+        assert_eq!(coerce("[1, 2]", ints.clone()), Ok(expected));
+        assert_eq!(coerce("", ints.clone()), Ok(Value::Array(Vec::new())));
+        assert!(coerce("[\"a\"]", ints).is_err());
+        assert!(coerce("{ a = 1 }", ValueKind::Table).unwrap().is_table());
+        assert!(coerce("1", ValueKind::Table).is_err());
+    }
+
+    #[test]
+    fn test_policy() {
+        let policy = Policy::default()
+            .with_bools(BoolWords::Lenient)
+            .with_list_separator(Some(';'))
+            .with_trim_list_items(false);
+        assert_eq!(to_bool("Off", &policy), Ok(false));
+        assert_eq!(to_bool("1", &policy), Ok(true));
+        assert_eq!(split_list("a; b", &policy), vec!["a", " b"]);
+        let policy = policy.with_list_separator(None);
+        assert_eq!(split_list("a,b", &policy), vec!["a,b"]);
+    }
+
+    #[test]
+    fn test_coerce_path() {
+        let schema = Schema::new(vec![
+            field("port", "Option<u16>"),
+            field("hosts.*.weight", "f64"),
+        ]);
+        let policy = Policy::default();
+        assert_eq!(
+            coerce_path(&schema, "port", "80", &policy),
+            Ok(Value::Integer(80))
+        );
+        assert_eq!(
+            coerce_path(&schema, "hosts.a.weight", "2", &policy),
+            Ok(Value::Float(2.0))
+        );
+        assert!(coerce_path(&schema, "port", "a", &policy)
+            .unwrap_err()
+            .starts_with("`port` "));
+        assert!(coerce_path(&schema, "other", "1", &policy).is_err());
+
+        let mut table = Table::new();
+        set_path(&mut table, "hosts.a.weight", Value::Float(2.0)).unwrap();
+        set_path(&mut table, "port", Value::Integer(80)).unwrap();
+        assert_eq!(
+            Value::Table(table.clone()),
+            "port = 80\n[hosts.a]\nweight = 2.0"
+                .parse::<Value>()
+                .unwrap()
+        );
+        assert!(set_path(&mut table, "port.a", Value::Integer(1)).is_err());
+    }
+}
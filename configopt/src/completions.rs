@@ -0,0 +1,22 @@
+use crate::arena_trait::Arena;
+use structopt::clap::{App, ArgSettings};
+
+/// Append the default value of each argument to its help so it is shown by shells which describe
+/// completions (eg zsh and fish)
+pub(crate) fn describe_defaults<'a>(app: &mut App<'_, 'a>, arena: &'a impl Arena<String>) {
+    for arg in &mut app.p.opts {
+        if arg.b.is_set(ArgSettings::HideDefaultValue) {
+            continue;
+        }
+        if let Some(default) = arg.v.default_val {
+            let help = match arg.b.help {
+                Some(help) => format!("{} [default: {}]", help, default.to_string_lossy()),
+                None => format!("[default: {}]", default.to_string_lossy()),
+            };
+            arg.b.help = Some(arena.alloc(help).as_str());
+        }
+    }
+    for app in &mut app.p.subcommands {
+        describe_defaults(app, arena);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{
+    field_path::field_value, get_field, set_in_file, unset_in_file, ConfigOptType, Error,
+    FieldPath, Result, Value,
+};
+use serde::de::DeserializeOwned;
+use std::{
+    env,
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+use structopt::StructOpt;
+
+/// Read and change the config file
+///
+/// Add it to a CLI with `#[configopt(config_command)]` and run the command with the generated
+/// `try_run_config_command`.
+#[derive(Clone, Debug, PartialEq, StructOpt)]
+#[structopt(name = "config")]
+pub struct ConfigCommand {
+    /// The config file to use instead of the default config file
+    #[structopt(long, short)]
+    pub file: Option<PathBuf>,
+    #[structopt(subcommand)]
+    pub action: ConfigAction,
+}
+
+#[derive(Clone, Debug, PartialEq, StructOpt)]
+pub enum ConfigAction {
+    /// Print the value of a key
+    Get { key: String },
+    /// Set the value of a key keeping the comments of the file
+    Set { key: String, value: String },
+    /// Remove a key so its default applies
+    Unset { key: String },
+    /// Print each key which is set and its value
+    List,
+    /// Open the config file with `$VISUAL` or `$EDITOR` and check it afterwards
+    Edit,
+}
+
+fn invalid(path: &Path, message: String) -> Error {
+    Error::ConfigFile(
+        path.to_path_buf(),
+        io::Error::new(ErrorKind::InvalidInput, message),
+    )
+}
+
+/// Parse `key` checking it is a key of `T`
+fn parse_key<T: ConfigOptType>(path: &Path, key: &str) -> Result<FieldPath> {
+    let field = key.parse::<FieldPath>().map_err(|e| invalid(path, e))?;
+    // Only the key is checked so any value will do
+    field_value::<T>(&field, Value::Bool(false)).map_err(|e| invalid(path, e))?;
+    Ok(field)
+}
+
+/// The config in the file at `path` or `None` if it does not exist
+fn read<T: ConfigOptType + DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    match T::binary() {
+        Some(binary) => crate::from_shared_config_file(
+            path,
+            binary,
+            T::shared_sections(),
+            T::runtime_schema,
+            &[],
+            &[],
+            &mut Vec::new(),
+        ),
+        None => crate::from_config_file(path, T::runtime_schema, &[], &[], &mut Vec::new()),
+    }
+    .map(Some)
+}
+
+/// Strings are printed without quotes so the output can be used in scripts
+fn print(out: &mut impl Write, value: &Value) -> io::Result<()> {
+    match value {
+        Value::String(s) => writeln!(out, "{}", s),
+        value => writeln!(out, "{}", value),
+    }
+}
+
+fn edit(path: &Path) -> Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from("vi"));
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let status = Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .map_err(|e| invalid(path, format!("failed to run `{}`, err: {}", editor, e)))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(invalid(
+            path,
+            format!("`{}` failed with {}", editor, status),
+        ))
+    }
+}
+
+impl ConfigCommand {
+    /// The config file the command operates on
+    pub fn path(&self, default_config_files: &[PathBuf]) -> Option<PathBuf> {
+        self.file
+            .clone()
+            .or_else(|| default_config_files.first().cloned())
+    }
+
+    /// Run the command on the config file at `path` writing its output to `out`
+    ///
+    /// Secrets are never printed. A changed file is checked to still be a valid config.
+    pub fn run<T>(&self, path: &Path, out: &mut impl Write) -> Result<()>
+    where
+        T: ConfigOptType + DeserializeOwned,
+    {
+        let io = |e| Error::ConfigFile(path.to_path_buf(), e);
+        match &self.action {
+            ConfigAction::Get { key } => {
+                let field = parse_key::<T>(path, key)?;
+                let value = read::<T>(path)?.and_then(|config| get_field(&config, &field));
+                match value {
+                    Some(value) => print(out, &value).map_err(io),
+                    None => Err(invalid(path, format!("`{}` is not set", key))),
+                }
+            }
+            ConfigAction::Set { key, value } => {
+                let field = parse_key::<T>(path, key)?;
+                set_in_file::<T>(path, &field, Value::from(value.as_str()))
+            }
+            ConfigAction::Unset { key } => {
+                let field = parse_key::<T>(path, key)?;
+                unset_in_file::<T>(path, &field).map(|_| ())
+            }
+            ConfigAction::List => {
+                if let Some(config) = read::<T>(path)? {
+                    // Secrets are left out as `get_field` never returns them
+                    for key in config.set_fields() {
+                        if let Some(value) = get_field(&config, &FieldPath::from(key.as_str())) {
+                            writeln!(out, "{} = {}", key, value).map_err(io)?;
+                        }
+                    }
+                }
+                Ok(())
+            }
+            ConfigAction::Edit => {
+                edit(path)?;
+                read::<T>(path).map(|_| ())
+            }
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use crate::{
+    error::{Error, Result},
+    ConfigOptType, Schema,
+};
+use std::{
+    ffi::OsString,
+    fs::{self, OpenOptions},
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    path::{Path, PathBuf},
+    process, thread,
+    time::{Duration, SystemTime},
+};
+use toml::value::Table;
+
+/// The format of a config file written with `ConfigOptType::write_to_file` or of an in-memory
+/// config given to `ConfigOpt::try_from_iter_with_files`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+    #[cfg(feature = "yaml")]
+    Yaml,
+    /// Sections of `key = value` lines whose values are coerced into the type of their field
+    ///
+    /// A section or a dotted key is a nested table so `[server]` followed by `port = 80` sets
+    /// `server.port`. A list is a TOML array or items separated with `,`.
+    #[cfg(feature = "ini")]
+    Ini,
+    /// Java properties whose dotted keys are nested tables and whose values are coerced into the
+    /// type of their field like an INI file
+    #[cfg(feature = "properties")]
+    Properties,
+    /// HOCON with its substitutions and includes resolved
+    ///
+    /// A `null` value or an optional substitution (eg `${?PORT}`) which is not set leaves its key
+    /// unset so a lower priority layer provides it. Included files are relative to the including
+    /// file when it is read from disk.
+    #[cfg(feature = "hocon")]
+    Hocon,
+}
+
+impl ConfigFormat {
+    /// The format of a config file from its extension or `None` if it is not recognized
+    ///
+    /// The `.enc` extension of an encrypted file is skipped so `config.yaml.enc` is YAML.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let path = match path.extension() {
+            Some(extension) if extension == "enc" => Path::new(path.file_stem()?),
+            _ => path,
+        };
+        match path.extension()?.to_str()? {
+            "toml" => Some(Self::Toml),
+            "json" => Some(Self::Json),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Some(Self::Yaml),
+            #[cfg(feature = "ini")]
+            "ini" => Some(Self::Ini),
+            #[cfg(feature = "properties")]
+            "properties" => Some(Self::Properties),
+            #[cfg(feature = "hocon")]
+            "conf" | "hocon" => Some(Self::Hocon),
+            _ => None,
+        }
+    }
+
+    /// The extension of files in this format without the `.`
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Toml => "toml",
+            Self::Json => "json",
+            #[cfg(feature = "yaml")]
+            Self::Yaml => "yaml",
+            #[cfg(feature = "ini")]
+            Self::Ini => "ini",
+            #[cfg(feature = "properties")]
+            Self::Properties => "properties",
+            #[cfg(feature = "hocon")]
+            Self::Hocon => "conf",
+        }
+    }
+
+    /// Render the values of a config in this format
+    ///
+    /// HOCON is written as JSON which is a valid HOCON document.
+    pub(crate) fn render(self, values: &Table, path: &Path) -> Result<String> {
+        match self {
+            // A `Value` writes the keys of each table before its nested tables
+            Self::Toml => toml::to_string(&toml::Value::Table(values.clone()))
+                .map_err(|e| render_error(path, e)),
+            #[cfg(feature = "hocon")]
+            Self::Hocon => serde_json::to_string_pretty(values).map_err(|e| render_error(path, e)),
+            Self::Json => serde_json::to_string_pretty(values).map_err(|e| render_error(path, e)),
+            #[cfg(feature = "yaml")]
+            Self::Yaml => serde_yaml::to_string(values).map_err(|e| render_error(path, e)),
+            #[cfg(feature = "ini")]
+            Self::Ini => crate::key_value::render_ini(values).map_err(|e| render_error(path, e)),
+            #[cfg(feature = "properties")]
+            Self::Properties => {
+                crate::key_value::render_properties(values).map_err(|e| render_error(path, e))
+            }
+        }
+    }
+
+    /// Parse a document in this format into the table a TOML config file would have
+    ///
+    /// `schema` is only called for the untyped formats to find the type of each value.
+    pub(crate) fn parse_table(
+        self,
+        contents: &str,
+        path: &Path,
+        #[cfg_attr(
+            not(any(feature = "ini", feature = "properties")),
+            allow(unused_variables)
+        )]
+        schema: fn() -> Schema,
+    ) -> Result<Table> {
+        let value = match self {
+            Self::Toml => contents
+                .parse::<toml::Value>()
+                .map_err(|e| Error::ConfigFile(path.to_path_buf(), e.into()))?,
+            Self::Json => serde_json::from_str(contents).map_err(|e| parse_error(path, e))?,
+            #[cfg(feature = "yaml")]
+            Self::Yaml => serde_yaml::from_str(contents).map_err(|e| parse_error(path, e))?,
+            #[cfg(feature = "ini")]
+            Self::Ini => {
+                return crate::key_value::parse_ini(contents, &schema())
+                    .map_err(|e| parse_error(path, e))
+            }
+            #[cfg(feature = "properties")]
+            Self::Properties => {
+                return crate::key_value::parse_properties(contents, &schema())
+                    .map_err(|e| parse_error(path, e))
+            }
+            #[cfg(feature = "hocon")]
+            Self::Hocon => {
+                return crate::hocon_format::parse_str(contents).map_err(|e| parse_error(path, e))
+            }
+        };
+        match value {
+            toml::Value::Table(table) => Ok(table),
+            _ => Err(parse_error(path, "the document must be a table")),
+        }
+    }
+
+    /// Read and parse the config file at `path` in this format
+    pub(crate) fn read_table(self, path: &Path, schema: fn() -> Schema) -> Result<Table> {
+        let contents = crate::decrypt::read_config_file(path)?;
+        match self {
+            // Let the loader read the file so its includes are relative to it
+            #[cfg(feature = "hocon")]
+            Self::Hocon if !crate::decrypt::is_encrypted_file(path) => {
+                crate::hocon_format::parse_file(path).map_err(|e| parse_error(path, e))
+            }
+            _ => self.parse_table(&contents, path, schema),
+        }
+    }
+}
+
+pub(crate) fn parse_error(path: &Path, e: impl std::fmt::Display) -> Error {
+    Error::ConfigFile(
+        path.to_path_buf(),
+        IoError::new(IoErrorKind::InvalidData, e.to_string()),
+    )
+}
+
+fn render_error(path: &Path, e: impl std::fmt::Display) -> Error {
+    Error::WriteConfig {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    }
+}
+
+/// The values of `config` which are written to the file at `path`
+pub(crate) fn values<T: ConfigOptType + ?Sized>(config: &T, path: &Path) -> Result<Table> {
+    config.toml_values().map_err(|e| render_error(path, e))
+}
+
+pub(crate) fn write(path: &Path, contents: &str) -> Result<()> {
+    fs::write(path, contents).map_err(|e| Error::ConfigFile(path.to_path_buf(), e))
+}
+
+/// How long a lock file may exist before it is assumed to belong to a process which died
+const STALE_LOCK: Duration = Duration::from_secs(30);
+
+const LOCK_POLL: Duration = Duration::from_millis(10);
+
+/// A path next to `path` with `suffix` appended to its file name
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn is_stale(lock: &Path) -> bool {
+    fs::metadata(lock)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .map_or(false, |age| age > STALE_LOCK)
+}
+
+/// Write `contents` to `path` unless it already exists, returning if this call wrote it
+///
+/// Processes coordinate through a `<path>.lock` file which is created atomically. The process
+/// which creates it writes a temporary file and renames it into place so the file is never seen
+/// partially written or truncated. The others wait for the lock to be released and then see the
+/// file exists. A lock which is older than 30 seconds is assumed to be left behind by a process
+/// which died and is removed.
+pub(crate) fn write_once(path: &Path, contents: &str) -> Result<bool> {
+    let error = |e| Error::ConfigFile(path.to_path_buf(), e);
+    let lock = sibling(path, ".lock");
+    loop {
+        if path.exists() {
+            return Ok(false);
+        }
+        match OpenOptions::new().write(true).create_new(true).open(&lock) {
+            Ok(_) => break,
+            Err(e) if e.kind() == IoErrorKind::AlreadyExists => {
+                if is_stale(&lock) {
+                    // Another waiter may have removed it first
+                    fs::remove_file(&lock).ok();
+                } else {
+                    thread::sleep(LOCK_POLL);
+                }
+            }
+            Err(e) => return Err(error(e)),
+        }
+    }
+    // The file may have been written between the check and acquiring the lock
+    let result = if path.exists() {
+        Ok(false)
+    } else {
+        let tmp = sibling(path, &format!(".{}.tmp", process::id()));
+        fs::write(&tmp, contents)
+            .and_then(|_| fs::rename(&tmp, path))
+            .map(|_| true)
+            .map_err(|e| {
+                fs::remove_file(&tmp).ok();
+                error(e)
+            })
+    };
+    fs::remove_file(&lock).ok();
+    result
+}
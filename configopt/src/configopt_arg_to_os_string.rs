@@ -4,6 +4,11 @@ use std::{ffi::OsString, path::PathBuf};
 pub trait ConfigOptArgToOsString {
     /// Lookup a default value for the path to an argument
     fn arg_to_os_string(&self, arg_path: &[String]) -> Option<OsString>;
+
+    /// Is the argument at the path a secret whose default value should not be displayed
+    fn arg_is_secret(&self, _arg_path: &[String]) -> bool {
+        false
+    }
 }
 
 // Use this trick[1] to get around the lack of specialization.
@@ -15,6 +20,13 @@ impl<T: ToString> ConfigOptArgToOsString for &T {
     }
 }
 
+// OS strings are passed to `clap` as they are so values which are not UTF-8 are not lost
+impl ConfigOptArgToOsString for OsString {
+    fn arg_to_os_string(&self, _arg_path: &[String]) -> Option<OsString> {
+        Some(self.clone())
+    }
+}
+
 impl ConfigOptArgToOsString for PathBuf {
     fn arg_to_os_string(&self, _arg_path: &[String]) -> Option<OsString> {
         Some(self.clone().into_os_string())
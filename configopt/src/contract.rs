@@ -0,0 +1,348 @@
+use crate::{Schema, SchemaField};
+#[cfg(feature = "patterns")]
+use regex::Regex;
+use std::fmt;
+use toml::Value;
+
+/// The kind of TOML value a field accepts
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValueKind {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    Array(Box<ValueKind>),
+    Table,
+    /// A type which is not known to serialize as a single kind of value
+    Any,
+}
+
+impl ValueKind {
+    /// The kind of value the Rust type `ty` is deserialized from
+    pub fn from_rust_ty(ty: &str) -> Self {
+        // Ignore the path of the outermost type
+        let generics = ty.find('<').unwrap_or_else(|| ty.len());
+        let ty = &ty[ty[..generics].rfind("::").map_or(0, |i| i + 2)..];
+        if let Some(inner) = generic_arg(ty, "Option") {
+            return Self::from_rust_ty(inner);
+        }
+        if let Some(inner) = generic_arg(ty, "Vec") {
+            return Self::Array(Box::new(Self::from_rust_ty(inner)));
+        }
+        if generic_arg(ty, "HashMap").is_some() || generic_arg(ty, "BTreeMap").is_some() {
+            return Self::Table;
+        }
+        match ty {
+            "String" | "str" | "char" | "PathBuf" | "OsString" | "Interned" | "SmolStr"
+            | "CompactString" | "IpAddr" | "Ipv4Addr" | "Ipv6Addr" | "SocketAddr" => Self::String,
+            "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64"
+            | "i128" | "isize" => Self::Integer,
+            "f32" | "f64" => Self::Float,
+            "bool" | "ConfigOptBool" => Self::Boolean,
+            "FeatureFlags" => Self::Table,
+            _ if ty.starts_with("Cow<") => Self::String,
+            _ => Self::Any,
+        }
+    }
+
+    /// Can a value of this kind be deserialized from `value`
+    pub fn matches(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Self::Any, _)
+            | (Self::String, Value::String(_))
+            | (Self::Integer, Value::Integer(_))
+            | (Self::Float, Value::Float(_))
+            | (Self::Float, Value::Integer(_))
+            | (Self::Boolean, Value::Boolean(_))
+            | (Self::Table, Value::Table(_)) => true,
+            (Self::Array(kind), Value::Array(values)) => values.iter().all(|v| kind.matches(v)),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String => write!(f, "string"),
+            Self::Integer => write!(f, "integer"),
+            Self::Float => write!(f, "float"),
+            Self::Boolean => write!(f, "boolean"),
+            Self::Array(kind) => write!(f, "array of {}", kind),
+            Self::Table => write!(f, "table"),
+            Self::Any => write!(f, "any"),
+        }
+    }
+}
+
+/// The argument of the generic type `outer` if `ty` is `outer<..>`
+fn generic_arg<'a>(ty: &'a str, outer: &str) -> Option<&'a str> {
+    ty.strip_prefix(outer)?.strip_prefix('<')?.strip_suffix('>')
+}
+
+impl SchemaField {
+    /// The kind of value this field accepts in a config file
+    pub fn kind(&self) -> ValueKind {
+        // Human friendly parsers accept both strings and numbers
+        if self.format.is_some() {
+            return ValueKind::Any;
+        }
+        ValueKind::from_rust_ty(&self.ty)
+    }
+}
+
+impl SchemaField {
+    /// Why `value` does not satisfy the possible values, bounds, or pattern of this field
+    fn unsatisfied(&self, value: &Value) -> Option<String> {
+        if let Value::Array(values) = value {
+            return values.iter().find_map(|v| self.unsatisfied(v));
+        }
+        if let (false, Value::String(s)) = (self.values.is_empty(), value) {
+            if !self.values.contains(s) {
+                return Some(format!(
+                    "must be one of {} not `{}`",
+                    self.values
+                        .iter()
+                        .map(|v| format!("`{}`", v))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    s
+                ));
+            }
+        }
+        if let (Some(pattern), Value::String(s)) = (&self.constraints.pattern, value) {
+            if let Some(message) = unmatched(pattern, s) {
+                return Some(message);
+            }
+        }
+        let number = match value {
+            Value::Integer(i) => *i as f64,
+            Value::Float(f) => *f,
+            _ => return None,
+        };
+        if let Some(min) = self.constraints.min {
+            if number < min {
+                return Some(format!("must be at least {} not {}", min, value));
+            }
+        }
+        if let Some(max) = self.constraints.max {
+            if number > max {
+                return Some(format!("must be at most {} not {}", max, value));
+            }
+        }
+        None
+    }
+}
+
+/// Why `s` does not match the regular expression `pattern`
+#[cfg(feature = "patterns")]
+fn unmatched(pattern: &str, s: &str) -> Option<String> {
+    match Regex::new(pattern) {
+        Ok(regex) if regex.is_match(s) => None,
+        Ok(_) => Some(format!("must match `{}` not `{}`", pattern, s)),
+        Err(e) => Some(format!("has an invalid pattern `{}`, err: {}", pattern, e)),
+    }
+}
+
+#[cfg(not(feature = "patterns"))]
+fn unmatched(pattern: &str, _: &str) -> Option<String> {
+    Some(format!(
+        "cannot be checked: matching `{}` requires the `patterns` feature",
+        pattern
+    ))
+}
+
+/// A way a config document does not satisfy a schema
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// A required key is not set
+    Missing { path: String },
+    /// A key has a value of the wrong kind
+    TypeMismatch {
+        path: String,
+        expected: String,
+        found: String,
+    },
+    /// A key is not in the schema
+    Unknown { path: String },
+    /// A value does not satisfy the constraints of its key
+    Constraint { path: String, message: String },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing { path } => write!(f, "`{}` is required", path),
+            Self::TypeMismatch {
+                path,
+                expected,
+                found,
+            } => write!(f, "`{}` must be a {} not a {}", path, expected, found),
+            Self::Unknown { path } => write!(f, "`{}` is not a known key", path),
+            Self::Constraint { path, message } => write!(f, "`{}` {}", path, message),
+        }
+    }
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        String::from(key)
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+/// The values of the document at `path` where each `*` matches every key of a table
+fn expand<'a>(
+    value: &'a Value,
+    segments: &[&str],
+    prefix: String,
+    out: &mut Vec<(String, &'a Value)>,
+) {
+    let (segment, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => {
+            out.push((prefix, value));
+            return;
+        }
+    };
+    if let Value::Table(table) = value {
+        if *segment == "*" {
+            for (key, value) in table {
+                expand(value, rest, join(&prefix, key), out);
+            }
+        } else if let Some(value) = table.get(*segment) {
+            expand(value, rest, join(&prefix, segment), out);
+        }
+    }
+}
+
+/// Does the dotted `path` of a document match the schema `pattern`
+fn matches_pattern(pattern: &str, path: &str) -> bool {
+    let mut pattern = pattern.split('.');
+    let mut path = path.split('.');
+    loop {
+        match (pattern.next(), path.next()) {
+            (None, None) => return true,
+            (Some(p), Some(k)) if p == "*" || p == k => {}
+            _ => return false,
+        }
+    }
+}
+
+/// Is the dotted `path` of a document a table containing keys which match the schema `pattern`
+fn is_parent_pattern(pattern: &str, path: &str) -> bool {
+    let mut pattern = pattern.split('.');
+    for key in path.split('.') {
+        match pattern.next() {
+            Some(p) if p == "*" || p == key => {}
+            _ => return false,
+        }
+    }
+    pattern.next().is_some()
+}
+
+impl Schema {
+    /// Check that a config document produced elsewhere (eg a template or a Helm chart) sets every
+    /// required key with a value of the right kind and sets no unknown keys
+    ///
+    /// This lets the producers of config files validate against the schema a consumer exports
+    /// with `Schema::to_json` in their own CI.
+    pub fn check(&self, document: &Value) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for field in &self.fields {
+            let mut segments = field.path.split('.').collect::<Vec<_>>();
+            let key = segments.pop().unwrap_or_default();
+            let mut parents = Vec::new();
+            expand(document, &segments, String::new(), &mut parents);
+            let keys = Some(key).into_iter().chain(
+                field
+                    .aliases
+                    .iter()
+                    .map(|a| a.rsplit('.').next().unwrap_or(a)),
+            );
+            let keys = keys.collect::<Vec<_>>();
+            for (parent, value) in parents {
+                let found = keys
+                    .iter()
+                    .find_map(|key| value.get(*key).map(|value| (join(&parent, key), value)));
+                match found {
+                    Some((path, value)) => {
+                        let kind = field.kind();
+                        if !kind.matches(value) {
+                            violations.push(Violation::TypeMismatch {
+                                path,
+                                expected: kind.to_string(),
+                                found: String::from(value.type_str()),
+                            });
+                        } else if let Some(message) = field.unsatisfied(value) {
+                            violations.push(Violation::Constraint { path, message });
+                        }
+                    }
+                    None if field.required => violations.push(Violation::Missing {
+                        path: join(&parent, key),
+                    }),
+                    None if field
+                        .constraints
+                        .required_if
+                        .as_ref()
+                        .map_or(false, |other| value.get(other.as_str()).is_some()) =>
+                    {
+                        violations.push(Violation::Missing {
+                            path: join(&parent, key),
+                        })
+                    }
+                    None => {}
+                }
+            }
+        }
+        self.unknown_keys(document, "", &mut violations);
+        violations
+    }
+
+    /// Parse and check a TOML config document
+    pub fn check_str(&self, document: &str) -> Result<Vec<Violation>, toml::de::Error> {
+        Ok(self.check(&document.parse::<Value>()?))
+    }
+
+    /// Check only the values which are set against the possible values and constraints of their
+    /// keys
+    ///
+    /// Unlike `check` a document which leaves required keys unset is not a violation so a
+    /// partial config can be checked.
+    pub fn check_constraints(&self, document: &Value) -> Vec<Violation> {
+        self.check(document)
+            .into_iter()
+            .filter(|violation| match violation {
+                Violation::Constraint { .. } => true,
+                _ => false,
+            })
+            .collect()
+    }
+
+    fn unknown_keys(&self, value: &Value, prefix: &str, violations: &mut Vec<Violation>) {
+        let table = match value {
+            Value::Table(table) => table,
+            _ => return,
+        };
+        for (key, value) in table {
+            let path = join(prefix, key);
+            let known = self.fields.iter().any(|field| {
+                matches_pattern(&field.path, &path)
+                    || field.aliases.iter().any(|a| matches_pattern(a, &path))
+            });
+            if known {
+                continue;
+            }
+            let is_parent = self
+                .fields
+                .iter()
+                .any(|field| is_parent_pattern(&field.path, &path));
+            if is_parent && value.is_table() {
+                self.unknown_keys(value, &path, violations);
+            } else {
+                violations.push(Violation::Unknown { path });
+            }
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use serde::{de::Error as _, Deserialize, Deserializer};
+use std::{convert::TryFrom, fmt::Display};
+
+/// Deserialize a `#[configopt(from = "I")]` field by converting the intermediate type `I`
+#[doc(hidden)]
+pub fn deserialize_from<'de, D, I, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    I: Deserialize<'de> + Into<T>,
+{
+    I::deserialize(deserializer).map(|value| Some(value.into()))
+}
+
+/// Deserialize a `#[configopt(try_from = "I")]` field by converting the intermediate type `I`
+/// reporting a failed conversion as a deserialization error
+#[doc(hidden)]
+pub fn deserialize_try_from<'de, D, I, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    I: Deserialize<'de>,
+    T: TryFrom<I>,
+    T::Error: Display,
+{
+    let value = I::deserialize(deserializer)?;
+    T::try_from(value).map(Some).map_err(D::Error::custom)
+}
+
+/// Deserialize a `#[configopt(empty_is_unset)]` field as `None` if the list is empty
+#[doc(hidden)]
+pub fn deserialize_empty_as_none<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Ok(Option::<Vec<T>>::deserialize(deserializer)?.filter(|values| !values.is_empty()))
+}
@@ -0,0 +1,116 @@
+use crate::error::{Error, Result};
+use lazy_static::lazy_static;
+use std::{
+    fs,
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+lazy_static! {
+    static ref DECRYPTOR: RwLock<Option<Arc<dyn Decryptor>>> = RwLock::new(None);
+}
+
+/// Decrypts config values and files which are encrypted at rest (eg with SOPS, age, or a KMS)
+///
+/// Install one with [`set_decryptor`](fn.set_decryptor.html). It decrypts the value of each field
+/// marked `#[configopt(decrypt)]` and every config file whose name has an `.enc` part (eg
+/// `secrets.enc.toml` or `config.toml.enc`). A closure which decrypts a single value is a
+/// `Decryptor` which decrypts files as a single value too.
+pub trait Decryptor: Send + Sync {
+    /// Decrypt the value of a field
+    fn decrypt_value(&self, ciphertext: &str) -> std::result::Result<String, String>;
+
+    /// Decrypt the contents of the config file at `path`
+    fn decrypt_file(&self, path: &Path, contents: &[u8]) -> std::result::Result<String, String> {
+        let _ = path;
+        let ciphertext = std::str::from_utf8(contents).map_err(|e| e.to_string())?;
+        self.decrypt_value(ciphertext)
+    }
+}
+
+impl<F> Decryptor for F
+where
+    F: Fn(&str) -> std::result::Result<String, String> + Send + Sync,
+{
+    fn decrypt_value(&self, ciphertext: &str) -> std::result::Result<String, String> {
+        self(ciphertext)
+    }
+}
+
+/// Use `decryptor` for all encrypted fields and files
+pub fn set_decryptor(decryptor: impl Decryptor + 'static) {
+    *DECRYPTOR.write().expect("decryptor lock poisoned") = Some(Arc::new(decryptor));
+}
+
+/// Remove the installed decryptor so encrypted fields and files fail to load
+pub fn clear_decryptor() {
+    *DECRYPTOR.write().expect("decryptor lock poisoned") = None;
+}
+
+fn installed() -> std::result::Result<Arc<dyn Decryptor>, String> {
+    DECRYPTOR
+        .read()
+        .expect("decryptor lock poisoned")
+        .clone()
+        .ok_or_else(|| String::from("no decryptor is installed, see `configopt::set_decryptor`"))
+}
+
+/// Decrypt the value of the field at `path` with `with` or the installed decryptor
+#[doc(hidden)]
+pub fn decrypt_value(
+    path: &str,
+    value: &str,
+    with: Option<fn(&str) -> std::result::Result<String, String>>,
+) -> Result<String> {
+    match with {
+        Some(with) => with(value),
+        None => installed().and_then(|decryptor| decryptor.decrypt_value(value)),
+    }
+    .map_err(|message| Error::Decrypt {
+        path: String::from(path),
+        message,
+    })
+}
+
+/// Is the config file at `path` encrypted as a whole
+pub(crate) fn is_encrypted_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map_or(false, |name| {
+            name.split('.').skip(1).any(|part| part == "enc")
+        })
+}
+
+/// Read the config file at `path` decrypting it if it is encrypted
+pub(crate) fn read_config_file(path: &Path) -> Result<String> {
+    if !is_encrypted_file(path) {
+        return fs::read_to_string(path).map_err(|e| Error::ConfigFile(path.to_path_buf(), e));
+    }
+    let contents = fs::read(path).map_err(|e| Error::ConfigFile(path.to_path_buf(), e))?;
+    installed()
+        .and_then(|decryptor| decryptor.decrypt_file(path, &contents))
+        .map_err(|e| {
+            Error::ConfigFile(
+                path.to_path_buf(),
+                IoError::new(
+                    IoErrorKind::InvalidData,
+                    format!("failed to decrypt: {}", e),
+                ),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_encrypted_file() {
+        assert!(is_encrypted_file(Path::new("secrets.enc.toml")));
+        assert!(is_encrypted_file(Path::new("/etc/app/config.toml.enc")));
+        assert!(!is_encrypted_file(Path::new("config.toml")));
+        assert!(!is_encrypted_file(Path::new("enc.toml")));
+        assert!(!is_encrypted_file(Path::new("/enc/config.toml")));
+    }
+}
@@ -0,0 +1,299 @@
+use crate::{diagnostic, LoadTrace};
+use std::{
+    ffi::OsString,
+    fmt,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+use toml::value::{Table, Value};
+
+/// An alternative name for a field set with `#[configopt(alias = "...")]`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alias {
+    /// The keys of the tables holding the field where `*` matches any instance of a map, empty for
+    /// a top level field
+    pub table: Vec<&'static str>,
+    /// The config file key of the field
+    pub key: &'static str,
+    /// The alias config file key of the field
+    pub alias_key: &'static str,
+    /// The CLI argument name of the field
+    pub arg: &'static str,
+    /// The alias CLI argument name of the field
+    pub alias_arg: &'static str,
+    /// The note set with `#[configopt(deprecated = "...")]` if the alias is deprecated
+    pub deprecated: Option<&'static str>,
+}
+
+impl Alias {
+    /// The alias of a field of a type which is nested in the table at `table`
+    pub fn nested(mut self, table: &[&'static str]) -> Self {
+        self.table.splice(0..0, table.iter().copied());
+        self
+    }
+
+    /// The dotted paths of the keys of `table` which use this alias
+    pub(crate) fn uses(&self, table: &Table) -> Vec<Vec<String>> {
+        let mut uses = Vec::new();
+        find_uses(
+            table,
+            &self.table,
+            self.alias_key,
+            &mut Vec::new(),
+            &mut uses,
+        );
+        uses
+    }
+}
+
+fn find_uses(
+    table: &Table,
+    path: &[&str],
+    key: &str,
+    prefix: &mut Vec<String>,
+    uses: &mut Vec<Vec<String>>,
+) {
+    let (first, rest) = match path.split_first() {
+        Some(split) => split,
+        None => {
+            if table.contains_key(key) {
+                let mut path = prefix.clone();
+                path.push(String::from(key));
+                uses.push(path);
+            }
+            return;
+        }
+    };
+    for (name, value) in table {
+        if let (true, Value::Table(table)) = (*first == "*" || *first == name.as_str(), value) {
+            prefix.push(name.clone());
+            find_uses(table, rest, key, prefix, uses);
+            prefix.pop();
+        }
+    }
+}
+
+/// The deprecated aliases the config file `path` uses
+///
+/// `contents` is the text of the file if it is TOML. It is used to find the line of each use.
+pub(crate) fn config_file_deprecations(
+    table: &Table,
+    contents: Option<&str>,
+    aliases: &[Alias],
+    path: &Path,
+) -> Vec<Deprecation> {
+    let mut deprecations = Vec::new();
+    for alias in aliases {
+        let note = match alias.deprecated {
+            Some(note) => note,
+            None => continue,
+        };
+        for mut used in alias.uses(table) {
+            let used_key = used.join(".");
+            used.pop();
+            used.push(String::from(alias.key));
+            deprecations.push(Deprecation {
+                line: contents
+                    .and_then(|contents| diagnostic::find_key(contents, &used_key))
+                    .map(|(line, _)| line),
+                alias: used_key,
+                name: used.join("."),
+                note: Some(String::from(note)),
+                source: Source::ConfigFile(path.to_path_buf()),
+            });
+        }
+    }
+    deprecations
+}
+
+/// Where a deprecated alias was used
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    Cli,
+    ConfigFile(PathBuf),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cli => write!(f, "the command line"),
+            Self::ConfigFile(path) => write!(f, "config file '{}'", path.to_string_lossy()),
+        }
+    }
+}
+
+/// A warning that a deprecated alias was used
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deprecation {
+    /// The deprecated name as it was used
+    pub alias: String,
+    /// The name which should be used instead
+    pub name: String,
+    pub note: Option<String>,
+    pub source: Source,
+    /// The line of the config file which uses the alias if it is known
+    pub line: Option<usize>,
+}
+
+impl Deprecation {
+    /// Write the warning to `stderr`
+    pub fn warn(&self) {
+        LoadTrace::warn(|| self.to_string());
+        writeln!(&mut io::stderr(), "{}", self).ok();
+    }
+}
+
+impl fmt::Display for Deprecation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "warning: {} uses deprecated `{}`",
+            self.source, self.alias
+        )?;
+        if let Some(line) = self.line {
+            write!(f, " on line {}", line)?;
+        }
+        if self.alias != self.name {
+            write!(f, ", use `{}` instead", self.name)?;
+        }
+        if let Some(note) = &self.note {
+            write!(f, ": {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+/// Replace all alias long arguments with the argument they are an alias for warning about any
+/// deprecated usages
+pub(crate) fn rewrite_args(args: Vec<OsString>, aliases: &[Alias]) -> Vec<OsString> {
+    if aliases.is_empty() {
+        return args;
+    }
+    let mut trailing = false;
+    args.into_iter()
+        .enumerate()
+        .map(|(i, arg)| {
+            // Never rewrite the binary name or anything after `--`
+            if i == 0 || trailing {
+                return arg;
+            }
+            let arg_str = match arg.to_str() {
+                Some(arg_str) => arg_str,
+                None => return arg,
+            };
+            if arg_str == "--" {
+                trailing = true;
+                return arg;
+            }
+            for alias in aliases {
+                let alias_flag = format!("--{}", alias.alias_arg);
+                let rest = if arg_str == alias_flag {
+                    ""
+                } else if arg_str.starts_with(&format!("{}=", alias_flag)) {
+                    &arg_str[alias_flag.len()..]
+                } else {
+                    continue;
+                };
+                if let Some(note) = alias.deprecated {
+                    Deprecation {
+                        alias: alias_flag.clone(),
+                        name: format!("--{}", alias.arg),
+                        note: Some(String::from(note)),
+                        source: Source::Cli,
+                        line: None,
+                    }
+                    .warn();
+                }
+                return OsString::from(format!("--{}{}", alias.arg, rest));
+            }
+            arg
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alias() -> Alias {
+        Alias {
+            table: Vec::new(),
+            key: "port",
+            alias_key: "old_port",
+            arg: "port",
+            alias_arg: "old-port",
+            deprecated: Some("use `port`"),
+        }
+    }
+
+    #[test]
+    fn rewrite_alias_args() {
+        let args = vec![
+            "app",
+            "--old-port",
+            "1",
+            "--old-port=2",
+            "--old-portable",
+            "--",
+            "--old-port",
+        ]
+        .into_iter()
+        .map(OsString::from)
+        .collect();
+        assert_eq!(
+            rewrite_args(args, &[alias()]),
+            vec![
+                "app",
+                "--port",
+                "1",
+                "--port=2",
+                "--old-portable",
+                "--",
+                "--old-port"
+            ]
+        );
+    }
+
+    #[test]
+    fn deprecation_display() {
+        let deprecation = Deprecation {
+            alias: String::from("old_port"),
+            name: String::from("port"),
+            note: None,
+            source: Source::ConfigFile(PathBuf::from("/config.toml")),
+            line: None,
+        };
+        assert_eq!(
+            deprecation.to_string(),
+            "warning: config file '/config.toml' uses deprecated `old_port`, use `port` instead"
+        );
+    }
+
+    #[test]
+    fn nested_deprecations() {
+        let contents = "old_port = 1\n[server]\nold_port = 2\n[upstreams.a]\nold_port = 3\n";
+        let table = toml::from_str(contents).unwrap();
+        let aliases = [
+            alias().nested(&["server"]),
+            alias().nested(&["upstreams", "*"]),
+            alias().nested(&["missing"]),
+        ];
+        let path = Path::new("/config.toml");
+        let deprecations = config_file_deprecations(&table, Some(contents), &aliases, path);
+        assert_eq!(
+            deprecations
+                .iter()
+                .map(|d| (d.alias.as_str(), d.name.as_str(), d.line))
+                .collect::<Vec<_>>(),
+            vec![
+                ("server.old_port", "server.port", Some(3)),
+                ("upstreams.a.old_port", "upstreams.a.port", Some(5)),
+            ]
+        );
+        assert_eq!(
+            deprecations[0].to_string(),
+            "warning: config file '/config.toml' uses deprecated `server.old_port` on line 3, use \
+             `server.port` instead: use `port`"
+        );
+    }
+}
@@ -0,0 +1,365 @@
+use crate::{
+    messages::{message, MessageId},
+    Error, UnknownKey,
+};
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// The severity of a [`Diagnostic`](struct.Diagnostic.html)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+/// A position in a config file
+#[derive(Clone, Debug, PartialEq)]
+pub struct Location {
+    pub path: PathBuf,
+    /// The line number starting at `1`
+    pub line: usize,
+    /// The column number starting at `1`
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.path.to_string_lossy(),
+            self.line,
+            self.column
+        )
+    }
+}
+
+/// A problem with a config rendered with the same look and feel as `clap` errors
+///
+/// ```text
+/// error: unknown key `prot`
+///  --> /etc/app.toml:2:1
+///   |
+/// 2 | prot = 80
+///   | ^
+///   = help: did you mean `port`?
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub message: String,
+    pub location: Option<Location>,
+    /// The line of the config file the location points at
+    pub source_line: Option<String>,
+    pub help: Option<String>,
+    pub usage: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Level::Error, message.into())
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Level::Warning, message.into())
+    }
+
+    fn new(level: Level, message: String) -> Self {
+        Self {
+            level,
+            message,
+            location: None,
+            source_line: None,
+            help: None,
+            usage: None,
+        }
+    }
+
+    /// Point at a line of a config file, reading the line so it can be displayed
+    pub fn at(mut self, path: impl AsRef<Path>, line: usize, column: usize) -> Self {
+        let path = path.as_ref();
+        self.source_line = fs::read_to_string(path).ok().and_then(|contents| {
+            contents
+                .lines()
+                .nth(line.saturating_sub(1))
+                .map(String::from)
+        });
+        self.location = Some(Location {
+            path: path.to_path_buf(),
+            line,
+            column,
+        });
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Add a usage hint shown after the diagnostic the same way `clap` shows it
+    pub fn with_usage(mut self, usage: impl Into<String>) -> Self {
+        self.usage = Some(usage.into());
+        self
+    }
+
+    /// A diagnostic for fields which were not set by any layer
+    pub fn missing_fields(fields: &[String]) -> Self {
+        let fields = fields
+            .iter()
+            .map(|f| format!("`{}`", f))
+            .collect::<Vec<_>>()
+            .join(", ");
+        Self::error(message(
+            MessageId::MissingFields,
+            &[("fields", fields.as_str())],
+        ))
+        .with_help(message(MessageId::MissingFieldsHelp, &[]))
+    }
+
+    /// A diagnostic for an `Error` which `clap` does not already render
+    ///
+    /// Returns `None` for `clap` errors, help messages, and generated configs.
+    pub fn from_error(error: &Error) -> Option<Self> {
+        match error {
+            Error::ConfigFile(path, e) => {
+                let diagnostic = Self::error(message(
+                    MessageId::ConfigFile,
+                    &[
+                        ("path", &*path.to_string_lossy()),
+                        ("error", &*e.to_string()),
+                    ],
+                ));
+                // Errors of the TOML deserializer know where in the file they happened
+                let line_col = e
+                    .get_ref()
+                    .and_then(|e| e.downcast_ref::<toml::de::Error>())
+                    .and_then(toml::de::Error::line_col);
+                Some(match line_col {
+                    Some((line, column)) => diagnostic.at(path, line + 1, column + 1),
+                    None => diagnostic,
+                })
+            }
+            Error::UnknownKey { key, suggestion } => {
+                let diagnostic = key.diagnostic_with_level(Level::Error);
+                Some(match suggestion {
+                    Some(suggestion) => diagnostic.with_help(message(
+                        MessageId::DidYouMean,
+                        &[("suggestion", &*format!("`{}`?", suggestion))],
+                    )),
+                    None => diagnostic,
+                })
+            }
+            Error::Env(name, e) => Some(Self::error(message(
+                MessageId::Env,
+                &[("name", name.as_str()), ("error", e.as_str())],
+            ))),
+            Error::Interpolation(e) => Some(Self::error(message(
+                MessageId::Interpolation,
+                &[("error", e.as_str())],
+            ))),
+            Error::Field {
+                path,
+                source,
+                location,
+                message: e,
+            } => {
+                let diagnostic = Self::error(message(
+                    MessageId::InvalidValue,
+                    &[
+                        ("path", path.as_str()),
+                        ("source", &*source.to_string()),
+                        ("error", e.as_str()),
+                    ],
+                ));
+                Some(match location {
+                    Some(location) => diagnostic.at(&location.path, location.line, location.column),
+                    None => diagnostic,
+                })
+            }
+            Error::MissingFields(fields) => Some(Self::missing_fields(fields)),
+            Error::WriteConfig { .. }
+            | Error::Reference(_)
+            | Error::Decrypt { .. }
+            | Error::Constraints(_)
+            | Error::ExpectedError(_) => Some(Self::error(error.to_string())),
+            Error::ConfigGenerated(_) | Error::ConfigExplained(_) | Error::Clap(_) => None,
+        }
+    }
+
+    /// Render the diagnostic optionally using terminal colors
+    pub fn render(&self, color: bool) -> String {
+        let paint = |code: &str, s: &str| {
+            if color {
+                format!("\x1b[{}m{}\x1b[0m", code, s)
+            } else {
+                String::from(s)
+            }
+        };
+        let level_color = match self.level {
+            Level::Error => "1;31",
+            Level::Warning => "1;33",
+        };
+        let level = match self.level {
+            Level::Error => message(MessageId::Error, &[]),
+            Level::Warning => message(MessageId::Warning, &[]),
+        };
+        let mut result = format!("{} {}", paint(level_color, &level), self.message);
+        let gutter = match &self.location {
+            Some(location) => " ".repeat(location.line.to_string().len()),
+            None => String::new(),
+        };
+        if let Some(location) = &self.location {
+            result = format!(
+                "{}\n{}{} {}",
+                result,
+                gutter,
+                paint("1;34", "-->"),
+                location
+            );
+            if let Some(source_line) = &self.source_line {
+                let bar = paint("1;34", "|");
+                result = format!(
+                    "{}\n{} {}\n{} {} {}\n{} {} {}{}",
+                    result,
+                    gutter,
+                    bar,
+                    paint("1;34", &location.line.to_string()),
+                    bar,
+                    source_line,
+                    gutter,
+                    bar,
+                    " ".repeat(location.column.saturating_sub(1)),
+                    paint(level_color, "^")
+                );
+            }
+        }
+        if let Some(help) = &self.help {
+            result = format!(
+                "{}\n{} {} {} {}",
+                result,
+                gutter,
+                paint("1;34", "="),
+                message(MessageId::Help, &[]),
+                help
+            );
+        }
+        if let Some(usage) = &self.usage {
+            result = format!(
+                "{}\n\n{}\n    {}\n\n{}",
+                result,
+                paint("33", &message(MessageId::Usage, &[])),
+                usage,
+                message(
+                    MessageId::MoreInformation,
+                    &[("help", &*paint("32", "--help"))]
+                )
+            );
+        }
+        result
+    }
+
+    /// Write the diagnostic to `stderr`
+    pub fn eprint(&self, color: bool) {
+        writeln!(&mut io::stderr(), "{}", self.render(color)).ok();
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(false))
+    }
+}
+
+impl UnknownKey {
+    /// A diagnostic pointing at the line which sets this key if it can be found
+    pub fn diagnostic(&self) -> Diagnostic {
+        self.diagnostic_with_level(Level::Warning)
+    }
+
+    fn diagnostic_with_level(&self, level: Level) -> Diagnostic {
+        let diagnostic = Diagnostic::new(
+            level,
+            message(MessageId::UnknownKey, &[("key", self.key.as_str())]),
+        );
+        match fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|contents| find_key(&contents, &self.key))
+        {
+            Some((line, column)) => diagnostic.at(&self.path, line, column),
+            None => diagnostic.with_help(message(
+                MessageId::UnknownKeyLocation,
+                &[("path", &*self.path.to_string_lossy())],
+            )),
+        }
+    }
+}
+
+/// Find the line and column which set the dotted `key`
+///
+/// Table headers are followed so only a key at the full path matches. A key which is only set as a
+/// table matches its header. This is a best effort search which does not look into inline tables
+/// or arrays of tables.
+pub(crate) fn find_key(contents: &str, key: &str) -> Option<(usize, usize)> {
+    let key = key.split('.').collect::<Vec<_>>();
+    let mut table = Vec::new();
+    contents.lines().enumerate().find_map(|(i, line)| {
+        let trimmed = line.trim_start();
+        let position = (i + 1, line.len() - trimmed.len() + 1);
+        if trimmed.starts_with('[') {
+            let header = trimmed.trim_start_matches('[');
+            table = split_key(header.split(']').next().unwrap_or_default());
+            if table == key {
+                return Some(position);
+            }
+        } else if let (false, Some(end)) = (trimmed.starts_with('#'), trimmed.find('=')) {
+            let mut path = table.clone();
+            path.extend(split_key(&trimmed[..end]));
+            if path == key {
+                return Some(position);
+            }
+        }
+        None
+    })
+}
+
+/// Split a dotted TOML key removing the quotes and whitespace around each component
+fn split_key(key: &str) -> Vec<&str> {
+    key.split('.')
+        .map(|k| k.trim().trim_matches('"').trim_matches('\''))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_key() {
+        let contents = "name = \"a\"\n[server]\n  prot = 80\n";
+        assert_eq!(find_key(contents, "server.prot"), Some((3, 3)));
+        assert_eq!(find_key(contents, "server"), Some((2, 1)));
+        assert_eq!(find_key(contents, "missing"), None);
+        // Only the key at the full path matches
+        let contents = "prot = 1\n[client]\nprot = 2\n[server]\nprot = 3\nlog.\"level\" = 4\n";
+        assert_eq!(find_key(contents, "server.prot"), Some((5, 1)));
+        assert_eq!(find_key(contents, "prot"), Some((1, 1)));
+        assert_eq!(find_key(contents, "server.log.level"), Some((6, 1)));
+        assert_eq!(find_key(contents, "log.level"), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let diagnostic = Diagnostic::missing_fields(&[String::from("port")]);
+        assert_eq!(
+            diagnostic.render(false),
+            "error: missing required config values `port`\n = help: set them on the command line, in the environment, or in a config file"
+        );
+        assert!(diagnostic
+            .render(true)
+            .starts_with("\x1b[1;31merror:\x1b[0m"));
+    }
+}
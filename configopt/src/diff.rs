@@ -0,0 +1,136 @@
+use std::{
+    error::Error as StdError,
+    fmt,
+    io::{self, Write},
+};
+
+/// The fields which differ between two configs
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConfigDiff {
+    changed: Vec<String>,
+    restart_required: Vec<String>,
+}
+
+impl ConfigDiff {
+    pub fn new(changed: Vec<String>) -> Self {
+        Self {
+            changed,
+            restart_required: Vec::new(),
+        }
+    }
+
+    /// Mark the changed fields at the dotted `paths` as only taking effect after a restart
+    pub fn with_restart_required(mut self, paths: Vec<String>) -> Self {
+        self.restart_required = paths;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty()
+    }
+
+    /// The dotted paths of the changed fields
+    pub fn changed(&self) -> &[String] {
+        &self.changed
+    }
+
+    /// The dotted paths of the changed fields marked `#[configopt(reload = "restart")]`
+    pub fn restart_required(&self) -> &[String] {
+        &self.restart_required
+    }
+
+    /// The dotted paths of the changed fields which take effect on reload
+    pub fn dynamic(&self) -> Vec<&str> {
+        self.changed
+            .iter()
+            .filter(|path| !self.restart_required.contains(path))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Reject a reload which changes a field requiring a restart
+    ///
+    /// Call this before replacing the current config, for example in `Live::try_update`, so a
+    /// reload either fully takes effect or does not happen.
+    pub fn check_reload(&self) -> Result<(), RestartRequired> {
+        if self.restart_required.is_empty() {
+            Ok(())
+        } else {
+            Err(RestartRequired {
+                fields: self.restart_required.clone(),
+            })
+        }
+    }
+
+    /// Warn on `stderr` about each changed field which requires a restart returning if there
+    /// were any
+    ///
+    /// Use this instead of [`check_reload`](#method.check_reload) to apply the other changes
+    /// anyway.
+    pub fn warn_restart_required(&self) -> bool {
+        for path in &self.restart_required {
+            writeln!(
+                &mut io::stderr(),
+                "warning: `{}` changed but only takes effect after a restart",
+                path
+            )
+            .ok();
+        }
+        !self.restart_required.is_empty()
+    }
+
+    /// Check if the field at `path` or any field nested under it changed
+    pub fn contains(&self, path: &str) -> bool {
+        self.changed.iter().any(|changed| {
+            changed == path || (changed.starts_with(path) && changed[path.len()..].starts_with('.'))
+        })
+    }
+
+    /// The names of the instances of the `#[configopt(map)]` field at `path` which were added,
+    /// removed, or changed
+    ///
+    /// A reload handler can use this to restart only the instances whose config changed.
+    pub fn changed_instances(&self, path: &str) -> Vec<&str> {
+        let mut instances = Vec::new();
+        for changed in &self.changed {
+            let instance = changed
+                .strip_prefix(path)
+                .and_then(|rest| rest.strip_prefix('.'))
+                .and_then(|rest| rest.split('.').next());
+            if let Some(instance) = instance {
+                if !instances.contains(&instance) {
+                    instances.push(instance);
+                }
+            }
+        }
+        instances
+    }
+}
+
+impl fmt::Display for ConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for changed in &self.changed {
+            writeln!(f, "{}", changed)?;
+        }
+        Ok(())
+    }
+}
+
+/// A reload changed fields which only take effect after a restart
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RestartRequired {
+    pub fields: Vec<String>,
+}
+
+impl fmt::Display for RestartRequired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = self
+            .fields
+            .iter()
+            .map(|path| format!("`{}`", path))
+            .collect::<Vec<_>>();
+        write!(f, "changing {} requires a restart", fields.join(", "))
+    }
+}
+
+impl StdError for RestartRequired {}
@@ -0,0 +1,57 @@
+use crate::{
+    coerce::{self, Policy},
+    invalid_config_file, Error, Result, Schema,
+};
+use std::{fs, path::Path};
+use toml::value::{Table, Value};
+
+/// Read each file in the directory `path` as the value of a key using `schema` to find the kind
+/// of each value
+///
+/// This is the layout of a Kubernetes ConfigMap or Secret mounted as a volume. The name of a file
+/// is the dotted path of its key (eg `server.port`) and a subdirectory is a nested table so
+/// `server/port` sets the same key. The contents of a file are the value without the trailing
+/// newline. Hidden files are skipped including the `..data` links Kubernetes uses to swap the
+/// files of an update at once. A file which does not refer to a field is kept as a string so it
+/// is reported like any other unknown key.
+pub fn dir_table(path: &Path, schema: &Schema) -> Result<Table> {
+    let mut table = Table::new();
+    read_dir(path, "", schema, &Policy::default(), &mut table)?;
+    Ok(table)
+}
+
+fn read_dir(
+    dir: &Path,
+    prefix: &str,
+    schema: &Schema,
+    policy: &Policy,
+    table: &mut Table,
+) -> Result<()> {
+    let mut entries = fs::read_dir(dir)
+        .and_then(|entries| entries.collect::<std::io::Result<Vec<_>>>())
+        .map_err(|e| Error::ConfigFile(dir.to_path_buf(), e))?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let name = match entry.file_name().into_string() {
+            Ok(name) if !name.starts_with('.') => name,
+            _ => continue,
+        };
+        let path = entry.path();
+        let key = format!("{}{}", prefix, name);
+        // Follow the symlinks of a mounted volume
+        let metadata = fs::metadata(&path).map_err(|e| Error::ConfigFile(path.clone(), e))?;
+        if metadata.is_dir() {
+            read_dir(&path, &format!("{}.", key), schema, policy, table)?;
+            continue;
+        }
+        let contents = fs::read_to_string(&path).map_err(|e| Error::ConfigFile(path.clone(), e))?;
+        let contents = contents.trim_end_matches(|c| c == '\n' || c == '\r');
+        let value = match coerce::find_field(schema, &key) {
+            Some(field) => coerce::coerce(contents, &field.kind(), policy)
+                .map_err(|e| invalid_config_file(&path, format!("`{}` {}", key, e)))?,
+            None => Value::String(String::from(contents)),
+        };
+        coerce::set_path(table, &key, value).map_err(|e| invalid_config_file(&path, e))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use crate::{invalid_config_file, Error, Result};
+use std::{env, fs, io, path::Path};
+
+/// Parse a single value removing its quotes
+///
+/// A single quoted value is used verbatim. A double quoted value may use the escapes `\n`, `\t`,
+/// `\"`, and `\\`. An unquoted value ends at a ` #` comment and has the whitespace around it
+/// removed.
+fn parse_value(value: &str) -> std::result::Result<String, String> {
+    let value = value.trim();
+    if value.starts_with('\'') {
+        return match value[1..].find('\'') {
+            Some(end) => Ok(String::from(&value[1..=end])),
+            None => Err(String::from("the single quote is not closed")),
+        };
+    }
+    if value.starts_with('"') {
+        let mut result = String::new();
+        let mut chars = value[1..].chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Ok(result),
+                '\\' => match chars.next() {
+                    Some('n') => result.push('\n'),
+                    Some('t') => result.push('\t'),
+                    Some(c) => result.push(c),
+                    None => break,
+                },
+                c => result.push(c),
+            }
+        }
+        return Err(String::from("the double quote is not closed"));
+    }
+    let value = match value.find(" #") {
+        Some(comment) => &value[..comment],
+        None => value,
+    };
+    Ok(String::from(value.trim()))
+}
+
+/// Parse the `KEY=value` lines of a dotenv file
+///
+/// Blank lines and lines starting with `#` are ignored and a line may start with `export` so the
+/// file can also be sourced by a shell.
+pub(crate) fn parse(contents: &str) -> std::result::Result<Vec<(String, String)>, String> {
+    let mut vars = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = if line.starts_with("export ") {
+            line["export ".len()..].trim_start()
+        } else {
+            line
+        };
+        let error = |e| format!("line {}: {}", i + 1, e);
+        let separator = line
+            .find('=')
+            .ok_or_else(|| error(String::from("expected `KEY=value`")))?;
+        let name = line[..separator].trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(error(format!("`{}` is not a variable name", name)));
+        }
+        let value = parse_value(&line[separator + 1..]).map_err(error)?;
+        vars.push((String::from(name), value));
+    }
+    Ok(vars)
+}
+
+/// Set the environment variables of the dotenv file at `path` which are not already set,
+/// returning the names of the variables which were set
+///
+/// Variables in the real environment take precedence over the file so a value exported in a
+/// shell or set by a deployment always wins. Load the file before parsing so its variables are
+/// seen by every environment variable layer (`env = ".."` args, `configopt(env_json)`, and
+/// `configopt(env_prefix)`). It is not an error if the file does not exist so the same binary
+/// runs where there is no `.env` file.
+///
+/// Use `#[configopt(dotenv(".env"))]` to load a file before parsing the CLI.
+pub fn load_dotenv(path: impl AsRef<Path>) -> Result<Vec<String>> {
+    let path = path.as_ref();
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(Error::ConfigFile(path.to_path_buf(), e)),
+    };
+    let vars = parse(&contents).map_err(|e| invalid_config_file(path, e))?;
+    let mut set = Vec::new();
+    for (name, value) in vars {
+        if env::var_os(&name).is_none() {
+            env::set_var(&name, value);
+            set.push(name);
+        }
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let vars = parse(
+            "# comment\n\nexport A=1\nB = two words # comment\nC='${literal} \\n'\nD=\"a\\nb \\\"c\\\"\"\nE=\n",
+        )
+        .unwrap();
+        assert_eq!(
+            vars,
+            vec![
+                (String::from("A"), String::from("1")),
+                (String::from("B"), String::from("two words")),
+                (String::from("C"), String::from("${literal} \\n")),
+                (String::from("D"), String::from("a\nb \"c\"")),
+                (String::from("E"), String::new()),
+            ]
+        );
+        assert_eq!(parse("A\n").unwrap_err(), "line 1: expected `KEY=value`");
+        assert!(parse("A=\"open\n").is_err());
+        assert!(parse("A B=1\n").is_err());
+    }
+}
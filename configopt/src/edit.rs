@@ -0,0 +1,168 @@
+//! Change the keys of a config file a user maintains without losing their formatting
+//!
+//! A TOML file is edited in place with `toml_edit` so comments, the order of keys, and whitespace
+//! are kept and only the changed value is rewritten. Files in the other formats are parsed and
+//! written again which loses their comments.
+
+use crate::{
+    coerce, config_format, field_path::field_value, ConfigFormat, ConfigOptType, Error, FieldPath,
+    Result, Value,
+};
+use serde::de::DeserializeOwned;
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+};
+use toml::value::Table;
+use toml_edit::{Document, Item};
+
+fn invalid(path: &Path, message: impl Into<String>) -> Error {
+    Error::ConfigFile(
+        path.to_path_buf(),
+        io::Error::new(ErrorKind::InvalidInput, message.into()),
+    )
+}
+
+/// The contents of `path` or an empty document if it does not exist yet
+fn read(path: &Path) -> Result<String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(contents),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(Error::ConfigFile(path.to_path_buf(), e)),
+    }
+}
+
+/// Set `field` to `value` keeping the decor (eg a trailing comment) of the value it replaces
+fn set_item(document: &mut Document, field: &FieldPath, value: toml_edit::Value) {
+    let (last, parents) = field.keys().split_last().expect("a path has a key");
+    let mut item = &mut document.root;
+    for key in parents {
+        item = &mut item[key.as_str()];
+    }
+    let item = &mut item[last.as_str()];
+    let value = match item.as_value() {
+        Some(old) => toml_edit::decorated(value, old.decor().prefix(), old.decor().suffix()),
+        None => value,
+    };
+    *item = Item::Value(value);
+}
+
+/// Remove `field` returning if it was set
+fn remove_item(document: &mut Document, field: &FieldPath) -> bool {
+    let (last, parents) = field.keys().split_last().expect("a path has a key");
+    let mut item = &mut document.root;
+    for key in parents {
+        item = match item.as_table_mut() {
+            Some(table) if table.contains_key(key) => &mut table[key.as_str()],
+            _ => return false,
+        };
+    }
+    match item {
+        Item::Table(table) => table.remove(last).map_or(false, |item| !item.is_none()),
+        Item::Value(value) => value
+            .as_inline_table_mut()
+            .and_then(|table| table.remove(last))
+            .is_some(),
+        _ => false,
+    }
+}
+
+/// Remove `field` from a parsed table returning if it was set
+fn remove_path(table: &mut Table, field: &FieldPath) -> bool {
+    let (last, parents) = field.keys().split_last().expect("a path has a key");
+    let mut table = table;
+    for key in parents {
+        table = match table.get_mut(key) {
+            Some(toml::Value::Table(nested)) => nested,
+            _ => return false,
+        };
+    }
+    table.remove(last).is_some()
+}
+
+/// Check the edited document still makes a valid `T`
+fn check<T: DeserializeOwned>(path: &Path, contents: &str) -> Result<()> {
+    toml::from_str::<T>(contents)
+        .map(|_| ())
+        .map_err(|e| Error::from_toml(path, e))
+}
+
+/// Apply an edit to the config file at `path` writing it if the edit returns that it changed it
+fn edit<T>(
+    path: &Path,
+    edit_toml: impl FnOnce(&mut Document) -> Result<bool>,
+    edit_table: impl FnOnce(&mut Table) -> Result<bool>,
+) -> Result<bool>
+where
+    T: ConfigOptType + DeserializeOwned,
+{
+    let format = ConfigFormat::from_path(path).unwrap_or(ConfigFormat::Toml);
+    if format == ConfigFormat::Toml {
+        let mut document = read(path)?
+            .parse::<Document>()
+            .map_err(|e| invalid(path, e.to_string()))?;
+        if !edit_toml(&mut document)? {
+            return Ok(false);
+        }
+        let contents = document.to_string();
+        check::<T>(path, &contents)?;
+        return config_format::write(path, &contents).map(|()| true);
+    }
+    let contents = read(path)?;
+    let mut table = format.parse_table(&contents, path, T::runtime_schema)?;
+    if !edit_table(&mut table)? {
+        return Ok(false);
+    }
+    let toml_config = toml::to_string(&table)
+        .map_err(|e| invalid(path, format!("failed to render, err: {}", e)))?;
+    check::<T>(path, &toml_config)?;
+    config_format::write(path, &format.render(&table, path)?).map(|()| true)
+}
+
+/// Set the key `field` of the config file at `path` creating the file if it does not exist
+///
+/// `field` must be a key of `T` and a string value is parsed as the kind of value the field holds
+/// like [`set_field`](fn.set_field.html). The file is only written if it is still a valid config
+/// after the change.
+pub fn set_in_file<T>(path: impl AsRef<Path>, field: &FieldPath, value: Value) -> Result<()>
+where
+    T: ConfigOptType + DeserializeOwned,
+{
+    let path = path.as_ref();
+    let value = field_value::<T>(field, value).map_err(|e| invalid(path, e))?;
+    let toml_value = value.clone();
+    edit::<T>(
+        path,
+        |document| {
+            let value = crate::inline_toml(&toml_value)
+                .parse::<toml_edit::Value>()
+                .map_err(|e| invalid(path, e.to_string()))?;
+            set_item(document, field, value);
+            Ok(true)
+        },
+        |table| {
+            coerce::set_path(table, &field.to_string(), value).map_err(|e| invalid(path, e))?;
+            Ok(true)
+        },
+    )
+    .map(|_| ())
+}
+
+/// Remove the key `field` from the config file at `path` so a lower layer or the default applies
+///
+/// Returns if the key was set. The file is not written if the key was not set.
+pub fn unset_in_file<T>(path: impl AsRef<Path>, field: &FieldPath) -> Result<bool>
+where
+    T: ConfigOptType + DeserializeOwned,
+{
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(false);
+    }
+    edit::<T>(
+        path,
+        |document| Ok(remove_item(document, field)),
+        |table| Ok(remove_path(table, field)),
+    )
+}
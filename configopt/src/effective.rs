@@ -0,0 +1,73 @@
+use crate::{ConfigOptType, LoadTrace, TraceSource, REDACTED};
+use serde::Serialize;
+use toml::Value;
+
+/// The value of a field of the running config and where it came from
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct EffectiveField {
+    /// The dotted path of the field
+    pub path: String,
+    /// The value as inline TOML or `REDACTED` for secrets
+    pub value: String,
+    /// The layer which set the value or `None` if the trace did not record it
+    pub source: Option<TraceSource>,
+}
+
+/// Describe each field which is set in `config` with the source recorded in `trace`
+///
+/// Secrets are never rendered and fields which are left out of the generated config (eg hidden
+/// fields and leaves) are skipped. Capture `trace` with `LoadTrace::capture` around the load which
+/// produced `config`.
+pub fn effective_fields<T: ConfigOptType>(config: &T, trace: &LoadTrace) -> Vec<EffectiveField> {
+    let toml_config = config.toml_config();
+    let values = toml_config
+        .parse::<Value>()
+        .unwrap_or_else(|_| Value::Table(Default::default()));
+    config
+        .set_fields()
+        .into_iter()
+        .filter_map(|path| {
+            let value = match lookup(&values, &path) {
+                Some(value) => value.to_string(),
+                // Secrets are only written as a comment
+                None if toml_config
+                    .lines()
+                    .any(|l| l == format!("# {} = {}", path, REDACTED)) =>
+                {
+                    String::from(REDACTED)
+                }
+                None => return None,
+            };
+            Some(EffectiveField {
+                source: trace.source_of(&path).cloned(),
+                path,
+                value,
+            })
+        })
+        .collect()
+}
+
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, key| value.get(key))
+}
+
+/// Emit one `tracing` event per field which is set in `config` with its value and source
+///
+/// The events have the target `configopt::effective` and the fields `path`, `value`, and `source`.
+#[cfg(feature = "effective-config")]
+pub fn log_effective_config<T: ConfigOptType>(config: &T, trace: &LoadTrace) {
+    for field in effective_fields(config, trace) {
+        let source = field
+            .source
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_else(|| String::from("unknown"));
+        tracing::info!(
+            target: "configopt::effective",
+            path = field.path.as_str(),
+            value = field.value.as_str(),
+            source = source.as_str(),
+            "effective config"
+        );
+    }
+}
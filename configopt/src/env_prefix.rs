@@ -0,0 +1,116 @@
+use crate::{
+    coerce::{self, Policy},
+    Error, Result, Schema,
+};
+use std::{borrow::Cow, env};
+use toml::value::Table;
+
+/// The default separator between the keys of a nested path (eg `MYAPP_SERVER__PORT`)
+pub const DEFAULT_ENV_SEPARATOR: &str = "__";
+
+/// Find the dotted path of the schema field the keys of a variable name refer to
+///
+/// Keys are matched ignoring case and treating `-` as `_` so kebab case keys can be set. A `*` of
+/// the schema (the instances of a map) matches any key which is used as written.
+fn resolve_path(schema: &Schema, keys: &[&str]) -> Option<String> {
+    schema.fields.iter().find_map(|field| {
+        let pattern = field.path.split('.').collect::<Vec<_>>();
+        if pattern.len() != keys.len() {
+            return None;
+        }
+        pattern
+            .iter()
+            .zip(keys)
+            .map(|(p, k)| match *p {
+                "*" => Some(*k),
+                p if p.replace('-', "_").eq_ignore_ascii_case(k) => Some(p),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(|path| path.join("."))
+    })
+}
+
+/// Collect the environment variables starting with `prefix` into a table using `schema` to find
+/// the key and the kind of value of each
+///
+/// The rest of the name of a variable is split on `separator` into the keys of a nested path so
+/// with the prefix `MYAPP_`, `MYAPP_SERVER__PORT=8080` sets `port = 8080` in the `server` table.
+/// Variables which do not refer to a field are ignored. They are often set by something else, for
+/// example a container orchestrator which adds `<SERVICE>_PORT` for every service.
+pub fn prefixed_env_table(prefix: &str, separator: &str, schema: &Schema) -> Result<Table> {
+    let policy = Policy::default();
+    let mut table = Table::new();
+    let mut vars = env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+        .filter(|(name, _)| name.starts_with(prefix) && name.len() > prefix.len())
+        .collect::<Vec<_>>();
+    // Set the variables in a stable order so a conflict always fails the same way
+    vars.sort();
+    for (name, value) in vars {
+        let keys = name[prefix.len()..].split(separator).collect::<Vec<_>>();
+        let path = match resolve_path(schema, &keys) {
+            Some(path) => path,
+            None => continue,
+        };
+        let decode = coerce::find_field(schema, &path).map_or(false, |field| field.decode_env);
+        let value = if decode {
+            crate::decode_env_value(&value).map_err(|e| Error::Env(name.clone(), e))?
+        } else {
+            Cow::Borrowed(value.as_str())
+        };
+        let value = coerce::coerce_path(schema, &path, &value, &policy)
+            .map_err(|e| Error::Env(name.clone(), e))?;
+        coerce::set_path(&mut table, &path, value).map_err(|e| Error::Env(name.clone(), e))?;
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SchemaField;
+
+    fn field(path: &str, ty: &str) -> SchemaField {
+        SchemaField {
+            path: String::from(path),
+            ty: String::from(ty),
+            required: false,
+            aliases: Vec::new(),
+            format: None,
+            env: None,
+            decode_env: false,
+            arg: None,
+            values: Vec::new(),
+            constraints: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        let schema = Schema::new(vec![
+            field("log_level", "String"),
+            field("server.port", "u16"),
+            field("outputs.*.path", "String"),
+            field("max-connections", "u32"),
+        ]);
+        assert_eq!(
+            resolve_path(&schema, &["LOG_LEVEL"]).as_deref(),
+            Some("log_level")
+        );
+        assert_eq!(
+            resolve_path(&schema, &["SERVER", "PORT"]).as_deref(),
+            Some("server.port")
+        );
+        assert_eq!(
+            resolve_path(&schema, &["OUTPUTS", "stdout", "PATH"]).as_deref(),
+            Some("outputs.stdout.path")
+        );
+        assert_eq!(
+            resolve_path(&schema, &["MAX_CONNECTIONS"]).as_deref(),
+            Some("max-connections")
+        );
+        assert_eq!(resolve_path(&schema, &["SERVER"]), None);
+        assert_eq!(resolve_path(&schema, &["SERVER", "HOST"]), None);
+    }
+}
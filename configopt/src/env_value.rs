@@ -0,0 +1,166 @@
+use std::{borrow::Cow, ffi::OsString};
+use structopt::clap::{App, Error as ClapError, ErrorKind as ClapErrorKind, Result as ClapResult};
+
+const BASE64: &str = "base64:";
+const JSON: &str = "json:";
+const PERCENT: &str = "percent:";
+const RAW: &str = "raw:";
+
+#[cfg(feature = "env-encoding")]
+fn decode_base64(encoded: &str) -> Result<Vec<u8>, String> {
+    // Accept both the standard and URL safe alphabets
+    let config = if encoded.contains(|c| c == '-' || c == '_') {
+        base64::URL_SAFE
+    } else {
+        base64::STANDARD
+    };
+    base64::decode_config(encoded, config).map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "env-encoding")]
+fn decode_percent(encoded: &str) -> Result<Vec<u8>, String> {
+    // A malformed escape is kept as it is by `percent_decode_str` so reject it first
+    let bytes = encoded.as_bytes();
+    let malformed = (0..bytes.len()).find(|&i| {
+        bytes[i] == b'%'
+            && !bytes
+                .get(i + 1..i + 3)
+                .map_or(false, |hex| hex.iter().all(u8::is_ascii_hexdigit))
+    });
+    if let Some(i) = malformed {
+        return Err(format!("invalid percent encoding at position {}", i));
+    }
+    Ok(percent_encoding::percent_decode_str(encoded).collect())
+}
+
+#[cfg(not(feature = "env-encoding"))]
+fn decode_base64(_encoded: &str) -> Result<Vec<u8>, String> {
+    Err(String::from(
+        "decoding `base64:` values requires the `env-encoding` feature",
+    ))
+}
+
+#[cfg(not(feature = "env-encoding"))]
+fn decode_percent(_encoded: &str) -> Result<Vec<u8>, String> {
+    Err(String::from(
+        "decoding `percent:` values requires the `env-encoding` feature",
+    ))
+}
+
+fn utf8(decoded: Vec<u8>) -> Result<String, String> {
+    String::from_utf8(decoded).map_err(|_| String::from("decoded value is not valid UTF-8"))
+}
+
+/// Decode the value of an environment variable
+///
+/// This is applied to the environment variables of `#[configopt(decode_env)]` fields. Values are
+/// used verbatim unless they start with one of these prefixes:
+///
+/// * `base64:` - the rest of the value is base64 encoded (eg for binary safe multi-line values)
+/// * `json:` - the rest of the value is JSON. A JSON string is unescaped (eg `json:"a\nb"`) and any
+/// other JSON value is used as its compact text.
+/// * `percent:` - the rest of the value is percent encoded (eg `percent:a%20b`)
+/// * `raw:` - the rest of the value is used verbatim (eg to set a value which starts with a
+/// prefix)
+///
+/// Quotes are never removed from unprefixed values. Decoding `base64:` and `percent:` values
+/// requires the `env-encoding` feature, without it they are an error.
+pub fn decode_env_value(value: &str) -> Result<Cow<'_, str>, String> {
+    if let Some(encoded) = value.strip_prefix(BASE64) {
+        Ok(Cow::Owned(utf8(decode_base64(encoded)?)?))
+    } else if let Some(encoded) = value.strip_prefix(JSON) {
+        let json = serde_json::from_str::<serde_json::Value>(encoded).map_err(|e| e.to_string())?;
+        Ok(Cow::Owned(match json {
+            serde_json::Value::String(s) => s,
+            json => json.to_string(),
+        }))
+    } else if let Some(encoded) = value.strip_prefix(PERCENT) {
+        Ok(Cow::Owned(utf8(decode_percent(encoded)?)?))
+    } else if let Some(raw) = value.strip_prefix(RAW) {
+        Ok(Cow::Borrowed(raw))
+    } else {
+        Ok(Cow::Borrowed(value))
+    }
+}
+
+// Like setting defaults this reaches into clap internals. Clap reads the environment variables
+// when the app is built so the values can be decoded before parsing. Only the arguments named in
+// `decoded` opted in with `#[configopt(decode_env)]` so the value of any other variable keeps its
+// meaning even if it starts with a prefix.
+pub(crate) fn decode_app(app: &mut App<'_, '_>, decoded: &[&str]) -> ClapResult<()> {
+    fn decode(name: &str, env: &mut OsString) -> ClapResult<()> {
+        let decoded = match env.to_str().map(decode_env_value) {
+            Some(Ok(Cow::Owned(decoded))) => decoded,
+            Some(Ok(Cow::Borrowed(decoded))) if decoded.len() != env.len() => String::from(decoded),
+            Some(Err(e)) => {
+                return Err(ClapError::with_description(
+                    &format!("Invalid value for '{}' from the environment: {}", name, e),
+                    ClapErrorKind::InvalidValue,
+                ))
+            }
+            _ => return Ok(()),
+        };
+        *env = OsString::from(decoded);
+        Ok(())
+    }
+
+    if decoded.is_empty() {
+        return Ok(());
+    }
+    for arg in &mut app.p.opts {
+        if let (true, Some((_, Some(env)))) = (decoded.contains(&arg.b.name), &mut arg.v.env) {
+            decode(arg.b.name, env)?;
+        }
+    }
+    for (_, arg) in &mut app.p.positionals {
+        if let (true, Some((_, Some(env)))) = (decoded.contains(&arg.b.name), &mut arg.v.env) {
+            decode(arg.b.name, env)?;
+        }
+    }
+    for app in &mut app.p.subcommands {
+        decode_app(app, decoded)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_prefixed_values() {
+        assert_eq!(decode_env_value("plain").unwrap(), "plain");
+        assert_eq!(decode_env_value("\"quoted\"").unwrap(), "\"quoted\"");
+        assert_eq!(decode_env_value("json:\"a\\nb\"").unwrap(), "a\nb");
+        assert_eq!(
+            decode_env_value("json:{\"a\": [1, 2]}").unwrap(),
+            "{\"a\":[1,2]}"
+        );
+        assert_eq!(decode_env_value("raw:json:x").unwrap(), "json:x");
+        assert!(decode_env_value("json:{").is_err());
+    }
+
+    #[cfg(feature = "env-encoding")]
+    #[test]
+    fn decode_encoded_values() {
+        assert_eq!(decode_env_value("base64:YQpi").unwrap(), "a\nb");
+        assert_eq!(decode_env_value("base64:YWJj").unwrap(), "abc");
+        assert_eq!(decode_env_value("base64:YWI=").unwrap(), "ab");
+        assert_eq!(decode_env_value("percent:a%20b%2Fc").unwrap(), "a b/c");
+        assert_eq!(decode_env_value("base64:Pz8_").unwrap(), "???");
+        assert!(decode_env_value("base64:a*").is_err());
+        // The bits after the last byte must be zero
+        assert!(decode_env_value("base64:YR==").is_err());
+        assert!(decode_env_value("percent:%zz").is_err());
+        assert!(decode_env_value("percent:%+1").is_err());
+        assert!(decode_env_value("percent:%4").is_err());
+    }
+
+    #[cfg(not(feature = "env-encoding"))]
+    #[test]
+    fn decode_encoded_values_requires_feature() {
+        assert!(decode_env_value("base64:YQ==").is_err());
+        assert!(decode_env_value("percent:a%20b").is_err());
+        assert_eq!(decode_env_value("raw:base64:YQ==").unwrap(), "base64:YQ==");
+    }
+}
@@ -1,15 +1,60 @@
+use crate::{
+    error_policy,
+    messages::{message, MessageId},
+    ErrorPolicy, Location, TraceSource, UnknownKey, Violation,
+};
 use std::{
     fmt,
     io::{self, Error as IoError, ErrorKind as IoErrorKind, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process,
 };
 use structopt::clap::{Error as ClapError, ErrorKind as ClapErrorKind};
 
+const FOR_KEY: &str = " for key `";
+const AT_LINE: &str = " at line ";
+
 #[derive(Debug)]
 pub enum Error {
     ConfigGenerated(String),
+    /// The table of the effective config requested with `--explain-config`
+    ConfigExplained(String),
     ConfigFile(PathBuf, IoError),
+    /// A config file of a `#[configopt(strict)]` type set a key which is not a field
+    UnknownKey {
+        key: UnknownKey,
+        /// The closest known key if one is close enough to be a likely typo
+        suggestion: Option<String>,
+    },
+    /// A config could not be rendered in the format of the file it is written to
+    WriteConfig {
+        path: PathBuf,
+        message: String,
+    },
+    Env(String, String),
+    Interpolation(String),
+    /// A string value refers to a value which could not be resolved
+    Reference(String),
+    /// The value of a `#[configopt(decrypt)]` field could not be decrypted
+    Decrypt {
+        /// The dotted path of the field
+        path: String,
+        message: String,
+    },
+    /// The value a layer set for a field could not be used
+    Field {
+        /// The dotted path of the field
+        path: String,
+        /// The layer which set the value
+        source: TraceSource,
+        /// Where the value is in the config file if it came from one
+        location: Option<Location>,
+        message: String,
+    },
+    /// The dotted paths of the required fields which no layer set
+    MissingFields(Vec<String>),
+    /// The merged values which do not satisfy the constraints of their fields
+    Constraints(Vec<Violation>),
     ExpectedError(ClapError),
     Clap(ClapError),
 }
@@ -26,7 +71,17 @@ impl Error {
     pub fn use_stderr(&self) -> bool {
         match self {
             Self::ConfigGenerated(_) => false,
+            Self::ConfigExplained(_) => false,
             Self::ConfigFile(_, _) => true,
+            Self::UnknownKey { .. } => true,
+            Self::WriteConfig { .. } => true,
+            Self::Env(_, _) => true,
+            Self::Interpolation(_) => true,
+            Self::Reference(_) => true,
+            Self::Decrypt { .. } => true,
+            Self::Field { .. } => true,
+            Self::MissingFields(_) => true,
+            Self::Constraints(_) => true,
             Self::ExpectedError(e) => e.use_stderr(),
             Self::Clap(e) => e.use_stderr(),
         }
@@ -34,15 +89,22 @@ impl Error {
 
     /// Prints the error to `stderr` or `stdout` and exits.
     ///
-    /// This exits with a `0` when writing to `stdout` and `1` when writing to `stderr`.
+    /// This uses the installed [`ErrorPolicy`](struct.ErrorPolicy.html) if there is one. Otherwise,
+    /// this exits with a `0` when writing to `stdout` and `1` when writing to `stderr`.
     pub fn exit(&self) -> ! {
-        self.exit_with_codes(1, 0)
+        match ErrorPolicy::installed() {
+            Some(policy) => policy.exit(self),
+            None => self.exit_with_codes(0, 1),
+        }
     }
 
     /// Prints the error to `stderr` or `stdout` and exits with the specified code.
+    ///
+    /// Errors are rendered as a [`Diagnostic`](struct.Diagnostic.html) the same way the default
+    /// [`ErrorPolicy`](struct.ErrorPolicy.html) renders them.
     pub fn exit_with_codes(&self, stdout_exit: i32, stderr_exit: i32) -> ! {
         if self.use_stderr() {
-            wlnerr!("{}", self);
+            wlnerr!("{}", error_policy::render_text(self, false));
             process::exit(stderr_exit);
         }
         let out = io::stdout();
@@ -57,16 +119,172 @@ impl Error {
             _ => false,
         }
     }
+
+    /// The dotted path of the field this error is about
+    pub fn field_path(&self) -> Option<&str> {
+        match self {
+            Self::Field { path, .. } => Some(path),
+            Self::Constraints(violations) => match violations.as_slice() {
+                [Violation::Constraint { path, .. }] => Some(path),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Convert an error deserializing the TOML config file at `path` keeping the field and
+    /// position the deserializer reported
+    pub(crate) fn from_toml(path: &Path, e: toml::de::Error) -> Self {
+        let text = e.to_string();
+        let location = e.line_col().map(|(line, column)| Location {
+            path: path.to_path_buf(),
+            line: line + 1,
+            column: column + 1,
+        });
+        Self::from_toml_text(path, &text, location)
+            // Keep the deserializer error so diagnostics can point at its position
+            .unwrap_or_else(|| {
+                Self::ConfigFile(
+                    path.to_path_buf(),
+                    IoError::new(IoErrorKind::InvalidData, e),
+                )
+            })
+    }
+
+    /// Convert an error deserializing a table read from the config file at `path` keeping the
+    /// field the deserializer reported
+    ///
+    /// The table is deserialized from its own rendering so the position is left out as it does not
+    /// point into the file.
+    pub(crate) fn from_toml_table(path: &Path, e: toml::de::Error) -> Self {
+        let text = e.to_string();
+        let text = match (e.line_col(), text.rfind(AT_LINE)) {
+            (Some(_), Some(end)) => &text[..end],
+            _ => text.as_str(),
+        };
+        Self::from_toml_text(path, text, None).unwrap_or_else(|| {
+            Self::ConfigFile(
+                path.to_path_buf(),
+                IoError::new(IoErrorKind::InvalidData, text),
+            )
+        })
+    }
+
+    // The error about the field named in `text` if it names one
+    fn from_toml_text(path: &Path, text: &str, location: Option<Location>) -> Option<Self> {
+        let start = text.find(FOR_KEY)?;
+        let rest = &text[start + FOR_KEY.len()..];
+        let end = rest.find('`')?;
+        Some(Self::Field {
+            path: String::from(&rest[..end]),
+            source: TraceSource::ConfigFile {
+                path: path.to_string_lossy().into_owned(),
+            },
+            location,
+            message: String::from(&text[..start]),
+        })
+    }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Error::ConfigGenerated(config) => write!(f, "{}", config),
-            Self::ConfigFile(path, e) => write!(f, "Failed to parse file '{}', err: {}", path.to_string_lossy(), e),
-            Error::ExpectedError(e) => write!(f, "The `configopt` app generated an error, but the actual app did not. This should never happen. err: {}", e),
-            Error::Clap(e) => write!(f, "{}", e),
-        }
+        let rendered = match self {
+            Error::ConfigGenerated(config) => return write!(f, "{}", config),
+            Self::ConfigExplained(table) => return write!(f, "{}", table),
+            Error::Clap(e) => return write!(f, "{}", e),
+            Self::ConfigFile(path, e) => message(
+                MessageId::ConfigFile,
+                &[
+                    ("path", &*path.to_string_lossy()),
+                    ("error", &*e.to_string()),
+                ],
+            ),
+            Self::UnknownKey {
+                key,
+                suggestion: Some(suggestion),
+            } => message(
+                MessageId::StrictUnknownKeyDidYouMean,
+                &[
+                    ("path", &*key.path.to_string_lossy()),
+                    ("key", key.key.as_str()),
+                    ("suggestion", suggestion.as_str()),
+                ],
+            ),
+            Self::UnknownKey {
+                key,
+                suggestion: None,
+            } => message(
+                MessageId::StrictUnknownKey,
+                &[
+                    ("path", &*key.path.to_string_lossy()),
+                    ("key", key.key.as_str()),
+                ],
+            ),
+            Self::WriteConfig { path, message: e } => message(
+                MessageId::WriteConfig,
+                &[("path", &*path.to_string_lossy()), ("error", e.as_str())],
+            ),
+            Self::Env(name, e) => message(
+                MessageId::Env,
+                &[("name", name.as_str()), ("error", e.as_str())],
+            ),
+            Self::Interpolation(e) => message(MessageId::Interpolation, &[("error", e.as_str())]),
+            Self::Reference(e) => message(MessageId::Reference, &[("error", e.as_str())]),
+            Self::Decrypt { path, message: e } => message(
+                MessageId::Decrypt,
+                &[("path", path.as_str()), ("error", e.as_str())],
+            ),
+            Self::Field {
+                path,
+                location: Some(location),
+                message: e,
+                ..
+            } => message(
+                MessageId::FieldAt,
+                &[
+                    ("path", path.as_str()),
+                    ("location", &*location.to_string()),
+                    ("error", e.as_str()),
+                ],
+            ),
+            Self::Field {
+                path,
+                source,
+                location: None,
+                message: e,
+            } => message(
+                MessageId::FieldFrom,
+                &[
+                    ("path", path.as_str()),
+                    ("source", &*source.to_string()),
+                    ("error", e.as_str()),
+                ],
+            ),
+            Self::MissingFields(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|f| format!("`{}`", f))
+                    .collect::<Vec<_>>();
+                message(
+                    MessageId::MissingFieldsError,
+                    &[("fields", &*fields.join(", "))],
+                )
+            }
+            Self::Constraints(violations) => {
+                let violations = violations
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>();
+                message(
+                    MessageId::Constraints,
+                    &[("violations", &*violations.join(", "))],
+                )
+            }
+            Error::ExpectedError(e) => {
+                message(MessageId::ExpectedError, &[("error", &*e.to_string())])
+            }
+        };
+        write!(f, "{}", rendered)
     }
 }
 
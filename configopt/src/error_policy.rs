@@ -0,0 +1,267 @@
+use crate::{Diagnostic, Error, TraceSource};
+use lazy_static::lazy_static;
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    process,
+    sync::{Arc, RwLock},
+};
+use structopt::clap::ErrorKind as ClapErrorKind;
+
+lazy_static! {
+    static ref INSTALLED_POLICY: RwLock<Option<Arc<ErrorPolicy>>> = RwLock::new(None);
+}
+
+/// The broad category of an [`Error`](enum.Error.html) used to select an exit code
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// A config was generated with `--generate-config` or explained with `--explain-config`
+    Generated,
+    /// Help or version information was requested
+    Help,
+    /// The command line arguments were invalid
+    Usage,
+    /// A config file could not be read or parsed
+    ConfigFile,
+    /// An environment variable could not be parsed
+    Env,
+    /// An internal inconsistency between the `configopt` and actual app
+    Internal,
+}
+
+impl ErrorCategory {
+    /// The name used for this category when rendering as JSON
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Generated => "generated",
+            Self::Help => "help",
+            Self::Usage => "usage",
+            Self::ConfigFile => "config_file",
+            Self::Env => "env",
+            Self::Internal => "internal",
+        }
+    }
+
+    fn default_exit_code(self) -> i32 {
+        match self {
+            Self::Generated | Self::Help => 0,
+            Self::Usage | Self::ConfigFile | Self::Env | Self::Internal => 1,
+        }
+    }
+}
+
+/// How an error is rendered before exiting
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderStyle {
+    /// The same layout `clap` uses for its errors
+    Plain,
+    /// Like `Plain` with colors the way `clap` colors its errors
+    Colored,
+    /// A single line JSON object with `category` and `message` keys
+    Json,
+}
+
+type Renderer = dyn Fn(&Error, ErrorCategory) -> String + Send + Sync;
+
+/// Controls how fatal errors are rendered and which exit code each category uses
+///
+/// A policy can be used directly with [`Error::exit_with_policy`](enum.Error.html#method.exit_with_policy)
+/// or installed globally so that [`Error::exit`](enum.Error.html#method.exit), and therefore all
+/// the non-`try_` constructors, use it.
+pub struct ErrorPolicy {
+    style: RenderStyle,
+    exit_codes: HashMap<ErrorCategory, i32>,
+    renderer: Option<Box<Renderer>>,
+}
+
+impl Default for ErrorPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ErrorPolicy {
+    pub fn new() -> Self {
+        Self {
+            style: RenderStyle::Plain,
+            exit_codes: HashMap::new(),
+            renderer: None,
+        }
+    }
+
+    /// Set the style used to render errors
+    pub fn style(mut self, style: RenderStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Set the exit code used for a category of errors
+    pub fn exit_code(mut self, category: ErrorCategory, code: i32) -> Self {
+        self.exit_codes.insert(category, code);
+        self
+    }
+
+    /// Replace the builtin rendering with a custom function
+    pub fn renderer(
+        mut self,
+        renderer: impl Fn(&Error, ErrorCategory) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.renderer = Some(Box::new(renderer));
+        self
+    }
+
+    /// Use this policy for all calls to `Error::exit`
+    pub fn install(self) {
+        *INSTALLED_POLICY
+            .write()
+            .expect("error policy lock poisoned") = Some(Arc::new(self));
+    }
+
+    /// The currently installed policy if there is one
+    pub fn installed() -> Option<Arc<ErrorPolicy>> {
+        INSTALLED_POLICY
+            .read()
+            .expect("error policy lock poisoned")
+            .clone()
+    }
+
+    /// The exit code for a category of errors
+    pub fn code_for(&self, category: ErrorCategory) -> i32 {
+        self.exit_codes
+            .get(&category)
+            .copied()
+            .unwrap_or_else(|| category.default_exit_code())
+    }
+
+    /// Render an error according to this policy
+    pub fn render(&self, error: &Error) -> String {
+        let category = error.category();
+        if let Some(renderer) = &self.renderer {
+            return renderer(error, category);
+        }
+        match self.style {
+            RenderStyle::Plain => render_text(error, false),
+            RenderStyle::Colored => render_text(error, true),
+            RenderStyle::Json => {
+                let mut value = json!({
+                    "category": category.as_str(),
+                    "message": message(error),
+                });
+                match error {
+                    Error::Clap(e) | Error::ExpectedError(e) => {
+                        value["kind"] = json!(format!("{:?}", e.kind));
+                    }
+                    _ => {}
+                }
+                if let Some(location) = Diagnostic::from_error(error).and_then(|d| d.location) {
+                    value["path"] = json!(location.path.to_string_lossy());
+                    value["line"] = json!(location.line);
+                    value["column"] = json!(location.column);
+                }
+                value.to_string()
+            }
+        }
+    }
+
+    /// Render an error to `stderr` or `stdout` and exit with the code for its category
+    pub fn exit(&self, error: &Error) -> ! {
+        let rendered = self.render(error);
+        if error.use_stderr() {
+            writeln!(&mut io::stderr(), "{}", rendered).ok();
+        } else {
+            let out = io::stdout();
+            writeln!(&mut out.lock(), "{}", rendered).expect("Error writing Error to stdout");
+        }
+        process::exit(self.code_for(error.category()));
+    }
+}
+
+impl Error {
+    /// The category this error falls into
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::ConfigGenerated(_) | Self::ConfigExplained(_) => ErrorCategory::Generated,
+            Self::ConfigFile(_, _) | Self::UnknownKey { .. } | Self::WriteConfig { .. } => {
+                ErrorCategory::ConfigFile
+            }
+            Self::Env(_, _) => ErrorCategory::Env,
+            Self::Interpolation(_) | Self::Reference(_) | Self::Decrypt { .. } => {
+                ErrorCategory::ConfigFile
+            }
+            Self::Field { source, .. } => match source {
+                TraceSource::Cli | TraceSource::Admin => ErrorCategory::Usage,
+                TraceSource::ConfigFile { .. }
+                | TraceSource::Dir { .. }
+                | TraceSource::Message { .. }
+                | TraceSource::DefaultConfig { .. } => ErrorCategory::ConfigFile,
+                TraceSource::EnvJson { .. } | TraceSource::EnvPrefix { .. } => ErrorCategory::Env,
+            },
+            Self::MissingFields(_) | Self::Constraints(_) => ErrorCategory::Usage,
+            Self::ExpectedError(_) => ErrorCategory::Internal,
+            Self::Clap(e) => match e.kind {
+                ClapErrorKind::HelpDisplayed | ClapErrorKind::VersionDisplayed => {
+                    ErrorCategory::Help
+                }
+                _ => ErrorCategory::Usage,
+            },
+        }
+    }
+
+    /// Prints the error according to `policy` and exits with the code for its category.
+    pub fn exit_with_policy(&self, policy: &ErrorPolicy) -> ! {
+        policy.exit(self)
+    }
+}
+
+fn message(error: &Error) -> String {
+    match error {
+        // Clap messages already include the `error:` prefix and usage, strip the prefix when
+        // rendering as JSON so all categories look the same
+        Error::Clap(e) => {
+            let message = strip_ansi(&e.message);
+            message
+                .strip_prefix("error: ")
+                .unwrap_or(&message)
+                .to_string()
+        }
+        _ => strip_ansi(&error.to_string()),
+    }
+}
+
+pub(crate) fn render_text(error: &Error, color: bool) -> String {
+    // Clap already formats its errors, and generated configs and help are not errors
+    match Diagnostic::from_error(error) {
+        Some(diagnostic) => diagnostic.render(color),
+        None => error.to_string(),
+    }
+}
+
+fn strip_ansi(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip the escape sequence up to and including its final letter
+            for c in &mut chars {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi("\x1b[1;31merror:\x1b[0m bad"), "error: bad");
+        assert_eq!(strip_ansi("plain"), "plain");
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{fingerprint, trace::LoadTrace};
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::sync::RwLock;
+use toml::value::{Table, Value};
+
+lazy_static! {
+    static ref SEED: RwLock<Option<String>> = RwLock::new(None);
+}
+
+/// The key of a table which declares a weighted choice of values
+pub const VARIANTS_KEY: &str = "variants";
+
+/// Set the seed which selects the variant of each experiment (eg an instance or user ID)
+///
+/// The same seed always selects the same variants. Without a seed every instance selects the same
+/// variants.
+pub fn set_experiment_seed(seed: impl Into<String>) {
+    *SEED.write().expect("seed lock poisoned") = Some(seed.into());
+}
+
+pub fn clear_experiment_seed() {
+    *SEED.write().expect("seed lock poisoned") = None;
+}
+
+/// The variant of an experiment which was selected while loading a config file
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct VariantChoice {
+    /// The dotted path of the field
+    pub path: String,
+    /// The index of the selected variant
+    pub index: usize,
+    pub weight: u64,
+    pub total_weight: u64,
+}
+
+/// Replace each table of the form `{ variants = [{ value = .., weight = .. }, ..] }` in `table`
+/// with the value of one of its variants
+///
+/// The variant is selected deterministically from the experiment seed and the path of the field so
+/// separate experiments are independent. Variants without a weight have a weight of 1.
+pub(crate) fn resolve_variants(table: &mut Table) -> Result<(), String> {
+    let seed = SEED
+        .read()
+        .expect("seed lock poisoned")
+        .clone()
+        .unwrap_or_default();
+    resolve_with_prefix(table, "", &seed)
+}
+
+/// Does `table` contain an experiment at any depth
+pub(crate) fn has_variants(table: &Table) -> bool {
+    table.values().any(|value| match value {
+        Value::Table(table) => is_experiment(table) || has_variants(table),
+        _ => false,
+    })
+}
+
+fn is_experiment(table: &Table) -> bool {
+    table.len() == 1 && table.contains_key(VARIANTS_KEY)
+}
+
+fn resolve_with_prefix(table: &mut Table, prefix: &str, seed: &str) -> Result<(), String> {
+    for (key, value) in table.iter_mut() {
+        let path = format!("{}{}", prefix, key);
+        let experiment = match value {
+            Value::Table(table) if is_experiment(table) => table,
+            Value::Table(table) => {
+                resolve_with_prefix(table, &format!("{}.", path), seed)?;
+                continue;
+            }
+            _ => continue,
+        };
+        let (choice, selected) = select(experiment, &path, seed)?;
+        LoadTrace::record_variant(choice);
+        *value = selected;
+    }
+    Ok(())
+}
+
+fn select(experiment: &Table, path: &str, seed: &str) -> Result<(VariantChoice, Value), String> {
+    let invalid = |message: &str| format!("experiment `{}` {}", path, message);
+    let variants = match &experiment[VARIANTS_KEY] {
+        Value::Array(variants) if !variants.is_empty() => variants,
+        _ => return Err(invalid("must have a non-empty array of variants")),
+    };
+    let mut weighted = Vec::new();
+    for variant in variants {
+        let variant = variant
+            .as_table()
+            .ok_or_else(|| invalid("must only contain tables"))?;
+        let value = variant
+            .get("value")
+            .ok_or_else(|| invalid("has a variant without a `value`"))?;
+        let weight = match variant.get("weight") {
+            Some(Value::Integer(weight)) if *weight >= 0 => *weight as u64,
+            Some(_) => return Err(invalid("has a weight which is not a non-negative integer")),
+            None => 1,
+        };
+        weighted.push((value, weight));
+    }
+    let total_weight = weighted.iter().map(|(_, weight)| weight).sum::<u64>();
+    if total_weight == 0 {
+        return Err(invalid("must have a variant with a positive weight"));
+    }
+    let mut point = fingerprint::fnv1a(format!("{}\u{0}{}", seed, path).as_bytes()) % total_weight;
+    for (index, (value, weight)) in weighted.into_iter().enumerate() {
+        if point < weight {
+            let choice = VariantChoice {
+                path: String::from(path),
+                index,
+                weight,
+                total_weight,
+            };
+            return Ok((choice, value.clone()));
+        }
+        point -= weight;
+    }
+    unreachable!("the point is less than the total weight")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(s: &str) -> Table {
+        match s.parse::<Value>().unwrap() {
+            Value::Table(table) => table,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_select() {
+        let experiment =
+            table("variants = [{ value = 10, weight = 90 }, { value = 50, weight = 10 }]");
+        let mut counts = [0, 0];
+        for i in 0..1000 {
+            let (choice, value) = select(&experiment, "limit", &i.to_string()).unwrap();
+            assert_eq!(value, Value::Integer([10, 50][choice.index]));
+            assert_eq!(choice.total_weight, 100);
+            counts[choice.index] += 1;
+        }
+        assert!(counts[0] > 800 && counts[1] > 50, "{:?}", counts);
+        assert_eq!(
+            select(&experiment, "limit", "a").unwrap(),
+            select(&experiment, "limit", "a").unwrap()
+        );
+
+        let only = table("variants = [{ value = 1, weight = 0 }, { value = 2 }]");
+        assert_eq!(select(&only, "a", "").unwrap().1, Value::Integer(2));
+        assert!(select(&table("variants = []"), "a", "").is_err());
+        assert!(select(&table("variants = [{ weight = 1 }]"), "a", "").is_err());
+        assert!(select(&table("variants = [{ value = 1, weight = -1 }]"), "a", "").is_err());
+    }
+
+    #[test]
+    fn test_resolve_variants() {
+        let mut config = table(
+            r#"
+            host = "a"
+            [limits]
+            connections = { variants = [{ value = 5 }] }
+            "#,
+        );
+        assert!(has_variants(&config));
+        resolve_with_prefix(&mut config, "", "").unwrap();
+        assert!(!has_variants(&config));
+        assert_eq!(config, table("host = \"a\"\n[limits]\nconnections = 5"));
+    }
+}
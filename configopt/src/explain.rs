@@ -0,0 +1,117 @@
+use crate::{effective_fields, reference, ConfigOptType, LoadTrace};
+use structopt::clap::App;
+
+const HEADER: [&str; 3] = ["FIELD", "VALUE", "SOURCE"];
+
+/// A table of every field of `config` with its effective value and the source which set it
+///
+/// A field no layer set shows the default of its CLI argument or `<unset>`. This is what
+/// `--explain-config` prints. `trace` must be captured around the load which produced `config`
+/// and `app` is the app of the actual type which knows the defaults.
+pub fn explain_config<T: ConfigOptType>(
+    config: &T,
+    trace: &LoadTrace,
+    app: &App<'_, '_>,
+) -> String {
+    let docs = reference::arg_docs(app);
+    let mut set = effective_fields(config, trace);
+    let mut rows = Vec::new();
+    for field in T::runtime_schema().fields {
+        if field.path.split('.').any(|key| key == "*") {
+            // Only the instances of a map which were set have a value
+            let pattern = field.path.split('.').collect::<Vec<_>>();
+            let (instances, rest) = set.into_iter().partition::<Vec<_>, _>(|effective| {
+                let keys = effective.path.split('.').collect::<Vec<_>>();
+                keys.len() == pattern.len()
+                    && keys.iter().zip(&pattern).all(|(k, p)| p == &"*" || k == p)
+            });
+            set = rest;
+            rows.extend(instances.into_iter().map(|effective| {
+                let source = effective.source.map(|s| s.to_string()).unwrap_or_default();
+                [effective.path, effective.value, source]
+            }));
+            continue;
+        }
+        match set
+            .iter()
+            .position(|effective| effective.path == field.path)
+        {
+            Some(i) => {
+                let effective = set.remove(i);
+                let source = effective.source.map(|s| s.to_string()).unwrap_or_default();
+                rows.push([effective.path, effective.value, source]);
+            }
+            None => {
+                let default = field
+                    .arg
+                    .as_ref()
+                    .and_then(|arg| docs.get(arg))
+                    .and_then(|doc| doc.default.clone());
+                rows.push(match default {
+                    Some(default) => [field.path, default, String::from("default")],
+                    None => [field.path, String::from("<unset>"), String::new()],
+                });
+            }
+        }
+    }
+    // Fields the schema does not describe (eg a type with `configopt(skip(schema))`)
+    rows.extend(set.into_iter().map(|effective| {
+        let source = effective.source.map(|s| s.to_string()).unwrap_or_default();
+        [effective.path, effective.value, source]
+    }));
+    render(&rows)
+}
+
+fn render(rows: &[[String; 3]]) -> String {
+    let mut widths = [HEADER[0].len(), HEADER[1].len(), HEADER[2].len()];
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: [&str; 3]| {
+        format!(
+            "{:<name$}  {:<value$}  {}",
+            cells[0],
+            cells[1],
+            cells[2],
+            name = widths[0],
+            value = widths[1]
+        )
+        .trim_end()
+        .to_string()
+    };
+    let mut table = line(HEADER);
+    for row in rows {
+        table.push('\n');
+        table.push_str(&line([&row[0], &row[1], &row[2]]));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let rows = [
+            [
+                String::from("port"),
+                String::from("8080"),
+                String::from("the command line"),
+            ],
+            [
+                String::from("log.level"),
+                String::from("<unset>"),
+                String::new(),
+            ],
+        ];
+        assert_eq!(
+            render(&rows),
+            "FIELD      VALUE    SOURCE\n\
+             port       8080     the command line\n\
+             log.level  <unset>"
+        );
+    }
+}
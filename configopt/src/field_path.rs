@@ -0,0 +1,267 @@
+use crate::{
+    coerce::{self, Policy},
+    ConfigOptType, Merge, ValueKind,
+};
+use serde::de::DeserializeOwned;
+use std::{collections::BTreeMap, fmt, str::FromStr};
+use toml::value::Table;
+
+/// The dotted path of a field (eg `server.port`) as written in config files
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FieldPath(Vec<String>);
+
+impl FieldPath {
+    pub fn new<I, S>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self(keys.into_iter().map(Into::into).collect())
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.0
+    }
+
+    /// The path of the field `key` of the table at this path
+    pub fn join(&self, key: impl Into<String>) -> Self {
+        let mut keys = self.0.clone();
+        keys.push(key.into());
+        Self(keys)
+    }
+}
+
+impl FromStr for FieldPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let keys = s.split('.').map(String::from).collect::<Vec<_>>();
+        if keys.iter().any(String::is_empty) {
+            return Err(format!("`{}` is not a dotted path", s));
+        }
+        Ok(Self(keys))
+    }
+}
+
+impl From<&str> for FieldPath {
+    /// Split `path` on `.` without checking for empty keys
+    fn from(path: &str) -> Self {
+        Self::new(path.split('.'))
+    }
+}
+
+impl fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.join("."))
+    }
+}
+
+/// The value of a field without compile time knowledge of its type
+///
+/// This is the TOML data model so any value a config file can set can be read and written.
+/// Datetimes are strings.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    List(Vec<Value>),
+    Table(BTreeMap<String, Value>),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Self::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl From<toml::Value> for Value {
+    fn from(value: toml::Value) -> Self {
+        match value {
+            toml::Value::Boolean(b) => Self::Bool(b),
+            toml::Value::Integer(i) => Self::Integer(i),
+            toml::Value::Float(f) => Self::Float(f),
+            toml::Value::String(s) => Self::String(s),
+            toml::Value::Datetime(d) => Self::String(d.to_string()),
+            toml::Value::Array(items) => Self::List(items.into_iter().map(Self::from).collect()),
+            toml::Value::Table(table) => {
+                Self::Table(table.into_iter().map(|(k, v)| (k, Self::from(v))).collect())
+            }
+        }
+    }
+}
+
+impl From<Value> for toml::Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Bool(b) => Self::Boolean(b),
+            Value::Integer(i) => Self::Integer(i),
+            Value::Float(f) => Self::Float(f),
+            Value::String(s) => Self::String(s),
+            Value::List(items) => Self::Array(items.into_iter().map(Self::from).collect()),
+            Value::Table(table) => {
+                Self::Table(table.into_iter().map(|(k, v)| (k, Self::from(v))).collect())
+            }
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Self::Bool(b)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(i: i64) -> Self {
+        Self::Integer(i)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(f: f64) -> Self {
+        Self::Float(f)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Self::String(String::from(s))
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Self::String(s)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(items: Vec<Value>) -> Self {
+        Self::List(items)
+    }
+}
+
+impl fmt::Display for Value {
+    /// Render the value as TOML
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", toml::Value::from(self.clone()))
+    }
+}
+
+/// The value of the field at `path` of `config` or `None` if it is not set
+///
+/// Secrets are never returned and neither are the fields left out of the generated config (eg
+/// hidden fields and leaves).
+pub fn get_field<T: ConfigOptType>(config: &T, path: &FieldPath) -> Option<Value> {
+    let values = config.toml_config().parse::<toml::Value>().ok()?;
+    path.keys()
+        .iter()
+        .try_fold(&values, |value, key| value.get(key))
+        .cloned()
+        .map(Value::from)
+}
+
+/// The TOML value to set the field at `path` of a `T` to
+///
+/// A string is parsed as the kind of value the field holds. It is an error if `path` is not a key
+/// of the schema.
+pub(crate) fn field_value<T: ConfigOptType>(
+    path: &FieldPath,
+    value: Value,
+) -> Result<toml::Value, String> {
+    let path = path.to_string();
+    let schema = T::runtime_schema();
+    let field = coerce::find_field(&schema, &path)
+        .ok_or_else(|| format!("`{}` is not a known key", path))?;
+    Ok(match (value, field.kind()) {
+        (Value::String(s), kind) if kind != ValueKind::String && kind != ValueKind::Any => {
+            coerce::coerce(&s, &kind, &Policy::default())
+                .map_err(|e| format!("`{}` {}", path, e))?
+        }
+        (value, _) => toml::Value::from(value),
+    })
+}
+
+/// Set the field at `path` of `config` to `value`
+///
+/// A string is parsed as the kind of value the field holds so the text typed into a REPL or sent
+/// to an admin endpoint can be used as it is. It is an error if `path` is not a key of the schema
+/// or the value does not fit the field.
+pub fn set_field<T>(config: &mut T, path: &FieldPath, value: Value) -> Result<(), String>
+where
+    T: ConfigOptType + Merge + DeserializeOwned,
+{
+    let value = field_value::<T>(path, value)?;
+    let path = path.to_string();
+    let mut table = Table::new();
+    coerce::set_path(&mut table, &path, value)?;
+    let mut other = toml::Value::Table(table)
+        .try_into::<T>()
+        .map_err(|e| format!("`{}` {}", path, e))?;
+    config.take(&mut other);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_path() {
+        let path = "server.port".parse::<FieldPath>().unwrap();
+        assert_eq!(path.keys(), &[String::from("server"), String::from("port")]);
+        assert_eq!(path.to_string(), "server.port");
+        assert_eq!(FieldPath::from("server").join("port"), path);
+        assert!("server..port".parse::<FieldPath>().is_err());
+    }
+
+    #[test]
+    fn test_value_round_trip() {
+        let toml = "a = 1\nb = [true, 1.5]\n[c]\nd = \"e\"\n"
+            .parse::<toml::Value>()
+            .unwrap();
+        let value = Value::from(toml.clone());
+        assert_eq!(
+            value,
+            Value::Table(
+                vec![
+                    (String::from("a"), Value::Integer(1)),
+                    (
+                        String::from("b"),
+                        Value::List(vec![Value::Bool(true), Value::Float(1.5)])
+                    ),
+                    (
+                        String::from("c"),
+                        Value::Table(
+                            vec![(String::from("d"), Value::from("e"))]
+                                .into_iter()
+                                .collect()
+                        )
+                    ),
+                ]
+                .into_iter()
+                .collect()
+            )
+        );
+        assert_eq!(toml::Value::from(value), toml);
+    }
+}
@@ -0,0 +1,103 @@
+use crate::{Clock, SystemClock};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, ErrorKind as IoErrorKind},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+/// Access to the files the dynamic subsystem reads and watches
+///
+/// Use [`MemoryFileSystem`](struct.MemoryFileSystem.html) to change files deterministically in
+/// tests.
+pub trait FileSystem: Send + Sync {
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()>;
+
+    /// When the file at `path` was last modified
+    fn modified(&self, path: &Path) -> io::Result<SystemTime>;
+}
+
+/// The real filesystem
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RealFileSystem;
+
+impl FileSystem for RealFileSystem {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()> {
+        fs::write(path, contents)
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        fs::metadata(path)?.modified()
+    }
+}
+
+/// A filesystem which only exists in memory
+///
+/// Files are marked as modified at the current time of the clock when they are written.
+pub struct MemoryFileSystem {
+    clock: Arc<dyn Clock>,
+    files: Mutex<HashMap<PathBuf, (String, SystemTime)>>,
+}
+
+impl MemoryFileSystem {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            files: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Remove the file at `path` returning its contents
+    pub fn remove(&self, path: impl AsRef<Path>) -> Option<String> {
+        let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        files.remove(path.as_ref()).map(|(contents, _)| contents)
+    }
+
+    fn not_found(path: &Path) -> io::Error {
+        io::Error::new(
+            IoErrorKind::NotFound,
+            format!("'{}' does not exist", path.to_string_lossy()),
+        )
+    }
+}
+
+impl Default for MemoryFileSystem {
+    fn default() -> Self {
+        Self::new(Arc::new(SystemClock))
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        let files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        files
+            .get(path)
+            .map(|(contents, _)| contents.clone())
+            .ok_or_else(|| Self::not_found(path))
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()> {
+        let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        files.insert(
+            path.to_path_buf(),
+            (String::from(contents), self.clock.now()),
+        );
+        Ok(())
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        let files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        files
+            .get(path)
+            .map(|(_, modified)| *modified)
+            .ok_or_else(|| Self::not_found(path))
+    }
+}
@@ -0,0 +1,62 @@
+use std::fmt;
+
+/// A stable hash of the values of a config
+///
+/// Only the values are hashed so field help, the order of keys, and formatting do not change the
+/// fingerprint. The hash does not depend on the Rust version or platform so fingerprints can be
+/// compared across replicas and releases.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fingerprint(u64);
+
+impl Fingerprint {
+    /// Fingerprint the values of a TOML config document
+    pub fn of_toml(toml_config: &str) -> Self {
+        match toml::from_str::<toml::value::Table>(toml_config) {
+            Ok(values) => Self::of_values(&values),
+            Err(_) => Self(fnv1a(toml_config.as_bytes())),
+        }
+    }
+
+    /// Fingerprint a table of values
+    pub fn of_values(values: &toml::value::Table) -> Self {
+        // Keys of tables are sorted which gives a canonical form for the values
+        let canonical = serde_json::to_string(values).unwrap_or_default();
+        Self(fnv1a(canonical.as_bytes()))
+    }
+
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+// The 64 bit FNV-1a hash
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+
+        let a = Fingerprint::of_toml("## The port\nport = 1\nhost = \"a\"\n# unset =\n");
+        let b = Fingerprint::of_toml("host = 'a'\nport = 1");
+        assert_eq!(a, b);
+        assert_ne!(a, Fingerprint::of_toml("host = \"a\"\nport = 2"));
+        assert_eq!(a.to_string().len(), 16);
+    }
+}
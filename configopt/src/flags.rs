@@ -0,0 +1,203 @@
+//! Feature flags which are toggled at runtime without a restart
+//!
+//! A [`FeatureFlags`](struct.FeatureFlags.html) field is recognized by the derive and gets special
+//! treatment:
+//!
+//! * Layers are merged flag by flag so a config file which sets one flag does not turn off the
+//!   flags a lower layer set. Use `#[configopt(merge = "overwrite")]` to replace the flags as a
+//!   whole.
+//! * `diff` reports the dotted path of each flag which changed (eg `flags.new_ui`) so a reload
+//!   says which flags were toggled.
+//! * Each flag is a key of the schema so a single flag can be set through `set_field` and
+//!   patched through the admin endpoint (eg `PATCH /config/flags/new_ui` with `true`).
+//!
+//! The field holds a map so it has no CLI argument. Declare it with `#[structopt(skip)]` and
+//! `#[configopt(file_only)]`.
+//!
+//! ```toml
+//! [flags]
+//! dark_mode = true
+//! new_ui = { percentage = 10, targets = ["beta-tester"] }
+//! ```
+
+use crate::fingerprint;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{btree_map, BTreeMap},
+    iter::FromIterator,
+};
+
+/// The state of a single feature flag
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Flag {
+    /// On or off for everyone
+    Enabled(bool),
+    /// On for some subjects
+    Rollout(Rollout),
+}
+
+impl From<bool> for Flag {
+    fn from(enabled: bool) -> Self {
+        Self::Enabled(enabled)
+    }
+}
+
+impl From<Rollout> for Flag {
+    fn from(rollout: Rollout) -> Self {
+        Self::Rollout(rollout)
+    }
+}
+
+/// A flag which is on for the targeted subjects and a percentage of the others
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rollout {
+    /// The percentage of subjects from `0` to `100` the flag is on for
+    #[serde(default)]
+    pub percentage: f64,
+    /// The subjects (eg user or tenant IDs) the flag is always on for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<String>,
+}
+
+impl Rollout {
+    /// Is the flag `name` on for `subject`
+    ///
+    /// A subject is always in the same bucket of a flag so raising the percentage only turns the
+    /// flag on for more subjects. Different flags bucket subjects independently.
+    fn is_enabled_for(&self, name: &str, subject: &str) -> bool {
+        if self.targets.iter().any(|target| target == subject) {
+            return true;
+        }
+        let bucket = fingerprint::fnv1a(format!("{}\u{0}{}", name, subject).as_bytes()) % 10_000;
+        (bucket as f64) < self.percentage * 100.0
+    }
+}
+
+/// A map of feature flags by name
+///
+/// A flag which is not in the map is off.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FeatureFlags {
+    flags: BTreeMap<String, Flag>,
+}
+
+impl FeatureFlags {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Flag> {
+        self.flags.get(name)
+    }
+
+    pub fn set(&mut self, name: impl Into<String>, flag: impl Into<Flag>) {
+        self.flags.insert(name.into(), flag.into());
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Flag> {
+        self.flags.remove(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.flags.is_empty()
+    }
+
+    /// The names of the flags in order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.flags.keys().map(String::as_str)
+    }
+
+    /// Is the flag `name` on for everyone
+    ///
+    /// A rollout is only on for everyone at 100 percent. Use `is_enabled_for` to check a rollout
+    /// for a particular subject.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        match self.flags.get(name) {
+            Some(Flag::Enabled(enabled)) => *enabled,
+            Some(Flag::Rollout(rollout)) => rollout.percentage >= 100.0,
+            None => false,
+        }
+    }
+
+    /// Is the flag `name` on for `subject` (eg a user or tenant ID)
+    pub fn is_enabled_for(&self, name: &str, subject: &str) -> bool {
+        match self.flags.get(name) {
+            Some(Flag::Enabled(enabled)) => *enabled,
+            Some(Flag::Rollout(rollout)) => rollout.is_enabled_for(name, subject),
+            None => false,
+        }
+    }
+
+    /// The names of the flags which are different in `other` including flags only one of them has
+    pub fn changed(&self, other: &FeatureFlags) -> Vec<String> {
+        let mut names = self
+            .flags
+            .iter()
+            .filter(|(name, flag)| other.flags.get(*name) != Some(flag))
+            .map(|(name, _)| name.clone())
+            .chain(
+                other
+                    .flags
+                    .keys()
+                    .filter(|name| !self.flags.contains_key(*name))
+                    .cloned(),
+            )
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+}
+
+impl IntoIterator for FeatureFlags {
+    type Item = (String, Flag);
+    type IntoIter = btree_map::IntoIter<String, Flag>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.flags.into_iter()
+    }
+}
+
+/// Extending replaces the flags with the same name which is how layers are merged
+impl Extend<(String, Flag)> for FeatureFlags {
+    fn extend<I: IntoIterator<Item = (String, Flag)>>(&mut self, iter: I) {
+        self.flags.extend(iter)
+    }
+}
+
+impl FromIterator<(String, Flag)> for FeatureFlags {
+    fn from_iter<I: IntoIterator<Item = (String, Flag)>>(iter: I) -> Self {
+        Self {
+            flags: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rollout() {
+        let rollout = Rollout {
+            percentage: 25.0,
+            targets: vec![String::from("beta")],
+        };
+        assert!(rollout.is_enabled_for("new_ui", "beta"));
+        let enabled = (0..1000)
+            .filter(|i| rollout.is_enabled_for("new_ui", &i.to_string()))
+            .count();
+        assert!(enabled > 200 && enabled < 300, "{}", enabled);
+        // Raising the percentage keeps the flag on for the same subjects
+        let raised = Rollout {
+            percentage: 50.0,
+            ..rollout.clone()
+        };
+        assert!((0..1000)
+            .map(|i| i.to_string())
+            .filter(|i| rollout.is_enabled_for("new_ui", i))
+            .all(|i| raised.is_enabled_for("new_ui", &i)));
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{interpolate, ConfigFormat, ConfigOptType, Error, Result};
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// The variables of each target of a fleet by the name of the target (eg its host name)
+pub type Targets = BTreeMap<String, BTreeMap<String, String>>;
+
+/// Render `template` once for each of `targets` replacing each `${name}` in its string values
+///
+/// A name is looked up in the variables of the target first and then as the dotted path of a field
+/// of `template`. Unlike `interpolate_config` the environment is not read so the rendered configs
+/// only depend on the template and the variables. It is an error if a target is missing a variable
+/// the template uses.
+pub fn render<T>(template: &T, targets: &Targets) -> Result<BTreeMap<String, T>>
+where
+    T: ConfigOptType + Clone,
+{
+    let fields = template
+        .toml_config()
+        .parse::<toml::Value>()
+        .unwrap_or_else(|_| toml::Value::Table(toml::value::Table::new()));
+    targets
+        .iter()
+        .map(|(target, variables)| {
+            let mut config = template.clone();
+            config
+                .interpolate_with(&|name| {
+                    variables
+                        .get(name)
+                        .cloned()
+                        .or_else(|| interpolate::lookup_field(&fields, name))
+                })
+                .map_err(|e| Error::Interpolation(format!("target `{}` {}", target, e)))?;
+            Ok((target.clone(), config))
+        })
+        .collect()
+}
+
+/// Render `template` for each of `targets` and write each config to `<dir>/<target>.<extension>`
+///
+/// Returns the paths of the written files. Secrets are never written. A target name must be a
+/// file name and not a path.
+pub fn render_to_dir<T>(
+    template: &T,
+    targets: &Targets,
+    dir: impl AsRef<Path>,
+    format: ConfigFormat,
+) -> Result<Vec<PathBuf>>
+where
+    T: ConfigOptType + Clone,
+{
+    let dir = dir.as_ref();
+    let configs = render(template, targets)?;
+    configs
+        .into_iter()
+        .map(|(target, config)| {
+            let path = dir.join(format!("{}.{}", target, format.extension()));
+            if target.is_empty() || Path::new(&target).components().count() != 1 {
+                return Err(Error::ConfigFile(
+                    path,
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("target `{}` is not a file name", target),
+                    ),
+                ));
+            }
+            config.write_to_file(&path, format)?;
+            Ok(path)
+        })
+        .collect()
+}
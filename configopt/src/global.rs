@@ -0,0 +1,83 @@
+use crate::scoped;
+use std::{
+    marker::PhantomData,
+    ptr,
+    sync::{
+        atomic::{AtomicPtr, Ordering},
+        Arc,
+    },
+};
+
+/// Storage for a config which is initialized once and then lives for the rest of the program
+///
+/// This backs the accessors generated by `#[configopt(global)]`. It is only `Sync`, and so can
+/// only be a `static`, if the config can be shared between threads:
+///
+/// ```compile_fail
+/// static GLOBAL: configopt::Global<std::rc::Rc<u16>> = configopt::Global::new();
+/// ```
+pub struct Global<T> {
+    value: AtomicPtr<Arc<T>>,
+    // `AtomicPtr` is `Send` and `Sync` for any pointee so this makes the auto traits follow the
+    // shared `Arc<T>`
+    marker: PhantomData<Arc<T>>,
+}
+
+impl<T> Default for Global<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Global<T> {
+    pub const fn new() -> Self {
+        Self {
+            value: AtomicPtr::new(ptr::null_mut()),
+            marker: PhantomData,
+        }
+    }
+
+    fn get_arc(&'static self) -> Option<&'static Arc<T>> {
+        let value = self.value.load(Ordering::Acquire);
+        // Safety: a non-null pointer was leaked from a `Box` and is never freed
+        unsafe { value.as_ref() }
+    }
+
+    pub fn get(&'static self) -> Option<&'static T> {
+        self.get_arc().map(|value| &**value)
+    }
+
+    /// Get the value set with `configopt::with_override` falling back to the initialized value
+    pub fn current(&'static self) -> Option<Arc<T>>
+    where
+        T: 'static,
+    {
+        scoped::current_override().or_else(|| self.get_arc().cloned())
+    }
+
+    /// Get the value initializing it with `init` if it is not already initialized
+    ///
+    /// If multiple threads race to initialize the value, the first one wins and the others are
+    /// dropped.
+    pub fn get_or_try_init<F, E>(&'static self, init: F) -> Result<&'static T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+    {
+        if let Some(value) = self.get() {
+            return Ok(value);
+        }
+        let new = Box::into_raw(Box::new(Arc::new(init()?)));
+        match self
+            .value
+            .compare_exchange(ptr::null_mut(), new, Ordering::AcqRel, Ordering::Acquire)
+        {
+            // Safety: `new` was just leaked from a `Box`
+            Ok(_) => Ok(unsafe { &**new }),
+            Err(current) => {
+                // Safety: `new` was not shared so it can be reclaimed and `current` is non-null
+                drop(unsafe { Box::from_raw(new) });
+                Ok(unsafe { &**current })
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! The HOCON format
+//!
+//! A HOCON document is resolved by the `hocon` crate before it is converted to the table a TOML
+//! file would have. Substitutions (eg `${server.host}` or `${?PORT}`) are replaced with the value
+//! they refer to which may be an environment variable. `include` statements are merged in place
+//! and relative paths are relative to the including file. A `null` value and an optional
+//! substitution which is not set leave their key unset so the field falls through to the next
+//! layer like any other key missing from a config file.
+
+use hocon::{Hocon, HoconLoader};
+use std::path::Path;
+use toml::value::{Table, Value};
+
+pub(crate) fn parse_str(contents: &str) -> Result<Table, String> {
+    let hocon = HoconLoader::new()
+        .load_str(contents)
+        .and_then(|loader| loader.hocon())
+        .map_err(|e| e.to_string())?;
+    to_table(hocon)
+}
+
+pub(crate) fn parse_file(path: &Path) -> Result<Table, String> {
+    let hocon = HoconLoader::new()
+        .load_file(path)
+        .and_then(|loader| loader.hocon())
+        .map_err(|e| e.to_string())?;
+    to_table(hocon)
+}
+
+fn to_table(hocon: Hocon) -> Result<Table, String> {
+    match to_value(hocon, "")? {
+        Some(Value::Table(table)) => Ok(table),
+        _ => Err(String::from("the document must be an object")),
+    }
+}
+
+fn to_value(hocon: Hocon, key: &str) -> Result<Option<Value>, String> {
+    Ok(Some(match hocon {
+        Hocon::Real(f) => Value::Float(f),
+        Hocon::Integer(i) => Value::Integer(i),
+        Hocon::String(s) => Value::String(s),
+        Hocon::Boolean(b) => Value::Boolean(b),
+        Hocon::Array(items) => {
+            let mut array = Vec::with_capacity(items.len());
+            for (i, item) in items.into_iter().enumerate() {
+                match to_value(item, &format!("{}[{}]", key, i))? {
+                    Some(value) => array.push(value),
+                    None => return Err(format!("`{}[{}]` is null", key, i)),
+                }
+            }
+            Value::Array(array)
+        }
+        Hocon::Hash(hash) => {
+            let mut table = Table::new();
+            for (k, v) in hash {
+                let path = if key.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", key, k)
+                };
+                if let Some(value) = to_value(v, &path)? {
+                    table.insert(k, value);
+                }
+            }
+            Value::Table(table)
+        }
+        Hocon::Null => return Ok(None),
+        Hocon::BadValue(e) => return Err(format!("`{}` {}", key, e)),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hocon() {
+        let table = parse_str(
+            "server { host = localhost, port = 80 }\nurl = \"http://\"${server.host}\nunset = null",
+        )
+        .unwrap();
+        assert_eq!(table["server"]["port"].as_integer(), Some(80));
+        assert_eq!(table["url"].as_str(), Some("http://localhost"));
+        assert!(!table.contains_key("unset"));
+        assert!(parse_str("[1, 2]").is_err());
+    }
+}
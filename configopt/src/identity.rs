@@ -0,0 +1,100 @@
+use std::collections::{HashMap, HashSet};
+
+/// A stable identity for an element of an ordered list (eg a pipeline stage)
+///
+/// This is implemented by `#[derive(ConfigOpt)]` for types with a `#[configopt(id)]` field.
+pub trait Identity {
+    fn id(&self) -> Option<&str>;
+}
+
+/// A change to a single element between two versions of an ordered list
+#[derive(Clone, Debug, PartialEq)]
+pub enum ListChange {
+    /// An element was added at `index` of the new list
+    Added { id: Option<String>, index: usize },
+    /// The element at `index` of the old list was removed
+    Removed { id: Option<String>, index: usize },
+    /// The element with `id` changed position
+    Moved { id: String, from: usize, to: usize },
+    /// The value of the element at `index` of the new list changed
+    Modified { id: Option<String>, index: usize },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Key<'a> {
+    Id(&'a str),
+    Index(usize),
+}
+
+impl<'a> Key<'a> {
+    fn id(self) -> Option<String> {
+        match self {
+            Self::Id(id) => Some(String::from(id)),
+            Self::Index(_) => None,
+        }
+    }
+}
+
+/// Key elements by their id falling back to their position if they do not have an id or their id
+/// was already used
+fn keys<T: Identity>(list: &[T]) -> Vec<Key<'_>> {
+    let mut seen = HashSet::new();
+    list.iter()
+        .enumerate()
+        .map(|(i, item)| match item.id() {
+            Some(id) if seen.insert(id) => Key::Id(id),
+            _ => Key::Index(i),
+        })
+        .collect()
+}
+
+/// Compute the changes between two versions of an ordered list
+///
+/// Elements with an id are matched by id so reordering is reported as `Moved` instead of as a
+/// modification of every element. Elements without an id are matched by position.
+pub fn diff_list<T: Identity + PartialEq>(old: &[T], new: &[T]) -> Vec<ListChange> {
+    let old_keys = keys(old);
+    let new_keys = keys(new);
+    let old_indices = old_keys
+        .iter()
+        .enumerate()
+        .map(|(i, key)| (*key, i))
+        .collect::<HashMap<_, _>>();
+    let new_indices = new_keys.iter().copied().collect::<HashSet<_>>();
+
+    let mut changes = old_keys
+        .iter()
+        .enumerate()
+        .filter(|(_, key)| !new_indices.contains(key))
+        .map(|(index, key)| ListChange::Removed {
+            id: key.id(),
+            index,
+        })
+        .collect::<Vec<_>>();
+    for (index, key) in new_keys.iter().enumerate() {
+        match old_indices.get(key) {
+            Some(&old_index) => {
+                if let Key::Id(id) = key {
+                    if old_index != index {
+                        changes.push(ListChange::Moved {
+                            id: String::from(*id),
+                            from: old_index,
+                            to: index,
+                        });
+                    }
+                }
+                if old[old_index] != new[index] {
+                    changes.push(ListChange::Modified {
+                        id: key.id(),
+                        index,
+                    });
+                }
+            }
+            None => changes.push(ListChange::Added {
+                id: key.id(),
+                index,
+            }),
+        }
+    }
+    changes
+}
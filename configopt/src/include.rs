@@ -0,0 +1,126 @@
+use crate::{profile, Error, Result};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use toml::value::{Table, Value};
+
+/// The key listing the config files a config file includes
+pub const INCLUDE_KEY: &str = "include";
+
+/// Merge the files included by the config file at `path` under the values of `table`
+///
+/// Included paths are relative to the including file. Later includes take precedence over earlier
+/// ones and the including file takes precedence over all of its includes.
+pub(crate) fn resolve(path: &Path, table: Table) -> Result<Table> {
+    resolve_with_stack(path, table, &mut Vec::new())
+}
+
+fn resolve_with_stack(path: &Path, mut table: Table, stack: &mut Vec<PathBuf>) -> Result<Table> {
+    let invalid = |message: String| {
+        Error::ConfigFile(
+            path.to_path_buf(),
+            io::Error::new(io::ErrorKind::InvalidData, message),
+        )
+    };
+    let includes = match table.remove(INCLUDE_KEY) {
+        Some(Value::Array(includes)) => includes,
+        Some(_) => return Err(invalid(format!("`{}` must be an array", INCLUDE_KEY))),
+        None => return Ok(table),
+    };
+    let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if stack.contains(&canonical) {
+        let cycle = stack
+            .iter()
+            .chain(Some(&canonical))
+            .map(|p| format!("'{}'", p.to_string_lossy()))
+            .collect::<Vec<_>>();
+        return Err(invalid(format!("include cycle {}", cycle.join(" -> "))));
+    }
+    stack.push(canonical);
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut merged = Table::new();
+    for include in includes {
+        let include = match include {
+            Value::String(include) => dir.join(include),
+            _ => {
+                return Err(invalid(format!(
+                    "`{}` must only contain paths",
+                    INCLUDE_KEY
+                )))
+            }
+        };
+        // A missing include is an error even though a missing config file is not
+        let contents = fs::read_to_string(&include).map_err(|e| {
+            invalid(format!(
+                "failed to include '{}', err: {}",
+                include.to_string_lossy(),
+                e
+            ))
+        })?;
+        let included = toml::from_str::<Table>(&contents)
+            .map_err(|e| Error::ConfigFile(include.clone(), e.into()))?;
+        profile::merge(&mut merged, resolve_with_stack(&include, included, stack)?);
+    }
+    stack.pop();
+    profile::merge(&mut merged, table);
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn write(dir: &Path, name: &str, contents: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn resolve_file(path: &Path) -> Result<Table> {
+        let table = toml::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        resolve(path, table)
+    }
+
+    #[test]
+    fn test_include() {
+        let dir = env::temp_dir().join(format!("configopt-include-{}", std::process::id()));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        write(
+            &dir,
+            "common.toml",
+            "host = \"common\"\nport = 1\nworkers = 1\n",
+        );
+        write(
+            &dir,
+            "nested/secrets.toml",
+            "port = 2\ninclude = [\"../extra.toml\"]\n",
+        );
+        write(&dir, "extra.toml", "workers = 3\nname = \"extra\"\n");
+        let main = write(
+            &dir,
+            "main.toml",
+            "include = [\"common.toml\", \"nested/secrets.toml\"]\nhost = \"main\"\n",
+        );
+        let table = resolve_file(&main).unwrap();
+        assert_eq!(table["host"].as_str(), Some("main"));
+        assert_eq!(table["port"].as_integer(), Some(2));
+        assert_eq!(table["workers"].as_integer(), Some(3));
+        assert_eq!(table["name"].as_str(), Some("extra"));
+        assert!(!table.contains_key(INCLUDE_KEY));
+
+        let a = write(&dir, "a.toml", "include = [\"b.toml\"]\n");
+        write(&dir, "b.toml", "include = [\"a.toml\"]\n");
+        assert!(resolve_file(&a)
+            .unwrap_err()
+            .to_string()
+            .contains("include cycle"));
+
+        let missing = write(&dir, "missing.toml", "include = [\"nope.toml\"]\n");
+        let e = resolve_file(&missing).unwrap_err();
+        assert!(!e.config_file_not_found());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,174 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    borrow::Borrow,
+    collections::HashSet,
+    convert::Infallible,
+    fmt,
+    ops::Deref,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+lazy_static! {
+    static ref TABLE: Table = Table::default();
+}
+
+// The side-table of interned strings which is separate from the global one in tests
+#[derive(Default)]
+struct Table(Mutex<HashSet<Arc<str>>>);
+
+impl Table {
+    fn intern(&self, s: &str) -> Arc<str> {
+        // A panic while inserting cannot leave the set partially written so ignore poisoning
+        let mut table = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(existing) = table.get(s) {
+            return Arc::clone(existing);
+        }
+        let value = Arc::<str>::from(s);
+        table.insert(Arc::clone(&value));
+        value
+    }
+
+    fn collect(&self) -> usize {
+        let mut table = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        table.retain(|value| Arc::strong_count(value) > 1);
+        table.len()
+    }
+}
+
+/// A string which shares one allocation with every other equal `Interned` string
+///
+/// Use this in place of `String` for fields of huge configs which repeat the same values many
+/// times. Equal values parsed from the CLI or deserialized from config files all point to the same
+/// `Arc<str>` in a global side-table.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interned(Arc<str>);
+
+impl Interned {
+    pub fn new(s: &str) -> Self {
+        Self(TABLE.intern(s))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_arc(self) -> Arc<str> {
+        self.0
+    }
+
+    /// Do the two values share the same allocation
+    pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+        Arc::ptr_eq(&a.0, &b.0)
+    }
+
+    /// Drop the strings from the side-table which are no longer used, returning how many remain
+    pub fn collect() -> usize {
+        TABLE.collect()
+    }
+}
+
+impl Deref for Interned {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Interned {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Interned {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Interned {
+    fn from(s: &str) -> Self {
+        Self::new(s)
+    }
+}
+
+impl From<String> for Interned {
+    fn from(s: String) -> Self {
+        Self::new(&s)
+    }
+}
+
+impl FromStr for Interned {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(s))
+    }
+}
+
+impl fmt::Display for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Debug for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl PartialEq<str> for Interned {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Interned {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl Serialize for Interned {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Interned {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Ok(Self::new(&s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interned() {
+        let a = Interned::new("configopt-interned-test");
+        let b = "configopt-interned-test".parse::<Interned>().unwrap();
+        let c = Interned::from(String::from("configopt-interned-other"));
+        assert!(Interned::ptr_eq(&a, &b));
+        assert!(!Interned::ptr_eq(&a, &c));
+        assert_eq!(a, "configopt-interned-test");
+    }
+
+    #[test]
+    fn test_collect() {
+        // Other tests intern strings in the global table so count with a table of our own
+        let table = Table::default();
+        let a = Interned(table.intern("a"));
+        let b = Interned(table.intern("b"));
+        assert_eq!(table.collect(), 2);
+        drop(b);
+        assert_eq!(table.collect(), 1);
+        assert_eq!(a, "a");
+    }
+}
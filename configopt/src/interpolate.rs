@@ -0,0 +1,146 @@
+use std::{borrow::Cow, env, path::PathBuf};
+
+/// Replace each `${name}` in `template` with the value `lookup` returns for `name`
+///
+/// A `$${` is an escaped literal `${`. It is an error if a name cannot be resolved or a `${` is
+/// never closed.
+pub fn interpolate_str(
+    template: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if after.starts_with("${") {
+            result.push_str("${");
+            rest = &after[2..];
+        } else if after.starts_with('{') {
+            let end = after
+                .find('}')
+                .ok_or_else(|| format!("unclosed `${{` in `{}`", template))?;
+            let name = after[1..end].trim();
+            let value = lookup(name).ok_or_else(|| format!("`{}` is not set", name))?;
+            result.push_str(&value);
+            rest = &after[end + 1..];
+        } else {
+            result.push('$');
+            rest = after;
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+fn field<'a>(config: &'a toml::Value, name: &str) -> Option<&'a toml::Value> {
+    name.split('.')
+        .try_fold(config, |value, segment| value.get(segment))
+}
+
+/// The text a value is interpolated as, tables and arrays cannot be interpolated
+fn text(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Table(_) | toml::Value::Array(_) => None,
+        value => Some(value.to_string()),
+    }
+}
+
+/// Look up `name` as the dotted path of a field in `config` falling back to an environment variable
+pub fn lookup(config: &toml::Value, name: &str) -> Option<String> {
+    match field(config, name) {
+        Some(value) => text(value),
+        None => env::var(name).ok(),
+    }
+}
+
+/// Look up `name` as the dotted path of a field in `config` without reading the environment
+pub fn lookup_field(config: &toml::Value, name: &str) -> Option<String> {
+    field(config, name).and_then(text)
+}
+
+/// A value whose strings can be interpolated
+pub trait Interpolate {
+    fn interpolate(&mut self, lookup: &dyn Fn(&str) -> Option<String>) -> Result<(), String>;
+}
+
+impl Interpolate for String {
+    fn interpolate(&mut self, lookup: &dyn Fn(&str) -> Option<String>) -> Result<(), String> {
+        if self.contains('$') {
+            *self = interpolate_str(self, lookup)?;
+        }
+        Ok(())
+    }
+}
+
+impl Interpolate for Cow<'static, str> {
+    fn interpolate(&mut self, lookup: &dyn Fn(&str) -> Option<String>) -> Result<(), String> {
+        if self.contains('$') {
+            *self = Cow::Owned(interpolate_str(self, lookup)?);
+        }
+        Ok(())
+    }
+}
+
+impl Interpolate for PathBuf {
+    fn interpolate(&mut self, lookup: &dyn Fn(&str) -> Option<String>) -> Result<(), String> {
+        // Paths which are not UTF-8 cannot contain a template
+        if let Some(path) = self.to_str().filter(|p| p.contains('$')) {
+            *self = PathBuf::from(interpolate_str(path, lookup)?);
+        }
+        Ok(())
+    }
+}
+
+impl<T: Interpolate> Interpolate for Option<T> {
+    fn interpolate(&mut self, lookup: &dyn Fn(&str) -> Option<String>) -> Result<(), String> {
+        match self {
+            Some(value) => value.interpolate(lookup),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: Interpolate> Interpolate for Vec<T> {
+    fn interpolate(&mut self, lookup: &dyn Fn(&str) -> Option<String>) -> Result<(), String> {
+        self.iter_mut()
+            .try_for_each(|value| value.interpolate(lookup))
+    }
+}
+
+// Use the same autoref trick as `ConfigOptArgToOsString` so the generated code can call
+// `interpolate` on fields of every type and only strings are changed.
+#[doc(hidden)]
+pub trait InterpolateOther {
+    fn interpolate(&mut self, lookup: &dyn Fn(&str) -> Option<String>) -> Result<(), String>;
+}
+
+impl<T> InterpolateOther for &mut T {
+    fn interpolate(&mut self, _lookup: &dyn Fn(&str) -> Option<String>) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interpolate_str() {
+        let config = "name = \"app\"\n[server]\nport = 8080\n"
+            .parse::<toml::Value>()
+            .unwrap();
+        let lookup = |name: &str| lookup(&config, name);
+        assert_eq!(
+            interpolate_str("http://${name}:${server.port}/", &lookup).unwrap(),
+            "http://app:8080/"
+        );
+        assert_eq!(
+            interpolate_str("$${name} costs $5", &lookup).unwrap(),
+            "${name} costs $5"
+        );
+        assert!(interpolate_str("${missing}", &lookup).is_err());
+        assert!(interpolate_str("${name", &lookup).is_err());
+    }
+}
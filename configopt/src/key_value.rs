@@ -0,0 +1,337 @@
+//! The INI and Java properties formats
+//!
+//! Both formats map onto the same table as a TOML file. A key is a dotted path (eg
+//! `server.port`) and an INI section is the prefix of the keys under it so `[server]` followed by
+//! `port = 80` sets the same key. Values are untyped strings which are coerced into the kind the
+//! schema expects for their key exactly like the files of `dir_table`. A list is written as a TOML
+//! array or as items separated with `,`. A key which does not refer to a field is kept as a string so it is reported like any
+//! other unknown key.
+
+use crate::{
+    coerce::{self, Policy},
+    Schema,
+};
+use toml::value::{Table, Value};
+
+/// A key and its value from line `line` of a document
+struct Pair {
+    line: usize,
+    key: String,
+    value: String,
+}
+
+fn to_table(pairs: Vec<Pair>, schema: &Schema) -> Result<Table, String> {
+    let policy = Policy::default();
+    let mut table = Table::new();
+    for Pair { line, key, value } in pairs {
+        let error = |e| format!("line {}: `{}` {}", line, key, e);
+        let value = match coerce::find_field(schema, &key) {
+            Some(field) => coerce::coerce(&value, &field.kind(), &policy).map_err(error)?,
+            None => Value::String(value),
+        };
+        coerce::set_path(&mut table, &key, value).map_err(error)?;
+    }
+    Ok(table)
+}
+
+/// Flatten `table` into dotted keys with the scalar values as strings
+#[cfg(feature = "properties")]
+fn flatten(table: &Table, prefix: &str, pairs: &mut Vec<(String, String)>) -> Result<(), String> {
+    for (key, value) in table {
+        let key = format!("{}{}", prefix, key);
+        match value {
+            Value::Table(table) => flatten(table, &format!("{}.", key), pairs)?,
+            value => pairs.push((key.clone(), scalar(&key, value)?)),
+        }
+    }
+    Ok(())
+}
+
+fn scalar(key: &str, value: &Value) -> Result<String, String> {
+    Ok(match value {
+        Value::String(s) => s.clone(),
+        Value::Datetime(d) => d.to_string(),
+        Value::Array(items) => {
+            let items = items
+                .iter()
+                .map(|item| match item {
+                    Value::Array(_) | Value::Table(_) => Err(format!(
+                        "`{}` is a list of lists or tables which has no key value form",
+                        key
+                    )),
+                    item => scalar(key, item),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            items.join(",")
+        }
+        Value::Table(_) => unreachable!("tables are flattened"),
+        value => value.to_string(),
+    })
+}
+
+/// Parse an INI document
+///
+/// Lines starting with `;` or `#` are comments. A key is separated from its value with `=` or `:`
+/// and the whitespace around both is removed. Wrap a value in `"` to keep its leading or trailing
+/// whitespace. Keys before the first section are at the top level.
+#[cfg(feature = "ini")]
+pub(crate) fn parse_ini(contents: &str, schema: &Schema) -> Result<Table, String> {
+    let mut pairs = Vec::new();
+    let mut section = String::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            if !line.ends_with(']') {
+                return Err(format!("line {}: the section is not closed", line_number));
+            }
+            let name = line[1..line.len() - 1].trim();
+            section = if name.is_empty() {
+                String::new()
+            } else {
+                format!("{}.", name)
+            };
+            continue;
+        }
+        let separator = line
+            .find(|c| c == '=' || c == ':')
+            .ok_or_else(|| format!("line {}: expected `key = value`", line_number))?;
+        let key = line[..separator].trim();
+        if key.is_empty() {
+            return Err(format!("line {}: the key is empty", line_number));
+        }
+        let value = line[separator + 1..].trim();
+        let value = if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+            &value[1..value.len() - 1]
+        } else {
+            value
+        };
+        pairs.push(Pair {
+            line: line_number,
+            key: format!("{}{}", section, key),
+            value: String::from(value),
+        });
+    }
+    to_table(pairs, schema)
+}
+
+/// Write `table` as an INI document with a section for each table of values
+#[cfg(feature = "ini")]
+pub(crate) fn render_ini(table: &Table) -> Result<String, String> {
+    let value = |value: String| {
+        if value.trim() != value || value.starts_with('"') {
+            format!("\"{}\"", value)
+        } else {
+            value
+        }
+    };
+    let mut doc = String::new();
+    let mut sections = Vec::new();
+    for (key, v) in table {
+        match v {
+            Value::Table(table) => sections.push((key.clone(), table)),
+            v => doc.push_str(&format!("{} = {}\n", key, value(scalar(key, v)?))),
+        }
+    }
+    while !sections.is_empty() {
+        let mut nested = Vec::new();
+        for (name, table) in sections {
+            let mut pairs = Vec::new();
+            for (key, v) in table {
+                match v {
+                    Value::Table(table) => nested.push((format!("{}.{}", name, key), table)),
+                    v => pairs.push((key, scalar(key, v)?)),
+                }
+            }
+            if pairs.is_empty() {
+                continue;
+            }
+            if !doc.is_empty() {
+                doc.push('\n');
+            }
+            doc.push_str(&format!("[{}]\n", name));
+            for (key, v) in pairs {
+                doc.push_str(&format!("{} = {}\n", key, value(v)));
+            }
+        }
+        sections = nested;
+    }
+    Ok(doc)
+}
+
+/// Parse a Java properties document
+///
+/// This follows `java.util.Properties`. Lines starting with `#` or `!` are comments. A key ends at
+/// the first unescaped `=`, `:`, or whitespace. A line ending in `\` continues on the next line
+/// and `\t`, `\n`, `\r`, `\f`, and `\uXXXX` are escapes.
+#[cfg(feature = "properties")]
+pub(crate) fn parse_properties(contents: &str, schema: &Schema) -> Result<Table, String> {
+    let mut pairs = Vec::new();
+    let mut lines = contents.lines().enumerate();
+    while let Some((i, line)) = lines.next() {
+        let line_number = i + 1;
+        let line = line.trim_start();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+            continue;
+        }
+        // Join the continuation lines dropping their leading whitespace
+        let mut logical = String::from(line);
+        while ends_with_escape(&logical) {
+            logical.pop();
+            match lines.next() {
+                Some((_, next)) => logical.push_str(next.trim_start()),
+                None => break,
+            }
+        }
+        let (key, value) = split_property(&logical);
+        pairs.push(Pair {
+            line: line_number,
+            key: unescape(key).map_err(|e| format!("line {}: {}", line_number, e))?,
+            value: unescape(value).map_err(|e| format!("line {}: {}", line_number, e))?,
+        });
+    }
+    to_table(pairs, schema)
+}
+
+/// Does `line` end in an odd number of backslashes
+#[cfg(feature = "properties")]
+fn ends_with_escape(line: &str) -> bool {
+    line.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1
+}
+
+/// Split a logical line into its escaped key and value
+#[cfg(feature = "properties")]
+fn split_property(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    let mut end = line.len();
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '=' || c == ':' || c.is_whitespace() {
+            end = i;
+            break;
+        }
+    }
+    let key = &line[..end];
+    let rest = line[end..].trim_start();
+    let rest = if rest.starts_with('=') || rest.starts_with(':') {
+        rest[1..].trim_start()
+    } else {
+        rest
+    };
+    (key, rest)
+}
+
+#[cfg(feature = "properties")]
+fn unescape(s: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('f') => result.push('\u{c}'),
+            Some('u') => {
+                let hex = chars.by_ref().take(4).collect::<String>();
+                let c = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == 4)
+                    .and_then(std::char::from_u32)
+                    .ok_or_else(|| format!("`\\u{}` is not a valid escape", hex))?;
+                result.push(c);
+            }
+            Some(c) => result.push(c),
+            None => {}
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(feature = "properties")]
+fn escape(s: &str, is_key: bool) -> String {
+    let mut result = String::with_capacity(s.len());
+    for (i, c) in s.chars().enumerate() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\t' => result.push_str("\\t"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\u{c}' => result.push_str("\\f"),
+            '=' | ':' if is_key => {
+                result.push('\\');
+                result.push(c);
+            }
+            '#' | '!' if is_key && i == 0 => {
+                result.push('\\');
+                result.push(c);
+            }
+            ' ' if is_key || i == 0 => result.push_str("\\ "),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Write `table` as a Java properties document with a dotted key for each value
+#[cfg(feature = "properties")]
+pub(crate) fn render_properties(table: &Table) -> Result<String, String> {
+    let mut pairs = Vec::new();
+    flatten(table, "", &mut pairs)?;
+    Ok(pairs
+        .into_iter()
+        .map(|(key, value)| format!("{} = {}\n", escape(&key, true), escape(&value, false)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "ini")]
+    #[test]
+    fn test_ini() {
+        let table = parse_ini(
+            "; comment\nname = top\n[server]\nport: 80\n[server.tls]\ncert = \" a \"\n",
+            &Schema::default(),
+        )
+        .unwrap();
+        assert_eq!(table["name"].as_str(), Some("top"));
+        assert_eq!(table["server"]["port"].as_str(), Some("80"));
+        assert_eq!(table["server"]["tls"]["cert"].as_str(), Some(" a "));
+        assert_eq!(
+            render_ini(&table).unwrap(),
+            "name = top\n\n[server]\nport = 80\n\n[server.tls]\ncert = \" a \"\n"
+        );
+        assert!(parse_ini("[server\n", &Schema::default()).is_err());
+        assert!(parse_ini("port\n", &Schema::default()).is_err());
+    }
+
+    #[cfg(feature = "properties")]
+    #[test]
+    fn test_properties() {
+        let table = parse_properties(
+            "! comment\nserver.port 80\nserver.name=a\\\n    b\nkey\\=with\\:escapes : \\u0041\\tB\n",
+            &Schema::default(),
+        )
+        .unwrap();
+        assert_eq!(table["server"]["port"].as_str(), Some("80"));
+        assert_eq!(table["server"]["name"].as_str(), Some("ab"));
+        assert_eq!(table["key=with:escapes"].as_str(), Some("A\tB"));
+        let rendered = render_properties(&table).unwrap();
+        assert_eq!(
+            parse_properties(&rendered, &Schema::default()).unwrap(),
+            table
+        );
+        assert!(parse_properties("key = \\u00zz\n", &Schema::default()).is_err());
+    }
+}
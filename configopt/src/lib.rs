@@ -1,7 +1,104 @@
+#[cfg(feature = "admin")]
+mod admin;
 mod arena_trait;
+#[cfg(feature = "aws")]
+mod aws;
+#[cfg(feature = "axum")]
+mod axum_state;
+mod clock;
+pub mod coerce;
+mod completions;
+#[cfg(feature = "edit")]
+mod config_command;
+mod config_format;
 mod configopt_arg_to_os_string;
 mod configopt_bool;
+mod contract;
+mod convert;
+mod decrypt;
+mod deprecated;
+mod diagnostic;
+mod diff;
+mod dir;
+mod dotenv;
+#[cfg(feature = "edit")]
+mod edit;
+mod effective;
+mod env_prefix;
+mod env_value;
 mod error;
+mod error_policy;
+mod experiment;
+mod explain;
+mod field_path;
+mod filesystem;
+mod fingerprint;
+pub mod flags;
+mod fleet;
+mod global;
+#[cfg(feature = "hocon")]
+mod hocon_format;
+mod identity;
+mod include;
+mod interned;
+mod interpolate;
+#[cfg(any(feature = "ini", feature = "properties"))]
+mod key_value;
+mod lint;
+mod live;
+#[cfg(feature = "logging")]
+mod logging;
+mod messages;
+mod migration;
+mod negation;
+mod occurrences;
+pub mod os_str;
+mod overrides;
+pub mod parsers;
+mod partial;
+mod prefix;
+mod preset;
+mod profile;
+#[cfg(feature = "protobuf")]
+mod proto;
+mod provenance;
+mod reference;
+mod registry;
+#[cfg(feature = "dev")]
+mod reload_journal;
+mod reloadable;
+#[cfg(feature = "async")]
+mod resolve;
+mod resolver;
+mod schema;
+mod scoped;
+mod secret;
+#[cfg(feature = "shaku")]
+mod shaku_module;
+mod shared;
+mod shutdown;
+#[cfg(all(unix, feature = "sighup"))]
+mod sighup;
+#[cfg(feature = "dev")]
+mod simulation;
+#[cfg(feature = "async")]
+mod source;
+mod strict;
+mod string_like;
+mod subcommand;
+pub mod testing;
+#[cfg(feature = "tls")]
+mod tls;
+mod toml_layout;
+mod trace;
+mod value;
+#[cfg(feature = "vault")]
+mod vault;
+mod watcher;
+mod wizard;
+
+// Lets the modules of this crate derive `ConfigOpt` whose generated code refers to `::configopt`
+extern crate self as configopt;
 
 use arena_trait::Arena;
 use colosseum::{sync::Arena as SyncArena, unsync::Arena as UnsyncArena};
@@ -11,19 +108,120 @@ use std::path::Path;
 use std::{
     env,
     ffi::OsString,
-    fs,
     io::{self, Write},
     process,
 };
 use structopt::{
-    clap::{App, ErrorKind as ClapErrorKind, Result as ClapResult},
+    clap::{App, ArgSettings, ErrorKind as ClapErrorKind, Result as ClapResult, Shell},
     StructOpt,
 };
 
+#[cfg(feature = "admin")]
+pub use admin::{AdminService, AdminServiceBuilder, CONFIG_PATH};
+#[cfg(feature = "aws")]
+pub use aws::{AwsClient, AwsReference, SsmSource, SECRETS_MANAGER_PREFIX, SSM_PREFIX};
+#[cfg(feature = "axum")]
+pub use axum_state::CurrentConfig;
+pub use clock::{Clock, ManualClock, SystemClock};
+#[cfg(feature = "edit")]
+pub use config_command::{ConfigAction, ConfigCommand};
+pub use config_format::ConfigFormat;
 pub use configopt_arg_to_os_string::ConfigOptArgToOsString;
 pub use configopt_bool::ConfigOptBool;
-pub use configopt_derive::{configopt_fields, ConfigOpt};
+pub use configopt_derive::{configopt_fields, ConfigOpt, ConfigOptSection, ConfigOptValue};
+pub use contract::{ValueKind, Violation};
+pub use convert::{deserialize_empty_as_none, deserialize_from, deserialize_try_from};
+pub use decrypt::{clear_decryptor, decrypt_value, set_decryptor, Decryptor};
+pub use deprecated::{Alias, Deprecation, Source};
+pub use diagnostic::{Diagnostic, Level, Location};
+pub use diff::{ConfigDiff, RestartRequired};
+pub use dir::dir_table;
+pub use dotenv::load_dotenv;
+#[cfg(feature = "edit")]
+pub use edit::{set_in_file, unset_in_file};
+#[cfg(feature = "effective-config")]
+pub use effective::log_effective_config;
+pub use effective::{effective_fields, EffectiveField};
+pub use env_prefix::{prefixed_env_table, DEFAULT_ENV_SEPARATOR};
+pub use env_value::decode_env_value;
 pub use error::{Error, Result};
+pub use error_policy::{ErrorCategory, ErrorPolicy, RenderStyle};
+pub use experiment::{clear_experiment_seed, set_experiment_seed, VariantChoice, VARIANTS_KEY};
+pub use explain::explain_config;
+pub use field_path::{get_field, set_field, FieldPath, Value};
+pub use filesystem::{FileSystem, MemoryFileSystem, RealFileSystem};
+pub use fingerprint::Fingerprint;
+pub use fleet::{render, render_to_dir, Targets};
+pub use global::Global;
+pub use identity::{diff_list, Identity, ListChange};
+pub use include::INCLUDE_KEY;
+pub use interned::Interned;
+#[doc(hidden)]
+pub use interpolate::InterpolateOther;
+pub use interpolate::{interpolate_str, Interpolate};
+pub use lint::{lint_toml_file, Lint};
+pub use live::{Guard, Live, Snapshot, Subscription};
+#[cfg(feature = "logging")]
+pub use logging::{ConfigOptLoggingConfig, Logging, LoggingConfig, LoggingError};
+pub use messages::{clear_catalog, message, set_catalog, Catalog, MessageId};
+pub use migration::{current_version, migrate_file, migrate_table, Migration};
+pub use occurrences::{occurrences, FromOccurrences, Occurrences};
+pub use overrides::{clear_override_keys, set_override_keys, OVERRIDES_KEY};
+#[doc(hidden)]
+pub use partial::warn_skipped_instance;
+pub use partial::{merge_layers, Merge, Partial};
+#[doc(hidden)]
+pub use prefix::prefix_args;
+pub use preset::{describe_presets, Preset, PRESETS_KEY, PRESET_KEY};
+pub use profile::PROFILES_KEY;
+#[cfg(feature = "protobuf")]
+pub use proto::{
+    from_message, from_proto_struct, struct_from_table, table_from_struct, to_message,
+    to_proto_struct,
+};
+pub use provenance::Provenance;
+pub use reference::{reference_doc, DocFormat};
+pub use registry::{ConfigOptDefaults, Registry};
+#[cfg(feature = "dev")]
+pub use reload_journal::{RecordedInput, ReloadJournal, ReloadRecord};
+pub use reloadable::Reloadable;
+#[cfg(feature = "async")]
+pub use resolve::{ReferenceResolver, ResolvedSource};
+pub use resolver::{
+    clear_resolvers, register_builtin_resolvers, register_resolver, unregister_resolver,
+    CmdResolver, EnvResolver, FileResolver, ResolveReference, ResolveReferenceOther, Resolver,
+};
+pub use schema::{BreakingChange, Constraints, Schema, SchemaField};
+pub use scoped::{current_override, with_override};
+pub use secret::{read_secret_file, Redacted, REDACTED};
+#[cfg(feature = "shaku")]
+pub use shaku_module::{ConfigInterface, ConfigModule};
+pub use shutdown::ShutdownToken;
+#[cfg(all(unix, feature = "sighup"))]
+pub use sighup::SighupReload;
+#[cfg(feature = "dev")]
+pub use simulation::{ReloadOutcome, ReloadSimulation, ReloadStep};
+#[cfg(feature = "http")]
+pub use source::UrlSource;
+#[cfg(feature = "async")]
+pub use source::{from_source_async, AsyncSource, FileSource, SourceFuture};
+pub use strict::{reject_unknown_keys, UnknownKey};
+pub use string_like::cow_from_str;
+pub use subcommand::{select_subcommand, SubcommandVariant};
+#[cfg(feature = "tls")]
+pub use tls::{server_config, ConfigOptTlsConfig, TlsConfig, TlsError, TlsRotation};
+pub use trace::{LoadStep, LoadTrace, TraceSource};
+pub use value::{
+    inline_toml, insert_variant_field, parse_inline_variant, split_variant, take_variant_field,
+    variant_value, ConfigOptValue, Representation, TomlValueError, TomlValueOf, TomlValueOther,
+    TomlValueSerialize, ValuesOf, ValuesOfOther, ValuesOfValue,
+};
+#[cfg(feature = "vault")]
+pub use vault::{ConfigOptVaultConfig, VaultClient, VaultConfig, VAULT_PREFIX};
+pub use watcher::{FileWatcher, WatcherHandle};
+#[cfg(feature = "wizard")]
+pub use wizard::wizard;
+pub use wizard::{wizard_with, Question};
 
 lazy_static! {
     static ref DEFAULT_VALUE_STORE: SyncArena<OsString> = SyncArena::new();
@@ -42,6 +240,10 @@ fn set_defaults_impl<'a>(
         arg_path.push(String::from(arg.b.name));
         if let Some(default) = defaults.arg_to_os_string(arg_path.as_slice()) {
             arg.v.default_val = Some(arena.alloc(default));
+            // Do not leak secrets in the help message
+            if defaults.arg_is_secret(arg_path.as_slice()) {
+                arg.b.set(ArgSettings::HideDefaultValue);
+            }
         }
         arg_path.pop();
     }
@@ -49,6 +251,9 @@ fn set_defaults_impl<'a>(
         arg_path.push(String::from(arg.b.name));
         if let Some(default) = defaults.arg_to_os_string(arg_path.as_slice()) {
             arg.v.default_val = Some(arena.alloc(default));
+            if defaults.arg_is_secret(arg_path.as_slice()) {
+                arg.b.set(ArgSettings::HideDefaultValue);
+            }
         }
         arg_path.pop();
     }
@@ -62,10 +267,351 @@ fn set_defaults_impl<'a>(
 
 /// CODO
 pub fn from_toml_file<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T> {
+    from_toml_file_with_aliases(path, &[])
+}
+
+/// Parse a TOML config file warning about any keys which are deprecated aliases
+pub fn from_toml_file_with_aliases<T: DeserializeOwned>(
+    path: impl AsRef<Path>,
+    aliases: &[Alias],
+) -> Result<T> {
+    from_toml_file_collecting_unknown(path, aliases, &mut Vec::new())
+}
+
+/// Parse a TOML config file warning about any keys which are deprecated aliases and collecting any
+/// keys which are not recognized
+///
+/// The files listed in the `include` key are merged under the values of the file.
+pub fn from_toml_file_collecting_unknown<T: DeserializeOwned>(
+    path: impl AsRef<Path>,
+    aliases: &[Alias],
+    unknown_keys: &mut Vec<UnknownKey>,
+) -> Result<T> {
+    from_toml_file_with_presets(path, &[], aliases, unknown_keys)
+}
+
+/// Parse a TOML config file expanding the presets selected by its `preset` key collecting any keys
+/// which are not recognized
+///
+/// Presets defined in the file take precedence over `presets` with the same name.
+pub fn from_toml_file_with_presets<T: DeserializeOwned>(
+    path: impl AsRef<Path>,
+    presets: &[Preset],
+    aliases: &[Alias],
+    unknown_keys: &mut Vec<UnknownKey>,
+) -> Result<T> {
+    let path = path.as_ref();
+    let contents = decrypt::read_config_file(path)?;
+    from_toml_str_collecting_unknown(&contents, path, presets, aliases, unknown_keys)
+}
+
+/// Parse a config file in the format of its extension collecting any keys which are not
+/// recognized
+///
+/// A file with an extension which is not a recognized [`ConfigFormat`](enum.ConfigFormat.html) is
+/// read as TOML.
+pub fn from_config_file<T: DeserializeOwned>(
+    path: impl AsRef<Path>,
+    schema: fn() -> Schema,
+    presets: &[Preset],
+    aliases: &[Alias],
+    unknown_keys: &mut Vec<UnknownKey>,
+) -> Result<T> {
+    let path = path.as_ref();
+    match ConfigFormat::from_path(path) {
+        Some(ConfigFormat::Toml) | None => {
+            from_toml_file_with_presets(path, presets, aliases, unknown_keys)
+        }
+        Some(format) => {
+            let table = format.read_table(path, schema)?;
+            from_config_table(table, path, presets, aliases, unknown_keys)
+        }
+    }
+}
+
+/// Parse a TOML config file read from `fs` collecting any keys which are not recognized
+///
+/// The files listed in the `include` key are always read from the real filesystem.
+pub fn from_toml_file_in<T: DeserializeOwned>(
+    fs: &dyn FileSystem,
+    path: impl AsRef<Path>,
+    aliases: &[Alias],
+    unknown_keys: &mut Vec<UnknownKey>,
+) -> Result<T> {
+    let path = path.as_ref();
+    let contents = fs
+        .read_to_string(path)
+        .map_err(|e| Error::ConfigFile(path.to_path_buf(), e))?;
+    from_toml_str_collecting_unknown(&contents, path, &[], aliases, unknown_keys)
+}
+
+/// Parse a config document which is already in memory collecting any keys which are not
+/// recognized
+///
+/// `path` only labels errors. The files listed in the `include` key are relative to its directory.
+pub fn from_config_str<T: DeserializeOwned>(
+    contents: &str,
+    format: ConfigFormat,
+    path: &Path,
+    presets: &[Preset],
+    aliases: &[Alias],
+    unknown_keys: &mut Vec<UnknownKey>,
+) -> Result<T> {
+    from_config_str_with_schema(
+        contents,
+        format,
+        path,
+        Schema::default,
+        presets,
+        aliases,
+        unknown_keys,
+    )
+}
+
+/// Parse a config document which is already in memory using `schema` to find the type of each
+/// value of the untyped formats (eg [`ConfigFormat::Ini`](enum.ConfigFormat.html))
+///
+/// Without a schema every value of the untyped formats is a string.
+pub fn from_config_str_with_schema<T: DeserializeOwned>(
+    contents: &str,
+    format: ConfigFormat,
+    path: &Path,
+    schema: fn() -> Schema,
+    presets: &[Preset],
+    aliases: &[Alias],
+    unknown_keys: &mut Vec<UnknownKey>,
+) -> Result<T> {
+    if format == ConfigFormat::Toml {
+        return from_toml_str_collecting_unknown(contents, path, presets, aliases, unknown_keys);
+    }
+    let table = format.parse_table(contents, path, schema)?;
+    from_config_table(table, path, presets, aliases, unknown_keys)
+}
+
+/// Parse a config file shared by several binaries as the config of `binary`
+///
+/// The keys are read from the `binary` table and the tables of the `sections` at the top level.
+/// See [`ConfigOptType::binary`](trait.ConfigOptType.html#method.binary).
+pub fn from_shared_config_file<T: DeserializeOwned>(
+    path: impl AsRef<Path>,
+    binary: &str,
+    sections: &[&str],
+    schema: fn() -> Schema,
+    presets: &[Preset],
+    aliases: &[Alias],
+    unknown_keys: &mut Vec<UnknownKey>,
+) -> Result<T> {
     let path = path.as_ref();
-    let contents =
-        fs::read_to_string(path).map_err(|e| Error::ConfigFile(path.to_path_buf(), e))?;
-    toml::from_str(&contents).map_err(|e| Error::ConfigFile(path.to_path_buf(), e.into()))
+    let format = ConfigFormat::from_path(path).unwrap_or(ConfigFormat::Toml);
+    let table = format.read_table(path, schema)?;
+    from_shared_config_table(
+        table,
+        path,
+        binary,
+        sections,
+        presets,
+        aliases,
+        unknown_keys,
+    )
+}
+
+/// Like [`from_shared_config_file`](fn.from_shared_config_file.html) for a document which is
+/// already in memory
+#[allow(clippy::too_many_arguments)]
+pub fn from_shared_config_str<T: DeserializeOwned>(
+    contents: &str,
+    format: ConfigFormat,
+    path: &Path,
+    binary: &str,
+    sections: &[&str],
+    schema: fn() -> Schema,
+    presets: &[Preset],
+    aliases: &[Alias],
+    unknown_keys: &mut Vec<UnknownKey>,
+) -> Result<T> {
+    let table = format.parse_table(contents, path, schema)?;
+    from_shared_config_table(
+        table,
+        path,
+        binary,
+        sections,
+        presets,
+        aliases,
+        unknown_keys,
+    )
+}
+
+fn from_shared_config_table<T: DeserializeOwned>(
+    table: toml::value::Table,
+    path: &Path,
+    binary: &str,
+    sections: &[&str],
+    presets: &[Preset],
+    aliases: &[Alias],
+    unknown_keys: &mut Vec<UnknownKey>,
+) -> Result<T> {
+    // Resolve the includes first so an included file can hold the table of a binary
+    let table = include::resolve(path, table)?;
+    let table =
+        shared::select_binary(table, binary, sections).map_err(|e| invalid_config_file(path, e))?;
+    from_config_table(table, path, presets, aliases, unknown_keys)
+}
+
+/// Resolve the includes, overrides, and presets of a table parsed from a config file which is not
+/// TOML and deserialize it
+fn from_config_table<T: DeserializeOwned>(
+    table: toml::value::Table,
+    path: &Path,
+    presets: &[Preset],
+    aliases: &[Alias],
+    unknown_keys: &mut Vec<UnknownKey>,
+) -> Result<T> {
+    let table = include::resolve(path, table)?;
+    let table = resolve_table(table, path, presets)?;
+    warn_deprecated(&table, None, aliases, path);
+    from_toml_table(table, path, unknown_keys)
+}
+
+/// Parse the contents of the TOML config file at `path` which was already read
+pub(crate) fn from_toml_str_collecting_unknown<T: DeserializeOwned>(
+    contents: &str,
+    path: &Path,
+    presets: &[Preset],
+    aliases: &[Alias],
+    unknown_keys: &mut Vec<UnknownKey>,
+) -> Result<T> {
+    // Invalid documents are reported by the deserializer below which knows the error location
+    if let Ok(table) = toml::from_str::<toml::value::Table>(contents) {
+        if [
+            include::INCLUDE_KEY,
+            OVERRIDES_KEY,
+            PRESET_KEY,
+            PRESETS_KEY,
+            PROFILES_KEY,
+        ]
+        .iter()
+        .any(|k| table.contains_key(*k))
+            || experiment::has_variants(&table)
+        {
+            let table = include::resolve(path, table)?;
+            let table = resolve_table(table, path, presets)?;
+            warn_deprecated(&table, Some(contents), aliases, path);
+            return from_toml_table(table, path, unknown_keys);
+        }
+        warn_deprecated(&table, Some(contents), aliases, path);
+    }
+    let to_error = |e| Error::from_toml(path, e);
+    let mut deserializer = toml::Deserializer::new(contents);
+    let result = serde_ignored::deserialize(&mut deserializer, |key| {
+        unknown_keys.push(UnknownKey {
+            key: key.to_string(),
+            path: path.to_path_buf(),
+        })
+    })
+    .map_err(to_error)?;
+    deserializer.end().map_err(to_error)?;
+    Ok(result)
+}
+
+/// Parse a TOML config file merging the `[profiles.<profile>]` table over the base values
+///
+/// The `profiles` table itself is not deserialized. It is an error if `profile` does not exist.
+pub fn from_toml_file_with_profile<T: DeserializeOwned>(
+    path: impl AsRef<Path>,
+    profile: &str,
+    aliases: &[Alias],
+    unknown_keys: &mut Vec<UnknownKey>,
+) -> Result<T> {
+    from_toml_file_with_profile_and_presets(path, profile, &[], aliases, unknown_keys)
+}
+
+/// Parse a TOML config file merging the `[profiles.<profile>]` table over the base values then
+/// expanding the selected presets
+///
+/// A profile may select different presets than the base values.
+pub fn from_toml_file_with_profile_and_presets<T: DeserializeOwned>(
+    path: impl AsRef<Path>,
+    profile: &str,
+    presets: &[Preset],
+    aliases: &[Alias],
+    unknown_keys: &mut Vec<UnknownKey>,
+) -> Result<T> {
+    let path = path.as_ref();
+    let contents = decrypt::read_config_file(path)?;
+    let table =
+        toml::from_str::<toml::value::Table>(&contents).map_err(|e| Error::from_toml(path, e))?;
+    let table = include::resolve(path, table)?;
+    let table =
+        profile::select_profile(table, profile).map_err(|e| invalid_config_file(path, e))?;
+    let table = resolve_table(table, path, presets)?;
+    warn_deprecated(&table, Some(contents.as_str()), aliases, path);
+    from_toml_table(table, path, unknown_keys)
+}
+
+// Apply the overrides of the override keys then expand the presets and experiments
+//
+// The profiles of a file which is loaded without selecting one are not part of the config.
+fn resolve_table(
+    mut table: toml::value::Table,
+    path: &Path,
+    presets: &[Preset],
+) -> Result<toml::value::Table> {
+    table.remove(PROFILES_KEY);
+    let table = overrides::apply_overrides(table).map_err(|e| invalid_config_file(path, e))?;
+    let mut table =
+        preset::expand_presets(table, presets).map_err(|e| invalid_config_file(path, e))?;
+    experiment::resolve_variants(&mut table).map_err(|e| invalid_config_file(path, e))?;
+    Ok(table)
+}
+
+fn invalid_config_file(path: &Path, message: String) -> Error {
+    Error::ConfigFile(
+        path.to_path_buf(),
+        io::Error::new(io::ErrorKind::InvalidData, message),
+    )
+}
+
+/// Deserialize a table which was read from the TOML config file at `path` collecting any keys
+/// which are not recognized
+pub fn from_toml_table<T: DeserializeOwned>(
+    table: toml::value::Table,
+    path: &Path,
+    unknown_keys: &mut Vec<UnknownKey>,
+) -> Result<T> {
+    // A `toml::Value` only deserializes an enum from a string so the table is deserialized from
+    // its text which also accepts the `[key.Variant]` table of a subcommand
+    let contents = toml::to_string(&toml::Value::Table(table))
+        .map_err(|e| invalid_config_file(path, e.to_string()))?;
+    serde_ignored::deserialize(&mut toml::Deserializer::new(&contents), |key| {
+        unknown_keys.push(UnknownKey {
+            key: key.to_string(),
+            path: path.to_path_buf(),
+        })
+    })
+    .map_err(|e| Error::from_toml_table(path, e))
+}
+
+// `contents` is the text of a TOML file which is used to find the line of each deprecated key
+fn warn_deprecated(
+    table: &toml::value::Table,
+    contents: Option<&str>,
+    aliases: &[Alias],
+    path: &Path,
+) {
+    for deprecation in deprecated::config_file_deprecations(table, contents, aliases, path) {
+        deprecation.warn();
+    }
+}
+
+/// Parse the JSON document in the environment variable `name` if it is set
+pub fn from_json_env<T: DeserializeOwned>(name: &str) -> Result<Option<T>> {
+    match env::var(name) {
+        Ok(json) => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| Error::Env(String::from(name), e.to_string())),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(e) => Err(Error::Env(String::from(name), e.to_string())),
+    }
 }
 
 /// Set the defaults for a `clap::App`
@@ -85,8 +631,66 @@ where
         .filter(|a| a != "-h" && a != "--help")
 }
 
+/// Like `StructOpt::from_iter_safe` but prefixing the arguments of flattened types, decoding
+/// the environment variable values of `#[configopt(decode_env)]` arguments and accepting the
+/// negations of the `negatable` flags
+///
+/// The negated flags are returned with the parsed type.
+fn from_iter_decoding_env<S, I>(
+    iter: I,
+    prefixes: &[(String, String)],
+    negatable: &[&'static str],
+) -> ClapResult<(S, Vec<&'static str>)>
+where
+    S: IgnoreHelp,
+    I: IntoIterator,
+    I::Item: Into<OsString> + Clone,
+{
+    let mut app = S::clap();
+    prefix::apply(&mut app, prefixes);
+    negation::add_negations(&mut app, negatable);
+    env_value::decode_app(&mut app, &S::decoded_env_args())?;
+    let matches = app.get_matches_from_safe(iter)?;
+    Ok((
+        S::from_clap(&matches),
+        negation::negated(&matches, negatable),
+    ))
+}
+
+/// Like `IgnoreHelp::try_from_iter_ignore_help` but accepting the negations of the `negatable`
+/// flags
+fn try_from_iter_ignore_help_negating<S: IgnoreHelp>(
+    iter: &[OsString],
+    negatable: &[&'static str],
+) -> ClapResult<(S, Vec<&'static str>)> {
+    let prefixes = S::arg_prefixes();
+    match from_iter_decoding_env(iter, &prefixes, negatable) {
+        Err(e) if e.kind == ClapErrorKind::HelpDisplayed => {
+            // Only filter the help after ensuring the help will be displayed. This avoids
+            // wrong behavior in which `--help` or `-h` are part of trailing arguments and
+            // intended to be passed to an external command.
+            let iter = filter_help(iter);
+            from_iter_decoding_env(iter, &prefixes, negatable)
+        }
+        result @ _ => result,
+    }
+}
+
 /// CODO
 pub trait IgnoreHelp: StructOpt + Sized {
+    /// The prefix of each argument of a flattened type set with `#[configopt(prefix = "..")]`
+    #[doc(hidden)]
+    fn arg_prefixes() -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    /// The names of the arguments whose environment variable values are decoded with
+    /// `decode_env_value` set with `#[configopt(decode_env)]`
+    #[doc(hidden)]
+    fn decoded_env_args() -> Vec<&'static str> {
+        Vec::new()
+    }
+
     /// CODO
     fn from_args_ignore_help() -> Self {
         Self::from_iter_ignore_help(env::args())
@@ -104,7 +708,9 @@ pub trait IgnoreHelp: StructOpt + Sized {
         I::Item: Into<OsString> + Clone,
     {
         let iter = filter_help(iter);
-        Self::from_iter(iter)
+        let mut app = Self::clap();
+        prefix::apply(&mut app, &Self::arg_prefixes());
+        Self::from_clap(&app.get_matches_from(iter))
     }
 
     /// CODO
@@ -114,16 +720,7 @@ pub trait IgnoreHelp: StructOpt + Sized {
         I::Item: Into<OsString> + Clone,
     {
         let iter = iter.into_iter().map(Into::into).collect::<Vec<_>>();
-        match Self::from_iter_safe(&iter) {
-            Err(e) if e.kind == ClapErrorKind::HelpDisplayed => {
-                // Only filter the help after ensuring the help will be displayed. This avoids
-                // wrong behavior in which `--help` or `-h` are part of trailing arguments and
-                // intended to be passed to an external command.
-                let iter = filter_help(&iter);
-                Self::from_iter_safe(iter)
-            }
-            result @ _ => result,
-        }
+        try_from_iter_ignore_help_negating(&iter, &[]).map(|(s, _)| s)
     }
 }
 
@@ -132,6 +729,11 @@ pub trait ConfigOptType: ConfigOptArgToOsString + StructOpt {
     /// If the `--generate-config` flag is set, return the current configuration.
     fn maybe_config_file(&self) -> Option<String>;
 
+    /// Is the `--explain-config` flag set
+    fn maybe_explain_config(&self) -> bool {
+        false
+    }
+
     /// If the `--generate-config` flag is set output the current configuration to stdout and exit.
     fn maybe_generate_config_file_and_exit(&self) {
         if let Some(config) = self.maybe_config_file() {
@@ -144,12 +746,380 @@ pub trait ConfigOptType: ConfigOptArgToOsString + StructOpt {
     /// Patch with values from the `--config-files` argument
     fn patch_with_config_files(&mut self) -> Result<&mut Self>;
 
+    /// Patch with values from a config document which is already in memory
+    ///
+    /// Unlike `patch_with_config_files` no file and no environment variable is read.
+    fn patch_with_config_str(&mut self, contents: &str, format: ConfigFormat) -> Result<&mut Self>;
+
     #[doc(hidden)]
     fn toml_config_with_prefix(&self, serde_prefix: &[String]) -> String;
 
     /// Generate TOML configuration.
+    ///
+    /// The fields are in declaration order and sections can be moved first with
+    /// `#[configopt(section_order(..))]` so the output only changes when the config does. A
+    /// versioned type starts the config with its current schema version.
     fn toml_config(&self) -> String {
-        self.toml_config_with_prefix(&[])
+        let config = match Self::version_field() {
+            Some(version_field) => format!(
+                "{} = {}\n\n{}",
+                version_field,
+                current_version(&Self::migrations()),
+                self.toml_config_with_prefix(&[])
+            ),
+            None => self.toml_config_with_prefix(&[]),
+        };
+        toml_layout::arrange(&config, Self::section_order())
+    }
+
+    #[doc(hidden)]
+    fn toml_values_with_prefix(
+        &self,
+        serde_prefix: &[String],
+        secrets: bool,
+    ) -> std::result::Result<toml::value::Table, TomlValueError>;
+
+    /// The values which are set as a TOML table
+    ///
+    /// Unlike `toml_config` every set value is included as it is, including hidden fields and
+    /// leaves whose type implements `Serialize`. The values of a selected subcommand are in a
+    /// table named after its variant (eg `[command.Serve]`). Secrets are left out. It is an error
+    /// if a value cannot be represented in TOML (eg an integer above `i64::MAX`). A versioned type
+    /// includes its current schema version.
+    fn toml_values(&self) -> std::result::Result<toml::value::Table, TomlValueError> {
+        let mut values = self.toml_values_with_prefix(&[], false)?;
+        if let Some(version_field) = Self::version_field() {
+            let version = current_version(&Self::migrations()) as i64;
+            values.insert(String::from(version_field), toml::Value::Integer(version));
+        }
+        Ok(values)
+    }
+
+    /// A stable hash of the values which are set excluding secrets
+    ///
+    /// Log it as the version of the config, compare it across replicas to detect drift, or compare
+    /// it before and after a reload to check if anything changed. Every value of
+    /// [`toml_values`](#method.toml_values) is hashed so hidden fields and leaves change the
+    /// fingerprint too. It is an error if a value cannot be represented in TOML.
+    fn fingerprint(&self) -> std::result::Result<Fingerprint, TomlValueError> {
+        self.toml_values()
+            .map(|values| Fingerprint::of_values(&values))
+    }
+
+    /// Write the values which are set to a file
+    ///
+    /// The values of [`toml_values`](#method.toml_values) are written so secrets are never
+    /// written. Use `From` to convert a full config into its `ConfigOptType` to persist the
+    /// current effective configuration.
+    fn write_to_file(&self, path: impl AsRef<Path>, format: ConfigFormat) -> Result<()> {
+        let path = path.as_ref();
+        let contents = format.render(&config_format::values(self, path)?, path)?;
+        config_format::write(path, &contents)
+    }
+
+    /// Write the configuration to a file unless the file already exists, returning if this call
+    /// wrote it
+    ///
+    /// This is safe to call from several processes at once, for example replicas which all
+    /// generate their config on first start. Exactly one of them writes the file and the rest wait
+    /// until it is complete. The file is never seen partially written.
+    fn write_to_file_once(&self, path: impl AsRef<Path>, format: ConfigFormat) -> Result<bool> {
+        let path = path.as_ref();
+        if path.exists() {
+            return Ok(false);
+        }
+        let contents = format.render(&config_format::values(self, path)?, path)?;
+        config_format::write_once(path, &contents)
+    }
+
+    /// Prompt on the terminal for each required field which is not set and write the completed
+    /// config to `path`
+    ///
+    /// See [`wizard`](fn.wizard.html).
+    #[cfg(feature = "wizard")]
+    fn wizard(&mut self, path: impl AsRef<Path>) -> Result<()>
+    where
+        Self: Sized + IgnoreHelp + Partial + DeserializeOwned,
+    {
+        wizard::wizard(self, path)
+    }
+
+    /// Get the aliases of all fields including the fields of flattened structs and subcommands
+    fn deprecated_aliases() -> Vec<Alias>;
+
+    /// The presets defined in code with `#[configopt(presets(..))]`
+    fn presets() -> Vec<Preset> {
+        Vec::new()
+    }
+
+    /// The key holding the schema version of config files set with
+    /// `#[configopt(version_field("<key>"))]`
+    fn version_field() -> Option<&'static str> {
+        None
+    }
+
+    /// The migrations between schema versions defined in code with `#[configopt(migrations(..))]`
+    ///
+    /// Config files at an older version are migrated in memory when they are loaded.
+    fn migrations() -> Vec<Migration> {
+        Vec::new()
+    }
+
+    /// The dotenv file loaded into the environment before parsing set with
+    /// `#[configopt(dotenv("<path>"))]`
+    fn dotenv_file() -> Option<&'static str> {
+        None
+    }
+
+    /// The top level sections which come first in the generated config in this order set with
+    /// `#[configopt(section_order("<section>", ..))]`
+    fn section_order() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// The table holding the keys of this binary in a config file shared with other binaries set
+    /// with `#[configopt(binary("<binary>"))]`
+    ///
+    /// The sections the binaries share are defined once as a `ConfigOptSection` and used as a
+    /// `#[configopt(section)]` field of each root type. A section is read from its table at the
+    /// top level and the keys of the table of this binary are merged over it. Every other key at
+    /// the top level belongs to another binary and is not read, so the binaries do not list each
+    /// other and a field may be named like another binary.
+    ///
+    /// ```toml
+    /// [common]
+    /// log_level = "info"
+    ///
+    /// [server]
+    /// port = 8080
+    ///
+    /// [worker]
+    /// threads = 4
+    /// ```
+    fn binary() -> Option<&'static str> {
+        None
+    }
+
+    /// The `#[configopt(section)]` fields of a `binary` read from the top level of the config file
+    #[doc(hidden)]
+    fn shared_sections() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Describe each preset defined in code and the values it sets
+    fn presets_help() -> String
+    where
+        Self: Sized,
+    {
+        describe_presets(&Self::presets())
+    }
+
+    /// The CLI argument names of bool flags set with `#[configopt(negatable)]` which can be turned
+    /// off with `--no-<long>`
+    fn negatable_args() -> Vec<&'static str>;
+
+    /// Turn off the negatable bool flag with the CLI argument name `arg`
+    fn negate_arg(&mut self, arg: &str);
+
+    /// The dotted paths of the fields which are set
+    ///
+    /// A missing key is unset so lower layers and defaults apply. An empty list (`key = []`) is set
+    /// and replaces the lists of lower layers unless the field is `#[configopt(empty_is_unset)]` or
+    /// a positional `Vec`. Positional values are set from config files like any other list
+    /// (`args = ["a", "b"]`) and positional values on the CLI replace them as a whole.
+    fn set_fields(&self) -> Vec<String>;
+
+    /// Are string values interpolated while loading set with `#[configopt(interpolate)]`
+    ///
+    /// Interpolation is opt-in because a value from the CLI may contain a literal `${`.
+    #[doc(hidden)]
+    fn interpolates() -> bool {
+        false
+    }
+
+    /// Replace each `${name}` in string values using `lookup`
+    fn interpolate_with(
+        &mut self,
+        lookup: &dyn Fn(&str) -> Option<String>,
+    ) -> std::result::Result<(), String>;
+
+    /// Replace each `${name}` in string values with the field at the dotted path `name` or the
+    /// environment variable `name`
+    ///
+    /// Fields are referenced by their values before interpolation. Secrets cannot be referenced so
+    /// a template never copies one into a field which is not secret. Use `$${` for a literal `${`
+    /// and `#[configopt(no_interpolate)]` to leave a field as it is.
+    fn interpolate_config(&mut self) -> Result<()> {
+        let config = toml::Value::Table(
+            self.toml_values_with_prefix(&[], false)
+                .map_err(|e| Error::Interpolation(e.to_string()))?,
+        );
+        self.interpolate_with(&|name| interpolate::lookup(&config, name))
+            .map_err(Error::Interpolation)
+    }
+
+    /// Replace each string value which is a reference using `resolve`
+    fn resolve_references_with(
+        &mut self,
+        resolve: &dyn Fn(&str) -> Option<std::result::Result<String, String>>,
+    ) -> std::result::Result<(), String>;
+
+    /// Replace each string value starting with the scheme of a registered
+    /// [`Resolver`](trait.Resolver.html) (eg `file:/run/secrets/db`) with the value it refers to
+    ///
+    /// Secrets are resolved too. References are resolved after interpolation so a template may
+    /// build a reference. Only fields holding strings or paths can be references since other
+    /// values are parsed before they are resolved.
+    fn resolve_references(&mut self) -> Result<()> {
+        if !resolver::has_resolvers() {
+            return Ok(());
+        }
+        self.resolve_references_with(&resolver::resolve)
+            .map_err(Error::Reference)
+    }
+
+    #[doc(hidden)]
+    fn schema_fields(prefix: &str) -> Vec<SchemaField>;
+
+    /// The subcommands a config file can select by name if this is a subcommand enum
+    fn subcommand_variants() -> Vec<SubcommandVariant> {
+        Vec::new()
+    }
+
+    /// The CLI names of the subcommand which is set and any nested subcommands
+    fn subcommand_path(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// The keys accepted in config files
+    ///
+    /// Export the schema of a released binary with `Schema::to_json` and compare it to the schema
+    /// of a new build with `Schema::breaking_changes` to catch changes which would break deployed
+    /// config files.
+    ///
+    /// This is empty for a type with `#[configopt(skip(schema))]`.
+    fn schema() -> Schema
+    where
+        Self: Sized,
+    {
+        Self::runtime_schema()
+    }
+
+    /// The schema used to load and check values
+    ///
+    /// Unlike `schema` this is generated with `#[configopt(skip(schema))]` too so constraints,
+    /// directory loading, and field paths keep working.
+    #[doc(hidden)]
+    fn runtime_schema() -> Schema
+    where
+        Self: Sized,
+    {
+        Schema::new(Self::schema_fields(""))
+    }
+
+    /// Check each field which is set satisfies the `#[configopt(min, max, matches)]` constraints
+    /// and possible values of its key
+    ///
+    /// This runs on the merged config when parsing with `ConfigOpt`. The typed values are checked
+    /// so secrets, hidden fields, and leaves are checked too. A value which cannot be represented
+    /// in TOML (eg an integer above `i64::MAX`) cannot be checked and is reported as a violation.
+    fn check_constraints(&self) -> Result<()>
+    where
+        Self: Sized,
+    {
+        let values = self
+            .toml_values_with_prefix(&[], true)
+            .map_err(|e| Error::Constraints(vec![unchecked_value(e)]))?;
+        let violations = Self::runtime_schema().check_constraints(&toml::Value::Table(values));
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Constraints(violations))
+        }
+    }
+}
+
+fn unchecked_value(e: TomlValueError) -> Violation {
+    Violation::Constraint {
+        path: e.path,
+        message: format!("cannot be checked: {}", e.message),
+    }
+}
+
+/// Parse `iter` the way `ConfigOpt::try_from_iter_with_configopt` does getting the values of config
+/// files with `patch`
+fn parse_with_configopt<S, I, P>(iter: I, patch: P) -> Result<S>
+where
+    S: ConfigOpt,
+    I: IntoIterator,
+    I::Item: Into<OsString> + Clone,
+    P: FnOnce(&mut S::ConfigOptType) -> Result<()>,
+{
+    if let Some(path) = S::ConfigOptType::dotenv_file() {
+        load_dotenv(path)?;
+    }
+    let iter = iter.into_iter().map(Into::into).collect::<Vec<_>>();
+    // Only the `configopt` type knows about aliases so rewrite them to the actual arguments
+    let iter = deprecated::rewrite_args(iter, &S::ConfigOptType::deprecated_aliases());
+    // The `--no-<arg>` negations are applied after the config files are patched in
+    let negatable = S::ConfigOptType::negatable_args();
+    // We ignore the help for the `configopt` type so the help message is rendered for the
+    // actual app.
+    let started = std::time::Instant::now();
+    match try_from_iter_ignore_help_negating::<S::ConfigOptType>(&iter, &negatable) {
+        Ok((mut configopt, negated)) => {
+            let cli_subcommand = configopt.subcommand_path();
+            let load = |configopt: &mut S::ConfigOptType| {
+                LoadTrace::record(TraceSource::Cli, || configopt.set_fields(), started);
+                patch(configopt)
+            };
+            // The trace is the provenance passed to `validate` and explains the config
+            let (result, trace) = LoadTrace::capture(|| load(&mut configopt));
+            // A trace captured by the caller sees every step as well
+            trace.forward();
+            result?;
+            // Negations come from the CLI so they take precedence over config files
+            for arg in negated {
+                configopt.negate_arg(arg);
+            }
+            if let Some(config) = configopt.maybe_config_file() {
+                return Err(Error::ConfigGenerated(config));
+            }
+            if S::ConfigOptType::interpolates() {
+                configopt.interpolate_config()?;
+            }
+            configopt.resolve_references()?;
+            configopt.check_constraints()?;
+            if configopt.maybe_explain_config() {
+                let explained = explain_config(&configopt, &trace, &S::prefixed_clap());
+                return Err(Error::ConfigExplained(explained));
+            }
+            // A subcommand selected by a config file is parsed as if it was on the CLI
+            let iter = if cli_subcommand.is_empty() {
+                subcommand::append_subcommand(iter, &configopt.subcommand_path())
+            } else {
+                iter
+            };
+            // Take into account any values from config files by setting default values. This
+            // is needed so we do not get failures for missing arguments when they are really
+            // set in the config file.
+            let mut s = S::try_from_iter_with_defaults(&iter, &configopt)?;
+            // Take into account any values from config files by taking the values from the
+            // configopt type. This is needed for types that do not always set their value if
+            // a default is set (eg Option<T>). We must use `take` instead of `patch` to be
+            // sure to override any default values with values from the config file.
+            <S as ConfigOpt>::take(&mut s, &mut configopt);
+            s.validate(&Provenance::new(trace))?;
+            Ok(s)
+        }
+        Err(e) => {
+            // Get the error using the actual app
+            S::prefixed_clap().get_matches_from_safe(&iter)?;
+            // We always expect an error to be generated. If we do not get an error return this
+            // error type. This helps with debugging. It would be confusing if ever returned an
+            // `Ok(Self)` with no config file information applied.
+            Err(Error::ExpectedError(e))
+        }
     }
 }
 
@@ -157,6 +1127,38 @@ pub trait ConfigOptType: ConfigOptArgToOsString + StructOpt {
 pub trait ConfigOpt: Sized + StructOpt {
     type ConfigOptType: ConfigOptType + IgnoreHelp;
 
+    /// Check the rules between fields of the loaded config set with `#[configopt(validate(..))]`
+    ///
+    /// This runs last when parsing with `try_from_iter_with_configopt`. `provenance` tells which
+    /// layer set each value.
+    fn validate(&self, _provenance: &Provenance) -> Result<()> {
+        Ok(())
+    }
+
+    /// The `clap::App` of the type with the long flags and environment variables of flattened
+    /// types prefixed as set with `#[configopt(prefix = "..")]` and the environment variables
+    /// named as set with `#[configopt(rename_all_env(".."))]`. The `--no-<flag>` negations of
+    /// `#[configopt(negatable)]` flags are added as well.
+    ///
+    /// Use this instead of `StructOpt::clap` to render help or completions which match what is
+    /// parsed. `StructOpt::clap` and `StructOpt::from_args` know nothing of the prefixes so they
+    /// show and parse the flags of flattened types unprefixed.
+    ///
+    /// Only the long flags and the environment variables are prefixed. Short flags keep their
+    /// names and an argument without a `structopt(env)` is not read from any variable. The
+    /// prefixes are applied to the arguments of the `clap::App` built by `structopt` so they rely
+    /// on the internals of clap 2.
+    fn prefixed_clap<'a, 'b>() -> App<'a, 'b> {
+        let prefixes = Self::ConfigOptType::arg_prefixes();
+        let mut app = Self::clap();
+        prefix::apply(&mut app, &prefixes);
+        let mut configopt_app = Self::ConfigOptType::clap();
+        prefix::apply(&mut configopt_app, &prefixes);
+        prefix::copy_env(&mut app, &configopt_app);
+        negation::add_negations(&mut app, &Self::ConfigOptType::negatable_args());
+        app
+    }
+
     /// Set argument default values then get the struct from the command line arguments.
     ///
     /// Print the error message and quit the program in case of failure.
@@ -197,11 +1199,12 @@ pub trait ConfigOpt: Sized + StructOpt {
         I: IntoIterator,
         I::Item: Into<OsString> + Clone,
     {
-        let mut app = Self::clap();
+        let mut app = Self::prefixed_clap();
         // An arena allocator is used to extend the lifetimes of the default value strings.
         let arena = UnsyncArena::new();
         let mut arg_path = Vec::new();
         set_defaults_impl(&mut app, &mut arg_path, defaults, &arena);
+        env_value::decode_app(&mut app, &Self::ConfigOptType::decoded_env_args())?;
         let matches = app.get_matches_from_safe(iter)?;
         Ok(Self::from_clap(&matches))
     }
@@ -240,46 +1243,88 @@ pub trait ConfigOpt: Sized + StructOpt {
     /// Returns a configopt::Error in case of failure. This does not exit in the case of --help,
     /// --version, or --generated-config, to achieve the same behavior as `from_iter()` you must
     /// call .exit() on the error value.
+    ///
+    /// Those requests are returned as errors with the `Help` or `Generated`
+    /// [`ErrorCategory`](enum.ErrorCategory.html) without printing anything so this suits library
+    /// code, tests, and REPLs. Warnings such as deprecated or unknown keys in config files are
+    /// still written to stderr.
     fn try_from_iter_with_configopt<I>(iter: I) -> Result<Self>
     where
         I: IntoIterator,
         I::Item: Into<OsString> + Clone,
     {
-        let iter = iter.into_iter().map(Into::into).collect::<Vec<_>>();
-        // We ignore the help for the `configopt` type so the help message is rendered for the
-        // actual app.
-        match Self::ConfigOptType::try_from_iter_ignore_help(&iter) {
-            Ok(mut configopt) => {
-                configopt.patch_with_config_files()?;
-                if let Some(config) = configopt.maybe_config_file() {
-                    return Err(Error::ConfigGenerated(config));
-                }
-                // Take into account any values from config files by setting default values. This
-                // is needed so we do not get failures for missing arguments when they are really
-                // set in the config file.
-                let mut s = Self::try_from_iter_with_defaults(&iter, &configopt)?;
-                // Take into account any values from config files by taking the values from the
-                // configopt type. This is needed for types that do not always set their value if
-                // a default is set (eg Option<T>). We must use `take` instead of `patch` to be
-                // sure to override any default values with values from the config file.
-                <Self as ConfigOpt>::take(&mut s, &mut configopt);
-                Ok(s)
-            }
-            Err(e) => {
-                // Get the error using the actual app
-                Self::from_iter_safe(&iter)?;
-                // We always expect an error to be generated. If we do not get an error return this
-                // error type. This helps with debugging. It would be confusing if ever returned an
-                // `Ok(Self)` with no config file information applied.
-                Err(Error::ExpectedError(e))
+        parse_with_configopt::<Self, _, _>(iter, |configopt| {
+            configopt.patch_with_config_files()?;
+            Ok(())
+        })
+    }
+
+    /// Get the struct from any iterator taking into account config documents which are already in
+    /// memory instead of config files
+    ///
+    /// Print the error message and quit the program in case of failure.
+    fn from_iter_with_files<I>(iter: I, files: &[(ConfigFormat, &str)]) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString> + Clone,
+    {
+        Self::try_from_iter_with_files(iter, files).unwrap_or_else(|e| e.exit())
+    }
+
+    /// Get the struct from any iterator taking into account config documents which are already in
+    /// memory instead of config files
+    ///
+    /// This exercises the same precedence as `try_from_iter_with_configopt` without touching the
+    /// filesystem so tests are deterministic. Later documents take precedence like later
+    /// `--config-files`. The default config files, `--config-files`, and the environment are
+    /// ignored.
+    fn try_from_iter_with_files<I>(iter: I, files: &[(ConfigFormat, &str)]) -> Result<Self>
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString> + Clone,
+    {
+        parse_with_configopt::<Self, _, _>(iter, |configopt| {
+            for (format, contents) in files.iter().rev() {
+                configopt.patch_with_config_str(contents, *format)?;
             }
-        }
+            Ok(())
+        })
+    }
+
+    /// Write completions of the CLI for `shell` using the values of `defaults` as the default
+    /// values of arguments
+    ///
+    /// The defaults are included in the argument descriptions of shells which show them (eg zsh and
+    /// fish). The possible values of an argument, such as the variants of an enum, are completed
+    /// and other arguments which take a value, such as `--config-files`, complete paths.
+    fn write_completions_with_defaults(
+        bin_name: &str,
+        shell: Shell,
+        defaults: &impl ConfigOptArgToOsString,
+        out: &mut impl Write,
+    ) {
+        let mut app = Self::prefixed_clap();
+        let arena = UnsyncArena::new();
+        let help_arena = UnsyncArena::new();
+        set_defaults_impl(&mut app, &mut Vec::new(), defaults, &arena);
+        completions::describe_defaults(&mut app, &help_arena);
+        app.gen_completions_to(bin_name, shell, out);
+    }
+
+    /// Generate completions of the CLI for `shell` with the values of the default config files as
+    /// the default values of arguments
+    fn completions(bin_name: &str, shell: Shell) -> Result<String> {
+        let mut configopt = Self::ConfigOptType::try_from_iter_ignore_help(&[bin_name])?;
+        configopt.patch_with_config_files()?;
+        let mut out = Vec::new();
+        Self::write_completions_with_defaults(bin_name, shell, &configopt, &mut out);
+        Ok(String::from_utf8_lossy(&out).into_owned())
     }
 
     /// CODO
     fn get_help(&self) -> String {
         let mut help = Vec::new();
-        let app = Self::clap();
+        let app = Self::prefixed_clap();
         app.write_help(&mut help).expect("failed to write to help");
         String::from_utf8_lossy(&help).to_string()
     }
@@ -287,7 +1332,7 @@ pub trait ConfigOpt: Sized + StructOpt {
     /// CODO
     fn get_long_help(&self) -> String {
         let mut help = Vec::new();
-        let mut app = Self::clap();
+        let mut app = Self::prefixed_clap();
         app.write_long_help(&mut help)
             .expect("failed to write to long help");
         String::from_utf8_lossy(&help).to_string()
@@ -298,4 +1343,17 @@ pub trait ConfigOpt: Sized + StructOpt {
 
     /// Take each field from `other` and set it in `self`
     fn take(&mut self, other: &mut Self::ConfigOptType);
+
+    /// Run `f` with a copy of `self` updated with the fields set in `partial` overriding the config
+    /// on the current thread
+    ///
+    /// See [`with_override`](fn.with_override.html) for which accessors see the override.
+    fn with_override<R>(&self, partial: &mut Self::ConfigOptType, f: impl FnOnce() -> R) -> R
+    where
+        Self: Clone + 'static,
+    {
+        let mut value = self.clone();
+        value.take(partial);
+        scoped::with_override(value, f)
+    }
 }
@@ -0,0 +1,126 @@
+use crate::{deprecated, include, Alias, Level, Schema, Violation};
+use std::{fmt, fs, path::Path};
+
+/// A problem found by linting a config file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Lint {
+    /// The file could not be read, is not valid TOML, or its includes could not be resolved
+    Invalid { message: String },
+    /// A key which is not in the schema
+    Unknown { key: String },
+    /// A key with a value of the wrong kind
+    TypeMismatch {
+        key: String,
+        expected: String,
+        found: String,
+    },
+    /// A deprecated alias of a key
+    Deprecated {
+        key: String,
+        replacement: String,
+        note: String,
+    },
+    /// A required key which is not set
+    Missing { key: String },
+    /// A value which does not satisfy the constraints of its key
+    Constraint { key: String, message: String },
+}
+
+impl Lint {
+    /// Unknown and deprecated keys do not prevent loading the file so they are only warnings
+    pub fn level(&self) -> Level {
+        match self {
+            Self::Unknown { .. } | Self::Deprecated { .. } => Level::Warning,
+            _ => Level::Error,
+        }
+    }
+}
+
+impl From<Violation> for Lint {
+    fn from(violation: Violation) -> Self {
+        match violation {
+            Violation::Missing { path } => Self::Missing { key: path },
+            Violation::TypeMismatch {
+                path,
+                expected,
+                found,
+            } => Self::TypeMismatch {
+                key: path,
+                expected,
+                found,
+            },
+            Violation::Unknown { path } => Self::Unknown { key: path },
+            Violation::Constraint { path, message } => Self::Constraint { key: path, message },
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.level() {
+            Level::Error => write!(f, "error: ")?,
+            Level::Warning => write!(f, "warning: ")?,
+        }
+        match self {
+            Self::Invalid { message } => write!(f, "{}", message),
+            Self::Unknown { key } => write!(f, "unknown key `{}`", key),
+            Self::TypeMismatch {
+                key,
+                expected,
+                found,
+            } => write!(f, "`{}` must be a {} not a {}", key, expected, found),
+            Self::Deprecated {
+                key,
+                replacement,
+                note,
+            } => write!(
+                f,
+                "`{}` is deprecated, use `{}` instead: {}",
+                key, replacement, note
+            ),
+            Self::Missing { key } => write!(f, "required key `{}` is not set", key),
+            Self::Constraint { key, message } => write!(f, "`{}` {}", key, message),
+        }
+    }
+}
+
+/// Lint the TOML config file at `path` against `schema` without loading it
+///
+/// The files listed in the `include` key are merged the same way they are when loading.
+pub fn lint_toml_file(path: impl AsRef<Path>, schema: &Schema, aliases: &[Alias]) -> Vec<Lint> {
+    let path = path.as_ref();
+    let invalid = |message: String| vec![Lint::Invalid { message }];
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            return invalid(format!(
+                "failed to read '{}', err: {}",
+                path.to_string_lossy(),
+                e
+            ))
+        }
+    };
+    let table = match toml::from_str::<toml::value::Table>(&contents) {
+        Ok(table) => table,
+        Err(e) => return invalid(format!("'{}' {}", path.to_string_lossy(), e)),
+    };
+    let table = match include::resolve(path, table) {
+        Ok(table) => table,
+        Err(e) => return invalid(e.to_string()),
+    };
+    let mut lints = deprecated::config_file_deprecations(&table, None, aliases, path)
+        .into_iter()
+        .map(|deprecation| Lint::Deprecated {
+            key: deprecation.alias,
+            replacement: deprecation.name,
+            note: deprecation.note.unwrap_or_default(),
+        })
+        .collect::<Vec<_>>();
+    lints.extend(
+        schema
+            .check(&toml::Value::Table(table))
+            .into_iter()
+            .map(Lint::from),
+    );
+    lints
+}
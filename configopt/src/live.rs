@@ -0,0 +1,447 @@
+use crate::ConfigDiff;
+use arc_swap::ArcSwap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fmt, fs,
+    io::{self, ErrorKind as IoErrorKind},
+    ops::Deref,
+    path::Path,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant, SystemTime},
+};
+
+struct History<T> {
+    capacity: usize,
+    diff: fn(&T, &T) -> ConfigDiff,
+    // From oldest to newest where the newest is the current value
+    snapshots: VecDeque<Snapshot<T>>,
+}
+
+impl<T> History<T> {
+    fn push(&mut self, value: &Arc<T>, version: u64) {
+        let diff = match self.snapshots.back() {
+            Some(previous) => (self.diff)(&previous.value, value),
+            None => ConfigDiff::default(),
+        };
+        self.snapshots.push_back(Snapshot {
+            version,
+            timestamp: SystemTime::now(),
+            value: Arc::clone(value),
+            diff,
+        });
+        self.truncate();
+    }
+
+    fn truncate(&mut self) {
+        while self.snapshots.len() > self.capacity.max(1) {
+            self.snapshots.pop_front();
+        }
+    }
+}
+
+// The current value and its version which are swapped together so readers never see a mismatch
+struct Current<T> {
+    value: Arc<T>,
+    version: u64,
+}
+
+// What is only touched by replacements and subscribers waiting for them
+struct State<T> {
+    version: u64,
+    history: Option<History<T>>,
+}
+
+struct Shared<T> {
+    // Read without locking so readers never wait on a replacement, history, or subscribers
+    current: ArcSwap<Current<T>>,
+    state: Mutex<State<T>>,
+    changed: Condvar,
+}
+
+/// The current resolved config which is replaced atomically on reload
+///
+/// Every clone shares the same value. Reads take a [`Guard`](struct.Guard.html) which is a
+/// snapshot unaffected by later replacements so a request sees one consistent config from start
+/// to finish. Reads do not lock so they never wait on a replacement. Each replacement increments the version and wakes every
+/// [`Subscription`](struct.Subscription.html) so background work can react to new config
+/// without polling.
+///
+/// Create it with [`with_history`](#method.with_history) to keep the most recent values so a bad
+/// dynamic change can be inspected with [`history`](#method.history) and reverted with
+/// [`rollback`](#method.rollback).
+pub struct Live<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Live<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                current: ArcSwap::from_pointee(Current {
+                    value: Arc::new(value),
+                    version: 0,
+                }),
+                state: Mutex::new(State {
+                    version: 0,
+                    history: None,
+                }),
+                changed: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Keep the last `capacity` values including the current one
+    ///
+    /// `diff` finds the fields which changed between two values, for example
+    /// `|a, b| ConfigOptApp::from(a.clone()).diff(&ConfigOptApp::from(b.clone()))`.
+    pub fn with_history(value: T, capacity: usize, diff: fn(&T, &T) -> ConfigDiff) -> Self {
+        let live = Self::new(value);
+        {
+            let mut state = live.lock();
+            let mut history = History {
+                capacity,
+                diff,
+                snapshots: VecDeque::new(),
+            };
+            history.push(&live.shared.current.load().value, state.version);
+            state.history = Some(history);
+        }
+        live
+    }
+
+    // A panic while replacing cannot leave the value partially written so ignore poisoning
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.shared.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Replacements hold the lock so the current value only changes while it is held
+    fn replace(&self, state: &mut State<T>, value: Arc<T>) -> Arc<T> {
+        state.version += 1;
+        if let Some(history) = &mut state.history {
+            history.push(&value, state.version);
+        }
+        let previous = self.shared.current.swap(Arc::new(Current {
+            value,
+            version: state.version,
+        }));
+        self.shared.changed.notify_all();
+        Arc::clone(&previous.value)
+    }
+
+    /// A snapshot of the current value
+    ///
+    /// This does not lock so it never waits on a replacement.
+    pub fn load(&self) -> Guard<T> {
+        let current = self.shared.current.load();
+        Guard {
+            value: Arc::clone(&current.value),
+            version: current.version,
+        }
+    }
+
+    /// The number of times the value was replaced
+    pub fn version(&self) -> u64 {
+        self.shared.current.load().version
+    }
+
+    /// Replace the current value returning the previous value
+    pub fn store(&self, value: T) -> Arc<T> {
+        let mut state = self.lock();
+        self.replace(&mut state, Arc::new(value))
+    }
+
+    /// Replace the current value with a value computed from it
+    ///
+    /// No other replacement can happen between reading the current value and storing the new one.
+    pub fn update(&self, f: impl FnOnce(&T) -> T) -> Guard<T> {
+        match self.try_update(|current| Ok::<_, ()>(f(current))) {
+            Ok(guard) => guard,
+            Err(()) => unreachable!("the update is infallible"),
+        }
+    }
+
+    /// Like [`update`](#method.update) but keep the current value if `f` fails, for example when
+    /// a reloaded config is invalid
+    pub fn try_update<E>(&self, f: impl FnOnce(&T) -> Result<T, E>) -> Result<Guard<T>, E> {
+        let mut state = self.lock();
+        let value = Arc::new(f(&self.shared.current.load().value)?);
+        self.replace(&mut state, Arc::clone(&value));
+        Ok(Guard {
+            value,
+            version: state.version,
+        })
+    }
+
+    /// The kept values from oldest to newest where the last is the current value
+    ///
+    /// This is empty unless the config was created with [`with_history`](#method.with_history).
+    pub fn history(&self) -> Vec<Snapshot<T>> {
+        self.lock()
+            .history
+            .as_ref()
+            .map_or_else(Vec::new, |history| {
+                history.snapshots.iter().cloned().collect()
+            })
+    }
+
+    /// Restore the value from `n` replacements ago returning `None` if it is no longer kept
+    ///
+    /// The restored value is stored as a new replacement so subscribers are notified and the
+    /// rollback itself can be rolled back.
+    pub fn rollback(&self, n: usize) -> Option<Guard<T>> {
+        let mut state = self.lock();
+        let snapshots = &state.history.as_ref()?.snapshots;
+        let value = Arc::clone(&snapshots.get(snapshots.len().checked_sub(n + 1)?)?.value);
+        self.replace(&mut state, Arc::clone(&value));
+        Some(Guard {
+            value,
+            version: state.version,
+        })
+    }
+
+    /// Write the kept values to the JSON file at `path` so they survive a restart
+    ///
+    /// `record` converts each value into what is written. Secrets are written in plain text unless
+    /// `record` leaves them out or encrypts them.
+    pub fn save_history<V: Serialize>(
+        &self,
+        path: impl AsRef<Path>,
+        record: impl Fn(&T) -> V,
+    ) -> io::Result<()> {
+        let records = self
+            .history()
+            .iter()
+            .map(|snapshot| Record {
+                version: snapshot.version,
+                timestamp: snapshot.timestamp,
+                changed: snapshot.diff.changed().to_vec(),
+                restart_required: snapshot.diff.restart_required().to_vec(),
+                value: record(&snapshot.value),
+            })
+            .collect::<Vec<_>>();
+        let json = serde_json::to_string_pretty(&records)
+            .map_err(|e| io::Error::new(IoErrorKind::InvalidData, e))?;
+        fs::write(path, json)
+    }
+
+    /// Keep the values written with [`save_history`](#method.save_history) as older than the
+    /// current value so they can be rolled back to
+    ///
+    /// `value` converts each written record back into a value. The versions of the kept values
+    /// continue from the last written one so every version is unique. Call this before subscribing
+    /// because the change of version wakes subscribers. The values are only kept if the config was
+    /// created with [`with_history`](#method.with_history).
+    pub fn load_history<V: DeserializeOwned>(
+        &self,
+        path: impl AsRef<Path>,
+        value: impl Fn(V) -> T,
+    ) -> io::Result<()> {
+        let json = fs::read_to_string(path)?;
+        let records = serde_json::from_str::<Vec<Record<V>>>(&json)
+            .map_err(|e| io::Error::new(IoErrorKind::InvalidData, e))?;
+        let mut state = self.lock();
+        let State { version, history } = &mut *state;
+        if let Some(history) = history {
+            let loaded = records
+                .into_iter()
+                .map(|record| Snapshot {
+                    version: record.version,
+                    timestamp: record.timestamp,
+                    value: Arc::new(value(record.value)),
+                    diff: ConfigDiff::new(record.changed)
+                        .with_restart_required(record.restart_required),
+                })
+                .collect::<Vec<_>>();
+            if let Some(last) = loaded.last() {
+                let offset = last.version + 1;
+                *version += offset;
+                let current = self.shared.current.load_full();
+                self.shared.current.store(Arc::new(Current {
+                    value: Arc::clone(&current.value),
+                    version: *version,
+                }));
+                for snapshot in &mut history.snapshots {
+                    snapshot.version += offset;
+                }
+                // The oldest value of this run changed from the last loaded value
+                let diff = history.diff;
+                if let Some(first) = history.snapshots.front_mut() {
+                    first.diff = diff(&last.value, &first.value);
+                }
+            }
+            for snapshot in loaded.into_iter().rev() {
+                history.snapshots.push_front(snapshot);
+            }
+            history.truncate();
+        }
+        Ok(())
+    }
+
+    /// Get notified of each replacement after this call
+    pub fn subscribe(&self) -> Subscription<T> {
+        Subscription {
+            live: self.clone(),
+            seen: self.version(),
+        }
+    }
+}
+
+impl<T> Clone for Live<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> From<T> for Live<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Live<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Live").field(&*self.load()).finish()
+    }
+}
+
+/// A value kept in the history of a [`Live`](struct.Live.html) config
+#[derive(Debug)]
+pub struct Snapshot<T> {
+    version: u64,
+    timestamp: SystemTime,
+    value: Arc<T>,
+    diff: ConfigDiff,
+}
+
+impl<T> Snapshot<T> {
+    /// The version of the `Live` config when the value was stored
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// When the value was stored
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    pub fn value(&self) -> &Arc<T> {
+        &self.value
+    }
+
+    /// The fields which changed from the previous value
+    pub fn diff(&self) -> &ConfigDiff {
+        &self.diff
+    }
+}
+
+impl<T> Clone for Snapshot<T> {
+    fn clone(&self) -> Self {
+        Self {
+            version: self.version,
+            timestamp: self.timestamp,
+            value: Arc::clone(&self.value),
+            diff: self.diff.clone(),
+        }
+    }
+}
+
+// A snapshot as it is written by `save_history`
+#[derive(Serialize, Deserialize)]
+struct Record<V> {
+    version: u64,
+    timestamp: SystemTime,
+    changed: Vec<String>,
+    #[serde(default)]
+    restart_required: Vec<String>,
+    value: V,
+}
+
+/// A snapshot of the value of a [`Live`](struct.Live.html) config
+#[derive(Debug)]
+pub struct Guard<T> {
+    value: Arc<T>,
+    version: u64,
+}
+
+impl<T> Guard<T> {
+    /// The version of the `Live` config this snapshot was taken at
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn into_arc(self) -> Arc<T> {
+        self.value
+    }
+}
+
+impl<T> Clone for Guard<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: Arc::clone(&self.value),
+            version: self.version,
+        }
+    }
+}
+
+impl<T> Deref for Guard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// Waits for the value of a [`Live`](struct.Live.html) config to be replaced
+///
+/// Replacements which happen while no one waits are not lost. The next wait returns the latest
+/// value immediately skipping any values in between.
+pub struct Subscription<T> {
+    live: Live<T>,
+    seen: u64,
+}
+
+impl<T> Subscription<T> {
+    /// Has the value been replaced since it was last seen
+    pub fn has_changed(&self) -> bool {
+        self.live.version() != self.seen
+    }
+
+    /// Wait until the value is replaced and return the new value
+    pub fn changed(&mut self) -> Guard<T> {
+        let mut state = self.live.lock();
+        while state.version == self.seen {
+            state = self
+                .live
+                .shared
+                .changed
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        self.seen = state.version;
+        self.live.load()
+    }
+
+    /// Like [`changed`](#method.changed) but give up after `timeout`
+    pub fn changed_timeout(&mut self, timeout: Duration) -> Option<Guard<T>> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.live.lock();
+        while state.version == self.seen {
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            state = self
+                .live
+                .shared
+                .changed
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        self.seen = state.version;
+        Some(self.live.load())
+    }
+}
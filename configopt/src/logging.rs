@@ -0,0 +1,173 @@
+//! Drive a `tracing-subscriber` from a [`LoggingConfig`](struct.LoggingConfig.html) block
+//!
+//! Only the filter can be swapped on a running subscriber so reloads of `format` and `output` are
+//! reported as needing a restart.
+use crate::{ConfigOpt, Reloadable};
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+use structopt::StructOpt;
+use tracing_subscriber::{layer::SubscriberExt, reload, EnvFilter, Registry};
+
+/// The logging settings of an app
+///
+/// Flatten this into the config of an app to get the `--log-level`, `--log-format`, and
+/// `--log-output` arguments and a `[logging]` table in its config files.
+#[derive(ConfigOpt, StructOpt, Clone, Debug, PartialEq)]
+#[configopt(derive(Clone, Debug, PartialEq))]
+pub struct LoggingConfig {
+    /// The `tracing` filter directives (eg `info,my_app=debug`) [default: info]
+    #[structopt(long = "log-level")]
+    pub level: Option<String>,
+    /// How log lines are formatted [default: full]
+    #[structopt(long = "log-format", possible_values = &["full", "compact"])]
+    pub format: Option<String>,
+    /// The file log lines are appended to instead of `stderr`
+    #[structopt(long = "log-output")]
+    pub output: Option<PathBuf>,
+}
+
+// The defaults are applied here instead of with `default_value` which would always take precedence
+// over config files
+impl LoggingConfig {
+    pub fn level(&self) -> &str {
+        self.level.as_deref().unwrap_or("info")
+    }
+
+    pub fn format(&self) -> &str {
+        self.format.as_deref().unwrap_or("full")
+    }
+}
+
+/// An error initializing or reloading logging
+#[derive(Debug)]
+pub enum LoggingError {
+    /// The level is not a valid filter
+    Filter(String),
+    /// The output file could not be opened
+    Output(PathBuf, io::Error),
+    /// A global subscriber is already installed
+    Init(String),
+    /// The subscriber installed by `Logging::init` no longer exists
+    Reload(String),
+}
+
+impl fmt::Display for LoggingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Filter(e) => write!(f, "Invalid log level, err: {}", e),
+            Self::Output(path, e) => write!(
+                f,
+                "Failed to open log output '{}', err: {}",
+                path.to_string_lossy(),
+                e
+            ),
+            Self::Init(e) => write!(f, "Failed to install the logger, err: {}", e),
+            Self::Reload(e) => write!(f, "Failed to reload the log level, err: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LoggingError {}
+
+enum LogWriter {
+    Stderr(io::Stderr),
+    File(Arc<File>),
+}
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Stderr(stderr) => stderr.write(buf),
+            Self::File(file) => (&**file).write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Stderr(stderr) => stderr.flush(),
+            Self::File(file) => (&**file).flush(),
+        }
+    }
+}
+
+fn filter(config: &LoggingConfig) -> Result<EnvFilter, LoggingError> {
+    EnvFilter::try_new(config.level()).map_err(|e| LoggingError::Filter(e.to_string()))
+}
+
+/// The global `tracing` subscriber installed from a `LoggingConfig`
+///
+/// This is the applier for the logging block of a config: call [`apply`](#method.apply) with each
+/// reloaded config.
+pub struct Logging {
+    handle: reload::Handle<EnvFilter, Registry>,
+    config: Reloadable<LoggingConfig>,
+}
+
+impl Logging {
+    /// Install a global subscriber configured by `config`
+    pub fn init(config: &LoggingConfig) -> Result<Self, LoggingError> {
+        let (filter, handle) = reload::Layer::new(filter(config)?);
+        let file = match &config.output {
+            Some(path) => Some(Arc::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| LoggingError::Output(path.clone(), e))?,
+            )),
+            None => None,
+        };
+        let ansi = file.is_none();
+        let make_writer = move || match &file {
+            Some(file) => LogWriter::File(Arc::clone(file)),
+            None => LogWriter::Stderr(io::stderr()),
+        };
+        let registry = Registry::default().with(filter);
+        let result = if config.format() == "compact" {
+            let layer = tracing_subscriber::fmt::layer()
+                .compact()
+                .with_ansi(ansi)
+                .with_writer(make_writer);
+            tracing::subscriber::set_global_default(registry.with(layer))
+        } else {
+            let layer = tracing_subscriber::fmt::layer()
+                .with_ansi(ansi)
+                .with_writer(make_writer);
+            tracing::subscriber::set_global_default(registry.with(layer))
+        };
+        result.map_err(|e| LoggingError::Init(e.to_string()))?;
+        Ok(Self {
+            handle,
+            config: Reloadable::new(config.clone()),
+        })
+    }
+
+    /// Swap the filter for the level of `config`
+    ///
+    /// An invalid level keeps the current filter. Returns `true` if `format` or `output` changed
+    /// which only take effect after a restart.
+    pub fn apply(&self, config: &LoggingConfig) -> Result<bool, LoggingError> {
+        let current = self.config.get();
+        if current.level() != config.level() {
+            self.handle
+                .reload(filter(config)?)
+                .map_err(|e| LoggingError::Reload(e.to_string()))?;
+        }
+        let needs_restart = current.format() != config.format() || current.output != config.output;
+        if needs_restart {
+            tracing::warn!("changes to the log format and output take effect after a restart");
+        }
+        self.config.replace(config.clone());
+        Ok(needs_restart)
+    }
+
+    /// A handle to the logging config which is currently applied
+    pub fn config(&self) -> Reloadable<LoggingConfig> {
+        self.config.clone()
+    }
+}
@@ -0,0 +1,182 @@
+use lazy_static::lazy_static;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+lazy_static! {
+    static ref CATALOG: RwLock<Option<Arc<dyn Catalog>>> = RwLock::new(None);
+}
+
+/// Identifies a diagnostic message which can be translated
+///
+/// The named placeholders each template can use are listed with the variant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MessageId {
+    /// The `error:` label
+    Error,
+    /// The `warning:` label
+    Warning,
+    /// The `help:` label
+    Help,
+    /// The `USAGE:` label
+    Usage,
+    /// `{help}` the help flag
+    MoreInformation,
+    /// `{fields}` the missing fields
+    MissingFields,
+    MissingFieldsHelp,
+    /// `{key}` the unknown key
+    UnknownKey,
+    /// `{path}` the config file which set the unknown key
+    UnknownKeyLocation,
+    /// `{suggestion}` the known key
+    DidYouMean,
+    /// `{path}` the config file and `{error}` the underlying error
+    ConfigFile,
+    /// `{name}` the environment variable and `{error}` the underlying error
+    Env,
+    /// `{error}` the underlying error
+    Interpolation,
+    /// `{path}` the field, `{source}` the layer which set it, and `{error}` the underlying error
+    InvalidValue,
+    /// `{path}` the config file and `{key}` the unknown key rejected by `#[configopt(strict)]`
+    StrictUnknownKey,
+    /// `{path}` the config file, `{key}` the unknown key, and `{suggestion}` the known key
+    StrictUnknownKeyDidYouMean,
+    /// `{path}` the file and `{error}` the underlying error
+    WriteConfig,
+    /// `{error}` the underlying error
+    Reference,
+    /// `{path}` the field and `{error}` the underlying error
+    Decrypt,
+    /// `{path}` the field, `{location}` the line in a config file, and `{error}` the underlying
+    /// error
+    FieldAt,
+    /// `{path}` the field, `{source}` the layer which set it, and `{error}` the underlying error
+    FieldFrom,
+    /// `{fields}` the missing fields
+    MissingFieldsError,
+    /// `{violations}` the violated constraints
+    Constraints,
+    /// `{error}` the error of the `configopt` app
+    ExpectedError,
+}
+
+impl MessageId {
+    /// The builtin English template
+    pub fn default_template(self) -> &'static str {
+        match self {
+            Self::Error => "error:",
+            Self::Warning => "warning:",
+            Self::Help => "help:",
+            Self::Usage => "USAGE:",
+            Self::MoreInformation => "For more information try {help}",
+            Self::MissingFields => "missing required config values {fields}",
+            Self::MissingFieldsHelp => {
+                "set them on the command line, in the environment, or in a config file"
+            }
+            Self::UnknownKey => "unknown key `{key}`",
+            Self::UnknownKeyLocation => "the key is set in config file '{path}'",
+            Self::DidYouMean => "did you mean {suggestion}",
+            Self::ConfigFile => "Failed to parse file '{path}', err: {error}",
+            Self::Env => "Failed to parse environment variable '{name}', err: {error}",
+            Self::Interpolation => "Failed to interpolate config value, err: {error}",
+            Self::InvalidValue => "invalid value for `{path}` from {source}: {error}",
+            Self::StrictUnknownKey => "Failed to parse file '{path}', err: unknown key `{key}`",
+            Self::StrictUnknownKeyDidYouMean => {
+                "Failed to parse file '{path}', err: unknown key `{key}`, did you mean `{suggestion}`?"
+            }
+            Self::WriteConfig => "Failed to write file '{path}', err: {error}",
+            Self::Reference => "Failed to resolve config value, err: {error}",
+            Self::Decrypt => "Failed to decrypt `{path}`, err: {error}",
+            Self::FieldAt => "Invalid value for `{path}` at {location}, err: {error}",
+            Self::FieldFrom => "Invalid value for `{path}` from {source}, err: {error}",
+            Self::MissingFieldsError => "Missing required config values {fields}",
+            Self::Constraints => "Invalid config values {violations}",
+            Self::ExpectedError => {
+                "The `configopt` app generated an error, but the actual app did not. This should never happen. err: {error}"
+            }
+        }
+    }
+}
+
+/// A source of translated message templates
+///
+/// Implement this to back diagnostics with a localization system (eg fluent). A plain
+/// `HashMap<MessageId, String>` can be used as a simple string table.
+pub trait Catalog: Send + Sync {
+    /// The template for `id` or `None` to use the builtin English template
+    fn template(&self, id: MessageId) -> Option<String>;
+}
+
+impl Catalog for HashMap<MessageId, String> {
+    fn template(&self, id: MessageId) -> Option<String> {
+        self.get(&id).cloned()
+    }
+}
+
+/// Use `catalog` for all diagnostic messages
+pub fn set_catalog(catalog: impl Catalog + 'static) {
+    *CATALOG.write().expect("catalog lock poisoned") = Some(Arc::new(catalog));
+}
+
+/// Go back to using the builtin English messages
+pub fn clear_catalog() {
+    *CATALOG.write().expect("catalog lock poisoned") = None;
+}
+
+/// Render the message `id` replacing each `{name}` placeholder with its value from `args`
+pub fn message(id: MessageId, args: &[(&str, &str)]) -> String {
+    let catalog = CATALOG.read().expect("catalog lock poisoned").clone();
+    let template = catalog
+        .and_then(|catalog| catalog.template(id))
+        .unwrap_or_else(|| String::from(id.default_template()));
+    fill(&template, args)
+}
+
+fn fill(template: &str, args: &[(&str, &str)]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after.find('}').and_then(|end| {
+            let name = &after[..end];
+            args.iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, value)| (*value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                result.push_str(value);
+                rest = &after[end + 1..];
+            }
+            // Leave unknown placeholders as they are
+            None => {
+                result.push('{');
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill() {
+        assert_eq!(
+            fill("unknown key `{key}`", &[("key", "prot")]),
+            "unknown key `prot`"
+        );
+        assert_eq!(
+            fill("{a} and {b} and {c}", &[("a", "1"), ("b", "2")]),
+            "1 and 2 and {c}"
+        );
+        assert_eq!(fill("no placeholders {", &[]), "no placeholders {");
+    }
+}
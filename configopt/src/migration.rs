@@ -0,0 +1,163 @@
+use crate::{config_format, invalid_config_file, Error, Result};
+use std::{fs, path::Path};
+use toml::value::{Table, Value};
+
+/// Upgrade a config document from one schema version to the next
+///
+/// Select the key holding the version of config files with
+/// `#[configopt(version_field("<key>"))]` and register migrations with
+/// `#[configopt(migrations(<path>))]` where the path is a function returning every migration. A
+/// migration from version `n` upgrades a document to version `n + 1` so the latest version is one
+/// more than the highest `from`. A file without the version key is at version 0.
+#[derive(Clone, Copy)]
+pub struct Migration {
+    pub from: u64,
+    pub migrate: fn(&mut Table) -> std::result::Result<(), String>,
+}
+
+impl Migration {
+    pub const fn new(
+        from: u64,
+        migrate: fn(&mut Table) -> std::result::Result<(), String>,
+    ) -> Self {
+        Self { from, migrate }
+    }
+}
+
+/// The version of the schema the migrations upgrade to
+pub fn current_version(migrations: &[Migration]) -> u64 {
+    migrations.iter().map(|m| m.from + 1).max().unwrap_or(0)
+}
+
+/// Remove the version key from `table` and apply the migrations upgrading it to the current version
+///
+/// Evaluates to the version the document was at.
+pub(crate) fn migrate(
+    table: &mut Table,
+    version_field: &str,
+    migrations: &[Migration],
+) -> std::result::Result<u64, String> {
+    let from = match table.remove(version_field) {
+        None => 0,
+        Some(Value::Integer(version)) if version >= 0 => version as u64,
+        Some(_) => {
+            return Err(format!(
+                "`{}` must be a non-negative integer",
+                version_field
+            ))
+        }
+    };
+    let current = current_version(migrations);
+    if from > current {
+        return Err(format!(
+            "`{}` is {} but the latest version this binary supports is {}",
+            version_field, from, current
+        ));
+    }
+    for version in from..current {
+        let migration = migrations
+            .iter()
+            .find(|m| m.from == version)
+            .ok_or_else(|| format!("there is no migration from version {}", version))?;
+        (migration.migrate)(table)
+            .map_err(|e| format!("migrating from version {} failed: {}", version, e))?;
+    }
+    Ok(from)
+}
+
+#[doc(hidden)]
+pub fn migrate_table(
+    table: &mut Table,
+    version_field: Option<&str>,
+    migrations: &[Migration],
+    path: &Path,
+) -> Result<()> {
+    if let Some(version_field) = version_field {
+        migrate(table, version_field, migrations).map_err(|e| invalid_config_file(path, e))?;
+    }
+    Ok(())
+}
+
+/// Upgrade the TOML config file at `path` to the current version rewriting it in place, returning
+/// if it was rewritten
+///
+/// The file is rewritten from its parsed values so comments and formatting are not kept.
+pub fn migrate_file(
+    path: impl AsRef<Path>,
+    version_field: &str,
+    migrations: &[Migration],
+) -> Result<bool> {
+    let path = path.as_ref();
+    let contents =
+        fs::read_to_string(path).map_err(|e| Error::ConfigFile(path.to_path_buf(), e))?;
+    let mut table = toml::from_str::<Table>(&contents).map_err(|e| Error::from_toml(path, e))?;
+    let current = current_version(migrations);
+    let stamped = table.contains_key(version_field);
+    let from =
+        migrate(&mut table, version_field, migrations).map_err(|e| invalid_config_file(path, e))?;
+    if from == current && stamped {
+        return Ok(false);
+    }
+    let mut contents = format!("{} = {}\n\n", version_field, current);
+    // Serialize a `Value` so values are written before tables
+    contents.push_str(
+        &toml::to_string(&Value::Table(table))
+            .map_err(|e| invalid_config_file(path, e.to_string()))?,
+    );
+    config_format::write(path, &contents)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rename_port(table: &mut Table) -> std::result::Result<(), String> {
+        if let Some(port) = table.remove("listen_port") {
+            table.insert(String::from("port"), port);
+        }
+        Ok(())
+    }
+
+    fn require_host(table: &mut Table) -> std::result::Result<(), String> {
+        match table.get("host") {
+            Some(_) => Ok(()),
+            None => Err(String::from("`host` is required")),
+        }
+    }
+
+    const MIGRATIONS: &[Migration] = &[
+        Migration::new(0, rename_port),
+        Migration::new(1, require_host),
+    ];
+
+    #[test]
+    fn test_migrate() {
+        assert_eq!(current_version(MIGRATIONS), 2);
+        assert_eq!(current_version(&[]), 0);
+
+        let mut table = toml::from_str::<Table>("listen_port = 1\nhost = 'a'").unwrap();
+        assert_eq!(migrate(&mut table, "version", MIGRATIONS), Ok(0));
+        assert_eq!(
+            table,
+            toml::from_str::<Table>("port = 1\nhost = 'a'").unwrap()
+        );
+
+        // Only the migrations after the version of the document are applied
+        let mut table = toml::from_str::<Table>("version = 1\nlisten_port = 1").unwrap();
+        assert_eq!(
+            migrate(&mut table, "version", MIGRATIONS),
+            Err(String::from(
+                "migrating from version 1 failed: `host` is required"
+            ))
+        );
+        let mut table = toml::from_str::<Table>("version = 2\nlisten_port = 1").unwrap();
+        assert_eq!(migrate(&mut table, "version", MIGRATIONS), Ok(2));
+        assert!(table.contains_key("listen_port"));
+
+        let mut table = toml::from_str::<Table>("version = 3").unwrap();
+        assert!(migrate(&mut table, "version", MIGRATIONS).is_err());
+        let mut table = toml::from_str::<Table>("version = 'latest'").unwrap();
+        assert!(migrate(&mut table, "version", MIGRATIONS).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use crate::prefix::leak;
+use std::mem;
+use structopt::clap::{App, Arg, ArgMatches};
+
+// The name of the argument which turns off the flag with the argument name `name`. It cannot
+// collide with a user argument because `structopt` never generates names with this prefix.
+fn negation_name(name: &str) -> &'static str {
+    leak(format!("configopt-no-{}", name))
+}
+
+/// Add a `--no-<long>` flag to `app` and its subcommands for each of the `negatable` bool flags
+///
+/// The negation and the flag override each other so the last occurrence on the command line wins.
+/// Adding real arguments lets `clap` list the negations in the help and completions and never
+/// mistake the value of an option for one. The long flag of a flattened type must already be
+/// prefixed so this is called after `prefix::apply`.
+pub(crate) fn add_negations(app: &mut App<'_, '_>, negatable: &[&'static str]) {
+    if negatable.is_empty() {
+        return;
+    }
+    let negations = app
+        .p
+        .flags
+        .iter()
+        .filter(|flag| negatable.iter().any(|name| *name == flag.b.name))
+        .filter_map(|flag| Some((flag.b.name, flag.s.long?)))
+        .map(|(name, long)| {
+            Arg::with_name(negation_name(name))
+                .long(leak(format!("no-{}", long)))
+                .help(leak(format!("Turn off --{}", long)))
+                .overrides_with(name)
+        })
+        .collect::<Vec<_>>();
+    if !negations.is_empty() {
+        let owned = mem::replace(app, App::new(""));
+        *app = owned.args(&negations);
+    }
+    for subcommand in &mut app.p.subcommands {
+        add_negations(subcommand, negatable);
+    }
+}
+
+/// The `negatable` flags which are turned off in `matches` or the matches of its subcommands
+pub(crate) fn negated(matches: &ArgMatches<'_>, negatable: &[&'static str]) -> Vec<&'static str> {
+    let mut names = negatable
+        .iter()
+        .copied()
+        .filter(|name| matches.is_present(negation_name(name)))
+        .collect::<Vec<_>>();
+    if let (_, Some(matches)) = matches.subcommand() {
+        for name in negated(matches, negatable) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn negated_by(args: &[&str]) -> Vec<&'static str> {
+        let negatable = ["verbose", "color"];
+        let mut app = App::new("app")
+            .arg(Arg::with_name("verbose").long("verbose"))
+            .arg(Arg::with_name("color").long("colour"))
+            .arg(Arg::with_name("name").long("name").takes_value(true))
+            .arg(Arg::with_name("trailing").multiple(true).last(true));
+        add_negations(&mut app, &negatable);
+        negated(&app.get_matches_from_safe(args).unwrap(), &negatable)
+    }
+
+    #[test]
+    fn test_negated() {
+        assert_eq!(
+            negated_by(&["app", "--no-verbose", "--name", "x"]),
+            vec!["verbose"]
+        );
+
+        // The last occurrence wins
+        assert_eq!(
+            negated_by(&["app", "--no-verbose", "--verbose", "--no-colour"]),
+            vec!["color"]
+        );
+        assert_eq!(
+            negated_by(&["app", "--verbose", "--no-verbose"]),
+            vec!["verbose"]
+        );
+
+        // The negation uses the long flag and is never the value of an option or trailing
+        assert_eq!(
+            negated_by(&["app", "--name=--no-verbose", "--", "--no-colour"]),
+            Vec::<&str>::new()
+        );
+    }
+
+    #[test]
+    fn test_add_negations_help() {
+        let mut app = App::new("app").arg(Arg::with_name("verbose").long("verbose"));
+        add_negations(&mut app, &["verbose"]);
+        let mut help = Vec::new();
+        app.write_help(&mut help).unwrap();
+        assert!(String::from_utf8(help).unwrap().contains("--no-verbose"));
+    }
+}
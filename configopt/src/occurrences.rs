@@ -0,0 +1,55 @@
+/// The type of a `parse(from_occurrences)` field on the `configopt` type
+///
+/// This is an alias so `structopt` treats the field as a plain value instead of an `Option`, which
+/// it does not support for occurrences.
+pub type Occurrences<T> = Option<T>;
+
+/// An integer which can hold the number of occurrences of a flag
+pub trait FromOccurrences {
+    /// Convert `count` saturating at the largest value of the type
+    fn from_occurrences(count: u64) -> Self;
+}
+
+macro_rules! impl_from_occurrences {
+    ($($ty:ty),*) => {
+        $(
+            impl FromOccurrences for $ty {
+                fn from_occurrences(count: u64) -> Self {
+                    if count > <$ty>::MAX as u64 {
+                        <$ty>::MAX
+                    } else {
+                        count as $ty
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_occurrences!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// The `from_occurrences` parser used for `Occurrences` fields
+///
+/// Zero occurrences means the flag was not set on the CLI so it is `None`. A count which does not
+/// fit in `T` saturates instead of being dropped.
+pub fn occurrences<T: FromOccurrences>(count: u64) -> Occurrences<T> {
+    if count == 0 {
+        None
+    } else {
+        Some(T::from_occurrences(count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_occurrences() {
+        assert_eq!(occurrences::<u8>(0), None);
+        assert_eq!(occurrences::<u8>(3), Some(3));
+        assert_eq!(occurrences::<u8>(300), Some(u8::MAX));
+        assert_eq!(occurrences::<i8>(200), Some(i8::MAX));
+        assert_eq!(occurrences::<u64>(u64::MAX), Some(u64::MAX));
+    }
+}
@@ -0,0 +1,215 @@
+//! `serde` support for fields holding OS strings such as `PathBuf` and `OsString`
+//!
+//! `serde` refuses to serialize a `PathBuf` which is not valid UTF-8 and represents an `OsString`
+//! as a platform specific enum which no one writes in a config file. The `configopt` type uses
+//! this module for `PathBuf` and `OsString` fields (and `Option` and `Vec` of them) so both are
+//! written as plain strings when they are valid UTF-8. Anything else is written as an array of
+//! its bytes on Unix where it is read back losslessly. Elsewhere invalid UTF-8 is replaced with
+//! `U+FFFD REPLACEMENT CHARACTER` so the value still reads but may differ from the original.
+//!
+//! Use it on other types with `#[serde(with = "configopt::os_str")]`.
+
+use serde::{
+    de::{self, Deserialize, Deserializer, SeqAccess},
+    ser::{Serialize, SerializeSeq, Serializer},
+};
+use std::{
+    ffi::{OsStr, OsString},
+    fmt,
+    path::PathBuf,
+};
+
+/// A value which is serialized with this module
+pub trait OsStrValue: Sized {
+    fn serialize_os<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+
+    fn deserialize_os<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+}
+
+/// Serialize `value` with this module outside of a `serde(with)` attribute
+pub struct Serialized<'a, T>(pub &'a T);
+
+impl<'a, T: OsStrValue> Serialize for Serialized<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize_os(serializer)
+    }
+}
+
+struct Deserialized<T>(T);
+
+impl<'de, T: OsStrValue> Deserialize<'de> for Deserialized<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize_os(deserializer).map(Deserialized)
+    }
+}
+
+pub fn serialize<T: OsStrValue, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    value.serialize_os(serializer)
+}
+
+pub fn deserialize<'de, T: OsStrValue, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    T::deserialize_os(deserializer)
+}
+
+#[cfg(unix)]
+fn serialize_non_utf8<S: Serializer>(s: &OsStr, serializer: S) -> Result<S::Ok, S::Error> {
+    use std::os::unix::ffi::OsStrExt;
+
+    // Not every format has bytes so write an array of integers
+    let bytes = s.as_bytes();
+    let mut seq = serializer.serialize_seq(Some(bytes.len()))?;
+    for byte in bytes {
+        seq.serialize_element(byte)?;
+    }
+    seq.end()
+}
+
+#[cfg(not(unix))]
+fn serialize_non_utf8<S: Serializer>(s: &OsStr, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&s.to_string_lossy())
+}
+
+#[cfg(unix)]
+fn from_bytes(bytes: Vec<u8>) -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+
+    OsString::from_vec(bytes)
+}
+
+#[cfg(not(unix))]
+fn from_bytes(bytes: Vec<u8>) -> OsString {
+    OsString::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+struct Visitor;
+
+impl<'de> de::Visitor<'de> for Visitor {
+    type Value = OsString;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a string or an array of bytes")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+        Ok(OsString::from(s))
+    }
+
+    fn visit_string<E: de::Error>(self, s: String) -> Result<Self::Value, E> {
+        Ok(OsString::from(s))
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        Ok(from_bytes(bytes.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(from_bytes(bytes))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        Ok(from_bytes(bytes))
+    }
+}
+
+impl OsStrValue for OsString {
+    fn serialize_os<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.to_str() {
+            Some(s) => serializer.serialize_str(s),
+            None => serialize_non_utf8(self, serializer),
+        }
+    }
+
+    fn deserialize_os<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+impl OsStrValue for PathBuf {
+    fn serialize_os<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.to_str() {
+            Some(s) => serializer.serialize_str(s),
+            None => serialize_non_utf8(self.as_os_str(), serializer),
+        }
+    }
+
+    fn deserialize_os<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        OsString::deserialize_os(deserializer).map(PathBuf::from)
+    }
+}
+
+impl<T: OsStrValue> OsStrValue for Option<T> {
+    fn serialize_os<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Some(value) => serializer.serialize_some(&Serialized(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    fn deserialize_os<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Option::<Deserialized<T>>::deserialize(deserializer)?.map(|d| d.0))
+    }
+}
+
+impl<T: OsStrValue> OsStrValue for Vec<T> {
+    fn serialize_os<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter().map(Serialized))
+    }
+
+    fn deserialize_os<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<Deserialized<T>>::deserialize(deserializer)?
+            .into_iter()
+            .map(|d| d.0)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Paths {
+        #[serde(default, with = "super")]
+        path: Option<PathBuf>,
+        #[serde(default, with = "super")]
+        names: Vec<OsString>,
+    }
+
+    #[test]
+    fn test_utf8() {
+        let paths = toml::from_str::<Paths>("path = '/a/b'\nnames = ['c']").unwrap();
+        assert_eq!(paths.path, Some(PathBuf::from("/a/b")));
+        assert_eq!(paths.names, vec![OsString::from("c")]);
+        let value = toml::Value::try_from(Serialized(&paths.path)).unwrap();
+        assert_eq!(value, toml::Value::String(String::from("/a/b")));
+        assert_eq!(
+            toml::from_str::<Paths>("").unwrap(),
+            Paths {
+                path: None,
+                names: Vec::new()
+            }
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let name = OsString::from_vec(vec![b'a', 0xff]);
+        let value = toml::Value::try_from(Serialized(&vec![name.clone()])).unwrap();
+        assert_eq!(value.to_string(), "[[97, 255]]");
+        let paths = toml::from_str::<Paths>("names = [[97, 255]]").unwrap();
+        assert_eq!(paths.names, vec![name]);
+    }
+}
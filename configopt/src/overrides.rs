@@ -0,0 +1,93 @@
+use crate::profile;
+use lazy_static::lazy_static;
+use std::sync::RwLock;
+use toml::value::{Table, Value};
+
+lazy_static! {
+    static ref KEYS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+}
+
+/// The table holding the overrides of a config file keyed by identity values
+pub const OVERRIDES_KEY: &str = "overrides";
+
+/// Set the identity values (eg the region then the datacenter) which select the
+/// `[overrides.<key>]` tables of config files
+///
+/// The tables of later keys take precedence. A key without a table in a config file is ignored.
+pub fn set_override_keys<I, K>(keys: I)
+where
+    I: IntoIterator<Item = K>,
+    K: Into<String>,
+{
+    *KEYS.write().expect("override keys lock poisoned") =
+        keys.into_iter().map(Into::into).collect();
+}
+
+pub fn clear_override_keys() {
+    KEYS.write().expect("override keys lock poisoned").clear();
+}
+
+/// Remove the overrides from `table` and merge the tables of the override keys over the base values
+pub(crate) fn apply_overrides(table: Table) -> Result<Table, String> {
+    let keys = KEYS.read().expect("override keys lock poisoned").clone();
+    apply(table, &keys)
+}
+
+fn apply(mut table: Table, keys: &[String]) -> Result<Table, String> {
+    let mut overrides = match table.remove(OVERRIDES_KEY) {
+        Some(Value::Table(overrides)) => overrides,
+        Some(_) => return Err(format!("`{}` must be a table", OVERRIDES_KEY)),
+        None => return Ok(table),
+    };
+    for key in keys {
+        match overrides.remove(key) {
+            Some(Value::Table(values)) => profile::merge(&mut table, values),
+            Some(_) => return Err(format!("override `{}` must be a table", key)),
+            None => {}
+        }
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(s: &str) -> Table {
+        match s.parse::<Value>().unwrap() {
+            Value::Table(table) => table,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_apply_overrides() {
+        let base = table(
+            r#"
+            max_conns = 100
+            [db]
+            host = "db"
+            [overrides.us-east-1]
+            max_conns = 500
+            [overrides.us-east-1.db]
+            replicas = 3
+            [overrides.use1-az2]
+            max_conns = 50
+            "#,
+        );
+        let keys = |keys: &[&str]| keys.iter().map(|k| String::from(*k)).collect::<Vec<_>>();
+        assert_eq!(
+            apply(base.clone(), &keys(&["us-east-1"])).unwrap(),
+            table("max_conns = 500\n[db]\nhost = \"db\"\nreplicas = 3")
+        );
+        assert_eq!(
+            apply(base.clone(), &keys(&["us-east-1", "use1-az2"])).unwrap(),
+            table("max_conns = 50\n[db]\nhost = \"db\"\nreplicas = 3")
+        );
+        assert_eq!(
+            apply(base, &keys(&["eu-west-1"])).unwrap(),
+            table("max_conns = 100\n[db]\nhost = \"db\"")
+        );
+        assert!(apply(table("overrides = 1"), &[]).is_err());
+    }
+}
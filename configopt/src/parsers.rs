@@ -0,0 +1,296 @@
+//! Parsers for human friendly formats
+//!
+//! Each parser can be used on the CLI with `#[structopt(parse(try_from_str = ...))]` and in config
+//! files with the matching [`serde_with`](serde_with/index.html) module. When the `structopt`
+//! parser is one of these the `configopt` type uses the matching `serde` module automatically so
+//! both layers accept the same syntax.
+use std::{
+    ffi::OsString,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+const DURATION_UNITS: &[(&str, u64)] = &[
+    ("ns", 1),
+    ("us", 1_000),
+    ("ms", 1_000_000),
+    ("s", 1_000_000_000),
+    ("m", 60 * 1_000_000_000),
+    ("h", 60 * 60 * 1_000_000_000),
+    ("d", 24 * 60 * 60 * 1_000_000_000),
+];
+
+const BYTE_UNITS: &[(&str, u64)] = &[
+    ("B", 1),
+    ("KB", 1_000),
+    ("MB", 1_000_000),
+    ("GB", 1_000_000_000),
+    ("TB", 1_000_000_000_000),
+    ("KiB", 1 << 10),
+    ("MiB", 1 << 20),
+    ("GiB", 1 << 30),
+    ("TiB", 1 << 40),
+];
+
+/// Split `s` into a number and the unit which follows it
+fn split_number(s: &str) -> Result<(f64, &str), String> {
+    let end = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or_else(|| s.len());
+    let number = s[..end]
+        .parse::<f64>()
+        .map_err(|_| format!("expected a number in `{}`", s))?;
+    Ok((number, s[end..].trim_start()))
+}
+
+/// Parse a duration such as `30s`, `5m`, `1h30m`, or `250ms`
+///
+/// A number without a unit is in seconds.
+pub fn duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    if let Ok(secs) = s.parse::<f64>() {
+        return Ok(Duration::from_secs_f64(secs));
+    }
+    let mut nanos = 0u64;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let (number, after) = split_number(rest)?;
+        let unit_len = after
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or_else(|| after.len());
+        let unit = &after[..unit_len];
+        let scale = DURATION_UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .map(|(_, scale)| *scale)
+            .ok_or_else(|| format!("unknown duration unit `{}` in `{}`", unit, s))?;
+        nanos += (number * scale as f64).round() as u64;
+        rest = after[unit_len..].trim_start();
+    }
+    Ok(Duration::from_nanos(nanos))
+}
+
+/// Parse a size in bytes such as `512`, `10KB`, or `10MiB`
+pub fn byte_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (number, unit) = split_number(s)?;
+    let scale = if unit.is_empty() {
+        1
+    } else {
+        BYTE_UNITS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(unit))
+            .map(|(_, scale)| *scale)
+            .ok_or_else(|| format!("unknown byte size unit `{}` in `{}`", unit, s))?
+    };
+    Ok((number * scale as f64).round() as u64)
+}
+
+/// Parse a socket address such as `0.0.0.0:8080` or `:8080` which listens on all interfaces
+pub fn socket_addr(s: &str) -> Result<SocketAddr, String> {
+    let s = s.trim();
+    if let Some(port) = s.strip_prefix(':') {
+        let port = port
+            .parse()
+            .map_err(|_| format!("invalid port in `{}`", s))?;
+        return Ok(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port));
+    }
+    s.parse()
+        .map_err(|_| format!("invalid socket address `{}`", s))
+}
+
+/// Format a duration so it can be parsed with [`duration`](fn.duration.html)
+pub fn format_duration(duration: &Duration) -> String {
+    let mut nanos = duration.as_nanos() as u64;
+    if nanos == 0 {
+        return String::from("0s");
+    }
+    let mut result = String::new();
+    for (unit, scale) in DURATION_UNITS.iter().rev() {
+        if nanos >= *scale {
+            result.push_str(&format!("{}{}", nanos / scale, unit));
+            nanos %= scale;
+        }
+    }
+    result
+}
+
+/// Format a size so it can be parsed with [`byte_size`](fn.byte_size.html)
+pub fn format_byte_size(bytes: &u64) -> String {
+    let unit = BYTE_UNITS
+        .iter()
+        .rev()
+        .find(|(_, scale)| *bytes != 0 && *scale != 1 && bytes % scale == 0);
+    match unit {
+        Some((unit, scale)) => format!("{}{}", bytes / scale, unit),
+        None => format!("{}B", bytes),
+    }
+}
+
+/// Format a socket address so it can be parsed with [`socket_addr`](fn.socket_addr.html)
+pub fn format_socket_addr(addr: &SocketAddr) -> String {
+    addr.to_string()
+}
+
+#[doc(hidden)]
+pub fn duration_to_os_string(duration: &Duration) -> OsString {
+    format_duration(duration).into()
+}
+
+#[doc(hidden)]
+pub fn byte_size_to_os_string(bytes: &u64) -> OsString {
+    format_byte_size(bytes).into()
+}
+
+#[doc(hidden)]
+pub fn socket_addr_to_os_string(addr: &SocketAddr) -> OsString {
+    format_socket_addr(addr).into()
+}
+
+/// `serde` modules for use with `#[serde(with = "...")]`
+///
+/// Each module has an `option` module for `Option` fields. Numbers are accepted as seconds for
+/// durations and bytes for sizes.
+pub mod serde_with {
+    macro_rules! serde_with_module {
+        ($name:ident, $ty:ty, $parse:path, $format:path, $number:expr) => {
+            pub mod $name {
+                use serde::{de, Deserializer, Serializer};
+                use std::fmt;
+
+                struct Visitor;
+
+                impl<'de> de::Visitor<'de> for Visitor {
+                    type Value = $ty;
+
+                    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        write!(f, "a {}", stringify!($name))
+                    }
+
+                    fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+                        $parse(s).map_err(E::custom)
+                    }
+
+                    fn visit_u64<E: de::Error>(self, n: u64) -> Result<Self::Value, E> {
+                        let number: Option<fn(f64) -> $ty> = $number;
+                        match number {
+                            Some(number) => Ok(number(n as f64)),
+                            None => Err(E::invalid_type(de::Unexpected::Unsigned(n), &self)),
+                        }
+                    }
+
+                    fn visit_i64<E: de::Error>(self, n: i64) -> Result<Self::Value, E> {
+                        if n < 0 {
+                            return Err(E::invalid_value(de::Unexpected::Signed(n), &self));
+                        }
+                        self.visit_u64(n as u64)
+                    }
+
+                    fn visit_f64<E: de::Error>(self, n: f64) -> Result<Self::Value, E> {
+                        let number: Option<fn(f64) -> $ty> = $number;
+                        match number {
+                            Some(number) if n >= 0.0 => Ok(number(n)),
+                            _ => Err(E::invalid_type(de::Unexpected::Float(n), &self)),
+                        }
+                    }
+                }
+
+                pub fn serialize<S: Serializer>(
+                    value: &$ty,
+                    serializer: S,
+                ) -> Result<S::Ok, S::Error> {
+                    serializer.serialize_str(&$format(value))
+                }
+
+                pub fn deserialize<'de, D: Deserializer<'de>>(
+                    deserializer: D,
+                ) -> Result<$ty, D::Error> {
+                    deserializer.deserialize_any(Visitor)
+                }
+
+                pub mod option {
+                    use serde::{Deserialize, Deserializer, Serializer};
+
+                    pub fn serialize<S: Serializer>(
+                        value: &Option<$ty>,
+                        serializer: S,
+                    ) -> Result<S::Ok, S::Error> {
+                        match value {
+                            Some(value) => super::serialize(value, serializer),
+                            None => serializer.serialize_none(),
+                        }
+                    }
+
+                    pub fn deserialize<'de, D: Deserializer<'de>>(
+                        deserializer: D,
+                    ) -> Result<Option<$ty>, D::Error> {
+                        #[derive(Deserialize)]
+                        struct Wrapper(#[serde(with = "super")] $ty);
+
+                        Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|w| w.0))
+                    }
+                }
+            }
+        };
+    }
+
+    serde_with_module!(
+        duration,
+        std::time::Duration,
+        crate::parsers::duration,
+        crate::parsers::format_duration,
+        Some(std::time::Duration::from_secs_f64)
+    );
+    serde_with_module!(
+        byte_size,
+        u64,
+        crate::parsers::byte_size,
+        crate::parsers::format_byte_size,
+        Some(|n| n as u64)
+    );
+    serde_with_module!(
+        socket_addr,
+        std::net::SocketAddr,
+        crate::parsers::socket_addr,
+        crate::parsers::format_socket_addr,
+        None
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration() {
+        assert_eq!(duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(duration("1.5").unwrap(), Duration::from_millis(1500));
+        assert!(duration("5 parsecs").is_err());
+        assert_eq!(format_duration(&Duration::from_secs(5400)), "1h30m");
+        assert_eq!(format_duration(&Duration::from_millis(1500)), "1s500ms");
+    }
+
+    #[test]
+    fn test_byte_size() {
+        assert_eq!(byte_size("512").unwrap(), 512);
+        assert_eq!(byte_size("10KB").unwrap(), 10_000);
+        assert_eq!(byte_size("10MiB").unwrap(), 10 * 1024 * 1024);
+        assert_eq!(byte_size("1.5 gib").unwrap(), 3 << 29);
+        assert!(byte_size("10XB").is_err());
+        assert_eq!(format_byte_size(&(10 * 1024 * 1024)), "10MiB");
+        assert_eq!(format_byte_size(&10_000), "10KB");
+        assert_eq!(format_byte_size(&513), "513B");
+    }
+
+    #[test]
+    fn test_socket_addr() {
+        assert_eq!(
+            socket_addr("0.0.0.0:8080").unwrap(),
+            socket_addr(":8080").unwrap()
+        );
+        assert!(socket_addr("localhost").is_err());
+    }
+}
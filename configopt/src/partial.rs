@@ -0,0 +1,66 @@
+use crate::{Error, LoadTrace};
+use std::io::{self, Write};
+
+/// Merge one layer of a partial config into another
+///
+/// This is implemented by `#[derive(ConfigOpt)]` and `#[derive(ConfigOptSection)]` for their
+/// `configopt` types.
+pub trait Merge {
+    /// Take each field which is set in `other` and set it in `self`
+    fn take(&mut self, other: &mut Self);
+
+    /// For each field in `self` which is not set, take the value from `other` and set it in `self`
+    fn patch(&mut self, other: &mut Self);
+}
+
+/// A partial config where each field may be unset
+///
+/// This is implemented by `#[derive(ConfigOpt)]` and `#[derive(ConfigOptSection)]` for their
+/// `configopt` types so generic code can work with any of them.
+pub trait Partial: Merge {
+    /// The full type the partial config converts into
+    type Full;
+
+    /// Take each field from `self` and set it in `full`
+    fn take_for(&mut self, full: &mut Self::Full);
+
+    /// For each field in `full` if it is `None`, take the value from `self` and set it in `full`
+    fn patch_for(&mut self, full: &mut Self::Full);
+
+    /// Check if all fields are unset
+    fn is_empty(&self) -> bool;
+
+    /// Check if all fields are set applied recursively
+    fn is_complete(&self) -> bool;
+
+    /// Check if `self` can be converted into `Self::Full`
+    fn is_convertible(&self) -> bool;
+
+    /// The dotted paths of the required fields which prevent `self` from being converted
+    fn missing_fields(&self) -> Vec<String>;
+
+    /// The dotted paths of the fields which are set
+    fn set_fields(&self) -> Vec<String>;
+}
+
+/// Merge `layers` ordered from the lowest to the highest precedence
+pub fn merge_layers<T, I>(layers: I) -> T
+where
+    T: Merge + Default,
+    I: IntoIterator<Item = T>,
+{
+    let mut result = T::default();
+    for mut layer in layers {
+        result.take(&mut layer);
+    }
+    result
+}
+
+/// Warn that the instance at `path` of a `#[configopt(map)]` field was not added to the full config
+/// because it could not be converted
+#[doc(hidden)]
+pub fn warn_skipped_instance(path: &str, error: &Error) {
+    let warning = format!("warning: `{}` was not applied, err: {}", path, error);
+    LoadTrace::warn(|| warning.clone());
+    writeln!(&mut io::stderr(), "{}", warning).ok();
+}
@@ -0,0 +1,146 @@
+use lazy_static::lazy_static;
+use std::{
+    collections::HashSet,
+    env,
+    ffi::{OsStr, OsString},
+    sync::Mutex,
+};
+use structopt::clap::App;
+
+lazy_static! {
+    static ref NAMES: Mutex<HashSet<&'static str>> = Mutex::new(HashSet::new());
+}
+
+// `clap` borrows the flags and environment variables of arguments so keep a single copy of each
+// prefixed name for the life of the program
+pub(crate) fn leak(name: String) -> &'static str {
+    // A panic while inserting cannot leave the set partially written so ignore poisoning
+    let mut names = NAMES.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(name) = names.get(name.as_str()) {
+        return name;
+    }
+    let name = Box::leak(name.into_boxed_str());
+    names.insert(name);
+    name
+}
+
+/// Collect the prefix of each argument of a flattened type
+///
+/// `app` is the app of the flattened type and `nested` the prefixes of its own flattened types.
+/// The prefixes of nested types are joined with `-` (eg `db-primary`).
+#[doc(hidden)]
+pub fn prefix_args(
+    prefixes: &mut Vec<(String, String)>,
+    app: &App<'_, '_>,
+    nested: Vec<(String, String)>,
+    prefix: Option<&str>,
+) {
+    let prefix = match prefix {
+        Some(prefix) => prefix,
+        None => {
+            prefixes.extend(nested);
+            return;
+        }
+    };
+    let names = app
+        .p
+        .opts
+        .iter()
+        .map(|arg| arg.b.name)
+        .chain(app.p.flags.iter().map(|arg| arg.b.name))
+        .chain(app.p.positionals.values().map(|arg| arg.b.name));
+    for name in names {
+        let full = match nested.iter().find(|(n, _)| n == name) {
+            Some((_, inner)) => format!("{}-{}", prefix, inner),
+            None => String::from(prefix),
+        };
+        prefixes.push((String::from(name), full));
+    }
+}
+
+/// Prefix the long flag (eg `--db-host`) and environment variable (eg `DB_HOST`) of each argument
+/// of a flattened type with `#[configopt(prefix = "..")]`
+///
+/// The names of the arguments are left as they are because the flattened type finds its values by
+/// them after parsing. Short flags are not prefixed and only an argument which has an environment
+/// variable gets a prefixed one.
+pub(crate) fn apply(app: &mut App<'_, '_>, prefixes: &[(String, String)]) {
+    let prefix_of = |name: &str| {
+        prefixes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, prefix)| prefix.as_str())
+    };
+    for arg in &mut app.p.opts {
+        if let Some(prefix) = prefix_of(arg.b.name) {
+            prefix_long(prefix, &mut arg.s.long);
+            prefix_env(prefix, &mut arg.v.env);
+        }
+    }
+    for arg in &mut app.p.flags {
+        if let Some(prefix) = prefix_of(arg.b.name) {
+            prefix_long(prefix, &mut arg.s.long);
+        }
+    }
+    for (_, arg) in &mut app.p.positionals {
+        if let Some(prefix) = prefix_of(arg.b.name) {
+            prefix_env(prefix, &mut arg.v.env);
+        }
+    }
+}
+
+/// Read each argument from the environment variable of the argument with the same name in `from`
+///
+/// Only the `configopt` type knows the names set with `#[configopt(rename_all_env(".."))]` so the
+/// app of the actual type takes its variables from it.
+pub(crate) fn copy_env(app: &mut App<'_, '_>, from: &App<'_, '_>) {
+    let env_of = |name: &str| {
+        from.p
+            .opts
+            .iter()
+            .find(|arg| arg.b.name == name)
+            .map(|arg| &arg.v.env)
+            .or_else(|| {
+                from.p
+                    .positionals
+                    .values()
+                    .find(|arg| arg.b.name == name)
+                    .map(|arg| &arg.v.env)
+            })
+            .and_then(|env| env.as_ref())
+            .map(|(env, _)| leak(env.to_string_lossy().into_owned()))
+    };
+    for arg in &mut app.p.opts {
+        if let Some(name) = env_of(arg.b.name) {
+            rename_env(name, &mut arg.v.env);
+        }
+    }
+    for (_, arg) in &mut app.p.positionals {
+        if let Some(name) = env_of(arg.b.name) {
+            rename_env(name, &mut arg.v.env);
+        }
+    }
+}
+
+fn prefix_long(prefix: &str, long: &mut Option<&str>) {
+    if let Some(name) = long {
+        *long = Some(leak(format!("{}-{}", prefix, name)));
+    }
+}
+
+fn prefix_env(prefix: &str, env: &mut Option<(&OsStr, Option<OsString>)>) {
+    if let Some((name, _)) = env {
+        let prefix = prefix.to_uppercase().replace('-', "_");
+        let name = leak(format!("{}_{}", prefix, name.to_string_lossy()));
+        rename_env(name, env);
+    }
+}
+
+// The value was read when the argument was defined so read the value of the new name
+fn rename_env(name: &'static str, env: &mut Option<(&OsStr, Option<OsString>)>) {
+    if let Some((old, _)) = env {
+        if *old != name {
+            *env = Some((OsStr::new(name), env::var_os(name)));
+        }
+    }
+}
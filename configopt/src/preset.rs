@@ -0,0 +1,197 @@
+use crate::profile;
+use toml::value::{Table, Value};
+
+/// The key selecting the presets a config file expands
+pub const PRESET_KEY: &str = "preset";
+
+/// The table holding the presets defined in a config file
+pub const PRESETS_KEY: &str = "presets";
+
+/// A named bundle of config values
+///
+/// Select presets in a config file with `preset = "<name>"` or `preset = ["<name>", ..]`. The
+/// values of the selected presets are merged in order under the values of the config file itself
+/// so the file can still override any of them. Presets are defined in code with
+/// `#[configopt(presets(<path>))]` or in a config file as `[presets.<name>]` tables which take
+/// precedence over presets defined in code with the same name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Preset {
+    pub name: &'static str,
+    /// A short explanation of when to use the preset
+    pub description: &'static str,
+    /// The values of the preset as a TOML document
+    pub config: &'static str,
+}
+
+impl Preset {
+    pub const fn new(name: &'static str, description: &'static str, config: &'static str) -> Self {
+        Self {
+            name,
+            description,
+            config,
+        }
+    }
+
+    /// Parse the values of the preset
+    pub fn values(&self) -> Result<Table, String> {
+        toml::from_str(self.config)
+            .map_err(|e| format!("preset `{}` is not a valid TOML table: {}", self.name, e))
+    }
+
+    /// The values the preset sets as `<dotted path> = <value>` lines sorted by path
+    pub fn effects(&self) -> Result<Vec<String>, String> {
+        let mut effects = Vec::new();
+        flatten(&self.values()?, "", &mut effects);
+        effects.sort();
+        Ok(effects)
+    }
+}
+
+fn flatten(table: &Table, prefix: &str, effects: &mut Vec<String>) {
+    for (key, value) in table {
+        let path = format!("{}{}", prefix, key);
+        match value {
+            Value::Table(table) => flatten(table, &format!("{}.", path), effects),
+            value => effects.push(format!("{} = {}", path, value)),
+        }
+    }
+}
+
+/// Describe each preset and the values it sets
+pub fn describe_presets(presets: &[Preset]) -> String {
+    let mut description = String::new();
+    for preset in presets {
+        description.push_str(&format!("{}: {}\n", preset.name, preset.description));
+        match preset.effects() {
+            Ok(effects) => {
+                for effect in effects {
+                    description.push_str(&format!("    {}\n", effect));
+                }
+            }
+            Err(e) => description.push_str(&format!("    {}\n", e)),
+        }
+    }
+    description
+}
+
+/// Remove the preset keys from `table` and merge the selected presets under its values
+pub(crate) fn expand_presets(mut table: Table, presets: &[Preset]) -> Result<Table, String> {
+    let defined = match table.remove(PRESETS_KEY) {
+        Some(Value::Table(defined)) => defined,
+        Some(_) => return Err(format!("`{}` must be a table", PRESETS_KEY)),
+        None => Table::new(),
+    };
+    let selected = match table.remove(PRESET_KEY) {
+        Some(Value::String(name)) => vec![name],
+        Some(Value::Array(names)) => names
+            .into_iter()
+            .map(|name| match name {
+                Value::String(name) => Ok(name),
+                _ => Err(format!("`{}` must only contain preset names", PRESET_KEY)),
+            })
+            .collect::<Result<_, _>>()?,
+        Some(_) => {
+            return Err(format!(
+                "`{}` must be a preset name or an array of names",
+                PRESET_KEY
+            ))
+        }
+        None => return Ok(table),
+    };
+    let mut merged = Table::new();
+    for name in selected {
+        let values = match defined.get(&name) {
+            Some(Value::Table(values)) => values.clone(),
+            Some(_) => return Err(format!("preset `{}` must be a table", name)),
+            None => match presets.iter().find(|p| p.name == name) {
+                Some(preset) => preset.values()?,
+                None => {
+                    let mut known = defined
+                        .keys()
+                        .map(String::as_str)
+                        .chain(presets.iter().map(|p| p.name))
+                        .map(|k| format!("`{}`", k))
+                        .collect::<Vec<_>>();
+                    known.sort();
+                    known.dedup();
+                    return Err(format!(
+                        "unknown preset `{}`, expected one of {}",
+                        name,
+                        known.join(", ")
+                    ));
+                }
+            },
+        };
+        profile::merge(&mut merged, values);
+    }
+    profile::merge(&mut merged, table);
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRESETS: &[Preset] = &[
+        Preset::new(
+            "low-memory",
+            "Use less memory",
+            "workers = 1\n[cache]\nsize = 16",
+        ),
+        Preset::new("fast", "Use more workers", "workers = 8"),
+    ];
+
+    fn table(s: &str) -> Table {
+        match s.parse::<Value>().unwrap() {
+            Value::Table(table) => table,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_expand_presets() {
+        let expanded = expand_presets(
+            table("preset = \"low-memory\"\nhost = \"a\"\n[cache]\nttl = 5"),
+            PRESETS,
+        )
+        .unwrap();
+        assert_eq!(
+            expanded,
+            table("workers = 1\nhost = \"a\"\n[cache]\nsize = 16\nttl = 5")
+        );
+
+        // Later presets, presets defined in the file, and the file itself take precedence
+        let expanded = expand_presets(
+            table(
+                r#"
+                preset = ["low-memory", "fast"]
+                [cache]
+                size = 32
+                [presets.fast]
+                workers = 4
+                "#,
+            ),
+            PRESETS,
+        )
+        .unwrap();
+        assert_eq!(expanded, table("workers = 4\n[cache]\nsize = 32"));
+
+        assert_eq!(
+            expand_presets(table("preset = \"tiny\"\n[presets.small]"), PRESETS).unwrap_err(),
+            "unknown preset `tiny`, expected one of `fast`, `low-memory`, `small`"
+        );
+        assert!(expand_presets(table("preset = 1"), PRESETS).is_err());
+        assert_eq!(
+            expand_presets(table("[presets.small]\nworkers = 1"), PRESETS).unwrap(),
+            Table::new()
+        );
+    }
+
+    #[test]
+    fn test_describe_presets() {
+        assert_eq!(
+            describe_presets(PRESETS),
+            "low-memory: Use less memory\n    cache.size = 16\n    workers = 1\nfast: Use more workers\n    workers = 8\n"
+        );
+    }
+}
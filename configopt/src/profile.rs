@@ -0,0 +1,91 @@
+use toml::value::{Table, Value};
+
+/// The table holding the profiles of a config file
+pub const PROFILES_KEY: &str = "profiles";
+
+/// Remove the profiles from `table` and merge the selected `profile` over the base values
+pub(crate) fn select_profile(mut table: Table, profile: &str) -> Result<Table, String> {
+    let mut profiles = match table.remove(PROFILES_KEY) {
+        Some(Value::Table(profiles)) => profiles,
+        Some(_) => return Err(format!("`{}` must be a table", PROFILES_KEY)),
+        None => return Err(format!("there is no `{}` table", PROFILES_KEY)),
+    };
+    match profiles.remove(profile) {
+        Some(Value::Table(overrides)) => {
+            merge(&mut table, overrides);
+            Ok(table)
+        }
+        Some(_) => Err(format!("profile `{}` must be a table", profile)),
+        None => {
+            let mut known = profiles
+                .keys()
+                .map(|k| format!("`{}`", k))
+                .collect::<Vec<_>>();
+            known.sort();
+            Err(format!(
+                "unknown profile `{}`, expected one of {}",
+                profile,
+                known.join(", ")
+            ))
+        }
+    }
+}
+
+// Nested tables are merged key by key while every other value is replaced
+pub(crate) fn merge(base: &mut Table, overrides: Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(overrides)) => merge(base, overrides),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(s: &str) -> Table {
+        match s.parse::<Value>().unwrap() {
+            Value::Table(table) => table,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_select_profile() {
+        let base = table(
+            r#"
+            host = "localhost"
+            workers = 1
+            [server]
+            port = 8080
+            tls = false
+            [profiles.production]
+            host = "example.com"
+            [profiles.production.server]
+            tls = true
+            "#,
+        );
+        let selected = select_profile(base.clone(), "production").unwrap();
+        assert_eq!(
+            selected,
+            table(
+                r#"
+                host = "example.com"
+                workers = 1
+                [server]
+                port = 8080
+                tls = true
+                "#
+            )
+        );
+        assert_eq!(
+            select_profile(base, "dev").unwrap_err(),
+            "unknown profile `dev`, expected one of `production`"
+        );
+        assert!(select_profile(table("host = \"a\""), "dev").is_err());
+    }
+}
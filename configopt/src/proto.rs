@@ -0,0 +1,204 @@
+//! Load config from protobuf messages pushed over gRPC
+//!
+//! A control plane can push a `google.protobuf.Struct` (`prost_types::Struct`) holding the same
+//! document a config file would or a message of its own schema generated by `prost`. Either is
+//! converted to a table and goes through the same parsing as a config file so the result can be
+//! merged with the other layers and validated the same way:
+//!
+//! ```ignore
+//! let mut pushed: ConfigOptApp =
+//!     configopt::from_proto_struct(&request.config, "grpc://control", &mut Vec::new())?;
+//! configopt.patch(&mut pushed);
+//! let app = App::try_from(configopt)?;
+//! ```
+
+use crate::{ConfigOptType, Error, LoadTrace, Result, TraceSource, UnknownKey};
+use prost_types::{value::Kind, ListValue, Struct};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{path::Path, time::Instant};
+use toml::value::{Table, Value};
+
+/// The largest integer a `f64` holds exactly
+const MAX_EXACT_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+fn from_proto_value(value: prost_types::Value) -> std::result::Result<Option<Value>, String> {
+    Ok(Some(match value.kind {
+        None | Some(Kind::NullValue(_)) => return Ok(None),
+        Some(Kind::BoolValue(b)) => Value::Boolean(b),
+        // A `Struct` only has doubles so whole numbers are integers to fit integer fields
+        Some(Kind::NumberValue(n)) if n.fract() == 0.0 && n.abs() <= MAX_EXACT_INTEGER => {
+            Value::Integer(n as i64)
+        }
+        Some(Kind::NumberValue(n)) => Value::Float(n),
+        Some(Kind::StringValue(s)) => Value::String(s),
+        Some(Kind::ListValue(list)) => Value::Array(
+            list.values
+                .into_iter()
+                .map(|value| {
+                    from_proto_value(value)?
+                        .ok_or_else(|| String::from("a list cannot hold a null value"))
+                })
+                .collect::<std::result::Result<_, _>>()?,
+        ),
+        Some(Kind::StructValue(message)) => Value::Table(table_from_struct(message)?),
+    }))
+}
+
+fn to_proto_value(value: Value) -> prost_types::Value {
+    let kind = match value {
+        Value::Boolean(b) => Kind::BoolValue(b),
+        Value::Integer(i) => Kind::NumberValue(i as f64),
+        Value::Float(f) => Kind::NumberValue(f),
+        Value::String(s) => Kind::StringValue(s),
+        Value::Datetime(d) => Kind::StringValue(d.to_string()),
+        Value::Array(items) => Kind::ListValue(ListValue {
+            values: items.into_iter().map(to_proto_value).collect(),
+        }),
+        Value::Table(table) => Kind::StructValue(struct_from_table(table)),
+    };
+    prost_types::Value { kind: Some(kind) }
+}
+
+/// Convert a `Struct` to a table
+///
+/// Null values are left out so they leave a field unset. Numbers without a fractional part are
+/// integers.
+pub fn table_from_struct(message: Struct) -> std::result::Result<Table, String> {
+    let mut table = Table::new();
+    for (key, value) in message.fields {
+        if let Some(value) = from_proto_value(value).map_err(|e| format!("`{}` {}", key, e))? {
+            table.insert(key, value);
+        }
+    }
+    Ok(table)
+}
+
+/// Convert a table to a `Struct`
+///
+/// Integers become doubles and datetimes become strings.
+pub fn struct_from_table(table: Table) -> Struct {
+    Struct {
+        fields: table
+            .into_iter()
+            .map(|(key, value)| (key, to_proto_value(value)))
+            .collect(),
+    }
+}
+
+fn from_table<T>(table: Table, name: &str, unknown_keys: &mut Vec<UnknownKey>) -> Result<T>
+where
+    T: ConfigOptType + DeserializeOwned,
+{
+    let started = Instant::now();
+    let configopt = crate::from_toml_table::<T>(table, Path::new(name), unknown_keys)?;
+    LoadTrace::record(
+        TraceSource::Message {
+            name: String::from(name),
+        },
+        || configopt.set_fields(),
+        started,
+    );
+    Ok(configopt)
+}
+
+fn invalid_message(name: &str, message: String) -> Error {
+    Error::ConfigFile(
+        Path::new(name).to_path_buf(),
+        std::io::Error::new(std::io::ErrorKind::InvalidData, message),
+    )
+}
+
+/// Parse the config document `message` holds collecting any keys which are not recognized
+///
+/// `name` identifies where the message came from (eg `grpc://control-plane`) in errors and the
+/// load trace.
+pub fn from_proto_struct<T>(
+    message: &Struct,
+    name: &str,
+    unknown_keys: &mut Vec<UnknownKey>,
+) -> Result<T>
+where
+    T: ConfigOptType + DeserializeOwned,
+{
+    let table = table_from_struct(message.clone()).map_err(|e| invalid_message(name, e))?;
+    from_table(table, name, unknown_keys)
+}
+
+/// The config document of `config` as a `Struct`
+///
+/// Secrets and the fields left out of the generated config (eg hidden fields) are not included.
+pub fn to_proto_struct<T: ConfigOptType>(config: &T) -> Struct {
+    let table = toml::from_str::<Table>(&config.toml_config()).unwrap_or_default();
+    struct_from_table(table)
+}
+
+/// Parse a `prost` generated message whose fields are named like the config keys
+///
+/// The message must implement `Serialize` which `prost-build` adds with
+/// `type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]")`. Only fields which
+/// are `Option`s in the message (eg nested messages) can be left unset. Scalars are always set
+/// since proto3 does not tell a default value from a missing one.
+pub fn from_message<T, M>(message: &M, name: &str, unknown_keys: &mut Vec<UnknownKey>) -> Result<T>
+where
+    T: ConfigOptType + DeserializeOwned,
+    M: Serialize,
+{
+    let table = match Value::try_from(message) {
+        Ok(Value::Table(table)) => table,
+        Ok(_) => return Err(invalid_message(name, String::from("expected a message"))),
+        Err(e) => return Err(invalid_message(name, e.to_string())),
+    };
+    from_table(table, name, unknown_keys)
+}
+
+/// Build a `prost` generated message from the config document of `config`
+///
+/// Like [`to_proto_struct`](fn.to_proto_struct.html) secrets are not included. The message must
+/// implement `Deserialize` and default its missing fields (eg `#[serde(default)]`).
+pub fn to_message<M, T>(config: &T) -> std::result::Result<M, String>
+where
+    M: DeserializeOwned,
+    T: ConfigOptType,
+{
+    toml::from_str(&config.toml_config()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_struct_round_trip() {
+        let table = toml::from_str::<Table>(
+            "port = 80\nratio = 0.5\ntags = [\"a\"]\n[tls]\nenabled = true\n",
+        )
+        .unwrap();
+        let message = struct_from_table(table.clone());
+        assert_eq!(message.fields["port"].kind, Some(Kind::NumberValue(80.0)));
+        assert_eq!(table_from_struct(message).unwrap(), table);
+    }
+
+    #[test]
+    fn test_null_values() {
+        let mut message = Struct::default();
+        message.fields.insert(
+            String::from("host"),
+            prost_types::Value {
+                kind: Some(Kind::NullValue(0)),
+            },
+        );
+        assert!(table_from_struct(message.clone()).unwrap().is_empty());
+        message.fields.insert(
+            String::from("tags"),
+            prost_types::Value {
+                kind: Some(Kind::ListValue(ListValue {
+                    values: vec![prost_types::Value { kind: None }],
+                })),
+            },
+        );
+        assert_eq!(
+            table_from_struct(message).unwrap_err(),
+            "`tags` a list cannot hold a null value"
+        );
+    }
+}
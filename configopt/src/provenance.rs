@@ -0,0 +1,54 @@
+use crate::{Error, LoadTrace, TraceSource, Violation};
+
+/// Where each value of a loaded config came from
+///
+/// This is passed to the `#[configopt(validate(..))]` function of a type so rules between fields
+/// can depend on the layer which set them (eg a value from the CLI forbids a value from a config
+/// file) and errors can name the layer to fix.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Provenance {
+    trace: LoadTrace,
+}
+
+impl Provenance {
+    pub fn new(trace: LoadTrace) -> Self {
+        Self { trace }
+    }
+
+    /// The layer which set the dotted path `key` or `None` if no layer set it
+    pub fn source_of(&self, key: &str) -> Option<&TraceSource> {
+        self.trace.source_of(key)
+    }
+
+    /// Did any layer set the dotted path `key`
+    pub fn is_set(&self, key: &str) -> bool {
+        self.source_of(key).is_some()
+    }
+
+    /// Was the dotted path `key` set on the command line or by its environment variable
+    pub fn is_from_cli(&self, key: &str) -> bool {
+        self.source_of(key) == Some(&TraceSource::Cli)
+    }
+
+    /// Every step taken while loading the config
+    pub fn trace(&self) -> &LoadTrace {
+        &self.trace
+    }
+
+    /// An error that the value of `key` is invalid attributed to the layer which set it
+    pub fn invalid(&self, key: &str, message: impl Into<String>) -> Error {
+        let message = message.into();
+        match self.source_of(key) {
+            Some(source) => Error::Field {
+                path: String::from(key),
+                source: source.clone(),
+                location: None,
+                message,
+            },
+            None => Error::Constraints(vec![Violation::Constraint {
+                path: String::from(key),
+                message,
+            }]),
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use crate::{Schema, SchemaField};
+use std::collections::HashMap;
+use structopt::clap::{App, ArgSettings};
+
+/// The format of a [`reference_doc`](fn.reference_doc.html)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocFormat {
+    Markdown,
+    /// A man page in section 5 (file formats and conventions)
+    Roff,
+}
+
+/// What the CLI knows about an argument
+pub(crate) struct ArgDoc {
+    pub(crate) flag: String,
+    pub(crate) help: String,
+    pub(crate) default: Option<String>,
+}
+
+/// The documentation of each visible argument of `app` by its name
+pub(crate) fn arg_docs(app: &App<'_, '_>) -> HashMap<String, ArgDoc> {
+    let help = |long_help: Option<&str>, help: Option<&str>| {
+        String::from(long_help.or(help).unwrap_or_default())
+    };
+    let switch = |short: Option<char>, long: Option<&str>| {
+        let short = short.map(|short| format!("-{}", short));
+        let long = long.map(|long| format!("--{}", long));
+        short.into_iter().chain(long).collect::<Vec<_>>().join(", ")
+    };
+    let mut docs = HashMap::new();
+    for arg in &app.p.flags {
+        if arg.b.is_set(ArgSettings::Hidden) {
+            continue;
+        }
+        docs.insert(
+            String::from(arg.b.name),
+            ArgDoc {
+                flag: switch(arg.s.short, arg.s.long),
+                help: help(arg.b.long_help, arg.b.help),
+                default: None,
+            },
+        );
+    }
+    for arg in &app.p.opts {
+        if arg.b.is_set(ArgSettings::Hidden) {
+            continue;
+        }
+        docs.insert(
+            String::from(arg.b.name),
+            ArgDoc {
+                flag: format!("{} <{}>", switch(arg.s.short, arg.s.long), arg.b.name),
+                help: help(arg.b.long_help, arg.b.help),
+                default: arg.v.default_val.map(|d| d.to_string_lossy().into_owned()),
+            },
+        );
+    }
+    for (_, arg) in &app.p.positionals {
+        if arg.b.is_set(ArgSettings::Hidden) {
+            continue;
+        }
+        docs.insert(
+            String::from(arg.b.name),
+            ArgDoc {
+                flag: format!("<{}>", arg.b.name),
+                help: help(arg.b.long_help, arg.b.help),
+                default: arg.v.default_val.map(|d| d.to_string_lossy().into_owned()),
+            },
+        );
+    }
+    docs
+}
+
+/// A row of the reference for a single field
+struct Entry<'a> {
+    field: &'a SchemaField,
+    arg: Option<&'a ArgDoc>,
+}
+
+impl<'a> Entry<'a> {
+    fn properties(&self) -> Vec<(&'static str, String)> {
+        let mut properties = Vec::new();
+        if let Some(arg) = self.arg {
+            properties.push(("CLI", arg.flag.clone()));
+        }
+        if let Some(env) = &self.field.env {
+            properties.push(("Environment", env.clone()));
+        }
+        properties.push(("Config key", self.field.path.clone()));
+        properties.push(("Type", self.field.ty.clone()));
+        if let Some(default) = self.arg.and_then(|arg| arg.default.as_ref()) {
+            properties.push(("Default", default.clone()));
+        }
+        if self.field.required {
+            properties.push(("Required", String::from("yes")));
+        }
+        if !self.field.values.is_empty() {
+            properties.push(("Possible values", self.field.values.join(", ")));
+        }
+        let constraints = &self.field.constraints;
+        if let Some(min) = constraints.min {
+            properties.push(("Minimum", min.to_string()));
+        }
+        if let Some(max) = constraints.max {
+            properties.push(("Maximum", max.to_string()));
+        }
+        if let Some(pattern) = &constraints.pattern {
+            properties.push(("Pattern", pattern.clone()));
+        }
+        if let Some(other) = &constraints.required_if {
+            properties.push(("Required if set", other.clone()));
+        }
+        if !self.field.aliases.is_empty() {
+            properties.push(("Aliases", self.field.aliases.join(", ")));
+        }
+        properties
+    }
+
+    fn help(&self) -> &str {
+        self.arg.map(|arg| arg.help.as_str()).unwrap_or_default()
+    }
+}
+
+/// Document every field of `schema` with the CLI argument it is read from in `app`
+///
+/// The reference lists the CLI flag, environment variable, config key, type, default, and help of
+/// each field. Fields nested in a map (`*` in their path) are only set from config files so they
+/// have no CLI flag.
+pub fn reference_doc(app: &App<'_, '_>, schema: &Schema, format: DocFormat) -> String {
+    let docs = arg_docs(app);
+    let entries = schema
+        .fields
+        .iter()
+        .map(|field| Entry {
+            field,
+            arg: field
+                .arg
+                .as_ref()
+                .filter(|_| !field.path.split('.').any(|key| key == "*"))
+                .and_then(|arg| docs.get(arg)),
+        })
+        .collect::<Vec<_>>();
+    let name = &app.p.meta.name;
+    let about = app
+        .p
+        .meta
+        .long_about
+        .or(app.p.meta.about)
+        .unwrap_or_default();
+    match format {
+        DocFormat::Markdown => markdown(name, about, &entries),
+        DocFormat::Roff => roff(name, about, &entries),
+    }
+}
+
+fn markdown(name: &str, about: &str, entries: &[Entry<'_>]) -> String {
+    let mut doc = format!("# {} configuration reference\n\n", name);
+    if !about.is_empty() {
+        doc.push_str(&format!("{}\n\n", about));
+    }
+    for entry in entries {
+        doc.push_str(&format!("## `{}`\n\n", entry.field.path));
+        if !entry.help().is_empty() {
+            doc.push_str(&format!("{}\n\n", entry.help()));
+        }
+        doc.push_str("| | |\n|---|---|\n");
+        for (property, value) in entry.properties() {
+            // Escape pipes so a value cannot end its table cell
+            doc.push_str(&format!(
+                "| {} | `{}` |\n",
+                property,
+                value.replace('|', "\\|")
+            ));
+        }
+        doc.push('\n');
+    }
+    doc
+}
+
+/// Escape text so roff does not read it as a request or an escape sequence
+fn roff_escape(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let line = line.replace('\\', "\\e").replace('-', "\\-");
+            if line.starts_with('.') || line.starts_with('\'') {
+                format!("\\&{}", line)
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn roff(name: &str, about: &str, entries: &[Entry<'_>]) -> String {
+    let mut doc = format!(
+        ".TH {} 5\n.SH NAME\n{} \\- configuration reference\n",
+        roff_escape(&name.to_uppercase()),
+        roff_escape(name)
+    );
+    if !about.is_empty() {
+        doc.push_str(&format!(".SH DESCRIPTION\n{}\n", roff_escape(about)));
+    }
+    doc.push_str(".SH OPTIONS\n");
+    for entry in entries {
+        doc.push_str(&format!(".TP\n.B {}\n", roff_escape(&entry.field.path)));
+        if !entry.help().is_empty() {
+            doc.push_str(&format!("{}\n", roff_escape(entry.help())));
+        }
+        doc.push_str(".RS\n");
+        for (property, value) in entry.properties() {
+            doc.push_str(&format!(
+                ".PP\n{}: \\fB{}\\fR\n",
+                property,
+                roff_escape(&value)
+            ));
+        }
+        doc.push_str(".RE\n");
+    }
+    doc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roff_escape() {
+        assert_eq!(roff_escape("--port"), "\\-\\-port");
+        assert_eq!(roff_escape(".hidden\n'quoted"), "\\&.hidden\n\\&'quoted");
+        assert_eq!(roff_escape("a\\b"), "a\\eb");
+    }
+}
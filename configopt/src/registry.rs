@@ -0,0 +1,263 @@
+use crate::{
+    config_format::parse_error, field_path, ConfigFormat, ConfigOptArgToOsString, ConfigOptType,
+    FieldPath, Result, Schema, TomlValueError, Value,
+};
+use std::{any::Any, ffi::OsString, fmt, path::Path};
+
+/// The lookups of a `configopt` type which do not depend on the concrete type
+///
+/// Unlike `ConfigOptType` this trait is object safe so unrelated config types can be stored
+/// together as `Box<dyn ConfigOptDefaults>`, for example in a [`Registry`](struct.Registry.html).
+/// It is implemented for every `configopt` type. The methods are named so they do not collide
+/// with the methods of `ConfigOptType` when both traits are in scope.
+///
+/// It includes the lookups used to set the defaults of the CLI: the structopt name of a field,
+/// the serde path of an argument (the casing of the config file), and the default a field gives
+/// its argument.
+pub trait ConfigOptDefaults: ConfigOptArgToOsString + Any {
+    /// The keys the type accepts each starting with `prefix`
+    ///
+    /// The paths use the serde names of the fields so they follow any `rename_all` casing.
+    fn schema_with_prefix(&self, prefix: &str) -> Schema;
+
+    /// The dotted paths of the fields which are set
+    fn fields_set(&self) -> Vec<String>;
+
+    /// The value at `path` or `None` if it is not set or is a secret
+    fn value_at(&self, path: &FieldPath) -> Option<Value>;
+
+    /// The structopt name of the argument of the field at the serde `path`
+    ///
+    /// A field which is not a CLI argument (eg `#[configopt(file_only)]`) has no name.
+    fn structopt_name_of(&self, path: &str) -> Option<String> {
+        self.schema_with_prefix("")
+            .fields
+            .into_iter()
+            .find(|field| field.path == path)
+            .and_then(|field| field.arg)
+    }
+
+    /// The serde path of the field of the argument named `structopt_name`
+    fn path_of_arg(&self, structopt_name: &str) -> Option<String> {
+        self.schema_with_prefix("")
+            .fields
+            .into_iter()
+            .find(|field| field.arg.as_deref() == Some(structopt_name))
+            .map(|field| field.path)
+    }
+
+    /// The default the field at the serde `path` gives its argument
+    fn default_at(&self, path: &str) -> Option<OsString> {
+        let name = self.structopt_name_of(path)?;
+        self.arg_to_os_string(&[name])
+    }
+
+    /// The set values as a TOML document
+    fn config_toml(&self) -> String;
+
+    /// The set values as a TOML table, see `ConfigOptType::toml_values`
+    fn config_values(&self) -> std::result::Result<toml::value::Table, TomlValueError>;
+
+    /// Patch with values from a config document which is already in memory
+    fn patch_with_document(&mut self, contents: &str, format: ConfigFormat) -> Result<()>;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: ConfigOptType + 'static> ConfigOptDefaults for T {
+    fn schema_with_prefix(&self, prefix: &str) -> Schema {
+        Schema::new(T::schema_fields(prefix))
+    }
+
+    fn fields_set(&self) -> Vec<String> {
+        self.set_fields()
+    }
+
+    fn value_at(&self, path: &FieldPath) -> Option<Value> {
+        field_path::get_field(self, path)
+    }
+
+    fn config_toml(&self) -> String {
+        self.toml_config()
+    }
+
+    fn config_values(&self) -> std::result::Result<toml::value::Table, TomlValueError> {
+        self.toml_values()
+    }
+
+    fn patch_with_document(&mut self, contents: &str, format: ConfigFormat) -> Result<()> {
+        self.patch_with_config_str(contents, format).map(|_| ())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Unrelated `configopt` types composed into a single application wide config at runtime
+///
+/// Each type is registered under a name which is the table of its keys in a config document and
+/// the first segment of the dotted paths and argument paths given to it. A framework can let each
+/// plugin register its own config type and still load, render, and look up every config in one
+/// place. The registry is itself a lookup of default values so it can be passed to
+/// `configopt::set_defaults` for an app with a subcommand named after each type.
+///
+/// ```ignore
+/// let mut registry = Registry::new();
+/// registry.register("http", ConfigOptHttp::default());
+/// registry.register("db", ConfigOptDb::default());
+/// registry.patch_with_config_str(&contents, ConfigFormat::Toml)?;
+/// let db = registry.get::<ConfigOptDb>("db").unwrap();
+/// ```
+#[derive(Default)]
+pub struct Registry {
+    entries: Vec<(String, Box<dyn ConfigOptDefaults>)>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `config` under `name` replacing any config already registered under it
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        config: impl ConfigOptDefaults,
+    ) -> &mut Self {
+        let name = name.into();
+        let config = Box::new(config);
+        match self.entries.iter_mut().find(|(n, _)| *n == name) {
+            Some(entry) => entry.1 = config,
+            None => self.entries.push((name, config)),
+        }
+        self
+    }
+
+    /// The names of the registered configs in the order they were registered
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// The config registered under `name` as a trait object
+    pub fn config(&self, name: &str) -> Option<&dyn ConfigOptDefaults> {
+        self.entries
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, config)| config.as_ref())
+    }
+
+    /// The config registered under `name` if it is a `T`
+    pub fn get<T: 'static>(&self, name: &str) -> Option<&T> {
+        self.config(name)?.as_any().downcast_ref()
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, name: &str) -> Option<&mut T> {
+        self.entries
+            .iter_mut()
+            .find(|(n, _)| n == name)
+            .and_then(|(_, config)| config.as_any_mut().downcast_mut())
+    }
+
+    /// The keys of every registered config under the name it was registered with
+    pub fn schema(&self) -> Schema {
+        Schema::new(
+            self.entries
+                .iter()
+                .flat_map(|(name, config)| config.schema_with_prefix(&format!("{}.", name)).fields)
+                .collect(),
+        )
+    }
+
+    /// The dotted paths of the fields which are set in every registered config
+    pub fn fields_set(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .flat_map(|(name, config)| {
+                config
+                    .fields_set()
+                    .into_iter()
+                    .map(move |path| format!("{}.{}", name, path))
+            })
+            .collect()
+    }
+
+    /// The value at `path` whose first key is the name of a registered config
+    pub fn get_value(&self, path: &FieldPath) -> Option<Value> {
+        let (name, rest) = path.keys().split_first()?;
+        self.config(name)?
+            .value_at(&FieldPath::new(rest.iter().map(String::as_str)))
+    }
+
+    /// The set values of every registered config as a TOML document with a table per config
+    ///
+    /// The values are those of `ConfigOptType::toml_values` so hidden fields and leaves are
+    /// included and secrets are left out. It is an error if a value cannot be represented in TOML.
+    pub fn toml_config(&self) -> std::result::Result<String, TomlValueError> {
+        let mut table = toml::value::Table::new();
+        for (name, config) in &self.entries {
+            let values = config.config_values().map_err(|e| TomlValueError {
+                path: format!("{}.{}", name, e.path),
+                message: e.message,
+            })?;
+            if !values.is_empty() {
+                table.insert(name.clone(), toml::Value::Table(values));
+            }
+        }
+        Ok(toml::to_string(&table).expect("a table is always serializable"))
+    }
+
+    /// Patch each registered config with the table named after it in a config document
+    ///
+    /// Tables which are not named after a registered config are an error. The untyped formats
+    /// (eg INI) cannot be used as the types of their values are not known ahead of time.
+    pub fn patch_with_config_str(&mut self, contents: &str, format: ConfigFormat) -> Result<()> {
+        let path = Path::new("<memory>");
+        let table = format.parse_table(contents, path, Schema::default)?;
+        for (name, values) in table {
+            let config = match self.entries.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, config)) => config,
+                None => {
+                    return Err(parse_error(
+                        path,
+                        format!("`{}` is not a registered config", name),
+                    ))
+                }
+            };
+            let contents = toml::to_string(&values)
+                .map_err(|e| parse_error(path, format!("`{}` {}", name, e)))?;
+            config.patch_with_document(&contents, ConfigFormat::Toml)?;
+        }
+        Ok(())
+    }
+}
+
+impl ConfigOptArgToOsString for Registry {
+    fn arg_to_os_string(&self, arg_path: &[String]) -> Option<OsString> {
+        let (name, rest) = arg_path.split_first()?;
+        self.config(name)?.arg_to_os_string(rest)
+    }
+
+    fn arg_is_secret(&self, arg_path: &[String]) -> bool {
+        match arg_path.split_first() {
+            Some((name, rest)) => self
+                .config(name)
+                .map_or(false, |config| config.arg_is_secret(rest)),
+            None => false,
+        }
+    }
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Registry")
+            .field("names", &self.names().collect::<Vec<_>>())
+            .finish()
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{ConfigDiff, Error, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, ErrorKind as IoErrorKind},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A document which was read during a reload
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedInput {
+    /// Where the document came from (eg the path of a config file)
+    pub source: String,
+    pub contents: String,
+}
+
+impl RecordedInput {
+    pub fn new(source: impl Into<String>, contents: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            contents: contents.into(),
+        }
+    }
+
+    /// Record the current contents of the config file at `path`
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        Ok(Self::new(path.to_string_lossy(), fs::read_to_string(path)?))
+    }
+}
+
+/// A single reload stored in a [`ReloadJournal`](struct.ReloadJournal.html)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReloadRecord {
+    pub sequence: u64,
+    /// Seconds since the Unix epoch when the reload happened
+    pub timestamp: u64,
+    pub inputs: Vec<RecordedInput>,
+    /// The dotted paths of the fields the reload changed
+    pub changed: Vec<String>,
+    /// The changed fields which only take effect after a restart
+    #[serde(default)]
+    pub restart_required: Vec<String>,
+}
+
+impl ReloadRecord {
+    /// The fields the reload changed
+    pub fn diff(&self) -> ConfigDiff {
+        ConfigDiff::new(self.changed.clone()).with_restart_required(self.restart_required.clone())
+    }
+
+    /// Parse each recorded TOML document with the config types of the current binary
+    pub fn replay<T: DeserializeOwned>(&self) -> Result<Vec<T>> {
+        self.inputs
+            .iter()
+            .map(|input| {
+                toml::from_str(&input.contents)
+                    .map_err(|e| Error::ConfigFile(PathBuf::from(&input.source), e.into()))
+            })
+            .collect()
+    }
+}
+
+/// A ring buffer on disk of the inputs and diffs of every reload
+///
+/// This is a development tool. When a config change causes an incident the recorded reloads can be
+/// inspected and replayed against the current binary long after the files were changed again.
+#[derive(Clone, Debug)]
+pub struct ReloadJournal {
+    dir: PathBuf,
+    capacity: usize,
+}
+
+impl ReloadJournal {
+    /// Use `dir` to store at most `capacity` of the most recent reloads
+    pub fn open(dir: impl Into<PathBuf>, capacity: usize) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, capacity })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Store a reload dropping the oldest reloads beyond the capacity
+    pub fn record(
+        &self,
+        inputs: Vec<RecordedInput>,
+        diff: &ConfigDiff,
+    ) -> io::Result<ReloadRecord> {
+        let sequences = self.sequences()?;
+        let record = ReloadRecord {
+            sequence: sequences.last().map_or(0, |s| s + 1),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            inputs,
+            changed: diff.changed().to_vec(),
+            restart_required: diff.restart_required().to_vec(),
+        };
+        let json = serde_json::to_string(&record)
+            .map_err(|e| io::Error::new(IoErrorKind::InvalidData, e))?;
+        fs::write(self.path(record.sequence), json)?;
+        let stored = sequences.len() + 1;
+        for sequence in sequences.iter().take(stored.saturating_sub(self.capacity)) {
+            fs::remove_file(self.path(*sequence))?;
+        }
+        Ok(record)
+    }
+
+    /// The stored reloads from oldest to newest
+    pub fn records(&self) -> io::Result<Vec<ReloadRecord>> {
+        self.sequences()?
+            .into_iter()
+            .map(|sequence| {
+                let json = fs::read_to_string(self.path(sequence))?;
+                serde_json::from_str(&json).map_err(|e| io::Error::new(IoErrorKind::InvalidData, e))
+            })
+            .collect()
+    }
+
+    fn path(&self, sequence: u64) -> PathBuf {
+        self.dir.join(format!("{:020}.json", sequence))
+    }
+
+    fn sequences(&self) -> io::Result<Vec<u64>> {
+        let mut sequences = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().map_or(false, |e| e == "json") {
+                if let Some(sequence) = path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .and_then(|s| s.parse().ok())
+                {
+                    sequences.push(sequence);
+                }
+            }
+        }
+        sequences.sort();
+        Ok(sequences)
+    }
+}
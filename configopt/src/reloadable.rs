@@ -0,0 +1,68 @@
+use crate::{scoped, Live};
+use std::{fmt, sync::Arc};
+
+/// A cheaply cloneable handle to the current value of a config which can be replaced on reload
+///
+/// Every clone shares the same value so a handle can be given to a dependency injection container
+/// or web framework state instead of storing the config in a global static. Readers get an `Arc`
+/// snapshot which is unaffected by later reloads.
+///
+/// This is a [`Live`](struct.Live.html) config whose reads also respect
+/// `configopt::with_override`. Use [`live`](#method.live) for versions, history and
+/// subscriptions. With the `axum` feature it can be extracted from router state and with the
+/// `shaku` feature it can be given to a module as a `ConfigModule`.
+pub struct Reloadable<T> {
+    live: Live<T>,
+}
+
+impl<T: 'static> Reloadable<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            live: Live::new(value),
+        }
+    }
+
+    /// Get a snapshot of the current value or the value set with `configopt::with_override`
+    pub fn get(&self) -> Arc<T> {
+        if let Some(value) = scoped::current_override() {
+            return value;
+        }
+        self.live.load().into_arc()
+    }
+
+    /// Replace the current value returning the previous value
+    pub fn replace(&self, value: T) -> Arc<T> {
+        self.live.store(value)
+    }
+
+    /// The `Live` config shared by every clone of this handle
+    pub fn live(&self) -> &Live<T> {
+        &self.live
+    }
+}
+
+impl<T> Clone for Reloadable<T> {
+    fn clone(&self) -> Self {
+        Self {
+            live: self.live.clone(),
+        }
+    }
+}
+
+impl<T: 'static> From<T> for Reloadable<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> From<Live<T>> for Reloadable<T> {
+    fn from(live: Live<T>) -> Self {
+        Self { live }
+    }
+}
+
+impl<T: fmt::Debug + 'static> fmt::Debug for Reloadable<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Reloadable").field(&self.get()).finish()
+    }
+}
@@ -0,0 +1,186 @@
+//! Replace the string values of a config document which refer to a value stored elsewhere
+//!
+//! A reference is a string value starting with a scheme (eg `ssm:/myapp/db_password` or
+//! `vault:secret/data/app#password`). Every [`ReferenceResolver`](trait.ReferenceResolver.html)
+//! of a [`ResolvedSource`](struct.ResolvedSource.html) takes part in the same pass so a document
+//! may mix the schemes of several backends.
+
+use crate::{
+    coerce::{self, Policy},
+    AsyncSource, Schema, SourceFuture,
+};
+use std::{fmt, io, sync::Arc};
+use toml::value::{Table, Value};
+
+pub(crate) fn other(e: impl fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e.to_string())
+}
+
+/// Fetches the values references with its schemes refer to
+pub trait ReferenceResolver: Send + Sync {
+    /// The prefixes of the references this resolves including the `:` (eg `vault:`)
+    fn schemes(&self) -> &[&'static str];
+
+    /// Fetch the value of `reference` which starts with one of the schemes
+    fn resolve<'a>(&'a self, reference: &'a str) -> SourceFuture<'a>;
+}
+
+/// Set the string `value` at `path` coercing it to the kind of value the schema expects
+///
+/// A value without a schema field is kept as a string so it is reported as an unknown key.
+pub(crate) fn set_value(
+    table: &mut Table,
+    path: &str,
+    value: &str,
+    schema: Option<&Schema>,
+) -> io::Result<()> {
+    let value = match schema.and_then(|schema| coerce::find_field(schema, path)) {
+        Some(field) => coerce::coerce(value, &field.kind(), &Policy::default())
+            .map_err(|e| other(format!("`{}` {}", path, e)))?,
+        None => Value::String(String::from(value)),
+    };
+    coerce::set_path(table, path, value).map_err(other)
+}
+
+pub(crate) fn render(table: Table) -> io::Result<String> {
+    toml::to_string(&Value::Table(table)).map_err(other)
+}
+
+/// The dotted path and value of every string value of `table` starting with one of `schemes`
+fn references(table: &Table, prefix: &str, schemes: &[&str], found: &mut Vec<(String, String)>) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            Value::String(s) => {
+                if schemes.iter().any(|scheme| s.starts_with(scheme)) {
+                    found.push((path, s.clone()));
+                }
+            }
+            Value::Table(table) => references(table, &path, schemes, found),
+            _ => {}
+        }
+    }
+}
+
+/// A source whose references are replaced with the values they refer to
+///
+/// Wrap a [`FileSource`](struct.FileSource.html) to keep `ssm:/myapp/db_password` in a config file
+/// instead of the password. Only the values of tables are resolved and not the items of arrays.
+/// A value whose scheme has no resolver is left as it is.
+#[derive(Clone)]
+pub struct ResolvedSource<S> {
+    inner: S,
+    resolvers: Vec<Arc<dyn ReferenceResolver>>,
+    schema: Option<Schema>,
+}
+
+impl<S: AsyncSource> ResolvedSource<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            resolvers: Vec::new(),
+            schema: None,
+        }
+    }
+
+    /// Resolve the references with the schemes of `resolver`
+    ///
+    /// The first resolver added for a scheme is used.
+    pub fn with_resolver(mut self, resolver: impl ReferenceResolver + 'static) -> Self {
+        self.resolvers.push(Arc::new(resolver));
+        self
+    }
+
+    /// Coerce each resolved value to the kind of value `schema` expects (eg `T::schema()`)
+    pub fn with_schema(mut self, schema: Schema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for ResolvedSource<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let schemes = self
+            .resolvers
+            .iter()
+            .flat_map(|r| r.schemes().iter())
+            .collect::<Vec<_>>();
+        f.debug_struct("ResolvedSource")
+            .field("inner", &self.inner)
+            .field("schemes", &schemes)
+            .finish()
+    }
+}
+
+impl<S: AsyncSource> AsyncSource for ResolvedSource<S> {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn read(&self) -> SourceFuture<'_> {
+        Box::pin(async move {
+            let contents = self.inner.read().await?;
+            let mut table = toml::from_str::<Table>(&contents)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let schemes = self
+                .resolvers
+                .iter()
+                .flat_map(|r| r.schemes().iter().copied())
+                .collect::<Vec<_>>();
+            let mut found = Vec::new();
+            references(&table, "", &schemes, &mut found);
+            if found.is_empty() {
+                return Ok(contents);
+            }
+            for (path, reference) in found {
+                let resolver = self
+                    .resolvers
+                    .iter()
+                    .find(|r| r.schemes().iter().any(|s| reference.starts_with(s)))
+                    .expect("a reference has a resolver");
+                let value = resolver
+                    .resolve(&reference)
+                    .await
+                    .map_err(|e| io::Error::new(e.kind(), format!("`{}` {}", path, e)))?;
+                set_value(&mut table, &path, &value, self.schema.as_ref())?;
+            }
+            render(table)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_references() {
+        let table = toml::from_str::<Table>(
+            "user = \"admin\"\npassword = \"ssm:/myapp/password\"\n[db]\nurl = \"vault:db#url\"\n",
+        )
+        .unwrap();
+        let mut found = Vec::new();
+        references(&table, "", &["ssm:", "vault:"], &mut found);
+        assert_eq!(
+            found,
+            vec![
+                (String::from("db.url"), String::from("vault:db#url")),
+                (
+                    String::from("password"),
+                    String::from("ssm:/myapp/password")
+                ),
+            ]
+        );
+        found.clear();
+        references(&table, "", &["vault:"], &mut found);
+        assert_eq!(found.len(), 1);
+    }
+}
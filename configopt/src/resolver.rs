@@ -0,0 +1,254 @@
+use lazy_static::lazy_static;
+use std::{
+    borrow::Cow,
+    env, fs,
+    path::PathBuf,
+    process::Command,
+    sync::{Arc, RwLock},
+};
+
+lazy_static! {
+    static ref RESOLVERS: RwLock<Vec<(String, Arc<dyn Resolver>)>> = RwLock::new(Vec::new());
+}
+
+/// Fetches the value a `<scheme>:<reference>` string value refers to
+///
+/// Register one for a scheme with [`register_resolver`](fn.register_resolver.html). String values
+/// are resolved after every layer is merged and interpolated and before they are validated so a
+/// secret backend only needs a resolver and no changes to this crate. A closure is a `Resolver`.
+pub trait Resolver: Send + Sync {
+    /// Fetch the value of `reference`, the rest of the string value after `<scheme>:`
+    fn resolve(&self, reference: &str) -> Result<String, String>;
+}
+
+impl<F> Resolver for F
+where
+    F: Fn(&str) -> Result<String, String> + Send + Sync,
+{
+    fn resolve(&self, reference: &str) -> Result<String, String> {
+        self(reference)
+    }
+}
+
+/// Resolve `file:<path>` to the contents of the file without a trailing newline
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FileResolver;
+
+impl Resolver for FileResolver {
+    fn resolve(&self, reference: &str) -> Result<String, String> {
+        let contents = fs::read_to_string(reference)
+            .map_err(|e| format!("failed to read '{}', err: {}", reference, e))?;
+        Ok(String::from(
+            contents.trim_end_matches(|c| c == '\n' || c == '\r'),
+        ))
+    }
+}
+
+/// Resolve `env:<name>` to the value of an environment variable
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EnvResolver;
+
+impl Resolver for EnvResolver {
+    fn resolve(&self, reference: &str) -> Result<String, String> {
+        env::var(reference).map_err(|e| format!("`{}` {}", reference, e))
+    }
+}
+
+/// Resolve `cmd:<program> <args>..` to the output of running a command without a trailing newline
+///
+/// The arguments are split on whitespace and no shell is involved. Only register this if config
+/// files are trusted as much as the binary.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CmdResolver;
+
+impl Resolver for CmdResolver {
+    fn resolve(&self, reference: &str) -> Result<String, String> {
+        let mut words = reference.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| String::from("the command is empty"))?;
+        let output = Command::new(program)
+            .args(words)
+            .output()
+            .map_err(|e| format!("failed to run `{}`, err: {}", reference, e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "`{}` failed with {}, stderr: {}",
+                reference,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim_end()
+            ));
+        }
+        let stdout = String::from_utf8(output.stdout)
+            .map_err(|_| format!("the output of `{}` is not UTF-8", reference))?;
+        Ok(String::from(
+            stdout.trim_end_matches(|c| c == '\n' || c == '\r'),
+        ))
+    }
+}
+
+/// Resolve the string values starting with `<scheme>:` with `resolver`
+///
+/// Registering a scheme again replaces its resolver. No scheme is registered by default so a value
+/// like `env:prod` is only a reference once an app opts in.
+pub fn register_resolver(scheme: &str, resolver: impl Resolver + 'static) {
+    let scheme = String::from(scheme.trim_end_matches(':'));
+    let mut resolvers = RESOLVERS.write().expect("resolvers lock poisoned");
+    resolvers.retain(|(s, _)| *s != scheme);
+    resolvers.push((scheme, Arc::new(resolver)));
+}
+
+/// Stop resolving the string values starting with `<scheme>:`
+pub fn unregister_resolver(scheme: &str) {
+    let scheme = scheme.trim_end_matches(':');
+    RESOLVERS
+        .write()
+        .expect("resolvers lock poisoned")
+        .retain(|(s, _)| s != scheme);
+}
+
+/// Register the `file:`, `env:`, and `cmd:` resolvers
+pub fn register_builtin_resolvers() {
+    register_resolver("file", FileResolver);
+    register_resolver("env", EnvResolver);
+    register_resolver("cmd", CmdResolver);
+}
+
+/// Remove every registered resolver
+pub fn clear_resolvers() {
+    RESOLVERS.write().expect("resolvers lock poisoned").clear();
+}
+
+pub(crate) fn has_resolvers() -> bool {
+    !RESOLVERS
+        .read()
+        .expect("resolvers lock poisoned")
+        .is_empty()
+}
+
+/// Resolve `value` with the resolver of its scheme returning `None` if it is not a reference
+pub(crate) fn resolve(value: &str) -> Option<Result<String, String>> {
+    let separator = value.find(':')?;
+    let (scheme, reference) = (&value[..separator], &value[separator + 1..]);
+    let resolver = RESOLVERS
+        .read()
+        .expect("resolvers lock poisoned")
+        .iter()
+        .find(|(s, _)| s == scheme)
+        .map(|(_, resolver)| Arc::clone(resolver))?;
+    // The lock is released so a resolver may register resolvers
+    Some(
+        resolver
+            .resolve(reference)
+            .map_err(|e| format!("`{}:` {}", scheme, e)),
+    )
+}
+
+/// A value whose strings can be references
+pub trait ResolveReference {
+    fn resolve_reference(
+        &mut self,
+        resolve: &dyn Fn(&str) -> Option<Result<String, String>>,
+    ) -> Result<(), String>;
+}
+
+impl ResolveReference for String {
+    fn resolve_reference(
+        &mut self,
+        resolve: &dyn Fn(&str) -> Option<Result<String, String>>,
+    ) -> Result<(), String> {
+        if let Some(value) = resolve(self) {
+            *self = value?;
+        }
+        Ok(())
+    }
+}
+
+impl ResolveReference for Cow<'static, str> {
+    fn resolve_reference(
+        &mut self,
+        resolve: &dyn Fn(&str) -> Option<Result<String, String>>,
+    ) -> Result<(), String> {
+        if let Some(value) = resolve(self) {
+            *self = Cow::Owned(value?);
+        }
+        Ok(())
+    }
+}
+
+impl ResolveReference for PathBuf {
+    fn resolve_reference(
+        &mut self,
+        resolve: &dyn Fn(&str) -> Option<Result<String, String>>,
+    ) -> Result<(), String> {
+        // Paths which are not UTF-8 cannot be a reference
+        if let Some(value) = self.to_str().and_then(resolve) {
+            *self = PathBuf::from(value?);
+        }
+        Ok(())
+    }
+}
+
+impl<T: ResolveReference> ResolveReference for Option<T> {
+    fn resolve_reference(
+        &mut self,
+        resolve: &dyn Fn(&str) -> Option<Result<String, String>>,
+    ) -> Result<(), String> {
+        match self {
+            Some(value) => value.resolve_reference(resolve),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: ResolveReference> ResolveReference for Vec<T> {
+    fn resolve_reference(
+        &mut self,
+        resolve: &dyn Fn(&str) -> Option<Result<String, String>>,
+    ) -> Result<(), String> {
+        self.iter_mut()
+            .try_for_each(|value| value.resolve_reference(resolve))
+    }
+}
+
+// Use the same autoref trick as `InterpolateOther` so the generated code can call
+// `resolve_reference` on fields of every type and only strings are changed.
+#[doc(hidden)]
+pub trait ResolveReferenceOther {
+    fn resolve_reference(
+        &mut self,
+        resolve: &dyn Fn(&str) -> Option<Result<String, String>>,
+    ) -> Result<(), String>;
+}
+
+impl<T> ResolveReferenceOther for &mut T {
+    fn resolve_reference(
+        &mut self,
+        _resolve: &dyn Fn(&str) -> Option<Result<String, String>>,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_reference() {
+        let resolve = |value: &str| -> Option<Result<String, String>> {
+            if value.starts_with("upper:") {
+                Some(Ok(value["upper:".len()..].to_uppercase()))
+            } else if value == "fail:" {
+                Some(Err(String::from("failed")))
+            } else {
+                None
+            }
+        };
+        let mut values = vec![String::from("upper:abc"), String::from("plain")];
+        values.resolve_reference(&resolve).unwrap();
+        assert_eq!(values, vec![String::from("ABC"), String::from("plain")]);
+        let mut value = Some(String::from("fail:"));
+        assert_eq!(value.resolve_reference(&resolve).unwrap_err(), "failed");
+    }
+}
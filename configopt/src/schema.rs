@@ -0,0 +1,317 @@
+use crate::ValueKind;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::fmt;
+
+/// Rules the value of a field must satisfy beyond its type
+///
+/// Set them with `#[configopt(min = .., max = .., matches = "..", required_if = "..")]` where
+/// `pattern` is another name for `matches`. The bounds and pattern apply to each item of a list.
+/// They are checked after every layer is merged. Checking patterns requires the `patterns` feature,
+/// without it a set value of a field with a pattern is reported as a violation.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Constraints {
+    /// The smallest number which is allowed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// The largest number which is allowed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// A regular expression a string must match somewhere unless it is anchored with `^` and `$`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// The key in the same table which makes this key required when it is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_if: Option<String>,
+}
+
+impl Constraints {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// A key which can be set in a config file
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SchemaField {
+    /// The dotted path of the key where `*` matches any instance of a map
+    pub path: String,
+    /// The Rust type of the field
+    pub ty: String,
+    /// Must the key be set for the config to be complete
+    pub required: bool,
+    /// Other keys which are accepted for this field
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// The [parser](parsers/index.html) of a field which accepts a human friendly format
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// The environment variable the field is read from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
+    /// Is the value of an environment variable decoded with `decode_env_value`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub decode_env: bool,
+    /// The name of the CLI argument the field is read from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arg: Option<String>,
+    /// The possible values of a field whose type derives `ConfigOptValue`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+    #[serde(default, skip_serializing_if = "Constraints::is_empty")]
+    pub constraints: Constraints,
+}
+
+/// The keys a config type accepts which can be exported from one binary and checked against another
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Schema {
+    pub fields: Vec<SchemaField>,
+}
+
+/// A change between two schemas which could break a config file written for the older one
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BreakingChange {
+    /// The key is no longer accepted
+    Removed { path: String },
+    /// The key has a different type
+    TypeChanged {
+        path: String,
+        old: String,
+        new: String,
+    },
+    /// The key was optional and must now be set
+    NowRequired { path: String },
+    /// A new key must be set
+    AddedRequired { path: String },
+}
+
+impl fmt::Display for BreakingChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Removed { path } => write!(f, "`{}` was removed", path),
+            Self::TypeChanged { path, old, new } => {
+                write!(f, "`{}` changed type from `{}` to `{}`", path, old, new)
+            }
+            Self::NowRequired { path } => write!(f, "`{}` is now required", path),
+            Self::AddedRequired { path } => write!(f, "`{}` was added and is required", path),
+        }
+    }
+}
+
+impl Schema {
+    pub fn new(fields: Vec<SchemaField>) -> Self {
+        Self { fields }
+    }
+
+    /// The field which accepts the key `path` by its name or an alias
+    pub fn field(&self, path: &str) -> Option<&SchemaField> {
+        self.fields.iter().find(|f| f.path == path).or_else(|| {
+            self.fields
+                .iter()
+                .find(|f| f.aliases.iter().any(|a| a == path))
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("schema is always serializable")
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// The schema as a [JSON Schema](https://json-schema.org) (draft 7) of the config file
+    ///
+    /// The types, possible values, constraints, and required keys are included so external
+    /// validators and UIs enforce the same rules as the binary. Each `*` of a map becomes
+    /// `additionalProperties`.
+    pub fn to_json_schema(&self) -> String {
+        let mut root = object_schema();
+        root["$schema"] = json!("http://json-schema.org/draft-07/schema#");
+        for field in &self.fields {
+            let segments = field.path.split('.').collect::<Vec<_>>();
+            let (key, parents) = match segments.split_last() {
+                Some(split) => split,
+                None => continue,
+            };
+            let mut object = &mut root;
+            for segment in parents {
+                object = child_schema(object, segment);
+            }
+            // A key of its own for the values of a map (eg each feature flag)
+            if *key == "*" {
+                object["additionalProperties"] = field_schema(field);
+                continue;
+            }
+            object["properties"][*key] = field_schema(field);
+            if field.required {
+                push(&mut object["required"], key);
+            }
+            if let Some(other) = &field.constraints.required_if {
+                push(&mut object["dependencies"][other.as_str()], key);
+            }
+        }
+        serde_json::to_string_pretty(&root).expect("schema is always serializable")
+    }
+
+    /// The changes from `self` to `new` which could break config files written for `self`
+    pub fn breaking_changes(&self, new: &Schema) -> Vec<BreakingChange> {
+        let mut changes = Vec::new();
+        for old in &self.fields {
+            match new.field(&old.path) {
+                None => changes.push(BreakingChange::Removed {
+                    path: old.path.clone(),
+                }),
+                Some(field) => {
+                    if field.ty != old.ty {
+                        changes.push(BreakingChange::TypeChanged {
+                            path: old.path.clone(),
+                            old: old.ty.clone(),
+                            new: field.ty.clone(),
+                        });
+                    }
+                    if field.required && !old.required {
+                        changes.push(BreakingChange::NowRequired {
+                            path: old.path.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        for field in &new.fields {
+            let existed = self.field(&field.path).is_some()
+                || field.aliases.iter().any(|a| self.field(a).is_some());
+            if field.required && !existed {
+                changes.push(BreakingChange::AddedRequired {
+                    path: field.path.clone(),
+                });
+            }
+        }
+        changes
+    }
+}
+
+fn object_schema() -> JsonValue {
+    json!({"type": "object", "properties": {}})
+}
+
+fn child_schema<'a>(object: &'a mut JsonValue, segment: &str) -> &'a mut JsonValue {
+    let child = if segment == "*" {
+        &mut object["additionalProperties"]
+    } else {
+        &mut object["properties"][segment]
+    };
+    if child.is_null() {
+        *child = object_schema();
+    }
+    child
+}
+
+fn push(array: &mut JsonValue, value: &str) {
+    match array {
+        JsonValue::Array(array) => array.push(json!(value)),
+        array => *array = json!([value]),
+    }
+}
+
+fn field_schema(field: &SchemaField) -> JsonValue {
+    let mut schema = kind_schema(&field.kind());
+    // The values and constraints of a list apply to each item
+    let mut item = &mut schema;
+    while item.get("items").is_some() {
+        item = &mut item["items"];
+    }
+    if !field.values.is_empty() {
+        item["enum"] = json!(field.values);
+    }
+    let constraints = &field.constraints;
+    if let Some(min) = constraints.min {
+        item["minimum"] = number(min);
+    }
+    if let Some(max) = constraints.max {
+        item["maximum"] = number(max);
+    }
+    if let Some(pattern) = &constraints.pattern {
+        item["pattern"] = json!(pattern);
+    }
+    schema
+}
+
+// Write whole bounds as integers so they read like the attribute which set them
+fn number(number: f64) -> JsonValue {
+    if number.fract() == 0.0 && number.abs() < 1e15 {
+        json!(number as i64)
+    } else {
+        json!(number)
+    }
+}
+
+fn kind_schema(kind: &ValueKind) -> JsonValue {
+    match kind {
+        ValueKind::String => json!({"type": "string"}),
+        ValueKind::Integer => json!({"type": "integer"}),
+        ValueKind::Float => json!({"type": "number"}),
+        ValueKind::Boolean => json!({"type": "boolean"}),
+        ValueKind::Array(kind) => json!({"type": "array", "items": kind_schema(kind)}),
+        ValueKind::Table => json!({"type": "object"}),
+        ValueKind::Any => json!({}),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(path: &str, ty: &str, required: bool) -> SchemaField {
+        SchemaField {
+            path: String::from(path),
+            ty: String::from(ty),
+            required,
+            aliases: Vec::new(),
+            format: None,
+            env: None,
+            decode_env: false,
+            arg: None,
+            values: Vec::new(),
+            constraints: Constraints::default(),
+        }
+    }
+
+    #[test]
+    fn test_breaking_changes() {
+        let old = Schema::new(vec![
+            field("host", "String", true),
+            field("port", "u16", false),
+            field("timeout", "u64", false),
+            field("name", "String", false),
+        ]);
+        let mut renamed = field("server_name", "String", false);
+        renamed.aliases.push(String::from("name"));
+        let new = Schema::new(vec![
+            field("host", "String", true),
+            field("port", "u16", true),
+            field("timeout", "Duration", false),
+            renamed,
+            field("region", "String", true),
+            field("zone", "String", false),
+        ]);
+        assert_eq!(
+            old.breaking_changes(&new),
+            vec![
+                BreakingChange::NowRequired {
+                    path: String::from("port")
+                },
+                BreakingChange::TypeChanged {
+                    path: String::from("timeout"),
+                    old: String::from("u64"),
+                    new: String::from("Duration"),
+                },
+                BreakingChange::AddedRequired {
+                    path: String::from("region")
+                },
+            ]
+        );
+        assert!(Schema::from_json(&new.to_json()).unwrap() == new);
+        assert!(new.breaking_changes(&new).is_empty());
+    }
+}
@@ -0,0 +1,37 @@
+use std::{any::Any, cell::RefCell, sync::Arc};
+
+thread_local! {
+    static OVERRIDES: RefCell<Vec<Box<dyn Any>>> = RefCell::new(Vec::new());
+}
+
+struct OverrideGuard;
+
+impl Drop for OverrideGuard {
+    fn drop(&mut self) {
+        OVERRIDES.with(|overrides| {
+            overrides.borrow_mut().pop();
+        });
+    }
+}
+
+/// Run `f` with `value` overriding the config of type `T` on the current thread
+///
+/// While `f` runs, `Reloadable::get` and the `current` accessor generated by
+/// `#[configopt(global)]` return `value` instead of the shared config. Overrides can be nested and
+/// are removed when `f` returns or panics.
+pub fn with_override<T: 'static, R>(value: T, f: impl FnOnce() -> R) -> R {
+    OVERRIDES.with(|overrides| overrides.borrow_mut().push(Box::new(Arc::new(value))));
+    let _guard = OverrideGuard;
+    f()
+}
+
+/// Get the innermost override of the config of type `T` on the current thread
+pub fn current_override<T: 'static>() -> Option<Arc<T>> {
+    OVERRIDES.with(|overrides| {
+        overrides
+            .borrow()
+            .iter()
+            .rev()
+            .find_map(|value| value.downcast_ref::<Arc<T>>().cloned())
+    })
+}
@@ -0,0 +1,91 @@
+use crate::error::{Error, Result};
+use std::{
+    fmt, fs,
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    path::Path,
+    str::FromStr,
+};
+
+/// The text shown in place of the value of a `#[configopt(secret)]` field
+pub const REDACTED: &str = "<redacted>";
+
+/// `Debug` output for a secret which shows if it is set without revealing its value
+///
+/// It is used by the `Debug` impl generated for the `configopt` type. The original type derives its
+/// own `Debug` which `configopt` cannot change so wrap its secret fields in a type which redacts
+/// them or implement `Debug` by hand with `Redacted`.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Redacted(bool);
+
+impl Redacted {
+    pub fn new(is_set: bool) -> Self {
+        Self(is_set)
+    }
+}
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 {
+            write!(f, "Some({})", REDACTED)
+        } else {
+            write!(f, "None")
+        }
+    }
+}
+
+/// Read and parse the value of a secret from a file
+#[doc(hidden)]
+pub fn read_secret_file<T>(path: &Path) -> Result<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    // A missing secret is an error even though a missing config file is not
+    let contents = fs::read_to_string(path).map_err(|e| {
+        Error::ConfigFile(
+            path.to_path_buf(),
+            IoError::new(
+                IoErrorKind::InvalidData,
+                format!("failed to read secret file, err: {}", e),
+            ),
+        )
+    })?;
+    // Secret files commonly end with a newline which is not part of the secret
+    contents
+        .trim_end_matches(|c| c == '\n' || c == '\r')
+        .parse()
+        .map_err(|e: T::Err| {
+            Error::ConfigFile(
+                path.to_path_buf(),
+                IoError::new(IoErrorKind::InvalidData, e.to_string()),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn redacted_debug() {
+        assert_eq!(format!("{:?}", Redacted::new(true)), "Some(<redacted>)");
+        assert_eq!(format!("{:?}", Redacted::new(false)), "None");
+    }
+
+    #[test]
+    fn read_secret_file_trims_newline() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "hunter2").unwrap();
+        assert_eq!(
+            read_secret_file::<String>(file.path()).unwrap(),
+            String::from("hunter2")
+        );
+        assert!(read_secret_file::<u16>(file.path()).is_err());
+        let missing = file.path().with_extension("missing");
+        assert!(!read_secret_file::<String>(&missing)
+            .unwrap_err()
+            .config_file_not_found());
+    }
+}
@@ -0,0 +1,92 @@
+//! Register a config as a shaku component
+//!
+//! [`ConfigModule`](struct.ConfigModule.html) is a module with the component
+//! `dyn ConfigInterface<T>` which is built from a `Reloadable<T>`. Use it as a submodule so the
+//! config must be given to the builder of the app module:
+//!
+//! ```ignore
+//! module! {
+//!     AppModule {
+//!         components = [],
+//!         providers = [],
+//!
+//!         use ConfigModule<AppConfig> {
+//!             components = [dyn ConfigInterface<AppConfig>],
+//!             providers = []
+//!         }
+//!     }
+//! }
+//!
+//! let module = AppModule::builder(Arc::new(ConfigModule::new(config.clone()))).build();
+//! let config: &dyn ConfigInterface<AppConfig> = module.resolve_ref();
+//! ```
+//!
+//! The module shares the handle so a reload through any clone is seen by every component which
+//! depends on the config.
+
+use crate::Reloadable;
+use shaku::{HasComponent, Interface, Module, ModuleBuildContext, ModuleBuilder};
+use std::sync::Arc;
+
+/// The interface of a config registered in a shaku module
+pub trait ConfigInterface<T: Send + Sync + 'static>: Interface {
+    /// A snapshot of the current value
+    fn get(&self) -> Arc<T>;
+
+    /// The handle shared with the module
+    fn reloadable(&self) -> Reloadable<T>;
+}
+
+impl<T: Send + Sync + 'static> ConfigInterface<T> for Reloadable<T> {
+    fn get(&self) -> Arc<T> {
+        Reloadable::get(self)
+    }
+
+    fn reloadable(&self) -> Reloadable<T> {
+        self.clone()
+    }
+}
+
+/// A shaku module providing `dyn ConfigInterface<T>` from a `Reloadable<T>`
+///
+/// The submodules of the module are the `Reloadable<T>` so
+/// `ModuleBuilder::with_submodules(config)` builds it too.
+pub struct ConfigModule<T: Send + Sync + 'static> {
+    config: Arc<dyn ConfigInterface<T>>,
+}
+
+impl<T: Send + Sync + 'static> ConfigModule<T> {
+    pub fn new(config: Reloadable<T>) -> Self {
+        ModuleBuilder::with_submodules(config).build()
+    }
+}
+
+impl<T: Send + Sync + 'static> From<Reloadable<T>> for ConfigModule<T> {
+    fn from(config: Reloadable<T>) -> Self {
+        Self::new(config)
+    }
+}
+
+impl<T: Send + Sync + 'static> Module for ConfigModule<T> {
+    type Submodules = Reloadable<T>;
+
+    fn build(mut context: ModuleBuildContext<Self>) -> Self {
+        Self {
+            config: Self::build_component(&mut context),
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> HasComponent<dyn ConfigInterface<T>> for ConfigModule<T> {
+    fn build_component(context: &mut ModuleBuildContext<Self>) -> Arc<dyn ConfigInterface<T>> {
+        Arc::new(context.submodules().clone())
+    }
+
+    fn resolve(&self) -> Arc<dyn ConfigInterface<T>> {
+        Arc::clone(&self.config)
+    }
+
+    fn resolve_ref(&self) -> &dyn ConfigInterface<T> {
+        &*self.config
+    }
+}
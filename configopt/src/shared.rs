@@ -0,0 +1,83 @@
+// A config file shared by several binaries has a table for the keys each binary reads alone. See
+// `ConfigOptType::binary`.
+
+use crate::profile;
+use toml::value::{Table, Value};
+
+/// The keys of `binary` in a config file shared with other binaries
+///
+/// The keys are those of the table of `binary`. Only the tables of the `sections` are read from
+/// the top level and the table of `binary` is merged over them. Every other key at the top level
+/// belongs to another binary.
+pub(crate) fn select_binary(
+    mut table: Table,
+    binary: &str,
+    sections: &[&str],
+) -> Result<Table, String> {
+    let mut selected = sections
+        .iter()
+        .filter_map(|section| Some((String::from(*section), table.remove(*section)?)))
+        .collect::<Table>();
+    match table.remove(binary) {
+        Some(Value::Table(own)) => {
+            profile::merge(&mut selected, own);
+            Ok(selected)
+        }
+        Some(_) => Err(format!("`{}` must be a table", binary)),
+        None => Ok(selected),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(s: &str) -> Table {
+        match s.parse::<Value>().unwrap() {
+            Value::Table(table) => table,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_select_binary() {
+        let shared = table(
+            r#"
+            port = 80
+            [common]
+            log_level = "info"
+            [server]
+            port = 8080
+            worker = "pool"
+            [server.common]
+            log_level = "debug"
+            [worker]
+            threads = 4
+            "#,
+        );
+        // A field may be named like another binary
+        assert_eq!(
+            select_binary(shared.clone(), "server", &["common"]).unwrap(),
+            table(
+                r#"
+                port = 8080
+                worker = "pool"
+                [common]
+                log_level = "debug"
+                "#
+            )
+        );
+        // Keys at the top level other than the sections are not read
+        assert_eq!(
+            select_binary(shared, "worker", &["common"]).unwrap(),
+            table(
+                r#"
+                threads = 4
+                [common]
+                log_level = "info"
+                "#
+            )
+        );
+        assert!(select_binary(table("server = 1"), "server", &[]).is_err());
+    }
+}
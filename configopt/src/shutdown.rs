@@ -0,0 +1,83 @@
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Default)]
+struct State {
+    cancelled: bool,
+    children: Vec<ShutdownToken>,
+}
+
+/// A cheaply cloneable flag used to ask background reload work to stop
+///
+/// Every clone shares the same flag so one token can be handed to each part of the dynamic config
+/// subsystem and cancelled once during service shutdown.
+#[derive(Clone, Debug, Default)]
+pub struct ShutdownToken {
+    inner: Arc<(Mutex<State>, Condvar)>,
+}
+
+impl ShutdownToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask everything holding a clone of this token or one of its children to stop, waking any
+    /// waiting threads
+    pub fn cancel(&self) {
+        let (state, condvar) = &*self.inner;
+        let children = {
+            let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+            if state.cancelled {
+                return;
+            }
+            state.cancelled = true;
+            std::mem::take(&mut state.children)
+        };
+        condvar.notify_all();
+        for child in children {
+            child.cancel();
+        }
+    }
+
+    /// A token which is cancelled with this token but which can also be cancelled on its own
+    pub fn child(&self) -> ShutdownToken {
+        let child = ShutdownToken::new();
+        let mut state = self.inner.0.lock().unwrap_or_else(|e| e.into_inner());
+        if state.cancelled {
+            drop(state);
+            child.cancel();
+        } else {
+            state.children.retain(|c| !c.is_cancelled());
+            state.children.push(child.clone());
+        }
+        child
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner
+            .0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .cancelled
+    }
+
+    /// Wait for `timeout` or until the token is cancelled returning if it was cancelled
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let (state, condvar) = &*self.inner;
+        let deadline = Instant::now() + timeout;
+        let mut guard = state.lock().unwrap_or_else(|e| e.into_inner());
+        while !guard.cancelled {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            guard = condvar
+                .wait_timeout(guard, deadline - now)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        guard.cancelled
+    }
+}
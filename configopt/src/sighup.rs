@@ -0,0 +1,117 @@
+use crate::{Reloadable, Result, ShutdownToken, WatcherHandle};
+use signal_hook::SigId;
+use std::{
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+/// How often the reload thread checks if a signal arrived
+const POLL: Duration = Duration::from_millis(50);
+
+/// Reload a config whenever the process receives `SIGHUP`
+///
+/// This is the conventional `kill -HUP <pid>` reload of a daemon. `load` re-reads every source
+/// of the config, for example with `ConfigOpt::try_from_args_with_configopt`. A new value replaces
+/// the value of [`config`](#method.config) and is sent to [`changes`](#method.changes). If `load`
+/// fails the previous value is kept and the error is available from
+/// [`last_error`](#method.last_error).
+///
+/// Reloading stops when `shutdown` is cancelled or this is dropped which also removes the signal
+/// handler.
+pub struct SighupReload<T> {
+    current: Reloadable<T>,
+    last_error: Arc<Mutex<Option<String>>>,
+    requested: Arc<AtomicBool>,
+    changes: Receiver<Arc<T>>,
+    signal: SigId,
+    // Dropped before the signal handler is removed
+    watcher: Option<WatcherHandle>,
+}
+
+impl<T: Send + Sync + 'static> SighupReload<T> {
+    /// Install the signal handler and replace the value of `current` with `load` on each signal
+    pub fn start(
+        current: Reloadable<T>,
+        shutdown: ShutdownToken,
+        mut load: impl FnMut() -> Result<T> + Send + 'static,
+    ) -> io::Result<Self> {
+        // Dropping the watcher cancels its token so give it a child which leaves `shutdown` alone
+        let shutdown = shutdown.child();
+        let requested = Arc::new(AtomicBool::new(false));
+        let signal = signal_hook::flag::register(signal_hook::SIGHUP, Arc::clone(&requested))?;
+        let last_error = Arc::new(Mutex::new(None));
+        let (sender, changes) = mpsc::channel();
+        let thread = {
+            let current = current.clone();
+            let last_error = Arc::clone(&last_error);
+            let requested = Arc::clone(&requested);
+            let token = shutdown.clone();
+            thread::spawn(move || {
+                while !token.wait_timeout(POLL) {
+                    if !requested.swap(false, Ordering::SeqCst) {
+                        continue;
+                    }
+                    let error = match load() {
+                        Ok(value) => {
+                            current.replace(value);
+                            // No one listening for changes is fine
+                            sender.send(current.get()).ok();
+                            None
+                        }
+                        Err(e) => {
+                            writeln!(&mut io::stderr(), "warning: reload failed: {}", e).ok();
+                            Some(e.to_string())
+                        }
+                    };
+                    *last_error.lock().unwrap_or_else(|e| e.into_inner()) = error;
+                }
+            })
+        };
+        Ok(Self {
+            current,
+            last_error,
+            requested,
+            changes,
+            signal,
+            watcher: Some(WatcherHandle::new(shutdown, thread)),
+        })
+    }
+}
+
+impl<T> SighupReload<T> {
+    /// The shared handle to the current config
+    pub fn config(&self) -> Reloadable<T> {
+        self.current.clone()
+    }
+
+    /// Each config which replaced the previous one in the order they were loaded
+    pub fn changes(&self) -> &Receiver<Arc<T>> {
+        &self.changes
+    }
+
+    /// The error from the last reload if it failed
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Reload as if `SIGHUP` was received, for example from an admin endpoint
+    pub fn trigger(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+    }
+}
+
+impl<T> Drop for SighupReload<T> {
+    fn drop(&mut self) {
+        self.watcher = None;
+        signal_hook::unregister(self.signal);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{Clock, ConfigDiff, FileSystem, FileWatcher, ManualClock, MemoryFileSystem, Result};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+type Load<T> = Box<dyn Fn(&dyn FileSystem, &[PathBuf]) -> Result<T>>;
+type Diff<T> = Box<dyn Fn(&T, &T) -> ConfigDiff>;
+
+struct Applier<T> {
+    name: String,
+    path: String,
+    apply: Box<dyn FnMut(&T, &ConfigDiff)>,
+}
+
+/// What happened when a change to the config files was detected
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReloadOutcome<T> {
+    /// The config was loaded and the appliers watching the changed fields were invoked in the
+    /// order they were added
+    Reloaded {
+        config: T,
+        diff: ConfigDiff,
+        applied: Vec<String>,
+    },
+    /// The config failed to load so the previous config was kept
+    Failed { error: String },
+}
+
+/// A single reload of a [`ReloadSimulation`](struct.ReloadSimulation.html)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReloadStep<T> {
+    /// The time of the simulated clock when the change was detected
+    pub at: SystemTime,
+    pub changed_files: Vec<PathBuf>,
+    pub outcome: ReloadOutcome<T>,
+}
+
+/// A harness which scripts changes to config files and records how a reloading app reacts
+///
+/// Files live in a [`MemoryFileSystem`](struct.MemoryFileSystem.html) and time only moves with
+/// [`advance`](#method.advance) so the reload reaction logic of an app can be tested without real
+/// files or sleeps.
+pub struct ReloadSimulation<T> {
+    clock: Arc<ManualClock>,
+    fs: Arc<MemoryFileSystem>,
+    paths: Vec<PathBuf>,
+    watcher: Option<FileWatcher>,
+    load: Load<T>,
+    diff: Diff<T>,
+    appliers: Vec<Applier<T>>,
+    current: Option<T>,
+    steps: Vec<ReloadStep<T>>,
+}
+
+impl<T: Clone> ReloadSimulation<T> {
+    /// Simulate an app which loads its config from the files with `load` and compares configs with
+    /// `diff`
+    pub fn new(
+        load: impl Fn(&dyn FileSystem, &[PathBuf]) -> Result<T> + 'static,
+        diff: impl Fn(&T, &T) -> ConfigDiff + 'static,
+    ) -> Self {
+        let clock = Arc::new(ManualClock::default());
+        Self {
+            fs: Arc::new(MemoryFileSystem::new(clock.clone())),
+            clock,
+            paths: Vec::new(),
+            watcher: None,
+            load: Box::new(load),
+            diff: Box::new(diff),
+            appliers: Vec::new(),
+            current: None,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Add a config file which is loaded in the order it was added
+    pub fn file(mut self, path: impl Into<PathBuf>, contents: &str) -> Self {
+        let path = path.into();
+        self.fs
+            .write(&path, contents)
+            .expect("memory filesystem write to succeed");
+        self.paths.push(path);
+        self
+    }
+
+    /// Invoke `apply` on every reload which changes the field at `path` or any field nested under
+    /// it
+    ///
+    /// An empty `path` watches every field.
+    pub fn on_change(
+        mut self,
+        name: impl Into<String>,
+        path: impl Into<String>,
+        apply: impl FnMut(&T, &ConfigDiff) + 'static,
+    ) -> Self {
+        self.appliers.push(Applier {
+            name: name.into(),
+            path: path.into(),
+            apply: Box::new(apply),
+        });
+        self
+    }
+
+    /// Load the initial config and start watching the files
+    pub fn start(&mut self) -> Result<&T> {
+        let config = (self.load)(&*self.fs, &self.paths)?;
+        self.watcher = Some(FileWatcher::with(
+            &self.paths,
+            self.clock.clone(),
+            self.fs.clone(),
+        ));
+        self.current = Some(config);
+        Ok(self.current.as_ref().expect("config to be loaded"))
+    }
+
+    /// Change the contents of a config file
+    ///
+    /// The change is written one nanosecond after the current time so it is never hidden by a
+    /// previous change.
+    pub fn write(&mut self, path: impl AsRef<Path>, contents: &str) -> &mut Self {
+        self.clock.advance(Duration::from_nanos(1));
+        self.fs
+            .write(path.as_ref(), contents)
+            .expect("memory filesystem write to succeed");
+        self
+    }
+
+    /// Remove a config file
+    pub fn remove(&mut self, path: impl AsRef<Path>) -> &mut Self {
+        self.clock.advance(Duration::from_nanos(1));
+        self.fs.remove(path);
+        self
+    }
+
+    /// Move time forward by `duration` then poll the files and reload if any changed
+    pub fn advance(&mut self, duration: Duration) -> Option<&ReloadStep<T>> {
+        self.clock.advance(duration);
+        let changed_files = self
+            .watcher
+            .as_mut()
+            .expect("simulation to be started")
+            .poll();
+        if changed_files.is_empty() {
+            return None;
+        }
+        let outcome = match (self.load)(&*self.fs, &self.paths) {
+            Ok(config) => {
+                let diff = match &self.current {
+                    Some(current) => (self.diff)(current, &config),
+                    None => ConfigDiff::default(),
+                };
+                let mut applied = Vec::new();
+                for applier in &mut self.appliers {
+                    if !diff.is_empty() && (applier.path.is_empty() || diff.contains(&applier.path))
+                    {
+                        (applier.apply)(&config, &diff);
+                        applied.push(applier.name.clone());
+                    }
+                }
+                self.current = Some(config.clone());
+                ReloadOutcome::Reloaded {
+                    config,
+                    diff,
+                    applied,
+                }
+            }
+            Err(e) => ReloadOutcome::Failed {
+                error: e.to_string(),
+            },
+        };
+        self.steps.push(ReloadStep {
+            at: self.clock.now(),
+            changed_files,
+            outcome,
+        });
+        self.steps.last()
+    }
+
+    /// The config of the last successful load
+    pub fn current(&self) -> Option<&T> {
+        self.current.as_ref()
+    }
+
+    /// Every reload in the order they happened
+    pub fn steps(&self) -> &[ReloadStep<T>] {
+        &self.steps
+    }
+
+    pub fn clock(&self) -> &Arc<ManualClock> {
+        &self.clock
+    }
+
+    pub fn fs(&self) -> &Arc<MemoryFileSystem> {
+        &self.fs
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{Alias, Error, Result, UnknownKey};
+use serde::de::DeserializeOwned;
+use std::{
+    future::Future,
+    io,
+    path::{Path, PathBuf},
+    pin::Pin,
+};
+
+/// The future returned by [`AsyncSource::read`](trait.AsyncSource.html#tymethod.read)
+pub type SourceFuture<'a> = Pin<Box<dyn Future<Output = io::Result<String>> + Send + 'a>>;
+
+/// A TOML config document which is read asynchronously
+///
+/// Implement this to hydrate a config from a remote store without blocking the runtime.
+pub trait AsyncSource: Send + Sync {
+    /// Where the document comes from used in errors and load traces
+    fn name(&self) -> String;
+
+    /// Read the contents of the document
+    fn read(&self) -> SourceFuture<'_>;
+}
+
+/// A config file read with `tokio::fs`
+#[derive(Clone, Debug)]
+pub struct FileSource {
+    path: PathBuf,
+}
+
+impl FileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsyncSource for FileSource {
+    fn name(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+
+    fn read(&self) -> SourceFuture<'_> {
+        Box::pin(tokio::fs::read_to_string(&self.path))
+    }
+}
+
+/// A config document fetched with an HTTP `GET` request
+#[cfg(feature = "http")]
+#[derive(Clone, Debug)]
+pub struct UrlSource {
+    url: String,
+}
+
+#[cfg(feature = "http")]
+impl UrlSource {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+#[cfg(feature = "http")]
+impl AsyncSource for UrlSource {
+    fn name(&self) -> String {
+        self.url.clone()
+    }
+
+    fn read(&self) -> SourceFuture<'_> {
+        let to_io = |e: reqwest::Error| io::Error::new(io::ErrorKind::Other, e);
+        Box::pin(async move {
+            reqwest::get(&self.url)
+                .await
+                .and_then(reqwest::Response::error_for_status)
+                .map_err(to_io)?
+                .text()
+                .await
+                .map_err(to_io)
+        })
+    }
+}
+
+/// Read and parse a TOML config document from `source` collecting any keys which are not
+/// recognized
+///
+/// Only reading the document is asynchronous. The `include` key is resolved relative to the source
+/// name so it is only supported for file sources.
+pub async fn from_source_async<T: DeserializeOwned>(
+    source: &dyn AsyncSource,
+    aliases: &[Alias],
+    unknown_keys: &mut Vec<UnknownKey>,
+) -> Result<T> {
+    let path = PathBuf::from(source.name());
+    let contents = source
+        .read()
+        .await
+        .map_err(|e| Error::ConfigFile(path.clone(), e))?;
+    crate::from_toml_str_collecting_unknown(&contents, &path, &[], aliases, unknown_keys)
+}
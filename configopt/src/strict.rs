@@ -0,0 +1,166 @@
+use crate::{Error, LoadTrace, Result, Schema};
+use std::{
+    fmt,
+    io::{self, Write},
+    iter,
+    path::PathBuf,
+};
+
+/// A key in a config file which did not match any field
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnknownKey {
+    /// The dotted path of the key
+    pub key: String,
+    pub path: PathBuf,
+}
+
+impl UnknownKey {
+    /// Write the warning to `stderr`
+    pub fn warn(&self) {
+        LoadTrace::warn(|| self.to_string());
+        writeln!(&mut io::stderr(), "{}", self).ok();
+    }
+}
+
+impl fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "warning: config file '{}' has unknown key `{}`",
+            self.path.to_string_lossy(),
+            self.key
+        )
+    }
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if a == *b {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(current)
+            };
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+/// The known key closest to `unknown` if any are close enough to be a likely typo
+fn did_you_mean<'a>(unknown: &str, known: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let threshold = 2.max(unknown.chars().count() / 3);
+    known
+        .map(|k| (levenshtein(unknown, k), k))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, k)| k)
+}
+
+/// The keys `schema` accepts in the table at `parent` where `*` matches any key
+fn known_keys<'a>(schema: &'a Schema, parent: &[&str]) -> Vec<&'a str> {
+    let mut known = Vec::new();
+    let paths = schema
+        .fields
+        .iter()
+        .flat_map(|field| iter::once(&field.path).chain(&field.aliases));
+    for path in paths {
+        let components = path.split('.').collect::<Vec<_>>();
+        if components.len() <= parent.len() {
+            continue;
+        }
+        let matches = components
+            .iter()
+            .zip(parent)
+            .all(|(component, key)| *component == "*" || component == key);
+        let key = components[parent.len()];
+        if matches && key != "*" && !known.contains(&key) {
+            known.push(key);
+        }
+    }
+    known
+}
+
+/// Fail with the first of `unknown_keys` suggesting the nearest key `schema` accepts in the same
+/// table
+///
+/// This is how `#[configopt(strict)]` rejects unknown keys. Because it works on the keys collected
+/// while deserializing the whole document it applies to the keys of flattened types, sections,
+/// and maps the same way it applies to top level keys.
+pub fn reject_unknown_keys(unknown_keys: &[UnknownKey], schema: &Schema) -> Result<()> {
+    let unknown = match unknown_keys.first() {
+        Some(unknown) => unknown,
+        None => return Ok(()),
+    };
+    let components = unknown.key.split('.').collect::<Vec<_>>();
+    let (last, parent) = components
+        .split_last()
+        .expect("split to return at least one component");
+    let suggestion = did_you_mean(last, known_keys(schema, parent).into_iter()).map(|known| {
+        let mut suggestion = parent.to_vec();
+        suggestion.push(known);
+        suggestion.join(".")
+    });
+    Err(Error::UnknownKey {
+        key: unknown.clone(),
+        suggestion,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SchemaField;
+    use std::path::Path;
+
+    fn field(path: &str) -> SchemaField {
+        SchemaField {
+            path: String::from(path),
+            ty: String::from("u16"),
+            required: false,
+            aliases: Vec::new(),
+            format: None,
+            env: None,
+            decode_env: false,
+            arg: None,
+            values: Vec::new(),
+            constraints: Default::default(),
+        }
+    }
+
+    fn suggestion(key: &str) -> Option<String> {
+        let schema = Schema::new(vec![
+            field("host"),
+            field("port"),
+            field("server.host"),
+            field("server.port"),
+            field("upstreams.*.port"),
+        ]);
+        let unknown_keys = [UnknownKey {
+            key: String::from(key),
+            path: Path::new("/config.toml").to_path_buf(),
+        }];
+        match reject_unknown_keys(&unknown_keys, &schema) {
+            Err(Error::UnknownKey { suggestion, .. }) => suggestion,
+            _ => panic!("expected an unknown key error"),
+        }
+    }
+
+    #[test]
+    fn suggest_known_key() {
+        assert_eq!(suggestion("prot"), Some(String::from("port")));
+        assert_eq!(suggestion("server.hots"), Some(String::from("server.host")));
+        assert_eq!(suggestion("servr"), Some(String::from("server")));
+        assert_eq!(
+            suggestion("upstreams.a.prot"),
+            Some(String::from("upstreams.a.port"))
+        );
+        assert_eq!(suggestion("database"), None);
+        assert!(reject_unknown_keys(&[], &Schema::default()).is_ok());
+    }
+}
@@ -0,0 +1,10 @@
+use std::borrow::Cow;
+
+/// Parse a `Cow<'static, str>` argument
+///
+/// `Cow` does not implement `FromStr` so use this as its parser with
+/// `#[structopt(parse(from_str = configopt::cow_from_str))]`. Small string types (eg `SmolStr` or
+/// `CompactString`) implement `FromStr` and need no parser.
+pub fn cow_from_str(s: &str) -> Cow<'static, str> {
+    Cow::Owned(String::from(s))
+}
@@ -0,0 +1,113 @@
+use std::ffi::OsString;
+use toml::value::{Table, Value};
+
+/// A subcommand which a config file can select by its CLI name
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SubcommandVariant {
+    /// The CLI name of the subcommand
+    pub name: &'static str,
+    /// The name of the enum variant the subcommand is deserialized as
+    pub variant: &'static str,
+    /// Does the subcommand take arguments which are set in a table named after the subcommand
+    pub has_table: bool,
+}
+
+/// Rewrite `<key> = "<name>"` and its `[<name>]` table into the variant of the subcommand field
+/// `key`
+///
+/// A `key` which is already a table or does not name one of `variants` is left for the
+/// deserializer to accept or report.
+pub fn select_subcommand(table: &mut Table, key: &str, variants: &[SubcommandVariant]) {
+    let variant = match table.get(key) {
+        Some(Value::String(name)) => match variants.iter().find(|v| v.name == name) {
+            Some(variant) => variant,
+            None => return,
+        },
+        _ => return,
+    };
+    let selected = if variant.has_table {
+        let args = match table.remove(variant.name) {
+            Some(args) => args,
+            None => Value::Table(Table::new()),
+        };
+        let mut selected = Table::new();
+        selected.insert(String::from(variant.variant), args);
+        Value::Table(selected)
+    } else {
+        Value::String(String::from(variant.variant))
+    };
+    table.insert(String::from(key), selected);
+}
+
+/// Add the subcommand selected by a config file to `args` so the CLI parses it as if it was given
+pub(crate) fn append_subcommand(mut args: Vec<OsString>, path: &[&str]) -> Vec<OsString> {
+    // Anything after `--` is a trailing argument so the subcommand goes before it
+    let at = args
+        .iter()
+        .position(|arg| arg == "--")
+        .unwrap_or_else(|| args.len());
+    for (i, name) in path.iter().enumerate() {
+        args.insert(at + i, OsString::from(name));
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VARIANTS: &[SubcommandVariant] = &[
+        SubcommandVariant {
+            name: "serve",
+            variant: "Serve",
+            has_table: true,
+        },
+        SubcommandVariant {
+            name: "check",
+            variant: "Check",
+            has_table: false,
+        },
+    ];
+
+    fn table(toml: &str) -> Table {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn select(toml: &str) -> Table {
+        let mut table = table(toml);
+        select_subcommand(&mut table, "command", VARIANTS);
+        table
+    }
+
+    #[test]
+    fn test_select_subcommand() {
+        assert_eq!(
+            select("command = \"serve\"\nport = 1\n[serve]\nhost = \"a\""),
+            table("port = 1\n[command.Serve]\nhost = \"a\"")
+        );
+        assert_eq!(select("command = \"serve\""), table("[command.Serve]"));
+        assert_eq!(select("command = \"check\""), table("command = \"Check\""));
+        // Unknown names and the variant form are left as they are
+        assert_eq!(
+            select("command = \"other\"\n[serve]"),
+            table("command = \"other\"\n[serve]")
+        );
+        assert_eq!(
+            select("[command.Serve]\n[serve]"),
+            table("[command.Serve]\n[serve]")
+        );
+    }
+
+    #[test]
+    fn test_append_subcommand() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(
+            append_subcommand(args(&["app", "-v"]), &["db", "migrate"]),
+            args(&["app", "-v", "db", "migrate"])
+        );
+        assert_eq!(
+            append_subcommand(args(&["app", "--", "-v"]), &["serve"]),
+            args(&["app", "serve", "--", "-v"])
+        );
+    }
+}
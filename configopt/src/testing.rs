@@ -0,0 +1,101 @@
+//! Helpers which check that a config survives a round trip through config files
+//!
+//! A value is converted into its `ConfigOptType`, written with `toml_values`, rendered in a
+//! [`ConfigFormat`](../enum.ConfigFormat.html), then parsed again with
+//! `ConfigOpt::try_from_iter_with_files`. This exercises the `serde` view of the type when the
+//! config is read and the `structopt` view when its values become the default values of the CLI
+//! arguments, so attributes which disagree between the two views show up as an error or a
+//! different value.
+//!
+//! Secrets are never written to config files so types with secret fields do not round trip.
+
+use crate::{config_format, ConfigFormat, ConfigOpt, ConfigOptType, Result};
+use std::{fmt::Debug, path::Path};
+
+const BIN_NAME: &str = "round-trip";
+
+/// The formats this build can read and write
+pub fn formats() -> Vec<ConfigFormat> {
+    #[allow(unused_mut)]
+    let mut formats = vec![ConfigFormat::Toml, ConfigFormat::Json];
+    #[cfg(feature = "yaml")]
+    formats.push(ConfigFormat::Yaml);
+    formats
+}
+
+/// Write `value` as a config document in `format`
+pub fn to_config_str<T>(value: &T, format: ConfigFormat) -> Result<String>
+where
+    T: ConfigOpt + Clone,
+    T::ConfigOptType: From<T>,
+{
+    let path = Path::new("<memory>");
+    let config = T::ConfigOptType::from(value.clone());
+    format.render(&config_format::values(&config, path)?, path)
+}
+
+/// Write `value` as a config document in `format` and parse it again
+pub fn round_trip<T>(value: &T, format: ConfigFormat) -> Result<T>
+where
+    T: ConfigOpt + Clone,
+    T::ConfigOptType: From<T>,
+{
+    let contents = to_config_str(value, format)?;
+    T::try_from_iter_with_files(&[BIN_NAME], &[(format, contents.as_str())])
+}
+
+/// Assert that `value` is unchanged by a round trip through each of the [`formats`](fn.formats.html)
+pub fn assert_round_trip<T>(value: &T)
+where
+    T: ConfigOpt + Clone + PartialEq + Debug,
+    T::ConfigOptType: From<T>,
+{
+    for format in formats() {
+        let contents = to_config_str(value, format)
+            .unwrap_or_else(|e| panic!("failed to write {:?} as {:?}: {}", value, format, e));
+        match T::try_from_iter_with_files(&[BIN_NAME], &[(format, contents.as_str())]) {
+            Ok(parsed) => assert_eq!(
+                &parsed, value,
+                "{:?} changed in a round trip through\n{}",
+                format, contents
+            ),
+            Err(e) => panic!(
+                "failed to parse {:?} after a round trip through\n{}\n{}",
+                value, contents, e
+            ),
+        }
+    }
+}
+
+/// Assert that every value generated by `strategy` survives a round trip
+///
+/// Use a strategy from `proptest-derive` or the
+/// [`Arbitrary`](https://docs.rs/proptest/*/proptest/arbitrary/trait.Arbitrary.html)
+/// implementation of the type. Failures are shrunk to a minimal value like any other property
+/// test.
+#[cfg(feature = "proptest")]
+pub fn check_round_trips<T, S>(strategy: S)
+where
+    T: ConfigOpt + Clone + PartialEq + Debug,
+    T::ConfigOptType: From<T>,
+    S: proptest::strategy::Strategy<Value = T>,
+{
+    use proptest::test_runner::{TestCaseError, TestRunner};
+
+    let result = TestRunner::default().run(&strategy, |value| {
+        for format in formats() {
+            let parsed = round_trip(&value, format)
+                .map_err(|e| TestCaseError::fail(format!("{:?}: {}", format, e)))?;
+            if parsed != value {
+                return Err(TestCaseError::fail(format!(
+                    "{:?} changed {:?} into {:?}",
+                    format, value, parsed
+                )));
+            }
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        panic!("{}", e);
+    }
+}
@@ -0,0 +1,185 @@
+//! Rebuild a `rustls::ServerConfig` whenever the certificate or key referenced by a
+//! [`TlsConfig`](struct.TlsConfig.html) block changes
+use crate::{ConfigOpt, FileWatcher, ShutdownToken, WatcherHandle};
+use arc_swap::ArcSwap;
+use rustls::{internal::pemfile, Certificate, NoClientAuth, PrivateKey, ServerConfig};
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use structopt::StructOpt;
+
+/// The certificate and key an app serves TLS with
+///
+/// Flatten this into the config of an app to get the `--tls-cert` and `--tls-key` arguments and a
+/// `[tls]` table in its config files.
+#[derive(ConfigOpt, StructOpt, Clone, Debug, PartialEq)]
+#[configopt(derive(Clone, Debug, PartialEq))]
+pub struct TlsConfig {
+    /// The PEM file of the certificate chain
+    #[structopt(long = "tls-cert")]
+    pub cert: PathBuf,
+    /// The PEM file of the private key in PKCS8 or RSA format
+    #[structopt(long = "tls-key")]
+    pub key: PathBuf,
+}
+
+/// An error building a `rustls::ServerConfig` from a `TlsConfig`
+#[derive(Debug)]
+pub enum TlsError {
+    Read(PathBuf, io::Error),
+    /// The file does not contain any PEM encoded certificates
+    NoCertificates(PathBuf),
+    /// The file does not contain a PEM encoded private key
+    NoPrivateKey(PathBuf),
+    /// `rustls` rejected the certificate or key
+    Invalid(String),
+}
+
+impl fmt::Display for TlsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(path, e) => write!(
+                f,
+                "Failed to read TLS file '{}', err: {}",
+                path.to_string_lossy(),
+                e
+            ),
+            Self::NoCertificates(path) => write!(
+                f,
+                "TLS file '{}' does not contain any certificates",
+                path.to_string_lossy()
+            ),
+            Self::NoPrivateKey(path) => write!(
+                f,
+                "TLS file '{}' does not contain a private key",
+                path.to_string_lossy()
+            ),
+            Self::Invalid(e) => write!(f, "Invalid TLS certificate or key, err: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TlsError {}
+
+fn reader(path: &Path) -> Result<BufReader<File>, TlsError> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| TlsError::Read(path.to_path_buf(), e))
+}
+
+fn certs(path: &Path) -> Result<Vec<Certificate>, TlsError> {
+    match pemfile::certs(&mut reader(path)?) {
+        Ok(certs) if !certs.is_empty() => Ok(certs),
+        _ => Err(TlsError::NoCertificates(path.to_path_buf())),
+    }
+}
+
+fn private_key(path: &Path) -> Result<PrivateKey, TlsError> {
+    let mut keys = pemfile::pkcs8_private_keys(&mut reader(path)?).unwrap_or_default();
+    if keys.is_empty() {
+        keys = pemfile::rsa_private_keys(&mut reader(path)?).unwrap_or_default();
+    }
+    keys.into_iter()
+        .next()
+        .ok_or_else(|| TlsError::NoPrivateKey(path.to_path_buf()))
+}
+
+/// Build a `rustls::ServerConfig` without client authentication from the files of `config`
+pub fn server_config(config: &TlsConfig) -> Result<ServerConfig, TlsError> {
+    let certs = certs(&config.cert)?;
+    let key = private_key(&config.key)?;
+    let mut server_config = ServerConfig::new(NoClientAuth::new());
+    server_config
+        .set_single_cert(certs, key)
+        .map_err(|e| TlsError::Invalid(e.to_string()))?;
+    Ok(server_config)
+}
+
+/// A `rustls::ServerConfig` which is rebuilt whenever its certificate or key file changes
+///
+/// Acceptors should `load_full` the config from [`server_config`](#method.server_config) for each
+/// new connection so they pick up rotated certificates. If a changed file cannot be used the
+/// previous config is kept and the error is available from [`last_error`](#method.last_error).
+pub struct TlsRotation {
+    current: Arc<ArcSwap<ServerConfig>>,
+    last_error: Arc<Mutex<Option<String>>>,
+    interval: Duration,
+    shutdown: ShutdownToken,
+    watcher: Option<WatcherHandle>,
+}
+
+impl TlsRotation {
+    /// Build the initial config from `config` and poll its files every `interval` until
+    /// `shutdown` is cancelled or this is dropped
+    pub fn start(
+        config: &TlsConfig,
+        interval: Duration,
+        shutdown: ShutdownToken,
+    ) -> Result<Self, TlsError> {
+        let mut rotation = Self {
+            current: Arc::new(ArcSwap::from_pointee(server_config(config)?)),
+            last_error: Arc::new(Mutex::new(None)),
+            interval,
+            shutdown,
+            watcher: None,
+        };
+        rotation.watch(config);
+        Ok(rotation)
+    }
+
+    fn watch(&mut self, config: &TlsConfig) {
+        // Stop watching the previous files, each watcher has its own child token so this does
+        // not cancel `shutdown`
+        self.watcher = None;
+        let current = Arc::clone(&self.current);
+        let last_error = Arc::clone(&self.last_error);
+        let config = config.clone();
+        let watched = [config.cert.clone(), config.key.clone()];
+        self.watcher = Some(FileWatcher::new(&watched).spawn(
+            self.interval,
+            self.shutdown.child(),
+            move |_| {
+                let error = match server_config(&config) {
+                    Ok(server_config) => {
+                        current.store(Arc::new(server_config));
+                        None
+                    }
+                    Err(e) => {
+                        writeln!(&mut io::stderr(), "warning: {}", e).ok();
+                        Some(e.to_string())
+                    }
+                };
+                *last_error.lock().unwrap_or_else(|e| e.into_inner()) = error;
+                true
+            },
+        ));
+    }
+
+    /// The shared handle to the current config
+    pub fn server_config(&self) -> Arc<ArcSwap<ServerConfig>> {
+        Arc::clone(&self.current)
+    }
+
+    /// The error from the last rebuild if it failed
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Rebuild the config from a reloaded `TlsConfig` and watch its files instead
+    ///
+    /// The previous config and files are kept if the new files cannot be used.
+    pub fn apply(&mut self, config: &TlsConfig) -> Result<(), TlsError> {
+        self.current.store(Arc::new(server_config(config)?));
+        *self.last_error.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        self.watch(config);
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+/// The key an entry of a generated config sets whether it is commented out or not
+fn entry_key(entry: &str) -> &str {
+    let line = entry.lines().last().unwrap_or("");
+    let line = if line.starts_with("# ") {
+        &line[2..]
+    } else {
+        line
+    };
+    line.split(" =").next().unwrap_or("").trim()
+}
+
+/// Arrange the entries of a config generated by `ConfigOptType::toml_config`
+///
+/// Each entry is its comment lines and key line followed by a blank line. With a `section_order`
+/// the top level keys come first followed by the sections in `section_order` and then the
+/// remaining sections. Entries are otherwise kept in declaration order. Trailing whitespace is
+/// trimmed from every line so the output does not depend on the help text a field was documented
+/// with.
+pub(crate) fn arrange(config: &str, section_order: &[&str]) -> String {
+    let mut entries = config
+        .split("\n\n")
+        .map(|entry| {
+            entry
+                .lines()
+                .map(str::trim_end)
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .filter(|entry| !entry.trim().is_empty())
+        .collect::<Vec<_>>();
+    if !section_order.is_empty() {
+        let rank = |entry: &String| {
+            let key = entry_key(entry);
+            match key.find('.') {
+                None => 0,
+                Some(i) => section_order
+                    .iter()
+                    .position(|section| *section == &key[..i])
+                    .map_or(section_order.len() + 1, |position| position + 1),
+            }
+        };
+        // The sort is stable so entries of the same rank stay in declaration order
+        entries.sort_by_key(rank);
+    }
+    entries
+        .into_iter()
+        .map(|entry| format!("{}\n\n", entry))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arrange() {
+        let config = "### Help  \ndb.url = \"a\"\n\n# server.port =\n\nname = \"app\"\n\n\
+                      log.level = \"info\"\n\n";
+        assert_eq!(
+            arrange(config, &[]),
+            "### Help\ndb.url = \"a\"\n\n# server.port =\n\nname = \"app\"\n\n\
+             log.level = \"info\"\n\n"
+        );
+        assert_eq!(
+            arrange(config, &["server"]),
+            "name = \"app\"\n\n# server.port =\n\n### Help\ndb.url = \"a\"\n\n\
+             log.level = \"info\"\n\n"
+        );
+    }
+}
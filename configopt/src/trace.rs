@@ -0,0 +1,257 @@
+use crate::experiment::VariantChoice;
+use serde::Serialize;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+thread_local! {
+    static ACTIVE: RefCell<Vec<LoadTrace>> = RefCell::new(Vec::new());
+}
+
+/// Where the values of a load step came from
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TraceSource {
+    /// The command line including per field environment variables
+    Cli,
+    /// A config file
+    ConfigFile { path: String },
+    /// A directory with a file for each key
+    Dir { path: String },
+    /// The `configopt(env_json)` environment variable
+    EnvJson { name: String },
+    /// The environment variables starting with the `configopt(env_prefix)`
+    EnvPrefix { prefix: String },
+    /// The `configopt(default_config)` document embedded in the binary
+    DefaultConfig { path: String },
+    /// A protobuf message pushed by a control plane
+    Message { name: String },
+    /// A change made at runtime through the admin endpoint
+    Admin,
+}
+
+impl TraceSource {
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Admin => 5,
+            Self::Cli => 4,
+            Self::EnvPrefix { .. } => 3,
+            Self::EnvJson { .. } => 2,
+            Self::ConfigFile { .. } | Self::Dir { .. } | Self::Message { .. } => 1,
+            Self::DefaultConfig { .. } => 0,
+        }
+    }
+}
+
+impl fmt::Display for TraceSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cli => write!(f, "the command line"),
+            Self::ConfigFile { path } => write!(f, "config file '{}'", path),
+            Self::Dir { path } => write!(f, "config directory '{}'", path),
+            Self::EnvJson { name } => write!(f, "environment variable '{}'", name),
+            Self::EnvPrefix { prefix } => write!(f, "environment variables '{}*'", prefix),
+            Self::DefaultConfig { path } => write!(f, "the default config '{}'", path),
+            Self::Message { name } => write!(f, "message '{}'", name),
+            Self::Admin => write!(f, "an admin request"),
+        }
+    }
+}
+
+/// A single source which was read while loading the config
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct LoadStep {
+    pub source: TraceSource,
+    /// The dotted paths of the keys this step set
+    pub keys: Vec<String>,
+    /// The keys this step set which a higher precedence step replaced
+    pub overridden: Vec<String>,
+    pub duration_micros: u64,
+}
+
+/// A record of every step taken while loading the config
+///
+/// Use `LoadTrace::capture` around the load to get a trace which can be emitted as one
+/// structured log line.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct LoadTrace {
+    pub steps: Vec<LoadStep>,
+    pub warnings: Vec<String>,
+    /// The variant selected for each experiment
+    pub variants: Vec<VariantChoice>,
+    pub total_micros: u64,
+}
+
+impl LoadTrace {
+    /// Run `f` recording every load step it takes on this thread
+    pub fn capture<R>(f: impl FnOnce() -> R) -> (R, LoadTrace) {
+        let started = Instant::now();
+        ACTIVE.with(|active| active.borrow_mut().push(LoadTrace::default()));
+        let result = f();
+        let mut trace = ACTIVE
+            .with(|active| active.borrow_mut().pop())
+            .unwrap_or_default();
+        trace.resolve_overrides();
+        trace.total_micros = micros(started.elapsed());
+        (result, trace)
+    }
+
+    /// The trace as a single line of JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("trace is always serializable")
+    }
+
+    /// Add a step taken after the load, for example a change made through the admin endpoint
+    pub fn push_step(&mut self, source: TraceSource, keys: Vec<String>) {
+        self.steps.push(LoadStep {
+            source,
+            keys,
+            overridden: Vec::new(),
+            duration_micros: 0,
+        });
+        self.resolve_overrides();
+    }
+
+    /// The source of the step which set the value of `key` which was not overridden
+    pub fn source_of(&self, key: &str) -> Option<&TraceSource> {
+        self.steps
+            .iter()
+            .find(|step| {
+                step.keys.iter().any(|k| k == key) && !step.overridden.iter().any(|k| k == key)
+            })
+            .map(|step| &step.source)
+    }
+
+    /// Record a load step if a trace is being captured
+    ///
+    /// `keys` is only called when a trace is active.
+    #[doc(hidden)]
+    pub fn record(source: TraceSource, keys: impl FnOnce() -> Vec<String>, started: Instant) {
+        let duration_micros = micros(started.elapsed());
+        ACTIVE.with(|active| {
+            if let Some(trace) = active.borrow_mut().last_mut() {
+                trace.steps.push(LoadStep {
+                    source,
+                    keys: keys(),
+                    overridden: Vec::new(),
+                    duration_micros,
+                });
+            }
+        });
+    }
+
+    /// Add the steps, warnings, and variants of this trace to the trace being captured around the
+    /// capture which produced it
+    pub(crate) fn forward(&self) {
+        ACTIVE.with(|active| {
+            if let Some(outer) = active.borrow_mut().last_mut() {
+                outer.steps.extend(self.steps.iter().cloned());
+                outer.warnings.extend(self.warnings.iter().cloned());
+                outer.variants.extend(self.variants.iter().cloned());
+            }
+        });
+    }
+
+    pub(crate) fn warn(warning: impl FnOnce() -> String) {
+        ACTIVE.with(|active| {
+            if let Some(trace) = active.borrow_mut().last_mut() {
+                trace.warnings.push(warning());
+            }
+        });
+    }
+
+    pub(crate) fn record_variant(choice: VariantChoice) {
+        ACTIVE.with(|active| {
+            if let Some(trace) = active.borrow_mut().last_mut() {
+                trace.variants.push(choice);
+            }
+        });
+    }
+
+    fn resolve_overrides(&mut self) {
+        // For equal precedence the step recorded last wins
+        let mut winners = HashMap::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            let rank = (step.source.precedence(), i);
+            for key in &step.keys {
+                let winner = winners.entry(key.as_str()).or_insert(rank);
+                if rank > *winner {
+                    *winner = rank;
+                }
+            }
+        }
+        let overridden = self
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| {
+                let rank = (step.source.precedence(), i);
+                step.keys
+                    .iter()
+                    .filter(|key| winners[key.as_str()] != rank)
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        for (step, overridden) in self.steps.iter_mut().zip(overridden) {
+            step.overridden = overridden;
+        }
+    }
+}
+
+fn micros(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000 + u64::from(duration.subsec_micros())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(source: TraceSource, keys: &[&str]) -> LoadStep {
+        LoadStep {
+            source,
+            keys: keys.iter().map(|k| String::from(*k)).collect(),
+            overridden: Vec::new(),
+            duration_micros: 0,
+        }
+    }
+
+    #[test]
+    fn test_resolve_overrides() {
+        let file = |path: &str| TraceSource::ConfigFile {
+            path: String::from(path),
+        };
+        let mut trace = LoadTrace {
+            steps: vec![
+                step(TraceSource::Cli, &["port"]),
+                step(file("a.toml"), &["port", "host"]),
+                step(file("b.toml"), &["host", "name"]),
+                step(
+                    TraceSource::EnvJson {
+                        name: String::from("APP"),
+                    },
+                    &["name"],
+                ),
+            ],
+            ..LoadTrace::default()
+        };
+        trace.resolve_overrides();
+        let overridden = trace
+            .steps
+            .iter()
+            .map(|s| s.overridden.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            overridden,
+            vec![
+                vec![],
+                vec![String::from("port"), String::from("host")],
+                vec![String::from("name")],
+                vec![],
+            ]
+        );
+    }
+}
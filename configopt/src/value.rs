@@ -0,0 +1,348 @@
+use serde::{
+    de::{value::Error as ValueError, DeserializeOwned, IntoDeserializer},
+    Serialize,
+};
+use std::{fmt, marker::PhantomData};
+use toml::value::{Table, Value};
+
+/// An enum used as the value of a field
+///
+/// This is implemented by `#[derive(ConfigOptValue)]` which also implements `FromStr`, `Display`,
+/// `Serialize`, and `Deserialize` using the names in `VARIANTS`. Use
+/// `#[structopt(possible_values = T::VARIANTS)]` to list them in the help message of a unit-only
+/// enum.
+///
+/// Unit variants are always written as their name. Variants with named fields are written as a
+/// table in the form selected by `REPRESENTATION`. On the CLI they are written as an inline TOML
+/// table (eg `--storage '{ type = "s3", bucket = "x" }'`).
+pub trait ConfigOptValue: Sized {
+    /// The name of each variant as it is written on the CLI and in config files
+    const VARIANTS: &'static [&'static str];
+
+    /// How variants with fields are written in config files
+    const REPRESENTATION: Representation = Representation::Internal("type");
+}
+
+/// How a variant with fields of a `ConfigOptValue` is written in config files
+///
+/// Select it with `#[configopt(tag = "<key>")]` or `#[configopt(external)]`. The TOML, JSON, and
+/// YAML config files all use the same representation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Representation {
+    /// The name of the variant is under the tag key next to its fields (eg
+    /// `{ type = "s3", bucket = "x" }`)
+    Internal(&'static str),
+    /// The fields are in a table under the name of the variant (eg `[storage.s3]` with
+    /// `bucket = "x"`)
+    External,
+}
+
+/// Build the value of the variant `name` with `fields`
+#[doc(hidden)]
+pub fn variant_value(representation: Representation, name: &str, fields: Table) -> Value {
+    match representation {
+        Representation::Internal(tag) => {
+            let mut table = Table::new();
+            table.insert(String::from(tag), Value::String(String::from(name)));
+            table.extend(fields);
+            Value::Table(table)
+        }
+        Representation::External => {
+            let mut table = Table::new();
+            table.insert(String::from(name), Value::Table(fields));
+            Value::Table(table)
+        }
+    }
+}
+
+/// Insert the field `key` of a variant leaving it out if it is `None`
+#[doc(hidden)]
+pub fn insert_variant_field<T: Serialize>(
+    fields: &mut Table,
+    key: &str,
+    value: &T,
+) -> Result<(), String> {
+    match Value::try_from(value) {
+        Ok(value) => {
+            fields.insert(String::from(key), value);
+            Ok(())
+        }
+        Err(toml::ser::Error::UnsupportedNone) => Ok(()),
+        Err(e) => Err(format!("field `{}` {}", key, e)),
+    }
+}
+
+/// Split a value into the name of its variant and its fields
+#[doc(hidden)]
+pub fn split_variant(
+    representation: Representation,
+    value: Value,
+) -> Result<(String, Table), String> {
+    let mut table = match value {
+        Value::String(name) => return Ok((name, Table::new())),
+        Value::Table(table) => table,
+        value => {
+            return Err(format!(
+                "expected a string or table, found {}",
+                value.type_str()
+            ))
+        }
+    };
+    match representation {
+        Representation::Internal(tag) => match table.remove(tag) {
+            Some(Value::String(name)) => Ok((name, table)),
+            Some(_) => Err(format!("`{}` must be a string", tag)),
+            None => Err(format!("missing `{}`", tag)),
+        },
+        Representation::External => {
+            let mut entries = table.into_iter();
+            match (entries.next(), entries.next()) {
+                (Some((name, Value::Table(fields))), None) => Ok((name, fields)),
+                _ => Err(String::from(
+                    "expected a table with the name of a variant as its only key",
+                )),
+            }
+        }
+    }
+}
+
+/// Remove the field `key` of a variant which is only allowed to be missing if it is an `Option`
+#[doc(hidden)]
+pub fn take_variant_field<T: DeserializeOwned>(fields: &mut Table, key: &str) -> Result<T, String> {
+    match fields.remove(key) {
+        Some(value) => value
+            .try_into()
+            .map_err(|e| format!("field `{}` {}", key, e)),
+        None => T::deserialize(IntoDeserializer::<ValueError>::into_deserializer(()))
+            .map_err(|_| format!("missing field `{}`", key)),
+    }
+}
+
+/// Parse the value of a variant written on the CLI as inline TOML
+#[doc(hidden)]
+pub fn parse_inline_variant(s: &str) -> Result<Value, String> {
+    toml::from_str::<Table>(&format!("value = {}", s))
+        .map_err(|e| e.to_string())
+        .and_then(|mut table| table.remove("value").ok_or_else(String::new))
+}
+
+/// Render `value` as it is written after `key = ` in a TOML document
+///
+/// Unlike the `Display` of `toml::Value`, tables are rendered inline. Strings which contain
+/// backslashes, like regexes and Windows paths, are rendered as literal strings and strings which
+/// span lines, like scripts, as multi-line literal strings so they read as they are used. Floats
+/// which are not finite are rendered as `nan`, `inf`, and `-inf`.
+pub fn inline_toml(value: &Value) -> String {
+    render(value, true)
+}
+
+fn render(value: &Value, top_level: bool) -> String {
+    match value {
+        Value::Table(table) => {
+            if table.is_empty() {
+                return String::from("{}");
+            }
+            let entries = table
+                .iter()
+                .map(|(key, value)| format!("{} = {}", inline_key(key), render(value, false)))
+                .collect::<Vec<_>>();
+            format!("{{ {} }}", entries.join(", "))
+        }
+        Value::Array(values) => {
+            let values = values
+                .iter()
+                .map(|value| render(value, false))
+                .collect::<Vec<_>>();
+            format!("[{}]", values.join(", "))
+        }
+        Value::String(s) => render_string(s, top_level),
+        Value::Float(f) if f.is_nan() => String::from("nan"),
+        Value::Float(f) if f.is_infinite() => String::from(if *f > 0.0 { "inf" } else { "-inf" }),
+        value => value.to_string(),
+    }
+}
+
+fn render_string(s: &str, top_level: bool) -> String {
+    // Literal strings have no escapes so they cannot hold control characters other than tabs
+    let literal = !s.chars().any(|c| c.is_control() && c != '\t' && c != '\n');
+    let multi_line = s.contains('\n');
+    if literal && multi_line && top_level && !s.contains("'''") && !s.ends_with('\'') {
+        // A newline right after the opening delimiter is trimmed
+        format!("'''\n{}'''", s)
+    } else if literal && !multi_line && s.contains('\\') && !s.contains('\'') {
+        format!("'{}'", s)
+    } else {
+        Value::String(String::from(s)).to_string()
+    }
+}
+
+fn inline_key(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        String::from(key)
+    } else {
+        Value::String(String::from(key)).to_string()
+    }
+}
+
+/// The possible values of `T` if it is a `ConfigOptValue`
+///
+/// Calling `(&ValuesOf::<T>::new()).values()` resolves to `ValuesOfValue` for a `ConfigOptValue`
+/// and falls back to `ValuesOfOther` for any other type.
+#[doc(hidden)]
+pub struct ValuesOf<T>(PhantomData<T>);
+
+impl<T> ValuesOf<T> {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+#[doc(hidden)]
+pub trait ValuesOfValue {
+    fn values(&self) -> Vec<String>;
+}
+
+impl<T: ConfigOptValue> ValuesOfValue for ValuesOf<T> {
+    fn values(&self) -> Vec<String> {
+        T::VARIANTS.iter().map(|v| String::from(*v)).collect()
+    }
+}
+
+#[doc(hidden)]
+pub trait ValuesOfOther {
+    fn values(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+impl<T> ValuesOfOther for &ValuesOf<T> {}
+
+/// A set value which cannot be represented in TOML (eg an integer above `i64::MAX`)
+#[derive(Clone, Debug, PartialEq)]
+pub struct TomlValueError {
+    /// The dotted key of the value
+    pub path: String,
+    /// Why the value cannot be represented
+    pub message: String,
+}
+
+impl fmt::Display for TomlValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for TomlValueError {}
+
+/// The TOML value of a field if its type implements `Serialize`
+///
+/// Calling `(&TomlValueOf(&value)).toml_value()` resolves to `TomlValueSerialize` for a type which
+/// implements `Serialize` and falls back to `TomlValueOther` which leaves the value out. Leaves
+/// are not required to implement `Serialize` so this lets them be written when they do.
+#[doc(hidden)]
+pub struct TomlValueOf<'a, T>(pub &'a T);
+
+#[doc(hidden)]
+pub trait TomlValueSerialize {
+    fn toml_value(&self) -> Result<Option<Value>, toml::ser::Error>;
+}
+
+impl<T: Serialize> TomlValueSerialize for TomlValueOf<'_, T> {
+    fn toml_value(&self) -> Result<Option<Value>, toml::ser::Error> {
+        match Value::try_from(self.0) {
+            Ok(value) => Ok(Some(value)),
+            // An unset field
+            Err(toml::ser::Error::UnsupportedNone) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[doc(hidden)]
+pub trait TomlValueOther {
+    fn toml_value(&self) -> Result<Option<Value>, toml::ser::Error> {
+        Ok(None)
+    }
+}
+
+impl<T> TomlValueOther for &TomlValueOf<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_representations() {
+        let mut fields = Table::new();
+        insert_variant_field(&mut fields, "bucket", &"x").unwrap();
+        insert_variant_field(&mut fields, "region", &None::<String>).unwrap();
+        let internal = variant_value(Representation::Internal("type"), "s3", fields.clone());
+        assert_eq!(inline_toml(&internal), r#"{ bucket = "x", type = "s3" }"#);
+        let external = variant_value(Representation::External, "s3", fields.clone());
+        assert_eq!(inline_toml(&external), r#"{ s3 = { bucket = "x" } }"#);
+
+        assert_eq!(
+            split_variant(Representation::Internal("type"), internal),
+            Ok((String::from("s3"), fields.clone()))
+        );
+        assert_eq!(
+            split_variant(Representation::External, external),
+            Ok((String::from("s3"), fields.clone()))
+        );
+        assert_eq!(
+            split_variant(
+                Representation::External,
+                Value::String(String::from("local"))
+            ),
+            Ok((String::from("local"), Table::new()))
+        );
+
+        let mut taken = fields;
+        assert_eq!(
+            take_variant_field::<Option<String>>(&mut taken, "region"),
+            Ok(None)
+        );
+        assert_eq!(
+            take_variant_field::<String>(&mut taken, "bucket"),
+            Ok(String::from("x"))
+        );
+        assert_eq!(
+            take_variant_field::<String>(&mut taken, "bucket"),
+            Err(String::from("missing field `bucket`"))
+        );
+    }
+
+    #[test]
+    fn test_inline_toml() {
+        let string = |s: &str| Value::String(String::from(s));
+        assert_eq!(inline_toml(&string("a")), r#""a""#);
+        assert_eq!(inline_toml(&string(r"^\d+$")), r"'^\d+$'");
+        assert_eq!(inline_toml(&string(r"it's \d")), r#""it's \\d""#);
+        assert_eq!(inline_toml(&string("a\nb\n")), "'''\na\nb\n'''");
+        assert_eq!(inline_toml(&string("a\u{7}\nb")), r#""a\u0007\nb""#);
+        let array = Value::Array(vec![string("a\nb"), string(r"\d")]);
+        assert_eq!(inline_toml(&array), r#"["a\nb", '\d']"#);
+        assert_eq!(inline_toml(&Value::Float(std::f64::NAN)), "nan");
+        assert_eq!(inline_toml(&Value::Float(-std::f64::INFINITY)), "-inf");
+        assert_eq!(inline_toml(&Value::Float(1.0)), "1.0");
+
+        // Everything which is rendered parses back to the same value
+        for value in &[
+            string(r"^\d+$"),
+            string("#!/bin/sh\necho 'hi'\n"),
+            string("tab\tand\r\nreturn"),
+            Value::Float(std::f64::INFINITY),
+            Value::Integer(std::i64::MIN),
+            array,
+        ] {
+            let parsed =
+                toml::from_str::<Table>(&format!("value = {}", inline_toml(value))).unwrap();
+            assert_eq!(&parsed["value"], value);
+        }
+    }
+}
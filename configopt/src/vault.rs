@@ -0,0 +1,239 @@
+//! HashiCorp Vault secret resolution
+//!
+//! [`VaultClient`](struct.VaultClient.html) resolves the `vault:<path>#<key>` references of a
+//! [`ResolvedSource`](struct.ResolvedSource.html) (eg `vault:secret/data/app#password`) by reading
+//! the secret at `<path>` and picking its `<key>`. Both versions of the KV secrets engine are
+//! supported. It authenticates with a token or logs in with AppRole the first time a secret is
+//! read.
+
+use crate::{resolve::other, ConfigOpt, ReferenceResolver, SourceFuture, REDACTED};
+use std::{fmt, io, sync::Mutex};
+use structopt::StructOpt;
+
+/// The prefix of a value which refers to a Vault secret
+pub const VAULT_PREFIX: &str = "vault:";
+
+/// How to reach and authenticate with a Vault server
+///
+/// Flatten this into the config of an app to get the `--vault-*` arguments and a `[vault]` table in
+/// its config files. The arguments fall back to the environment variables the `vault` CLI uses.
+/// A token takes precedence over an AppRole.
+#[derive(ConfigOpt, StructOpt, Clone, PartialEq)]
+#[configopt(derive(Clone, Debug, PartialEq))]
+pub struct VaultConfig {
+    /// The address of the Vault server
+    #[structopt(
+        long = "vault-addr",
+        env = "VAULT_ADDR",
+        default_value = "https://127.0.0.1:8200"
+    )]
+    pub address: String,
+    /// The token to authenticate with
+    #[structopt(long = "vault-token", env = "VAULT_TOKEN")]
+    #[configopt(secret)]
+    pub token: Option<String>,
+    /// The role id to log in with AppRole
+    #[structopt(long = "vault-role-id", env = "VAULT_ROLE_ID")]
+    pub role_id: Option<String>,
+    /// The secret id to log in with AppRole
+    #[structopt(long = "vault-secret-id", env = "VAULT_SECRET_ID")]
+    #[configopt(secret)]
+    pub secret_id: Option<String>,
+    /// The path the AppRole auth method is mounted at
+    #[structopt(long = "vault-approle-mount", default_value = "approle")]
+    pub approle_mount: String,
+    /// The Vault Enterprise namespace
+    #[structopt(long = "vault-namespace", env = "VAULT_NAMESPACE")]
+    pub namespace: Option<String>,
+}
+
+impl fmt::Debug for VaultConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VaultConfig")
+            .field("address", &self.address)
+            .field("token", &self.token.as_ref().map(|_| REDACTED))
+            .field("role_id", &self.role_id)
+            .field("secret_id", &self.secret_id.as_ref().map(|_| REDACTED))
+            .field("approle_mount", &self.approle_mount)
+            .field("namespace", &self.namespace)
+            .finish()
+    }
+}
+
+/// The path and key of a `vault:<path>#<key>` reference
+fn parse_reference(reference: &str) -> Option<(&str, Option<&str>)> {
+    if !reference.starts_with(VAULT_PREFIX) {
+        return None;
+    }
+    let rest = reference[VAULT_PREFIX.len()..].trim_start_matches('/');
+    let (path, key) = match rest.find('#') {
+        Some(i) => (&rest[..i], Some(&rest[i + 1..])),
+        None => (rest, None),
+    };
+    if path.is_empty() {
+        None
+    } else {
+        Some((path, key))
+    }
+}
+
+/// The value of `key` of the secret of a read response
+///
+/// A KV version 2 response nests the secret in `data.data` next to its `data.metadata`. Without a
+/// key the secret must have a single value.
+fn secret_value(body: &str, key: Option<&str>) -> Result<String, String> {
+    let body = serde_json::from_str::<serde_json::Value>(body)
+        .map_err(|e| format!("the response is not JSON, err: {}", e))?;
+    let mut data = &body["data"];
+    if data["data"].is_object() && data.get("metadata").is_some() {
+        data = &data["data"];
+    }
+    let data = data
+        .as_object()
+        .ok_or_else(|| String::from("the response has no secret"))?;
+    let value = match key {
+        Some(key) => data
+            .get(key)
+            .ok_or_else(|| format!("the secret has no key `{}`", key))?,
+        None if data.len() == 1 => data.values().next().expect("a single value"),
+        None => {
+            return Err(String::from(
+                "the secret has several keys so pick one with `#key`",
+            ))
+        }
+    };
+    Ok(match value {
+        serde_json::Value::String(s) => s.clone(),
+        value => value.to_string(),
+    })
+}
+
+/// Reads the secrets `vault:` references refer to
+pub struct VaultClient {
+    config: VaultConfig,
+    http: reqwest::Client,
+    token: Mutex<Option<String>>,
+}
+
+impl VaultClient {
+    pub fn new(config: VaultConfig) -> Self {
+        let token = config.token.clone();
+        Self {
+            config,
+            http: reqwest::Client::new(),
+            token: Mutex::new(token),
+        }
+    }
+
+    pub fn config(&self) -> &VaultConfig {
+        &self.config
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/v1/{}", self.config.address.trim_end_matches('/'), path)
+    }
+
+    fn request(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.config.namespace {
+            Some(namespace) => builder.header("X-Vault-Namespace", namespace),
+            None => builder,
+        }
+    }
+
+    async fn send(&self, builder: reqwest::RequestBuilder) -> io::Result<String> {
+        self.request(builder)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(other)?
+            .text()
+            .await
+            .map_err(other)
+    }
+
+    /// The token to read secrets with logging in with AppRole if there is no token yet
+    async fn token(&self) -> io::Result<String> {
+        if let Some(token) = self
+            .token
+            .lock()
+            .expect("vault token lock poisoned")
+            .clone()
+        {
+            return Ok(token);
+        }
+        let (role_id, secret_id) = match (&self.config.role_id, &self.config.secret_id) {
+            (Some(role_id), Some(secret_id)) => (role_id, secret_id),
+            _ => return Err(other("no Vault token or AppRole is configured")),
+        };
+        let url = self.url(&format!("auth/{}/login", self.config.approle_mount));
+        let body = serde_json::json!({ "role_id": role_id, "secret_id": secret_id });
+        let response = self
+            .send(self.http.post(&url).body(body.to_string()))
+            .await?;
+        let token = serde_json::from_str::<serde_json::Value>(&response)
+            .ok()
+            .and_then(|response| response["auth"]["client_token"].as_str().map(String::from))
+            .ok_or_else(|| other("the AppRole login response has no token"))?;
+        *self.token.lock().expect("vault token lock poisoned") = Some(token.clone());
+        Ok(token)
+    }
+
+    /// Read the value of a `vault:<path>#<key>` reference
+    pub async fn fetch(&self, reference: &str) -> io::Result<String> {
+        let (path, key) = parse_reference(reference)
+            .ok_or_else(|| other(format!("`{}` is not a valid reference", reference)))?;
+        let token = self.token().await?;
+        let builder = self
+            .http
+            .get(&self.url(path))
+            .header("X-Vault-Token", token);
+        let body = self.send(builder).await?;
+        secret_value(&body, key).map_err(other)
+    }
+}
+
+impl fmt::Debug for VaultClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VaultClient")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl ReferenceResolver for VaultClient {
+    fn schemes(&self) -> &[&'static str] {
+        &[VAULT_PREFIX]
+    }
+
+    fn resolve<'a>(&'a self, reference: &'a str) -> SourceFuture<'a> {
+        Box::pin(self.fetch(reference))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reference() {
+        assert_eq!(
+            parse_reference("vault:secret/data/app#password"),
+            Some(("secret/data/app", Some("password")))
+        );
+        assert_eq!(parse_reference("vault:/kv/app"), Some(("kv/app", None)));
+        assert_eq!(parse_reference("vault:"), None);
+        assert_eq!(parse_reference("ssm:/app"), None);
+    }
+
+    #[test]
+    fn test_secret_value() {
+        let v2 = r#"{"data": {"data": {"password": "hunter2", "port": 5432}, "metadata": {}}}"#;
+        assert_eq!(secret_value(v2, Some("password")).unwrap(), "hunter2");
+        assert_eq!(secret_value(v2, Some("port")).unwrap(), "5432");
+        assert!(secret_value(v2, Some("user")).is_err());
+        assert!(secret_value(v2, None).is_err());
+        let v1 = r#"{"data": {"password": "hunter2"}}"#;
+        assert_eq!(secret_value(v1, None).unwrap(), "hunter2");
+        assert!(secret_value("{}", None).is_err());
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{Clock, FileSystem, RealFileSystem, ShutdownToken, SystemClock};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime},
+};
+
+/// Poll config files for changes
+///
+/// The clock and filesystem are pluggable so reloads can be simulated deterministically with
+/// [`ManualClock`](struct.ManualClock.html) and [`MemoryFileSystem`](struct.MemoryFileSystem.html).
+pub struct FileWatcher {
+    clock: Arc<dyn Clock>,
+    fs: Arc<dyn FileSystem>,
+    // `None` if the file did not exist when it was last polled
+    modified: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl FileWatcher {
+    /// Watch `paths` on the real filesystem
+    pub fn new<T: AsRef<Path>>(paths: &[T]) -> Self {
+        Self::with(paths, Arc::new(SystemClock), Arc::new(RealFileSystem))
+    }
+
+    /// Watch `paths` using `clock` and `fs`
+    pub fn with<T: AsRef<Path>>(
+        paths: &[T],
+        clock: Arc<dyn Clock>,
+        fs: Arc<dyn FileSystem>,
+    ) -> Self {
+        let modified = paths
+            .iter()
+            .map(|path| {
+                let path = path.as_ref();
+                (path.to_path_buf(), fs.modified(path).ok())
+            })
+            .collect();
+        Self {
+            clock,
+            fs,
+            modified,
+        }
+    }
+
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    pub fn fs(&self) -> &Arc<dyn FileSystem> {
+        &self.fs
+    }
+
+    /// The paths which were created, modified, or removed since the last poll in sorted order
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let fs = &self.fs;
+        let mut changed = self
+            .modified
+            .iter_mut()
+            .filter_map(|(path, modified)| {
+                let current = fs.modified(path).ok();
+                if current == *modified {
+                    return None;
+                }
+                *modified = current;
+                Some(path.clone())
+            })
+            .collect::<Vec<_>>();
+        changed.sort();
+        changed
+    }
+
+    /// Poll every `interval` calling `on_change` with the paths which changed until it returns
+    /// `false`
+    pub fn watch(&mut self, interval: Duration, mut on_change: impl FnMut(&[PathBuf]) -> bool) {
+        loop {
+            self.clock.sleep(interval);
+            let changed = self.poll();
+            if !changed.is_empty() && !on_change(&changed) {
+                return;
+            }
+        }
+    }
+
+    /// Like [`watch`](#method.watch) but also stop once `shutdown` is cancelled
+    ///
+    /// The token is checked before and after each sleep of the clock so a cancelled watcher never
+    /// calls `on_change` again.
+    pub fn watch_until(
+        &mut self,
+        interval: Duration,
+        shutdown: &ShutdownToken,
+        mut on_change: impl FnMut(&[PathBuf]) -> bool,
+    ) {
+        while !shutdown.is_cancelled() {
+            self.clock.sleep(interval);
+            if shutdown.is_cancelled() {
+                return;
+            }
+            let changed = self.poll();
+            if !changed.is_empty() && !on_change(&changed) {
+                return;
+            }
+        }
+    }
+
+    /// Poll every `interval` on a background thread until `on_change` returns `false` or
+    /// `shutdown` is cancelled
+    ///
+    /// The interval is waited for in real time instead of with the clock of the watcher so
+    /// cancelling wakes the thread immediately.
+    pub fn spawn(
+        mut self,
+        interval: Duration,
+        shutdown: ShutdownToken,
+        mut on_change: impl FnMut(&[PathBuf]) -> bool + Send + 'static,
+    ) -> WatcherHandle {
+        let token = shutdown.clone();
+        let thread = thread::spawn(move || {
+            while !token.wait_timeout(interval) {
+                let changed = self.poll();
+                if !changed.is_empty() && !on_change(&changed) {
+                    return;
+                }
+            }
+        });
+        WatcherHandle::new(shutdown, thread)
+    }
+}
+
+/// A [`FileWatcher`](struct.FileWatcher.html) polling on a background thread
+///
+/// Dropping the handle cancels the watcher and waits for its thread to exit so the thread is never
+/// leaked.
+pub struct WatcherHandle {
+    shutdown: ShutdownToken,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WatcherHandle {
+    pub(crate) fn new(shutdown: ShutdownToken, thread: JoinHandle<()>) -> Self {
+        Self {
+            shutdown,
+            thread: Some(thread),
+        }
+    }
+
+    /// The token which stops the watcher
+    pub fn shutdown_token(&self) -> &ShutdownToken {
+        &self.shutdown
+    }
+
+    /// Cancel the watcher and wait for its thread to exit
+    ///
+    /// Returns an error if `on_change` panicked.
+    pub fn stop(self) -> thread::Result<()> {
+        self.shutdown.cancel();
+        self.join()
+    }
+
+    /// Wait for the watcher to exit without cancelling it
+    pub fn join(mut self) -> thread::Result<()> {
+        match self.thread.take() {
+            Some(thread) => thread.join(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for WatcherHandle {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.shutdown.cancel();
+            thread.join().ok();
+        }
+    }
+}
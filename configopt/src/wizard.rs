@@ -0,0 +1,129 @@
+use crate::{
+    coerce, config_format, prefix, reference::arg_docs, set_field, ConfigFormat, ConfigOptType,
+    Error, FieldPath, IgnoreHelp, Partial, Result, Value,
+};
+use serde::de::DeserializeOwned;
+use std::{io, path::Path};
+
+/// A question the wizard asks for a required field which is not set
+#[derive(Clone, Debug, PartialEq)]
+pub struct Question {
+    /// The dotted path of the field
+    pub key: String,
+    /// The help of the CLI argument the field is read from
+    pub help: String,
+    /// The value used when the answer is empty
+    pub default: Option<String>,
+    /// The possible values of a field whose type derives `ConfigOptValue`
+    pub values: Vec<String>,
+    /// Is the field a secret whose answer should not be echoed
+    pub secret: bool,
+    /// Why the previous answer to this question was rejected
+    pub error: Option<String>,
+}
+
+/// Ask `prompt` for each required field of `config` which is not set and write the completed
+/// config to `path`
+///
+/// An answer is parsed as the kind of value the field holds and the question is asked again with
+/// the reason if it does not fit. Secrets are asked for so `config` is complete but like any
+/// generated config they are never written to `path`.
+pub fn wizard_with<T>(
+    config: &mut T,
+    path: impl AsRef<Path>,
+    mut prompt: impl FnMut(&Question) -> io::Result<String>,
+) -> Result<()>
+where
+    T: ConfigOptType + IgnoreHelp + Partial + DeserializeOwned,
+{
+    let path = path.as_ref();
+    let schema = T::runtime_schema();
+    // The defaults of prefixed arguments are read from their prefixed environment variables
+    let mut app = T::clap();
+    prefix::apply(&mut app, &T::arg_prefixes());
+    let docs = arg_docs(&app);
+    for key in config.missing_fields() {
+        let field = coerce::find_field(&schema, &key);
+        let arg = field
+            .and_then(|field| field.arg.as_ref())
+            .and_then(|arg| docs.get(arg).map(|doc| (arg, doc)));
+        let mut question = Question {
+            help: arg.map(|(_, doc)| doc.help.clone()).unwrap_or_default(),
+            default: arg.and_then(|(_, doc)| doc.default.clone()),
+            values: field.map(|field| field.values.clone()).unwrap_or_default(),
+            secret: arg.map_or(false, |(arg, _)| config.arg_is_secret(&[arg.clone()])),
+            error: None,
+            key,
+        };
+        loop {
+            let answer = prompt(&question).map_err(|e| Error::ConfigFile(path.to_path_buf(), e))?;
+            let answer = match (answer.is_empty(), &question.default) {
+                (true, Some(default)) => default.clone(),
+                (true, None) => {
+                    question.error = Some(String::from("a value is required"));
+                    continue;
+                }
+                (false, _) => answer,
+            };
+            let field = FieldPath::from(question.key.as_str());
+            match set_field(config, &field, Value::from(answer)) {
+                Ok(()) => break,
+                Err(e) => question.error = Some(e),
+            }
+        }
+    }
+    let format = ConfigFormat::from_path(path).unwrap_or(ConfigFormat::Toml);
+    let contents = format.render(&config_format::values(config, path)?, path)?;
+    config_format::write(path, &contents)
+}
+
+/// Ask a question on the terminal
+#[cfg(feature = "wizard")]
+fn ask(question: &Question) -> io::Result<String> {
+    use dialoguer::{Input, PasswordInput, Select};
+
+    if let Some(error) = &question.error {
+        eprintln!("error: {}", error);
+    }
+    if !question.help.is_empty() {
+        eprintln!("{}", question.help);
+    }
+    if question.secret {
+        return PasswordInput::new()
+            .with_prompt(&question.key)
+            .allow_empty_password(question.default.is_some())
+            .interact();
+    }
+    if !question.values.is_empty() {
+        let default = question
+            .default
+            .as_ref()
+            .and_then(|default| question.values.iter().position(|value| value == default))
+            .unwrap_or(0);
+        let choice = Select::new()
+            .with_prompt(&question.key)
+            .items(&question.values)
+            .default(default)
+            .interact()?;
+        return Ok(question.values[choice].clone());
+    }
+    let mut input = Input::<String>::new();
+    input.with_prompt(&question.key).allow_empty(true);
+    if let Some(default) = &question.default {
+        input.default(default.clone());
+    }
+    input.interact()
+}
+
+/// Prompt on the terminal for each required field of `config` which is not set and write the
+/// completed config to `path`
+///
+/// This is meant for the first run of a CLI tool when there is no config file yet. See
+/// [`wizard_with`](fn.wizard_with.html) for how answers are handled.
+#[cfg(feature = "wizard")]
+pub fn wizard<T>(config: &mut T, path: impl AsRef<Path>) -> Result<()>
+where
+    T: ConfigOptType + IgnoreHelp + Partial + DeserializeOwned,
+{
+    wizard_with(config, path, ask)
+}
@@ -0,0 +1,107 @@
+#![cfg(feature = "async")]
+
+use configopt::{
+    AsyncSource, ConfigOpt, ConfigOptType, ReferenceResolver, ResolvedSource, SourceFuture,
+};
+use std::{convert::TryFrom, io, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+}
+
+struct InMemory(&'static str);
+
+impl AsyncSource for InMemory {
+    fn name(&self) -> String {
+        String::from("memory")
+    }
+
+    fn read(&self) -> SourceFuture<'_> {
+        Box::pin(async move { Ok(String::from(self.0)) })
+    }
+}
+
+#[tokio::test]
+async fn test_from_file_async() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "host = \"localhost\"\nport = 8080").unwrap();
+    let c = ConfigOptMyStruct::from_file_async(config_file.path())
+        .await
+        .unwrap();
+    let s = MyStruct::try_from(c).unwrap();
+    assert_eq!(s.host, "localhost");
+    assert_eq!(s.port, 8080);
+
+    assert!(
+        ConfigOptMyStruct::from_file_async("/configopt/does/not/exist.toml")
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_patch_from_source_async() {
+    let mut c = ConfigOptMyStruct::from_iter_safe(&["app", "--port", "9000"]).unwrap();
+    c.patch_from_source_async(&InMemory("host = \"example.com\"\nport = 1\n"))
+        .await
+        .unwrap();
+    let s = MyStruct::try_from(c).unwrap();
+    assert_eq!(s.host, "example.com");
+    assert_eq!(s.port, 9000);
+
+    let mut c = ConfigOptMyStruct::default();
+    assert!(c
+        .patch_from_source_async(&InMemory("port = \"not a number\""))
+        .await
+        .is_err());
+}
+
+struct Upper;
+
+impl ReferenceResolver for Upper {
+    fn schemes(&self) -> &[&'static str] {
+        &["upper:", "port:"]
+    }
+
+    fn resolve<'a>(&'a self, reference: &'a str) -> SourceFuture<'a> {
+        Box::pin(async move {
+            match reference.find(':') {
+                Some(_) if reference.starts_with("port:") => Ok(String::from("8080")),
+                Some(i) if reference[i + 1..].is_empty() => {
+                    Err(io::Error::new(io::ErrorKind::Other, "is empty"))
+                }
+                Some(i) => Ok(reference[i + 1..].to_uppercase()),
+                None => unreachable!(),
+            }
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_resolved_source() {
+    let source = ResolvedSource::new(InMemory("host = \"upper:example.com\"\nport = \"port:\"\n"))
+        .with_resolver(Upper)
+        .with_schema(ConfigOptMyStruct::schema());
+    let c = ConfigOptMyStruct::from_source_async(&source).await.unwrap();
+    assert_eq!(c.host, Some(String::from("EXAMPLE.COM")));
+    assert_eq!(c.port, Some(8080));
+
+    // A scheme without a resolver is left as it is
+    let source = ResolvedSource::new(InMemory("host = \"other:example.com\"\nport = 1\n"))
+        .with_resolver(Upper);
+    let c = ConfigOptMyStruct::from_source_async(&source).await.unwrap();
+    assert_eq!(c.host, Some(String::from("other:example.com")));
+
+    let source = ResolvedSource::new(InMemory("host = \"upper:\"\n")).with_resolver(Upper);
+    let e = ConfigOptMyStruct::from_source_async(&source)
+        .await
+        .unwrap_err();
+    assert!(e.to_string().contains("`host` is empty"));
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "axum")]
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::FromRef,
+    http::Request,
+    routing::get,
+    Router,
+};
+use configopt::{ConfigOpt, CurrentConfig, Reloadable};
+use structopt::StructOpt;
+use tower::ServiceExt;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+struct MyStruct {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[derive(Clone)]
+struct AppState {
+    config: Reloadable<MyStruct>,
+}
+
+impl FromRef<AppState> for Reloadable<MyStruct> {
+    fn from_ref(state: &AppState) -> Self {
+        state.config.clone()
+    }
+}
+
+async fn port(config: CurrentConfig<MyStruct>) -> String {
+    config.port.to_string()
+}
+
+async fn get_port(router: &Router) -> String {
+    let request = Request::builder().uri("/port").body(Body::empty()).unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    let body = response.into_body().data().await.unwrap().unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_current_config_from_state() {
+    let config = Reloadable::new(MyStruct { port: 1 });
+    let router = Router::new()
+        .route("/port", get(port))
+        .with_state(AppState {
+            config: config.clone(),
+        });
+    assert_eq!(get_port(&router).await, "1");
+    // Each request extracts a snapshot of the config which is current when it is handled
+    config.replace(MyStruct { port: 2 });
+    assert_eq!(get_port(&router).await, "2");
+}
@@ -0,0 +1,42 @@
+use configopt::{ConfigOpt, ConfigOptBool};
+use std::{convert::TryFrom, path::PathBuf};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    path: Option<PathBuf>,
+    #[structopt(long)]
+    numbers: Vec<u32>,
+    #[structopt(long)]
+    verbose: bool,
+}
+
+#[test]
+fn test_builder() {
+    let c = ConfigOptMyStruct::default()
+        .with_host("localhost")
+        .with_port(8080)
+        .with_path("/tmp")
+        .with_numbers(vec![1, 2])
+        .with_verbose(true);
+    assert_eq!(c.host, Some(String::from("localhost")));
+    assert_eq!(c.port, Some(8080));
+    assert_eq!(c.path, Some(PathBuf::from("/tmp")));
+    assert_eq!(c.verbose, ConfigOptBool::from(true));
+    assert_eq!(
+        MyStruct::try_from(c).unwrap(),
+        MyStruct {
+            host: String::from("localhost"),
+            port: 8080,
+            path: Some(PathBuf::from("/tmp")),
+            numbers: vec![1, 2],
+            verbose: true,
+        }
+    );
+}
@@ -0,0 +1,38 @@
+use configopt::{configopt_fields, ConfigOpt, ConfigOptType};
+use std::io::Write;
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long, default_value = "plain")]
+    name: String,
+    /// How to render the help message
+    #[structopt(long)]
+    #[configopt(cli_only)]
+    help_style: Option<String>,
+}
+
+#[test]
+fn test_cli_only() {
+    let c = ConfigOptMyStruct::from_iter_safe(&["app", "--help-style", "fancy"]).unwrap();
+    assert_eq!(c.help_style, Some(String::from("fancy")));
+    let config = c.toml_config();
+    assert!(config.contains("name"));
+    assert!(!config.contains("help_style"));
+    assert!(!config.contains("generate_config"));
+    assert!(!config.contains("config_files"));
+
+    // CLI only fields are ignored in config files
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "name = \"file\"\nhelp_style = \"fancy\"").unwrap();
+    let mut unknown = Vec::new();
+    let c = ConfigOptMyStruct::try_from_path_collecting_unknown(config_file.path(), &mut unknown)
+        .unwrap();
+    assert_eq!(c.name, Some(String::from("file")));
+    assert_eq!(c.help_style, None);
+    assert_eq!(unknown.len(), 1);
+    assert_eq!(unknown[0].key, "help_style");
+}
@@ -0,0 +1,37 @@
+use configopt::{configopt_fields, ConfigOpt, ConfigOptType};
+use std::io::Write;
+use structopt::{clap::Shell, StructOpt};
+use tempfile::NamedTempFile;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    /// The host to bind to
+    #[structopt(long)]
+    host: String,
+    #[structopt(long, possible_values = &["debug", "info", "error"])]
+    level: String,
+}
+
+#[test]
+fn test_completions() {
+    let bash = MyStruct::completions("app", Shell::Bash).unwrap();
+    assert!(bash.contains("--host"));
+    assert!(bash.contains("--config-files"));
+    assert!(bash.contains("debug info error"));
+
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "host = \"example.com\"").unwrap();
+    let mut defaults = ConfigOptMyStruct::from_iter_safe(&[
+        "app",
+        "--config-files",
+        config_file.path().to_str().unwrap(),
+    ])
+    .unwrap();
+    defaults.patch_with_config_files().unwrap();
+    let mut zsh = Vec::new();
+    MyStruct::write_completions_with_defaults("app", Shell::Zsh, &defaults, &mut zsh);
+    let zsh = String::from_utf8(zsh).unwrap();
+    assert!(zsh.contains("The host to bind to \\[default: example.com\\]"));
+}
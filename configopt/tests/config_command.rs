@@ -0,0 +1,70 @@
+#![cfg(feature = "edit")]
+
+use configopt::{ConfigCommand, ConfigOpt};
+use std::{fs, path::Path};
+use structopt::StructOpt;
+use tempfile::TempDir;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug), config_command)]
+struct Server {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    #[configopt(secret)]
+    token: Option<String>,
+}
+
+fn run(path: &Path, args: &[&str]) -> configopt::Result<String> {
+    let command = ConfigCommand::from_iter(["config"].iter().chain(args).copied());
+    let mut out = Vec::new();
+    command.run::<ConfigOptServer>(path, &mut out)?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn test_config_command() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, "# The host to bind\nhost = \"localhost\"\n").unwrap();
+
+    assert_eq!(run(&path, &["get", "host"]).unwrap(), "localhost\n");
+    assert!(run(&path, &["get", "port"]).is_err());
+    assert!(run(&path, &["get", "tls"]).is_err());
+
+    // Values are parsed as the kind of the field and comments are kept
+    run(&path, &["set", "port", "8080"]).unwrap();
+    assert!(run(&path, &["set", "port", "http"]).is_err());
+    let contents = fs::read_to_string(&path).unwrap();
+    assert!(contents.starts_with("# The host to bind\nhost = \"localhost\"\n"));
+    assert!(contents.contains("port = 8080"));
+    assert_eq!(run(&path, &["get", "port"]).unwrap(), "8080\n");
+
+    // Secrets are never listed
+    run(&path, &["set", "token", "hunter2"]).unwrap();
+    assert_eq!(
+        run(&path, &["list"]).unwrap(),
+        "host = \"localhost\"\nport = 8080\n"
+    );
+
+    run(&path, &["unset", "host"]).unwrap();
+    assert!(run(&path, &["get", "host"]).is_err());
+}
+
+#[test]
+fn test_try_run_config_command() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    let path = path.to_str().unwrap();
+
+    assert!(!ConfigOptServer::try_run_config_command(vec!["app", "--port", "80"]).unwrap());
+    assert!(ConfigOptServer::try_run_config_command(vec![
+        "app", "config", "--file", path, "set", "port", "80"
+    ])
+    .unwrap());
+    assert!(fs::read_to_string(path).unwrap().contains("port = 80"));
+    // There is no default config file to fall back to
+    assert!(ConfigOptServer::try_run_config_command(vec!["app", "config", "list"]).is_err());
+}
@@ -0,0 +1,211 @@
+use configopt::{ConfigOpt, ConfigOptType, ConfigOptValue, DocFormat, Error, Violation};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use structopt::StructOpt;
+
+#[derive(ConfigOptValue, Debug)]
+enum Mode {
+    Fast,
+    Safe,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+struct Server {
+    #[structopt(long)]
+    #[configopt(min = 1, max = 65535)]
+    port: u16,
+    #[structopt(long)]
+    tls: bool,
+    #[structopt(long)]
+    #[configopt(required_if = "tls")]
+    cert: Option<String>,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+struct MyStruct {
+    #[structopt(long)]
+    #[configopt(pattern = "^[a-z]+$")]
+    name: String,
+    #[structopt(long)]
+    #[configopt(min = -1.5)]
+    weights: Vec<f64>,
+    #[structopt(long)]
+    mode: Option<Mode>,
+    #[structopt(skip)]
+    #[configopt(map)]
+    servers: HashMap<String, Server>,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+struct App {
+    #[structopt(long)]
+    #[configopt(min = 1, max = 65535)]
+    port: u32,
+    #[structopt(long)]
+    #[configopt(matches = "^v[0-9]+$")]
+    version: Option<String>,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+struct Secrets {
+    #[structopt(long)]
+    #[configopt(secret, matches = "^[0-9a-f]+$")]
+    token: String,
+    #[structopt(long)]
+    #[configopt(max = 10)]
+    retries: Option<u64>,
+}
+
+#[test]
+fn test_constraints_in_schema() {
+    let schema = ConfigOptMyStruct::schema();
+    let port = &schema.field("servers.*.port").unwrap().constraints;
+    assert_eq!(port.min, Some(1.0));
+    assert_eq!(port.max, Some(65535.0));
+    let cert = &schema.field("servers.*.cert").unwrap().constraints;
+    assert_eq!(cert.required_if.as_deref(), Some("tls"));
+    assert!(schema.field("mode").unwrap().constraints.is_empty());
+
+    let json_schema = serde_json::from_str::<Value>(&schema.to_json_schema()).unwrap();
+    assert_eq!(
+        json_schema["properties"]["name"],
+        json!({"type": "string", "pattern": "^[a-z]+$"})
+    );
+    assert_eq!(
+        json_schema["properties"]["weights"],
+        json!({"type": "array", "items": {"type": "number", "minimum": -1.5}})
+    );
+    assert_eq!(
+        json_schema["properties"]["mode"],
+        json!({"enum": ["fast", "safe"]})
+    );
+    assert_eq!(json_schema["required"], json!(["name"]));
+    let server = &json_schema["properties"]["servers"]["additionalProperties"];
+    assert_eq!(
+        server["properties"]["port"],
+        json!({"type": "integer", "minimum": 1, "maximum": 65535})
+    );
+    assert_eq!(server["required"], json!(["port"]));
+    assert_eq!(server["dependencies"], json!({"tls": ["cert"]}));
+}
+
+#[test]
+#[cfg(feature = "patterns")]
+fn test_constraints_checked() {
+    let schema = ConfigOptMyStruct::schema();
+    let violations = schema
+        .check_str(
+            r#"
+name = "a"
+weights = [1.0, -2.0]
+mode = "slow"
+
+[servers.a]
+port = 0
+tls = true
+"#,
+        )
+        .unwrap();
+    assert_eq!(
+        violations,
+        vec![
+            Violation::Constraint {
+                path: String::from("weights"),
+                message: String::from("must be at least -1.5 not -2.0"),
+            },
+            Violation::Constraint {
+                path: String::from("mode"),
+                message: String::from("must be one of `fast`, `safe` not `slow`"),
+            },
+            Violation::Constraint {
+                path: String::from("servers.a.port"),
+                message: String::from("must be at least 1 not 0"),
+            },
+            Violation::Missing {
+                path: String::from("servers.a.cert"),
+            },
+        ]
+    );
+}
+
+#[test]
+#[cfg(feature = "patterns")]
+fn test_constraints_checked_after_merge() {
+    let app = App::try_from_iter_with_configopt(&["app", "--port", "80", "--version", "v2"]);
+    assert_eq!(app.unwrap().version.as_deref(), Some("v2"));
+
+    let err = App::try_from_iter_with_configopt(&["app", "--port", "0"]).unwrap_err();
+    assert_eq!(err.field_path(), Some("port"));
+    assert_eq!(
+        err.to_string(),
+        "Invalid config values `port` must be at least 1 not 0"
+    );
+
+    let err = App::try_from_iter_with_configopt(&["app", "--port", "70000", "--version", "2"])
+        .unwrap_err();
+    match err {
+        Error::Constraints(violations) => assert_eq!(
+            violations,
+            vec![
+                Violation::Constraint {
+                    path: String::from("port"),
+                    message: String::from("must be at most 65535 not 70000"),
+                },
+                Violation::Constraint {
+                    path: String::from("version"),
+                    message: String::from("must match `^v[0-9]+$` not `2`"),
+                },
+            ]
+        ),
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+#[test]
+#[cfg(feature = "patterns")]
+fn test_constraints_of_secrets_and_unrepresentable_values() {
+    assert!(Secrets::try_from_iter_with_configopt(&["app", "--token", "beef"]).is_ok());
+    let err = Secrets::try_from_iter_with_configopt(&["app", "--token", "xyz"]).unwrap_err();
+    assert_eq!(err.field_path(), Some("token"));
+
+    // A value which cannot be checked is reported instead of skipping every check
+    let err = Secrets::try_from_iter_with_configopt(&[
+        "app",
+        "--token",
+        "beef",
+        "--retries",
+        "18446744073709551615",
+    ])
+    .unwrap_err();
+    match err {
+        Error::Constraints(violations) => match violations.as_slice() {
+            [Violation::Constraint { path, message }] => {
+                assert_eq!(path, "retries");
+                assert!(message.starts_with("cannot be checked: "));
+            }
+            violations => panic!("unexpected violations {:?}", violations),
+        },
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+#[test]
+#[cfg(not(feature = "patterns"))]
+fn test_patterns_cannot_be_checked_without_the_feature() {
+    let err =
+        App::try_from_iter_with_configopt(&["app", "--port", "80", "--version", "v2"]).unwrap_err();
+    assert_eq!(err.field_path(), Some("version"));
+    assert_eq!(
+        err.to_string(),
+        "Invalid config values `version` cannot be checked: matching `^v[0-9]+$` requires the \
+         `patterns` feature"
+    );
+    assert!(App::try_from_iter_with_configopt(&["app", "--port", "80"]).is_ok());
+}
+
+#[test]
+fn test_constraints_in_reference_doc() {
+    let doc = ConfigOptApp::reference_doc(DocFormat::Markdown);
+    assert!(doc.contains("| Minimum | `1` |\n| Maximum | `65535` |\n"));
+    assert!(doc.contains("| Pattern | `^v[0-9]+$` |\n"));
+}
@@ -0,0 +1,94 @@
+use configopt::{ConfigOpt, ConfigOptType, Schema, ValueKind, Violation};
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+struct Server {
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    tls: bool,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    data_dir: Option<PathBuf>,
+    #[structopt(long)]
+    #[configopt(alias = "worker_count")]
+    workers: Vec<u32>,
+    #[structopt(long, parse(try_from_str = configopt::parsers::duration))]
+    timeout: Duration,
+    #[structopt(skip)]
+    #[configopt(map)]
+    servers: HashMap<String, Server>,
+}
+
+#[test]
+fn test_value_kind() {
+    assert_eq!(ValueKind::from_rust_ty("u16"), ValueKind::Integer);
+    assert_eq!(
+        ValueKind::from_rust_ty("Option<std::path::PathBuf>"),
+        ValueKind::String
+    );
+    assert_eq!(
+        ValueKind::from_rust_ty("Vec<bool>"),
+        ValueKind::Array(Box::new(ValueKind::Boolean))
+    );
+    assert_eq!(ValueKind::from_rust_ty("MyEnum"), ValueKind::Any);
+}
+
+#[test]
+fn test_contract() {
+    // The schema is exported by the consumer and checked in the CI of the producer
+    let schema = Schema::from_json(&ConfigOptMyStruct::schema().to_json()).unwrap();
+
+    let valid = r#"
+host = "localhost"
+worker_count = [1, 2]
+timeout = "30s"
+
+[servers.a]
+port = 8080
+
+[servers.b]
+port = 8081
+tls = true
+"#;
+    assert_eq!(schema.check_str(valid).unwrap(), Vec::new());
+
+    let invalid = r#"
+data_dir = 5
+timeout = 30
+hots = "localhost"
+
+[servers.a]
+tls = "yes"
+"#;
+    assert_eq!(
+        schema.check_str(invalid).unwrap(),
+        vec![
+            Violation::Missing {
+                path: String::from("host")
+            },
+            Violation::TypeMismatch {
+                path: String::from("data_dir"),
+                expected: String::from("string"),
+                found: String::from("integer"),
+            },
+            Violation::Missing {
+                path: String::from("servers.a.port")
+            },
+            Violation::TypeMismatch {
+                path: String::from("servers.a.tls"),
+                expected: String::from("boolean"),
+                found: String::from("string"),
+            },
+            Violation::Unknown {
+                path: String::from("hots")
+            },
+        ]
+    );
+}
@@ -0,0 +1,80 @@
+use configopt::{configopt_fields, ConfigOpt};
+use serde::Deserialize;
+use std::{convert::TryFrom, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+// A runtime type which is compiled from its config
+#[derive(Debug, Default, PartialEq)]
+struct Matcher(Vec<String>);
+
+impl TryFrom<String> for Matcher {
+    type Error = String;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        if pattern.is_empty() {
+            return Err(String::from("the pattern is empty"));
+        }
+        Ok(Self(pattern.split('|').map(String::from).collect()))
+    }
+}
+
+#[derive(Deserialize)]
+struct ClientConfig {
+    url: String,
+    retries: u32,
+}
+
+#[derive(Debug, PartialEq)]
+struct Client {
+    endpoint: String,
+}
+
+impl From<ClientConfig> for Client {
+    fn from(config: ClientConfig) -> Self {
+        Self {
+            endpoint: format!("{}?retries={}", config.url, config.retries),
+        }
+    }
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+struct MyStruct {
+    #[structopt(skip)]
+    #[configopt(try_from = "String")]
+    matcher: Matcher,
+    #[structopt(skip)]
+    #[configopt(from = "ClientConfig")]
+    client: Option<Client>,
+}
+
+fn parse(config: &str) -> configopt::Result<MyStruct> {
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "{}", config).unwrap();
+    let config_files = config_file.path().to_string_lossy().to_string();
+    MyStruct::try_from_iter_with_configopt(&["app", "--config-files", &config_files])
+}
+
+#[test]
+fn test_convert() {
+    let s = parse("matcher = \"a|b\"\n\n[client]\nurl = \"http://a\"\nretries = 2").unwrap();
+    assert_eq!(
+        s.matcher,
+        Matcher(vec![String::from("a"), String::from("b")])
+    );
+    assert_eq!(
+        s.client,
+        Some(Client {
+            endpoint: String::from("http://a?retries=2")
+        })
+    );
+
+    // Optional fields may be left unset but required fields may not
+    assert_eq!(parse("matcher = \"a\"").unwrap().client, None);
+    assert!(parse("").is_err());
+
+    // A failed conversion is reported when the config file is loaded
+    let err = parse("matcher = \"\"").unwrap_err();
+    assert!(err.to_string().contains("the pattern is empty"));
+}
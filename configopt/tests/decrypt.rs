@@ -0,0 +1,71 @@
+use configopt::{clear_decryptor, set_decryptor, ConfigOpt};
+use std::{convert::TryFrom, fs};
+use structopt::StructOpt;
+use tempfile::TempDir;
+
+fn reverse(ciphertext: &str) -> Result<String, String> {
+    match ciphertext.strip_prefix("ENC[") {
+        Some(rest) if rest.ends_with(']') => Ok(rest[..rest.len() - 1].chars().rev().collect()),
+        _ => Err(String::from("not encrypted")),
+    }
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+struct MyStruct {
+    #[structopt(long)]
+    user: String,
+    #[structopt(long)]
+    #[configopt(decrypt_with = "reverse")]
+    password: String,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+struct Installed {
+    #[structopt(long)]
+    #[configopt(decrypt)]
+    token: String,
+    #[structopt(long)]
+    port: u16,
+}
+
+#[test]
+fn test_decrypt_with() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, "user = \"admin\"\npassword = \"ENC[2retnuh]\"\n").unwrap();
+    let c = ConfigOptMyStruct::try_from(path.as_path()).unwrap();
+    assert_eq!(c.user, Some(String::from("admin")));
+    assert_eq!(c.password, Some(String::from("hunter2")));
+
+    fs::write(&path, "password = \"hunter2\"\n").unwrap();
+    let e = ConfigOptMyStruct::try_from(path.as_path()).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "Failed to decrypt `password`, err: not encrypted"
+    );
+}
+
+#[test]
+fn test_installed_decryptor() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, "token = \"ENC[cba]\"\nport = 80\n").unwrap();
+    let encrypted_path = dir.path().join("config.enc.toml");
+    fs::write(&encrypted_path, "ENC[\n08 = trop]").unwrap();
+
+    clear_decryptor();
+    let e = ConfigOptInstalled::try_from(path.as_path()).unwrap_err();
+    assert!(e.to_string().contains("no decryptor is installed"));
+    assert!(ConfigOptInstalled::try_from(encrypted_path.as_path()).is_err());
+
+    set_decryptor(reverse);
+    let c = ConfigOptInstalled::try_from(path.as_path()).unwrap();
+    assert_eq!(c.token, Some(String::from("abc")));
+    assert_eq!(c.port, Some(80));
+
+    // The whole file is decrypted before it is parsed
+    let c = ConfigOptInstalled::try_from(encrypted_path.as_path()).unwrap();
+    assert_eq!(c.token, None);
+    assert_eq!(c.port, Some(80));
+    clear_decryptor();
+}
@@ -0,0 +1,68 @@
+use configopt::{ConfigOpt, LoadTrace, TraceSource};
+use std::io::Write;
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(
+    derive(Debug, PartialEq),
+    default_config("tests/fixtures/defaults.toml")
+)]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    workers: u32,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct NoDefaultConfig {
+    #[structopt(long)]
+    host: Option<String>,
+}
+
+#[test]
+fn test_default_config() {
+    let c = ConfigOptMyStruct::from_default_config().unwrap();
+    assert_eq!(c.host.as_deref(), Some("0.0.0.0"));
+    assert_eq!(
+        ConfigOptNoDefaultConfig::from_default_config().unwrap(),
+        ConfigOptNoDefaultConfig::default()
+    );
+
+    // The embedded config applies even when no file is present
+    let s = MyStruct::try_from_iter_with_configopt(&["app"]).unwrap();
+    assert_eq!(
+        s,
+        MyStruct {
+            host: String::from("0.0.0.0"),
+            port: 8080,
+            workers: 4,
+        }
+    );
+
+    // Every other layer takes precedence over it
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(config_file, "port = 9000").unwrap();
+    let config_files = config_file.path().to_string_lossy().to_string();
+    let (s, trace) = LoadTrace::capture(|| {
+        MyStruct::try_from_iter_with_configopt(&[
+            "app",
+            "--config-files",
+            &config_files,
+            "--workers",
+            "1",
+        ])
+    });
+    let s = s.unwrap();
+    assert_eq!((s.host.as_str(), s.port, s.workers), ("0.0.0.0", 9000, 1));
+    assert_eq!(
+        trace.source_of("host"),
+        Some(&TraceSource::DefaultConfig {
+            path: String::from("tests/fixtures/defaults.toml")
+        })
+    );
+}
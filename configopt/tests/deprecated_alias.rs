@@ -0,0 +1,97 @@
+use configopt::{Alias, ConfigOpt, ConfigOptType, Lint};
+use std::{convert::TryFrom, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    #[configopt(alias = "old_port", deprecated = "use `port`")]
+    port: u16,
+    #[structopt(long)]
+    #[configopt(alias = "hostname")]
+    host: Option<String>,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct Nested {
+    #[structopt(long)]
+    name: Option<String>,
+    #[structopt(flatten)]
+    inner: MyStruct,
+}
+
+#[test]
+fn test_deprecated_aliases() {
+    assert_eq!(
+        ConfigOptMyStruct::deprecated_aliases(),
+        vec![
+            Alias {
+                table: Vec::new(),
+                key: "port",
+                alias_key: "old_port",
+                arg: "port",
+                alias_arg: "old-port",
+                deprecated: Some("use `port`"),
+            },
+            Alias {
+                table: Vec::new(),
+                key: "host",
+                alias_key: "hostname",
+                arg: "host",
+                alias_arg: "hostname",
+                deprecated: None,
+            }
+        ]
+    );
+}
+
+#[test]
+fn test_alias_from_cli() {
+    let s = MyStruct::try_from_iter_with_configopt(&["app", "--old-port", "8080"]).unwrap();
+    assert_eq!(s.port, 8080);
+    let s = MyStruct::try_from_iter_with_configopt(&["app", "--old-port=8080", "--hostname=x"])
+        .unwrap();
+    assert_eq!(s.port, 8080);
+    assert_eq!(s.host, Some(String::from("x")));
+    let c = ConfigOptMyStruct::from_iter_safe(&["app", "--old-port", "8080"]).unwrap();
+    assert_eq!(c.port, Some(8080));
+}
+
+#[test]
+fn test_alias_from_config_file() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "old_port = 9\nhostname = \"x\"").unwrap();
+    let c = ConfigOptMyStruct::try_from(config_file.path()).unwrap();
+    assert_eq!(c.port, Some(9));
+    assert_eq!(c.host, Some(String::from("x")));
+}
+
+#[test]
+fn test_nested_deprecated_aliases() {
+    let aliases = ConfigOptNested::deprecated_aliases();
+    assert_eq!(aliases[0].table, vec!["inner"]);
+    assert_eq!(aliases[0].alias_key, "old_port");
+
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "old_port = 1\n[inner]\nold_port = 9").unwrap();
+    let c = ConfigOptNested::try_from(config_file.path()).unwrap();
+    assert_eq!(c.inner.port, Some(9));
+
+    // Only the alias in the table of the flattened field is deprecated
+    let lints = ConfigOptNested::lint_file(config_file.path())
+        .into_iter()
+        .filter_map(|lint| match lint {
+            Lint::Deprecated {
+                key, replacement, ..
+            } => Some((key, replacement)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lints,
+        vec![(String::from("inner.old_port"), String::from("inner.port"))]
+    );
+}
@@ -0,0 +1,59 @@
+use configopt::{ConfigOpt, Diagnostic, ErrorPolicy, Level, RenderStyle};
+use std::{convert::TryFrom, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug), strict)]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+}
+
+#[test]
+fn test_config_file_diagnostic() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "host = \"localhost\"\nprot = 80").unwrap();
+    let path = config_file.path().to_string_lossy().to_string();
+    let err = ConfigOptMyStruct::try_from(config_file.path()).unwrap_err();
+
+    let diagnostic = Diagnostic::from_error(&err).unwrap();
+    assert_eq!(diagnostic.level, Level::Error);
+    assert_eq!(diagnostic.message, "unknown key `prot`");
+    assert_eq!(
+        diagnostic.location.as_ref().unwrap().path.to_string_lossy(),
+        path
+    );
+    assert_eq!(diagnostic.help.as_deref(), Some("did you mean `port`?"));
+
+    let rendered = ErrorPolicy::new().render(&err);
+    assert!(rendered.starts_with("error: unknown key `prot`\n --> "));
+    assert!(rendered.contains(&path));
+    assert!(rendered.ends_with("= help: did you mean `port`?"));
+    let rendered = ErrorPolicy::new().style(RenderStyle::Colored).render(&err);
+    assert!(rendered.starts_with("\x1b[1;31merror:\x1b[0m unknown key `prot`"));
+}
+
+#[test]
+fn test_missing_fields_diagnostic() {
+    let c = ConfigOptMyStruct::from_iter_safe(&["app", "--host", "localhost"]).unwrap();
+    let diagnostic = Diagnostic::missing_fields(&c.missing_fields()).with_usage("app [OPTIONS]");
+    let rendered = diagnostic.to_string();
+    assert!(rendered.starts_with("error: missing required config values `port`"));
+    assert!(rendered.ends_with("USAGE:\n    app [OPTIONS]\n\nFor more information try --help"));
+}
+
+#[test]
+fn test_invalid_file_diagnostic() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "host = \"localhost\"\nport = ").unwrap();
+    let err = ConfigOptMyStruct::try_from(config_file.path()).unwrap_err();
+
+    let diagnostic = Diagnostic::from_error(&err).unwrap();
+    let location = diagnostic.location.unwrap();
+    assert_eq!(location.path, config_file.path());
+    assert_eq!(location.line, 2);
+    assert_eq!(diagnostic.source_line.as_deref(), Some("port = "));
+}
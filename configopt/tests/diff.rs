@@ -0,0 +1,79 @@
+use configopt::{configopt_fields, ConfigOpt};
+use serde::Deserialize;
+use std::collections::HashMap;
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize)]
+#[configopt(derive(Debug, PartialEq), attrs(serde))]
+struct Server {
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    host: Option<String>,
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize)]
+#[configopt(derive(Debug, PartialEq), attrs(serde))]
+struct MyStruct {
+    #[structopt(long)]
+    name: String,
+    #[structopt(flatten)]
+    #[serde(flatten)]
+    server: Server,
+    #[structopt(skip)]
+    #[configopt(map)]
+    outputs: HashMap<String, Server>,
+}
+
+#[test]
+fn test_diff() {
+    let mut a = ConfigOptMyStruct::from_iter_safe(&["app", "--name", "a", "--port", "1"]).unwrap();
+    let mut b = ConfigOptMyStruct::from_iter_safe(&[
+        "app",
+        "--name",
+        "a",
+        "--port",
+        "2",
+        "--generate-config",
+    ])
+    .unwrap();
+    assert!(a.diff(&a).is_empty());
+    assert_eq!(a.diff(&b).changed(), &[String::from("port")]);
+
+    a.outputs.insert(
+        String::from("x"),
+        ConfigOptServer {
+            port: Some(1),
+            host: None,
+        },
+    );
+    a.outputs.insert(
+        String::from("y"),
+        ConfigOptServer {
+            port: Some(1),
+            host: None,
+        },
+    );
+    b.outputs.insert(
+        String::from("x"),
+        ConfigOptServer {
+            port: Some(1),
+            host: Some(String::from("x")),
+        },
+    );
+    let diff = a.diff(&b);
+    assert_eq!(
+        diff.changed(),
+        &[
+            String::from("port"),
+            String::from("outputs.x.host"),
+            String::from("outputs.y"),
+        ]
+    );
+    assert!(diff.contains("outputs"));
+    assert!(diff.contains("outputs.x"));
+    assert!(!diff.contains("name"));
+    assert!(!diff.contains("out"));
+    assert_eq!(diff.to_string(), "port\noutputs.x.host\noutputs.y\n");
+}
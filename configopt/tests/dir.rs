@@ -0,0 +1,86 @@
+use configopt::{configopt_fields, ConfigOpt, ConfigOptBool, ConfigOptSection};
+use std::{collections::HashMap, fs};
+use structopt::StructOpt;
+use tempfile::TempDir;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct Output {
+    #[structopt(long)]
+    path: String,
+}
+
+#[derive(ConfigOptSection, Debug, Default, PartialEq)]
+#[configopt(derive(Debug))]
+struct Server {
+    port: u16,
+    hosts: Vec<String>,
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    log_level: String,
+    #[structopt(long)]
+    verbose: bool,
+    #[structopt(skip)]
+    #[configopt(section)]
+    server: Server,
+    #[structopt(skip)]
+    #[configopt(map)]
+    outputs: HashMap<String, Output>,
+}
+
+#[test]
+fn test_from_dir() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("log_level"), "debug\n").unwrap();
+    fs::write(dir.path().join("verbose"), "true").unwrap();
+    fs::write(dir.path().join("server.port"), "8080\n").unwrap();
+    fs::create_dir(dir.path().join("server")).unwrap();
+    fs::write(dir.path().join("server").join("hosts"), "a,b").unwrap();
+    fs::write(dir.path().join("outputs.stdout.path"), "/dev/stdout").unwrap();
+    fs::write(dir.path().join(".hidden"), "ignored").unwrap();
+
+    let c = ConfigOptMyStruct::from_dir(dir.path()).unwrap();
+    assert_eq!(c.log_level.as_deref(), Some("debug"));
+    assert_eq!(c.verbose, ConfigOptBool::from(true));
+    assert_eq!(c.server.port, Some(8080));
+    assert_eq!(
+        c.server.hosts,
+        Some(vec![String::from("a"), String::from("b")])
+    );
+    assert_eq!(c.outputs["stdout"].path.as_deref(), Some("/dev/stdout"));
+
+    // Unknown files are reported like any other unknown key
+    fs::write(dir.path().join("colour"), "blue").unwrap();
+    let mut unknown_keys = Vec::new();
+    ConfigOptMyStruct::from_dir_collecting_unknown(dir.path(), &mut unknown_keys).unwrap();
+    assert_eq!(unknown_keys.len(), 1);
+    assert_eq!(unknown_keys[0].key, "colour");
+
+    // A value of the wrong kind names its file
+    fs::write(dir.path().join("server.port"), "http").unwrap();
+    let e = ConfigOptMyStruct::from_dir(dir.path()).unwrap_err();
+    assert!(e.to_string().contains("server.port"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_mounted_config_map() {
+    use std::os::unix::fs::symlink;
+
+    // Kubernetes writes the files to a timestamped directory and links each key through `..data`
+    let dir = TempDir::new().unwrap();
+    let data = dir.path().join("..2020_01_01_00_00_00.000000000");
+    fs::create_dir(&data).unwrap();
+    fs::write(data.join("log_level"), "info\n").unwrap();
+    symlink(&data, dir.path().join("..data")).unwrap();
+    symlink("..data/log_level", dir.path().join("log_level")).unwrap();
+
+    let c = ConfigOptMyStruct::from_dir(dir.path()).unwrap();
+    assert_eq!(c.log_level.as_deref(), Some("info"));
+    assert_eq!(c.server.port, None);
+}
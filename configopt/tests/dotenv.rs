@@ -0,0 +1,49 @@
+use configopt::{load_dotenv, ConfigOpt};
+use std::{env, fs};
+use structopt::StructOpt;
+use tempfile::TempDir;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(dotenv("tests/fixtures/test.env"))]
+struct MyStruct {
+    #[structopt(long, env = "CONFIGOPT_TEST_DOTENV_PORT")]
+    port: u16,
+    #[structopt(long, env = "CONFIGOPT_TEST_DOTENV_HOST")]
+    host: String,
+}
+
+#[test]
+fn test_dotenv_attribute() {
+    // The real environment takes precedence over the file
+    env::set_var("CONFIGOPT_TEST_DOTENV_HOST", "localhost");
+    let s = MyStruct::try_from_iter_with_configopt(&["app"]).unwrap();
+    assert_eq!(s.port, 8080);
+    assert_eq!(s.host, "localhost");
+}
+
+#[test]
+fn test_load_dotenv() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(".env");
+    fs::write(
+        &path,
+        "CONFIGOPT_TEST_LOAD_DOTENV_A=a\nCONFIGOPT_TEST_LOAD_DOTENV_B='b c'\n",
+    )
+    .unwrap();
+    env::set_var("CONFIGOPT_TEST_LOAD_DOTENV_B", "real");
+    assert_eq!(
+        load_dotenv(&path).unwrap(),
+        vec![String::from("CONFIGOPT_TEST_LOAD_DOTENV_A")]
+    );
+    assert_eq!(env::var("CONFIGOPT_TEST_LOAD_DOTENV_A").unwrap(), "a");
+    assert_eq!(env::var("CONFIGOPT_TEST_LOAD_DOTENV_B").unwrap(), "real");
+
+    // A missing file is not an error
+    assert!(load_dotenv(dir.path().join("missing.env"))
+        .unwrap()
+        .is_empty());
+
+    fs::write(&path, "NOT A VARIABLE\n").unwrap();
+    let e = load_dotenv(&path).unwrap_err();
+    assert!(e.to_string().contains("line 1"));
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "edit")]
+
+use configopt::{ConfigOpt, Value};
+use std::fs;
+use structopt::StructOpt;
+use tempfile::TempDir;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct Server {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    name: String,
+    #[structopt(flatten)]
+    server: Server,
+}
+
+const CONFIG: &str = "# The name of the app
+name = \"app\"
+
+[server]
+# Keep this in sync with the load balancer
+port = 80 # the default
+";
+
+#[test]
+fn test_set_in_file() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, CONFIG).unwrap();
+
+    // Only the value changes and every comment is kept
+    configopt::set_in_file::<ConfigOptMyStruct>(&path, &"server.port".into(), Value::from("8080"))
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        CONFIG.replace("port = 80 ", "port = 8080 ")
+    );
+    configopt::set_in_file::<ConfigOptMyStruct>(
+        &path,
+        &"server.host".into(),
+        Value::from("localhost"),
+    )
+    .unwrap();
+    let contents = fs::read_to_string(&path).unwrap();
+    assert!(contents.starts_with("# The name of the app\nname = \"app\"\n"));
+    assert!(contents.contains("host = \"localhost\""));
+
+    // Unknown keys and values which do not fit are rejected without touching the file
+    assert!(configopt::set_in_file::<ConfigOptMyStruct>(
+        &path,
+        &"server.tls".into(),
+        Value::Bool(true)
+    )
+    .is_err());
+    assert!(configopt::set_in_file::<ConfigOptMyStruct>(
+        &path,
+        &"server.port".into(),
+        Value::from("eighty")
+    )
+    .is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+
+    assert!(configopt::unset_in_file::<ConfigOptMyStruct>(&path, &"name".into()).unwrap());
+    assert!(!configopt::unset_in_file::<ConfigOptMyStruct>(&path, &"name".into()).unwrap());
+    let contents = fs::read_to_string(&path).unwrap();
+    assert!(!contents.contains("name = "));
+    assert!(contents.contains("# the default"));
+}
+
+#[test]
+fn test_set_in_new_file() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    configopt::set_in_file::<ConfigOptMyStruct>(&path, &"server.port".into(), Value::Integer(1))
+        .unwrap();
+    let c = configopt::from_toml_file::<ConfigOptMyStruct>(&path).unwrap();
+    assert_eq!(c.server.port, Some(1));
+}
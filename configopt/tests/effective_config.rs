@@ -0,0 +1,65 @@
+use configopt::{
+    configopt_fields, effective_fields, ConfigOpt, EffectiveField, LoadTrace, TraceSource,
+};
+use std::io::Write;
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    #[configopt(secret)]
+    password: String,
+}
+
+#[test]
+fn test_effective_config() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(
+        config_file,
+        "host = \"a\"\nport = 1\npassword = \"hunter2\""
+    )
+    .unwrap();
+    let config_files = config_file.path().to_string_lossy().to_string();
+    let (s, trace) = LoadTrace::capture(|| {
+        MyStruct::try_from_iter_with_configopt(&[
+            "app",
+            "--port",
+            "2",
+            "--config-files",
+            &config_files,
+        ])
+        .unwrap()
+    });
+    let config = ConfigOptMyStruct::from(s);
+    let file = Some(TraceSource::ConfigFile {
+        path: config_files.clone(),
+    });
+    // The config files themselves are not part of the config
+    assert_eq!(
+        effective_fields(&config, &trace),
+        vec![
+            EffectiveField {
+                path: String::from("host"),
+                value: String::from("\"a\""),
+                source: file.clone(),
+            },
+            EffectiveField {
+                path: String::from("port"),
+                value: String::from("2"),
+                source: Some(TraceSource::Cli),
+            },
+            EffectiveField {
+                path: String::from("password"),
+                value: String::from("<redacted>"),
+                source: file,
+            },
+        ]
+    );
+}
@@ -0,0 +1,75 @@
+use configopt::{configopt_fields, ConfigFormat, ConfigOpt, ConfigOptType};
+use structopt::StructOpt;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    tags: Vec<String>,
+    #[structopt(long)]
+    peers: Option<Vec<String>>,
+    #[structopt(long)]
+    #[configopt(empty_is_unset)]
+    hosts: Vec<String>,
+}
+
+const BASE: &str = "tags = [\"a\"]\npeers = [\"p\"]\nhosts = [\"h\"]";
+
+#[test]
+fn test_empty_is_set() {
+    // A missing key falls back to lower layers while `key = []` replaces them
+    let s = MyStruct::try_from_iter_with_files(
+        &["app"],
+        &[
+            (ConfigFormat::Toml, BASE),
+            (ConfigFormat::Toml, "tags = []\npeers = []"),
+        ],
+    )
+    .unwrap();
+    assert!(s.tags.is_empty());
+    assert_eq!(s.peers, Some(Vec::new()));
+    assert_eq!(s.hosts, vec![String::from("h")]);
+
+    let s = MyStruct::try_from_iter_with_files(&["app"], &[(ConfigFormat::Toml, "")]).unwrap();
+    assert!(s.tags.is_empty());
+    assert_eq!(s.peers, None);
+
+    let c = ConfigOptMyStruct::try_from_config_str("tags = []\npeers = []", ConfigFormat::Toml)
+        .unwrap();
+    assert_eq!(c.set_fields(), vec!["tags", "peers"]);
+    // An empty list is generated so it is still set when the config is loaded again
+    let config = c.toml_config();
+    assert!(config.contains("\ntags = []\n"));
+    assert!(config.contains("\npeers = []\n"));
+    assert!(config.contains("# hosts =\n"));
+}
+
+#[test]
+fn test_empty_is_unset() {
+    let s = MyStruct::try_from_iter_with_files(
+        &["app"],
+        &[
+            (ConfigFormat::Toml, BASE),
+            (ConfigFormat::Toml, "hosts = []"),
+        ],
+    )
+    .unwrap();
+    assert_eq!(s.hosts, vec![String::from("h")]);
+
+    let c = ConfigOptMyStruct::try_from_config_str("hosts = []", ConfigFormat::Toml).unwrap();
+    assert!(c.hosts.is_none());
+    assert!(c.is_empty());
+
+    // An empty list of a full config does not override other layers
+    let c = ConfigOptMyStruct::from(MyStruct {
+        tags: Vec::new(),
+        peers: None,
+        hosts: Vec::new(),
+        config_files: Vec::new(),
+        generate_config: false,
+        explain_config: false,
+    });
+    assert_eq!(c.tags, Some(Vec::new()));
+    assert_eq!(c.hosts, None);
+}
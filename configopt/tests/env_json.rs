@@ -0,0 +1,65 @@
+use configopt::{configopt_fields, ConfigOpt};
+use std::{collections::HashMap, env, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct Server {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug), env_json("CONFIGOPT_TEST_CONFIG_JSON"))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    verbose: bool,
+    #[structopt(skip)]
+    #[configopt(map)]
+    servers: HashMap<String, Server>,
+}
+
+#[test]
+fn test_env_json() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(
+        config_file,
+        "host = \"file\"\n[servers.a]\nport = 1\n[servers.b]\nport = 2\n"
+    )
+    .unwrap();
+    let config_file = config_file.path().to_str().unwrap();
+
+    env::set_var(
+        "CONFIGOPT_TEST_CONFIG_JSON",
+        r#"{"host": "env", "verbose": true, "servers": {"a": {"port": 9}}}"#,
+    );
+    let s =
+        MyStruct::try_from_iter_with_configopt(&["app", "--config-files", config_file]).unwrap();
+    // The environment takes precedence over config files
+    assert_eq!(s.host, "env");
+    assert!(s.verbose);
+    assert_eq!(s.servers["a"].port, 9);
+    assert_eq!(s.servers["b"].port, 2);
+
+    // The CLI takes precedence over the environment
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        config_file,
+        "--host",
+        "cli",
+    ])
+    .unwrap();
+    assert_eq!(s.host, "cli");
+
+    env::set_var("CONFIGOPT_TEST_CONFIG_JSON", "{");
+    let e = MyStruct::try_from_iter_with_configopt(&["app", "--config-files", config_file])
+        .unwrap_err();
+    assert!(e
+        .to_string()
+        .starts_with("Failed to parse environment variable 'CONFIGOPT_TEST_CONFIG_JSON'"));
+}
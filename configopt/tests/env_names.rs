@@ -0,0 +1,60 @@
+use configopt::{configopt_fields, ConfigOpt, ConfigOptType};
+use std::{env, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+#[structopt(rename_all_env = "snake")]
+struct MyStruct {
+    /// The host to bind to
+    #[structopt(long, env)]
+    configopt_test_host: String,
+    #[structopt(long, env = "CONFIGOPT_TEST_PORT_NUMBER")]
+    port: u16,
+    // An assigned name is used verbatim
+    #[structopt(long, name = "configopt-test-level", env)]
+    level: Option<String>,
+    #[structopt(long)]
+    verbose: bool,
+}
+
+#[test]
+fn test_env_names() {
+    let schema = ConfigOptMyStruct::schema();
+    let env_of = |path: &str| schema.field(path).unwrap().env.clone();
+    assert_eq!(
+        env_of("configopt_test_host").as_deref(),
+        Some("configopt_test_host")
+    );
+    assert_eq!(
+        env_of("port").as_deref(),
+        Some("CONFIGOPT_TEST_PORT_NUMBER")
+    );
+    assert_eq!(env_of("level").as_deref(), Some("configopt-test-level"));
+    assert_eq!(env_of("verbose"), None);
+
+    let config = ConfigOptMyStruct::from_iter_safe(&["app"])
+        .unwrap()
+        .toml_config();
+    assert!(config.contains("### The host to bind to\n### [env: configopt_test_host]\n"));
+    assert!(config.contains("### [env: CONFIGOPT_TEST_PORT_NUMBER]\n"));
+
+    // The environment variables take precedence over config files
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(
+        config_file,
+        "configopt_test_host = \"file\"\nport = 1\nlevel = \"info\""
+    )
+    .unwrap();
+    let config_files = config_file.path().to_string_lossy().to_string();
+    env::set_var("configopt_test_host", "env");
+    env::set_var("CONFIGOPT_TEST_PORT_NUMBER", "2");
+    env::set_var("configopt-test-level", "debug");
+    let s =
+        MyStruct::try_from_iter_with_configopt(&["app", "--config-files", &config_files]).unwrap();
+    assert_eq!(s.configopt_test_host, "env");
+    assert_eq!(s.port, 2);
+    assert_eq!(s.level.as_deref(), Some("debug"));
+}
@@ -0,0 +1,115 @@
+use configopt::{configopt_fields, ConfigOpt, ConfigOptSection};
+use std::{collections::HashMap, env, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct Output {
+    #[structopt(long)]
+    path: String,
+}
+
+#[derive(ConfigOptSection, Debug, Default, PartialEq)]
+#[configopt(derive(Debug))]
+struct Pool {
+    size: u32,
+    lazy: bool,
+}
+
+#[derive(ConfigOptSection, Debug, Default, PartialEq)]
+#[configopt(derive(Debug))]
+struct Server {
+    port: u16,
+    hosts: Vec<String>,
+    #[configopt(section)]
+    pool: Pool,
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug), env_prefix("CONFIGOPT_TEST_APP_"))]
+struct MyStruct {
+    #[structopt(long)]
+    log_level: String,
+    #[structopt(skip)]
+    #[configopt(section)]
+    server: Server,
+    #[structopt(skip)]
+    #[configopt(map)]
+    outputs: HashMap<String, Output>,
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(
+    derive(Debug),
+    env_prefix("CONFIGOPT_TEST_DOTTED_"),
+    env_separator(".")
+)]
+struct Dotted {
+    #[structopt(skip)]
+    #[configopt(section)]
+    server: Server,
+}
+
+#[test]
+fn test_env_prefix() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(
+        config_file,
+        "log_level = 'info'\n[server]\nport = 1\nhosts = ['file']\n[server.pool]\nsize = 2\n"
+    )
+    .unwrap();
+    let config_file = config_file.path().to_str().unwrap();
+
+    env::set_var("CONFIGOPT_TEST_APP_SERVER__PORT", "8080");
+    env::set_var("CONFIGOPT_TEST_APP_SERVER__HOSTS", "a, b");
+    env::set_var("CONFIGOPT_TEST_APP_SERVER__POOL__LAZY", "true");
+    env::set_var("CONFIGOPT_TEST_APP_OUTPUTS__stdout__PATH", "/dev/stdout");
+    // Variables which do not refer to a field are ignored
+    env::set_var("CONFIGOPT_TEST_APP_SERVICE_PORT", "tcp://10.0.0.1:80");
+    let s =
+        MyStruct::try_from_iter_with_configopt(&["app", "--config-files", config_file]).unwrap();
+    // The environment takes precedence over config files
+    assert_eq!(s.log_level, "info");
+    assert_eq!(s.server.port, 8080);
+    assert_eq!(s.server.hosts, vec!["a", "b"]);
+    assert_eq!(
+        s.server.pool,
+        Pool {
+            size: 2,
+            lazy: true
+        }
+    );
+    assert_eq!(s.outputs["stdout"].path, "/dev/stdout");
+
+    // The CLI takes precedence over the environment
+    env::set_var("CONFIGOPT_TEST_APP_LOG_LEVEL", "debug");
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        config_file,
+        "--log-level",
+        "warn",
+    ])
+    .unwrap();
+    assert_eq!(s.log_level, "warn");
+
+    // A value of the wrong kind names the variable
+    env::set_var("CONFIGOPT_TEST_APP_SERVER__PORT", "http");
+    let e = MyStruct::try_from_iter_with_configopt(&["app", "--config-files", config_file])
+        .unwrap_err();
+    assert!(e
+        .to_string()
+        .contains("environment variable 'CONFIGOPT_TEST_APP_SERVER__PORT'"));
+}
+
+#[test]
+fn test_env_separator() {
+    env::set_var("CONFIGOPT_TEST_DOTTED_server.pool.size", "4");
+    env::set_var("CONFIGOPT_TEST_DOTTED_server.port", "1");
+    let s = Dotted::try_from_iter_with_configopt(&["app"]).unwrap();
+    assert_eq!(s.server.port, 1);
+    assert_eq!(s.server.pool.size, 4);
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "env-encoding")]
+
+use configopt::ConfigOpt;
+use std::env;
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long, env = "CONFIGOPT_TEST_MULTI_LINE")]
+    #[configopt(decode_env)]
+    multi_line: String,
+    #[structopt(long, env = "CONFIGOPT_TEST_PORT")]
+    #[configopt(decode_env)]
+    port: u16,
+    #[structopt(long, env = "CONFIGOPT_TEST_RAW")]
+    #[configopt(decode_env)]
+    raw: Option<String>,
+    #[structopt(long, env = "CONFIGOPT_TEST_PLAIN")]
+    plain: Option<String>,
+}
+
+#[test]
+fn test_env_values() {
+    env::set_var("CONFIGOPT_TEST_MULTI_LINE", "base64:bGluZSAxCmxpbmUgMg==");
+    env::set_var("CONFIGOPT_TEST_PORT", "json:8080");
+    env::set_var("CONFIGOPT_TEST_RAW", "raw:base64:x");
+    env::set_var("CONFIGOPT_TEST_PLAIN", "base64:YQ==");
+    let s = MyStruct::try_from_iter_with_configopt(&["app"]).unwrap();
+    assert_eq!(s.multi_line, "line 1\nline 2");
+    assert_eq!(s.port, 8080);
+    assert_eq!(s.raw, Some(String::from("base64:x")));
+    // Only the fields which opted in are decoded
+    assert_eq!(s.plain, Some(String::from("base64:YQ==")));
+
+    env::set_var("CONFIGOPT_TEST_PORT", "percent:%zz");
+    let e = MyStruct::try_from_iter_with_configopt(&["app"]).unwrap_err();
+    assert!(e.to_string().contains("from the environment"));
+}
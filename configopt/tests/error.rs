@@ -0,0 +1,74 @@
+use configopt::{ConfigOpt, Diagnostic, Error, ErrorCategory, TraceSource};
+use std::{convert::TryFrom, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct Server {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(flatten)]
+    server: Server,
+}
+
+#[test]
+fn test_field_error() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "host = \"localhost\"\n[server]\nport = \"x\"").unwrap();
+    let path = config_file.path().to_string_lossy().to_string();
+    let err = ConfigOptMyStruct::try_from(config_file.path()).unwrap_err();
+    match &err {
+        Error::Field {
+            path: field,
+            source,
+            location,
+            message,
+        } => {
+            assert_eq!(field, "server.port");
+            assert_eq!(source, &TraceSource::ConfigFile { path: path.clone() });
+            let location = location.as_ref().unwrap();
+            assert_eq!(location.line, 3);
+            assert!(message.starts_with("invalid type: string"));
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+    assert_eq!(err.field_path(), Some("server.port"));
+    assert_eq!(err.category(), ErrorCategory::ConfigFile);
+    assert!(err
+        .to_string()
+        .starts_with(&format!("Invalid value for `server.port` at {}:3:", path)));
+
+    let diagnostic = Diagnostic::from_error(&err).unwrap();
+    assert!(diagnostic
+        .message
+        .starts_with("invalid value for `server.port` from config file"));
+    assert_eq!(diagnostic.source_line.as_deref(), Some("port = \"x\""));
+}
+
+#[test]
+fn test_missing_fields_error() {
+    let c = ConfigOptMyStruct::from_iter_safe(&["app"]).unwrap();
+    let err = MyStruct::try_from(c).unwrap_err();
+    match &err {
+        Error::MissingFields(fields) => {
+            assert_eq!(fields, &[String::from("host"), String::from("port")])
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+    assert_eq!(
+        err.to_string(),
+        "Missing required config values `host`, `port`"
+    );
+    assert_eq!(err.category(), ErrorCategory::Usage);
+
+    let c = ConfigOptMyStruct::from_iter_safe(&["app", "--host", "a", "--port", "1"]).unwrap();
+    assert_eq!(MyStruct::try_from(c).unwrap().server.port, 1);
+}
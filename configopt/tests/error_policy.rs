@@ -0,0 +1,50 @@
+use configopt::{ConfigOpt, Error, ErrorCategory, ErrorPolicy, RenderStyle};
+use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[test]
+fn test_error_policy() {
+    let file_error = Error::ConfigFile(
+        "/tmp/missing.toml".into(),
+        IoError::new(IoErrorKind::NotFound, "not found"),
+    );
+    assert_eq!(file_error.category(), ErrorCategory::ConfigFile);
+
+    let usage_error = MyStruct::try_from_iter_with_configopt(&["app", "--unknown"]).unwrap_err();
+    assert_eq!(usage_error.category(), ErrorCategory::Usage);
+
+    let help = MyStruct::try_from_iter_with_configopt(&["app", "--help"]).unwrap_err();
+    assert_eq!(help.category(), ErrorCategory::Help);
+
+    let policy = ErrorPolicy::new();
+    assert_eq!(policy.code_for(ErrorCategory::Help), 0);
+    assert_eq!(policy.code_for(ErrorCategory::ConfigFile), 1);
+    // Configopt errors get the same prefix as clap errors
+    assert!(policy
+        .render(&file_error)
+        .starts_with("error: Failed to parse file"));
+
+    let policy = ErrorPolicy::new()
+        .style(RenderStyle::Json)
+        .exit_code(ErrorCategory::ConfigFile, 66);
+    assert_eq!(policy.code_for(ErrorCategory::ConfigFile), 66);
+    assert_eq!(policy.code_for(ErrorCategory::Usage), 1);
+    let rendered = policy.render(&file_error);
+    assert!(rendered.starts_with("{\"category\":\"config_file\""));
+    let rendered = policy.render(&usage_error);
+    assert!(rendered.contains("\"kind\":\"UnknownArgument\""));
+    assert!(!rendered.contains("\\u001b"));
+
+    let policy = ErrorPolicy::new().renderer(|e, category| format!("{:?}: {}", category, e));
+    assert_eq!(
+        policy.render(&file_error),
+        "ConfigFile: Failed to parse file '/tmp/missing.toml', err: not found"
+    );
+}
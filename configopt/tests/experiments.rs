@@ -0,0 +1,60 @@
+use configopt::{configopt_fields, set_experiment_seed, ConfigOpt, LoadTrace};
+use std::io::Write;
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    batch_size: u32,
+}
+
+#[test]
+fn test_experiments() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(
+        config_file,
+        r#"
+host = "a"
+batch_size = {{ variants = [{{ value = 10, weight = 90 }}, {{ value = 50, weight = 10 }}] }}
+"#
+    )
+    .unwrap();
+    let config_files = config_file.path().to_string_lossy().to_string();
+    let parse = || {
+        LoadTrace::capture(|| {
+            MyStruct::try_from_iter_with_configopt(&["app", "--config-files", &config_files])
+                .unwrap()
+        })
+    };
+
+    // Each seed always selects the same variant
+    let mut selected = Vec::new();
+    for seed in 0..100 {
+        set_experiment_seed(seed.to_string());
+        let (s, trace) = parse();
+        assert_eq!(s.host, "a");
+        assert_eq!(parse().0.batch_size, s.batch_size);
+        let choice = &trace.variants[0];
+        assert_eq!(choice.path, "batch_size");
+        assert_eq!(choice.total_weight, 100);
+        assert_eq!(s.batch_size, [10, 50][choice.index]);
+        selected.push(s.batch_size);
+    }
+    assert!(selected.contains(&10));
+    assert!(selected.contains(&50));
+
+    // The CLI still takes precedence
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--batch-size",
+        "1",
+        "--config-files",
+        &config_files,
+    ])
+    .unwrap();
+    assert_eq!(s.batch_size, 1);
+}
@@ -0,0 +1,67 @@
+use configopt::{configopt_fields, ConfigOpt, Error};
+use std::io::Write;
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long, default_value = "4")]
+    workers: u32,
+    #[structopt(long)]
+    name: Option<String>,
+}
+
+#[test]
+fn test_explain_config() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "host = \"file\"").unwrap();
+    let path = config_file.path().to_str().unwrap();
+
+    let e = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--explain-config",
+        "--config-files",
+        path,
+    ])
+    .unwrap_err();
+    let table = match &e {
+        Error::ConfigExplained(table) => table,
+        e => panic!("expected an explained config not {:?}", e),
+    };
+    let lines = table.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines[0].split_whitespace().collect::<Vec<_>>(),
+        ["FIELD", "VALUE", "SOURCE"]
+    );
+    assert!(lines[1].starts_with("host"));
+    assert!(lines[1].ends_with(&format!("\"file\"   config file '{}'", path)));
+    assert_eq!(
+        lines[2].split_whitespace().collect::<Vec<_>>(),
+        ["workers", "4", "default"]
+    );
+    assert_eq!(
+        lines[3].split_whitespace().collect::<Vec<_>>(),
+        ["name", "<unset>"]
+    );
+    // The table is printed to stdout and exits successfully like `--generate-config`
+    assert!(!e.use_stderr());
+
+    // The command line wins over the config file
+    let e = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--explain-config",
+        "--host",
+        "cli",
+        "--config-files",
+        path,
+    ])
+    .unwrap_err();
+    assert!(e
+        .to_string()
+        .lines()
+        .any(|l| l.starts_with("host") && l.ends_with("\"cli\"    the command line")));
+}
@@ -0,0 +1,57 @@
+use configopt::{configopt_fields, flags::FeatureFlags, ConfigFormat, ConfigOpt};
+use structopt::StructOpt;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Clone, Debug, PartialEq))]
+struct App {
+    #[structopt(long)]
+    name: Option<String>,
+    #[structopt(skip)]
+    #[configopt(file_only)]
+    flags: FeatureFlags,
+}
+
+const LOW: &str = "[flags]\na = true\nb = true\n";
+const HIGH: &str = "[flags]\nb = false\nc = { percentage = 100, targets = [\"x\"] }\n";
+
+#[test]
+fn test_flags_merged_by_flag() {
+    let files = [(ConfigFormat::Toml, LOW), (ConfigFormat::Toml, HIGH)];
+    let app = App::try_from_iter_with_files(&["app"], &files).unwrap();
+    assert!(app.flags.is_enabled("a"));
+    assert!(!app.flags.is_enabled("b"));
+    assert!(app.flags.is_enabled("c"));
+    assert!(app.flags.is_enabled_for("c", "x"));
+    assert!(!app.flags.is_enabled("missing"));
+}
+
+#[test]
+fn test_flags_diffed_and_set_by_flag() {
+    let low = toml::from_str::<ConfigOptApp>(LOW).unwrap();
+    let mut high = toml::from_str::<ConfigOptApp>(HIGH).unwrap();
+    assert_eq!(
+        low.diff(&high).changed(),
+        &[
+            String::from("flags.a"),
+            String::from("flags.b"),
+            String::from("flags.c"),
+        ]
+    );
+
+    // Setting one flag keeps the others
+    let before = high.clone();
+    high.set(&"flags.d".into(), true).unwrap();
+    assert_eq!(before.diff(&high).changed(), &[String::from("flags.d")]);
+    let flags = high.flags.unwrap();
+    assert!(flags.is_enabled("d"));
+    assert!(flags.get("c").is_some());
+
+    let schema = ConfigOptApp::schema();
+    assert!(schema.field("flags.*").is_some());
+    let json_schema = serde_json::from_str::<serde_json::Value>(&schema.to_json_schema()).unwrap();
+    assert_eq!(
+        json_schema["properties"]["flags"]["additionalProperties"],
+        serde_json::json!({})
+    );
+}
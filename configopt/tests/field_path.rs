@@ -0,0 +1,58 @@
+use configopt::{ConfigOpt, FieldPath, Value};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct Server {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    name: String,
+    #[structopt(long)]
+    tags: Vec<String>,
+    #[structopt(long)]
+    #[configopt(secret)]
+    password: String,
+    #[structopt(flatten)]
+    server: Server,
+}
+
+#[test]
+fn test_get_and_set() {
+    let mut c =
+        ConfigOptMyStruct::from_iter_safe(&["app", "--name", "app", "--port", "80"]).unwrap();
+    assert_eq!(c.get(&"name".into()), Some(Value::from("app")));
+    assert_eq!(c.get(&"server.port".into()), Some(Value::Integer(80)));
+    assert_eq!(c.get(&"server.host".into()), None);
+
+    // Strings are parsed as the kind of value the field holds
+    c.set(&"server.port".into(), "8080").unwrap();
+    c.set(&"server.host".into(), "localhost").unwrap();
+    c.set(&"tags".into(), vec![Value::from("a"), Value::from("b")])
+        .unwrap();
+    assert_eq!(c.server.port, Some(8080));
+    assert_eq!(c.server.host, Some(String::from("localhost")));
+    assert_eq!(c.tags, Some(vec![String::from("a"), String::from("b")]));
+    assert_eq!(c.name, Some(String::from("app")));
+
+    // Secrets can be set but are never returned
+    c.set(&"password".into(), "hunter2").unwrap();
+    assert_eq!(c.password, Some(String::from("hunter2")));
+    assert_eq!(c.get(&"password".into()), None);
+
+    assert_eq!(
+        c.set(&"missing".into(), 1i64).unwrap_err(),
+        "`missing` is not a known key"
+    );
+    assert!(c.set(&"server.port".into(), "not a number").is_err());
+    assert!(c.set(&"server.port".into(), 100_000i64).is_err());
+    assert_eq!(c.server.port, Some(8080));
+    assert!("server..port".parse::<FieldPath>().is_err());
+}
@@ -0,0 +1,48 @@
+use configopt::{configopt_fields, ConfigOpt};
+use std::{convert::TryFrom, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    name: String,
+    #[structopt(skip = 64u32)]
+    #[configopt(file_only)]
+    buffer_size: u32,
+    #[structopt(skip)]
+    #[configopt(file_only)]
+    tuning: bool,
+}
+
+#[test]
+fn test_file_only() {
+    // Config only fields are not CLI flags
+    assert!(
+        MyStruct::try_from_iter_with_configopt(&["app", "--name", "a", "--buffer-size", "1"])
+            .is_err()
+    );
+
+    // Config only fields do not block conversion and use the `skip` default
+    let c = ConfigOptMyStruct::from_iter_safe(&["app", "--name", "a"]).unwrap();
+    assert!(c.is_convertible());
+    assert!(c.missing_fields().is_empty());
+    let s = MyStruct::try_from(c).unwrap();
+    assert_eq!(s.buffer_size, 64);
+    assert!(!s.tuning);
+
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "buffer_size = 128\ntuning = true").unwrap();
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--name",
+        "a",
+        "--config-files",
+        config_file.path().to_str().unwrap(),
+    ])
+    .unwrap();
+    assert_eq!(s.buffer_size, 128);
+    assert!(s.tuning);
+}
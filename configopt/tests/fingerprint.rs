@@ -0,0 +1,57 @@
+use configopt::{ConfigOpt, ConfigOptType};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    /// The host to connect to
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: Option<u16>,
+    #[structopt(long)]
+    #[configopt(secret)]
+    password: String,
+    #[structopt(long, hidden = true)]
+    workers: Option<u8>,
+}
+
+fn config(host: &str, port: Option<u16>, password: &str) -> ConfigOptMyStruct {
+    ConfigOptMyStruct {
+        host: Some(String::from(host)),
+        port,
+        password: Some(String::from(password)),
+        password_file: None,
+        workers: None,
+    }
+}
+
+#[test]
+fn test_fingerprint() {
+    let fingerprint = config("a", Some(1), "hunter2").fingerprint().unwrap();
+    assert_eq!(
+        fingerprint,
+        config("a", Some(1), "hunter2").fingerprint().unwrap()
+    );
+    assert_eq!(fingerprint.to_string().len(), 16);
+
+    // Secrets are excluded
+    assert_eq!(
+        fingerprint,
+        config("a", Some(1), "swordfish").fingerprint().unwrap()
+    );
+
+    assert_ne!(
+        fingerprint,
+        config("b", Some(1), "hunter2").fingerprint().unwrap()
+    );
+    assert_ne!(
+        fingerprint,
+        config("a", None, "hunter2").fingerprint().unwrap()
+    );
+
+    // Hidden fields are hashed too
+    let mut hidden = config("a", Some(1), "hunter2");
+    hidden.workers = Some(4);
+    assert_ne!(fingerprint, hidden.fingerprint().unwrap());
+}
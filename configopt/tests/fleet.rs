@@ -0,0 +1,74 @@
+use configopt::{ConfigFormat, ConfigOpt, Targets};
+use std::convert::TryFrom;
+use structopt::StructOpt;
+use tempfile::TempDir;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Clone, Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    name: String,
+    #[structopt(long)]
+    url: String,
+    #[structopt(long)]
+    data_dir: String,
+}
+
+fn targets() -> Targets {
+    vec![("web-1", "10.0.0.1"), ("web-2", "10.0.0.2")]
+        .into_iter()
+        .map(|(host, ip)| {
+            let variables = vec![(String::from("ip"), String::from(ip))]
+                .into_iter()
+                .collect();
+            (String::from(host), variables)
+        })
+        .collect()
+}
+
+fn template() -> ConfigOptMyStruct {
+    ConfigOptMyStruct::from_iter_safe(&[
+        "app",
+        "--name",
+        "web",
+        "--url",
+        "http://${ip}:80",
+        "--data-dir",
+        "/var/lib/${name}",
+    ])
+    .unwrap()
+}
+
+#[test]
+fn test_render() {
+    let configs = configopt::render(&template(), &targets()).unwrap();
+    assert_eq!(configs.len(), 2);
+    assert_eq!(configs["web-1"].url.as_deref(), Some("http://10.0.0.1:80"));
+    assert_eq!(configs["web-2"].url.as_deref(), Some("http://10.0.0.2:80"));
+    // Fields of the template can be referenced too
+    assert_eq!(configs["web-2"].data_dir.as_deref(), Some("/var/lib/web"));
+
+    let mut targets = targets();
+    targets.insert(String::from("web-3"), Default::default());
+    let e = configopt::render(&template(), &targets).unwrap_err();
+    assert!(e.to_string().contains("target `web-3` `ip` is not set"));
+}
+
+#[test]
+fn test_render_to_dir() {
+    let dir = TempDir::new().unwrap();
+    let paths =
+        configopt::render_to_dir(&template(), &targets(), dir.path(), ConfigFormat::Toml).unwrap();
+    assert_eq!(
+        paths,
+        vec![dir.path().join("web-1.toml"), dir.path().join("web-2.toml")]
+    );
+    let c = ConfigOptMyStruct::try_from(paths[0].as_path()).unwrap();
+    assert_eq!(c.url.as_deref(), Some("http://10.0.0.1:80"));
+
+    let mut targets = targets();
+    targets.insert(String::from("../web-3"), targets["web-1"].clone());
+    assert!(
+        configopt::render_to_dir(&template(), &targets, dir.path(), ConfigFormat::Toml).is_err()
+    );
+}
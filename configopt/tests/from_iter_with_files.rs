@@ -0,0 +1,36 @@
+use configopt::{configopt_fields, ConfigFormat, ConfigOpt};
+use structopt::StructOpt;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long, default_value = "1")]
+    workers: u32,
+}
+
+#[test]
+fn test_from_iter_with_files() {
+    let files = [
+        (ConfigFormat::Toml, "host = \"a\"\nport = 1\nworkers = 2"),
+        (ConfigFormat::Json, r#"{"host": "b"}"#),
+    ];
+    let s = MyStruct::try_from_iter_with_files(&["app", "--port", "3"], &files).unwrap();
+    // Later documents take precedence and the CLI takes precedence over all of them
+    assert_eq!((s.host.as_str(), s.port, s.workers), ("b", 3, 2));
+
+    // Config files on the CLI are not read
+    let s = MyStruct::try_from_iter_with_files(
+        &["app", "--config-files", "/does/not/exist.toml"],
+        &files[..1],
+    )
+    .unwrap();
+    assert_eq!((s.host.as_str(), s.port), ("a", 1));
+
+    let err =
+        MyStruct::try_from_iter_with_files(&["app"], &[(ConfigFormat::Json, "[]")]).unwrap_err();
+    assert!(err.to_string().contains("<memory>"));
+}
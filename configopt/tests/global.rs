@@ -0,0 +1,26 @@
+use configopt::ConfigOpt;
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug), global)]
+struct MyStruct {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[test]
+fn test_global() {
+    assert!(MyStruct::try_global().is_none());
+    assert!(MyStruct::try_current().is_none());
+    assert!(std::panic::catch_unwind(MyStruct::global).is_err());
+
+    let initialized =
+        MyStruct::init_global(|| MyStruct::try_from_iter_with_configopt(&["app", "--port", "1"]))
+            .unwrap();
+    assert_eq!(initialized.port, 1);
+    // Once initialized the loader is never called again
+    let initialized = MyStruct::init_global(|| unreachable!()).unwrap();
+    assert_eq!(initialized.port, 1);
+    assert_eq!(MyStruct::global(), &MyStruct { port: 1 });
+    assert_eq!(MyStruct::try_current().unwrap().port, 1);
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "hocon")]
+
+use configopt::{ConfigFormat, ConfigOpt, ConfigOptSection, ConfigOptType};
+use std::{convert::TryFrom, fs};
+use structopt::StructOpt;
+use tempfile::TempDir;
+
+#[derive(ConfigOptSection, Debug, Default, PartialEq)]
+#[configopt(derive(Debug))]
+struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    url: String,
+    #[structopt(long)]
+    name: Option<String>,
+    #[structopt(skip)]
+    #[configopt(section)]
+    server: Server,
+}
+
+#[test]
+fn test_hocon_file() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("server.conf"), "server.port = 8080\n").unwrap();
+    let path = dir.path().join("application.conf");
+    fs::write(
+        &path,
+        r#"
+        include "server.conf"
+        server {
+            host = localhost
+        }
+        url = "http://"${server.host}":"${server.port}
+        name = ${?CONFIGOPT_TEST_HOCON_UNSET}
+        "#,
+    )
+    .unwrap();
+    let c = ConfigOptMyStruct::try_from(path.as_path()).unwrap();
+    assert_eq!(c.server.host.as_deref(), Some("localhost"));
+    assert_eq!(c.server.port, Some(8080));
+    assert_eq!(c.url.as_deref(), Some("http://localhost:8080"));
+    // An optional substitution which is not set leaves the field unset
+    assert_eq!(c.name, None);
+
+    // Written files are JSON which is valid HOCON
+    c.write_to_file(&path, ConfigFormat::Hocon).unwrap();
+    let read = ConfigOptMyStruct::try_from(path.as_path()).unwrap();
+    assert_eq!(read.url, c.url);
+    assert_eq!(read.server.port, Some(8080));
+}
@@ -0,0 +1,55 @@
+use configopt::{configopt_fields, ConfigOpt};
+use std::fs;
+use structopt::StructOpt;
+use tempfile::TempDir;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug), strict)]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    password: String,
+}
+
+#[test]
+fn test_include() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("common.toml"),
+        "host = \"common\"\nport = 1\n",
+    )
+    .unwrap();
+    fs::create_dir(dir.path().join("private")).unwrap();
+    fs::write(
+        dir.path().join("private/secrets.toml"),
+        "password = \"hunter2\"\nport = 2\n",
+    )
+    .unwrap();
+    let main = dir.path().join("main.toml");
+    fs::write(
+        &main,
+        "include = [\"common.toml\", \"private/secrets.toml\"]\nhost = \"main\"\n",
+    )
+    .unwrap();
+
+    let s =
+        MyStruct::try_from_iter_with_configopt(&["app", "--config-files", main.to_str().unwrap()])
+            .unwrap();
+    assert_eq!(s.host, "main");
+    assert_eq!(s.port, 2);
+    assert_eq!(s.password, "hunter2");
+
+    fs::write(
+        dir.path().join("common.toml"),
+        "include = [\"main.toml\"]\n",
+    )
+    .unwrap();
+    let e =
+        MyStruct::try_from_iter_with_configopt(&["app", "--config-files", main.to_str().unwrap()])
+            .unwrap_err();
+    assert!(e.to_string().contains("include cycle"));
+}
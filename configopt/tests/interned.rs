@@ -0,0 +1,45 @@
+use configopt::{configopt_fields, ConfigOpt, Interned};
+use std::{collections::HashMap, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct Server {
+    #[structopt(long)]
+    region: Interned,
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    default_region: Interned,
+    #[structopt(skip)]
+    #[configopt(map)]
+    servers: HashMap<String, Server>,
+}
+
+#[test]
+fn test_interned() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    for i in 0..100 {
+        writeln!(config_file, "[servers.s{}]\nregion = \"us-east-1\"", i).unwrap();
+    }
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--default-region",
+        "us-east-1",
+        "--config-files",
+        config_file.path().to_str().unwrap(),
+    ])
+    .unwrap();
+    assert_eq!(s.servers.len(), 100);
+    // Every repeated value shares the allocation of the value parsed from the CLI
+    assert!(s
+        .servers
+        .values()
+        .all(|server| Interned::ptr_eq(&server.region, &s.default_region)));
+    assert_eq!(s.default_region, "us-east-1");
+}
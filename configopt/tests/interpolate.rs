@@ -0,0 +1,76 @@
+use configopt::{configopt_fields, ConfigOpt, ConfigOptType};
+use std::{env, io::Write, path::PathBuf};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct Server {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug), interpolate)]
+struct MyStruct {
+    #[structopt(long)]
+    data_dir: PathBuf,
+    #[structopt(long)]
+    url: String,
+    #[structopt(long)]
+    #[configopt(no_interpolate)]
+    template: String,
+    #[structopt(skip)]
+    #[configopt(map)]
+    servers: std::collections::HashMap<String, Server>,
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct Verbatim {
+    #[structopt(long)]
+    url: String,
+}
+
+#[test]
+fn test_interpolate() {
+    env::set_var("CONFIGOPT_TEST_INTERPOLATE_HOME", "/home/app");
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(
+        config_file,
+        r#"
+data_dir = "${{CONFIGOPT_TEST_INTERPOLATE_HOME}}/data"
+url = "http://${{servers.a.host}}:${{servers.a.port}}/$${{literal}}"
+template = "${{unresolved}}"
+
+[servers.a]
+host = "example.com"
+port = 8080
+"#
+    )
+    .unwrap();
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        config_file.path().to_str().unwrap(),
+    ])
+    .unwrap();
+    assert_eq!(s.data_dir, PathBuf::from("/home/app/data"));
+    assert_eq!(s.url, "http://example.com:8080/${literal}");
+    assert_eq!(s.template, "${unresolved}");
+
+    let mut c =
+        ConfigOptMyStruct::from_iter_safe(&["app", "--url", "${CONFIGOPT_TEST_UNSET}"]).unwrap();
+    let e = c.interpolate_config().unwrap_err();
+    assert!(e
+        .to_string()
+        .contains("`url` `CONFIGOPT_TEST_UNSET` is not set"));
+
+    // Only types which opt in are interpolated
+    let s = Verbatim::try_from_iter_with_configopt(&["app", "--url", "http://${host}/"]).unwrap();
+    assert_eq!(s.url, "http://${host}/");
+}
@@ -0,0 +1,81 @@
+#![cfg(any(feature = "ini", feature = "properties"))]
+
+use configopt::{ConfigFormat, ConfigOpt, ConfigOptSection, ConfigOptType};
+use std::{convert::TryFrom, fs};
+use structopt::StructOpt;
+use tempfile::TempDir;
+
+#[derive(ConfigOptSection, Debug, Default, PartialEq)]
+#[configopt(derive(Debug))]
+struct Server {
+    port: u16,
+    hosts: Vec<String>,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    name: String,
+    #[structopt(long)]
+    ratio: f64,
+    #[structopt(skip)]
+    #[configopt(section)]
+    server: Server,
+}
+
+#[cfg(feature = "ini")]
+#[test]
+fn test_ini_file() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.ini");
+    fs::write(
+        &path,
+        "; The service\nname = api\nratio = 0.5\n\n[server]\nport = 8080\nhosts = a, b\n",
+    )
+    .unwrap();
+    let c = ConfigOptMyStruct::try_from(path.as_path()).unwrap();
+    assert_eq!(c.name.as_deref(), Some("api"));
+    assert_eq!(c.ratio, Some(0.5));
+    assert_eq!(c.server.port, Some(8080));
+    assert_eq!(
+        c.server.hosts,
+        Some(vec![String::from("a"), String::from("b")])
+    );
+
+    // A value of the wrong type names its key and line
+    fs::write(&path, "[server]\nport = http\n").unwrap();
+    let e = ConfigOptMyStruct::try_from(path.as_path()).unwrap_err();
+    assert!(e.to_string().contains("line 2: `server.port`"));
+
+    // Written files read back the same values
+    c.write_to_file(&path, ConfigFormat::Ini).unwrap();
+    let read = ConfigOptMyStruct::try_from(path.as_path()).unwrap();
+    assert_eq!(read.server.port, Some(8080));
+    assert_eq!(read.server.hosts, c.server.hosts);
+}
+
+#[cfg(feature = "properties")]
+#[test]
+fn test_properties_file() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.properties");
+    fs::write(
+        &path,
+        "# The service\nname=my\\u0020api\nratio: 0.5\nserver.port 8080\nserver.hosts = a,\\\n    b\n",
+    )
+    .unwrap();
+    let c = ConfigOptMyStruct::try_from(path.as_path()).unwrap();
+    assert_eq!(c.name.as_deref(), Some("my api"));
+    assert_eq!(c.ratio, Some(0.5));
+    assert_eq!(c.server.port, Some(8080));
+    assert_eq!(
+        c.server.hosts,
+        Some(vec![String::from("a"), String::from("b")])
+    );
+
+    c.write_to_file(&path, ConfigFormat::Properties).unwrap();
+    let read = ConfigOptMyStruct::try_from(path.as_path()).unwrap();
+    assert_eq!(read.name, c.name);
+    assert_eq!(read.server.hosts, c.server.hosts);
+}
@@ -0,0 +1,79 @@
+use configopt::{configopt_fields, ConfigOpt, ConfigOptType};
+use serde::Deserialize;
+use std::{convert::TryFrom, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+// A third party type which only implements `Deserialize`
+#[derive(Deserialize, Debug, PartialEq)]
+struct Endpoint {
+    host: String,
+    port: u16,
+}
+
+impl Endpoint {
+    fn local() -> Self {
+        Self {
+            host: String::from("localhost"),
+            port: 80,
+        }
+    }
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+struct MyStruct {
+    #[structopt(long)]
+    name: String,
+    #[structopt(skip = Endpoint::local())]
+    #[configopt(leaf)]
+    endpoint: Endpoint,
+    #[structopt(skip)]
+    #[configopt(leaf)]
+    fallback: Option<Endpoint>,
+}
+
+#[test]
+fn test_leaf() {
+    // A leaf is required until a config file sets it
+    let c = ConfigOptMyStruct::from_iter_safe(&["app", "--name", "a"]).unwrap();
+    assert_eq!(c.missing_fields(), vec!["endpoint"]);
+    // Leaves are not required to implement `Serialize` so only their key is written
+    assert!(c.toml_config().contains("# endpoint =\n"));
+    assert!(MyStruct::try_from(c).is_err());
+
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "[endpoint]\nhost = \"a\"\nport = 1").unwrap();
+    let mut other_file = NamedTempFile::new().unwrap();
+    writeln!(other_file, "[endpoint]\nhost = \"b\"\nport = 2").unwrap();
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--name",
+        "a",
+        "--config-files",
+        config_file.path().to_str().unwrap(),
+        other_file.path().to_str().unwrap(),
+    ])
+    .unwrap();
+    // The whole value is replaced by the config file with the highest precedence
+    assert_eq!(
+        s.endpoint,
+        Endpoint {
+            host: String::from("b"),
+            port: 2
+        }
+    );
+    assert_eq!(s.fallback, None);
+
+    // A partial table is an error instead of being merged
+    let mut partial_file = NamedTempFile::new().unwrap();
+    writeln!(partial_file, "[endpoint]\nhost = \"a\"").unwrap();
+    assert!(MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--name",
+        "a",
+        "--config-files",
+        partial_file.path().to_str().unwrap(),
+    ])
+    .is_err());
+}
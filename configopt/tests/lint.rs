@@ -0,0 +1,64 @@
+use configopt::{ConfigOpt, Level, Lint};
+use std::io::Write;
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    #[configopt(alias = "old_port", deprecated = "use `port`")]
+    port: u16,
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    workers: Option<u32>,
+}
+
+#[test]
+fn test_lint_file() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(
+        config_file,
+        "old_port = 80\nworkers = \"many\"\nhots = \"x\""
+    )
+    .unwrap();
+    let lints = ConfigOptMyStruct::lint_file(config_file.path());
+    assert_eq!(
+        lints,
+        vec![
+            Lint::Deprecated {
+                key: String::from("old_port"),
+                replacement: String::from("port"),
+                note: String::from("use `port`"),
+            },
+            Lint::Missing {
+                key: String::from("host"),
+            },
+            Lint::TypeMismatch {
+                key: String::from("workers"),
+                expected: String::from("integer"),
+                found: String::from("string"),
+            },
+            Lint::Unknown {
+                key: String::from("hots"),
+            },
+        ]
+    );
+    assert_eq!(lints[0].level(), Level::Warning);
+    assert_eq!(
+        lints[1].to_string(),
+        "error: required key `host` is not set"
+    );
+
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "port = 80\nhost = \"localhost\"").unwrap();
+    assert!(ConfigOptMyStruct::lint_file(config_file.path()).is_empty());
+
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "port = ").unwrap();
+    assert!(matches!(
+        ConfigOptMyStruct::lint_file(config_file.path()).as_slice(),
+        [Lint::Invalid { .. }]
+    ));
+}
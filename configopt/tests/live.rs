@@ -0,0 +1,129 @@
+use configopt::{ConfigDiff, ConfigOpt, Live};
+use serde::{Deserialize, Serialize};
+use std::{thread, time::Duration};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[configopt(derive(Debug, PartialEq))]
+struct MyStruct {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[test]
+fn test_live() {
+    let config =
+        Live::new(MyStruct::try_from_iter_with_configopt(&["app", "--port", "1"]).unwrap());
+    let guard = config.load();
+    assert_eq!(guard.port, 1);
+    assert_eq!(guard.version(), 0);
+
+    let mut subscription = config.subscribe();
+    assert!(!subscription.has_changed());
+    let handle = config.clone();
+    let reloader = thread::spawn(move || handle.store(MyStruct { port: 2 }));
+    assert_eq!(subscription.changed().port, 2);
+    assert_eq!(reloader.join().unwrap().port, 1);
+    // Guards are snapshots which are not affected by reloads
+    assert_eq!(guard.port, 1);
+    assert_eq!(config.load().port, 2);
+    assert_eq!(config.version(), 1);
+
+    // A failed reload keeps the current value and notifies no one
+    let result = config.try_update(|_| Err("invalid"));
+    assert_eq!(result.unwrap_err(), "invalid");
+    assert_eq!(config.load().port, 2);
+    assert!(subscription
+        .changed_timeout(Duration::from_millis(10))
+        .is_none());
+
+    let updated = config.update(|current| MyStruct {
+        port: current.port + 1,
+    });
+    assert_eq!(updated.port, 3);
+    assert_eq!(updated.version(), 2);
+    // Replacements while no one waits are not lost
+    assert!(subscription.has_changed());
+    assert_eq!(
+        subscription
+            .changed_timeout(Duration::from_secs(1))
+            .unwrap()
+            .port,
+        3
+    );
+    assert_eq!(format!("{:?}", config), "Live(MyStruct { port: 3 })");
+}
+
+#[test]
+fn test_load_during_update() {
+    let config = Live::new(MyStruct { port: 1 });
+    // Reads do not wait for an update which is in progress
+    let updated = config.update(|current| {
+        let reader = config.clone();
+        let loaded = thread::spawn(move || (reader.load().port, reader.version()))
+            .join()
+            .unwrap();
+        assert_eq!(loaded, (1, 0));
+        MyStruct {
+            port: current.port + 1,
+        }
+    });
+    assert_eq!((updated.port, updated.version()), (2, 1));
+    assert_eq!(config.load().port, 2);
+}
+
+fn diff(a: &MyStruct, b: &MyStruct) -> ConfigDiff {
+    ConfigOptMyStruct::from(a.clone()).diff(&ConfigOptMyStruct::from(b.clone()))
+}
+
+#[test]
+fn test_history_and_rollback() {
+    let config = Live::with_history(MyStruct { port: 1 }, 3, diff);
+    config.store(MyStruct { port: 2 });
+    config.store(MyStruct { port: 2 });
+    config.store(MyStruct { port: 3 });
+    let history = config.history();
+    // Only the last 3 values are kept
+    assert_eq!(
+        history.iter().map(|s| s.value().port).collect::<Vec<_>>(),
+        &[2, 2, 3]
+    );
+    assert_eq!(history[2].version(), 3);
+    assert!(history[1].diff().is_empty());
+    assert_eq!(history[2].diff().changed(), &[String::from("port")]);
+
+    let mut subscription = config.subscribe();
+    let restored = config.rollback(2).unwrap();
+    assert_eq!((restored.port, restored.version()), (2, 4));
+    assert_eq!(subscription.changed().port, 2);
+    assert!(config.rollback(3).is_none());
+    // A rollback can be rolled back
+    assert_eq!(config.rollback(1).unwrap().port, 3);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("history.json");
+    config.save_history(&path, |s| s.port).unwrap();
+    let restarted = Live::with_history(MyStruct { port: 4 }, 5, diff);
+    restarted
+        .load_history(&path, |port| MyStruct { port })
+        .unwrap();
+    let history = restarted.history();
+    assert_eq!(
+        history.iter().map(|s| s.value().port).collect::<Vec<_>>(),
+        &[3, 2, 3, 4]
+    );
+    // The versions continue from the saved history and the current value is diffed against it
+    assert_eq!(
+        history.iter().map(|s| s.version()).collect::<Vec<_>>(),
+        &[3, 4, 5, 6]
+    );
+    assert_eq!(restarted.version(), 6);
+    assert_eq!(history[3].diff().changed(), &[String::from("port")]);
+    assert_eq!(restarted.rollback(1).unwrap().port, 3);
+
+    // Without a history nothing is kept
+    let config = Live::new(MyStruct { port: 1 });
+    config.store(MyStruct { port: 2 });
+    assert!(config.history().is_empty());
+    assert!(config.rollback(1).is_none());
+}
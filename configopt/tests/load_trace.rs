@@ -0,0 +1,72 @@
+use configopt::{configopt_fields, ConfigOpt, LoadTrace, TraceSource};
+use std::{collections::HashMap, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct Server {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    workers: u32,
+    #[structopt(skip)]
+    #[configopt(map)]
+    servers: HashMap<String, Server>,
+}
+
+#[test]
+fn test_load_trace() {
+    let mut first = NamedTempFile::new().unwrap();
+    writeln!(first, "host = \"first\"\nworkers = 4").unwrap();
+    let first = first.path().to_str().unwrap();
+    let mut second = NamedTempFile::new().unwrap();
+    writeln!(second, "workers = 8\n[servers.a]\nport = 1").unwrap();
+    let second = second.path().to_str().unwrap();
+
+    let (s, trace) = LoadTrace::capture(|| {
+        MyStruct::try_from_iter_with_configopt(&[
+            "app",
+            "--host",
+            "cli",
+            "--config-files",
+            first,
+            second,
+        ])
+        .unwrap()
+    });
+    assert_eq!(s.host, "cli");
+    assert_eq!(s.workers, 8);
+
+    assert_eq!(trace.steps.len(), 3);
+    assert_eq!(trace.steps[0].source, TraceSource::Cli);
+    assert_eq!(trace.steps[0].keys, vec!["host", "config_files"]);
+    assert!(trace.steps[0].overridden.is_empty());
+    assert_eq!(
+        trace.steps[1].source,
+        TraceSource::ConfigFile {
+            path: String::from(first)
+        }
+    );
+    assert_eq!(trace.steps[1].keys, vec!["host", "workers"]);
+    assert_eq!(trace.steps[1].overridden, vec!["host", "workers"]);
+    assert_eq!(trace.steps[2].keys, vec!["workers", "servers.a.port"]);
+    assert!(trace.steps[2].overridden.is_empty());
+
+    let json = trace.to_json();
+    assert!(!json.contains('\n'));
+    assert!(json.contains(r#""source":{"type":"config_file","path":"#));
+
+    // Nothing is recorded outside of a capture
+    let (_, trace) = LoadTrace::capture(|| ());
+    MyStruct::try_from_iter_with_configopt(&["app", "--host", "cli"]).unwrap();
+    assert!(trace.steps.is_empty());
+}
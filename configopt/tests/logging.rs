@@ -0,0 +1,61 @@
+#![cfg(feature = "logging")]
+
+use configopt::{ConfigOpt, ConfigOptLoggingConfig, Logging, LoggingConfig, LoggingError};
+use std::{fs, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(flatten)]
+    logging: LoggingConfig,
+}
+
+#[test]
+fn test_logging() {
+    let output = NamedTempFile::new().unwrap();
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(
+        config_file,
+        "[logging]\nlevel = \"warn\"\noutput = {:?}",
+        output.path()
+    )
+    .unwrap();
+    let config_files = config_file.path().to_string_lossy().to_string();
+    let s =
+        MyStruct::try_from_iter_with_configopt(&["app", "--config-files", &config_files]).unwrap();
+    assert_eq!(s.logging.format(), "full");
+    let logging = Logging::init(&s.logging).unwrap();
+    tracing::info!("hidden");
+    tracing::warn!("shown");
+
+    let mut reloaded = s.logging.clone();
+    reloaded.level = Some(String::from("info"));
+    assert!(!logging.apply(&reloaded).unwrap());
+    assert_eq!(logging.config().get().level(), "info");
+    tracing::info!("now shown");
+
+    let mut invalid = reloaded.clone();
+    invalid.level = Some(String::from("app=loud"));
+    assert!(matches!(
+        logging.apply(&invalid),
+        Err(LoggingError::Filter(_))
+    ));
+    assert_eq!(logging.config().get().level(), "info");
+
+    let mut compact = reloaded.clone();
+    compact.format = Some(String::from("compact"));
+    assert!(logging.apply(&compact).unwrap());
+
+    let logged = fs::read_to_string(output.path()).unwrap();
+    assert!(!logged.contains("hidden"));
+    assert!(logged.contains("shown"));
+    assert!(logged.contains("now shown"));
+
+    // Only one global subscriber can be installed
+    assert!(matches!(
+        Logging::init(&s.logging),
+        Err(LoggingError::Init(_))
+    ));
+}
@@ -0,0 +1,135 @@
+use configopt::{ConfigOpt, ConfigOptType};
+use std::{collections::HashMap, convert::TryFrom, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct OutputConfig {
+    #[structopt(long)]
+    host: Option<String>,
+    #[structopt(long)]
+    port: u16,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug, PartialEq))]
+struct MyStruct {
+    #[structopt(long)]
+    name: String,
+    #[structopt(skip)]
+    #[configopt(map)]
+    outputs: HashMap<String, OutputConfig>,
+}
+
+#[test]
+fn test_map_instances() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(
+        config_file,
+        "name = \"app\"\n[outputs.a]\nhost = \"a\"\nport = 1\n[outputs.b]\nhost = \"b\"\n"
+    )
+    .unwrap();
+    let mut c = ConfigOptMyStruct::try_from(config_file.path()).unwrap();
+    assert_eq!(c.outputs.len(), 2);
+    assert_eq!(c.outputs["a"].port, Some(1));
+    assert!(!c.is_convertible());
+    assert_eq!(c.missing_fields(), vec![String::from("outputs.b.port")]);
+
+    let mut other = ConfigOptMyStruct {
+        name: None,
+        outputs: HashMap::new(),
+    };
+    other.outputs.insert(
+        String::from("b"),
+        ConfigOptOutputConfig {
+            host: Some(String::from("other")),
+            port: Some(2),
+        },
+    );
+    other.outputs.insert(
+        String::from("c"),
+        ConfigOptOutputConfig {
+            host: None,
+            port: Some(3),
+        },
+    );
+    c.patch(&mut other);
+    // Existing instances keep their values and only have missing fields filled in
+    assert_eq!(c.outputs["b"].host, Some(String::from("b")));
+    assert_eq!(c.outputs["b"].port, Some(2));
+    assert_eq!(c.outputs["c"].port, Some(3));
+    assert!(c.is_convertible());
+    assert!(c.missing_fields().is_empty());
+    assert!(c.toml_config().contains("outputs.a.host = \"a\"\n\n"));
+
+    let s = MyStruct::try_from(c).unwrap();
+    assert_eq!(s.name, "app");
+    assert_eq!(s.outputs.len(), 3);
+    assert_eq!(
+        s.outputs["c"],
+        OutputConfig {
+            host: None,
+            port: 3
+        }
+    );
+}
+
+fn output(host: &str, port: Option<u16>) -> ConfigOptOutputConfig {
+    ConfigOptOutputConfig {
+        host: Some(String::from(host)),
+        port,
+    }
+}
+
+#[test]
+fn test_map_instance_changes() {
+    let mut before = ConfigOptMyStruct {
+        name: Some(String::from("app")),
+        outputs: HashMap::new(),
+    };
+    before
+        .outputs
+        .insert(String::from("a"), output("a", Some(1)));
+    before
+        .outputs
+        .insert(String::from("b"), output("b", Some(2)));
+    let mut after = ConfigOptMyStruct {
+        name: Some(String::from("app")),
+        outputs: HashMap::new(),
+    };
+    after
+        .outputs
+        .insert(String::from("a"), output("a", Some(1)));
+    after
+        .outputs
+        .insert(String::from("b"), output("b", Some(3)));
+    after
+        .outputs
+        .insert(String::from("c"), output("c", Some(4)));
+
+    let diff = before.diff(&after);
+    assert_eq!(diff.changed_instances("outputs"), vec!["b", "c"]);
+    assert!(!diff.contains("outputs.a"));
+    assert!(diff.contains("outputs.b"));
+}
+
+#[test]
+fn test_incomplete_instance_is_kept() {
+    let mut s = MyStruct {
+        name: String::from("app"),
+        outputs: HashMap::new(),
+    };
+    let mut c = ConfigOptMyStruct {
+        name: None,
+        outputs: HashMap::new(),
+    };
+    c.outputs.insert(String::from("a"), output("a", Some(1)));
+    c.outputs.insert(String::from("b"), output("b", None));
+    s.patch(&mut c);
+    assert_eq!(s.outputs.len(), 1);
+    assert_eq!(s.outputs["a"].port, 1);
+    // The instance which could not be converted is not lost
+    assert_eq!(c.outputs.len(), 1);
+    assert_eq!(c.missing_fields(), vec![String::from("outputs.b.port")]);
+}
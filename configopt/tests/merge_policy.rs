@@ -0,0 +1,66 @@
+use configopt::ConfigOpt;
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug, PartialEq))]
+struct MyStruct {
+    #[structopt(long)]
+    overwrite: Option<String>,
+    #[structopt(long)]
+    #[configopt(merge = "keep")]
+    keep: Option<String>,
+    #[structopt(long)]
+    #[configopt(merge = "append")]
+    append: Vec<u32>,
+    #[structopt(long)]
+    #[configopt(merge = "min")]
+    min: u32,
+    #[structopt(long)]
+    #[configopt(merge = "max")]
+    max: Option<u32>,
+}
+
+fn high() -> ConfigOptMyStruct {
+    ConfigOptMyStruct {
+        overwrite: Some(String::from("high")),
+        keep: Some(String::from("high")),
+        append: Some(vec![3, 4]),
+        min: Some(10),
+        max: None,
+    }
+}
+
+fn low() -> ConfigOptMyStruct {
+    ConfigOptMyStruct {
+        overwrite: Some(String::from("low")),
+        keep: Some(String::from("low")),
+        append: Some(vec![1, 2]),
+        min: Some(5),
+        max: Some(7),
+    }
+}
+
+#[test]
+fn test_merge_policy() {
+    let expected = ConfigOptMyStruct {
+        overwrite: Some(String::from("high")),
+        keep: Some(String::from("low")),
+        append: Some(vec![1, 2, 3, 4]),
+        min: Some(5),
+        max: Some(7),
+    };
+
+    // `patch` merges a lower precedence layer into a higher one
+    let mut c = high();
+    c.patch(&mut low());
+    assert_eq!(c, expected);
+
+    // `take` merges a higher precedence layer into a lower one
+    let mut c = low();
+    c.take(&mut high());
+    assert_eq!(c, expected);
+
+    let mut c = ConfigOptMyStruct::default();
+    c.patch(&mut low());
+    assert_eq!(c, low());
+}
@@ -0,0 +1,58 @@
+use configopt::{clear_catalog, set_catalog, ConfigOpt, Diagnostic, Error, MessageId};
+use std::collections::HashMap;
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[test]
+fn test_message_catalog() {
+    let c = ConfigOptMyStruct::from_iter_safe(&["app"]).unwrap();
+    assert!(Diagnostic::missing_fields(&c.missing_fields())
+        .to_string()
+        .starts_with("error: missing required config values `port`"));
+
+    let mut table = HashMap::new();
+    table.insert(MessageId::Error, String::from("erreur :"));
+    table.insert(
+        MessageId::MissingFields,
+        String::from("valeurs de configuration requises manquantes {fields}"),
+    );
+    table.insert(
+        MessageId::StrictUnknownKey,
+        String::from("clé inconnue `{key}` dans '{path}'"),
+    );
+    set_catalog(table);
+    let rendered = Diagnostic::missing_fields(&c.missing_fields()).to_string();
+    assert!(rendered.starts_with("erreur : valeurs de configuration requises manquantes `port`"));
+    // Messages missing from the catalog fall back to English
+    assert!(rendered
+        .ends_with("help: set them on the command line, in the environment, or in a config file"));
+
+    // The errors are rendered with the catalog as well
+    let error = Error::UnknownKey {
+        key: configopt::UnknownKey {
+            key: String::from("prot"),
+            path: "app.toml".into(),
+        },
+        suggestion: None,
+    };
+    assert_eq!(error.to_string(), "clé inconnue `prot` dans 'app.toml'");
+    assert_eq!(
+        Error::MissingFields(vec![String::from("port")]).to_string(),
+        "Missing required config values `port`"
+    );
+
+    clear_catalog();
+    assert_eq!(
+        error.to_string(),
+        "Failed to parse file 'app.toml', err: unknown key `prot`"
+    );
+    assert!(Diagnostic::missing_fields(&c.missing_fields())
+        .to_string()
+        .starts_with("error:"));
+}
@@ -0,0 +1,120 @@
+use configopt::{configopt_fields, ConfigFormat, ConfigOpt, ConfigOptType, Migration};
+use std::{fs, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+use toml::value::{Table, Value};
+
+// Version 1 renamed `listen_port` to `port`
+fn rename_listen_port(table: &mut Table) -> Result<(), String> {
+    if let Some(port) = table.remove("listen_port") {
+        table.insert(String::from("port"), port);
+    }
+    Ok(())
+}
+
+// Version 2 replaced `verbose = true` with `log_level = "debug"`
+fn replace_verbose(table: &mut Table) -> Result<(), String> {
+    match table.remove("verbose") {
+        Some(Value::Boolean(true)) => {
+            table.insert(
+                String::from("log_level"),
+                Value::String(String::from("debug")),
+            );
+        }
+        Some(Value::Boolean(false)) | None => {}
+        Some(_) => return Err(String::from("`verbose` must be a bool")),
+    }
+    Ok(())
+}
+
+fn migrations() -> Vec<Migration> {
+    vec![
+        Migration::new(0, rename_listen_port),
+        Migration::new(1, replace_verbose),
+    ]
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(
+    derive(Debug, PartialEq),
+    version_field("config_version"),
+    migrations(migrations)
+)]
+struct MyStruct {
+    #[structopt(long, default_value = "80")]
+    port: u16,
+    #[structopt(long, default_value = "info")]
+    log_level: String,
+}
+
+fn write_config(config: &str) -> NamedTempFile {
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(config_file, "{}", config).unwrap();
+    config_file
+}
+
+#[test]
+fn test_old_file_is_migrated_in_memory() {
+    let config_file = write_config("listen_port = 8080\nverbose = true\n");
+    let config_files = config_file.path().to_string_lossy().to_string();
+    let s = MyStruct::try_from_iter_with_configopt(vec!["app", "--config-files", &config_files])
+        .unwrap();
+    assert_eq!(s.port, 8080);
+    assert_eq!(s.log_level, "debug");
+    // The file on disk is unchanged
+    assert_eq!(
+        fs::read_to_string(config_file.path()).unwrap(),
+        "listen_port = 8080\nverbose = true\n"
+    );
+
+    // Only the migrations after the version of the file are applied
+    let c = ConfigOptMyStruct::try_from_config_str(
+        "config_version = 1\nport = 1\nverbose = true\n",
+        ConfigFormat::Toml,
+    );
+    assert_eq!(c.unwrap().log_level.as_deref(), Some("debug"));
+}
+
+#[test]
+fn test_newer_and_invalid_versions_are_rejected() {
+    let e = ConfigOptMyStruct::try_from_config_str("config_version = 3", ConfigFormat::Toml)
+        .unwrap_err();
+    assert!(e
+        .to_string()
+        .contains("`config_version` is 3 but the latest version this binary supports is 2"));
+    let e = ConfigOptMyStruct::try_from_config_str(
+        "config_version = 1\nverbose = 'yes'",
+        ConfigFormat::Toml,
+    )
+    .unwrap_err();
+    assert!(e
+        .to_string()
+        .contains("migrating from version 1 failed: `verbose` must be a bool"));
+}
+
+#[test]
+fn test_migrate_file_rewrites_to_disk() {
+    let config_file = write_config("listen_port = 8080\n");
+    assert!(ConfigOptMyStruct::migrate_file(config_file.path()).unwrap());
+    assert_eq!(
+        fs::read_to_string(config_file.path()).unwrap(),
+        "config_version = 2\n\nport = 8080\n"
+    );
+    // A file at the current version is left alone
+    assert!(!ConfigOptMyStruct::migrate_file(config_file.path()).unwrap());
+}
+
+#[test]
+fn test_generated_config_is_stamped() {
+    assert_eq!(
+        <ConfigOptMyStruct as ConfigOptType>::version_field(),
+        Some("config_version")
+    );
+    let mut c = ConfigOptMyStruct::default();
+    c.port = Some(1);
+    let config = c.toml_config();
+    assert!(config.starts_with("config_version = 2\n\n"));
+    let parsed = ConfigOptMyStruct::try_from_config_str(&config, ConfigFormat::Toml).unwrap();
+    assert_eq!(parsed.port, Some(1));
+}
@@ -0,0 +1,71 @@
+use configopt::{configopt_fields, ConfigOpt, ConfigOptType};
+use std::io::Write;
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    #[configopt(negatable)]
+    verbose: bool,
+    #[structopt(long)]
+    color: bool,
+    #[structopt(long)]
+    name: Option<String>,
+}
+
+#[test]
+fn test_negatable() {
+    assert_eq!(ConfigOptMyStruct::negatable_args(), vec!["verbose"]);
+
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "verbose = true\ncolor = true").unwrap();
+    let config_file = config_file.path().to_str().unwrap();
+
+    let s =
+        MyStruct::try_from_iter_with_configopt(&["app", "--config-files", config_file]).unwrap();
+    assert!(s.verbose);
+    assert!(s.color);
+
+    // The CLI negation overrides the config file
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        config_file,
+        "--no-verbose",
+    ])
+    .unwrap();
+    assert!(!s.verbose);
+    assert!(s.color);
+
+    // The last occurrence wins
+    let s = MyStruct::try_from_iter_with_configopt(&["app", "--no-verbose", "--verbose"]).unwrap();
+    assert!(s.verbose);
+    let s = MyStruct::try_from_iter_with_configopt(&["app", "--verbose", "--no-verbose"]).unwrap();
+    assert!(!s.verbose);
+
+    // The value of an option is never a negation
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        config_file,
+        "--name=--no-verbose",
+    ])
+    .unwrap();
+    assert!(s.verbose);
+    assert_eq!(s.name.as_deref(), Some("--no-verbose"));
+
+    // Only negatable flags accept a negation
+    assert!(MyStruct::try_from_iter_with_configopt(&["app", "--no-color"]).is_err());
+}
+
+#[test]
+fn test_negatable_help() {
+    let mut help = Vec::new();
+    MyStruct::prefixed_clap().write_help(&mut help).unwrap();
+    let help = String::from_utf8(help).unwrap();
+    assert!(help.contains("--no-verbose"));
+    assert!(!help.contains("--no-color"));
+}
@@ -0,0 +1,45 @@
+use configopt::{configopt_fields, ConfigOpt};
+use std::io::Write;
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(short, long, parse(from_occurrences))]
+    verbose: u8,
+    #[structopt(short, long, parse(from_occurrences))]
+    #[configopt(merge = "add")]
+    quiet: u64,
+}
+
+#[test]
+fn test_occurrences() {
+    let c = ConfigOptMyStruct::from_iter_safe(&["app"]).unwrap();
+    assert_eq!(c.verbose, None);
+    let c = ConfigOptMyStruct::from_iter_safe(&["app", "-vv", "-q"]).unwrap();
+    assert_eq!(c.verbose, Some(2));
+    assert_eq!(c.quiet, Some(1));
+
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "verbose = 2\nquiet = 2").unwrap();
+    let config_file = config_file.path().to_str().unwrap();
+
+    let s =
+        MyStruct::try_from_iter_with_configopt(&["app", "--config-files", config_file]).unwrap();
+    assert_eq!(s.verbose, 2);
+    assert_eq!(s.quiet, 2);
+
+    // By default the CLI overrides the config file while `add` sums them
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        config_file,
+        "-v",
+        "-qq",
+    ])
+    .unwrap();
+    assert_eq!(s.verbose, 1);
+    assert_eq!(s.quiet, 4);
+}
@@ -0,0 +1,92 @@
+use configopt::{diff_list, ConfigOpt, Identity, ListChange};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct StageConfig {
+    #[structopt(long)]
+    #[configopt(id)]
+    id: Option<String>,
+    #[structopt(long)]
+    command: String,
+}
+
+fn stage(id: Option<&str>, command: &str) -> StageConfig {
+    StageConfig {
+        id: id.map(String::from),
+        command: String::from(command),
+    }
+}
+
+#[test]
+fn test_identity() {
+    assert_eq!(stage(Some("build"), "make").id(), Some("build"));
+    assert_eq!(stage(None, "make").id(), None);
+    let c = ConfigOptStageConfig {
+        id: Some(String::from("build")),
+        command: None,
+    };
+    assert_eq!(c.id(), Some("build"));
+}
+
+#[test]
+fn test_diff_list() {
+    let old = vec![
+        stage(Some("build"), "make"),
+        stage(Some("test"), "make test"),
+        stage(None, "echo done"),
+    ];
+    let new = vec![
+        stage(Some("test"), "make test"),
+        stage(Some("build"), "make all"),
+        stage(None, "echo finished"),
+        stage(Some("deploy"), "make deploy"),
+    ];
+    assert_eq!(
+        diff_list(&old, &new),
+        vec![
+            ListChange::Moved {
+                id: String::from("test"),
+                from: 1,
+                to: 0
+            },
+            ListChange::Moved {
+                id: String::from("build"),
+                from: 0,
+                to: 1
+            },
+            ListChange::Modified {
+                id: Some(String::from("build")),
+                index: 1
+            },
+            ListChange::Modified { id: None, index: 2 },
+            ListChange::Added {
+                id: Some(String::from("deploy")),
+                index: 3
+            },
+        ]
+    );
+    assert_eq!(
+        diff_list(&new, &old[..1]),
+        vec![
+            ListChange::Removed {
+                id: Some(String::from("test")),
+                index: 0
+            },
+            ListChange::Removed { id: None, index: 2 },
+            ListChange::Removed {
+                id: Some(String::from("deploy")),
+                index: 3
+            },
+            ListChange::Moved {
+                id: String::from("build"),
+                from: 1,
+                to: 0
+            },
+            ListChange::Modified {
+                id: Some(String::from("build")),
+                index: 0
+            },
+        ]
+    );
+}
@@ -0,0 +1,64 @@
+use configopt::{testing, ConfigFormat, ConfigOpt, ConfigOptType};
+use std::{ffi::OsString, path::PathBuf};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Clone, Debug, PartialEq)]
+struct MyStruct {
+    #[structopt(long, parse(from_os_str))]
+    path: PathBuf,
+    #[structopt(long, parse(from_os_str))]
+    name: Option<OsString>,
+    #[structopt(long, parse(from_os_str))]
+    includes: Vec<PathBuf>,
+}
+
+#[test]
+fn test_os_string_from_config() {
+    let c = ConfigOptMyStruct::try_from_config_str(
+        "path = '/etc/app'\nname = 'app'\nincludes = ['a', 'b']\n",
+        ConfigFormat::Toml,
+    )
+    .unwrap();
+    assert_eq!(c.path, Some(PathBuf::from("/etc/app")));
+    assert_eq!(c.name, Some(OsString::from("app")));
+    assert_eq!(
+        c.includes,
+        Some(vec![PathBuf::from("a"), PathBuf::from("b")])
+    );
+
+    let s = MyStruct {
+        path: PathBuf::from("/etc/app"),
+        name: Some(OsString::from("app")),
+        includes: vec![PathBuf::from("a")],
+    };
+    let config = ConfigOptMyStruct::from(s.clone()).toml_config();
+    assert!(config.contains("path = \"/etc/app\""));
+    assert!(config.contains("name = \"app\""));
+    testing::assert_round_trip(&s);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_non_utf8_is_lossless() {
+    use std::os::unix::ffi::OsStringExt;
+
+    let invalid = || OsString::from_vec(vec![b'/', b't', 0xff]);
+    let s = MyStruct {
+        path: PathBuf::from(invalid()),
+        name: Some(invalid()),
+        includes: vec![PathBuf::from(invalid())],
+    };
+    let config = ConfigOptMyStruct::from(s.clone()).toml_config();
+    assert!(config.contains("path = [47, 116, 255]"));
+    assert!(config.contains("includes = [[47, 116, 255]]"));
+    testing::assert_round_trip(&s);
+
+    // The CLI accepts the same values
+    let c = ConfigOptMyStruct::from_iter_safe(vec![
+        OsString::from("app"),
+        OsString::from("--path"),
+        invalid(),
+    ])
+    .unwrap();
+    assert_eq!(c.path, Some(PathBuf::from(invalid())));
+}
@@ -0,0 +1,53 @@
+use configopt::{clear_override_keys, configopt_fields, set_override_keys, ConfigOpt};
+use std::io::Write;
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    max_conns: u32,
+}
+
+#[test]
+fn test_overrides() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(
+        config_file,
+        r#"
+host = "db"
+max_conns = 100
+
+[overrides.us-east-1]
+max_conns = 500
+
+[overrides.eu-west-1]
+host = "db.eu"
+"#
+    )
+    .unwrap();
+    let config_files = config_file.path().to_string_lossy().to_string();
+    let parse = |args: &[&str]| {
+        let mut iter = vec!["app", "--config-files", config_files.as_str()];
+        iter.extend_from_slice(args);
+        MyStruct::try_from_iter_with_configopt(iter).unwrap()
+    };
+
+    let s = parse(&[]);
+    assert_eq!((s.host.as_str(), s.max_conns), ("db", 100));
+
+    set_override_keys(vec!["us-east-1"]);
+    let s = parse(&[]);
+    assert_eq!((s.host.as_str(), s.max_conns), ("db", 500));
+    // The CLI still takes precedence
+    assert_eq!(parse(&["--max-conns", "1"]).max_conns, 1);
+
+    set_override_keys(vec!["ap-south-1"]);
+    assert_eq!(parse(&[]).max_conns, 100);
+
+    clear_override_keys();
+    assert_eq!(parse(&[]).max_conns, 100);
+}
@@ -0,0 +1,61 @@
+use configopt::{configopt_fields, ConfigOpt, ConfigOptArgToOsString, ConfigOptType};
+use std::{io::Write, net::SocketAddr, time::Duration};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long, parse(try_from_str = configopt::parsers::duration))]
+    timeout: Duration,
+    #[structopt(long, parse(try_from_str = configopt::parsers::byte_size))]
+    buffer: Option<u64>,
+    #[structopt(long, parse(try_from_str = configopt::parsers::socket_addr))]
+    listen: SocketAddr,
+}
+
+#[test]
+fn test_parsers() {
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--timeout",
+        "1m30s",
+        "--buffer",
+        "10MiB",
+        "--listen",
+        ":8080",
+    ])
+    .unwrap();
+    assert_eq!(s.timeout, Duration::from_secs(90));
+    assert_eq!(s.buffer, Some(10 * 1024 * 1024));
+    assert_eq!(s.listen, "0.0.0.0:8080".parse().unwrap());
+
+    // Config files accept the same syntax
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(
+        config_file,
+        "timeout = \"250ms\"\nbuffer = \"4KiB\"\nlisten = \"127.0.0.1:9000\"\n"
+    )
+    .unwrap();
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        config_file.path().to_str().unwrap(),
+    ])
+    .unwrap();
+    assert_eq!(s.timeout, Duration::from_millis(250));
+    assert_eq!(s.buffer, Some(4096));
+    assert_eq!(s.listen, "127.0.0.1:9000".parse().unwrap());
+
+    // Generated configs and reconstructed arguments use the human friendly format
+    let c =
+        ConfigOptMyStruct::from_iter_safe(&["app", "--timeout", "90", "--buffer", "2048"]).unwrap();
+    let config = c.toml_config();
+    assert!(config.contains("timeout = \"1m30s\""));
+    assert!(config.contains("buffer = \"2KiB\""));
+    assert_eq!(
+        c.arg_to_os_string(&[String::from("timeout")]).unwrap(),
+        "1m30s"
+    );
+}
@@ -0,0 +1,70 @@
+use configopt::{merge_layers, ConfigOpt, ConfigOptSection, Merge, Partial};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+    #[structopt(skip)]
+    #[configopt(section)]
+    limits: Limits,
+}
+
+#[derive(ConfigOptSection, Debug, Default, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct Limits {
+    connections: Option<u32>,
+}
+
+// Generic code only needs the traits to work with any partial type
+fn describe<T: Partial>(partial: &T) -> (bool, Vec<String>, Vec<String>) {
+    (
+        partial.is_convertible(),
+        partial.set_fields(),
+        partial.missing_fields(),
+    )
+}
+
+#[test]
+fn test_partial_traits() {
+    let low = ConfigOptMyStruct::from_iter_safe(&["app", "--host", "low", "--port", "1"]).unwrap();
+    let high = ConfigOptMyStruct::from_iter_safe(&["app", "--host", "high"]).unwrap();
+    let mut limits = ConfigOptLimits::default();
+    limits.connections = Some(4);
+    let mut with_limits = ConfigOptMyStruct::default();
+    with_limits.limits = limits;
+
+    let merged: ConfigOptMyStruct = merge_layers(vec![low, high, with_limits]);
+    assert_eq!(
+        describe(&merged),
+        (
+            true,
+            vec![
+                String::from("host"),
+                String::from("port"),
+                String::from("limits.connections")
+            ],
+            Vec::new()
+        )
+    );
+
+    let mut empty = ConfigOptMyStruct::default();
+    assert!(Partial::is_empty(&empty));
+    assert_eq!(describe(&empty).2, vec!["host", "port"]);
+    Merge::patch(&mut empty, &mut ConfigOptMyStruct::default());
+    assert!(Partial::is_empty(&empty));
+
+    let mut full = MyStruct {
+        host: String::from("a"),
+        port: 2,
+        limits: Limits::default(),
+    };
+    let mut merged = merged;
+    Partial::take_for(&mut merged, &mut full);
+    assert_eq!(full.host, "high");
+    assert_eq!(full.port, 1);
+    assert_eq!(full.limits.connections, Some(4));
+}
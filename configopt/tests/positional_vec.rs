@@ -1,4 +1,4 @@
-use configopt::ConfigOpt;
+use configopt::{ConfigFormat, ConfigOpt, ConfigOptType};
 use serde::Serialize;
 use structopt::StructOpt;
 
@@ -11,3 +11,34 @@ struct MyStruct {
 
 #[test]
 fn positional_vec() {}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct Positionals {
+    #[structopt(long)]
+    verbose: bool,
+    target: String,
+    args: Vec<String>,
+}
+
+#[test]
+fn test_positionals_from_config() {
+    let files = [(ConfigFormat::Toml, "target = 'build'\nargs = ['a', 'b']\n")];
+    let s = Positionals::try_from_iter_with_files(&["app"], &files).unwrap();
+    assert_eq!(s.target, "build");
+    assert_eq!(s.args, vec![String::from("a"), String::from("b")]);
+
+    // Positionals on the CLI replace the values from config files as a whole
+    let s = Positionals::try_from_iter_with_files(&["app", "test", "c"], &files).unwrap();
+    assert_eq!(s.target, "test");
+    assert_eq!(s.args, vec![String::from("c")]);
+    let s = Positionals::try_from_iter_with_files(&["app", "test"], &files).unwrap();
+    assert_eq!(s.args, vec![String::from("a"), String::from("b")]);
+
+    let mut c =
+        ConfigOptPositionals::try_from_config_str("args = ['a']", ConfigFormat::Toml).unwrap();
+    assert_eq!(c.set_fields(), vec![String::from("args")]);
+    let mut other = ConfigOptPositionals::default();
+    c.take(&mut other);
+    assert_eq!(c.args, vec![String::from("a")]);
+}
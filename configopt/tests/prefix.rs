@@ -0,0 +1,83 @@
+use configopt::{ConfigOpt, ConfigOptType, IgnoreHelp, Value};
+use std::{env, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug))]
+struct Database {
+    #[structopt(long)]
+    url: String,
+    #[structopt(long, env)]
+    pool_size: u32,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(flatten)]
+    #[configopt(prefix = "db")]
+    database: Database,
+}
+
+#[test]
+fn test_prefix() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(
+        config_file,
+        "host = 'localhost'\n[db]\nurl = 'postgres://file'\npool_size = 2\n"
+    )
+    .unwrap();
+    let config_file = config_file.path().to_str().unwrap();
+
+    // The keys of the flattened type are read from the table named after the prefix
+    let s =
+        MyStruct::try_from_iter_with_configopt(&["app", "--config-files", config_file]).unwrap();
+    assert_eq!(
+        s,
+        MyStruct {
+            host: String::from("localhost"),
+            database: Database {
+                url: String::from("postgres://file"),
+                pool_size: 2,
+            },
+        }
+    );
+    let c = ConfigOptMyStruct::try_from_iter_ignore_help(&["app", "--db-url", "postgres://cli"])
+        .unwrap();
+    assert_eq!(c.get(&"db.url".into()), Some(Value::from("postgres://cli")));
+    assert!(c.toml_config().contains("[db]"));
+
+    env::set_var("DB_POOL_SIZE", "4");
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        config_file,
+        "--db-url",
+        "postgres://cli",
+    ])
+    .unwrap();
+    assert_eq!(s.database.url, "postgres://cli");
+    assert_eq!(s.database.pool_size, 4);
+    env::remove_var("DB_POOL_SIZE");
+
+    // The flags of the flattened type are only accepted with the prefix
+    assert!(MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--host",
+        "localhost",
+        "--url",
+        "postgres://cli",
+        "--pool-size",
+        "1",
+    ])
+    .is_err());
+    let help = s.get_help();
+    assert!(help.contains("--db-url"));
+    assert!(help.contains("--db-pool-size"));
+    assert!(help.contains("DB_POOL_SIZE"));
+    // Only an argument with an environment variable gets a prefixed one
+    assert!(!help.contains("DB_URL"));
+}
@@ -0,0 +1,90 @@
+use configopt::{configopt_fields, ConfigOpt, ConfigOptType, Preset};
+use std::io::Write;
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+fn presets() -> Vec<Preset> {
+    vec![
+        Preset::new(
+            "low-memory",
+            "Trade throughput for a smaller footprint",
+            "workers = 1\ncache_size = 16",
+        ),
+        Preset::new("high-throughput", "Use every core", "workers = 32"),
+    ]
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug), presets(presets))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    workers: u32,
+    #[structopt(long)]
+    cache_size: u32,
+}
+
+fn write_config(config: &str) -> NamedTempFile {
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(config_file, "{}", config).unwrap();
+    config_file
+}
+
+fn parse(config_file: &NamedTempFile, args: &[&str]) -> configopt::Result<MyStruct> {
+    let config_files = config_file.path().to_string_lossy().to_string();
+    let mut iter = vec!["app", "--config-files", config_files.as_str()];
+    iter.extend_from_slice(args);
+    MyStruct::try_from_iter_with_configopt(iter)
+}
+
+#[test]
+fn test_presets() {
+    // The file overrides its presets and the CLI overrides the file
+    let config_file = write_config("preset = \"low-memory\"\nhost = \"a\"\ncache_size = 64\n");
+    let s = parse(&config_file, &[]).unwrap();
+    assert_eq!((s.workers, s.cache_size), (1, 64));
+    let s = parse(&config_file, &["--workers", "2"]).unwrap();
+    assert_eq!(s.workers, 2);
+
+    // Presets defined in the file take precedence over presets defined in code
+    let config_file = write_config(
+        r#"
+preset = ["low-memory", "high-throughput"]
+host = "a"
+
+[presets.high-throughput]
+workers = 8
+"#,
+    );
+    let s = parse(&config_file, &[]).unwrap();
+    assert_eq!((s.workers, s.cache_size), (8, 16));
+
+    // A profile may select another preset
+    let config_file = write_config(
+        r#"
+preset = "low-memory"
+host = "a"
+
+[profiles.production]
+preset = "high-throughput"
+cache_size = 128
+"#,
+    );
+    let c = ConfigOptMyStruct::from_file_with_profile(config_file.path(), "production").unwrap();
+    assert_eq!(c.workers, Some(32));
+    assert_eq!(c.cache_size, Some(128));
+
+    let config_file = write_config("preset = \"tiny\"\nhost = \"a\"\n");
+    let err = parse(&config_file, &[]).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("unknown preset `tiny`, expected one of `high-throughput`, `low-memory`"));
+
+    assert_eq!(
+        ConfigOptMyStruct::presets_help(),
+        "low-memory: Trade throughput for a smaller footprint\n    cache_size = 16\n    workers = 1\n\
+         high-throughput: Use every core\n    workers = 32\n"
+    );
+}
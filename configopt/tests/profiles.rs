@@ -0,0 +1,75 @@
+use configopt::ConfigOpt;
+use std::{collections::HashMap, convert::TryFrom, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct Server {
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    tls: bool,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    workers: u32,
+    #[structopt(skip)]
+    #[configopt(map)]
+    servers: HashMap<String, Server>,
+}
+
+#[test]
+fn test_profiles() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(
+        config_file,
+        r#"
+host = "localhost"
+workers = 1
+
+[servers.a]
+port = 8080
+
+[profiles.production]
+host = "example.com"
+
+[profiles.production.servers.a]
+tls = true
+
+[profiles.dev]
+workers = 4
+"#
+    )
+    .unwrap();
+    let path = config_file.path();
+
+    let c = ConfigOptMyStruct::from_file_with_profile(path, "production").unwrap();
+    let s = MyStruct::try_from(c).unwrap();
+    assert_eq!(s.host, "example.com");
+    assert_eq!(s.workers, 1);
+    assert_eq!(s.servers["a"].port, 8080);
+    assert!(s.servers["a"].tls);
+
+    let c = ConfigOptMyStruct::from_file_with_profile(path, "dev").unwrap();
+    assert_eq!(c.host, Some(String::from("localhost")));
+    assert_eq!(c.workers, Some(4));
+    assert_eq!(c.servers["a"].tls, None);
+
+    // Without a profile the base values are used and the profiles are not unknown keys
+    let mut unknown_keys = Vec::new();
+    let c: ConfigOptMyStruct =
+        configopt::from_toml_file_collecting_unknown(path, &[], &mut unknown_keys).unwrap();
+    assert_eq!(c.host, Some(String::from("localhost")));
+    assert!(unknown_keys.is_empty());
+
+    let e = ConfigOptMyStruct::from_file_with_profile(path, "staging").unwrap_err();
+    assert!(e
+        .to_string()
+        .contains("unknown profile `staging`, expected one of `dev`, `production`"));
+}
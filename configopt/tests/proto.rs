@@ -0,0 +1,105 @@
+#![cfg(feature = "protobuf")]
+
+use configopt::{ConfigOpt, LoadTrace, TraceSource};
+use prost_types::{value::Kind, Struct, Value};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    #[configopt(secret)]
+    token: String,
+}
+
+/// A message like the ones `prost` generates with serde derives added
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct PushedConfig {
+    host: Option<String>,
+    port: u32,
+}
+
+fn value(kind: Kind) -> Value {
+    Value { kind: Some(kind) }
+}
+
+#[test]
+fn test_from_proto_struct() {
+    let mut message = Struct::default();
+    message
+        .fields
+        .insert(String::from("port"), value(Kind::NumberValue(8080.0)));
+    message
+        .fields
+        .insert(String::from("host"), value(Kind::NullValue(0)));
+    let (pushed, trace) = LoadTrace::capture(|| {
+        configopt::from_proto_struct::<ConfigOptMyStruct>(&message, "grpc://test", &mut Vec::new())
+            .unwrap()
+    });
+    assert_eq!(pushed.port, Some(8080));
+    assert_eq!(pushed.host, None);
+    assert_eq!(
+        trace.source_of("port"),
+        Some(&TraceSource::Message {
+            name: String::from("grpc://test")
+        })
+    );
+
+    // The message is merged under the command line like any other layer
+    let mut c = ConfigOptMyStruct::from_iter_safe(&["app", "--host", "a", "--token", "t"]).unwrap();
+    let mut pushed = pushed;
+    c.patch(&mut pushed);
+    assert_eq!(
+        MyStruct::try_from(c).unwrap(),
+        MyStruct {
+            host: String::from("a"),
+            port: 8080,
+            token: String::from("t"),
+        }
+    );
+
+    message.fields.insert(
+        String::from("port"),
+        value(Kind::StringValue(String::from("x"))),
+    );
+    let e =
+        configopt::from_proto_struct::<ConfigOptMyStruct>(&message, "grpc://test", &mut Vec::new())
+            .unwrap_err();
+    assert!(e.to_string().contains("grpc://test"));
+}
+
+#[test]
+fn test_messages() {
+    let message = PushedConfig {
+        host: None,
+        port: 80,
+    };
+    let c =
+        configopt::from_message::<ConfigOptMyStruct, _>(&message, "grpc://test", &mut Vec::new())
+            .unwrap();
+    assert_eq!(c.port, Some(80));
+    assert_eq!(c.host, None);
+
+    // Secrets are never emitted
+    let c = ConfigOptMyStruct::from_iter_safe(&["app", "--host", "a", "--token", "t"]).unwrap();
+    let emitted = configopt::to_proto_struct(&c);
+    assert_eq!(
+        emitted.fields["host"].kind,
+        Some(Kind::StringValue(String::from("a")))
+    );
+    assert!(!emitted.fields.contains_key("token"));
+    assert_eq!(
+        configopt::to_message::<PushedConfig, _>(&c).unwrap(),
+        PushedConfig {
+            host: Some(String::from("a")),
+            port: 0,
+        }
+    );
+}
@@ -0,0 +1,53 @@
+use configopt::{ConfigOpt, DocFormat};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+struct Database {
+    /// The connection string of the database
+    #[structopt(long)]
+    url: String,
+}
+
+/// Serve the api
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[structopt(name = "myapp")]
+struct MyApp {
+    /// The port to listen on
+    #[structopt(long, default_value = "8080", env = "MYAPP_PORT")]
+    port: u16,
+    /// Log each request
+    #[structopt(short, long)]
+    verbose: bool,
+    #[structopt(flatten)]
+    database: Database,
+}
+
+#[test]
+fn test_markdown_reference() {
+    let doc = ConfigOptMyApp::reference_doc(DocFormat::Markdown);
+    assert!(doc.starts_with("# myapp configuration reference\n\nServe the api\n\n"));
+    assert!(doc.contains(
+        "## `port`\n\nThe port to listen on\n\n\
+         | | |\n|---|---|\n\
+         | CLI | `--port <port>` |\n\
+         | Environment | `MYAPP_PORT` |\n\
+         | Config key | `port` |\n\
+         | Type | `u16` |\n\
+         | Default | `8080` |\n"
+    ));
+    assert!(doc.contains("## `verbose`\n\nLog each request\n\n"));
+    assert!(doc.contains("| CLI | `-v, --verbose` |\n"));
+    assert!(doc.contains("## `database.url`\n\nThe connection string of the database\n\n"));
+    assert!(doc.contains("| CLI | `--url <url>` |\n"));
+}
+
+#[test]
+fn test_roff_reference() {
+    let doc = ConfigOptMyApp::reference_doc(DocFormat::Roff);
+    assert!(doc.starts_with(".TH MYAPP 5\n.SH NAME\nmyapp \\- configuration reference\n"));
+    assert!(doc.contains(".SH DESCRIPTION\nServe the api\n"));
+    assert!(doc.contains(".TP\n.B port\nThe port to listen on\n.RS\n"));
+    assert!(doc.contains(".PP\nCLI: \\fB\\-\\-port <port>\\fR\n"));
+    assert!(doc.contains(".PP\nDefault: \\fB8080\\fR\n"));
+    assert!(doc.contains(".TP\n.B database.url\n"));
+}
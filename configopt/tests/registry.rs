@@ -0,0 +1,97 @@
+use configopt::{
+    ConfigFormat, ConfigOpt, ConfigOptArgToOsString, ConfigOptDefaults, Registry, Value,
+};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug, PartialEq))]
+struct Http {
+    #[structopt(long)]
+    listen_port: u16,
+    #[structopt(long, hidden = true)]
+    workers: u8,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug, PartialEq))]
+struct Db {
+    #[structopt(long)]
+    url: String,
+    #[structopt(long)]
+    #[configopt(secret)]
+    password: String,
+}
+
+#[test]
+fn test_registry() {
+    let mut registry = Registry::new();
+    registry
+        .register("http", ConfigOptHttp::default())
+        .register("db", ConfigOptDb::default());
+    assert_eq!(registry.names().collect::<Vec<_>>(), &["http", "db"]);
+    registry
+        .patch_with_config_str(
+            "[http]\nlisten_port = 80\nworkers = 4\n\n[db]\nurl = \"postgres://db\"\npassword = \"hunter2\"\n",
+            ConfigFormat::Toml,
+        )
+        .unwrap();
+
+    let http = registry.get::<ConfigOptHttp>("http").unwrap();
+    assert_eq!(http.listen_port, Some(80));
+    assert!(registry.get::<ConfigOptDb>("http").is_none());
+    registry.get_mut::<ConfigOptDb>("db").unwrap().url = Some(String::from("postgres://other"));
+
+    let paths = registry
+        .schema()
+        .fields
+        .into_iter()
+        .map(|f| f.path)
+        .collect::<Vec<_>>();
+    assert!(paths.contains(&String::from("http.listen_port")));
+    assert!(paths.contains(&String::from("db.password")));
+    assert_eq!(
+        registry.get_value(&"http.listen_port".into()),
+        Some(Value::Integer(80))
+    );
+    assert_eq!(registry.get_value(&"db.password".into()), None);
+    assert_eq!(
+        registry.fields_set(),
+        &["http.listen_port", "http.workers", "db.url", "db.password"]
+    );
+    // Hidden fields are kept and secrets are left out
+    assert_eq!(
+        registry.toml_config().unwrap(),
+        "[db]\nurl = \"postgres://other\"\n\n[http]\nlisten_port = 80\nworkers = 4\n"
+    );
+
+    // The argument of a field is looked up by its serde path and the other way around
+    let http = registry.config("http").unwrap();
+    assert_eq!(
+        http.structopt_name_of("listen_port"),
+        Some(String::from("listen-port"))
+    );
+    assert_eq!(
+        http.path_of_arg("listen-port"),
+        Some(String::from("listen_port"))
+    );
+    assert_eq!(http.default_at("listen_port"), Some("80".into()));
+    assert_eq!(http.default_at("missing"), None);
+
+    // Defaults are looked up by the name of the config and then the argument
+    let path = |p: &[&str]| p.iter().map(|s| String::from(*s)).collect::<Vec<_>>();
+    assert_eq!(
+        registry.arg_to_os_string(&path(&["db", "url"])),
+        Some("postgres://other".into())
+    );
+    assert!(registry.arg_is_secret(&path(&["db", "password"])));
+    assert!(registry
+        .arg_to_os_string(&path(&["cache", "url"]))
+        .is_none());
+
+    let err = registry
+        .patch_with_config_str("[cache]\nsize = 1\n", ConfigFormat::Toml)
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("`cache` is not a registered config"));
+}
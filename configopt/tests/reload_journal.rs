@@ -0,0 +1,51 @@
+#![cfg(feature = "dev")]
+
+use configopt::{ConfigDiff, ConfigOpt, RecordedInput, ReloadJournal};
+use structopt::StructOpt;
+use tempfile::TempDir;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug, PartialEq))]
+struct MyStruct {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[test]
+fn test_reload_journal() {
+    let dir = TempDir::new().unwrap();
+    let journal = ReloadJournal::open(dir.path(), 2).unwrap();
+    for port in 1..=3 {
+        journal
+            .record(
+                vec![RecordedInput::new(
+                    "config.toml",
+                    format!("port = {}\n", port),
+                )],
+                &ConfigDiff::new(vec![String::from("port")]),
+            )
+            .unwrap();
+    }
+
+    // Only the most recent reloads are kept
+    let records = journal.records().unwrap();
+    assert_eq!(
+        records.iter().map(|r| r.sequence).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert!(records[1].diff().contains("port"));
+
+    let replayed = records[1].replay::<ConfigOptMyStruct>().unwrap();
+    assert_eq!(replayed[0].port, Some(3));
+
+    // Reopening the journal continues the sequence
+    let journal = ReloadJournal::open(dir.path(), 2).unwrap();
+    let record = journal
+        .record(
+            vec![RecordedInput::new("config.toml", "port = \"invalid\"\n")],
+            &ConfigDiff::default(),
+        )
+        .unwrap();
+    assert_eq!(record.sequence, 3);
+    assert!(record.replay::<ConfigOptMyStruct>().is_err());
+}
@@ -0,0 +1,84 @@
+use configopt::{configopt_fields, ConfigOpt, Live, RestartRequired};
+use serde::Deserialize;
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize)]
+#[configopt(derive(Clone, Debug, PartialEq), attrs(serde))]
+struct Server {
+    #[structopt(long)]
+    #[configopt(reload = "restart")]
+    port: u16,
+    #[structopt(long)]
+    timeout: u64,
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug, Deserialize)]
+#[configopt(derive(Clone, Debug, PartialEq), attrs(serde))]
+struct MyStruct {
+    #[structopt(long)]
+    #[configopt(reload = "dynamic")]
+    log_level: String,
+    #[structopt(long)]
+    #[configopt(reload = "restart")]
+    workers: u32,
+    #[structopt(flatten)]
+    #[serde(flatten)]
+    server: Server,
+}
+
+fn parse(args: &[&str]) -> ConfigOptMyStruct {
+    let mut iter = vec!["app"];
+    iter.extend_from_slice(args);
+    ConfigOptMyStruct::from_iter_safe(iter).unwrap()
+}
+
+#[test]
+fn test_reload_policy() {
+    let a = parse(&["--log-level", "info", "--workers", "1", "--port", "1"]);
+    let b = parse(&["--log-level", "debug", "--workers", "1", "--port", "1"]);
+    let diff = a.diff(&b);
+    assert_eq!(diff.changed(), &[String::from("log_level")]);
+    assert!(diff.restart_required().is_empty());
+    assert_eq!(diff.check_reload(), Ok(()));
+    assert!(!diff.warn_restart_required());
+
+    // Restart fields nested in a flattened struct are found too
+    let c = parse(&[
+        "--log-level",
+        "debug",
+        "--workers",
+        "2",
+        "--port",
+        "2",
+        "--timeout",
+        "5",
+    ]);
+    let diff = a.diff(&c);
+    assert_eq!(
+        diff.restart_required(),
+        &[String::from("workers"), String::from("port")]
+    );
+    assert_eq!(diff.dynamic(), vec!["log_level", "timeout"]);
+    let e = diff.check_reload().unwrap_err();
+    assert_eq!(
+        e,
+        RestartRequired {
+            fields: vec![String::from("workers"), String::from("port")]
+        }
+    );
+    assert_eq!(
+        e.to_string(),
+        "changing `workers`, `port` requires a restart"
+    );
+    assert!(diff.warn_restart_required());
+
+    // A rejected reload keeps the current config
+    let live = Live::new(a);
+    let result = live.try_update(|current| {
+        current.diff(&c).check_reload()?;
+        Ok::<_, RestartRequired>(c.clone())
+    });
+    assert!(result.is_err());
+    assert_eq!(live.load().workers, Some(1));
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "dev")]
+
+use configopt::{ConfigOpt, ReloadOutcome, ReloadSimulation};
+use std::{cell::RefCell, rc::Rc, time::Duration};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Clone, Debug, PartialEq))]
+struct Server {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Clone, Debug, PartialEq))]
+struct MyStruct {
+    #[structopt(long)]
+    log_level: String,
+    #[structopt(flatten)]
+    server: Server,
+}
+
+#[test]
+fn test_reload_simulation() {
+    let restarts = Rc::new(RefCell::new(Vec::new()));
+    let restarts_handle = Rc::clone(&restarts);
+    let mut sim = ReloadSimulation::new(
+        |fs, paths| configopt::from_toml_file_in(fs, &paths[0], &[], &mut Vec::new()),
+        ConfigOptMyStruct::diff,
+    )
+    .file("/etc/app.toml", "log_level = \"info\"\n[server]\nport = 80")
+    .on_change("logger", "log_level", |_, _| {})
+    .on_change("server", "server", move |config: &ConfigOptMyStruct, _| {
+        restarts_handle
+            .borrow_mut()
+            .push(config.server.port.unwrap())
+    });
+    assert_eq!(sim.start().unwrap().log_level.as_deref(), Some("info"));
+
+    // Nothing changed
+    assert!(sim.advance(Duration::from_secs(1)).is_none());
+
+    sim.write(
+        "/etc/app.toml",
+        "log_level = \"debug\"\n[server]\nport = 80",
+    );
+    match &sim.advance(Duration::from_secs(1)).unwrap().outcome {
+        ReloadOutcome::Reloaded { diff, applied, .. } => {
+            assert_eq!(diff.changed(), &[String::from("log_level")]);
+            assert_eq!(applied, &[String::from("logger")]);
+        }
+        outcome => panic!("unexpected outcome {:?}", outcome),
+    }
+
+    sim.write(
+        "/etc/app.toml",
+        "log_level = \"debug\"\n[server]\nport = \"x\"",
+    );
+    assert!(matches!(
+        sim.advance(Duration::from_secs(1)).unwrap().outcome,
+        ReloadOutcome::Failed { .. }
+    ));
+    assert_eq!(sim.current().unwrap().server.port, Some(80));
+
+    sim.write(
+        "/etc/app.toml",
+        "log_level = \"debug\"\n[server]\nport = 81",
+    );
+    sim.advance(Duration::from_secs(1));
+    assert_eq!(*restarts.borrow(), vec![81]);
+    assert_eq!(sim.steps().len(), 3);
+}
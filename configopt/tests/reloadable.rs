@@ -0,0 +1,36 @@
+use configopt::{ConfigOpt, Reloadable};
+use std::thread;
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[test]
+fn test_reloadable() {
+    let config =
+        Reloadable::new(MyStruct::try_from_iter_with_configopt(&["app", "--port", "1"]).unwrap());
+    let handle = config.clone();
+    let snapshot = config.get();
+
+    let previous = thread::spawn(move || handle.replace(MyStruct { port: 2 }))
+        .join()
+        .unwrap();
+    assert_eq!(previous.port, 1);
+    // Snapshots are not affected by reloads but every handle sees the new value
+    assert_eq!(snapshot.port, 1);
+    assert_eq!(config.get().port, 2);
+    assert_eq!(format!("{:?}", config), "Reloadable(MyStruct { port: 2 })");
+}
+
+#[test]
+fn test_reloadable_live() {
+    let config = Reloadable::new(MyStruct { port: 1 });
+    let mut subscription = config.live().subscribe();
+    config.clone().replace(MyStruct { port: 2 });
+    assert_eq!(config.live().version(), 1);
+    assert_eq!(subscription.changed().port, 2);
+}
@@ -0,0 +1,50 @@
+use configopt::{ConfigOpt, ConfigOptType, IgnoreHelp};
+use std::{env, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug), rename_all_file("kebab"), rename_all_env("pascal"))]
+struct MyStruct {
+    #[structopt(long, env)]
+    casing_log_level: String,
+    #[structopt(long)]
+    max_connections: u32,
+}
+
+#[test]
+fn test_rename_layers() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(
+        config_file,
+        "casing-log-level = 'debug'\nmax-connections = 3\n"
+    )
+    .unwrap();
+    let config_file = config_file.path().to_str().unwrap();
+
+    // Config keys are kebab case while the flags keep the `structopt` casing
+    let s =
+        MyStruct::try_from_iter_with_configopt(&["app", "--config-files", config_file]).unwrap();
+    assert_eq!(s.casing_log_level, "debug");
+    assert_eq!(s.max_connections, 3);
+    let schema = ConfigOptMyStruct::schema();
+    assert!(schema.field("max-connections").is_some());
+    assert!(schema.field("max_connections").is_none());
+    let c =
+        ConfigOptMyStruct::try_from_iter_ignore_help(&["app", "--max-connections", "5"]).unwrap();
+    assert_eq!(c.toml_config().trim(), "max-connections = 5");
+
+    env::set_var("CasingLogLevel", "warn");
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        config_file,
+        "--max-connections",
+        "5",
+    ])
+    .unwrap();
+    env::remove_var("CasingLogLevel");
+    assert_eq!(s.casing_log_level, "warn");
+    assert_eq!(s.max_connections, 5);
+    assert!(s.get_help().contains("CasingLogLevel"));
+}
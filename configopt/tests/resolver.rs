@@ -0,0 +1,63 @@
+use configopt::{register_resolver, ConfigFormat, ConfigOpt, EnvResolver, FileResolver};
+use std::{env, io::Write, path::PathBuf};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+struct MyStruct {
+    #[structopt(long)]
+    user: String,
+    #[structopt(long)]
+    #[configopt(secret)]
+    password: String,
+    #[structopt(long)]
+    key_file: Option<PathBuf>,
+    #[structopt(long)]
+    tags: Vec<String>,
+}
+
+#[test]
+fn test_resolve_references() {
+    register_resolver("test-env", EnvResolver);
+    register_resolver("test-file", FileResolver);
+    register_resolver("test-upper", |reference: &str| Ok(reference.to_uppercase()));
+    env::set_var("CONFIGOPT_TEST_RESOLVER_USER", "admin");
+    let mut password_file = NamedTempFile::new().unwrap();
+    writeln!(password_file, "hunter2").unwrap();
+
+    let config = format!(
+        "password = \"test-file:{}\"\nkey_file = \"test-upper:/key\"\ntags = [\"test-upper:a\", \"b\"]\n",
+        password_file.path().to_string_lossy()
+    );
+    let s = MyStruct::try_from_iter_with_files(
+        &["app", "--user", "test-env:CONFIGOPT_TEST_RESOLVER_USER"],
+        &[(ConfigFormat::Toml, &config)],
+    )
+    .unwrap();
+    assert_eq!(s.user, "admin");
+    assert_eq!(s.password, "hunter2");
+    assert_eq!(s.key_file, Some(PathBuf::from("/KEY")));
+    assert_eq!(s.tags, vec![String::from("A"), String::from("b")]);
+
+    // A scheme which is not registered is a plain value
+    let s = MyStruct::try_from_iter_with_files(
+        &["app", "--user", "unregistered:admin", "--password", "x"],
+        &[],
+    )
+    .unwrap();
+    assert_eq!(s.user, "unregistered:admin");
+}
+
+#[test]
+fn test_resolve_reference_error() {
+    register_resolver("test-fail", |_: &str| Err(String::from("unavailable")));
+    let e = MyStruct::try_from_iter_with_files(
+        &["app", "--user", "admin", "--password", "test-fail:db"],
+        &[],
+    )
+    .unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "Failed to resolve config value, err: `password` `test-fail:` unavailable"
+    );
+}
@@ -0,0 +1,81 @@
+use configopt::{configopt_fields, testing, ConfigFormat, ConfigOpt, ConfigOptValue};
+use structopt::StructOpt;
+
+#[derive(ConfigOptValue, Clone, Debug, PartialEq)]
+enum Mode {
+    Fast,
+    Safe,
+}
+
+#[derive(ConfigOpt, StructOpt, Clone, Debug, PartialEq)]
+struct Server {
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    tls: bool,
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Clone, Debug, PartialEq)]
+struct MyStruct {
+    #[structopt(long)]
+    name: String,
+    #[structopt(long)]
+    mode: Option<Mode>,
+    #[structopt(long)]
+    tags: Vec<String>,
+    #[structopt(flatten)]
+    server: Server,
+}
+
+fn parse_upper(s: &str) -> Result<String, String> {
+    if s.chars().all(|c| !c.is_lowercase()) {
+        Ok(String::from(s))
+    } else {
+        Err(String::from("must be upper case"))
+    }
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Clone, Debug, PartialEq)]
+struct Mismatch {
+    // Config files accept any string but the CLI does not
+    #[structopt(long, parse(try_from_str = parse_upper))]
+    code: String,
+}
+
+#[test]
+fn test_round_trip() {
+    let value = MyStruct {
+        name: String::from("a \"b\""),
+        mode: Some(Mode::Safe),
+        tags: vec![String::from("x"), String::from("y")],
+        server: Server {
+            port: 8080,
+            tls: true,
+        },
+        config_files: Vec::new(),
+        generate_config: false,
+        explain_config: false,
+    };
+    testing::assert_round_trip(&value);
+    assert!(testing::to_config_str(&value, ConfigFormat::Json)
+        .unwrap()
+        .contains("\"port\": 8080"));
+}
+
+#[test]
+fn test_round_trip_mismatch() {
+    let value = Mismatch {
+        code: String::from("ABC"),
+        config_files: Vec::new(),
+        generate_config: false,
+        explain_config: false,
+    };
+    testing::assert_round_trip(&value);
+    let value = Mismatch {
+        code: String::from("abc"),
+        ..value
+    };
+    assert!(testing::round_trip(&value, ConfigFormat::Toml).is_err());
+}
@@ -0,0 +1,126 @@
+use configopt::{BreakingChange, ConfigOpt, ConfigOptType, Schema, SchemaField};
+use structopt::StructOpt;
+
+mod v1 {
+    use super::*;
+
+    #[derive(ConfigOpt, StructOpt, Debug)]
+    pub struct Database {
+        #[structopt(long)]
+        pub url: String,
+        #[structopt(long)]
+        pub pool_size: Option<u32>,
+    }
+
+    #[derive(ConfigOpt, StructOpt, Debug)]
+    pub struct App {
+        #[structopt(long)]
+        pub host: String,
+        #[structopt(long)]
+        pub port: Option<u16>,
+        #[structopt(long)]
+        pub timeout: u64,
+        #[structopt(long)]
+        pub name: Option<String>,
+        #[structopt(long)]
+        pub verbose: bool,
+        #[structopt(flatten)]
+        pub database: Database,
+    }
+}
+
+mod v2 {
+    use super::*;
+
+    #[derive(ConfigOpt, StructOpt, Debug)]
+    pub struct Database {
+        #[structopt(long)]
+        pub url: String,
+        #[structopt(long)]
+        pub pool_size: u32,
+    }
+
+    #[derive(ConfigOpt, StructOpt, Debug)]
+    pub struct App {
+        #[structopt(long)]
+        pub host: String,
+        #[structopt(long, default_value = "8080")]
+        pub port: u16,
+        #[structopt(long)]
+        pub timeout: String,
+        #[structopt(long)]
+        #[configopt(alias = "name")]
+        pub display_name: Option<String>,
+        #[structopt(long)]
+        pub region: String,
+        #[structopt(flatten)]
+        pub database: Database,
+    }
+}
+
+#[test]
+fn test_schema() {
+    let schema = v1::ConfigOptApp::schema();
+    let field = |path: &str| schema.field(path).unwrap().clone();
+    assert_eq!(
+        field("host"),
+        SchemaField {
+            path: String::from("host"),
+            ty: String::from("String"),
+            required: true,
+            aliases: Vec::new(),
+            format: None,
+            env: None,
+            decode_env: false,
+            arg: Some(String::from("host")),
+            values: Vec::new(),
+            constraints: Default::default(),
+        }
+    );
+    assert_eq!(field("port").ty, "Option<u16>");
+    assert!(!field("port").required);
+    assert!(!field("verbose").required);
+    assert!(field("database.url").required);
+
+    let schema = v2::ConfigOptApp::schema();
+    assert!(!schema.field("port").unwrap().required);
+    assert_eq!(schema.field("name").unwrap().path, "display_name");
+}
+
+#[test]
+fn test_breaking_changes() {
+    let old = Schema::from_json(&v1::ConfigOptApp::schema().to_json()).unwrap();
+    let new = v2::ConfigOptApp::schema();
+    let changes = old.breaking_changes(&new);
+    assert_eq!(
+        changes,
+        vec![
+            BreakingChange::TypeChanged {
+                path: String::from("port"),
+                old: String::from("Option<u16>"),
+                new: String::from("u16"),
+            },
+            BreakingChange::TypeChanged {
+                path: String::from("timeout"),
+                old: String::from("u64"),
+                new: String::from("String"),
+            },
+            BreakingChange::Removed {
+                path: String::from("verbose"),
+            },
+            BreakingChange::TypeChanged {
+                path: String::from("database.pool_size"),
+                old: String::from("Option<u32>"),
+                new: String::from("u32"),
+            },
+            BreakingChange::NowRequired {
+                path: String::from("database.pool_size"),
+            },
+            BreakingChange::AddedRequired {
+                path: String::from("region"),
+            },
+        ]
+    );
+    assert_eq!(changes[2].to_string(), "`verbose` was removed");
+    assert!(new.breaking_changes(&new).is_empty());
+}
@@ -0,0 +1,49 @@
+use configopt::{with_override, ConfigOpt, Reloadable};
+use std::thread;
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Clone, Debug, PartialEq)]
+#[configopt(derive(Debug), global)]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+}
+
+#[test]
+fn test_scoped_override() {
+    let config = Reloadable::new(MyStruct {
+        host: String::from("localhost"),
+        port: 1,
+    });
+    MyStruct::init_global(|| Ok(MyStruct::clone(&config.get()))).unwrap();
+
+    let mut partial = ConfigOptMyStruct {
+        host: None,
+        port: Some(2),
+    };
+    config.get().with_override(&mut partial, || {
+        assert_eq!(config.get().port, 2);
+        assert_eq!(config.get().host, "localhost");
+        assert_eq!(MyStruct::current().port, 2);
+        // The global itself is not modified
+        assert_eq!(MyStruct::global().port, 1);
+
+        with_override(
+            MyStruct {
+                host: String::from("nested"),
+                port: 3,
+            },
+            || assert_eq!(config.get().port, 3),
+        );
+        assert_eq!(config.get().port, 2);
+
+        // Overrides are only seen on the current thread
+        let handle = config.clone();
+        let port = thread::spawn(move || handle.get().port).join().unwrap();
+        assert_eq!(port, 1);
+    });
+    assert_eq!(config.get().port, 1);
+    assert_eq!(MyStruct::current().port, 1);
+}
@@ -0,0 +1,82 @@
+use configopt::{ConfigOpt, ConfigOptArgToOsString, ConfigOptType};
+use std::{convert::TryFrom, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    user: String,
+    #[structopt(long)]
+    #[configopt(secret)]
+    password: String,
+}
+
+#[test]
+fn test_secret_redaction() {
+    let c = ConfigOptMyStruct {
+        user: Some(String::from("admin")),
+        password: Some(String::from("hunter2")),
+        password_file: None,
+    };
+    let debug = format!("{:?}", c);
+    assert!(debug.contains("admin"));
+    assert!(debug.contains("<redacted>"));
+    assert!(!debug.contains("hunter2"));
+    assert_eq!(
+        c.toml_config(),
+        "user = \"admin\"\n\n# password = <redacted>\n\n"
+    );
+    assert!(c.arg_is_secret(&[String::from("password")]));
+    assert!(!c.arg_is_secret(&[String::from("user")]));
+    assert!(!MyStruct::try_from_iter_with_defaults(&["app", "--help"], &c)
+        .unwrap_err()
+        .message
+        .contains("hunter2"));
+}
+
+#[test]
+fn test_secret_file() {
+    let mut secret_file = NamedTempFile::new().unwrap();
+    writeln!(secret_file, "hunter2").unwrap();
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(
+        config_file,
+        "user = \"admin\"\npassword_file = \"{}\"\n",
+        secret_file.path().to_string_lossy()
+    )
+    .unwrap();
+
+    let c = ConfigOptMyStruct::try_from(config_file.path()).unwrap();
+    assert_eq!(c.user, Some(String::from("admin")));
+    assert_eq!(c.password, Some(String::from("hunter2")));
+    assert_eq!(c.password_file, None);
+}
+
+#[test]
+fn test_secret_file_relative_to_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("password"), "hunter2\n").unwrap();
+    let config_file = dir.path().join("config.toml");
+    std::fs::write(&config_file, "password_file = \"password\"\n").unwrap();
+
+    let c = ConfigOptMyStruct::try_from(config_file.as_path()).unwrap();
+    assert_eq!(c.password, Some(String::from("hunter2")));
+}
+
+#[test]
+fn test_missing_secret_file() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(
+        config_file,
+        "user = \"admin\"\npassword_file = \"/does/not/exist\"\n"
+    )
+    .unwrap();
+    let config_file = config_file.path().to_str().unwrap();
+
+    // The config file is not skipped as if it was missing
+    let err = MyStruct::try_from_iter_with_configopt(&["app", "--config-files", config_file])
+        .unwrap_err();
+    assert!(err.to_string().contains("failed to read secret file"));
+}
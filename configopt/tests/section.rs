@@ -0,0 +1,135 @@
+use configopt::{configopt_fields, ConfigOpt, ConfigOptSection, ConfigOptType};
+use serde::Deserialize;
+use std::io::Write;
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOptSection, Debug, Default, PartialEq)]
+#[configopt(derive(Debug))]
+struct Pool {
+    size: u32,
+    lazy: bool,
+}
+
+#[derive(ConfigOptSection, Debug, Default, PartialEq)]
+#[configopt(derive(Debug))]
+struct Database {
+    /// The database to connect to
+    url: String,
+    replicas: Vec<String>,
+    timeout: Option<u64>,
+    #[configopt(section)]
+    pool: Pool,
+}
+
+#[derive(ConfigOptSection, Deserialize, Debug, Default, PartialEq)]
+#[configopt(derive(Debug))]
+#[serde(rename_all = "kebab-case")]
+struct Cache {
+    max_entries: u32,
+    #[serde(rename = "ttl")]
+    time_to_live: Option<u64>,
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    name: String,
+    #[structopt(skip)]
+    #[configopt(section)]
+    database: Database,
+    #[structopt(skip)]
+    #[configopt(section)]
+    cache: Cache,
+}
+
+fn config_file(contents: &str) -> NamedTempFile {
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "{}", contents).unwrap();
+    config_file
+}
+
+#[test]
+fn test_section() {
+    // Sections are not CLI arguments and block conversion until their required fields are set
+    let c = ConfigOptMyStruct::from_iter_safe(&["app", "--name", "a"]).unwrap();
+    assert!(!c.is_convertible());
+    assert_eq!(
+        c.missing_fields(),
+        vec!["database.url", "database.pool.size", "cache.max-entries"]
+    );
+
+    // Each key of a section is merged individually across config files
+    let first = config_file(
+        "[database]\nurl = \"first\"\ntimeout = 5\n[database.pool]\nsize = 1\n[cache]\nmax-entries = 8",
+    );
+    let second = config_file("[database]\nurl = \"second\"\n[database.pool]\nlazy = true");
+    let config_files = format!(
+        "{} {}",
+        first.path().to_str().unwrap(),
+        second.path().to_str().unwrap()
+    );
+    let s = MyStruct::try_from_iter_with_configopt(
+        vec!["app", "--name", "a", "--config-files"]
+            .into_iter()
+            .chain(config_files.split(' ')),
+    )
+    .unwrap();
+    assert_eq!(
+        s.database,
+        Database {
+            url: String::from("second"),
+            replicas: Vec::new(),
+            timeout: Some(5),
+            pool: Pool {
+                size: 1,
+                lazy: true
+            },
+        }
+    );
+
+    let mut c = ConfigOptMyStruct::from_iter_safe(&["app", "--name", "a"]).unwrap();
+    c.patch(&mut ConfigOptMyStruct::from(s));
+    assert!(c.set_fields().contains(&String::from("database.pool.lazy")));
+    let config = c.toml_config();
+    assert!(config.contains("### The database to connect to\ndatabase.url = \"second\"\n"));
+    assert!(config.contains("database.pool.size = 1\n"));
+
+    let schema = ConfigOptMyStruct::schema();
+    assert!(schema.field("database.url").unwrap().required);
+    assert!(!schema.field("database.timeout").unwrap().required);
+    assert!(schema.field("database.pool.size").is_some());
+}
+
+#[test]
+fn test_section_renamed_fields() {
+    let cache = config_file("[cache]\nmax-entries = 8\nttl = 60");
+    let s = MyStruct::try_from_iter_with_configopt(vec![
+        "app",
+        "--name",
+        "a",
+        "--config-files",
+        cache.path().to_str().unwrap(),
+    ])
+    .unwrap();
+    assert_eq!(
+        s.cache,
+        Cache {
+            max_entries: 8,
+            time_to_live: Some(60),
+        }
+    );
+
+    // The keys of renamed fields are the keys they are read from
+    let c = ConfigOptMyStruct::from(s);
+    assert!(c.set_fields().contains(&String::from("cache.ttl")));
+    let config = c.toml_config();
+    assert!(config.contains("cache.max-entries = 8\n"));
+    assert!(config.contains("cache.ttl = 60\n"));
+    let schema = ConfigOptMyStruct::schema();
+    assert!(schema.field("cache.max-entries").unwrap().required);
+    assert!(schema.field("cache.ttl").is_some());
+    assert!(schema.field("cache.time_to_live").is_none());
+}
@@ -0,0 +1,58 @@
+use configopt::{ConfigOpt, ConfigOptType};
+use std::collections::HashSet;
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct Database {
+    #[structopt(long)]
+    url: String,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct Server {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug), section_order("server"))]
+struct MyStruct {
+    #[structopt(flatten)]
+    database: Database,
+    #[structopt(long)]
+    name: String,
+    #[structopt(flatten)]
+    server: Server,
+    #[structopt(long)]
+    tags: Vec<String>,
+    #[structopt(skip)]
+    #[configopt(file_only)]
+    features: HashSet<String>,
+}
+
+#[test]
+fn test_section_order() {
+    let mut c = ConfigOptMyStruct::from_iter_safe(&[
+        "app",
+        "--url",
+        "postgres://db",
+        "--port",
+        "80",
+        "--tags",
+        "b",
+        "a",
+    ])
+    .unwrap();
+    c.features = Some(vec!["z", "x", "y"].into_iter().map(String::from).collect());
+    // Top level keys come first and the sets are sorted
+    let expected = "# name =\n\n\
+                    tags = [\"b\", \"a\"]\n\n\
+                    features = [\"x\", \"y\", \"z\"]\n\n\
+                    server.port = 80\n\n\
+                    database.url = \"postgres://db\"\n\n";
+    assert_eq!(c.toml_config(), expected);
+    // The output is the same every time
+    assert_eq!(c.toml_config(), c.toml_config());
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "shaku")]
+
+use configopt::{ConfigInterface, ConfigModule, ConfigOpt, Reloadable};
+use shaku::{module, HasComponent};
+use std::sync::Arc;
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+struct MyStruct {
+    #[structopt(long)]
+    port: u16,
+}
+
+module! {
+    AppModule {
+        components = [],
+        providers = [],
+
+        use ConfigModule<MyStruct> {
+            components = [dyn ConfigInterface<MyStruct>],
+            providers = []
+        }
+    }
+}
+
+#[test]
+fn test_shaku_component() {
+    let config = Reloadable::new(MyStruct { port: 1 });
+    // The config is required to build the module
+    let module = AppModule::builder(Arc::new(ConfigModule::new(config.clone()))).build();
+    let component: &dyn ConfigInterface<MyStruct> = module.resolve_ref();
+    assert_eq!(component.get().port, 1);
+    // The module shares the handle so reloads are seen through it
+    config.replace(MyStruct { port: 2 });
+    assert_eq!(component.get().port, 2);
+    assert_eq!(component.reloadable().get().port, 2);
+    let resolved: Arc<dyn ConfigInterface<MyStruct>> = module.resolve();
+    assert_eq!(resolved.get().port, 2);
+}
@@ -0,0 +1,81 @@
+use configopt::{ConfigFormat, ConfigOpt, ConfigOptSection, ConfigOptType};
+use std::{convert::TryFrom, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+// Defined once and used by the root type of each binary
+#[derive(ConfigOptSection, Debug, Default, PartialEq)]
+#[configopt(derive(Debug))]
+struct Common {
+    log_level: String,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug), strict, binary("server"))]
+struct Server {
+    #[structopt(long)]
+    port: u16,
+    // Named like the other binary
+    #[structopt(long)]
+    worker: Option<String>,
+    #[structopt(skip)]
+    #[configopt(section)]
+    common: Common,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug), strict, binary("worker"))]
+struct Worker {
+    #[structopt(long)]
+    threads: u32,
+    #[structopt(skip)]
+    #[configopt(section)]
+    common: Common,
+}
+
+const SHARED: &str = r#"
+[common]
+log_level = "info"
+
+[server]
+port = 8080
+worker = "pool"
+
+[worker]
+threads = 4
+
+[worker.common]
+log_level = "debug"
+"#;
+
+#[test]
+fn test_shared_config_file() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(config_file, "{}", SHARED).unwrap();
+
+    // The tables of the other binaries are not unknown keys
+    let server = ConfigOptServer::try_from(config_file.path()).unwrap();
+    assert_eq!(server.port, Some(8080));
+    assert_eq!(server.worker, Some(String::from("pool")));
+    assert_eq!(server.common.log_level, Some(String::from("info")));
+    let worker = ConfigOptWorker::try_from(config_file.path()).unwrap();
+    assert_eq!(worker.threads, Some(4));
+    assert_eq!(worker.common.log_level, Some(String::from("debug")));
+
+    let files = [(ConfigFormat::Toml, SHARED)];
+    let server = Server::try_from_iter_with_files(&["server"], &files).unwrap();
+    assert_eq!(
+        (server.port, server.common.log_level.as_str()),
+        (8080, "info")
+    );
+    assert_eq!(ConfigOptServer::binary(), Some("server"));
+    assert_eq!(ConfigOptServer::shared_sections(), &["common"]);
+
+    // The keys of the table of the binary are still checked
+    let err = Worker::try_from_iter_with_files(
+        &["worker"],
+        &[(ConfigFormat::Toml, "[worker]\nverbose = true\n")],
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("`verbose`"));
+}
@@ -0,0 +1,62 @@
+#![cfg(all(unix, feature = "sighup"))]
+
+use configopt::{configopt_fields, ConfigOpt, Reloadable, ShutdownToken, SighupReload};
+use std::{
+    fs,
+    io::Write,
+    process::{self, Command},
+    time::Duration,
+};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    workers: u32,
+}
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[test]
+fn test_reload_on_sighup() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(config_file, "workers = 1").unwrap();
+    let config_files = config_file.path().to_string_lossy().to_string();
+    let load = move || {
+        MyStruct::try_from_iter_with_configopt(&["app", "--config-files", config_files.as_str()])
+    };
+
+    let shutdown = ShutdownToken::new();
+    let config = Reloadable::new(load().unwrap());
+    let reload = SighupReload::start(config.clone(), shutdown.clone(), load).unwrap();
+    assert_eq!(config.get().workers, 1);
+
+    fs::write(config_file.path(), "workers = 2").unwrap();
+    let status = Command::new("kill")
+        .args(&["-HUP", &process::id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(reload.changes().recv_timeout(TIMEOUT).unwrap().workers, 2);
+    assert_eq!(config.get().workers, 2);
+
+    // A config which fails to load keeps the previous value
+    fs::write(config_file.path(), "workers = 'many'").unwrap();
+    reload.trigger();
+    while reload.last_error().is_none() {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(config.get().workers, 2);
+
+    fs::write(config_file.path(), "workers = 3").unwrap();
+    reload.trigger();
+    assert_eq!(reload.changes().recv_timeout(TIMEOUT).unwrap().workers, 3);
+    assert_eq!(reload.last_error(), None);
+
+    // Dropping stops reloading without cancelling the token it was given
+    drop(reload);
+    assert!(!shutdown.is_cancelled());
+}
@@ -0,0 +1,57 @@
+use configopt::{ConfigOpt, ConfigOptType};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq), skip(builder, diff, partial, schema))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(subcommand)]
+    cmd: Option<Command>,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq), skip(schema))]
+enum Command {
+    Run {
+        #[structopt(long)]
+        fast: bool,
+    },
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(skip(schema))]
+struct Limited {
+    #[structopt(long)]
+    #[configopt(min = 1, max = 65535)]
+    port: u16,
+}
+
+#[test]
+fn test_skip() {
+    let s =
+        MyStruct::try_from_iter_with_configopt(&["app", "--host", "a", "run", "--fast"]).unwrap();
+    assert_eq!(s.host, "a");
+    assert_eq!(s.cmd, Some(Command::Run { fast: true }));
+
+    // The skipped surface is not generated
+    assert!(ConfigOptMyStruct::schema().fields.is_empty());
+    assert!(ConfigOptCommand::schema().fields.is_empty());
+    let mut c = ConfigOptMyStruct::default();
+    c.take(&mut ConfigOptMyStruct::from(s));
+    assert_eq!(c.host, Some(String::from("a")));
+    assert!(c.cmd.is_some());
+}
+
+#[test]
+fn test_skip_schema_still_checks_values() {
+    assert!(ConfigOptLimited::schema().fields.is_empty());
+    assert!(Limited::try_from_iter_with_configopt(&["app", "--port", "80"]).is_ok());
+    let err = Limited::try_from_iter_with_configopt(&["app", "--port", "0"]).unwrap_err();
+    assert_eq!(err.field_path(), Some("port"));
+
+    // Field paths are typed with the runtime schema
+    let mut c = ConfigOptLimited::default();
+    c.set(&"port".into(), "8080").unwrap();
+    assert_eq!(c.port, Some(8080));
+}
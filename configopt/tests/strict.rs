@@ -0,0 +1,76 @@
+use configopt::{ConfigOpt, Error};
+use std::{convert::TryFrom, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug), strict)]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct NotStrict {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct Server {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug), strict)]
+struct Nested {
+    #[structopt(long)]
+    name: String,
+    #[structopt(flatten)]
+    server: Server,
+}
+
+#[test]
+fn test_strict_unknown_key() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "host = \"localhost\"\nprot = 80").unwrap();
+    let err = ConfigOptMyStruct::try_from(config_file.path()).unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains(&*config_file.path().to_string_lossy()));
+    assert!(message.contains("unknown key `prot`"));
+    assert!(message.contains("did you mean `port`?"));
+
+    let c = ConfigOptNotStrict::try_from(config_file.path()).unwrap();
+    assert_eq!(c.port, None);
+}
+
+#[test]
+fn test_strict_nested_unknown_key() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(
+        config_file,
+        "name = \"app\"\n[server]\nhost = \"localhost\"\nprot = 80"
+    )
+    .unwrap();
+    // `Server` is not strict itself but its keys are checked as part of the strict type
+    match ConfigOptNested::try_from(config_file.path()).unwrap_err() {
+        Error::UnknownKey { key, suggestion } => {
+            assert_eq!(key.key, "server.prot");
+            assert_eq!(key.path, config_file.path());
+            assert_eq!(suggestion.as_deref(), Some("server.port"));
+        }
+        e => panic!("expected an unknown key error, got {}", e),
+    }
+
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "name = \"app\"\n[server]\nport = 80").unwrap();
+    let c = ConfigOptNested::try_from(config_file.path()).unwrap();
+    assert_eq!(c.server.port, Some(80));
+}
@@ -0,0 +1,104 @@
+use configopt::{configopt_fields, ConfigOpt, ConfigOptType};
+use std::{borrow::Cow, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long, parse(from_str = configopt::cow_from_str))]
+    name: Cow<'static, str>,
+    #[structopt(long, parse(from_str = configopt::cow_from_str))]
+    region: Option<Cow<'static, str>>,
+}
+
+#[test]
+fn test_cow_str() {
+    let s = MyStruct::try_from_iter_with_configopt(&["app", "--name", "cli"]).unwrap();
+    assert_eq!(s.name, "cli");
+    assert_eq!(s.region, None);
+
+    // The required field is reconstructed as an argument from the config file
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "name = \"file\"\nregion = \"eu\"").unwrap();
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        config_file.path().to_str().unwrap(),
+    ])
+    .unwrap();
+    assert_eq!(s.name, "file");
+    assert_eq!(s.region.as_deref(), Some("eu"));
+
+    let c = ConfigOptMyStruct::default()
+        .with_name("built")
+        .with_region(String::from("us"));
+    let config = c.toml_config();
+    assert!(config.contains("name = \"built\""));
+    assert!(config.contains("region = \"us\""));
+}
+
+#[cfg(feature = "smol_str")]
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct SmolStruct {
+    #[structopt(long)]
+    name: smol_str::SmolStr,
+    #[structopt(long)]
+    region: Option<smol_str::SmolStr>,
+}
+
+#[cfg(feature = "smol_str")]
+#[test]
+fn test_smol_str() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "name = \"file\"\nregion = \"eu\"").unwrap();
+    let s = SmolStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        config_file.path().to_str().unwrap(),
+    ])
+    .unwrap();
+    assert_eq!(s.name, "file");
+    assert_eq!(s.region.as_deref(), Some("eu"));
+
+    let config = ConfigOptSmolStruct::default()
+        .with_name("built")
+        .toml_config();
+    assert!(config.contains("name = \"built\""));
+}
+
+#[cfg(feature = "compact_str")]
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct CompactStruct {
+    #[structopt(long)]
+    name: compact_str::CompactString,
+    #[structopt(long)]
+    region: Option<compact_str::CompactString>,
+}
+
+#[cfg(feature = "compact_str")]
+#[test]
+fn test_compact_string() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "name = \"file\"\nregion = \"eu\"").unwrap();
+    let s = CompactStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--config-files",
+        config_file.path().to_str().unwrap(),
+        "--region",
+        "us",
+    ])
+    .unwrap();
+    assert_eq!(s.name, "file");
+    assert_eq!(s.region.as_deref(), Some("us"));
+
+    let config = ConfigOptCompactStruct::default()
+        .with_name("built")
+        .toml_config();
+    assert!(config.contains("name = \"built\""));
+}
@@ -0,0 +1,78 @@
+use configopt::ConfigOpt;
+use std::io::Write;
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct App {
+    #[structopt(long)]
+    level: Option<String>,
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+enum Command {
+    Serve(Serve),
+    Check,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct Serve {
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    host: Option<String>,
+}
+
+fn parse(config: &str, args: &[&str]) -> configopt::Result<App> {
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(config_file, "{}", config).unwrap();
+    let config_files = config_file.path().to_string_lossy().to_string();
+    let mut iter = vec!["app", "--config-files", &config_files];
+    iter.extend_from_slice(args);
+    App::try_from_iter_with_configopt(iter)
+}
+
+#[test]
+fn test_subcommand_from_config_file() {
+    let config = "level = \"debug\"\ncommand = \"serve\"\n\n[serve]\nport = 8080\nhost = \"a\"\n";
+
+    // The config file selects the subcommand if it is not on the CLI
+    assert_eq!(
+        parse(config, &[]).unwrap(),
+        App {
+            level: Some(String::from("debug")),
+            command: Command::Serve(Serve {
+                port: 8080,
+                host: Some(String::from("a")),
+            }),
+        }
+    );
+
+    // A subcommand on the CLI takes precedence
+    assert_eq!(parse(config, &["check"]).unwrap().command, Command::Check);
+    match parse(config, &["serve", "--port", "1"]).unwrap().command {
+        Command::Serve(serve) => assert_eq!(serve.port, 1),
+        command => panic!("unexpected subcommand {:?}", command),
+    }
+
+    assert_eq!(
+        parse("command = \"check\"", &[]).unwrap().command,
+        Command::Check
+    );
+    // The variant form is still accepted
+    assert_eq!(
+        parse("[command.Serve]\nport = 1", &[]).unwrap().command,
+        Command::Serve(Serve {
+            port: 1,
+            host: None,
+        })
+    );
+    // The arguments of the subcommand still need to be set somewhere
+    assert!(parse("command = \"serve\"", &[]).is_err());
+    assert!(parse("", &[]).is_err());
+}
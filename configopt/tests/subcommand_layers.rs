@@ -0,0 +1,137 @@
+use configopt::ConfigOpt;
+use std::io::Write;
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct App {
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+enum Command {
+    Serve(Serve),
+    Db(Db),
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct Serve {
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    host: String,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct Db {
+    #[structopt(long)]
+    url: String,
+    #[structopt(subcommand)]
+    command: DbCommand,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+enum DbCommand {
+    Migrate(Migrate),
+    Dump(Dump),
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct Migrate {
+    #[structopt(long)]
+    target: String,
+    #[structopt(long)]
+    steps: u32,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct Dump {
+    #[structopt(long)]
+    output: String,
+}
+
+fn parse(config: &str, args: &[&str]) -> configopt::Result<App> {
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(config_file, "{}", config).unwrap();
+    let config_files = config_file.path().to_string_lossy().to_string();
+    let mut iter = vec!["app", "--config-files", &config_files];
+    iter.extend_from_slice(args);
+    App::try_from_iter_with_configopt(iter)
+}
+
+#[test]
+fn test_subcommand_fields_are_merged() {
+    // The config file and the CLI each provide some of the fields of the same subcommand
+    let config = "command = \"serve\"\n\n[serve]\nport = 1\nhost = \"file\"\n";
+    assert_eq!(
+        parse(config, &["serve", "--host", "x"]).unwrap().command,
+        Command::Serve(Serve {
+            port: 1,
+            host: String::from("x"),
+        })
+    );
+    assert_eq!(
+        parse("[command.Serve]\nport = 1", &["serve", "--host", "x"])
+            .unwrap()
+            .command,
+        Command::Serve(Serve {
+            port: 1,
+            host: String::from("x"),
+        })
+    );
+}
+
+#[test]
+fn test_nested_subcommand_fields_are_merged() {
+    let config = "[command.Db]\nurl = \"file\"\n\n[command.Db.command.Migrate]\nsteps = 3\ntarget = \"file\"\n";
+    assert_eq!(
+        parse(config, &["db", "migrate", "--target", "x"])
+            .unwrap()
+            .command,
+        Command::Db(Db {
+            url: String::from("file"),
+            command: DbCommand::Migrate(Migrate {
+                target: String::from("x"),
+                steps: 3,
+            }),
+        })
+    );
+    assert_eq!(
+        parse(config, &["db", "--url", "x", "migrate"])
+            .unwrap()
+            .command,
+        Command::Db(Db {
+            url: String::from("x"),
+            command: DbCommand::Migrate(Migrate {
+                target: String::from("file"),
+                steps: 3,
+            }),
+        })
+    );
+}
+
+#[test]
+fn test_different_subcommands_are_not_merged() {
+    // The CLI replaces the subcommand of the config file as a whole
+    let config = "[command.Db]\nurl = \"file\"\n\n[command.Db.command.Migrate]\nsteps = 3\ntarget = \"file\"\n";
+    assert!(parse(config, &["serve", "--host", "x"]).is_err());
+    assert_eq!(
+        parse(config, &["db", "dump", "--output", "x"])
+            .unwrap()
+            .command,
+        Command::Db(Db {
+            url: String::from("file"),
+            command: DbCommand::Dump(Dump {
+                output: String::from("x"),
+            }),
+        })
+    );
+}
@@ -113,6 +113,7 @@ fn test_configopt_generate_config() {
             cmd: None,
             config_files: None,
             generate_config: Some(true).into(),
+            ..Default::default()
         },
         ConfigOptMyStruct::from_iter_safe(&["app", "--generate-config"]).unwrap()
     );
@@ -134,10 +135,12 @@ fn test_configopt_generate_config() {
                     flat_numbers: None,
                 },
                 config_files: None,
-                generate_config: Some(true).into()
+                generate_config: Some(true).into(),
+                ..Default::default()
             })),
             config_files: None,
             generate_config: None.into(),
+            ..Default::default()
         },
         ConfigOptMyStruct::from_iter_safe(&["app", "cmd3", "--generate-config"]).unwrap()
     );
@@ -159,10 +162,12 @@ fn test_configopt_generate_config() {
                     flat_numbers: None,
                 },
                 config_files: None,
-                generate_config: Some(true).into()
+                generate_config: Some(true).into(),
+                ..Default::default()
             })),
             config_files: None,
             generate_config: Some(true).into(),
+            ..Default::default()
         },
         ConfigOptMyStruct::from_iter_safe(&[
             "app",
@@ -190,10 +195,12 @@ fn test_configopt_generate_config() {
                     flat_numbers: None,
                 },
                 config_files: None,
-                generate_config: None.into()
+                generate_config: None.into(),
+                ..Default::default()
             })),
             config_files: None,
             generate_config: Some(true).into(),
+            ..Default::default()
         },
         ConfigOptMyStruct::from_iter_safe(&["app", "--generate-config", "cmd3",]).unwrap()
     );
@@ -212,7 +219,8 @@ fn test_configopt_from_cli_no_args() {
             path: None,
             cmd: None,
             config_files: None,
-            generate_config: None.into()
+            generate_config: None.into(),
+            ..Default::default()
         },
         ConfigOptMyStruct::from_iter_safe(&["app"]).unwrap()
     );
@@ -227,7 +235,8 @@ fn test_configopt_from_cli_no_args() {
             path: None,
             cmd: Some(ConfigOptMyEnum::Cmd1),
             config_files: None,
-            generate_config: None.into()
+            generate_config: None.into(),
+            ..Default::default()
         },
         ConfigOptMyStruct::from_iter_safe(&["app", "cmd1"]).unwrap()
     );
@@ -245,7 +254,8 @@ fn test_configopt_from_cli_no_args() {
                 field_2: None,
             }),
             config_files: None,
-            generate_config: None.into()
+            generate_config: None.into(),
+            ..Default::default()
         },
         ConfigOptMyStruct::from_iter_safe(&["app", "cmd2"]).unwrap()
     );
@@ -267,10 +277,12 @@ fn test_configopt_from_cli_no_args() {
                     flat_numbers: None,
                 },
                 config_files: None,
-                generate_config: None.into()
+                generate_config: None.into(),
+                ..Default::default()
             })),
             config_files: None,
-            generate_config: None.into()
+            generate_config: None.into(),
+            ..Default::default()
         },
         ConfigOptMyStruct::from_iter_safe(&["app", "cmd3"]).unwrap()
     );
@@ -293,7 +305,8 @@ fn test_configopt_from_cli_all_args() {
             path: Some(PathBuf::from("/some/path")),
             cmd: Some(ConfigOptMyEnum::Cmd1),
             config_files: None,
-            generate_config: None.into()
+            generate_config: None.into(),
+            ..Default::default()
         },
         ConfigOptMyStruct::from_iter_safe(cli).unwrap()
     );
@@ -315,7 +328,8 @@ fn test_configopt_from_cli_all_args() {
                 field_2: Some(String::from("from_cli4"))
             }),
             config_files: None,
-            generate_config: None.into()
+            generate_config: None.into(),
+            ..Default::default()
         },
         ConfigOptMyStruct::from_iter_safe(cli).unwrap()
     );
@@ -341,10 +355,12 @@ fn test_configopt_from_cli_all_args() {
                     flat_numbers: Some(vec![7]),
                 },
                 config_files: None,
-                generate_config: None.into()
+                generate_config: None.into(),
+                ..Default::default()
             })),
             config_files: None,
-            generate_config: None.into()
+            generate_config: None.into(),
+            ..Default::default()
         },
         ConfigOptMyStruct::from_iter_safe(cli).unwrap()
     );
@@ -481,6 +497,7 @@ fn test_take_for() {
         cmd: None,
         config_files: None,
         generate_config: None.into(),
+        ..Default::default()
     };
     let mut s =
         MyStruct::try_from_iter_with_defaults(&["app", "cmd3", "--field-a=from_cli"], &c).unwrap();
@@ -504,6 +521,7 @@ fn test_patch_for() {
         cmd: None,
         config_files: None,
         generate_config: None.into(),
+        ..Default::default()
     };
 
     let mut s = MyStruct::try_from_iter_with_defaults(
@@ -549,6 +567,7 @@ fn test_from() {
         cmd: MyEnum::Cmd1,
         config_files: Vec::new(),
         generate_config: false.into(),
+        explain_config: false.into(),
     };
     let c1 = ConfigOptMyStruct::from(m);
     let c2 = ConfigOptMyStruct {
@@ -562,6 +581,7 @@ fn test_from() {
         cmd: Some(ConfigOptMyEnum::Cmd1),
         config_files: Some(Vec::new()),
         generate_config: Some(false).into(),
+        explain_config: Some(false).into(),
     };
     assert_eq!(c1, c2);
 
@@ -576,6 +596,7 @@ fn test_from() {
         flat_struct: f,
         config_files: Vec::new(),
         generate_config: false,
+        explain_config: false,
     };
     let f = MyStruct {
         maybe: true,
@@ -588,6 +609,7 @@ fn test_from() {
         cmd: MyEnum::Cmd3(a),
         config_files: Vec::new(),
         generate_config: false,
+        explain_config: false,
     };
     let c1 = ConfigOptMyStruct::from(f);
     let f = ConfigOptFlatStruct {
@@ -601,6 +623,7 @@ fn test_from() {
         flat_struct: f,
         config_files: Some(Vec::new()),
         generate_config: Some(false).into(),
+        explain_config: Some(false).into(),
     };
     let c2 = ConfigOptMyStruct {
         maybe: Some(true).into(),
@@ -613,6 +636,7 @@ fn test_from() {
         cmd: Some(ConfigOptMyEnum::Cmd3(a)),
         config_files: Some(Vec::new()),
         generate_config: Some(false).into(),
+        explain_config: Some(false).into(),
     };
     assert_eq!(c1, c2);
 }
@@ -632,6 +656,7 @@ fn test_try_from() {
         cmd: Some(ConfigOptMyEnum::Cmd1),
         config_files: None,
         generate_config: None.into(),
+        ..Default::default()
     };
     assert!(!c.is_convertible());
     c.not_optional = Some(String::from("testing123"));
@@ -651,6 +676,7 @@ fn test_try_from() {
         cmd: MyEnum::Cmd1,
         config_files: Vec::new(),
         generate_config: false,
+        explain_config: false,
     };
     assert_eq!(m1, m2);
 
@@ -665,6 +691,7 @@ fn test_try_from() {
         flat_struct: f,
         config_files: None,
         generate_config: Some(false).into(),
+        ..Default::default()
     };
     let mut c = ConfigOptMyStruct {
         maybe: Some(true).into(),
@@ -677,6 +704,7 @@ fn test_try_from() {
         cmd: Some(ConfigOptMyEnum::Cmd3(a)),
         config_files: None,
         generate_config: None.into(),
+        ..Default::default()
     };
     assert!(!c.is_convertible());
     let f = ConfigOptFlatStruct {
@@ -690,6 +718,7 @@ fn test_try_from() {
         flat_struct: f,
         config_files: None,
         generate_config: Some(false).into(),
+        ..Default::default()
     };
     c.cmd = Some(ConfigOptMyEnum::Cmd3(a));
     assert!(c.is_convertible());
@@ -705,6 +734,7 @@ fn test_try_from() {
         flat_struct: f,
         config_files: Vec::new(),
         generate_config: false,
+        explain_config: false,
     };
     let m2 = MyStruct {
         maybe: true,
@@ -717,6 +747,7 @@ fn test_try_from() {
         cmd: MyEnum::Cmd3(a),
         config_files: Vec::new(),
         generate_config: false,
+        explain_config: false,
     };
     assert_eq!(m1, m2);
 }
@@ -26,6 +26,7 @@ fn test_configopt_with_optional_bool() {
         maybe: Some(true),
         config_files: None,
         generate_config: None.into(),
+        ..Default::default()
     };
     let s = MyStruct::try_from_iter_with_defaults(&["app"], &c).unwrap();
     assert_eq!(None, s.maybe);
@@ -40,6 +41,7 @@ fn test_configopt_with_optional_bool() {
         maybe: Some(false),
         config_files: None,
         generate_config: None.into(),
+        ..Default::default()
     };
     let s = MyStruct::try_from_iter_with_defaults(&["app"], &c).unwrap();
     assert_eq!(None, s.maybe);
@@ -73,6 +75,7 @@ fn test_configopt_with_bool() {
         maybe: Some(true).into(),
         config_files: None,
         generate_config: None.into(),
+        ..Default::default()
     };
     let s = MyStruct::try_from_iter_with_defaults(&["app"], &c).unwrap();
     // We want this to be true, but setting a default value for a boolean is impossible.
@@ -84,6 +87,7 @@ fn test_configopt_with_bool() {
         maybe: Some(false).into(),
         config_files: None,
         generate_config: None.into(),
+        ..Default::default()
     };
     let s = MyStruct::try_from_iter_with_defaults(&["app"], &c).unwrap();
     assert_eq!(false, s.maybe);
@@ -0,0 +1,89 @@
+#![cfg(feature = "tls")]
+
+use configopt::{ShutdownToken, TlsConfig, TlsError, TlsRotation};
+use std::{
+    fs,
+    path::Path,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+use tempfile::TempDir;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tls");
+
+fn copy_fixture(name: &str, to: &Path) {
+    // Leave the mtime of the previous write behind before writing again
+    thread::sleep(Duration::from_millis(20));
+    fs::copy(Path::new(FIXTURES).join(name), to).unwrap();
+}
+
+fn wait_until(mut condition: impl FnMut() -> bool) {
+    let started = Instant::now();
+    while !condition() {
+        assert!(started.elapsed() < Duration::from_secs(10), "timed out");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn test_tls_rotation() {
+    let dir = TempDir::new().unwrap();
+    let config = TlsConfig {
+        cert: dir.path().join("cert.pem"),
+        key: dir.path().join("key.pem"),
+    };
+    copy_fixture("cert.pem", &config.cert);
+    copy_fixture("key.pem", &config.key);
+
+    let shutdown = ShutdownToken::new();
+    let rotation =
+        TlsRotation::start(&config, Duration::from_millis(10), shutdown.clone()).unwrap();
+    let current = rotation.server_config();
+    let initial = current.load_full();
+
+    // Rotating both files swaps in a new config
+    copy_fixture("rotated_key.pem", &config.key);
+    copy_fixture("rotated_cert.pem", &config.cert);
+    wait_until(|| !Arc::ptr_eq(&initial, &current.load_full()));
+
+    // A broken file keeps the previous config
+    thread::sleep(Duration::from_millis(20));
+    fs::write(&config.cert, "not a certificate").unwrap();
+    wait_until(|| rotation.last_error().is_some());
+    let kept = current.load_full();
+    thread::sleep(Duration::from_millis(100));
+    assert!(Arc::ptr_eq(&kept, &current.load_full()));
+    assert!(rotation
+        .last_error()
+        .unwrap()
+        .contains("does not contain any certificates"));
+
+    shutdown.cancel();
+    drop(rotation);
+
+    let missing = TlsConfig {
+        cert: dir.path().join("missing.pem"),
+        key: config.key.clone(),
+    };
+    assert!(matches!(
+        TlsRotation::start(&missing, Duration::from_millis(10), ShutdownToken::new()),
+        Err(TlsError::Read(_, _))
+    ));
+}
+
+#[test]
+fn test_shutdown_child() {
+    let parent = ShutdownToken::new();
+    let child = parent.child();
+    let sibling = parent.child();
+
+    // Cancelling a child leaves its parent and siblings running
+    child.cancel();
+    assert!(!parent.is_cancelled());
+    assert!(!sibling.is_cancelled());
+
+    parent.cancel();
+    assert!(sibling.is_cancelled());
+    assert!(parent.child().is_cancelled());
+}
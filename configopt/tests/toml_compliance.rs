@@ -0,0 +1,78 @@
+use configopt::{testing, ConfigFormat, ConfigOpt, ConfigOptType};
+use std::collections::HashMap;
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Clone, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct Output {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[derive(ConfigOpt, StructOpt, Clone, Debug, PartialEq)]
+struct MyStruct {
+    #[structopt(long)]
+    script: String,
+    #[structopt(long)]
+    pattern: String,
+    #[structopt(long)]
+    ratio: f64,
+    #[structopt(long)]
+    count: u64,
+    #[structopt(skip)]
+    #[configopt(map)]
+    outputs: HashMap<String, Output>,
+}
+
+fn my_struct() -> MyStruct {
+    MyStruct {
+        script: String::from("#!/bin/sh\necho 'hello'\nexit 0\n"),
+        pattern: String::from(r"^\d+\.\d+$"),
+        ratio: std::f64::INFINITY,
+        count: std::i64::MAX as u64,
+        outputs: HashMap::new(),
+    }
+}
+
+#[test]
+fn test_literal_strings() {
+    let s = my_struct();
+    let config = ConfigOptMyStruct::from(s.clone()).toml_config();
+    assert!(config.contains("script = '''\n#!/bin/sh\necho 'hello'\nexit 0\n'''"));
+    assert!(config.contains(r"pattern = '^\d+\.\d+$'"));
+    assert!(config.contains("ratio = inf"));
+    assert!(config.contains(&format!("count = {}", std::i64::MAX)));
+    testing::assert_round_trip(&s);
+}
+
+#[test]
+fn test_nan() {
+    let mut s = my_struct();
+    s.ratio = std::f64::NAN;
+    let config = ConfigOptMyStruct::from(s.clone()).toml_config();
+    assert!(config.contains("ratio = nan"));
+    let parsed = testing::round_trip(&s, ConfigFormat::Toml).unwrap();
+    assert!(parsed.ratio.is_nan());
+}
+
+#[test]
+fn test_large_integer() {
+    let mut s = my_struct();
+    s.count = std::u64::MAX;
+    // The field is not dropped from the generated config
+    let config = ConfigOptMyStruct::from(s).toml_config();
+    assert!(config.contains("### [error: "));
+    assert!(config.contains("# count =\n"));
+}
+
+#[test]
+fn test_dotted_keys() {
+    let c = ConfigOptMyStruct::try_from_config_str(
+        "script = 'a'\noutputs.a.port = 1\noutputs.b = { port = 2 }\n",
+        ConfigFormat::Toml,
+    )
+    .unwrap();
+    assert_eq!(c.script.as_deref(), Some("a"));
+    assert_eq!(c.outputs["a"].port, Some(1));
+    assert_eq!(c.outputs["b"].port, Some(2));
+}
@@ -0,0 +1,31 @@
+use configopt::{configopt_fields, ConfigOpt, Error, ErrorCategory};
+use structopt::StructOpt;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[structopt(version = "1.0")]
+struct MyStruct {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[test]
+fn test_try_from_iter() {
+    let s = MyStruct::try_from_iter_with_configopt(&["app", "--port", "1"]).unwrap();
+    assert_eq!(s.port, 1);
+
+    // None of these exit the process
+    let category = |args: &[&str]| {
+        MyStruct::try_from_iter_with_configopt(args)
+            .unwrap_err()
+            .category()
+    };
+    assert_eq!(category(&["app"]), ErrorCategory::Usage);
+    assert_eq!(category(&["app", "--port", "x"]), ErrorCategory::Usage);
+    assert_eq!(category(&["app", "--help"]), ErrorCategory::Help);
+    assert_eq!(category(&["app", "--version"]), ErrorCategory::Help);
+    match MyStruct::try_from_iter_with_configopt(&["app", "--port", "2", "--generate-config"]) {
+        Err(Error::ConfigGenerated(config)) => assert!(config.contains("port = 2")),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
@@ -0,0 +1,57 @@
+use configopt::{ConfigOpt, UnknownKey};
+use std::{collections::HashMap, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct Server {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(skip)]
+    #[configopt(map)]
+    servers: HashMap<String, Server>,
+}
+
+#[test]
+fn test_collect_unknown_keys() {
+    let mut config_file = NamedTempFile::new().unwrap();
+    write!(
+        config_file,
+        "host = \"localhost\"\nhots = \"x\"\n[servers.a]\nport = 80\nprot = 81\n"
+    )
+    .unwrap();
+    let path = config_file.path();
+
+    let mut unknown_keys = Vec::new();
+    let c = ConfigOptMyStruct::try_from_path_collecting_unknown(path, &mut unknown_keys).unwrap();
+    assert_eq!(c.host, Some(String::from("localhost")));
+    assert_eq!(c.servers["a"].port, Some(80));
+    assert_eq!(
+        unknown_keys,
+        vec![
+            UnknownKey {
+                key: String::from("hots"),
+                path: path.to_path_buf(),
+            },
+            UnknownKey {
+                key: String::from("servers.a.prot"),
+                path: path.to_path_buf(),
+            }
+        ]
+    );
+    assert_eq!(
+        unknown_keys[0].to_string(),
+        format!(
+            "warning: config file '{}' has unknown key `hots`",
+            path.to_string_lossy()
+        )
+    );
+}
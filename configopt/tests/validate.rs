@@ -0,0 +1,71 @@
+use configopt::{configopt_fields, ConfigOpt, Error, Provenance, TraceSource};
+use std::io::Write;
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(validate(check))]
+struct MyStruct {
+    #[structopt(long)]
+    tls_cert: Option<String>,
+    #[structopt(long)]
+    tls_key: Option<String>,
+    #[structopt(long)]
+    debug: bool,
+    #[structopt(long)]
+    log_file: Option<String>,
+}
+
+fn check(s: &MyStruct, provenance: &Provenance) -> configopt::Result<()> {
+    if s.tls_cert.is_some() && s.tls_key.is_none() {
+        return Err(provenance.invalid("tls_cert", "requires `tls_key`"));
+    }
+    let log_file_from_file = match provenance.source_of("log_file") {
+        Some(TraceSource::ConfigFile { .. }) => true,
+        _ => false,
+    };
+    if provenance.is_from_cli("debug") && log_file_from_file {
+        return Err(provenance.invalid("log_file", "cannot be set in a config file with `--debug`"));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_validate() {
+    let s = MyStruct::try_from_iter_with_configopt(&["app", "--tls-cert", "a", "--tls-key", "b"])
+        .unwrap();
+    assert_eq!(s.tls_key.as_deref(), Some("b"));
+
+    // The error names the layer which set the value
+    let err = MyStruct::try_from_iter_with_configopt(&["app", "--tls-cert", "a"]).unwrap_err();
+    assert_eq!(err.field_path(), Some("tls_cert"));
+    assert_eq!(
+        err.to_string(),
+        "Invalid value for `tls_cert` from the command line, err: requires `tls_key`"
+    );
+
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "log_file = \"app.log\"").unwrap();
+    let path = config_file.path().to_str().unwrap();
+    MyStruct::try_from_iter_with_configopt(&["app", "--config-files", path]).unwrap();
+    MyStruct::try_from_iter_with_configopt(&["app", "--debug", "--log-file", "a.log"]).unwrap();
+    let err = MyStruct::try_from_iter_with_configopt(&["app", "--debug", "--config-files", path])
+        .unwrap_err();
+    match err {
+        Error::Field {
+            path: field,
+            source,
+            ..
+        } => {
+            assert_eq!(field, "log_file");
+            assert_eq!(
+                source,
+                TraceSource::ConfigFile {
+                    path: String::from(path)
+                }
+            );
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+}
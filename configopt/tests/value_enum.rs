@@ -0,0 +1,76 @@
+use configopt::{configopt_fields, ConfigOpt, ConfigOptType, ConfigOptValue};
+use std::io::Write;
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOptValue, Clone, Copy, Debug, PartialEq)]
+enum LogFormat {
+    Json,
+    PlainText,
+}
+
+#[derive(ConfigOptValue, Debug, PartialEq)]
+#[configopt(rename_all = "screaming_snake")]
+enum Level {
+    Info,
+    Debug,
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    /// The format of the logs
+    #[structopt(long, possible_values = LogFormat::VARIANTS)]
+    format: LogFormat,
+    #[structopt(long)]
+    level: Option<Level>,
+}
+
+#[test]
+fn test_value_enum() {
+    assert_eq!(LogFormat::VARIANTS, &["json", "plain-text"]);
+    assert_eq!(Level::VARIANTS, &["INFO", "DEBUG"]);
+    assert_eq!("plain-text".parse::<LogFormat>(), Ok(LogFormat::PlainText));
+    assert_eq!("JSON".parse::<LogFormat>(), Ok(LogFormat::Json));
+    assert_eq!(
+        "xml".parse::<LogFormat>(),
+        Err(String::from(
+            "invalid value `xml`, expected one of `json`, `plain-text`"
+        ))
+    );
+    assert_eq!(LogFormat::PlainText.to_string(), "plain-text");
+
+    let s = MyStruct::try_from_iter_with_configopt(&["app", "--format", "json"]).unwrap();
+    assert_eq!(s.format, LogFormat::Json);
+
+    // Config files use the same names and the CLI takes precedence
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(config_file, "format = \"plain-text\"\nlevel = \"DEBUG\"").unwrap();
+    let config_files = config_file.path().to_string_lossy().to_string();
+    let s =
+        MyStruct::try_from_iter_with_configopt(&["app", "--config-files", &config_files]).unwrap();
+    assert_eq!(s.format, LogFormat::PlainText);
+    assert_eq!(s.level, Some(Level::Debug));
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--format",
+        "json",
+        "--config-files",
+        &config_files,
+    ])
+    .unwrap();
+    assert_eq!(s.format, LogFormat::Json);
+
+    // The variants are listed in the schema and generated config
+    let schema = ConfigOptMyStruct::schema();
+    assert_eq!(
+        schema.field("format").unwrap().values,
+        vec!["json", "plain-text"]
+    );
+    assert_eq!(schema.field("level").unwrap().values, vec!["INFO", "DEBUG"]);
+    let config = ConfigOptMyStruct::from(s).toml_config();
+    assert!(config.contains(
+        "### The format of the logs\n### [possible values: json, plain-text]\nformat = \"json\"\n"
+    ));
+}
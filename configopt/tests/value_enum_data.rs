@@ -0,0 +1,115 @@
+use configopt::{configopt_fields, ConfigFormat, ConfigOpt, ConfigOptType, ConfigOptValue};
+use std::{fs, io::Write};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOptValue, Clone, Debug, PartialEq)]
+enum Storage {
+    Local,
+    S3 {
+        bucket: String,
+        region: Option<String>,
+    },
+}
+
+#[derive(ConfigOptValue, Clone, Debug, PartialEq)]
+#[configopt(external)]
+enum Sink {
+    Stdout,
+    File { path: String },
+}
+
+#[configopt_fields]
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    storage: Storage,
+    #[structopt(long)]
+    sink: Option<Sink>,
+}
+
+#[test]
+fn test_value_enum_with_data() {
+    let s3 = Storage::S3 {
+        bucket: String::from("x"),
+        region: None,
+    };
+    assert_eq!(s3.to_string(), r#"{ bucket = "x", type = "s3" }"#);
+    assert_eq!(s3.to_string().parse::<Storage>(), Ok(s3.clone()));
+    assert_eq!("local".parse::<Storage>(), Ok(Storage::Local));
+    assert_eq!(
+        r#"{ file = { path = "a.log" } }"#.parse::<Sink>(),
+        Ok(Sink::File {
+            path: String::from("a.log")
+        })
+    );
+
+    let s = MyStruct::try_from_iter_with_configopt(&[
+        "app",
+        "--storage",
+        r#"{ type = "s3", bucket = "x" }"#,
+    ])
+    .unwrap();
+    assert_eq!(s.storage, s3);
+
+    // Config files write the variants as tables
+    let mut config_file = NamedTempFile::new().unwrap();
+    writeln!(
+        config_file,
+        "[storage]\ntype = \"s3\"\nbucket = \"y\"\nregion = \"eu\"\n[sink.file]\npath = \"a.log\""
+    )
+    .unwrap();
+    let config_files = config_file.path().to_string_lossy().to_string();
+    let s =
+        MyStruct::try_from_iter_with_configopt(&["app", "--config-files", &config_files]).unwrap();
+    let s3 = Storage::S3 {
+        bucket: String::from("y"),
+        region: Some(String::from("eu")),
+    };
+    assert_eq!(s.storage, s3);
+    assert_eq!(
+        s.sink,
+        Some(Sink::File {
+            path: String::from("a.log")
+        })
+    );
+
+    // JSON uses the same representation
+    let s = MyStruct::try_from_iter_with_files(
+        &["app"],
+        &[(
+            ConfigFormat::Json,
+            r#"{"storage": {"type": "local"}, "sink": "stdout"}"#,
+        )],
+    )
+    .unwrap();
+    assert_eq!((s.storage, s.sink), (Storage::Local, Some(Sink::Stdout)));
+    let err = MyStruct::try_from_iter_with_files(
+        &["app"],
+        &[(ConfigFormat::Json, r#"{"storage": {"type": "s3"}}"#)],
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("missing field `bucket`"));
+
+    // The generated config can be parsed again in every format
+    let config = ConfigOptMyStruct::from(MyStruct {
+        storage: s3.clone(),
+        sink: Some(Sink::File {
+            path: String::from("a.log"),
+        }),
+        config_files: Vec::new(),
+        generate_config: false,
+        explain_config: false,
+    });
+    let toml = config.toml_config();
+    assert!(toml.contains(r#"storage = { bucket = "y", region = "eu", type = "s3" }"#));
+    assert!(toml.contains(r#"sink = { file = { path = "a.log" } }"#));
+    let json_file = NamedTempFile::new().unwrap();
+    config
+        .write_to_file(json_file.path(), ConfigFormat::Json)
+        .unwrap();
+    let json = fs::read_to_string(json_file.path()).unwrap();
+    let s = MyStruct::try_from_iter_with_files(&["app"], &[(ConfigFormat::Json, &json)]).unwrap();
+    assert_eq!(s.storage, s3);
+}
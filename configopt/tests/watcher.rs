@@ -0,0 +1,90 @@
+use configopt::{ConfigOpt, FileSystem, FileWatcher, ManualClock, MemoryFileSystem, ShutdownToken};
+use std::{convert::TryFrom, path::Path, sync::mpsc, sync::Arc, time::Duration};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[test]
+fn test_watcher() {
+    let clock = Arc::new(ManualClock::default());
+    let fs = Arc::new(MemoryFileSystem::new(clock.clone()));
+    let path = Path::new("/etc/app/config.toml");
+    fs.write(path, "port = 1").unwrap();
+
+    let mut watcher = FileWatcher::with(&[path], clock.clone(), fs.clone());
+    assert!(watcher.poll().is_empty());
+
+    clock.advance(Duration::from_secs(1));
+    fs.write(path, "port = 2").unwrap();
+    assert_eq!(watcher.poll(), vec![path.to_path_buf()]);
+    assert!(watcher.poll().is_empty());
+
+    clock.advance(Duration::from_secs(1));
+    fs.write(path, "port = 3").unwrap();
+    let mut ports = Vec::new();
+    let mut remaining = vec!["port = 4"];
+    watcher.watch(Duration::from_secs(5), |changed| {
+        let c: ConfigOptMyStruct =
+            configopt::from_toml_file_in(&*fs, &changed[0], &[], &mut Vec::new()).unwrap();
+        ports.push(MyStruct::try_from(c).unwrap().port);
+        match remaining.pop() {
+            Some(next) => {
+                fs.write(path, next).unwrap();
+                true
+            }
+            None => false,
+        }
+    });
+    assert_eq!(ports, vec![3, 4]);
+
+    fs.remove(path);
+    assert_eq!(watcher.poll(), vec![path.to_path_buf()]);
+}
+
+#[test]
+fn test_watcher_shutdown() {
+    let clock = Arc::new(ManualClock::default());
+    let fs = Arc::new(MemoryFileSystem::new(clock.clone()));
+    let path = Path::new("/etc/app/config.toml");
+    fs.write(path, "port = 1").unwrap();
+
+    // A cancelled token stops a blocking watch before it polls
+    let mut watcher = FileWatcher::with(&[path], clock.clone(), fs.clone());
+    let shutdown = ShutdownToken::new();
+    shutdown.cancel();
+    watcher.watch_until(Duration::from_secs(5), &shutdown, |_| {
+        panic!("watcher was cancelled")
+    });
+    assert!(shutdown.wait_timeout(Duration::from_secs(60)));
+
+    let (sender, receiver) = mpsc::channel();
+    let shutdown = ShutdownToken::new();
+    let handle = FileWatcher::with(&[path], clock.clone(), fs.clone()).spawn(
+        Duration::from_millis(1),
+        shutdown.clone(),
+        move |changed| sender.send(changed.to_vec()).is_ok(),
+    );
+    clock.advance(Duration::from_secs(1));
+    fs.write(path, "port = 2").unwrap();
+    assert_eq!(
+        receiver.recv_timeout(Duration::from_secs(10)).unwrap(),
+        vec![path.to_path_buf()]
+    );
+    assert!(!handle.shutdown_token().is_cancelled());
+    handle.stop().unwrap();
+    assert!(shutdown.is_cancelled());
+
+    // Dropping the handle cancels its token and joins the thread
+    let shutdown = ShutdownToken::new();
+    drop(FileWatcher::with(&[path], clock, fs).spawn(
+        Duration::from_secs(60),
+        shutdown.clone(),
+        |_| true,
+    ));
+    assert!(shutdown.is_cancelled());
+}
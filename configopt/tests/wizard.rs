@@ -0,0 +1,59 @@
+use configopt::{ConfigOpt, Question};
+use std::{collections::VecDeque, fs};
+use structopt::StructOpt;
+use tempfile::TempDir;
+
+#[derive(ConfigOpt, StructOpt, Debug)]
+#[configopt(derive(Debug))]
+struct MyStruct {
+    /// The host to bind
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    #[configopt(secret)]
+    token: String,
+    #[structopt(long)]
+    name: Option<String>,
+}
+
+#[test]
+fn test_wizard_with() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    let mut config = ConfigOptMyStruct::from_iter_safe(&["app"]).unwrap();
+
+    let mut answers = VecDeque::from(vec!["localhost", "", "http", "80", "hunter2"]);
+    let mut questions = Vec::new();
+    configopt::wizard_with(&mut config, &path, |question: &Question| {
+        questions.push(question.clone());
+        Ok(String::from(answers.pop_front().unwrap()))
+    })
+    .unwrap();
+
+    // Only the required fields are asked for and a rejected answer is asked again
+    let asked = questions
+        .iter()
+        .map(|q| (q.key.as_str(), q.error.is_some(), q.secret))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        asked,
+        vec![
+            ("host", false, false),
+            ("port", false, false),
+            ("port", true, false),
+            ("port", true, false),
+            ("token", false, true),
+        ]
+    );
+    assert_eq!(questions[0].help, "The host to bind");
+    assert_eq!(config.port, Some(80));
+    assert_eq!(config.token.as_deref(), Some("hunter2"));
+
+    // The secret is never written
+    let contents = fs::read_to_string(&path).unwrap();
+    assert!(contents.contains("host = \"localhost\""));
+    assert!(contents.contains("port = 80"));
+    assert!(!contents.contains("hunter2"));
+}
@@ -0,0 +1,203 @@
+use configopt::{ConfigFormat, ConfigOpt, ConfigOptType};
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, fs};
+use structopt::StructOpt;
+use tempfile::NamedTempFile;
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct MyStruct {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    port: u16,
+    #[structopt(long)]
+    #[configopt(secret)]
+    password: Option<String>,
+}
+
+#[derive(Default, Deserialize, Serialize, Debug, PartialEq)]
+struct Endpoint {
+    host: String,
+    port: u16,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct Values {
+    #[structopt(long, hidden = true)]
+    level: u8,
+    #[structopt(skip)]
+    #[configopt(leaf)]
+    endpoint: Endpoint,
+    #[structopt(long)]
+    big: Option<u64>,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct App {
+    #[structopt(long)]
+    level: Option<String>,
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+enum Command {
+    Serve(Serve),
+    Check,
+}
+
+#[derive(ConfigOpt, StructOpt, Debug, PartialEq)]
+#[configopt(derive(Debug, PartialEq))]
+struct Serve {
+    #[structopt(long)]
+    #[configopt(min = 1)]
+    port: u16,
+}
+
+#[test]
+fn test_write_to_file() {
+    let s = MyStruct {
+        host: String::from("localhost"),
+        port: 80,
+        password: Some(String::from("hunter2")),
+    };
+    let c = ConfigOptMyStruct::from(s);
+    let config_file = NamedTempFile::new().unwrap();
+    c.write_to_file(config_file.path(), ConfigFormat::Toml)
+        .unwrap();
+    assert!(!fs::read_to_string(config_file.path())
+        .unwrap()
+        .contains("hunter2"));
+
+    let read = ConfigOptMyStruct::try_from(config_file.path()).unwrap();
+    assert_eq!(read.host, Some(String::from("localhost")));
+    assert_eq!(read.port, Some(80));
+    assert_eq!(read.password, None);
+}
+
+#[test]
+fn test_write_to_json_file() {
+    let c = ConfigOptMyStruct {
+        host: Some(String::from("localhost")),
+        port: None,
+        password: None,
+        password_file: None,
+    };
+    let config_file = NamedTempFile::new().unwrap();
+    c.write_to_file(config_file.path(), ConfigFormat::Json)
+        .unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(config_file.path()).unwrap()).unwrap();
+    assert_eq!(json, serde_json::json!({"host": "localhost"}));
+}
+
+#[test]
+fn test_write_to_file_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    // Replicas starting at the same time race to generate the file
+    let handles = (0..8u16)
+        .map(|port| {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                let c = ConfigOptMyStruct::from(MyStruct {
+                    host: String::from("localhost"),
+                    port,
+                    password: None,
+                });
+                c.write_to_file_once(&path, ConfigFormat::Toml).unwrap()
+            })
+        })
+        .collect::<Vec<_>>();
+    let written = handles
+        .into_iter()
+        .map(|h| h.join().unwrap())
+        .filter(|written| *written)
+        .count();
+    assert_eq!(written, 1);
+
+    // The file is complete and the lock is released
+    let read = ConfigOptMyStruct::try_from(path.as_path()).unwrap();
+    assert_eq!(read.host, Some(String::from("localhost")));
+    assert!(read.port.is_some());
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+    let c = ConfigOptMyStruct::from(MyStruct {
+        host: String::from("other"),
+        port: 1,
+        password: None,
+    });
+    assert!(!c.write_to_file_once(&path, ConfigFormat::Toml).unwrap());
+}
+
+#[test]
+fn test_write_values() {
+    let s = Values {
+        level: 3,
+        endpoint: Endpoint {
+            host: String::from("b"),
+            port: 2,
+        },
+        big: None,
+    };
+    let mut c = ConfigOptValues::from(s);
+    let config_file = NamedTempFile::new().unwrap();
+    c.write_to_file(config_file.path(), ConfigFormat::Toml)
+        .unwrap();
+    // Hidden fields and leaves are written with their values
+    let read = ConfigOptValues::try_from(config_file.path()).unwrap();
+    assert_eq!(read, c);
+
+    // A value TOML cannot represent is an error rather than being left out
+    c.big = Some(u64::MAX);
+    match c.write_to_file(config_file.path(), ConfigFormat::Toml) {
+        Err(configopt::Error::WriteConfig { path, message }) => {
+            assert_eq!(path, config_file.path());
+            assert!(message.starts_with("`big` "));
+        }
+        result => panic!("unexpected {:?}", result),
+    }
+}
+
+#[test]
+fn test_write_subcommand() {
+    let c = ConfigOptApp::from(App {
+        level: None,
+        command: Command::Serve(Serve { port: 8080 }),
+    });
+    // The values of the selected variant are nested under its name
+    let values = c.toml_values().unwrap();
+    assert_eq!(
+        values["command"]["Serve"]["port"],
+        toml::Value::Integer(8080)
+    );
+    let config_file = NamedTempFile::new().unwrap();
+    c.write_to_file(config_file.path(), ConfigFormat::Toml)
+        .unwrap();
+    let read = ConfigOptApp::try_from(config_file.path()).unwrap();
+    assert_eq!(read, c);
+
+    let c = ConfigOptApp::from(App {
+        level: None,
+        command: Command::Check,
+    });
+    let values = c.toml_values().unwrap();
+    assert_eq!(
+        values["command"]["Check"],
+        toml::Value::Table(toml::value::Table::new())
+    );
+
+    // The constraints of the variant are checked
+    let c = ConfigOptApp::from(App {
+        level: None,
+        command: Command::Serve(Serve { port: 0 }),
+    });
+    assert_eq!(
+        c.check_constraints().unwrap_err().field_path(),
+        Some("command.Serve.port")
+    );
+}